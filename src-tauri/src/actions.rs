@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::{self, Category};
+use crate::{crash, dispatch, events, middleware, OverlayManager};
+
+// ==========================================
+// Trigger Actions
+// ==========================================
+
+// What hot corners, gestures and other user triggers do when they fire. Anything the backend
+// can't do itself (switching scenes, for instance) is emitted for the UI to handle.
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Action {
+    ToggleOverlay,
+    ShowOverlay,
+    HideOverlay,
+    /// Emits `event` to the webview, e.g. `scene://activate` with the scene name as payload.
+    Emit {
+        event: String,
+        #[serde(default)]
+        payload: Value,
+    },
+    /// Speaks `text` with the system voice.
+    Speak {
+        text: String,
+    },
+    /// Runs a registered command, e.g. `toggle_overlay_for_monitor` with
+    /// `{"monitor_index": 1}`, through the same middleware as control requests.
+    Command {
+        command: String,
+        #[serde(default)]
+        args: Value,
+    },
+    /// Turns the cursor-following spotlight on or off.
+    ToggleSpotlight,
+    /// Turns the keystroke visualizer on or off, keeping its last style.
+    ToggleKeystrokes,
+    /// Opens the quick menu, or closes it if it's open.
+    QuickMenu,
+    /// Replays an input job, e.g. a recorded macro.
+    #[cfg(feature = "input-automation")]
+    RunMacro {
+        job: crate::injection::InputJob,
+    },
+    /// Emergency stop for running input jobs and scripts.
+    #[cfg(feature = "input-automation")]
+    StopAutomation,
+}
+
+impl Action {
+    /// Rejects commands the registry doesn't know or that can't run in-process, so
+    /// mistakes fail when bound rather than when the trigger fires.
+    pub fn check(&self) -> Result<(), String> {
+        match self {
+            Action::Command { command, args } => {
+                if commands::lookup(command).is_none() {
+                    return Err(format!("Unknown command '{}'", command));
+                }
+                if !dispatch::dispatchable(command) {
+                    return Err(format!("'{}' can't be run by a trigger", command));
+                }
+                if !(args.is_null() || args.is_object()) {
+                    return Err(format!("Arguments for '{}' must be an object", command));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The command `action` stands for in policies, rate limits and the audit log. Toggles
+/// go by the command that turns the feature on.
+fn pipeline_name(action: &Action) -> &str {
+    match action {
+        Action::ToggleOverlay => "toggle_overlay_for_monitor",
+        Action::ShowOverlay => "create_privacy_overlay",
+        Action::HideOverlay => "destroy_privacy_overlay",
+        Action::Emit { .. } => "emit_event",
+        Action::Speak { .. } => "speak",
+        Action::Command { command, .. } => command,
+        Action::ToggleSpotlight => "start_spotlight",
+        Action::ToggleKeystrokes => "start_keystroke_visualizer",
+        Action::QuickMenu => "toggle_quick_menu",
+        #[cfg(feature = "input-automation")]
+        Action::RunMacro { .. } => "run_input_job",
+        #[cfg(feature = "input-automation")]
+        Action::StopAutomation => "stop_input_jobs",
+    }
+}
+
+/// Runs `f` for a built-in action through the command pipeline, under `TRIGGER_ORIGIN`.
+fn guarded(source: &str, action: &Action, f: impl FnOnce() -> Result<(), String>) {
+    let method = pipeline_name(action);
+    let params = serde_json::to_value(action).unwrap_or_default();
+    if let Err(e) = middleware::run_as(middleware::TRIGGER_ORIGIN, method, &params, || {
+        f().map(|_| Value::Null)
+    }) {
+        log::warn!("trigger {} from {} failed: {}", method, source, e);
+    }
+}
+
+/// Runs `action` on behalf of `source` (e.g. "hot-corner:top-left"). Everything but the
+/// emergency stop passes the command pipeline, so triggers obey the same policies as
+/// control requests.
+pub fn run(app: &AppHandle, action: &Action, source: &str) {
+    crash::breadcrumb("actions", format!("{} -> {:?}", source, action));
+
+    let (owned_action, owned_source) = (action.clone(), source.to_string());
+    match action {
+        Action::ToggleOverlay | Action::ShowOverlay | Action::HideOverlay => {
+            let handle = app.clone();
+            // Overlay windows must be created on the UI thread.
+            let _ = app.run_on_main_thread(move || {
+                guarded(&owned_source, &owned_action, || {
+                    let manager = handle.state::<OverlayManager>();
+                    let shown = !manager.overlays.lock().unwrap().is_empty();
+                    match &owned_action {
+                        Action::ToggleOverlay if shown => crate::destroy_overlays_in(&manager),
+                        Action::ToggleOverlay | Action::ShowOverlay => {
+                            crate::create_overlay_in(&manager, None, None, None, None, None, None)
+                                .map(|_| ())
+                        }
+                        _ => crate::destroy_overlays_in(&manager),
+                    }
+                })
+            });
+        }
+        Action::Emit { event, payload } => guarded(source, action, || {
+            events::emit(app, event, payload);
+            Ok(())
+        }),
+        Action::Command { command, args } => {
+            // Overlay windows must be created on the UI thread; anything else may
+            // block, e.g. input jobs, so it gets a thread of its own.
+            let on_ui = commands::lookup(command).is_some_and(|c| c.category == Category::Overlay);
+            let (command, args, source) = (command.clone(), args.clone(), source.to_string());
+            let handle = app.clone();
+            let call = move || {
+                let manager = handle.state::<OverlayManager>();
+                if let Err(e) = dispatch::dispatch_trigger(&manager, &source, &command, &args) {
+                    log::warn!("trigger command {} failed: {}", command, e);
+                }
+            };
+            if on_ui {
+                let _ = app.run_on_main_thread(call);
+            } else {
+                std::thread::spawn(call);
+            }
+        }
+        Action::Speak { text } => guarded(source, action, || crate::speech::say(text)),
+        Action::ToggleSpotlight => {
+            let _ = app.run_on_main_thread(move || {
+                guarded(&owned_source, &owned_action, crate::spotlight::toggle)
+            });
+        }
+        Action::ToggleKeystrokes => {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                guarded(&owned_source, &owned_action, || {
+                    crate::keystrokes::toggle(handle)
+                })
+            });
+        }
+        Action::QuickMenu => {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                guarded(&owned_source, &owned_action, || {
+                    crate::quick_menu::toggle(&handle)
+                })
+            });
+        }
+        #[cfg(feature = "input-automation")]
+        Action::RunMacro { job } => {
+            let job = job.clone();
+            // Jobs block until their last event; triggers run on hook and sampler threads.
+            std::thread::spawn(move || {
+                guarded(&owned_source, &owned_action, || {
+                    crate::injection::run_job(job).map(|_| ())
+                })
+            });
+        }
+        // Never held up by a policy, rate limit or dry-run.
+        #[cfg(feature = "input-automation")]
+        Action::StopAutomation => crate::injection::stop_all(),
+    }
+}
@@ -0,0 +1,387 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::app_window::{self, WindowCriteria, WindowInfo};
+
+// ==========================================
+// Display Affinity
+// ==========================================
+
+// How overlays, private HUDs and, on request, the app's own window keep out of
+// screen shares. Excluding a window from
+// capture leaves it out of recordings entirely, but Windows only supports that from
+// 10 version 2004 (build 19041); older builds, and drivers that refuse it, get the
+// monitor-only affinity instead, which captures show as a black box. Either way the
+// caller is told what was actually applied, with a warning when it's not what they
+// asked for. macOS has only the exclusion, through the window's sharing type.
+//
+// Both OSes only let an app change the capture settings of its own windows, so
+// other apps' windows (a password manager, say) can't be excluded this way; the
+// share guard covers those instead.
+
+/// Label of the app's own window, from tauri.conf.json.
+const MAIN_WINDOW: &str = "main";
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const EXCLUDE_FROM_CAPTURE_BUILD: u32 = 19041;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AffinityMode {
+    /// Left out of captures; what's underneath shows instead.
+    #[default]
+    ExcludeFromCapture,
+    /// Shown as a black box in captures. Windows only.
+    Monitor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningCode {
+    /// The OS is too old for the requested mode.
+    OsTooOld,
+    /// The OS refused the requested mode when a window applied it.
+    Refused,
+    /// The platform doesn't have the requested mode.
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AffinityWarning {
+    pub code: WarningCode,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AffinityStatus {
+    pub requested: AffinityMode,
+    /// What windows get; differs from `requested` after a fallback.
+    pub effective: AffinityMode,
+    /// Windows build number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os_build: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<AffinityWarning>,
+}
+
+static MODE: Lazy<Mutex<AffinityMode>> = Lazy::new(|| Mutex::new(AffinityMode::default()));
+/// Set once the OS refuses to exclude a window, after which every window falls back.
+static REFUSED: AtomicBool = AtomicBool::new(false);
+
+/// The mode in force and why it differs from the requested one, if it does.
+pub fn status() -> AffinityStatus {
+    let requested = *MODE.lock().unwrap();
+    let os_build = platform::os_build();
+    let (effective, warning) = fallback(requested, os_build, REFUSED.load(Ordering::SeqCst));
+    AffinityStatus {
+        requested,
+        effective,
+        os_build,
+        warning,
+    }
+}
+
+/// The mode applied for `requested` on this platform and OS build, given whether
+/// the OS has refused an exclusion, and why it differs if it does.
+fn fallback(
+    requested: AffinityMode,
+    os_build: Option<u32>,
+    refused: bool,
+) -> (AffinityMode, Option<AffinityWarning>) {
+    match requested {
+        AffinityMode::Monitor if !cfg!(target_os = "windows") => (
+            AffinityMode::ExcludeFromCapture,
+            Some(AffinityWarning {
+                code: WarningCode::Unsupported,
+                message: "Monitor-only affinity is only available on Windows; windows are \
+                          excluded from capture instead"
+                    .to_string(),
+            }),
+        ),
+        AffinityMode::ExcludeFromCapture
+            if os_build.is_some_and(|build| build < EXCLUDE_FROM_CAPTURE_BUILD) =>
+        {
+            (
+                AffinityMode::Monitor,
+                Some(AffinityWarning {
+                    code: WarningCode::OsTooOld,
+                    message: format!(
+                        "Excluding windows from capture needs Windows build {} or later \
+                         (this is {}); captures show them as black boxes instead",
+                        EXCLUDE_FROM_CAPTURE_BUILD,
+                        os_build.unwrap_or_default()
+                    ),
+                }),
+            )
+        }
+        AffinityMode::ExcludeFromCapture if refused => (
+            AffinityMode::Monitor,
+            Some(AffinityWarning {
+                code: WarningCode::Refused,
+                message: "Windows refused to exclude a window from capture; captures show \
+                          them as black boxes instead"
+                    .to_string(),
+            }),
+        ),
+        mode => (mode, None),
+    }
+}
+
+/// Applies the effective mode to `hwnd`, falling back to monitor-only affinity
+/// if the OS refuses to exclude it.
+#[cfg(target_os = "windows")]
+pub fn apply(hwnd: windows::Win32::Foundation::HWND) -> windows::core::Result<()> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SetWindowDisplayAffinity, WDA_EXCLUDEFROMCAPTURE, WDA_MONITOR,
+    };
+
+    unsafe {
+        if status().effective == AffinityMode::Monitor {
+            return SetWindowDisplayAffinity(hwnd, WDA_MONITOR);
+        }
+        SetWindowDisplayAffinity(hwnd, WDA_EXCLUDEFROMCAPTURE).or_else(|e| {
+            if !REFUSED.swap(true, Ordering::SeqCst) {
+                log::warn!(
+                    "exclude from capture refused, using monitor affinity: {}",
+                    e
+                );
+                crate::audit_log::record(crate::audit_log::AuditEvent::VerificationFailed {
+                    check: crate::audit_log::FailureKind::CaptureExclusion,
+                    message: e.to_string(),
+                });
+            }
+            SetWindowDisplayAffinity(hwnd, WDA_MONITOR)
+        })
+    }
+}
+
+/// Excludes the window `criteria` matches from capture, or lets it back in.
+fn set_window_excluded(criteria: &WindowCriteria, excluded: bool) -> Result<WindowInfo, String> {
+    if criteria.is_empty() {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    let window = app_window::find_window(criteria)
+        .ok_or_else(|| "No window matches the criteria".to_string())?;
+    if window.pid != std::process::id() {
+        return Err(format!(
+            "'{}' belongs to {} (pid {}), and the OS only lets an app change capture \
+             exclusion of its own windows; the share guard can cover it instead",
+            window.title, window.process, window.pid
+        ));
+    }
+    platform::set_window(window.id, excluded)?;
+    Ok(window)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use once_cell::sync::Lazy;
+    use tauri::WebviewWindow;
+    use windows::Wdk::System::SystemServices::RtlGetVersion;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::SystemInformation::OSVERSIONINFOW;
+    use windows::Win32::UI::WindowsAndMessaging::{SetWindowDisplayAffinity, WDA_NONE};
+
+    /// Read once: GetVersionExW reports whatever the manifest claims to support,
+    /// RtlGetVersion the real build.
+    static BUILD: Lazy<Option<u32>> = Lazy::new(|| unsafe {
+        let mut info = OSVERSIONINFOW {
+            dwOSVersionInfoSize: std::mem::size_of::<OSVERSIONINFOW>() as u32,
+            ..Default::default()
+        };
+        RtlGetVersion(&mut info)
+            .is_ok()
+            .then_some(info.dwBuildNumber)
+    });
+
+    pub fn os_build() -> Option<u32> {
+        *BUILD
+    }
+
+    pub fn set_window(window: u64, excluded: bool) -> Result<(), String> {
+        let hwnd = HWND(window as *mut _);
+        if excluded {
+            super::apply(hwnd)
+        } else {
+            unsafe { SetWindowDisplayAffinity(hwnd, WDA_NONE) }
+        }
+        .map_err(|e| format!("Failed to set the window's display affinity: {}", e))
+    }
+
+    pub fn protect(window: &WebviewWindow, enabled: bool) -> Result<(), String> {
+        // Tauri's HWND comes from its own windows crate version; the handle carries over.
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0;
+        set_window(hwnd as u64, enabled)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+    use tauri::WebviewWindow;
+
+    /// NSWindowSharingNone and NSWindowSharingReadOnly, the default.
+    const SHARING_NONE: u64 = 0;
+    const SHARING_READ_ONLY: u64 = 1;
+
+    pub fn os_build() -> Option<u32> {
+        None
+    }
+
+    fn set_sharing(ns_window: id, excluded: bool) {
+        let sharing = if excluded {
+            SHARING_NONE
+        } else {
+            SHARING_READ_ONLY
+        };
+        unsafe {
+            let _: () = msg_send![ns_window, setSharingType: sharing];
+        }
+    }
+
+    /// `window` is a window number, which for this app's windows is the CGWindowID.
+    pub fn set_window(window: u64, excluded: bool) -> Result<(), String> {
+        let ns_window: id = unsafe {
+            let app: id = msg_send![class!(NSApplication), sharedApplication];
+            msg_send![app, windowWithWindowNumber: window as isize]
+        };
+        if ns_window == nil {
+            return Err(format!("Window {} is not one of this app's", window));
+        }
+        set_sharing(ns_window, excluded);
+        Ok(())
+    }
+
+    pub fn protect(window: &WebviewWindow, enabled: bool) -> Result<(), String> {
+        let ns_window = window.ns_window().map_err(|e| e.to_string())? as id;
+        set_sharing(ns_window, enabled);
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use tauri::WebviewWindow;
+
+    pub fn os_build() -> Option<u32> {
+        None
+    }
+
+    pub fn set_window(_window: u64, _excluded: bool) -> Result<(), String> {
+        Err("Capture exclusion is only supported on macOS and Windows".to_string())
+    }
+
+    pub fn protect(_window: &WebviewWindow, _enabled: bool) -> Result<(), String> {
+        Err("Protecting the app window is only supported on macOS and Windows".to_string())
+    }
+}
+
+// ==========================================
+// DISPLAY AFFINITY COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_display_affinity() -> AffinityStatus {
+    status()
+}
+
+/// Picks how overlays, which switch at once, and private HUDs opened from now on
+/// keep out of captures. Returns the mode they'll actually get, with a warning if
+/// the OS can't do the requested one.
+#[tauri::command]
+pub fn set_display_affinity(mode: AffinityMode) -> AffinityStatus {
+    *MODE.lock().unwrap() = mode;
+    crate::overlay_affinity_changed();
+    let status = status();
+    if let Some(warning) = &status.warning {
+        log::warn!("display affinity: {}", warning.message);
+    }
+    status
+}
+
+/// Keeps the app's own window out of screen shares, or lets it back in. When
+/// enabled it gets the same affinity as overlays.
+#[tauri::command]
+pub fn protect_app_window(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let window = app
+        .get_webview_window(MAIN_WINDOW)
+        .ok_or("The app window is not open")?;
+    platform::protect(&window, enabled)
+}
+
+/// Excludes a window, found by title, process or pid, from screen capture. Only
+/// this app's own windows can be excluded; others are refused with an explanation.
+#[tauri::command]
+pub fn exclude_window_from_capture(criteria: WindowCriteria) -> Result<WindowInfo, String> {
+    set_window_excluded(&criteria, true)
+}
+
+/// Reverts `exclude_window_from_capture`.
+#[tauri::command]
+pub fn include_window_in_capture(criteria: WindowCriteria) -> Result<WindowInfo, String> {
+    set_window_excluded(&criteria, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(
+        result: (AffinityMode, Option<AffinityWarning>),
+    ) -> (AffinityMode, Option<WarningCode>) {
+        (result.0, result.1.map(|warning| warning.code))
+    }
+
+    #[test]
+    fn exclusion_applies_when_supported() {
+        let applied = fallback(AffinityMode::ExcludeFromCapture, Some(22631), false);
+        assert_eq!(code(applied), (AffinityMode::ExcludeFromCapture, None));
+        // macOS and Linux have no build number to check.
+        let applied = fallback(AffinityMode::ExcludeFromCapture, None, false);
+        assert_eq!(code(applied), (AffinityMode::ExcludeFromCapture, None));
+    }
+
+    #[test]
+    fn old_windows_falls_back_to_monitor() {
+        let applied = fallback(AffinityMode::ExcludeFromCapture, Some(18363), false);
+        assert_eq!(
+            code(applied),
+            (AffinityMode::Monitor, Some(WarningCode::OsTooOld))
+        );
+    }
+
+    #[test]
+    fn refusal_falls_back_to_monitor() {
+        let applied = fallback(AffinityMode::ExcludeFromCapture, Some(22631), true);
+        assert_eq!(
+            code(applied),
+            (AffinityMode::Monitor, Some(WarningCode::Refused))
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn monitor_applies_on_windows() {
+        let applied = fallback(AffinityMode::Monitor, Some(22631), false);
+        assert_eq!(code(applied), (AffinityMode::Monitor, None));
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn monitor_falls_back_to_exclusion_elsewhere() {
+        let applied = fallback(AffinityMode::Monitor, None, false);
+        assert_eq!(
+            code(applied),
+            (
+                AffinityMode::ExcludeFromCapture,
+                Some(WarningCode::Unsupported)
+            )
+        );
+    }
+}
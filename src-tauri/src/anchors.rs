@@ -0,0 +1,171 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_window::{self, WindowCriteria},
+    coords::Anchor,
+    crash, secrets,
+};
+
+// ==========================================
+// Named Anchors
+// ==========================================
+
+// Points are stored relative to an application's window and resolved against its
+// current bounds on every use, so they follow the window when it moves or resizes.
+
+/// Prefix of anchor references accepted by click commands: `anchor:app/name`.
+pub const REFERENCE_PREFIX: &str = "anchor:";
+
+static ANCHOR_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Offset in native units from a reference point of the window.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AnchorPoint {
+    pub x: i32,
+    pub y: i32,
+    /// Window point the offset is measured from. Anchor controls to the edge they
+    /// stick to on resize, e.g. `bottom-right` with negative offsets for a dialog's OK.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<Anchor>,
+}
+
+/// Application (executable name) → anchor name → point.
+type AnchorMap = BTreeMap<String, BTreeMap<String, AnchorPoint>>;
+
+pub fn init(path: PathBuf) {
+    *ANCHOR_FILE.lock().unwrap() = Some(path);
+}
+
+fn anchor_file() -> Result<PathBuf, String> {
+    ANCHOR_FILE
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "Anchor storage is not initialized".to_string())
+}
+
+fn load_all() -> Result<AnchorMap, String> {
+    let path = anchor_file()?;
+    if !path.exists() {
+        return Ok(AnchorMap::new());
+    }
+    let data = secrets::open(fs::read(&path).map_err(|e| e.to_string())?)?;
+    serde_json::from_slice(&data).map_err(|e| format!("Anchor file is corrupt: {}", e))
+}
+
+fn save_all(anchors: &AnchorMap) -> Result<(), String> {
+    let path = anchor_file()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(anchors).map_err(|e| e.to_string())?;
+    fs::write(path, secrets::seal(json.as_bytes())?).map_err(|e| e.to_string())
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains('/')
+}
+
+pub fn save(app: &str, name: &str, point: AnchorPoint) -> Result<(), String> {
+    if !valid_name(app) || !valid_name(name) {
+        return Err(
+            "Application and anchor names must be non-empty and contain no '/'".to_string(),
+        );
+    }
+
+    let mut anchors = load_all()?;
+    anchors
+        .entry(app.to_lowercase())
+        .or_default()
+        .insert(name.to_string(), point);
+    save_all(&anchors)?;
+
+    crash::breadcrumb("anchors", format!("saved {}/{}", app, name));
+    Ok(())
+}
+
+pub fn delete(app: &str, name: &str) -> Result<(), String> {
+    let mut anchors = load_all()?;
+    let app_key = app.to_lowercase();
+    let removed = anchors
+        .get_mut(&app_key)
+        .and_then(|names| names.remove(name))
+        .is_some();
+    if !removed {
+        return Err(format!("No anchor '{}/{}'", app, name));
+    }
+    if anchors.get(&app_key).is_some_and(|names| names.is_empty()) {
+        anchors.remove(&app_key);
+    }
+    save_all(&anchors)
+}
+
+/// Resolves `app/name` (with or without the `anchor:` prefix) to native screen coordinates
+/// using the application's front-most window as it is right now.
+pub fn resolve(reference: &str) -> Result<(i32, i32), String> {
+    let reference = reference
+        .strip_prefix(REFERENCE_PREFIX)
+        .unwrap_or(reference);
+    let (app, name) = reference
+        .split_once('/')
+        .ok_or_else(|| format!("Anchor reference '{}' is not app/name", reference))?;
+
+    let anchors = load_all()?;
+    let point = anchors
+        .get(&app.to_lowercase())
+        .and_then(|names| names.get(name))
+        .ok_or_else(|| format!("No anchor '{}/{}'", app, name))?;
+
+    let criteria = WindowCriteria {
+        process: Some(app.to_string()),
+        ..Default::default()
+    };
+    let window = app_window::find_window(&criteria)
+        .ok_or_else(|| format!("No window of '{}' is open", app))?;
+
+    let (fx, fy) = point.from.unwrap_or(Anchor::TopLeft).fractions();
+    let rect = window.rect;
+    Ok((
+        rect.x + (fx * rect.width as f64).round() as i32 + point.x,
+        rect.y + (fy * rect.height as f64).round() as i32 + point.y,
+    ))
+}
+
+/// Parses a click target. Only anchor references are supported.
+pub fn resolve_target(target: &str) -> Result<(i32, i32), String> {
+    if !target.starts_with(REFERENCE_PREFIX) {
+        return Err(format!(
+            "Unknown click target '{}'; expected {}app/name",
+            target, REFERENCE_PREFIX
+        ));
+    }
+    resolve(target)
+}
+
+// ==========================================
+// ANCHOR COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn save_anchor(app: String, name: String, point: AnchorPoint) -> Result<(), String> {
+    save(&app, &name, point)
+}
+
+#[tauri::command]
+pub fn list_anchors() -> Result<AnchorMap, String> {
+    load_all()
+}
+
+#[tauri::command]
+pub fn delete_anchor(app: String, name: String) -> Result<(), String> {
+    delete(&app, &name)
+}
+
+/// Current screen position of `app/name`, for previewing an anchor.
+#[tauri::command]
+pub fn resolve_anchor(reference: String) -> Result<(i32, i32), String> {
+    resolve(&reference)
+}
@@ -0,0 +1,1104 @@
+use std::{
+    fmt::Write,
+    fs,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{imageops, Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    capture,
+    coords::{self, Rect},
+    crash, motion,
+    palette::{self, ColorRole, Rgb},
+};
+
+// ==========================================
+// Annotations
+// ==========================================
+
+// A transparent window over one monitor that the presenter draws on with a pen or
+// the mouse. Pen pressure sets the stroke width point by point; the pen's eraser
+// end (or its eraser button) removes whole strokes it touches. Strokes are kept as
+// points and rasterized here into a premultiplied RGBA canvas at the monitor's
+// native resolution, which the platform layer pushes to the window, so both
+// platforms draw identically. The canvas background is alpha 1/255 rather than 0:
+// both window systems let clicks through fully transparent pixels.
+//
+// A pen with a `ttl_ms` leaves strokes that fade out that long after they're
+// finished. While the window is open a thread ticks the fade on the main thread,
+// repainting only while some stroke is fading.
+
+/// Device-independent pixels.
+const DEFAULT_WIDTH: f64 = 4.0;
+const ERASER_RADIUS: f64 = 12.0;
+/// Light touches still leave a visible line.
+const MIN_PRESSURE: f64 = 0.2;
+const BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 1]);
+/// How long an expired stroke takes to fade out.
+const FADE: Duration = Duration::from_millis(600);
+const FADE_TICK: Duration = Duration::from_millis(33);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PenStyle {
+    /// "#RRGGBB"; the palette's accent color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Device-independent pixels at full pressure.
+    #[serde(default = "default_width")]
+    pub width: f64,
+    /// Strokes start fading out this long after they're finished; they stay
+    /// when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_ms: Option<u64>,
+}
+
+fn default_width() -> f64 {
+    DEFAULT_WIDTH
+}
+
+impl Default for PenStyle {
+    fn default() -> Self {
+        Self {
+            color: None,
+            width: DEFAULT_WIDTH,
+            ttl_ms: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Pen {
+    color: Rgb,
+    /// Canvas pixels at full pressure.
+    width: f64,
+    ttl: Option<Duration>,
+}
+
+impl PenStyle {
+    fn pen(&self, scale: f64) -> Result<Pen, String> {
+        if !(self.width > 0.0 && self.width <= 100.0) {
+            return Err(format!("Pen width {} is out of range", self.width));
+        }
+        if self.ttl_ms == Some(0) {
+            return Err("Stroke lifetime must be at least 1 ms".to_string());
+        }
+        Ok(Pen {
+            color: self
+                .color
+                .as_deref()
+                .map_or(Ok(palette::color(ColorRole::Accent)), palette::parse)?,
+            width: self.width * scale,
+            ttl: self.ttl_ms.map(Duration::from_millis),
+        })
+    }
+}
+
+/// A point in canvas pixels, with pressure from 0 to 1.
+#[derive(Clone, Copy, Debug)]
+pub struct StrokePoint {
+    pub x: f64,
+    pub y: f64,
+    pub pressure: f64,
+}
+
+#[derive(Clone, Debug)]
+struct Stroke {
+    pen: Pen,
+    points: Vec<StrokePoint>,
+    /// When the pen lifted; `None` while it's being drawn.
+    finished: Option<Instant>,
+}
+
+impl Stroke {
+    /// From 1 until the pen's lifetime has passed down to 0 once it has faded out
+    /// over `fade`.
+    fn opacity(&self, now: Instant, fade: Duration) -> f64 {
+        let (Some(ttl), Some(finished)) = (self.pen.ttl, self.finished) else {
+            return 1.0;
+        };
+        let age = now.saturating_duration_since(finished);
+        if fade.is_zero() {
+            return if age < ttl { 1.0 } else { 0.0 };
+        }
+        let fading = age.saturating_sub(ttl);
+        (1.0 - fading.as_secs_f64() / fade.as_secs_f64()).max(0.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnnotationFormat {
+    /// The strokes themselves, which `load_annotations` reads back.
+    Json,
+    /// The strokes on a transparent background.
+    Png,
+    Svg,
+    /// The strokes over a capture of the monitor, as PNG.
+    Screenshot,
+}
+
+/// A saved stroke, in device-independent pixels from the monitor's top left so a
+/// set drawn on one display loads at the same size on another.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SavedStroke {
+    color: String,
+    width: f64,
+    /// x, y and pressure.
+    points: Vec<[f64; 3]>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SavedAnnotations {
+    strokes: Vec<SavedStroke>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AnnotationInfo {
+    pub monitor: usize,
+    pub rect: Rect,
+    pub strokes: usize,
+}
+
+struct Board {
+    handle: usize,
+    monitor: usize,
+    rect: Rect,
+    /// Canvas pixels per device-independent pixel.
+    scale: f64,
+    style: PenStyle,
+    strokes: Vec<Stroke>,
+    /// The stroke being drawn; `None` between strokes and while erasing.
+    current: Option<Stroke>,
+    erasing: bool,
+    canvas: RgbaImage,
+}
+
+static BOARD: Lazy<Mutex<Option<Board>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped when the window closes, ending its fade thread.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Blends a disc of `color` at `opacity` centered on (`x`, `y`) into the canvas,
+/// with its edge antialiased over one pixel.
+fn stamp(canvas: &mut RgbaImage, x: f64, y: f64, radius: f64, (r, g, b): Rgb, opacity: f64) {
+    let (width, height) = canvas.dimensions();
+    let left = (x - radius - 1.0).floor().max(0.0) as u32;
+    let top = (y - radius - 1.0).floor().max(0.0) as u32;
+    let right = ((x + radius + 1.0).ceil().max(0.0) as u32).min(width);
+    let bottom = ((y + radius + 1.0).ceil().max(0.0) as u32).min(height);
+    for py in top..bottom {
+        for px in left..right {
+            let distance = ((px as f64 + 0.5 - x).powi(2) + (py as f64 + 0.5 - y).powi(2)).sqrt();
+            let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0) * opacity;
+            if coverage == 0.0 {
+                continue;
+            }
+            // Source over destination, both premultiplied.
+            let pixel = canvas.get_pixel_mut(px, py);
+            let keep = 1.0 - coverage;
+            for (channel, source) in pixel.0.iter_mut().zip([r, g, b, 255]) {
+                *channel = (source as f64 * coverage + *channel as f64 * keep).round() as u8;
+            }
+        }
+    }
+}
+
+fn radius(pen: &Pen, pressure: f64) -> f64 {
+    (pen.width * pressure.clamp(MIN_PRESSURE, 1.0) / 2.0).max(0.5)
+}
+
+/// Draws the segment from `a` to `b`, its width following the pressure between them.
+fn draw_segment(canvas: &mut RgbaImage, pen: &Pen, a: StrokePoint, b: StrokePoint, opacity: f64) {
+    let length = ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt();
+    let steps = length.ceil().max(1.0) as usize;
+    for step in 1..=steps {
+        let t = step as f64 / steps as f64;
+        let pressure = a.pressure + (b.pressure - a.pressure) * t;
+        stamp(
+            canvas,
+            a.x + (b.x - a.x) * t,
+            a.y + (b.y - a.y) * t,
+            radius(pen, pressure),
+            pen.color,
+            opacity,
+        );
+    }
+}
+
+fn draw_stroke(canvas: &mut RgbaImage, stroke: &Stroke, opacity: f64) {
+    let Some(&first) = stroke.points.first() else {
+        return;
+    };
+    stamp(
+        canvas,
+        first.x,
+        first.y,
+        radius(&stroke.pen, first.pressure),
+        stroke.pen.color,
+        opacity,
+    );
+    for pair in stroke.points.windows(2) {
+        draw_segment(canvas, &stroke.pen, pair[0], pair[1], opacity);
+    }
+}
+
+impl Board {
+    /// Redraws every stroke on a cleared canvas, e.g. after strokes were erased.
+    fn repaint(&mut self) {
+        for pixel in self.canvas.pixels_mut() {
+            *pixel = BACKGROUND;
+        }
+        let now = Instant::now();
+        let fade = motion::fade(FADE);
+        for stroke in self.strokes.iter().chain(&self.current) {
+            draw_stroke(&mut self.canvas, stroke, stroke.opacity(now, fade));
+        }
+    }
+
+    /// Removes every stroke passing within the eraser of `point`. Returns whether any
+    /// was removed.
+    fn erase(&mut self, point: StrokePoint) -> bool {
+        let reach = ERASER_RADIUS * self.scale;
+        let before = self.strokes.len();
+        self.strokes.retain(|stroke| {
+            !stroke.points.iter().any(|p| {
+                (p.x - point.x).powi(2) + (p.y - point.y).powi(2)
+                    <= (reach + stroke.pen.width / 2.0).powi(2)
+            })
+        });
+        self.strokes.len() != before
+    }
+}
+
+/// Starts a stroke, or erasing when `eraser` is set. Called by the platform layer
+/// on the main thread.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn pen_down(point: StrokePoint, eraser: bool) {
+    let mut board = BOARD.lock().unwrap();
+    let Some(board) = board.as_mut() else {
+        return;
+    };
+    board.erasing = eraser;
+    if eraser {
+        if board.erase(point) {
+            board.repaint();
+            platform::present(board.handle, &board.canvas);
+        }
+        return;
+    }
+    let Ok(pen) = board.style.pen(board.scale) else {
+        return;
+    };
+    let stroke = Stroke {
+        pen,
+        points: vec![point],
+        finished: None,
+    };
+    draw_stroke(&mut board.canvas, &stroke, 1.0);
+    board.current = Some(stroke);
+    platform::present(board.handle, &board.canvas);
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn pen_move(point: StrokePoint) {
+    let mut board = BOARD.lock().unwrap();
+    let Some(board) = board.as_mut() else {
+        return;
+    };
+    if board.erasing {
+        if board.erase(point) {
+            board.repaint();
+            platform::present(board.handle, &board.canvas);
+        }
+        return;
+    }
+    let Some(stroke) = board.current.as_mut() else {
+        return;
+    };
+    let Some(&last) = stroke.points.last() else {
+        return;
+    };
+    stroke.points.push(point);
+    let pen = stroke.pen;
+    draw_segment(&mut board.canvas, &pen, last, point, 1.0);
+    platform::present(board.handle, &board.canvas);
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn pen_up() {
+    let mut board = BOARD.lock().unwrap();
+    let Some(board) = board.as_mut() else {
+        return;
+    };
+    board.erasing = false;
+    if let Some(mut stroke) = board.current.take() {
+        stroke.finished = Some(Instant::now());
+        board.strokes.push(stroke);
+    }
+}
+
+/// Advances the fade of expired strokes, dropping those that have faded out. Runs
+/// on the main thread.
+fn fade() {
+    let mut board = BOARD.lock().unwrap();
+    let Some(board) = board.as_mut() else {
+        return;
+    };
+    let now = Instant::now();
+    let fade = motion::fade(FADE);
+    let before = board.strokes.len();
+    board
+        .strokes
+        .retain(|stroke| stroke.opacity(now, fade) > 0.0);
+    let fading = board
+        .strokes
+        .iter()
+        .any(|stroke| stroke.opacity(now, fade) < 1.0);
+    if fading || board.strokes.len() != before {
+        board.repaint();
+        platform::present(board.handle, &board.canvas);
+    }
+}
+
+fn info(board: &Board) -> AnnotationInfo {
+    AnnotationInfo {
+        monitor: board.monitor,
+        rect: board.rect,
+        strokes: board.strokes.len(),
+    }
+}
+
+/// Opens the drawing window over `monitor`, replacing any open one. Runs on the
+/// main thread.
+pub fn start(monitor: usize, style: PenStyle) -> Result<AnnotationInfo, String> {
+    let monitors = coords::monitors();
+    let m = monitors.get(monitor).ok_or_else(|| {
+        format!(
+            "Monitor index {} out of range (found {})",
+            monitor,
+            monitors.len()
+        )
+    })?;
+    style.pen(m.scale_factor)?;
+    stop();
+
+    let rect = Rect {
+        x: m.x,
+        y: m.y,
+        width: m.width,
+        height: m.height,
+    };
+    let (handle, scale, (width, height)) = platform::open(rect, m.scale_factor)?;
+    let board = Board {
+        handle,
+        monitor,
+        rect,
+        scale,
+        style,
+        strokes: Vec::new(),
+        current: None,
+        erasing: false,
+        canvas: RgbaImage::from_pixel(width, height, BACKGROUND),
+    };
+    platform::present(handle, &board.canvas);
+    let info = info(&board);
+    *BOARD.lock().unwrap() = Some(board);
+    Ok(info)
+}
+
+/// Closes the drawing window, discarding its strokes. Runs on the main thread.
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    let board = BOARD.lock().unwrap().take();
+    if let Some(board) = board {
+        platform::close(board.handle);
+    }
+}
+
+pub fn clear() -> Result<(), String> {
+    let mut board = BOARD.lock().unwrap();
+    let board = board.as_mut().ok_or("Annotation is not active")?;
+    board.strokes.clear();
+    board.current = None;
+    board.repaint();
+    platform::present(board.handle, &board.canvas);
+    Ok(())
+}
+
+pub fn set_style(style: PenStyle) -> Result<(), String> {
+    let mut board = BOARD.lock().unwrap();
+    let board = board.as_mut().ok_or("Annotation is not active")?;
+    style.pen(board.scale)?;
+    board.style = style;
+    Ok(())
+}
+
+// ==========================================
+// Saving and Export
+// ==========================================
+
+/// The strokes drawn on a transparent canvas, with straight (not premultiplied)
+/// alpha as image files expect.
+fn render(strokes: &[Stroke], width: u32, height: u32) -> RgbaImage {
+    let mut canvas = RgbaImage::new(width, height);
+    let now = Instant::now();
+    let fade = motion::fade(FADE);
+    for stroke in strokes {
+        draw_stroke(&mut canvas, stroke, stroke.opacity(now, fade));
+    }
+    for pixel in canvas.pixels_mut() {
+        let alpha = pixel[3];
+        if alpha > 0 && alpha < 255 {
+            for channel in &mut pixel.0[..3] {
+                *channel = (*channel as u32 * 255 / alpha as u32).min(255) as u8;
+            }
+        }
+    }
+    canvas
+}
+
+/// Each segment becomes a round-capped line as wide as the pen at its mean pressure;
+/// SVG strokes can't vary in width along a path.
+fn svg(strokes: &[Stroke], width: u32, height: u32) -> String {
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        width, height
+    );
+    for stroke in strokes {
+        let color = palette::hex(stroke.pen.color);
+        let _ = writeln!(
+            out,
+            "<g fill=\"{0}\" stroke=\"{0}\" stroke-linecap=\"round\">",
+            color
+        );
+        if let Some(first) = stroke.points.first() {
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.2}\"/>",
+                first.x,
+                first.y,
+                radius(&stroke.pen, first.pressure)
+            );
+        }
+        for pair in stroke.points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let _ = writeln!(
+                out,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke-width=\"{:.2}\"/>",
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                radius(&stroke.pen, (a.pressure + b.pressure) / 2.0) * 2.0
+            );
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Writes the board's strokes to `path`. The stroke in progress, if any, is left out.
+pub fn export(path: &str, format: AnnotationFormat) -> Result<(), String> {
+    // Copied out so the pen isn't blocked while the screen is captured.
+    let (strokes, rect, scale, (width, height)) = {
+        let board = BOARD.lock().unwrap();
+        let board = board.as_ref().ok_or("Annotation is not active")?;
+        (
+            board.strokes.clone(),
+            board.rect,
+            board.scale,
+            board.canvas.dimensions(),
+        )
+    };
+    crash::breadcrumb("annotate", format!("export {:?}", format));
+    match format {
+        AnnotationFormat::Json => {
+            let saved = SavedAnnotations {
+                strokes: strokes
+                    .iter()
+                    .map(|stroke| SavedStroke {
+                        color: palette::hex(stroke.pen.color),
+                        width: stroke.pen.width / scale,
+                        points: stroke
+                            .points
+                            .iter()
+                            .map(|p| [p.x / scale, p.y / scale, p.pressure])
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            let json = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+            fs::write(path, json).map_err(|e| format!("Failed to save annotations: {}", e))
+        }
+        AnnotationFormat::Svg => fs::write(path, svg(&strokes, width, height))
+            .map_err(|e| format!("Failed to save annotations: {}", e)),
+        AnnotationFormat::Png => render(&strokes, width, height)
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to save annotations: {}", e)),
+        AnnotationFormat::Screenshot => {
+            // The drawing window usually shows in the capture already; drawing the
+            // strokes again keeps them when it doesn't, at the same place either way.
+            let mut shot = capture::capture_rect(rect)?;
+            let mut layer = render(&strokes, width, height);
+            if layer.dimensions() != shot.dimensions() {
+                layer = imageops::resize(
+                    &layer,
+                    shot.width(),
+                    shot.height(),
+                    imageops::FilterType::Triangle,
+                );
+            }
+            imageops::overlay(&mut shot, &layer, 0, 0);
+            shot.save_with_format(path, image::ImageFormat::Png)
+                .map_err(|e| format!("Failed to save annotations: {}", e))
+        }
+    }
+}
+
+/// Replaces the board's strokes with the set saved at `path` in the JSON format.
+/// Returns how many were loaded.
+pub fn load(path: &str) -> Result<usize, String> {
+    let json =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read annotations: {}", e))?;
+    let saved: SavedAnnotations =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid annotations file: {}", e))?;
+
+    let mut board = BOARD.lock().unwrap();
+    let board = board.as_mut().ok_or("Annotation is not active")?;
+    let scale = board.scale;
+    let strokes = saved
+        .strokes
+        .iter()
+        .map(|stroke| {
+            // Restored strokes stay until erased, whatever pen drew them.
+            let pen = PenStyle {
+                color: Some(stroke.color.clone()),
+                width: stroke.width,
+                ttl_ms: None,
+            }
+            .pen(scale)?;
+            Ok(Stroke {
+                pen,
+                points: stroke
+                    .points
+                    .iter()
+                    .map(|&[x, y, pressure]| StrokePoint {
+                        x: x * scale,
+                        y: y * scale,
+                        pressure: pressure.clamp(0.0, 1.0),
+                    })
+                    .collect(),
+                finished: Some(Instant::now()),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    crash::breadcrumb("annotate", format!("load {} strokes", strokes.len()));
+    board.strokes = strokes;
+    board.current = None;
+    board.repaint();
+    platform::present(board.handle, &board.canvas);
+    Ok(board.strokes.len())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use image::RgbaImage;
+    use once_cell::sync::Lazy;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, SIZE, WPARAM};
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GetDC, ReleaseDC,
+        SelectObject, AC_SRC_ALPHA, AC_SRC_OVER, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        BLENDFUNCTION, DIB_RGB_COLORS,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{ReleaseCapture, SetCapture};
+    use windows::Win32::UI::Input::Pointer::{
+        GetPointerPenInfo, GetPointerType, POINTER_FLAG_INCONTACT, POINTER_PEN_INFO,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, GetWindowRect, RegisterClassW, ShowWindow,
+        UpdateLayeredWindow, PEN_FLAG_ERASER, PEN_FLAG_INVERTED, PEN_MASK_PRESSURE,
+        POINTER_INPUT_TYPE, PT_PEN, SW_SHOWNOACTIVATE, ULW_ALPHA, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MOUSEMOVE, WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE, WNDCLASSW, WS_EX_LAYERED,
+        WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+    };
+
+    use super::StrokePoint;
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    const CLASS_NAME: PCWSTR = w!("ScreenShareHostAnnotation");
+    /// Full scale of `POINTER_PEN_INFO::pressure`.
+    const MAX_PRESSURE: f64 = 1024.0;
+    const MK_LBUTTON: usize = 0x0001;
+
+    static REGISTERED: Lazy<bool> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(wnd_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        RegisterClassW(&class) != 0
+    });
+
+    /// Client coordinates, signed 16-bit each.
+    fn mouse_point(lparam: LPARAM) -> StrokePoint {
+        StrokePoint {
+            x: (lparam.0 & 0xFFFF) as i16 as f64,
+            y: ((lparam.0 >> 16) & 0xFFFF) as i16 as f64,
+            pressure: 1.0,
+        }
+    }
+
+    /// The pen's position and pressure, and whether its eraser is in use; `None` for
+    /// other pointers, which Windows then delivers again as mouse messages.
+    unsafe fn pen_point(hwnd: HWND, wparam: WPARAM) -> Option<(StrokePoint, bool, bool)> {
+        let pointer = (wparam.0 & 0xFFFF) as u32;
+        let mut kind = POINTER_INPUT_TYPE::default();
+        GetPointerType(pointer, &mut kind).ok()?;
+        if kind != PT_PEN {
+            return None;
+        }
+        let mut info = POINTER_PEN_INFO::default();
+        GetPointerPenInfo(pointer, &mut info).ok()?;
+        let mut window = RECT::default();
+        GetWindowRect(hwnd, &mut window).ok()?;
+        let location = info.pointerInfo.ptPixelLocation;
+        let pressure = if info.penMask & PEN_MASK_PRESSURE != 0 {
+            info.pressure as f64 / MAX_PRESSURE
+        } else {
+            1.0
+        };
+        let eraser = info.penFlags & (PEN_FLAG_ERASER | PEN_FLAG_INVERTED) != 0;
+        let contact = info.pointerInfo.pointerFlags.0 & POINTER_FLAG_INCONTACT.0 != 0;
+        let point = StrokePoint {
+            x: (location.x - window.left) as f64,
+            y: (location.y - window.top) as f64,
+            pressure,
+        };
+        Some((point, eraser, contact))
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP => {
+                let Some((point, eraser, contact)) = pen_point(hwnd, wparam) else {
+                    return DefWindowProcW(hwnd, msg, wparam, lparam);
+                };
+                match msg {
+                    WM_POINTERDOWN => super::pen_down(point, eraser),
+                    // Hovering pens send updates too.
+                    WM_POINTERUPDATE if contact => super::pen_move(point),
+                    WM_POINTERUP => super::pen_up(),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            WM_LBUTTONDOWN => {
+                SetCapture(hwnd);
+                super::pen_down(mouse_point(lparam), false);
+                LRESULT(0)
+            }
+            WM_MOUSEMOVE => {
+                if wparam.0 & MK_LBUTTON != 0 {
+                    super::pen_move(mouse_point(lparam));
+                }
+                LRESULT(0)
+            }
+            WM_LBUTTONUP => {
+                let _ = ReleaseCapture();
+                super::pen_up();
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Returns the window, its canvas scale and the canvas size in pixels.
+    pub fn open(rect: Rect, scale: f64) -> Result<(usize, f64, (u32, u32)), String> {
+        if !*REGISTERED {
+            return Err("Failed to register annotation window class".to_string());
+        }
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_NOACTIVATE,
+                CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null()).map_err(|e| e.to_string())?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create annotation window: {}", e))?;
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            layers::add(Layer::Annotation, hwnd.0 as usize);
+            Ok((
+                hwnd.0 as usize,
+                scale,
+                (rect.width.max(1) as u32, rect.height.max(1) as u32),
+            ))
+        }
+    }
+
+    /// Shows `canvas` (premultiplied RGBA) as the window's per-pixel-alpha content.
+    pub fn present(handle: usize, canvas: &RgbaImage) {
+        let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height for top-down rows.
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe {
+            let screen = GetDC(HWND::default());
+            let memory = CreateCompatibleDC(screen);
+            let mut bits: *mut c_void = std::ptr::null_mut();
+            if let Ok(bitmap) = CreateDIBSection(memory, &info, DIB_RGB_COLORS, &mut bits, None, 0)
+            {
+                let pixels = std::slice::from_raw_parts_mut(bits as *mut u8, canvas.len());
+                for (bgra, rgba) in pixels.chunks_exact_mut(4).zip(canvas.pixels()) {
+                    bgra.copy_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+                }
+                let previous = SelectObject(memory, bitmap);
+                let blend = BLENDFUNCTION {
+                    BlendOp: AC_SRC_OVER as u8,
+                    BlendFlags: 0,
+                    SourceConstantAlpha: 255,
+                    AlphaFormat: AC_SRC_ALPHA as u8,
+                };
+                let _ = UpdateLayeredWindow(
+                    HWND(handle as *mut _),
+                    screen,
+                    None,
+                    Some(&SIZE {
+                        cx: width,
+                        cy: height,
+                    }),
+                    memory,
+                    Some(&POINT::default()),
+                    COLORREF(0),
+                    Some(&blend),
+                    ULW_ALPHA,
+                );
+                SelectObject(memory, previous);
+                let _ = DeleteObject(bitmap);
+            }
+            let _ = DeleteDC(memory);
+            ReleaseDC(HWND::default(), screen);
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        unsafe {
+            let _ = DestroyWindow(HWND(handle as *mut _));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::appkit::{NSBackingStoreType, NSWindow, NSWindowStyleMask};
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString};
+    use image::RgbaImage;
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Class, Object, Sel, BOOL};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::StrokePoint;
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    const VIEW_CLASS: &str = "ScreenShareHostAnnotationView";
+    /// NSPointingDeviceTypeEraser.
+    const POINTING_DEVICE_ERASER: u64 = 3;
+
+    /// Set while the pen's eraser end is near the tablet; tablet proximity events
+    /// say which end is in use, point events don't.
+    static ERASER: AtomicBool = AtomicBool::new(false);
+
+    /// The event's position in canvas pixels, top-left based.
+    unsafe fn point(view: &Object, event: id) -> StrokePoint {
+        let window: id = msg_send![view, window];
+        let location: NSPoint = msg_send![event, locationInWindow];
+        let bounds: NSRect = msg_send![view, bounds];
+        let scale: f64 = msg_send![window, backingScaleFactor];
+        let pressure: f32 = msg_send![event, pressure];
+        StrokePoint {
+            x: location.x * scale,
+            y: (bounds.size.height - location.y) * scale,
+            pressure: pressure as f64,
+        }
+    }
+
+    extern "C" fn mouse_down(this: &Object, _sel: Sel, event: id) {
+        let point = unsafe { point(this, event) };
+        super::pen_down(point, ERASER.load(Ordering::SeqCst));
+    }
+
+    extern "C" fn mouse_dragged(this: &Object, _sel: Sel, event: id) {
+        let point = unsafe { point(this, event) };
+        super::pen_move(point);
+    }
+
+    extern "C" fn mouse_up(_this: &Object, _sel: Sel, _event: id) {
+        super::pen_up();
+    }
+
+    extern "C" fn tablet_proximity(_this: &Object, _sel: Sel, event: id) {
+        unsafe {
+            let entering: BOOL = msg_send![event, isEnteringProximity];
+            let device: u64 = msg_send![event, pointingDeviceType];
+            ERASER.store(
+                entering == YES && device == POINTING_DEVICE_ERASER,
+                Ordering::SeqCst,
+            );
+        }
+    }
+
+    extern "C" fn accepts_first_mouse(_this: &Object, _sel: Sel, _event: id) -> BOOL {
+        YES
+    }
+
+    fn view_class() -> &'static Class {
+        if let Some(class) = Class::get(VIEW_CLASS) {
+            return class;
+        }
+        let mut decl = ClassDecl::new(VIEW_CLASS, class!(NSView)).expect("annotation view class");
+        unsafe {
+            decl.add_method(
+                sel!(mouseDown:),
+                mouse_down as extern "C" fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(mouseDragged:),
+                mouse_dragged as extern "C" fn(&Object, Sel, id),
+            );
+            decl.add_method(sel!(mouseUp:), mouse_up as extern "C" fn(&Object, Sel, id));
+            decl.add_method(
+                sel!(tabletProximity:),
+                tablet_proximity as extern "C" fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(acceptsFirstMouse:),
+                accepts_first_mouse as extern "C" fn(&Object, Sel, id) -> BOOL,
+            );
+        }
+        decl.register()
+    }
+
+    /// Returns the window, its canvas scale and the canvas size in pixels. The
+    /// canvas matches the screen's backing pixels, so strokes stay sharp on Retina
+    /// screens.
+    pub fn open(rect: Rect, _scale: f64) -> Result<(usize, f64, (u32, u32)), String> {
+        unsafe {
+            let screens: id = msg_send![class!(NSScreen), screens];
+            let count: usize = msg_send![screens, count];
+            if count == 0 {
+                return Err("No screens found".to_string());
+            }
+            let primary: id = msg_send![screens, objectAtIndex: 0usize];
+            let primary: NSRect = msg_send![primary, frame];
+            let frame = NSRect::new(
+                NSPoint::new(
+                    rect.x as f64,
+                    primary.size.height - (rect.y + rect.height) as f64,
+                ),
+                NSSize::new(rect.width as f64, rect.height as f64),
+            );
+            let window: id = msg_send![class!(NSWindow), alloc];
+            let window: id = window.initWithContentRect_styleMask_backing_defer_(
+                frame,
+                NSWindowStyleMask::NSBorderlessWindowMask,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                false,
+            );
+            if window == nil {
+                return Err("Failed to create annotation window".to_string());
+            }
+            let clear: id = msg_send![class!(NSColor), clearColor];
+            window.setBackgroundColor_(clear);
+            let _: () = msg_send![window, setReleasedWhenClosed: NO];
+            let _: () = msg_send![window, setOpaque: NO];
+            let _: () = msg_send![window, setHasShadow: NO];
+
+            let view: id = msg_send![view_class(), alloc];
+            let view: id = msg_send![view, initWithFrame: NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                frame.size,
+            )];
+            let _: () = msg_send![view, setWantsLayer: YES];
+            window.setContentView_(view);
+            let _: () = msg_send![view, release];
+            layers::add(Layer::Annotation, window as usize);
+            window.orderFrontRegardless();
+
+            let scale: f64 = msg_send![window, backingScaleFactor];
+            let size = (
+                (frame.size.width * scale).round().max(1.0) as u32,
+                (frame.size.height * scale).round().max(1.0) as u32,
+            );
+            Ok((window as usize, scale, size))
+        }
+    }
+
+    /// Shows `canvas` (premultiplied RGBA) as the content view's layer contents.
+    pub fn present(handle: usize, canvas: &RgbaImage) {
+        let (width, height) = canvas.dimensions();
+        unsafe {
+            let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+            let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+            let rep: id = msg_send![rep,
+                initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>()
+                pixelsWide: width as isize
+                pixelsHigh: height as isize
+                bitsPerSample: 8isize
+                samplesPerPixel: 4isize
+                hasAlpha: YES
+                isPlanar: NO
+                colorSpaceName: color_space
+                bytesPerRow: (width * 4) as isize
+                bitsPerPixel: 32isize];
+            let _: () = msg_send![color_space, release];
+            if rep == nil {
+                return;
+            }
+            let data: *mut u8 = msg_send![rep, bitmapData];
+            std::ptr::copy_nonoverlapping(canvas.as_raw().as_ptr(), data, canvas.as_raw().len());
+
+            let view: id = msg_send![handle as id, contentView];
+            let bounds: NSRect = msg_send![view, bounds];
+            let picture: id = msg_send![class!(NSImage), alloc];
+            let picture: id = msg_send![picture, initWithSize: bounds.size];
+            let _: () = msg_send![picture, addRepresentation: rep];
+            let _: () = msg_send![rep, release];
+            let layer: id = msg_send![view, layer];
+            let _: () = msg_send![layer, setContents: picture];
+            let _: () = msg_send![picture, release];
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        ERASER.store(false, Ordering::SeqCst);
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, close];
+            let _: () = msg_send![window, release];
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use image::RgbaImage;
+
+    use crate::coords::Rect;
+
+    pub fn open(_rect: Rect, _scale: f64) -> Result<(usize, f64, (u32, u32)), String> {
+        Err("Annotation is not supported on this platform".to_string())
+    }
+
+    pub fn present(_handle: usize, _canvas: &RgbaImage) {}
+
+    pub fn close(_handle: usize) {}
+}
+
+// ==========================================
+// ANNOTATION COMMANDS
+// ==========================================
+
+/// Opens a window over `monitor` (the first when omitted) to draw on with a pen or
+/// the mouse. Pen pressure varies the stroke width; the pen's eraser removes the
+/// strokes it touches. Drawing takes every click on that monitor until stopped.
+#[tauri::command]
+pub fn start_annotating(
+    app: AppHandle,
+    monitor: Option<usize>,
+    pen: Option<PenStyle>,
+) -> Result<AnnotationInfo, String> {
+    crash::breadcrumb("annotate", "start_annotating");
+    let info = start(monitor.unwrap_or(0), pen.unwrap_or_default())?;
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    thread::Builder::new()
+        .name("annotation-fade".to_string())
+        .spawn(move || {
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(FADE_TICK);
+                let _ = app.run_on_main_thread(fade);
+            }
+        })
+        .map_err(|e| format!("Failed to spawn annotation fade timer: {}", e))?;
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn stop_annotating() {
+    crash::breadcrumb("annotate", "stop_annotating");
+    stop();
+}
+
+/// Removes every stroke, keeping the drawing window open.
+#[tauri::command]
+pub fn clear_annotations() -> Result<(), String> {
+    clear()
+}
+
+/// Changes the pen for strokes drawn from now on.
+#[tauri::command]
+pub fn set_annotation_pen(pen: PenStyle) -> Result<(), String> {
+    set_style(pen)
+}
+
+#[tauri::command]
+pub fn get_annotation() -> Option<AnnotationInfo> {
+    BOARD.lock().unwrap().as_ref().map(info)
+}
+
+/// Saves the strokes as `json` (for `load_annotations`), a transparent `png` or
+/// `svg`, or a `screenshot` of the monitor with the strokes on top.
+#[tauri::command]
+pub fn export_annotations(path: String, format: AnnotationFormat) -> Result<(), String> {
+    export(&path, format)
+}
+
+/// Replaces the strokes with a set saved as `json`.
+#[tauri::command]
+pub fn load_annotations(path: String) -> Result<usize, String> {
+    load(&path)
+}
@@ -0,0 +1,613 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::coords::Rect;
+
+// ==========================================
+// Application Windows
+// ==========================================
+
+/// A top-level window of some application, in native coordinates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WindowInfo {
+    /// HWND on Windows, CGWindowID on macOS, X11 window id on Linux.
+    pub id: u64,
+    pub title: String,
+    /// Executable name without path.
+    pub process: String,
+    pub pid: u32,
+    pub rect: Rect,
+    /// False for minimized windows.
+    pub visible: bool,
+    pub focused: bool,
+}
+
+/// Topmost visible window containing the point.
+#[cfg(feature = "scripting")]
+pub fn window_at(x: i32, y: i32) -> Option<WindowInfo> {
+    #[cfg(target_os = "windows")]
+    {
+        platform::window_at(x, y)
+    }
+
+    // Lists are ordered front to back.
+    #[cfg(not(target_os = "windows"))]
+    {
+        platform::list_windows().into_iter().find(|w| {
+            w.visible
+                && x >= w.rect.x
+                && y >= w.rect.y
+                && x < w.rect.x + w.rect.width
+                && y < w.rect.y + w.rect.height
+        })
+    }
+}
+
+pub fn list_windows() -> Vec<WindowInfo> {
+    platform::list_windows()
+}
+
+/// The window `id`, if it still exists.
+pub fn window(id: u64) -> Option<WindowInfo> {
+    #[cfg(target_os = "windows")]
+    {
+        platform::window(id)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        platform::list_windows().into_iter().find(|w| w.id == id)
+    }
+}
+
+/// Windows floating above normal application windows, front to back.
+#[cfg(target_os = "macos")]
+pub fn list_floating_windows() -> Vec<WindowInfo> {
+    platform::list_floating_windows()
+}
+
+/// The window currently receiving keyboard input.
+#[cfg(feature = "scripting")]
+pub fn focused_window() -> Option<WindowInfo> {
+    platform::list_windows().into_iter().find(|w| w.focused)
+}
+
+/// Identifies a window; every field that is set must match.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WindowCriteria {
+    /// Case-insensitive substring of the title.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Case-insensitive executable name; the `.exe` suffix is optional.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+}
+
+impl WindowCriteria {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.process.is_none() && self.pid.is_none()
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        let title = self.title.as_ref().map_or(true, |title| {
+            window.title.to_lowercase().contains(&title.to_lowercase())
+        });
+        let process = self.process.as_ref().map_or(true, |process| {
+            let wanted = process.to_lowercase();
+            let actual = window.process.to_lowercase();
+            actual == wanted || actual.strip_suffix(".exe") == Some(wanted.as_str())
+        });
+        let pid = self.pid.map_or(true, |pid| window.pid == pid);
+        title && process && pid
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowState {
+    Exists,
+    Visible,
+    Foreground,
+    /// Visible and done processing startup input; see `wait_for_window`.
+    Idle,
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Without an OS idle signal, a window is idle once its title and bounds stop changing.
+const IDLE_SETTLE: Duration = Duration::from_millis(500);
+
+/// Front-most window matching `criteria`.
+pub fn find_window(criteria: &WindowCriteria) -> Option<WindowInfo> {
+    platform::list_windows()
+        .into_iter()
+        .find(|window| criteria.matches(window))
+}
+
+/// Polls until a window matching `criteria` reaches `state`, and returns it.
+///
+/// "idle" uses `WaitForInputIdle` on Windows. Elsewhere, and for processes without a
+/// message queue, it falls back to the window being visible and unchanged for 500ms.
+pub fn wait_for_state(
+    criteria: &WindowCriteria,
+    state: WindowState,
+    timeout: Duration,
+) -> Result<WindowInfo, String> {
+    if criteria.is_empty() {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut settling: Option<(WindowInfo, Instant)> = None;
+
+    loop {
+        if let Some(window) = find_window(criteria) {
+            let reached = match state {
+                WindowState::Exists => true,
+                WindowState::Visible => window.visible,
+                WindowState::Foreground => window.focused,
+                WindowState::Idle if !window.visible => false,
+                WindowState::Idle => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    match platform::wait_input_idle(window.pid, remaining.min(POLL_INTERVAL)) {
+                        Some(idle) => idle,
+                        None => {
+                            let unchanged = settling.as_ref().is_some_and(|(last, _)| {
+                                last.id == window.id
+                                    && last.title == window.title
+                                    && last.rect == window.rect
+                            });
+                            if !unchanged {
+                                settling = Some((window.clone(), Instant::now()));
+                            }
+                            settling
+                                .as_ref()
+                                .is_some_and(|(_, since)| since.elapsed() >= IDLE_SETTLE)
+                        }
+                    }
+                }
+            };
+            if reached {
+                return Ok(window);
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Timed out after {}ms waiting for window {:?} to be {:?}",
+                timeout.as_millis(),
+                criteria,
+                state
+            ));
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())));
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::time::Duration;
+
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT, WAIT_TIMEOUT};
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, WaitForInputIdle, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SYNCHRONIZE,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetForegroundWindow, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId,
+        IsIconic, IsWindow, IsWindowVisible,
+    };
+
+    use super::WindowInfo;
+    use crate::coords::Rect;
+
+    fn process_name(pid: u32) -> String {
+        unsafe {
+            let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+                return String::new();
+            };
+            let mut buffer = [0u16; 1024];
+            let mut len = buffer.len() as u32;
+            let ok = QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut len,
+            )
+            .is_ok();
+            let _ = CloseHandle(process);
+
+            if ok {
+                let path = String::from_utf16_lossy(&buffer[..len as usize]);
+                path.rsplit('\\').next().unwrap_or(&path).to_string()
+            } else {
+                String::new()
+            }
+        }
+    }
+
+    pub fn describe(hwnd: HWND) -> WindowInfo {
+        unsafe {
+            let mut title = [0u16; 512];
+            let len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+
+            let mut pid = 0u32;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+            let mut r = RECT::default();
+            let _ = GetWindowRect(hwnd, &mut r);
+
+            WindowInfo {
+                id: hwnd.0 as u64,
+                title: String::from_utf16_lossy(&title[..len]),
+                process: process_name(pid),
+                pid,
+                rect: Rect {
+                    x: r.left,
+                    y: r.top,
+                    width: r.right - r.left,
+                    height: r.bottom - r.top,
+                },
+                visible: IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool(),
+                focused: GetForegroundWindow() == hwnd,
+            }
+        }
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn window_at(x: i32, y: i32) -> Option<WindowInfo> {
+        use windows::Win32::Foundation::POINT;
+        use windows::Win32::UI::WindowsAndMessaging::{GetAncestor, WindowFromPoint, GA_ROOT};
+
+        unsafe {
+            let hwnd = WindowFromPoint(POINT { x, y });
+            if hwnd.0.is_null() {
+                return None;
+            }
+            Some(describe(GetAncestor(hwnd, GA_ROOT)))
+        }
+    }
+
+    /// `None` when the process has no message queue (console apps) or can't be opened.
+    pub fn wait_input_idle(pid: u32, timeout: Duration) -> Option<bool> {
+        unsafe {
+            let process =
+                OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_SYNCHRONIZE, false, pid).ok()?;
+            let result = WaitForInputIdle(process, timeout.as_millis() as u32);
+            let _ = CloseHandle(process);
+            match result {
+                0 => Some(true),
+                r if r == WAIT_TIMEOUT.0 => Some(false),
+                _ => None,
+            }
+        }
+    }
+
+    pub fn window(id: u64) -> Option<WindowInfo> {
+        let hwnd = HWND(id as *mut _);
+        unsafe { IsWindow(hwnd) }.as_bool().then(|| describe(hwnd))
+    }
+
+    pub fn list_windows() -> Vec<WindowInfo> {
+        unsafe extern "system" fn callback(hwnd: HWND, data: LPARAM) -> BOOL {
+            let windows = &mut *(data.0 as *mut Vec<WindowInfo>);
+            // Untitled top-level windows are almost always tool or message windows.
+            let info = describe(hwnd);
+            if !info.title.is_empty() {
+                windows.push(info);
+            }
+            BOOL(1)
+        }
+
+        let mut windows: Vec<WindowInfo> = Vec::new();
+        unsafe {
+            let _ = EnumWindows(
+                Some(callback),
+                LPARAM(&mut windows as *mut Vec<WindowInfo> as isize),
+            );
+        }
+        windows
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{c_char, c_void, CStr};
+
+    use cocoa::base::id;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::WindowInfo;
+    use crate::coords::Rect;
+
+    /// macOS has no equivalent of `WaitForInputIdle`.
+    pub fn wait_input_idle(_pid: u32, _timeout: std::time::Duration) -> Option<bool> {
+        None
+    }
+
+    type CFTypeRef = *const c_void;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct CGRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    const WINDOW_LIST_ALL: u32 = 0;
+    const NUMBER_SINT64: isize = 4;
+    const UTF8: u32 = 0x0800_0100;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        static kCGWindowNumber: CFTypeRef;
+        static kCGWindowName: CFTypeRef;
+        static kCGWindowOwnerName: CFTypeRef;
+        static kCGWindowOwnerPID: CFTypeRef;
+        static kCGWindowBounds: CFTypeRef;
+        static kCGWindowLayer: CFTypeRef;
+        static kCGWindowIsOnscreen: CFTypeRef;
+        fn CGWindowListCopyWindowInfo(option: u32, relative_to: u32) -> CFTypeRef;
+        fn CGRectMakeWithDictionaryRepresentation(dict: CFTypeRef, rect: *mut CGRect) -> bool;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFArrayGetCount(array: CFTypeRef) -> isize;
+        fn CFArrayGetValueAtIndex(array: CFTypeRef, index: isize) -> CFTypeRef;
+        fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+        fn CFNumberGetValue(number: CFTypeRef, kind: isize, value: *mut c_void) -> bool;
+        fn CFBooleanGetValue(boolean: CFTypeRef) -> bool;
+        fn CFStringGetCString(
+            s: CFTypeRef,
+            buffer: *mut c_char,
+            size: isize,
+            encoding: u32,
+        ) -> bool;
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    unsafe fn number(dict: CFTypeRef, key: CFTypeRef) -> i64 {
+        let value = CFDictionaryGetValue(dict, key);
+        let mut out = 0i64;
+        if !value.is_null() {
+            CFNumberGetValue(value, NUMBER_SINT64, &mut out as *mut i64 as *mut c_void);
+        }
+        out
+    }
+
+    unsafe fn string(dict: CFTypeRef, key: CFTypeRef) -> String {
+        let value = CFDictionaryGetValue(dict, key);
+        let mut buffer = [0 as c_char; 1024];
+        if value.is_null()
+            || !CFStringGetCString(value, buffer.as_mut_ptr(), buffer.len() as isize, UTF8)
+        {
+            return String::new();
+        }
+        CStr::from_ptr(buffer.as_ptr())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn frontmost_pid() -> i64 {
+        unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let app: id = msg_send![workspace, frontmostApplication];
+            if app.is_null() {
+                return -1;
+            }
+            let pid: i32 = msg_send![app, processIdentifier];
+            pid as i64
+        }
+    }
+
+    /// Window titles are only reported once Screen Recording permission is granted;
+    /// without it `title` is empty but owner and bounds are still available.
+    pub fn list_windows() -> Vec<WindowInfo> {
+        // Layer 0 holds normal application windows; others are menus, the dock, etc.
+        windows_in_layers(|layer| layer == 0)
+    }
+
+    /// Windows above the normal layer: notification banners, panels, menus.
+    pub fn list_floating_windows() -> Vec<WindowInfo> {
+        windows_in_layers(|layer| layer > 0)
+    }
+
+    fn windows_in_layers(wanted: impl Fn(i64) -> bool) -> Vec<WindowInfo> {
+        let mut windows = Vec::new();
+        let frontmost = frontmost_pid();
+        let mut focus_assigned = false;
+
+        unsafe {
+            let list = CGWindowListCopyWindowInfo(WINDOW_LIST_ALL, 0);
+            if list.is_null() {
+                return windows;
+            }
+
+            for i in 0..CFArrayGetCount(list) {
+                let dict = CFArrayGetValueAtIndex(list, i);
+                if !wanted(number(dict, kCGWindowLayer)) {
+                    continue;
+                }
+
+                let mut bounds = CGRect::default();
+                let bounds_dict = CFDictionaryGetValue(dict, kCGWindowBounds);
+                if bounds_dict.is_null()
+                    || !CGRectMakeWithDictionaryRepresentation(bounds_dict, &mut bounds)
+                {
+                    continue;
+                }
+
+                let onscreen = CFDictionaryGetValue(dict, kCGWindowIsOnscreen);
+                let visible = !onscreen.is_null() && CFBooleanGetValue(onscreen);
+                let pid = number(dict, kCGWindowOwnerPID);
+                // The frontmost app's first on-screen window is the key window.
+                let focused = !focus_assigned && visible && pid == frontmost;
+                focus_assigned |= focused;
+
+                windows.push(WindowInfo {
+                    id: number(dict, kCGWindowNumber) as u64,
+                    title: string(dict, kCGWindowName),
+                    process: string(dict, kCGWindowOwnerName),
+                    pid: pid as u32,
+                    rect: Rect {
+                        x: bounds.x.round() as i32,
+                        y: bounds.y.round() as i32,
+                        width: bounds.width.round() as i32,
+                        height: bounds.height.round() as i32,
+                    },
+                    visible,
+                    focused,
+                });
+            }
+            CFRelease(list);
+        }
+        windows
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, MapState, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    use super::WindowInfo;
+    use crate::coords::Rect;
+
+    /// X11 has no notion of input idleness.
+    pub fn wait_input_idle(_pid: u32, _timeout: std::time::Duration) -> Option<bool> {
+        None
+    }
+
+    fn atom(conn: &RustConnection, name: &str) -> Option<u32> {
+        Some(
+            conn.intern_atom(false, name.as_bytes())
+                .ok()?
+                .reply()
+                .ok()?
+                .atom,
+        )
+    }
+
+    fn property_u32(conn: &RustConnection, window: Window, name: u32, kind: AtomEnum) -> Vec<u32> {
+        conn.get_property(false, window, name, kind, 0, u32::MAX / 4)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|values| values.collect()))
+            .unwrap_or_default()
+    }
+
+    /// Uses the EWMH client list, so windows are listed in the window manager's order
+    /// (stacking order, reversed to front-to-back, when available).
+    pub fn list_windows() -> Vec<WindowInfo> {
+        let Ok((conn, screen_num)) = x11rb::connect(None) else {
+            return Vec::new();
+        };
+        let root = conn.setup().roots[screen_num].root;
+
+        let (
+            Some(stacking),
+            Some(client_list),
+            Some(wm_name),
+            Some(utf8),
+            Some(wm_pid),
+            Some(active),
+        ) = (
+            atom(&conn, "_NET_CLIENT_LIST_STACKING"),
+            atom(&conn, "_NET_CLIENT_LIST"),
+            atom(&conn, "_NET_WM_NAME"),
+            atom(&conn, "UTF8_STRING"),
+            atom(&conn, "_NET_WM_PID"),
+            atom(&conn, "_NET_ACTIVE_WINDOW"),
+        )
+        else {
+            return Vec::new();
+        };
+
+        let mut clients = property_u32(&conn, root, stacking, AtomEnum::WINDOW);
+        if clients.is_empty() {
+            clients = property_u32(&conn, root, client_list, AtomEnum::WINDOW);
+        }
+        clients.reverse();
+        let focused = property_u32(&conn, root, active, AtomEnum::WINDOW)
+            .first()
+            .copied();
+
+        clients
+            .into_iter()
+            .filter_map(|window| {
+                let title = conn
+                    .get_property(false, window, wm_name, utf8, 0, 1024)
+                    .ok()?
+                    .reply()
+                    .ok()
+                    .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+                    .unwrap_or_default();
+                let pid = property_u32(&conn, window, wm_pid, AtomEnum::CARDINAL)
+                    .first()
+                    .copied()
+                    .unwrap_or(0);
+                let process = std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_default();
+
+                let geometry = conn.get_geometry(window).ok()?.reply().ok()?;
+                let origin = conn
+                    .translate_coordinates(window, root, 0, 0)
+                    .ok()?
+                    .reply()
+                    .ok()?;
+                let visible = conn
+                    .get_window_attributes(window)
+                    .ok()?
+                    .reply()
+                    .ok()?
+                    .map_state
+                    == MapState::VIEWABLE;
+
+                Some(WindowInfo {
+                    id: window as u64,
+                    title,
+                    process,
+                    pid,
+                    rect: Rect {
+                        x: origin.dst_x as i32,
+                        y: origin.dst_y as i32,
+                        width: geometry.width as i32,
+                        height: geometry.height as i32,
+                    },
+                    visible,
+                    focused: focused == Some(window),
+                })
+            })
+            .collect()
+    }
+}
+
+// ==========================================
+// WINDOW COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn list_app_windows() -> Vec<WindowInfo> {
+    list_windows()
+}
+
+/// Runs off the main thread: it can block for the whole timeout.
+#[tauri::command(async)]
+pub fn wait_for_window(
+    criteria: WindowCriteria,
+    state: WindowState,
+    timeout_ms: u64,
+) -> Result<WindowInfo, String> {
+    wait_for_state(&criteria, state, Duration::from_millis(timeout_ms))
+}
@@ -0,0 +1,403 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{crash, secrets, share_guard::CaptureKind};
+
+// ==========================================
+// Privacy Audit Log
+// ==========================================
+
+// A record, kept across runs, of what the app did to protect the screen: when
+// captures ran and what they shared, which overlays were up, which apps had focus
+// during captures, and every time a protection couldn't be applied. Records are
+// appended as JSON lines and read back in a single pass, so reports over long
+// periods never hold the whole log in memory. Only process names are recorded for
+// focused apps, never window titles. With storage encryption on, each line is
+// sealed on its own (see `secrets`), so the log can still be appended to and read
+// a line at a time.
+
+/// Reports cover the last week unless told otherwise.
+const DEFAULT_REPORT_MS: u64 = 7 * 24 * 60 * 60 * 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// The OS refused to keep a window out of captures.
+    CaptureExclusion,
+    /// A window couldn't be covered, by the share guard or an auto-cover rule.
+    Cover,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AuditEvent {
+    /// The app started; whatever a previous run left open ended before this.
+    Started,
+    /// The captures running changed; none means captures stopped.
+    Capture {
+        sources: Vec<CaptureKind>,
+    },
+    OverlayOpened {
+        id: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        monitor: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    OverlayClosed {
+        id: u64,
+    },
+    /// Another app took focus during a capture.
+    Focused {
+        process: String,
+        /// The auto-cover rule matching the app, which marks it as sensitive.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rule: Option<String>,
+    },
+    VerificationFailed {
+        check: FailureKind,
+        message: String,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AuditRecord {
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Milliseconds since the Unix epoch; either end may be left open.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct TimeRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_ms: Option<u64>,
+}
+
+impl TimeRange {
+    pub fn contains(&self, at_ms: u64) -> bool {
+        self.from_ms.map_or(true, |from| at_ms >= from) && self.to_ms.map_or(true, |to| at_ms < to)
+    }
+}
+
+static LOG_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Logs to `path` from now on and records the start of this run.
+pub fn init(path: PathBuf) {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    *LOG_FILE.lock().unwrap() = Some(path);
+    record(AuditEvent::Started);
+}
+
+/// Appends `event`, stamped with the current time. Dropped before `init`.
+pub fn record(event: AuditEvent) {
+    let file = LOG_FILE.lock().unwrap();
+    let Some(path) = file.as_ref() else {
+        return;
+    };
+    let entry = AuditRecord {
+        at_ms: crash::now_ms() as u64,
+        event,
+    };
+    let written = serde_json::to_string(&entry)
+        .map_err(|e| e.to_string())
+        .and_then(secrets::seal_line)
+        .and_then(|line| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut f| writeln!(f, "{}", line))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = written {
+        log::warn!("failed to write audit log: {}", e);
+    }
+}
+
+/// Drops the records older than `cutoff_ms`, or every record without one, and
+/// returns how many were dropped. Appends wait until the log is rewritten.
+pub fn prune(cutoff_ms: Option<u64>) -> Result<u64, String> {
+    let file = LOG_FILE.lock().unwrap();
+    let Some(path) = file.as_ref() else {
+        return Ok(0);
+    };
+    let input = match fs::File::open(path) {
+        Ok(input) => input,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read audit log: {}", e)),
+    };
+    let failed = |e: std::io::Error| format!("Failed to prune audit log: {}", e);
+    let temp = path.with_extension("jsonl.tmp");
+    let mut out = std::io::BufWriter::new(fs::File::create(&temp).map_err(failed)?);
+    let mut dropped = 0;
+    for line in BufReader::new(input).lines() {
+        let line = line.map_err(failed)?;
+        let keep = cutoff_ms.is_some_and(|cutoff| {
+            secrets::open_line(line.clone())
+                .ok()
+                .and_then(|opened| serde_json::from_str::<AuditRecord>(&opened).ok())
+                .is_some_and(|record| record.at_ms >= cutoff)
+        });
+        if keep {
+            writeln!(out, "{}", line).map_err(failed)?;
+        } else {
+            dropped += 1;
+        }
+    }
+    out.flush().map_err(failed)?;
+    drop(out);
+    fs::rename(&temp, path).map_err(failed)?;
+    Ok(dropped)
+}
+
+/// Every record up to the end of `range`, oldest first; unreadable lines are
+/// skipped. Reads lazily, a line at a time.
+pub fn records(range: TimeRange) -> Result<impl Iterator<Item = AuditRecord>, String> {
+    let path = LOG_FILE
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("The audit log isn't available")?;
+    let lines = match fs::File::open(&path) {
+        Ok(file) => Some(BufReader::new(file).lines()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read audit log: {}", e)),
+    };
+    Ok(lines
+        .into_iter()
+        .flatten()
+        .map_while(Result::ok)
+        .filter_map(|line| secrets::open_line(line).ok())
+        .filter_map(|line| serde_json::from_str::<AuditRecord>(&line).ok())
+        .take_while(move |record| range.to_ms.map_or(true, |to| record.at_ms < to)))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OverlaySpan {
+    pub id: u64,
+    pub opened_ms: u64,
+    /// `None` if still open at the end of the report.
+    pub closed_ms: Option<u64>,
+    /// Time up within the report's range.
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SensitiveApp {
+    pub process: String,
+    pub rule: String,
+    /// Times it took focus during a capture.
+    pub focused: u32,
+    pub focused_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PrivacyReport {
+    pub from_ms: u64,
+    pub to_ms: u64,
+    /// Captures started within the range.
+    pub captures: u32,
+    /// Time any capture was running.
+    pub capture_ms: u64,
+    /// Time a display share (or one of unknown kind) was running.
+    pub display_capture_ms: u64,
+    pub overlays: Vec<OverlaySpan>,
+    pub sensitive_apps: Vec<SensitiveApp>,
+    pub failures: Vec<AuditRecord>,
+}
+
+/// A report being folded from the log, with the spans still open.
+struct Fold {
+    report: PrivacyReport,
+    /// Since when a capture has run, and whether it shows a display.
+    capture: Option<(u64, bool)>,
+    overlays: HashMap<u64, OverlaySpan>,
+    /// Since when, which app and its auto-cover rule, during a capture.
+    focus: Option<(u64, String, Option<String>)>,
+    sensitive: BTreeMap<(String, String), SensitiveApp>,
+}
+
+impl Fold {
+    fn overlap(&self, start: u64, end: u64) -> u64 {
+        end.min(self.report.to_ms)
+            .saturating_sub(start.max(self.report.from_ms))
+    }
+
+    fn end_focus(&mut self, at: u64) {
+        let Some((since, process, Some(rule))) = self.focus.take() else {
+            return;
+        };
+        if since >= self.report.to_ms || at <= self.report.from_ms {
+            return;
+        }
+        let ms = self.overlap(since, at);
+        let app = self
+            .sensitive
+            .entry((process.clone(), rule.clone()))
+            .or_insert(SensitiveApp {
+                process,
+                rule,
+                focused: 0,
+                focused_ms: 0,
+            });
+        app.focused += 1;
+        app.focused_ms += ms;
+    }
+
+    fn end_capture(&mut self, at: u64) {
+        if let Some((since, display)) = self.capture.take() {
+            let ms = self.overlap(since, at);
+            self.report.capture_ms += ms;
+            if display {
+                self.report.display_capture_ms += ms;
+            }
+        }
+    }
+
+    /// Closes overlay `span` at `at`, or leaves it open at the end of the range.
+    fn close_overlay(&mut self, mut span: OverlaySpan, at: Option<u64>) {
+        span.closed_ms = at;
+        span.duration_ms = self.overlap(span.opened_ms, at.unwrap_or(self.report.to_ms));
+        if span.opened_ms < self.report.to_ms && at.map_or(true, |at| at > self.report.from_ms) {
+            self.report.overlays.push(span);
+        }
+    }
+
+    fn close_overlays(&mut self, at: Option<u64>) {
+        let open: Vec<OverlaySpan> = self.overlays.drain().map(|(_, span)| span).collect();
+        for span in open {
+            self.close_overlay(span, at);
+        }
+    }
+
+    fn add(&mut self, record: AuditRecord, last_ms: u64) {
+        let at = record.at_ms;
+        match record.event {
+            AuditEvent::Started => {
+                self.end_focus(last_ms);
+                self.end_capture(last_ms);
+                self.close_overlays(Some(last_ms));
+            }
+            AuditEvent::Capture { sources } if sources.is_empty() => {
+                self.end_focus(at);
+                self.end_capture(at);
+            }
+            AuditEvent::Capture { sources } => {
+                let display = sources.iter().any(|kind| *kind != CaptureKind::Window);
+                // A change of kind splits the span, but it's still one capture.
+                if self.capture.is_none() {
+                    if (self.report.from_ms..self.report.to_ms).contains(&at) {
+                        self.report.captures += 1;
+                    }
+                } else if self.capture.is_some_and(|(_, was)| was == display) {
+                    return;
+                }
+                self.end_capture(at);
+                self.capture = Some((at, display));
+            }
+            AuditEvent::OverlayOpened { id, monitor, text } => {
+                let span = OverlaySpan {
+                    id,
+                    opened_ms: at,
+                    closed_ms: None,
+                    duration_ms: 0,
+                    monitor,
+                    text,
+                };
+                self.overlays.insert(id, span);
+            }
+            AuditEvent::OverlayClosed { id } => {
+                if let Some(span) = self.overlays.remove(&id) {
+                    self.close_overlay(span, Some(at));
+                }
+            }
+            AuditEvent::Focused { process, rule } => {
+                self.end_focus(at);
+                self.focus = Some((at, process, rule));
+            }
+            event @ AuditEvent::VerificationFailed { .. } => {
+                if at >= self.report.from_ms {
+                    self.report.failures.push(AuditRecord { at_ms: at, event });
+                }
+            }
+        }
+    }
+}
+
+/// Folds the log into a report over `[from, to)`. Spans still open at a `Started`
+/// record ended when the previous run did, at the last record before it.
+fn report(records: impl Iterator<Item = AuditRecord>, from: u64, to: u64) -> PrivacyReport {
+    let mut fold = Fold {
+        report: PrivacyReport {
+            from_ms: from,
+            to_ms: to,
+            captures: 0,
+            capture_ms: 0,
+            display_capture_ms: 0,
+            overlays: Vec::new(),
+            sensitive_apps: Vec::new(),
+            failures: Vec::new(),
+        },
+        capture: None,
+        overlays: HashMap::new(),
+        focus: None,
+        sensitive: BTreeMap::new(),
+    };
+    let mut last_ms = from;
+    for record in records {
+        let at = record.at_ms;
+        fold.add(record, last_ms);
+        last_ms = at;
+    }
+
+    // Whatever is still open runs to the end of the range, or to now.
+    let end = to.min(crash::now_ms() as u64).max(last_ms);
+    fold.end_focus(end);
+    fold.end_capture(end);
+    fold.close_overlays(None);
+    let mut report = fold.report;
+    report.overlays.sort_by_key(|span| span.opened_ms);
+    report.sensitive_apps = fold.sensitive.into_values().collect();
+    report
+}
+
+// ==========================================
+// AUDIT LOG COMMANDS
+// ==========================================
+
+/// Summarizes the audit log over `range` (the last week by default): capture time,
+/// overlays shown, sensitive apps focused during captures and protection failures.
+#[tauri::command]
+pub fn get_privacy_report(range: Option<TimeRange>) -> Result<PrivacyReport, String> {
+    let range = range.unwrap_or_default();
+    let to = range.to_ms.unwrap_or(crash::now_ms() as u64);
+    let from = range
+        .from_ms
+        .unwrap_or(to.saturating_sub(DEFAULT_REPORT_MS));
+    if from >= to {
+        return Err("The report range is empty".to_string());
+    }
+    let records = records(TimeRange {
+        from_ms: Some(from),
+        to_ms: Some(to),
+    })?;
+    Ok(report(records, from, to))
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    app_window::{self, WindowCriteria, WindowInfo},
+    audit_log::{self, AuditEvent, FailureKind},
+    window_overlays, OverlayManager,
+};
+
+// ==========================================
+// Auto-Cover Rules
+// ==========================================
+
+// Windows of configured applications (a mail client, a password manager) get a
+// window-tracking overlay as soon as they appear, without anyone asking for one. A
+// watcher lists the windows, covers each new one that a rule matches and uncovers
+// windows that no rule matches any more; covers follow their window and close with
+// it on their own. A cover someone closes by hand stays closed until its window
+// does, so the watcher doesn't fight them.
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AutoCoverRule {
+    /// Identifies the rule; adding a rule with the same name replaces it.
+    pub name: String,
+    #[serde(flatten)]
+    pub criteria: WindowCriteria,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// A covered window.
+struct Cover {
+    rule: String,
+    /// `None` if opening the overlay failed.
+    overlay: Option<u64>,
+}
+
+static RULES: Lazy<Mutex<Vec<AutoCoverRule>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Window id → its cover.
+static COVERS: Lazy<Mutex<HashMap<u64, Cover>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// The rule, if any, that covers `window`; the first one listed wins.
+fn rule_for<'a>(rules: &'a [AutoCoverRule], window: &WindowInfo) -> Option<&'a AutoCoverRule> {
+    rules.iter().find(|rule| rule.criteria.matches(window))
+}
+
+/// Covers `new` windows and uncovers the `stale` ones. Runs on the main thread.
+fn apply(manager: &OverlayManager, new: Vec<(WindowInfo, AutoCoverRule)>, stale: Vec<u64>) {
+    let mut covers = COVERS.lock().unwrap();
+    for window in stale {
+        if let Some(overlay) = covers.remove(&window).and_then(|cover| cover.overlay) {
+            // Already gone if it closed with its window.
+            let _ = crate::destroy_overlay_in(manager, overlay);
+        }
+    }
+    for (window, rule) in new {
+        if covers.contains_key(&window.id) {
+            continue;
+        }
+        let overlay = window_overlays::cover(manager, &window, rule.text.as_deref(), rule.opacity)
+            .inspect_err(|e| {
+                log::warn!(
+                    "auto-cover {} failed for window {}: {}",
+                    rule.name,
+                    window.id,
+                    e
+                );
+                audit_log::record(AuditEvent::VerificationFailed {
+                    check: FailureKind::Cover,
+                    message: format!("{} ({}): {}", window.process, window.id, e),
+                });
+            })
+            .ok();
+        covers.insert(
+            window.id,
+            Cover {
+                rule: rule.name,
+                overlay,
+            },
+        );
+    }
+}
+
+/// The name of the rule that covers `window`, if any.
+pub fn rule_name(window: &WindowInfo) -> Option<String> {
+    rule_for(&RULES.lock().unwrap(), window).map(|rule| rule.name.clone())
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("auto-cover".to_string())
+        .spawn(move || {
+            let own_pid = std::process::id();
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(POLL_INTERVAL);
+                let rules = RULES.lock().unwrap().clone();
+                let covered: HashMap<u64, String> = COVERS
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(id, cover)| (*id, cover.rule.clone()))
+                    .collect();
+                if rules.is_empty() && covered.is_empty() {
+                    continue;
+                }
+
+                let windows: Vec<WindowInfo> = app_window::list_windows()
+                    .into_iter()
+                    .filter(|w| w.pid != own_pid)
+                    .collect();
+                let new: Vec<(WindowInfo, AutoCoverRule)> = windows
+                    .iter()
+                    .filter(|w| !covered.contains_key(&w.id))
+                    .filter_map(|w| Some((w.clone(), rule_for(&rules, w)?.clone())))
+                    .collect();
+                // Closed windows, and those whose rule was removed or no longer
+                // matches, e.g. after a title change.
+                let stale: Vec<u64> = covered
+                    .iter()
+                    .filter(|(id, rule)| {
+                        windows
+                            .iter()
+                            .find(|w| w.id == **id)
+                            .and_then(|w| rule_for(&rules, w))
+                            .map_or(true, |current| current.name != **rule)
+                    })
+                    .map(|(id, _)| *id)
+                    .collect();
+                if new.is_empty() && stale.is_empty() {
+                    continue;
+                }
+
+                let handle = app.clone();
+                let _ = app.run_on_main_thread(move || {
+                    apply(&handle.state::<OverlayManager>(), new, stale)
+                });
+            }
+
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                let all = COVERS.lock().unwrap().keys().copied().collect();
+                apply(&handle.state::<OverlayManager>(), Vec::new(), all);
+            });
+        })
+        .map_err(|e| format!("Failed to spawn auto-cover watcher: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+// ==========================================
+// AUTO-COVER COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn list_auto_cover_rules() -> Vec<AutoCoverRule> {
+    RULES.lock().unwrap().clone()
+}
+
+/// Covers the windows `rule` matches from now on, replacing the rule with the same
+/// name. Windows it matches that are already open are covered within a moment.
+#[tauri::command]
+pub fn add_auto_cover_rule(rule: AutoCoverRule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Auto-cover rules need a name".to_string());
+    }
+    if rule.criteria.is_empty() {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    crate::check_options(rule.opacity, None)?;
+    let mut rules = RULES.lock().unwrap();
+    match rules.iter_mut().find(|r| r.name == rule.name) {
+        Some(existing) => *existing = rule,
+        None => rules.push(rule),
+    }
+    Ok(())
+}
+
+/// Removes rule `name`; the windows it covered are uncovered within a moment.
+#[tauri::command]
+pub fn remove_auto_cover_rule(name: String) -> Result<(), String> {
+    let mut rules = RULES.lock().unwrap();
+    let before = rules.len();
+    rules.retain(|r| r.name != name);
+    if rules.len() == before {
+        return Err(format!("Auto-cover rule '{}' not found", name));
+    }
+    Ok(())
+}
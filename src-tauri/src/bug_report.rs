@@ -0,0 +1,585 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use flate2::{write::DeflateEncoder, Compression, Crc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    affinity, capture, coords, crash, features, locale, middleware, motion, palette, retention,
+    secrets, subsystems, watchdog,
+};
+
+// ==========================================
+// Bug Reports
+// ==========================================
+
+// Packs what's needed to look into a problem into one zip the user can attach to
+// an issue: diagnostics about the displays and the app's state, recent log lines
+// and commands, the configuration, handle counts, breadcrumbs and the latest crash
+// report. Nothing is sent anywhere; the user decides where the zip goes. Values
+// under keys that look like secrets are replaced, as are the values of `key=value`
+// and `key: value` pairs in text such as log messages, and URLs lose their
+// credentials and query strings, where endpoints tend to carry tokens. The app
+// writes no log files, so the logger keeps recent lines in memory on their way to
+// the logger that prints them.
+
+const MAX_LOG_LINES: usize = 500;
+/// Key names whose values are replaced, matched anywhere in the key.
+const SECRET_KEYS: &[&str] = &[
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "key",
+    "auth",
+    "credential",
+    "cookie",
+];
+const REDACTED: &str = "[redacted]";
+/// Authorization schemes, written between a `key:` and the secret itself.
+const AUTH_SCHEMES: &[&str] = &["bearer", "basic", "digest"];
+
+#[derive(Clone, Serialize)]
+struct LogLine {
+    at_ms: u64,
+    level: String,
+    target: String,
+    message: String,
+}
+
+/// Passes records on to `inner` and keeps those at info or above for reports.
+struct Tee {
+    inner: Box<dyn log::Log>,
+}
+
+/// Prints records to stderr, for the daemon, which has no log plugin.
+#[cfg(feature = "headless")]
+pub struct StderrLog;
+
+static LOG_LINES: Lazy<Mutex<VecDeque<LogLine>>> =
+    Lazy::new(|| Mutex::new(VecDeque::with_capacity(MAX_LOG_LINES)));
+static REPORT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+impl log::Log for Tee {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.level() <= log::Level::Info {
+            keep(record);
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(feature = "headless")]
+impl log::Log for StderrLog {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        eprintln!(
+            "[{}][{}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+fn keep(record: &log::Record) {
+    let Ok(mut lines) = LOG_LINES.lock() else {
+        return;
+    };
+    if lines.len() == MAX_LOG_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(LogLine {
+        at_ms: crash::now_ms() as u64,
+        level: record.level().to_string(),
+        target: record.target().to_string(),
+        message: record.args().to_string(),
+    });
+}
+
+/// Installs `inner`, which logs at `level` and below, as the app's logger, keeping
+/// recent lines for reports as they pass. Does nothing if a logger is installed.
+pub fn install_logger(inner: Box<dyn log::Log>, level: log::LevelFilter) {
+    let tee: &'static Tee = Box::leak(Box::new(Tee { inner }));
+    if log::set_logger(tee).is_ok() {
+        log::set_max_level(level.max(log::LevelFilter::Info));
+    }
+}
+
+/// Saves reports in `dir`.
+pub fn init(dir: PathBuf) {
+    *REPORT_DIR.lock().unwrap() = Some(dir);
+}
+
+#[derive(Serialize)]
+struct Resources {
+    /// Kernel handles on Windows, open file descriptors elsewhere.
+    handles: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gdi_objects: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_objects: Option<u32>,
+}
+
+#[cfg(target_os = "windows")]
+fn resources() -> Resources {
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, GetGuiResources, GetProcessHandleCount, GR_GDIOBJECTS, GR_USEROBJECTS,
+    };
+
+    unsafe {
+        let process = GetCurrentProcess();
+        let mut handles = 0;
+        Resources {
+            handles: GetProcessHandleCount(process, &mut handles)
+                .ok()
+                .map(|_| handles),
+            gdi_objects: Some(GetGuiResources(process, GR_GDIOBJECTS)),
+            user_objects: Some(GetGuiResources(process, GR_USEROBJECTS)),
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn resources() -> Resources {
+    Resources {
+        handles: fs::read_dir("/dev/fd")
+            .ok()
+            .map(|entries| entries.count() as u32),
+        gdi_objects: None,
+        user_objects: None,
+    }
+}
+
+/// Drops the credentials and query string of every URL in `text`.
+fn redact_urls(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let Some(scheme) = word.find("://") else {
+                return word.to_string();
+            };
+            let (head, rest) = word.split_at(scheme + 3);
+            let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+            let (authority, path) = rest.split_at(end);
+            let host = authority
+                .rsplit_once('@')
+                .map_or(authority, |(_, host)| host);
+            let path = path.split(['?', '#']).next().unwrap_or("");
+            format!("{}{}{}", head, host, path)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_secret(key: &str) -> bool {
+    let lower = key.to_lowercase();
+    SECRET_KEYS.iter().any(|s| lower.contains(s))
+}
+
+/// Replaces the values of `key=value` and `key: value` pairs in `text` whose key
+/// looks like a secret.
+fn redact_pairs(text: &str) -> String {
+    // Whether the next word is a secret value: after `key:`, or an auth scheme.
+    let mut pending = false;
+    text.split(' ')
+        .map(|word| {
+            if pending && !word.is_empty() {
+                pending = AUTH_SCHEMES.contains(&word.to_lowercase().as_str());
+                return REDACTED.to_string();
+            }
+            if let Some((key, _)) = word.split_once('=').filter(|(key, _)| is_secret(key)) {
+                return format!("{}={}", key, REDACTED);
+            }
+            pending = pending || word.strip_suffix(':').is_some_and(is_secret);
+            word.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn redact(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(redact_pairs(&redact_urls(&text))),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    // Only leaves: a map keyed by command names isn't a secret.
+                    let secret = !value.is_object()
+                        && !value.is_array()
+                        && !value.is_null()
+                        && is_secret(&key);
+                    let value = if secret {
+                        Value::from(REDACTED)
+                    } else {
+                        redact(value)
+                    };
+                    (key, value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        other => other,
+    }
+}
+
+fn diagnostics() -> Value {
+    json!({
+        "build": features::get_build_features(),
+        "monitors": coords::monitors(),
+        "subsystems": subsystems::states(),
+        "watchdog": watchdog::get_watchdog_status(),
+        "capture_backend": capture::get_capture_backend(),
+        "display_affinity": affinity::get_display_affinity(),
+        "motion": motion::get_motion_settings(),
+        "command_metrics": middleware::get_command_metrics(),
+    })
+}
+
+fn config() -> Value {
+    let config = json!({
+        "palette": palette::get_palette(),
+        "retention": retention::get_retention_policy(),
+        "storage_encryption": secrets::get_storage_encryption(),
+        "format_locale": locale::get_format_locale(),
+    });
+    #[cfg(feature = "self-update")]
+    let config = {
+        let mut config = config;
+        config["update"] = json!(crate::updater::get_update_config());
+        config["feature_flags"] = json!(crate::updater::get_feature_flags());
+        config
+    };
+    config
+}
+
+/// The newest crash report, if there is one.
+fn latest_crash() -> Option<(String, Value)> {
+    let name = crash::list_crash_reports().ok()?.pop()?;
+    let report = crash::get_crash_report(name.clone()).ok()?;
+    Some((format!("crash/{}", name), json!(report)))
+}
+
+/// DOS date and time, in UTC, as zip entries store them.
+fn dos_time(at_ms: u64) -> (u16, u16) {
+    let secs = at_ms / 1000;
+    let days = secs / 86_400;
+    let time = secs % 86_400;
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    let date = ((year.saturating_sub(1980) << 9) | (month << 5) | day) as u16;
+    let time = (((time / 3600) << 11) | ((time % 3600 / 60) << 5) | (time % 60 / 2)) as u16;
+    (date, time)
+}
+
+/// `n` as a 32-bit zip field. All ones marks a zip64 field, which this writer
+/// doesn't produce, so that and anything larger is refused.
+fn zip_u32(n: usize, what: &str) -> io::Result<u32> {
+    u32::try_from(n)
+        .ok()
+        .filter(|&n| n != u32::MAX)
+        .ok_or_else(|| too_large(what))
+}
+
+/// `n` as a 16-bit zip field; see `zip_u32`.
+fn zip_u16(n: usize, what: &str) -> io::Result<u16> {
+    u16::try_from(n)
+        .ok()
+        .filter(|&n| n != u16::MAX)
+        .ok_or_else(|| too_large(what))
+}
+
+fn too_large(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} is too large for a zip without zip64", what),
+    )
+}
+
+/// Writes `files` to `out` as a zip of deflated entries. Fails, rather than
+/// writing a corrupt zip, if any size or offset needs zip64.
+fn write_zip(out: &mut impl Write, files: &[(String, Vec<u8>)], at_ms: u64) -> io::Result<()> {
+    let (date, time) = dos_time(at_ms);
+    let count = zip_u16(files.len(), "The number of entries")?;
+    let mut central = Vec::new();
+    let mut offset = 0usize;
+    for (name, data) in files {
+        let mut crc = Crc::new();
+        crc.update(data);
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+
+        // Version 2.0, UTF-8 names, deflate.
+        let mut header = Vec::new();
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0x0800u16.to_le_bytes());
+        header.extend_from_slice(&8u16.to_le_bytes());
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        header.extend_from_slice(&crc.sum().to_le_bytes());
+        header.extend_from_slice(&zip_u32(compressed.len(), name)?.to_le_bytes());
+        header.extend_from_slice(&zip_u32(data.len(), name)?.to_le_bytes());
+        header.extend_from_slice(&zip_u16(name.len(), "An entry name")?.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+
+        out.write_all(&0x0403_4b50u32.to_le_bytes())?;
+        out.write_all(&header)?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&compressed)?;
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes());
+        central.extend_from_slice(&header);
+        // No comment, disk 0, no attributes.
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&zip_u32(offset, "The bug report")?.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        offset += 4 + header.len() + name.len() + compressed.len();
+    }
+
+    out.write_all(&central)?;
+    out.write_all(&0x0605_4b50u32.to_le_bytes())?;
+    out.write_all(&[0; 4])?;
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&count.to_le_bytes())?;
+    out.write_all(&zip_u32(central.len(), "The zip directory")?.to_le_bytes())?;
+    out.write_all(&zip_u32(offset, "The bug report")?.to_le_bytes())?;
+    out.write_all(&0u16.to_le_bytes())?;
+    out.flush()
+}
+
+// ==========================================
+// BUG REPORT COMMANDS
+// ==========================================
+
+/// Packs diagnostics, recent logs and commands, redacted configuration, handle
+/// counts, breadcrumbs and the latest crash report into a zip, and returns its
+/// path.
+#[tauri::command]
+pub fn create_bug_report() -> Result<String, String> {
+    let dir = REPORT_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Bug reports are not initialized")?;
+    let at_ms = crash::now_ms() as u64;
+    let logs: Vec<LogLine> = LOG_LINES.lock().unwrap().iter().cloned().collect();
+
+    let mut entries = vec![
+        ("diagnostics.json".to_string(), diagnostics()),
+        ("logs.json".to_string(), json!(logs)),
+        (
+            "commands.json".to_string(),
+            json!(middleware::get_command_audit()),
+        ),
+        ("config.json".to_string(), config()),
+        ("resources.json".to_string(), json!(resources())),
+        (
+            "breadcrumbs.json".to_string(),
+            json!(crash::recent_breadcrumbs()),
+        ),
+    ];
+    entries.extend(latest_crash());
+    let manifest = json!({
+        "created_ms": at_ms,
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "files": entries.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+    });
+    entries.insert(0, ("manifest.json".to_string(), manifest));
+
+    let mut files = Vec::new();
+    for (name, value) in entries {
+        let data = serde_json::to_vec_pretty(&redact(value)).map_err(|e| e.to_string())?;
+        files.push((name, data));
+    }
+
+    let mut zip = Vec::new();
+    write_zip(&mut zip, &files, at_ms).map_err(|e| format!("Failed to write bug report: {}", e))?;
+    // With storage encryption on the zip is sealed and only opens on this machine.
+    let extension = if secrets::enabled() {
+        "zip.sealed"
+    } else {
+        "zip"
+    };
+    let data = secrets::seal(&zip)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("bug-report-{}.{}", at_ms, extension));
+    fs::write(&path, data).map_err(|e| format!("Failed to write bug report: {}", e))?;
+    crash::breadcrumb("bug-report", format!("created {}", path.display()));
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::DeflateDecoder;
+
+    use super::*;
+
+    fn u16_at(zip: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([zip[at], zip[at + 1]])
+    }
+
+    fn u32_at(zip: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(zip[at..at + 4].try_into().unwrap())
+    }
+
+    /// Reads the zip back through its central directory, checking every CRC.
+    fn read_zip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(zip, end), 0x0605_4b50);
+        let count = u16_at(zip, end + 10) as usize;
+        let mut at = u32_at(zip, end + 16) as usize;
+        assert_eq!(at + u32_at(zip, end + 12) as usize, end);
+
+        let mut files = Vec::new();
+        for _ in 0..count {
+            assert_eq!(u32_at(zip, at), 0x0201_4b50);
+            let crc = u32_at(zip, at + 16);
+            let size = u32_at(zip, at + 24) as usize;
+            let name_len = u16_at(zip, at + 28) as usize;
+            let local = u32_at(zip, at + 42) as usize;
+            let name = String::from_utf8(zip[at + 46..at + 46 + name_len].to_vec()).unwrap();
+            at += 46 + name_len;
+
+            assert_eq!(u32_at(zip, local), 0x0403_4b50);
+            let compressed = u32_at(zip, local + 18) as usize;
+            let start = local + 30 + u16_at(zip, local + 26) as usize;
+            let mut data = Vec::new();
+            DeflateDecoder::new(&zip[start..start + compressed])
+                .read_to_end(&mut data)
+                .unwrap();
+            assert_eq!(data.len(), size);
+            let mut check = Crc::new();
+            check.update(&data);
+            assert_eq!(check.sum(), crc);
+            files.push((name, data));
+        }
+        files
+    }
+
+    #[test]
+    fn zip_round_trips() {
+        let files = vec![
+            ("manifest.json".to_string(), b"{}".to_vec()),
+            ("crash/ünïcode.json".to_string(), vec![b'x'; 100_000]),
+            ("empty.json".to_string(), Vec::new()),
+        ];
+        let mut zip = Vec::new();
+        write_zip(&mut zip, &files, 1_700_000_000_000).unwrap();
+        assert_eq!(read_zip(&zip), files);
+    }
+
+    #[test]
+    fn zip_refuses_more_entries_than_it_can_count() {
+        let files = vec![(String::new(), Vec::new()); u16::MAX as usize];
+        let mut zip = Vec::new();
+        assert!(write_zip(&mut zip, &files, 0).is_err());
+        assert!(zip.is_empty());
+    }
+
+    #[test]
+    fn zip_fields_refuse_zip64_sizes() {
+        assert_eq!(zip_u32(4096, "x").unwrap(), 4096);
+        assert!(zip_u32(u32::MAX as usize, "x").is_err());
+        assert!(zip_u32(u32::MAX as usize + 1, "x").is_err());
+        assert!(zip_u16(u16::MAX as usize, "x").is_err());
+    }
+
+    #[test]
+    fn redacts_secret_pairs_in_text() {
+        assert_eq!(
+            redact_pairs("connecting with token=abc123 as user=ann"),
+            "connecting with token=[redacted] as user=ann"
+        );
+        assert_eq!(
+            redact_pairs("sent Authorization: Bearer abc123 to host"),
+            "sent Authorization: [redacted] [redacted] to host"
+        );
+        assert_eq!(redact_pairs("password:  hunter2"), "password:  [redacted]");
+        assert_eq!(redact_pairs("state: ready"), "state: ready");
+    }
+
+    #[test]
+    fn redacts_pairs_in_log_messages() {
+        let logs = json!([{ "level": "INFO", "message": "api_key=xyz saved" }]);
+        assert_eq!(
+            redact(logs)[0]["message"],
+            format!("api_key={} saved", REDACTED)
+        );
+    }
+
+    #[test]
+    fn urls_lose_credentials_and_queries() {
+        assert_eq!(
+            redact_urls("GET https://ann:pw@api.example.com/v1/items?token=abc#top failed"),
+            "GET https://api.example.com/v1/items failed"
+        );
+        assert_eq!(redact_urls("no urls here"), "no urls here");
+    }
+
+    #[test]
+    fn secret_leaves_are_replaced_and_maps_kept() {
+        let value = json!({
+            "update": { "public_key": "abc", "endpoint": "https://u.example.com/m?k=1" },
+            "auth_tokens": { "ok": 1 },
+            "password": null,
+            "count": 3,
+        });
+        assert_eq!(
+            redact(value),
+            json!({
+                "update": { "public_key": REDACTED, "endpoint": "https://u.example.com/m" },
+                "auth_tokens": { "ok": 1 },
+                "password": null,
+                "count": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn dos_times_are_utc_with_two_second_steps() {
+        // 2023-11-14 22:13:21 UTC.
+        let (date, time) = dos_time(1_700_000_001_000);
+        assert_eq!(date, (43 << 9) | (11 << 5) | 14);
+        assert_eq!(time, (22 << 11) | (13 << 5) | 10);
+        // Leap day.
+        assert_eq!(dos_time(951_782_400_000).0, (20 << 9) | (2 << 5) | 29);
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    coords::{self, Anchor, Bounds},
+    hud::{self, HudStyle, UpdateError},
+    sessions::{self, Resource},
+};
+
+// ==========================================
+// Captions
+// ==========================================
+
+// A single large-text banner across one monitor, for private presenter notes or
+// accessibility captions. Hidden from capture by default; a new caption replaces
+// the current one. Every show or hide bumps the caption's revision, so a client can
+// replace only the caption it last saw.
+
+/// Banner height in lines of text, including padding.
+const BANNER_LINES: f64 = 2.6;
+/// Gap between the banner and the monitor edge, in device-independent pixels.
+const EDGE_MARGIN: f64 = 40.0;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptionPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CaptionStyle {
+    #[serde(flatten)]
+    pub text: HudStyle,
+    #[serde(default)]
+    pub position: CaptionPosition,
+    /// Monitor index; the primary monitor when omitted.
+    pub monitor: Option<usize>,
+    /// When false the caption shows up in screen shares too.
+    #[serde(default = "default_exclude")]
+    pub exclude_from_capture: bool,
+}
+
+fn default_exclude() -> bool {
+    true
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            text: HudStyle::default(),
+            position: CaptionPosition::default(),
+            monitor: None,
+            exclude_from_capture: default_exclude(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptionState {
+    pub revision: u64,
+    /// `None` while no caption is showing.
+    pub text: Option<String>,
+}
+
+/// HUD showing the current caption, and its text.
+static CAPTION: Lazy<Mutex<Option<(u64, String)>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped per caption and per hide; doubles as the caption revision, and lets a stale
+/// auto-hide timer leave newer captions alone.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn state() -> CaptionState {
+    CaptionState {
+        revision: GENERATION.load(Ordering::SeqCst),
+        text: CAPTION
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, text)| text.clone()),
+    }
+}
+
+fn hide() {
+    if let Some((id, _)) = CAPTION.lock().unwrap().take() {
+        hud::close(id);
+        sessions::untrack(|r| *r == Resource::Caption);
+    }
+}
+
+/// Runs on the main thread.
+pub fn show(text: &str, style: &CaptionStyle) -> Result<u64, String> {
+    let monitors = coords::monitors();
+    let monitor = match style.monitor {
+        Some(i) => monitors.get(i),
+        None => monitors.iter().find(|m| m.primary),
+    }
+    .ok_or_else(|| "Monitor not found".to_string())?;
+    let scale = monitor.scale_factor;
+    let (width, _) = monitor.physical_size();
+
+    let rect = coords::resolve_bounds(&Bounds::Anchored {
+        anchor: match style.position {
+            CaptionPosition::Top => Anchor::Top,
+            CaptionPosition::Bottom => Anchor::Bottom,
+        },
+        width: width as f64 * 0.9,
+        height: style.text.font_size * scale * BANNER_LINES,
+        margin_x: 0.0,
+        margin_y: EDGE_MARGIN * scale,
+        monitor: style.monitor,
+    })?;
+
+    hide();
+    let id = hud::open(rect, text, &style.text, style.exclude_from_capture)?;
+    *CAPTION.lock().unwrap() = Some((id, text.to_string()));
+    sessions::track(Resource::Caption);
+    Ok(GENERATION.fetch_add(1, Ordering::SeqCst) + 1)
+}
+
+// ==========================================
+// CAPTION COMMANDS
+// ==========================================
+
+/// Shows `text` as a caption, hiding it after `duration_ms` if given. With `revision`,
+/// the caption only replaces the one the caller last saw. Returns the new revision.
+#[tauri::command]
+pub fn show_caption(
+    app: AppHandle,
+    text: String,
+    duration_ms: Option<u64>,
+    style: Option<CaptionStyle>,
+    revision: Option<u64>,
+) -> Result<u64, UpdateError<CaptionState>> {
+    if let Some(expected) = revision.filter(|r| *r != GENERATION.load(Ordering::SeqCst)) {
+        return Err(UpdateError::Conflict {
+            expected,
+            current: state(),
+        });
+    }
+    let generation = show(&text, &style.unwrap_or_default())?;
+
+    if let Some(duration) = duration_ms {
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(duration));
+            if GENERATION.load(Ordering::SeqCst) == generation {
+                let _ = app.run_on_main_thread(move || {
+                    if GENERATION
+                        .compare_exchange(
+                            generation,
+                            generation + 1,
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        )
+                        .is_ok()
+                    {
+                        hide();
+                    }
+                });
+            }
+        });
+    }
+    Ok(generation)
+}
+
+#[tauri::command]
+pub fn hide_caption() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    hide();
+}
+
+#[tauri::command]
+pub fn get_caption() -> CaptionState {
+    state()
+}
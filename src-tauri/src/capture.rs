@@ -0,0 +1,684 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{coords::Rect, crash};
+
+// ==========================================
+// Screen Capture
+// ==========================================
+
+// Two backends sit behind `capture_rect`. The compatible one (GDI, CGWindowList, X11
+// GetImage) works everywhere but copies through the CPU, which is slow on 4K monitors
+// and can miss hardware-accelerated content. The GPU one reads the compositor's
+// output instead: DXGI Desktop Duplication on Windows, ScreenCaptureKit on macOS 14+.
+// In `auto` mode the GPU backend is tried first, and when it fails the compatible one
+// takes over until `GPU_RETRY` has passed.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureBackend {
+    /// The GPU backend where it works, the compatible one otherwise.
+    #[default]
+    Auto,
+    /// Desktop Duplication or ScreenCaptureKit only; captures fail without it.
+    Gpu,
+    /// GDI, CGWindowList or X11 GetImage only.
+    Compatible,
+}
+
+/// How long `auto` sticks with the compatible backend after the GPU one fails.
+const GPU_RETRY: Duration = Duration::from_secs(30);
+
+static BACKEND: Lazy<Mutex<CaptureBackend>> = Lazy::new(|| Mutex::new(CaptureBackend::Auto));
+/// When the GPU backend last failed under `auto`.
+static GPU_FAILED: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Captures `rect` (native coordinates) from the screen.
+pub fn capture_rect(rect: Rect) -> Result<RgbaImage, String> {
+    if rect.width <= 0 || rect.height <= 0 {
+        return Err("Capture region must have a positive width and height".to_string());
+    }
+    crash::breadcrumb(
+        "capture",
+        format!(
+            "capture {}x{} at {},{}",
+            rect.width, rect.height, rect.x, rect.y
+        ),
+    );
+
+    let backend = *BACKEND.lock().unwrap();
+    match backend {
+        CaptureBackend::Compatible => platform::capture(rect),
+        CaptureBackend::Gpu => gpu::capture(rect)?.ok_or_else(|| {
+            "The GPU capture backend can't capture a region spanning displays".to_string()
+        }),
+        CaptureBackend::Auto => {
+            let retry = GPU_FAILED
+                .lock()
+                .unwrap()
+                .map_or(true, |at| at.elapsed() >= GPU_RETRY);
+            if retry {
+                match gpu::capture(rect) {
+                    Ok(Some(image)) => return Ok(image),
+                    Ok(None) => {}
+                    Err(e) => {
+                        log::debug!("GPU capture failed, using compatible capture: {}", e);
+                        *GPU_FAILED.lock().unwrap() = Some(Instant::now());
+                    }
+                }
+            }
+            platform::capture(rect)
+        }
+    }
+}
+
+/// Builds an image from 4-byte BGRA/BGRX rows, forcing alpha opaque.
+fn from_bgra(width: u32, height: u32, stride: usize, data: &[u8]) -> Result<RgbaImage, String> {
+    if data.len() < stride * height as usize {
+        return Err("Captured buffer is smaller than expected".to_string());
+    }
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for row in data.chunks(stride).take(height as usize) {
+        for px in row[..width as usize * 4].chunks_exact(4) {
+            pixels.extend_from_slice(&[px[2], px[1], px[0], 255]);
+        }
+    }
+
+    RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| "Failed to assemble captured image".to_string())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use image::RgbaImage;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::Graphics::Gdi::{
+        BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+        GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT,
+        DIB_RGB_COLORS, SRCCOPY,
+    };
+
+    use crate::coords::Rect;
+
+    pub fn capture(rect: Rect) -> Result<RgbaImage, String> {
+        unsafe {
+            let screen = GetDC(HWND::default());
+            let memory = CreateCompatibleDC(screen);
+            let bitmap = CreateCompatibleBitmap(screen, rect.width, rect.height);
+            let previous = SelectObject(memory, bitmap);
+
+            // CAPTUREBLT includes layered windows, such as our own overlays.
+            let blit = BitBlt(
+                memory,
+                0,
+                0,
+                rect.width,
+                rect.height,
+                screen,
+                rect.x,
+                rect.y,
+                SRCCOPY | CAPTUREBLT,
+            );
+
+            let mut info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: rect.width,
+                    // Negative height requests top-down rows.
+                    biHeight: -rect.height,
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let stride = rect.width as usize * 4;
+            let mut data = vec![0u8; stride * rect.height as usize];
+            let lines = GetDIBits(
+                memory,
+                bitmap,
+                0,
+                rect.height as u32,
+                Some(data.as_mut_ptr() as *mut c_void),
+                &mut info,
+                DIB_RGB_COLORS,
+            );
+
+            SelectObject(memory, previous);
+            let _ = DeleteObject(bitmap);
+            let _ = DeleteDC(memory);
+            ReleaseDC(HWND::default(), screen);
+
+            blit.map_err(|e| format!("BitBlt failed: {}", e))?;
+            if lines != rect.height {
+                return Err("GetDIBits failed".to_string());
+            }
+            super::from_bgra(rect.width as u32, rect.height as u32, stride, &data)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    use image::RgbaImage;
+
+    use crate::coords::Rect;
+
+    #[repr(C)]
+    struct CGRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    const WINDOW_LIST_ON_SCREEN_ONLY: u32 = 1;
+    const NULL_WINDOW_ID: u32 = 0;
+    const IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGWindowListCreateImage(
+            bounds: CGRect,
+            options: u32,
+            window: u32,
+            image_options: u32,
+        ) -> *mut c_void;
+        fn CGImageGetWidth(image: *mut c_void) -> usize;
+        fn CGImageGetHeight(image: *mut c_void) -> usize;
+        fn CGImageGetBytesPerRow(image: *mut c_void) -> usize;
+        fn CGImageGetBitsPerPixel(image: *mut c_void) -> usize;
+        fn CGImageGetDataProvider(image: *mut c_void) -> *mut c_void;
+        fn CGDataProviderCopyData(provider: *mut c_void) -> *mut c_void;
+        fn CGImageRelease(image: *mut c_void);
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFDataGetBytePtr(data: *mut c_void) -> *const u8;
+        fn CFDataGetLength(data: *mut c_void) -> isize;
+        fn CFRelease(object: *mut c_void);
+    }
+
+    /// `rect` is in top-left points, which is what CGWindowList expects. The
+    /// result is at backing resolution, so Retina captures are 2x the point size.
+    pub fn capture(rect: Rect) -> Result<RgbaImage, String> {
+        unsafe {
+            let image = CGWindowListCreateImage(
+                CGRect {
+                    x: rect.x as f64,
+                    y: rect.y as f64,
+                    width: rect.width as f64,
+                    height: rect.height as f64,
+                },
+                WINDOW_LIST_ON_SCREEN_ONLY,
+                NULL_WINDOW_ID,
+                IMAGE_BEST_RESOLUTION,
+            );
+            if image.is_null() {
+                return Err("Screen capture failed (Screen Recording permission?)".to_string());
+            }
+            read_image(image)
+        }
+    }
+
+    /// Copies the pixels out of a CGImage and releases it.
+    pub unsafe fn read_image(image: *mut c_void) -> Result<RgbaImage, String> {
+        let width = CGImageGetWidth(image);
+        let height = CGImageGetHeight(image);
+        let stride = CGImageGetBytesPerRow(image);
+        let bpp = CGImageGetBitsPerPixel(image);
+        let data = CGDataProviderCopyData(CGImageGetDataProvider(image));
+        CGImageRelease(image);
+        if data.is_null() {
+            return Err("Failed to read captured pixels".to_string());
+        }
+
+        let bytes =
+            std::slice::from_raw_parts(CFDataGetBytePtr(data), CFDataGetLength(data) as usize);
+        // Window server images are 32-bit little-endian premultiplied ARGB, i.e. BGRA.
+        let result = if bpp == 32 {
+            super::from_bgra(width as u32, height as u32, stride, bytes)
+        } else {
+            Err(format!("Unsupported capture format ({} bpp)", bpp))
+        };
+        CFRelease(data);
+        result
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use image::RgbaImage;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+    use crate::coords::Rect;
+
+    pub fn capture(rect: Rect) -> Result<RgbaImage, String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X11: {}", e))?;
+        let screen = &conn.setup().roots[screen_num];
+        if screen.root_depth != 24 && screen.root_depth != 32 {
+            return Err(format!("Unsupported X11 depth {}", screen.root_depth));
+        }
+
+        let reply = conn
+            .get_image(
+                ImageFormat::Z_PIXMAP,
+                screen.root,
+                rect.x as i16,
+                rect.y as i16,
+                rect.width as u16,
+                rect.height as u16,
+                !0,
+            )
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("Screen capture failed: {}", e))?;
+
+        super::from_bgra(
+            rect.width as u32,
+            rect.height as u32,
+            rect.width as usize * 4,
+            &reply.data,
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod gpu {
+    use std::sync::Mutex;
+
+    use image::RgbaImage;
+    use once_cell::sync::Lazy;
+    use windows::core::Interface;
+    use windows::Win32::Foundation::{HMODULE, RECT};
+    use windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE_UNKNOWN;
+    use windows::Win32::Graphics::Direct3D11::{
+        D3D11CreateDevice, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D,
+        D3D11_CPU_ACCESS_READ, D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_MAPPED_SUBRESOURCE,
+        D3D11_MAP_READ, D3D11_SDK_VERSION, D3D11_TEXTURE2D_DESC, D3D11_USAGE_STAGING,
+    };
+    use windows::Win32::Graphics::Dxgi::Common::{
+        DXGI_MODE_ROTATION_IDENTITY, DXGI_MODE_ROTATION_UNSPECIFIED,
+    };
+    use windows::Win32::Graphics::Dxgi::{
+        CreateDXGIFactory1, IDXGIFactory1, IDXGIOutput1, IDXGIOutputDuplication,
+        DXGI_ERROR_ACCESS_LOST, DXGI_ERROR_WAIT_TIMEOUT, DXGI_OUTDUPL_FRAME_INFO,
+    };
+
+    use crate::coords::Rect;
+
+    /// How long the first capture of an output waits for its first frame. Later
+    /// captures don't wait: no new frame means the screen hasn't changed.
+    const FIRST_FRAME_MS: u32 = 500;
+
+    /// One duplicated display, with a CPU-readable copy of its latest frame.
+    struct Output {
+        bounds: RECT,
+        device: ID3D11Device,
+        context: ID3D11DeviceContext,
+        duplication: IDXGIOutputDuplication,
+        frame: Option<ID3D11Texture2D>,
+    }
+
+    /// Duplications are costly to set up, so they live until access is lost, e.g. on a
+    /// display mode change or a switch to the secure desktop.
+    static OUTPUTS: Lazy<Mutex<Option<Vec<Output>>>> = Lazy::new(|| Mutex::new(None));
+
+    pub fn capture(rect: Rect) -> Result<Option<RgbaImage>, String> {
+        let mut outputs = OUTPUTS.lock().unwrap();
+        if outputs.is_none() {
+            *outputs = Some(unsafe { open() }?);
+        }
+        let result = unsafe { compose(outputs.as_mut().unwrap(), rect) };
+        if result.is_err() {
+            *outputs = None;
+        }
+        result.map(Some)
+    }
+
+    /// Duplicates every display attached to the desktop, each on its own adapter.
+    unsafe fn open() -> Result<Vec<Output>, String> {
+        let factory: IDXGIFactory1 =
+            CreateDXGIFactory1().map_err(|e| format!("CreateDXGIFactory1 failed: {}", e))?;
+        let mut outputs = Vec::new();
+        let mut a = 0;
+        while let Ok(adapter) = factory.EnumAdapters1(a) {
+            a += 1;
+            let mut device = None;
+            let mut context = None;
+            if D3D11CreateDevice(
+                &adapter,
+                D3D_DRIVER_TYPE_UNKNOWN,
+                HMODULE::default(),
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                None,
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None,
+                Some(&mut context),
+            )
+            .is_err()
+            {
+                continue;
+            }
+            let (Some(device), Some(context)) = (device, context) else {
+                continue;
+            };
+
+            let mut o = 0;
+            while let Ok(output) = adapter.EnumOutputs(o) {
+                o += 1;
+                let desc = output
+                    .GetDesc()
+                    .map_err(|e| format!("IDXGIOutput::GetDesc failed: {}", e))?;
+                if !desc.AttachedToDesktop.as_bool() {
+                    continue;
+                }
+                if desc.Rotation != DXGI_MODE_ROTATION_IDENTITY
+                    && desc.Rotation != DXGI_MODE_ROTATION_UNSPECIFIED
+                {
+                    return Err("Desktop Duplication doesn't support rotated displays".to_string());
+                }
+                let duplication = output
+                    .cast::<IDXGIOutput1>()
+                    .and_then(|output| output.DuplicateOutput(&device))
+                    .map_err(|e| format!("DuplicateOutput failed: {}", e))?;
+                outputs.push(Output {
+                    bounds: desc.DesktopCoordinates,
+                    device: device.clone(),
+                    context: context.clone(),
+                    duplication,
+                    frame: None,
+                });
+            }
+        }
+        if outputs.is_empty() {
+            return Err("No displays to duplicate".to_string());
+        }
+        Ok(outputs)
+    }
+
+    /// Copies the parts of `rect` on each display into one BGRA buffer. Parts on no
+    /// display stay black, as they do with GDI.
+    unsafe fn compose(outputs: &mut [Output], rect: Rect) -> Result<RgbaImage, String> {
+        let stride = rect.width as usize * 4;
+        let mut data = vec![0u8; stride * rect.height as usize];
+        for output in outputs {
+            let left = rect.x.max(output.bounds.left);
+            let top = rect.y.max(output.bounds.top);
+            let right = (rect.x + rect.width).min(output.bounds.right);
+            let bottom = (rect.y + rect.height).min(output.bounds.bottom);
+            if left >= right || top >= bottom {
+                continue;
+            }
+            output.refresh()?;
+            let Some(frame) = &output.frame else {
+                return Err("A display hasn't produced a frame yet".to_string());
+            };
+
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            output
+                .context
+                .Map(frame, 0, D3D11_MAP_READ, 0, Some(&mut mapped))
+                .map_err(|e| format!("Failed to map captured frame: {}", e))?;
+            let pitch = mapped.RowPitch as usize;
+            let source = mapped.pData as *const u8;
+            let bytes = (right - left) as usize * 4;
+            for y in top..bottom {
+                let from = (y - output.bounds.top) as usize * pitch
+                    + (left - output.bounds.left) as usize * 4;
+                let to = (y - rect.y) as usize * stride + (left - rect.x) as usize * 4;
+                std::ptr::copy_nonoverlapping(source.add(from), data.as_mut_ptr().add(to), bytes);
+            }
+            output.context.Unmap(frame, 0);
+        }
+        super::from_bgra(rect.width as u32, rect.height as u32, stride, &data)
+    }
+
+    impl Output {
+        /// Copies the display's newest frame, if it changed, into `frame`.
+        unsafe fn refresh(&mut self) -> Result<(), String> {
+            let timeout = if self.frame.is_some() {
+                0
+            } else {
+                FIRST_FRAME_MS
+            };
+            let mut info = DXGI_OUTDUPL_FRAME_INFO::default();
+            let mut resource = None;
+            match self
+                .duplication
+                .AcquireNextFrame(timeout, &mut info, &mut resource)
+            {
+                Ok(()) => {}
+                Err(e) if e.code() == DXGI_ERROR_WAIT_TIMEOUT => return Ok(()),
+                Err(e) if e.code() == DXGI_ERROR_ACCESS_LOST => {
+                    return Err("Desktop Duplication access was lost".to_string())
+                }
+                Err(e) => return Err(format!("AcquireNextFrame failed: {}", e)),
+            }
+
+            // A zero present time means only the pointer moved.
+            let result = match resource {
+                Some(resource) if info.LastPresentTime != 0 || self.frame.is_none() => resource
+                    .cast::<ID3D11Texture2D>()
+                    .map_err(|e| e.to_string())
+                    .and_then(|texture| self.copy(&texture)),
+                _ => Ok(()),
+            };
+            let _ = self.duplication.ReleaseFrame();
+            result
+        }
+
+        unsafe fn copy(&mut self, texture: &ID3D11Texture2D) -> Result<(), String> {
+            if self.frame.is_none() {
+                let mut desc = D3D11_TEXTURE2D_DESC::default();
+                texture.GetDesc(&mut desc);
+                desc.MipLevels = 1;
+                desc.ArraySize = 1;
+                desc.Usage = D3D11_USAGE_STAGING;
+                desc.BindFlags = 0;
+                desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ.0 as u32;
+                desc.MiscFlags = 0;
+                let mut staging = None;
+                self.device
+                    .CreateTexture2D(&desc, None, Some(&mut staging))
+                    .map_err(|e| format!("Failed to create staging texture: {}", e))?;
+                self.frame = staging;
+            }
+            if let Some(frame) = &self.frame {
+                self.context.CopyResource(frame, texture);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod gpu {
+    use std::{ffi::c_void, sync::mpsc, time::Duration};
+
+    use block::ConcreteBlock;
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString};
+    use image::RgbaImage;
+    use objc::runtime::{Class, BOOL};
+    use objc::{class, msg_send, sel, sel_impl};
+    use once_cell::sync::Lazy;
+
+    use crate::coords::Rect;
+
+    /// How long to wait for ScreenCaptureKit's completion handlers.
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+        fn CGDisplayModeGetWidth(mode: *mut c_void) -> usize;
+        fn CGDisplayModeGetPixelWidth(mode: *mut c_void) -> usize;
+        fn CGDisplayModeRelease(mode: *mut c_void);
+    }
+
+    /// ScreenCaptureKit is loaded at runtime rather than linked, so the app still
+    /// starts on releases without it.
+    static LOADED: Lazy<bool> = Lazy::new(|| unsafe {
+        let path =
+            NSString::alloc(nil).init_str("/System/Library/Frameworks/ScreenCaptureKit.framework");
+        let bundle: id = msg_send![class!(NSBundle), bundleWithPath: path];
+        let _: () = msg_send![path, release];
+        if bundle == nil {
+            return false;
+        }
+        let loaded: BOOL = msg_send![bundle, load];
+        loaded == YES
+    });
+
+    /// `rect` is in top-left points. Returns `None` when it isn't on a single display.
+    pub fn capture(rect: Rect) -> Result<Option<RgbaImage>, String> {
+        if !*LOADED {
+            return Err("ScreenCaptureKit is not available".to_string());
+        }
+        let manager = Class::get("SCScreenshotManager")
+            .ok_or_else(|| "ScreenCaptureKit screenshots need macOS 14 or later".to_string())?;
+        unsafe {
+            let content = shareable_content()?;
+            let result = screenshot(manager, content, rect);
+            let _: () = msg_send![content, release];
+            result
+        }
+    }
+
+    /// Runs a ScreenCaptureKit call that completes with one object, retaining it.
+    unsafe fn complete(call: impl FnOnce(&block::Block<(id, id), ()>)) -> Result<id, String> {
+        let (tx, rx) = mpsc::channel::<usize>();
+        let handler = ConcreteBlock::new(move |object: id, _error: id| {
+            if object != nil {
+                let _: id = msg_send![object, retain];
+            }
+            let _ = tx.send(object as usize);
+        })
+        .copy();
+        call(&handler);
+        match rx.recv_timeout(TIMEOUT) {
+            Ok(object) if object != 0 => Ok(object as id),
+            Ok(_) => {
+                Err("ScreenCaptureKit capture failed (Screen Recording permission?)".to_string())
+            }
+            Err(_) => Err("ScreenCaptureKit timed out".to_string()),
+        }
+    }
+
+    unsafe fn shareable_content() -> Result<id, String> {
+        let class = Class::get("SCShareableContent")
+            .ok_or_else(|| "ScreenCaptureKit is not available".to_string())?;
+        complete(|handler| {
+            let _: () = msg_send![class, getShareableContentWithCompletionHandler: handler];
+        })
+    }
+
+    unsafe fn screenshot(
+        manager: &Class,
+        content: id,
+        rect: Rect,
+    ) -> Result<Option<RgbaImage>, String> {
+        let displays: id = msg_send![content, displays];
+        let count: usize = msg_send![displays, count];
+        let display = (0..count)
+            .map(|i| -> id { msg_send![displays, objectAtIndex: i] })
+            .find(|&display| {
+                let frame: NSRect = msg_send![display, frame];
+                rect.x as f64 >= frame.origin.x
+                    && rect.y as f64 >= frame.origin.y
+                    && (rect.x + rect.width) as f64 <= frame.origin.x + frame.size.width
+                    && (rect.y + rect.height) as f64 <= frame.origin.y + frame.size.height
+            });
+        let Some(display) = display else {
+            return Ok(None);
+        };
+        let frame: NSRect = msg_send![display, frame];
+        let display_id: u32 = msg_send![display, displayID];
+
+        // Match CGWindowList's best resolution: Retina captures are 2x the point size.
+        let mode = CGDisplayCopyDisplayMode(display_id);
+        let scale = if mode.is_null() {
+            1.0
+        } else {
+            let scale =
+                CGDisplayModeGetPixelWidth(mode) as f64 / CGDisplayModeGetWidth(mode).max(1) as f64;
+            CGDisplayModeRelease(mode);
+            scale
+        };
+
+        let Some(filter_class) = Class::get("SCContentFilter") else {
+            return Err("ScreenCaptureKit is not available".to_string());
+        };
+        let Some(config_class) = Class::get("SCStreamConfiguration") else {
+            return Err("ScreenCaptureKit is not available".to_string());
+        };
+        let empty: id = msg_send![class!(NSArray), array];
+        let filter: id = msg_send![filter_class, alloc];
+        let filter: id = msg_send![filter, initWithDisplay: display excludingWindows: empty];
+        let config: id = msg_send![config_class, new];
+        let source = NSRect::new(
+            NSPoint::new(
+                rect.x as f64 - frame.origin.x,
+                rect.y as f64 - frame.origin.y,
+            ),
+            NSSize::new(rect.width as f64, rect.height as f64),
+        );
+        let _: () = msg_send![config, setSourceRect: source];
+        let _: () = msg_send![config, setWidth: (rect.width as f64 * scale).round() as usize];
+        let _: () = msg_send![config, setHeight: (rect.height as f64 * scale).round() as usize];
+        let _: () = msg_send![config, setShowsCursor: NO];
+
+        // The handler receives a CGImageRef, which retains like an object.
+        let image = complete(|handler| {
+            let _: () = msg_send![manager, captureImageWithFilter: filter configuration: config completionHandler: handler];
+        });
+        let _: () = msg_send![filter, release];
+        let _: () = msg_send![config, release];
+        let image = image?;
+        super::platform::read_image(image as *mut c_void).map(Some)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod gpu {
+    use image::RgbaImage;
+
+    use crate::coords::Rect;
+
+    pub fn capture(_rect: Rect) -> Result<Option<RgbaImage>, String> {
+        Err("No GPU capture backend on this platform".to_string())
+    }
+}
+
+// ==========================================
+// CAPTURE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_capture_backend() -> CaptureBackend {
+    *BACKEND.lock().unwrap()
+}
+
+/// Picks the screenshot backend used by captures, goldens and region waits.
+#[tauri::command]
+pub fn set_capture_backend(backend: CaptureBackend) {
+    *BACKEND.lock().unwrap() = backend;
+    *GPU_FAILED.lock().unwrap() = None;
+}
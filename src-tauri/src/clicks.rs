@@ -0,0 +1,973 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    coords::{self, Rect},
+    motion,
+    palette::{self, ColorRole, Rgb},
+};
+
+// ==========================================
+// Click Effects
+// ==========================================
+
+// Marks mouse clicks for screencast viewers with a short animation where the click
+// landed and, optionally, a sound. Clicks the user makes and clicks injected by
+// automation have separate settings, so a recording can tell them apart or show
+// only one kind. Each effect is drawn into a small click-through window of its own,
+// which screen captures include. The platform hooks only report clicks; windows are
+// opened and animated on the main thread.
+
+const FRAME: Duration = Duration::from_millis(16);
+/// How far into its animation an effect is drawn when motion is reduced.
+const STILL: f64 = 0.3;
+/// Sample rate of the built-in click sound.
+const SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClickTheme {
+    /// A ring that spreads out from the click and fades.
+    #[default]
+    Ripple,
+    /// A ring around the click that fades in place.
+    Ring,
+    /// Crossed lines through the click that flash once.
+    Crosshair,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ClickSound {
+    /// A short built-in tick.
+    Click,
+    /// A WAV file; macOS also plays other formats.
+    File { path: String },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClickStyle {
+    #[serde(default)]
+    pub theme: ClickTheme,
+    /// "#RRGGBB"; the palette's accent color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Diameter in device-independent pixels.
+    #[serde(default = "default_size")]
+    pub size: f64,
+    #[serde(default = "default_duration_ms")]
+    pub duration_ms: u64,
+    /// Silent when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<ClickSound>,
+}
+
+fn default_size() -> f64 {
+    56.0
+}
+
+fn default_duration_ms() -> u64 {
+    450
+}
+
+impl Default for ClickStyle {
+    fn default() -> Self {
+        Self {
+            theme: ClickTheme::default(),
+            color: None,
+            size: default_size(),
+            duration_ms: default_duration_ms(),
+            sound: None,
+        }
+    }
+}
+
+impl ClickStyle {
+    fn check(&self) -> Result<(), String> {
+        if !(8.0..=400.0).contains(&self.size) {
+            return Err(format!("Click effect size {} is out of range", self.size));
+        }
+        if !(50..=5000).contains(&self.duration_ms) {
+            return Err(format!(
+                "Click effect duration {} ms is out of range",
+                self.duration_ms
+            ));
+        }
+        if let Some(color) = &self.color {
+            palette::parse(color)?;
+        }
+        Ok(())
+    }
+}
+
+/// `None` leaves that kind of click unmarked.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClickEffects {
+    #[serde(default)]
+    pub physical: Option<ClickStyle>,
+    #[serde(default)]
+    pub injected: Option<ClickStyle>,
+}
+
+impl Default for ClickEffects {
+    /// Injected clicks get a different theme, so viewers can tell them apart.
+    fn default() -> Self {
+        Self {
+            physical: Some(ClickStyle::default()),
+            injected: Some(ClickStyle {
+                theme: ClickTheme::Crosshair,
+                ..ClickStyle::default()
+            }),
+        }
+    }
+}
+
+/// An effect being animated.
+struct Effect {
+    handle: usize,
+    started: Instant,
+    duration: Duration,
+    theme: ClickTheme,
+    color: Rgb,
+    /// Canvas size in pixels.
+    size: (u32, u32),
+    /// Drawn once and left until it ends, with motion reduced.
+    still: bool,
+}
+
+static EFFECTS_CONFIG: Lazy<Mutex<ClickEffects>> =
+    Lazy::new(|| Mutex::new(ClickEffects::default()));
+/// Only touched on the main thread.
+static EFFECTS: Lazy<Mutex<Vec<Effect>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Set while the subsystem is on.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped by `stop`, ending the animation thread.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Called by the platform hooks for every mouse button press, with its native
+/// position.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn clicked(x: i32, y: i32, injected: bool) {
+    let style = {
+        let config = EFFECTS_CONFIG.lock().unwrap();
+        if injected {
+            config.injected.clone()
+        } else {
+            config.physical.clone()
+        }
+    };
+    let (Some(style), Some(app)) = (style, APP.lock().unwrap().clone()) else {
+        return;
+    };
+    let _ = app.run_on_main_thread(move || {
+        if let Some(sound) = &style.sound {
+            platform::play(sound);
+        }
+        if let Err(e) = show(x, y, &style) {
+            log::warn!("click effect failed: {}", e);
+        }
+    });
+}
+
+/// Opens an effect window centered on (`x`, `y`). Runs on the main thread.
+fn show(x: i32, y: i32, style: &ClickStyle) -> Result<(), String> {
+    let monitors = coords::monitors();
+    let Some(monitor) = monitors.iter().find(|m| m.contains(x as f64, y as f64)) else {
+        return Ok(());
+    };
+    let side = monitor.native_length(style.size).round().max(1.0) as i32;
+    let rect = Rect {
+        x: x - side / 2,
+        y: y - side / 2,
+        width: side,
+        height: side,
+    };
+    let color = style
+        .color
+        .as_deref()
+        .map_or(Ok(palette::color(ColorRole::Accent)), palette::parse)?;
+    let (handle, size) = platform::open(rect)?;
+    let effect = Effect {
+        handle,
+        started: Instant::now(),
+        duration: Duration::from_millis(style.duration_ms),
+        theme: style.theme,
+        color,
+        size,
+        still: motion::reduced(),
+    };
+    let t = if effect.still { STILL } else { 0.0 };
+    platform::present(handle, &frame(&effect, t));
+    EFFECTS.lock().unwrap().push(effect);
+    Ok(())
+}
+
+/// Advances every effect a frame, closing the finished ones. Runs on the main thread.
+fn tick() {
+    let now = Instant::now();
+    EFFECTS.lock().unwrap().retain(|effect| {
+        let t = (now - effect.started).as_secs_f64() / effect.duration.as_secs_f64();
+        if t >= 1.0 {
+            platform::close(effect.handle);
+            return false;
+        }
+        if !effect.still {
+            platform::present(effect.handle, &frame(effect, t));
+        }
+        true
+    });
+}
+
+/// The effect `t` (0 to 1) of the way through, premultiplied.
+fn frame(effect: &Effect, t: f64) -> RgbaImage {
+    let (width, height) = effect.size;
+    let side = width.min(height) as f64;
+    let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+    // Eases out, so effects start quickly and settle.
+    let eased = 1.0 - (1.0 - t).powi(3);
+    let fade = 1.0 - t;
+    let line = (side * 0.06).max(2.0);
+
+    // Coverage of pixel (px, py), before fading.
+    let coverage: Box<dyn Fn(f64, f64) -> f64> = match effect.theme {
+        ClickTheme::Ripple => {
+            let radius = side / 2.0 * (0.2 + 0.75 * eased);
+            Box::new(move |px, py| {
+                let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+                (line / 2.0 + 0.5 - (distance - radius).abs()).clamp(0.0, 1.0)
+            })
+        }
+        ClickTheme::Ring => {
+            let radius = side * 0.35;
+            Box::new(move |px, py| {
+                let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+                let ring = (line / 2.0 + 0.5 - (distance - radius).abs()).clamp(0.0, 1.0);
+                // A faint fill inside the ring.
+                let fill = (radius + 0.5 - distance).clamp(0.0, 1.0) * 0.25;
+                ring.max(fill)
+            })
+        }
+        ClickTheme::Crosshair => {
+            let arm = side / 2.0 - 1.0;
+            Box::new(move |px, py| {
+                let (dx, dy) = ((px - cx).abs(), (py - cy).abs());
+                let across = |d: f64| (line / 2.0 + 0.5 - d).clamp(0.0, 1.0);
+                let along = |d: f64| (arm + 0.5 - d).clamp(0.0, 1.0);
+                (across(dy) * along(dx)).max(across(dx) * along(dy))
+            })
+        }
+    };
+
+    let (r, g, b) = effect.color;
+    RgbaImage::from_fn(width, height, |px, py| {
+        let alpha = coverage(px as f64 + 0.5, py as f64 + 0.5) * fade;
+        let channel = |c: u8| (c as f64 * alpha).round() as u8;
+        Rgba([
+            channel(r),
+            channel(g),
+            channel(b),
+            (alpha * 255.0).round() as u8,
+        ])
+    })
+}
+
+/// A 16-bit mono WAV of a short, quickly decaying tick.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn click_wav() -> &'static [u8] {
+    static WAV: Lazy<Vec<u8>> = Lazy::new(|| {
+        let samples: Vec<i16> = (0..SAMPLE_RATE / 40)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                let wave = (2.0 * std::f64::consts::PI * 2200.0 * t).sin() * (-t / 0.004).exp();
+                (wave * 0.5 * i16::MAX as f64) as i16
+            })
+            .collect();
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::with_capacity(44 + data_len as usize);
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, one channel.
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    });
+    &WAV
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    *APP.lock().unwrap() = Some(app.clone());
+    platform::start().inspect_err(|_| *APP.lock().unwrap() = None)?;
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::Builder::new()
+        .name("click-effects".to_string())
+        .spawn(move || {
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(FRAME);
+                if !EFFECTS.lock().unwrap().is_empty() {
+                    let _ = app.run_on_main_thread(tick);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn click effect thread: {}", e))?;
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    platform::stop();
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(app) = APP.lock().unwrap().take() {
+        let _ = app.run_on_main_thread(|| {
+            for effect in EFFECTS.lock().unwrap().drain(..) {
+                platform::close(effect.handle);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+
+    use image::RgbaImage;
+    use once_cell::sync::Lazy;
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{
+        COLORREF, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, POINT, SIZE, WPARAM,
+    };
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GetDC, ReleaseDC,
+        SelectObject, AC_SRC_ALPHA, AC_SRC_OVER, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        BLENDFUNCTION, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Media::Audio::{
+        PlaySoundW, SND_ASYNC, SND_FILENAME, SND_MEMORY, SND_NODEFAULT,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, CreateWindowExW, DefWindowProcW, DestroyWindow, GetMessageW,
+        PostThreadMessageW, RegisterClassW, SetTimer, SetWindowsHookExW, ShowWindow,
+        UnhookWindowsHookEx, UpdateLayeredWindow, HHOOK, LLMHF_INJECTED, MSG, MSLLHOOKSTRUCT,
+        SW_SHOWNOACTIVATE, ULW_ALPHA, WH_MOUSE_LL, WM_LBUTTONDOWN, WM_MBUTTONDOWN, WM_QUIT,
+        WM_RBUTTONDOWN, WM_TIMER, WM_XBUTTONDOWN, WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE,
+        WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT, WS_POPUP,
+    };
+
+    use super::{click_wav, clicked, ClickSound};
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    const CLASS_NAME: PCWSTR = w!("ScreenShareHostClickEffect");
+
+    /// Watchdog entry of the hook thread.
+    const WATCHED: &str = "click-hook";
+
+    static THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    static REGISTERED: Lazy<bool> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        RegisterClassW(&class) != 0
+    });
+
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code >= 0
+            && matches!(
+                wparam.0 as u32,
+                WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
+            )
+        {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            clicked(info.pt.x, info.pt.y, info.flags & LLMHF_INJECTED != 0);
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    /// Installs the hook and has the watchdog replace the hook thread if it stops
+    /// pumping messages.
+    pub fn start() -> Result<(), String> {
+        spawn()?;
+        crate::watchdog::register(
+            WATCHED,
+            crate::watchdog::THREAD_TIMEOUT,
+            Some(Arc::new(|| {
+                quit();
+                spawn()
+            })),
+        );
+        Ok(())
+    }
+
+    fn spawn() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("click-hook".to_string())
+            .spawn(move || unsafe {
+                THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+                let hook = GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| e.to_string())
+                    .and_then(|module| {
+                        SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), HINSTANCE(module.0), 0)
+                            .map_err(|e| format!("Failed to install mouse hook: {}", e))
+                    });
+                let hook = match hook {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                let _ = SetTimer(HWND::default(), 0, crate::watchdog::HEARTBEAT_MS, None);
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    if msg.message == WM_TIMER {
+                        crate::watchdog::heartbeat(WATCHED);
+                    }
+                }
+
+                let _ = UnhookWindowsHookEx(hook);
+            })
+            .map_err(|e| format!("Failed to spawn click hook: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Click hook thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        crate::watchdog::unregister(WATCHED);
+        quit();
+    }
+
+    /// Ends the hook thread, or a stalled one once it recovers.
+    fn quit() {
+        unsafe {
+            let _ = PostThreadMessageW(
+                THREAD_ID.load(Ordering::SeqCst),
+                WM_QUIT,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+    }
+
+    /// Returns the window and its canvas size in pixels.
+    pub fn open(rect: Rect) -> Result<(usize, (u32, u32)), String> {
+        if !*REGISTERED {
+            return Err("Failed to register click effect window class".to_string());
+        }
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST
+                    | WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TRANSPARENT
+                    | WS_EX_NOACTIVATE,
+                CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null()).map_err(|e| e.to_string())?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create click effect window: {}", e))?;
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            layers::add(Layer::Pointer, hwnd.0 as usize);
+            Ok((
+                hwnd.0 as usize,
+                (rect.width.max(1) as u32, rect.height.max(1) as u32),
+            ))
+        }
+    }
+
+    /// Shows `canvas` (premultiplied RGBA) as the window's per-pixel-alpha content.
+    pub fn present(handle: usize, canvas: &RgbaImage) {
+        let (width, height) = (canvas.width() as i32, canvas.height() as i32);
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative height for top-down rows.
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe {
+            let screen = GetDC(HWND::default());
+            let memory = CreateCompatibleDC(screen);
+            let mut bits: *mut c_void = std::ptr::null_mut();
+            if let Ok(bitmap) = CreateDIBSection(memory, &info, DIB_RGB_COLORS, &mut bits, None, 0)
+            {
+                let pixels = std::slice::from_raw_parts_mut(bits as *mut u8, canvas.len());
+                for (bgra, rgba) in pixels.chunks_exact_mut(4).zip(canvas.pixels()) {
+                    bgra.copy_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+                }
+                let previous = SelectObject(memory, bitmap);
+                let blend = BLENDFUNCTION {
+                    BlendOp: AC_SRC_OVER as u8,
+                    BlendFlags: 0,
+                    SourceConstantAlpha: 255,
+                    AlphaFormat: AC_SRC_ALPHA as u8,
+                };
+                let _ = UpdateLayeredWindow(
+                    HWND(handle as *mut _),
+                    screen,
+                    None,
+                    Some(&SIZE {
+                        cx: width,
+                        cy: height,
+                    }),
+                    memory,
+                    Some(&POINT::default()),
+                    COLORREF(0),
+                    Some(&blend),
+                    ULW_ALPHA,
+                );
+                SelectObject(memory, previous);
+                let _ = DeleteObject(bitmap);
+            }
+            let _ = DeleteDC(memory);
+            ReleaseDC(HWND::default(), screen);
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        unsafe {
+            let _ = DestroyWindow(HWND(handle as *mut _));
+        }
+    }
+
+    /// Plays asynchronously, cutting off the previous click's sound.
+    pub fn play(sound: &ClickSound) {
+        unsafe {
+            let _ = match sound {
+                // The buffer is static, so it outlives the asynchronous playback.
+                ClickSound::Click => PlaySoundW(
+                    PCWSTR(click_wav().as_ptr() as *const u16),
+                    HMODULE::default(),
+                    SND_MEMORY | SND_ASYNC | SND_NODEFAULT,
+                ),
+                ClickSound::File { path } => PlaySoundW(
+                    &HSTRING::from(path.as_str()),
+                    HMODULE::default(),
+                    SND_FILENAME | SND_ASYNC | SND_NODEFAULT,
+                ),
+            };
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        collections::HashMap,
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Mutex,
+        },
+        thread,
+    };
+
+    use cocoa::appkit::{NSBackingStoreType, NSWindow, NSWindowStyleMask};
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString};
+    use image::RgbaImage;
+    use objc::{class, msg_send, sel, sel_impl};
+    use once_cell::sync::Lazy;
+
+    use super::{click_wav, clicked, ClickSound};
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    type CFTypeRef = *mut c_void;
+    type TapCallback =
+        extern "C" fn(proxy: CFTypeRef, kind: u32, event: CFTypeRef, user: CFTypeRef) -> CFTypeRef;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT: u32 = 0;
+    const LISTEN_ONLY: u32 = 1;
+
+    const LEFT_DOWN: u32 = 1;
+    const RIGHT_DOWN: u32 = 3;
+    const OTHER_DOWN: u32 = 25;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+
+    const FIELD_SOURCE_PID: u32 = 41;
+    const FIELD_SOURCE_STATE_ID: u32 = 45;
+    /// kCGEventSourceStateHIDSystemState, the state of events from real devices.
+    const HID_SYSTEM_STATE: i64 = 1;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            mask: u64,
+            callback: TapCallback,
+            user: CFTypeRef,
+        ) -> CFTypeRef;
+        fn CGEventTapEnable(tap: CFTypeRef, enable: bool);
+        fn CGEventGetLocation(event: CFTypeRef) -> CGPoint;
+        fn CGEventGetIntegerValueField(event: CFTypeRef, field: u32) -> i64;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: CFTypeRef;
+        fn CFMachPortCreateRunLoopSource(
+            alloc: CFTypeRef,
+            port: CFTypeRef,
+            order: isize,
+        ) -> CFTypeRef;
+        fn CFRunLoopGetCurrent() -> CFTypeRef;
+        fn CFRunLoopAddSource(run_loop: CFTypeRef, source: CFTypeRef, mode: CFTypeRef);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: CFTypeRef);
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    static RUN_LOOP: AtomicUsize = AtomicUsize::new(0);
+    static TAP: AtomicUsize = AtomicUsize::new(0);
+    /// Loaded sounds, kept so each plays again without reloading.
+    static SOUNDS: Lazy<Mutex<HashMap<ClickSound, usize>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+
+    extern "C" fn callback(
+        _proxy: CFTypeRef,
+        kind: u32,
+        event: CFTypeRef,
+        _user: CFTypeRef,
+    ) -> CFTypeRef {
+        unsafe {
+            if kind == TAP_DISABLED_BY_TIMEOUT {
+                CGEventTapEnable(TAP.load(Ordering::SeqCst) as CFTypeRef, true);
+                return event;
+            }
+            // Events posted by software, this app's included, don't come from the
+            // HID system state; some injectors borrow it, so the posting process
+            // is checked too.
+            let injected = CGEventGetIntegerValueField(event, FIELD_SOURCE_STATE_ID)
+                != HID_SYSTEM_STATE
+                || CGEventGetIntegerValueField(event, FIELD_SOURCE_PID)
+                    == std::process::id() as i64;
+            let location = CGEventGetLocation(event);
+            clicked(
+                location.x.round() as i32,
+                location.y.round() as i32,
+                injected,
+            );
+            event
+        }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("click-hook".to_string())
+            .spawn(move || unsafe {
+                let mask = (1u64 << LEFT_DOWN) | (1 << RIGHT_DOWN) | (1 << OTHER_DOWN);
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    LISTEN_ONLY,
+                    mask,
+                    callback,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = ready_tx.send(Err(
+                        "Failed to create event tap (grant Accessibility permission)".to_string(),
+                    ));
+                    return;
+                }
+                TAP.store(tap as usize, Ordering::SeqCst);
+
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null_mut(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                RUN_LOOP.store(run_loop as usize, Ordering::SeqCst);
+                let _ = ready_tx.send(Ok(()));
+
+                CFRunLoopRun();
+
+                CGEventTapEnable(tap, false);
+                RUN_LOOP.store(0, Ordering::SeqCst);
+                CFRelease(source);
+                CFRelease(tap);
+            })
+            .map_err(|e| format!("Failed to spawn click tap: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Click tap thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        let run_loop = RUN_LOOP.load(Ordering::SeqCst);
+        if run_loop != 0 {
+            unsafe { CFRunLoopStop(run_loop as CFTypeRef) };
+        }
+    }
+
+    /// Returns the window and its canvas size in backing pixels.
+    pub fn open(rect: Rect) -> Result<(usize, (u32, u32)), String> {
+        unsafe {
+            let screens: id = msg_send![class!(NSScreen), screens];
+            let count: usize = msg_send![screens, count];
+            if count == 0 {
+                return Err("No screens found".to_string());
+            }
+            let primary: id = msg_send![screens, objectAtIndex: 0usize];
+            let primary: NSRect = msg_send![primary, frame];
+            let frame = NSRect::new(
+                NSPoint::new(
+                    rect.x as f64,
+                    primary.size.height - (rect.y + rect.height) as f64,
+                ),
+                NSSize::new(rect.width as f64, rect.height as f64),
+            );
+            let window: id = msg_send![class!(NSWindow), alloc];
+            let window: id = window.initWithContentRect_styleMask_backing_defer_(
+                frame,
+                NSWindowStyleMask::NSBorderlessWindowMask,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                false,
+            );
+            if window == nil {
+                return Err("Failed to create click effect window".to_string());
+            }
+            let clear: id = msg_send![class!(NSColor), clearColor];
+            window.setBackgroundColor_(clear);
+            let _: () = msg_send![window, setReleasedWhenClosed: NO];
+            let _: () = msg_send![window, setOpaque: NO];
+            let _: () = msg_send![window, setHasShadow: NO];
+            let _: () = msg_send![window, setIgnoresMouseEvents: YES];
+            let view: id = window.contentView();
+            let _: () = msg_send![view, setWantsLayer: YES];
+            layers::add(Layer::Pointer, window as usize);
+            window.orderFrontRegardless();
+
+            let scale: f64 = msg_send![window, backingScaleFactor];
+            let size = (
+                (frame.size.width * scale).round().max(1.0) as u32,
+                (frame.size.height * scale).round().max(1.0) as u32,
+            );
+            Ok((window as usize, size))
+        }
+    }
+
+    /// Shows `canvas` (premultiplied RGBA) as the content view's layer contents.
+    pub fn present(handle: usize, canvas: &RgbaImage) {
+        let (width, height) = canvas.dimensions();
+        unsafe {
+            let color_space = NSString::alloc(nil).init_str("NSDeviceRGBColorSpace");
+            let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+            let rep: id = msg_send![rep,
+                initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>()
+                pixelsWide: width as isize
+                pixelsHigh: height as isize
+                bitsPerSample: 8isize
+                samplesPerPixel: 4isize
+                hasAlpha: YES
+                isPlanar: NO
+                colorSpaceName: color_space
+                bytesPerRow: (width * 4) as isize
+                bitsPerPixel: 32isize];
+            let _: () = msg_send![color_space, release];
+            if rep == nil {
+                return;
+            }
+            let data: *mut u8 = msg_send![rep, bitmapData];
+            std::ptr::copy_nonoverlapping(canvas.as_raw().as_ptr(), data, canvas.as_raw().len());
+
+            let view: id = msg_send![handle as id, contentView];
+            let bounds: NSRect = msg_send![view, bounds];
+            let picture: id = msg_send![class!(NSImage), alloc];
+            let picture: id = msg_send![picture, initWithSize: bounds.size];
+            let _: () = msg_send![picture, addRepresentation: rep];
+            let _: () = msg_send![rep, release];
+            let layer: id = msg_send![view, layer];
+            let _: () = msg_send![layer, setContents: picture];
+            let _: () = msg_send![picture, release];
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, close];
+            let _: () = msg_send![window, release];
+        }
+    }
+
+    /// Restarts the sound if the previous click's is still playing.
+    pub fn play(sound: &ClickSound) {
+        let mut sounds = SOUNDS.lock().unwrap();
+        let player = match sounds.get(sound) {
+            Some(player) => *player as id,
+            None => unsafe {
+                let player: id = msg_send![class!(NSSound), alloc];
+                let player: id = match sound {
+                    ClickSound::Click => {
+                        let wav = click_wav();
+                        let data: id = msg_send![class!(NSData),
+                            dataWithBytes: wav.as_ptr() as *const c_void
+                            length: wav.len()];
+                        msg_send![player, initWithData: data]
+                    }
+                    ClickSound::File { path } => {
+                        let path = NSString::alloc(nil).init_str(path);
+                        let player: id =
+                            msg_send![player, initWithContentsOfFile: path byReference: YES];
+                        let _: () = msg_send![path, release];
+                        player
+                    }
+                };
+                if player == nil {
+                    log::warn!("click sound {:?} could not be loaded", sound);
+                    return;
+                }
+                sounds.insert(sound.clone(), player as usize);
+                player
+            },
+        };
+        unsafe {
+            let _: () = msg_send![player, stop];
+            let _: () = msg_send![player, play];
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use image::RgbaImage;
+
+    use super::ClickSound;
+    use crate::coords::Rect;
+
+    pub fn start() -> Result<(), String> {
+        Err("Click effects are only supported on macOS and Windows".to_string())
+    }
+
+    pub fn stop() {}
+
+    pub fn open(_rect: Rect) -> Result<(usize, (u32, u32)), String> {
+        Err("Click effects are only supported on macOS and Windows".to_string())
+    }
+
+    pub fn present(_handle: usize, _canvas: &RgbaImage) {}
+
+    pub fn close(_handle: usize) {}
+
+    pub fn play(_sound: &ClickSound) {}
+}
+
+// ==========================================
+// CLICK EFFECT COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_click_effects() -> ClickEffects {
+    EFFECTS_CONFIG.lock().unwrap().clone()
+}
+
+/// Sets the themes and sounds for physical and injected clicks. The effects show
+/// while the "click-effects" subsystem is on.
+#[tauri::command]
+pub fn set_click_effects(effects: ClickEffects) -> Result<(), String> {
+    for style in [&effects.physical, &effects.injected].into_iter().flatten() {
+        style.check()?;
+    }
+    *EFFECTS_CONFIG.lock().unwrap() = effects;
+    Ok(())
+}
+
+/// Plays the effect and sound for one kind of click at (`x`, `y`), native
+/// coordinates, so settings can be tried out.
+#[tauri::command]
+pub fn preview_click_effect(x: i32, y: i32, injected: bool) -> Result<(), String> {
+    let style = {
+        let config = EFFECTS_CONFIG.lock().unwrap();
+        if injected {
+            config.injected.clone()
+        } else {
+            config.physical.clone()
+        }
+    }
+    .ok_or("That kind of click has no effect")?;
+    if let Some(sound) = &style.sound {
+        platform::play(sound);
+    }
+    show(x, y, &style)
+}
@@ -0,0 +1,501 @@
+use std::{thread, time::Duration};
+
+use enigo::{Enigo, Key, KeyboardControllable};
+
+use crate::{
+    crash,
+    locks::{self, LockResource},
+};
+
+// ==========================================
+// Clipboard Paste
+// ==========================================
+
+/// How long the target gets to read the clipboard after the paste shortcut before
+/// the previous contents are put back.
+const PASTE_SETTLE: Duration = Duration::from_millis(200);
+
+#[cfg(target_os = "macos")]
+const PASTE_MODIFIER: Key = Key::Meta;
+#[cfg(not(target_os = "macos"))]
+const PASTE_MODIFIER: Key = Key::Control;
+
+/// Pastes `text` into the focused window through the clipboard, which is much faster
+/// than typing and unaffected by keyboard layouts and IME state.
+pub fn paste(text: &str, restore_clipboard: bool) -> Result<(), String> {
+    let _locks = locks::acquire(
+        "paste_text",
+        [LockResource::Clipboard, LockResource::Keyboard],
+        Duration::ZERO,
+    )?;
+    crash::breadcrumb(
+        "input",
+        format!(
+            "paste_text {} chars restore={}",
+            text.chars().count(),
+            restore_clipboard
+        ),
+    );
+
+    let saved = if restore_clipboard {
+        Some(platform::save()?)
+    } else {
+        None
+    };
+    platform::set_text(text)?;
+
+    let mut enigo = Enigo::new();
+    enigo.key_down(PASTE_MODIFIER);
+    enigo.key_click(Key::Layout('v'));
+    enigo.key_up(PASTE_MODIFIER);
+
+    if let Some(saved) = saved {
+        thread::sleep(PASTE_SETTLE);
+        platform::restore(saved)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::{GlobalFree, HANDLE, HGLOBAL, HWND};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData, OpenClipboard,
+        SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{
+        GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+    };
+    use windows::Win32::System::Ole::{
+        CF_BITMAP, CF_DSPBITMAP, CF_DSPENHMETAFILE, CF_DSPMETAFILEPICT, CF_ENHMETAFILE,
+        CF_METAFILEPICT, CF_OWNERDISPLAY, CF_PALETTE, CF_UNICODETEXT,
+    };
+
+    /// Every memory-backed format and its bytes. GDI handle formats are skipped; the
+    /// system re-synthesizes bitmaps from the saved `CF_DIB`.
+    pub struct Saved(Vec<(u32, Vec<u8>)>);
+
+    const HANDLE_FORMATS: [u16; 8] = [
+        CF_BITMAP.0,
+        CF_DSPBITMAP.0,
+        CF_DSPENHMETAFILE.0,
+        CF_DSPMETAFILEPICT.0,
+        CF_ENHMETAFILE.0,
+        CF_METAFILEPICT.0,
+        CF_OWNERDISPLAY.0,
+        CF_PALETTE.0,
+    ];
+
+    /// Runs `f` with the clipboard open; another process may hold it briefly.
+    fn with_clipboard<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        let mut opened = false;
+        for _ in 0..10 {
+            if unsafe { OpenClipboard(HWND::default()) }.is_ok() {
+                opened = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        if !opened {
+            return Err("Clipboard is in use by another application".to_string());
+        }
+        let result = f();
+        let _ = unsafe { CloseClipboard() };
+        result
+    }
+
+    unsafe fn set_bytes(format: u32, bytes: &[u8]) -> Result<(), String> {
+        let memory = GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1)).map_err(|e| e.to_string())?;
+        let target = GlobalLock(memory) as *mut u8;
+        if target.is_null() {
+            let _ = GlobalFree(memory);
+            return Err("Failed to lock clipboard memory".to_string());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), target, bytes.len());
+        let _ = GlobalUnlock(memory);
+
+        // On success the clipboard owns the memory.
+        if let Err(e) = SetClipboardData(format, HANDLE(memory.0)) {
+            let _ = GlobalFree(memory);
+            return Err(format!("Failed to set clipboard data: {}", e));
+        }
+        Ok(())
+    }
+
+    pub fn save() -> Result<Saved, String> {
+        with_clipboard(|| unsafe {
+            let mut formats = Vec::new();
+            let mut format = EnumClipboardFormats(0);
+            while format != 0 {
+                if !HANDLE_FORMATS.contains(&(format as u16)) {
+                    if let Ok(handle) = GetClipboardData(format) {
+                        let memory = HGLOBAL(handle.0);
+                        let source = GlobalLock(memory) as *const u8;
+                        if !source.is_null() {
+                            let len = GlobalSize(memory);
+                            formats
+                                .push((format, std::slice::from_raw_parts(source, len).to_vec()));
+                            let _ = GlobalUnlock(memory);
+                        }
+                    }
+                }
+                format = EnumClipboardFormats(format);
+            }
+            Ok(Saved(formats))
+        })
+    }
+
+    pub fn set_text(text: &str) -> Result<(), String> {
+        let bytes: Vec<u8> = text
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        with_clipboard(|| unsafe {
+            EmptyClipboard().map_err(|e| e.to_string())?;
+            set_bytes(CF_UNICODETEXT.0 as u32, &bytes)
+        })
+    }
+
+    pub fn restore(saved: Saved) -> Result<(), String> {
+        with_clipboard(|| unsafe {
+            EmptyClipboard().map_err(|e| e.to_string())?;
+            for (format, bytes) in &saved.0 {
+                set_bytes(*format, bytes)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSArray, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    const TYPE_STRING: &str = "public.utf8-plain-text";
+
+    /// Retained (type, data) pairs per pasteboard item.
+    pub struct Saved(Vec<Vec<(id, id)>>);
+
+    unsafe fn pasteboard() -> id {
+        msg_send![class!(NSPasteboard), generalPasteboard]
+    }
+
+    pub fn save() -> Result<Saved, String> {
+        unsafe {
+            let items: id = msg_send![pasteboard(), pasteboardItems];
+            let mut saved = Vec::new();
+            if items == nil {
+                return Ok(Saved(saved));
+            }
+            for i in 0..items.count() {
+                let item = items.objectAtIndex(i);
+                let types: id = msg_send![item, types];
+                let mut entries = Vec::new();
+                for j in 0..types.count() {
+                    let kind = types.objectAtIndex(j);
+                    let data: id = msg_send![item, dataForType: kind];
+                    if data != nil {
+                        let _: id = msg_send![kind, retain];
+                        let _: id = msg_send![data, retain];
+                        entries.push((kind, data));
+                    }
+                }
+                saved.push(entries);
+            }
+            Ok(Saved(saved))
+        }
+    }
+
+    pub fn set_text(text: &str) -> Result<(), String> {
+        unsafe {
+            let board = pasteboard();
+            let _: isize = msg_send![board, clearContents];
+            let string = NSString::alloc(nil).init_str(text);
+            let kind = NSString::alloc(nil).init_str(TYPE_STRING);
+            let ok: bool = msg_send![board, setString: string forType: kind];
+            let _: () = msg_send![string, release];
+            let _: () = msg_send![kind, release];
+            if ok {
+                Ok(())
+            } else {
+                Err("Failed to write to the pasteboard".to_string())
+            }
+        }
+    }
+
+    pub fn restore(saved: Saved) -> Result<(), String> {
+        unsafe {
+            let board = pasteboard();
+            let _: isize = msg_send![board, clearContents];
+            let items: id = msg_send![class!(NSMutableArray), array];
+            for entries in saved.0 {
+                let item: id = msg_send![class!(NSPasteboardItem), alloc];
+                let item: id = msg_send![item, init];
+                for (kind, data) in entries {
+                    let _: bool = msg_send![item, setData: data forType: kind];
+                    let _: () = msg_send![kind, release];
+                    let _: () = msg_send![data, release];
+                }
+                let _: () = msg_send![items, addObject: item];
+                let _: () = msg_send![item, release];
+            }
+            if items.count() > 0 {
+                let _: bool = msg_send![board, writeObjects: items];
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        AtomEnum, ConnectionExt, CreateWindowAux, EventMask, PropMode, SelectionNotifyEvent,
+        Window, WindowClass, SELECTION_NOTIFY_EVENT,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::{CURRENT_TIME, NONE};
+
+    /// X11 selections are served live by their owner, so only text survives a
+    /// round-trip; other clipboard contents are dropped on restore.
+    pub struct Saved(Option<String>);
+
+    const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+    // Bumped whenever a new owner thread starts; the previous one exits.
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+    struct Atoms {
+        clipboard: u32,
+        targets: u32,
+        utf8: u32,
+        text: u32,
+        property: u32,
+    }
+
+    fn atom(conn: &RustConnection, name: &str) -> Result<u32, String> {
+        conn.intern_atom(false, name.as_bytes())
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map(|reply| reply.atom)
+            .map_err(|e| e.to_string())
+    }
+
+    fn connect() -> Result<(RustConnection, Window, Atoms), String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = Atoms {
+            clipboard: atom(&conn, "CLIPBOARD")?,
+            targets: atom(&conn, "TARGETS")?,
+            utf8: atom(&conn, "UTF8_STRING")?,
+            text: atom(&conn, "TEXT")?,
+            property: atom(&conn, "SCREENSHAREHOST_CLIPBOARD")?,
+        };
+        let window = conn.generate_id().map_err(|e| e.to_string())?;
+        conn.create_window(
+            0,
+            window,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok((conn, window, atoms))
+    }
+
+    pub fn save() -> Result<Saved, String> {
+        let (conn, window, atoms) = connect()?;
+        conn.convert_selection(
+            window,
+            atoms.clipboard,
+            atoms.utf8,
+            atoms.property,
+            CURRENT_TIME,
+        )
+        .map_err(|e| e.to_string())?;
+        conn.flush().map_err(|e| e.to_string())?;
+
+        let deadline = Instant::now() + READ_TIMEOUT;
+        let mut text = None;
+        while Instant::now() < deadline {
+            match conn.poll_for_event().map_err(|e| e.to_string())? {
+                Some(Event::SelectionNotify(event)) => {
+                    if event.property != NONE {
+                        text = conn
+                            .get_property(
+                                true,
+                                window,
+                                atoms.property,
+                                AtomEnum::ANY,
+                                0,
+                                u32::MAX / 4,
+                            )
+                            .map_err(|e| e.to_string())?
+                            .reply()
+                            .ok()
+                            .filter(|reply| reply.type_ == atoms.utf8)
+                            .map(|reply| String::from_utf8_lossy(&reply.value).into_owned());
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+        let _ = conn.destroy_window(window);
+        Ok(Saved(text))
+    }
+
+    fn serve(
+        conn: &RustConnection,
+        atoms: &Atoms,
+        text: &str,
+        generation: u64,
+    ) -> Result<(), String> {
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            let Some(event) = conn.poll_for_event().map_err(|e| e.to_string())? else {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            };
+            match event {
+                Event::SelectionClear(_) => break,
+                Event::SelectionRequest(request) => {
+                    let property = if request.target == atoms.targets {
+                        let supported = [
+                            atoms.targets,
+                            atoms.utf8,
+                            atoms.text,
+                            AtomEnum::STRING.into(),
+                        ];
+                        conn.change_property32(
+                            PropMode::REPLACE,
+                            request.requestor,
+                            request.property,
+                            AtomEnum::ATOM,
+                            &supported,
+                        )
+                        .map_err(|e| e.to_string())?;
+                        request.property
+                    } else if [atoms.utf8, atoms.text, AtomEnum::STRING.into()]
+                        .contains(&request.target)
+                    {
+                        conn.change_property8(
+                            PropMode::REPLACE,
+                            request.requestor,
+                            request.property,
+                            request.target,
+                            text.as_bytes(),
+                        )
+                        .map_err(|e| e.to_string())?;
+                        request.property
+                    } else {
+                        NONE
+                    };
+                    let notify = SelectionNotifyEvent {
+                        response_type: SELECTION_NOTIFY_EVENT,
+                        sequence: 0,
+                        time: request.time,
+                        requestor: request.requestor,
+                        selection: request.selection,
+                        target: request.target,
+                        property,
+                    };
+                    conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+                        .map_err(|e| e.to_string())?;
+                    conn.flush().map_err(|e| e.to_string())?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes ownership of the clipboard and serves `text` from a background thread
+    /// until another application copies something.
+    pub fn set_text(text: &str) -> Result<(), String> {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let text = text.to_string();
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("clipboard-owner".to_string())
+            .spawn(move || {
+                let owned = connect().and_then(|(conn, window, atoms)| {
+                    conn.set_selection_owner(window, atoms.clipboard, CURRENT_TIME)
+                        .map_err(|e| e.to_string())?;
+                    let owner = conn
+                        .get_selection_owner(atoms.clipboard)
+                        .map_err(|e| e.to_string())?
+                        .reply()
+                        .map_err(|e| e.to_string())?
+                        .owner;
+                    if owner != window {
+                        return Err("Failed to take ownership of the clipboard".to_string());
+                    }
+                    Ok((conn, window, atoms))
+                });
+                let (conn, window, atoms) = match owned {
+                    Ok(owned) => owned,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                if let Err(e) = serve(&conn, &atoms, &text, generation) {
+                    log::warn!("clipboard owner stopped: {}", e);
+                }
+                let _ = conn.destroy_window(window);
+                let _ = conn.flush();
+            })
+            .map_err(|e| format!("Failed to spawn clipboard owner: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Clipboard owner exited".to_string())?
+    }
+
+    pub fn restore(saved: Saved) -> Result<(), String> {
+        match saved.0 {
+            Some(text) => set_text(&text),
+            None => {
+                // Nothing restorable: stop serving the pasted text.
+                GENERATION.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    }
+}
+
+// ==========================================
+// CLIPBOARD COMMANDS
+// ==========================================
+
+/// Runs off the main thread: it waits for the target to read the clipboard.
+#[tauri::command(async)]
+pub fn paste_text(text: String, restore_clipboard: Option<bool>) -> Result<(), String> {
+    paste(&text, restore_clipboard.unwrap_or(true))
+}
@@ -0,0 +1,180 @@
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::{self, Category, CommandInfo, Risk};
+use crate::{dispatch, middleware, OverlayManager};
+
+// ==========================================
+// Command Palette
+// ==========================================
+
+// A Spotlight-like surface over the command registry: the UI searches it as the user
+// types and runs the chosen entry. Ranking favours whole words of the command name
+// over prefixes and substrings, and the name over its description; every word of
+// the query has to match somewhere. Running an entry goes through the command
+// middleware like any control request, under `PALETTE_ORIGIN`, so policies, audit
+// and dry-run apply to it as well.
+
+const DEFAULT_LIMIT: usize = 20;
+/// Commands the palette never runs: itself.
+const EXCLUDED: &[&str] = &["execute_action"];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PaletteAction {
+    /// The command name, passed back to `execute_action`.
+    pub id: &'static str,
+    /// The name spelled out, e.g. "Toggle overlay for monitor".
+    pub title: String,
+    pub category: Category,
+    pub risk: Risk,
+    pub description: &'static str,
+    /// Higher is a better match; 0 for an empty query.
+    pub score: u32,
+}
+
+/// Whether the palette offers command `name`: one `dispatch` can run, not itself.
+fn runnable(name: &str) -> bool {
+    !EXCLUDED.contains(&name) && dispatch::dispatchable(name)
+}
+
+fn title(name: &str) -> String {
+    let words = name.replace('_', " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => words,
+    }
+}
+
+/// Whether `needle`'s characters appear in `haystack` in order.
+fn subsequence(needle: &str, haystack: &str) -> bool {
+    let mut rest = haystack.chars();
+    needle.chars().all(|c| rest.any(|h| h == c))
+}
+
+/// How well one lowercase query `token` matches the command, or `None` if it doesn't.
+fn token_score(token: &str, name: &str, description: &str) -> Option<u32> {
+    let name_words = || name.split('_');
+    let description_words = || {
+        description
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+    };
+
+    if name_words().any(|w| w == token) {
+        Some(10)
+    } else if name_words().any(|w| w.starts_with(token)) {
+        Some(6)
+    } else if name.contains(token) {
+        Some(4)
+    } else if description_words().any(|w| w.starts_with(token)) {
+        Some(2)
+    } else if description.contains(token) || subsequence(token, name) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Scores `command` against the lowercase `query`, or `None` if some word misses.
+fn score(command: &CommandInfo, query: &str) -> Option<u32> {
+    let name = command.name;
+    let description = command.description.to_lowercase();
+    let mut total = 0;
+    for token in query.split(|c: char| c.is_whitespace() || c == '_') {
+        if !token.is_empty() {
+            total += token_score(token, name, &description)?;
+        }
+    }
+    // Typing the command out exactly puts it first.
+    if query.split_whitespace().collect::<Vec<_>>().join("_") == name {
+        total += 20;
+    }
+    Some(total)
+}
+
+/// Registered commands matching `query`, best first; ties go to the shorter name.
+/// An empty query lists commands in registry order.
+pub fn search(query: &str, limit: usize) -> Vec<PaletteAction> {
+    let query = query.trim().to_lowercase();
+    let mut matches: Vec<(u32, &CommandInfo)> = commands::COMMANDS
+        .iter()
+        .filter(|command| runnable(command.name))
+        .filter_map(|command| Some((score(command, &query)?, command)))
+        .collect();
+    if !query.is_empty() {
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then(a.name.len().cmp(&b.name.len()))
+                .then(a.name.cmp(b.name))
+        });
+    }
+
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(score, command)| PaletteAction {
+            id: command.name,
+            title: title(command.name),
+            category: command.category,
+            risk: command.risk,
+            description: command.description,
+            score,
+        })
+        .collect()
+}
+
+/// Runs command `id` with `args` through the middleware. Overlay commands run on
+/// the main thread, which owns the overlay windows; anything else runs on the
+/// calling thread, which must not be the main thread.
+fn run(app: &AppHandle, id: String, args: Value) -> Result<Value, String> {
+    let command = commands::lookup(&id)
+        .filter(|command| runnable(command.name))
+        .ok_or_else(|| format!("Unknown action '{}'", id))?;
+    if !(args.is_null() || args.is_object()) {
+        return Err(format!("Arguments for '{}' must be an object", id));
+    }
+
+    let handle = app.clone();
+    let call = move || {
+        let manager = handle.state::<OverlayManager>();
+        dispatch::dispatch_as(&manager, middleware::PALETTE_ORIGIN, "palette", &id, &args)
+    };
+    if command.category != Category::Overlay {
+        return call();
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(call());
+    })
+    .map_err(|e| format!("Failed to schedule action: {}", e))?;
+    rx.recv()
+        .map_err(|_| "Action was dropped before it ran".to_string())?
+}
+
+// ==========================================
+// COMMAND PALETTE COMMANDS
+// ==========================================
+
+/// Ranked registry commands matching `query`, with their category, risk and
+/// description; at most `limit` (default 20).
+#[tauri::command]
+pub fn search_actions(query: String, limit: Option<usize>) -> Vec<PaletteAction> {
+    search(&query, limit.unwrap_or(DEFAULT_LIMIT))
+}
+
+/// Runs a palette action with `args` (an object of the command's parameters) and
+/// returns its result.
+#[tauri::command]
+pub async fn execute_action(
+    app: AppHandle,
+    id: String,
+    args: Option<Value>,
+) -> Result<Value, String> {
+    let args = args.unwrap_or(Value::Null);
+    tauri::async_runtime::spawn_blocking(move || run(&app, id, args))
+        .await
+        .map_err(|e| format!("Action failed to run: {}", e))?
+}
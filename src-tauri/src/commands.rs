@@ -0,0 +1,416 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use tauri::ipc::Invoke;
+
+// ==========================================
+// Command Registry
+// ==========================================
+
+// The single list of webview commands. Each entry declares what the command touches
+// and how risky it is; the same table builds the invoke handler, feeds the command
+// middleware and the permission UI, is served over the control server and renders
+// the command reference (`app-daemon commands`). Entries that list their parameters
+// can also be called in-process with those parameters as JSON; `AppHandle` stands for
+// the running app. The rest need the overlay manager or the caller, or take file
+// names rather than paths, and `dispatch` runs them itself.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// Synthesizes mouse, keyboard, clipboard or drag-and-drop input.
+    InputInjection,
+    /// Reads screen contents, window lists or display layout.
+    ScreenRead,
+    /// Draws on screen: overlays, HUDs, captions, covers.
+    Overlay,
+    /// App configuration, diagnostics and subsystems.
+    System,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Risk {
+    /// No side effects.
+    ReadOnly,
+    Low,
+    Medium,
+    High,
+}
+
+/// Calls a command with its parameters as a JSON object.
+pub type Call = fn(&Value) -> Result<Value, String>;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    pub category: Category,
+    pub risk: Risk,
+    pub description: &'static str,
+    #[serde(skip)]
+    pub call: Option<Call>,
+}
+
+macro_rules! last_segment {
+    ($last:ident) => {
+        stringify!($last)
+    };
+    ($first:ident :: $($rest:ident)::+) => {
+        last_segment!($($rest)::+)
+    };
+}
+
+/// A command's return value on its way to JSON. `reply` on `&Reply<_>` resolves to
+/// `ReplyResult` for a `Result`, failing with its error, and otherwise falls back
+/// to `ReplyValue` through another autoref.
+struct Reply<T>(T);
+
+trait ReplyResult {
+    fn reply(&self) -> Result<Value, String>;
+}
+
+impl<T: Serialize, E: Serialize> ReplyResult for Reply<Result<T, E>> {
+    fn reply(&self) -> Result<Value, String> {
+        match &self.0 {
+            Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+            // Errors are strings, or structured (e.g. a hotkey conflict) as JSON.
+            Err(error) => Err(match serde_json::to_value(error) {
+                Ok(Value::String(message)) => message,
+                Ok(value) => value.to_string(),
+                Err(e) => e.to_string(),
+            }),
+        }
+    }
+}
+
+trait ReplyValue {
+    fn reply(&self) -> Result<Value, String>;
+}
+
+impl<T: Serialize> ReplyValue for &Reply<T> {
+    fn reply(&self) -> Result<Value, String> {
+        serde_json::to_value(&self.0).map_err(|e| e.to_string())
+    }
+}
+
+/// Parameter `name` of an in-process call. A missing one reads as null, so
+/// `Option` parameters may be left out.
+fn arg<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, String> {
+    match params.get(name) {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| format!("Invalid parameter '{}': {}", name, e)),
+        None => {
+            serde_json::from_value(Value::Null).map_err(|_| format!("Missing parameter '{}'", name))
+        }
+    }
+}
+
+macro_rules! call_arg {
+    ($params:ident, AppHandle) => {
+        crate::dispatch::app()?
+    };
+    ($params:ident, $arg:ident) => {
+        arg($params, stringify!($arg))?
+    };
+}
+
+macro_rules! call {
+    ($($segment:ident)::+) => {
+        None
+    };
+    ($($segment:ident)::+ ($($arg:ident),*)) => {
+        Some(|params: &Value| -> Result<Value, String> {
+            // Unused by commands that take no JSON parameters.
+            let _ = params;
+            (&Reply($($segment)::+($(call_arg!(params, $arg)),*))).reply()
+        })
+    };
+}
+
+macro_rules! registry {
+    ($(
+        $(#[cfg($($cfg:tt)*)])?
+        $($segment:ident)::+ $(($($arg:ident),*))? => $category:ident, $risk:ident, $description:literal;
+    )*) => {
+        /// Every registered command, in registration order.
+        pub static COMMANDS: &[CommandInfo] = &[$(
+            $(#[cfg($($cfg)*)])?
+            CommandInfo {
+                name: last_segment!($($segment)::+),
+                category: Category::$category,
+                risk: Risk::$risk,
+                description: $description,
+                call: call!($($segment)::+ $(($($arg),*))?),
+            },
+        )*];
+
+        pub fn handler() -> impl Fn(Invoke) -> bool + Send + Sync + 'static {
+            tauri::generate_handler![$(
+                $(#[cfg($($cfg)*)])?
+                $($segment)::+
+            ),*]
+        }
+    };
+}
+
+registry! {
+    #[cfg(feature = "input-automation")]
+    crate::input::mouse_move(x, y, space, monitor) => InputInjection, High, "Moves the pointer to a point in a chosen coordinate space.";
+    #[cfg(feature = "input-automation")]
+    crate::input::mouse_move_to(target) => InputInjection, High, "Moves the pointer to a layout position.";
+    #[cfg(feature = "input-automation")]
+    crate::input::mouse_move_relative(dx, dy, compensate_acceleration) => InputInjection, High, "Moves the pointer by a delta.";
+    #[cfg(feature = "input-automation")]
+    crate::injection::run_input_job => InputInjection, High, "Plays a timed sequence of input events.";
+    #[cfg(feature = "input-automation")]
+    crate::injection::stop_input_jobs() => InputInjection, Low, "Stops every running input job.";
+    #[cfg(feature = "input-automation")]
+    crate::multi_pointer::run_multi_pointer_job => InputInjection, High, "Plays parallel pointer tracks as concurrent touch contacts (experimental).";
+    #[cfg(feature = "input-automation")]
+    crate::input::mouse_click(button, at) => InputInjection, High, "Clicks a mouse button.";
+    #[cfg(feature = "input-automation")]
+    crate::input::key_press(text) => InputInjection, High, "Presses a key.";
+    #[cfg(feature = "input-automation")]
+    crate::clipboard::paste_text(text, restore_clipboard) => InputInjection, High, "Pastes text through the clipboard.";
+    crate::create_privacy_overlay => Overlay, Low, "Shows the privacy overlay.";
+    crate::create_overlay_for_monitor => Overlay, Low, "Shows the privacy overlay on one monitor.";
+    crate::toggle_overlay_for_monitor => Overlay, Low, "Shows or removes the privacy overlay on one monitor.";
+    crate::create_privacy_region => Overlay, Low, "Shows the privacy overlay over a screen region.";
+    crate::window_overlays::attach_overlay_to_window => Overlay, Low, "Covers an application window with the privacy overlay and follows it.";
+    crate::preview_layout(AppHandle, layout, duration_ms) => Overlay, Low, "Outlines where a layout's overlays would go.";
+    crate::clear_layout_preview() => Overlay, Low, "Removes the layout preview.";
+    crate::destroy_privacy_overlay => Overlay, Low, "Removes the privacy overlays.";
+    crate::update_overlay => Overlay, Low, "Moves or resizes a privacy overlay.";
+    crate::update_overlay_text => Overlay, Low, "Changes the text on a privacy overlay.";
+    crate::set_overlay_progress => Overlay, Low, "Shows a progress bar on a privacy overlay.";
+    crate::set_overlay_interactive => Overlay, Low, "Switches a privacy overlay between clickable and click-through.";
+    crate::destroy_overlay => Overlay, Low, "Removes one privacy overlay.";
+    crate::list_overlays => Overlay, ReadOnly, "Lists privacy overlays.";
+    crate::crash::list_crash_reports() => System, ReadOnly, "Lists saved crash reports.";
+    crate::crash::get_crash_report(name) => System, ReadOnly, "Reads one crash report.";
+    #[cfg(feature = "crash-upload")]
+    crate::crash::set_crash_upload_consent(enabled) => System, Medium, "Records consent to upload crash reports.";
+    #[cfg(feature = "crash-upload")]
+    crate::crash::upload_crash_report(name, endpoint) => System, Medium, "Uploads a crash report.";
+    #[cfg(feature = "self-update")]
+    crate::updater::get_update_config() => System, ReadOnly, "Reads the update endpoint and release channel.";
+    #[cfg(feature = "self-update")]
+    crate::updater::set_update_config(config) => System, High, "Sets the release channel and whether updates download on their own.";
+    #[cfg(feature = "self-update")]
+    crate::updater::check_for_update => System, Medium, "Checks the update endpoint for a newer version and refreshes feature flags, which may start subsystems.";
+    #[cfg(feature = "self-update")]
+    crate::updater::download_update(AppHandle) => System, Medium, "Downloads and verifies the available update in the background.";
+    #[cfg(feature = "self-update")]
+    crate::updater::install_update(AppHandle) => System, High, "Installs the downloaded update and quits.";
+    #[cfg(feature = "self-update")]
+    crate::updater::get_feature_flags() => System, ReadOnly, "Reports the feature flags the update endpoint rolled out to this install.";
+    crate::watchdog::get_watchdog_status() => System, ReadOnly, "Reports subsystem health.";
+    crate::subsystems::set_subsystem_enabled(name, enabled) => System, Medium, "Starts or stops a background subsystem.";
+    crate::features::get_build_features() => System, ReadOnly, "Lists compiled-in features.";
+    crate::vsync::wait_for_vsync => System, ReadOnly, "Waits for display refreshes.";
+    #[cfg(feature = "input-automation")]
+    crate::anchors::save_anchor(app, name, point) => ScreenRead, Low, "Saves an image anchor from a screen region.";
+    #[cfg(feature = "input-automation")]
+    crate::anchors::list_anchors() => ScreenRead, ReadOnly, "Lists saved anchors.";
+    #[cfg(feature = "input-automation")]
+    crate::anchors::delete_anchor(app, name) => ScreenRead, Low, "Deletes an anchor.";
+    #[cfg(feature = "input-automation")]
+    crate::anchors::resolve_anchor(reference) => ScreenRead, ReadOnly, "Finds an anchor on screen.";
+    crate::hot_corners::get_hot_corners() => System, ReadOnly, "Reads hot corner actions.";
+    crate::hot_corners::set_hot_corner(corner, hot_corner) => System, Medium, "Assigns an action to a hot corner.";
+    crate::hot_corners::set_hot_corners_enabled(enabled) => System, Low, "Turns hot corners on or off.";
+    crate::quick_menu::get_quick_menu() => Overlay, ReadOnly, "Reads the quick menu's items and layout.";
+    crate::quick_menu::set_quick_menu(config) => Overlay, Medium, "Sets the quick menu's items and layout.";
+    crate::quick_menu::toggle_quick_menu(AppHandle) => Overlay, Low, "Opens or closes the quick menu.";
+    crate::hotkeys::get_hotkeys() => System, ReadOnly, "Reads global hotkey bindings.";
+    crate::hotkeys::set_hotkey(keys, action) => System, Medium, "Binds or unbinds an action to a global hotkey.";
+    crate::hotkeys::list_hotkeys() => System, ReadOnly, "Lists hotkey bindings, OS shortcuts and combinations other apps hold.";
+    crate::captions::show_caption(AppHandle, text, duration_ms, style, revision) => Overlay, Low, "Shows a caption banner.";
+    crate::captions::hide_caption() => Overlay, Low, "Hides the caption banner.";
+    crate::captions::get_caption() => Overlay, ReadOnly, "Reports the caption text and revision.";
+    crate::keystrokes::start_keystroke_visualizer(AppHandle, style) => Overlay, Medium, "Shows pressed keys in a HUD that screen recordings pick up.";
+    crate::keystrokes::stop_keystroke_visualizer() => Overlay, Low, "Hides the keystroke HUD.";
+    crate::keystrokes::get_keystroke_visualizer() => Overlay, ReadOnly, "Reports whether keystrokes are shown, and their style.";
+    crate::clicks::get_click_effects() => Overlay, ReadOnly, "Reports the themes and sounds used to mark clicks.";
+    crate::clicks::set_click_effects(effects) => Overlay, Low, "Sets the themes and sounds for physical and injected clicks.";
+    crate::clicks::preview_click_effect(x, y, injected) => Overlay, Low, "Shows one click effect so its settings can be tried.";
+    crate::laser::start_laser_pointer => Overlay, Low, "Shows a laser dot that a remote client can move.";
+    crate::laser::move_laser_pointer => Overlay, Low, "Moves the laser dot.";
+    crate::laser::stop_laser_pointer => Overlay, Low, "Hides the laser dot.";
+    crate::laser::get_laser_pointer() => Overlay, ReadOnly, "Reports the laser dot's owner and position.";
+    crate::annotate::start_annotating(AppHandle, monitor, pen) => Overlay, Medium, "Opens a window to draw on a monitor.";
+    crate::annotate::stop_annotating() => Overlay, Low, "Closes the drawing window.";
+    crate::annotate::clear_annotations() => Overlay, Low, "Removes every annotation stroke.";
+    crate::annotate::set_annotation_pen(pen) => Overlay, Low, "Changes the annotation pen.";
+    crate::annotate::get_annotation() => Overlay, ReadOnly, "Reports the drawing window state.";
+    crate::annotate::export_annotations => ScreenRead, Medium, "Saves the annotations to a file.";
+    crate::annotate::load_annotations => Overlay, Low, "Restores saved annotations.";
+    crate::presenter::start_presenter_console(AppHandle, monitor, style) => Overlay, Low, "Opens the presenter console on a monitor.";
+    crate::presenter::stop_presenter_console() => Overlay, Low, "Closes the presenter console.";
+    crate::presenter::update_presenter_console(notes, next_slide, revision) => Overlay, Low, "Updates presenter notes or the next slide.";
+    crate::presenter::reset_presenter_timer() => Overlay, Low, "Restarts the presenter clock.";
+    crate::presenter::get_presenter_status() => Overlay, ReadOnly, "Reports the presenter console state.";
+    crate::share_guard::get_share_guard() => Overlay, ReadOnly, "Reads the share guard settings.";
+    crate::share_guard::set_share_guard(config) => Overlay, Medium, "Configures which windows stay visible while sharing.";
+    crate::share_guard::set_capture_active(active, source) => Overlay, Medium, "Tells the share guard a capture is running, and whether it shares a display or a window.";
+    crate::share_guard::get_share_guard_status() => Overlay, ReadOnly, "Lists windows currently covered by the share guard.";
+    crate::auto_cover::list_auto_cover_rules() => Overlay, ReadOnly, "Lists the rules that cover application windows automatically.";
+    crate::auto_cover::add_auto_cover_rule(rule) => Overlay, Medium, "Covers an application's windows automatically whenever they appear.";
+    crate::auto_cover::remove_auto_cover_rule(name) => Overlay, Low, "Stops covering an application's windows automatically.";
+    crate::middleware::get_command_policy() => System, ReadOnly, "Reads command permissions, limits and dry-run mode.";
+    crate::middleware::set_command_policy(policy) => System, High, "Sets command permissions, limits and dry-run mode.";
+    crate::middleware::get_command_audit() => System, ReadOnly, "Reads the command audit log.";
+    crate::middleware::get_command_metrics() => System, ReadOnly, "Reads per-command call metrics.";
+    crate::sessions::open_session => System, Low, "Opens a session that owns the resources created in it.";
+    crate::sessions::end_session => System, Low, "Ends a session and releases its resources.";
+    crate::sessions::list_sessions() => System, ReadOnly, "Lists open sessions and their resources.";
+    crate::commands::list_commands() => System, ReadOnly, "Lists registered commands with their metadata.";
+    crate::command_palette::search_actions(query, limit) => System, ReadOnly, "Searches registered commands for the command palette, best match first.";
+    crate::command_palette::execute_action => System, High, "Runs a registered command from the command palette.";
+    crate::context_actions::suggest_actions() => ScreenRead, ReadOnly, "Suggests actions for the focused application, e.g. covering a browser.";
+    crate::context_actions::list_context_rules() => System, ReadOnly, "Lists the rules that suggest actions per application.";
+    crate::context_actions::add_context_rule(rule) => System, Low, "Adds or replaces a rule suggesting actions for an application.";
+    crate::context_actions::remove_context_rule(name) => System, Low, "Removes a context action rule.";
+    crate::events::get_event_policies() => System, ReadOnly, "Reads event timing policies.";
+    crate::events::set_event_policy(event, policy) => System, Low, "Sets an event timing policy.";
+    crate::events::get_event_queues() => System, ReadOnly, "Reads event queue policies.";
+    crate::events::set_event_queue(event, queue) => System, Low, "Sets an event queue policy.";
+    crate::events::ack_events(event, count) => System, Low, "Acknowledges delivered events.";
+    crate::events::get_event_metrics() => System, ReadOnly, "Reads per-event delivery metrics.";
+    crate::toasts::get_toast_guard() => Overlay, ReadOnly, "Reads the toast interceptor settings.";
+    crate::toasts::set_toast_guard(config) => Overlay, Medium, "Configures the toast interceptor.";
+    crate::toasts::get_suppressed_toasts() => Overlay, ReadOnly, "Lists suppressed notifications.";
+    crate::toasts::clear_suppressed_toasts() => Overlay, Low, "Clears the suppressed notification log.";
+    crate::speech::speak(text) => System, Low, "Speaks text aloud.";
+    crate::speech::get_announcements() => System, ReadOnly, "Reads spoken announcement settings.";
+    crate::speech::set_announcements(config) => System, Low, "Sets spoken announcement settings.";
+    crate::shake::get_shake_config() => System, ReadOnly, "Reads cursor shake settings.";
+    crate::shake::set_shake_config(config) => System, Medium, "Sets cursor shake settings and action.";
+    crate::spotlight::start_spotlight() => Overlay, Low, "Dims the screen except around the cursor.";
+    crate::spotlight::stop_spotlight() => Overlay, Low, "Turns the cursor spotlight off.";
+    crate::spotlight::get_spotlight_config() => Overlay, ReadOnly, "Reads cursor spotlight settings.";
+    crate::spotlight::set_spotlight_config(config) => Overlay, Low, "Sets the spotlight's radius, smoothing and dimming.";
+    #[cfg(feature = "voice-commands")]
+    crate::voice::get_voice_commands() => System, ReadOnly, "Lists voice command phrases.";
+    #[cfg(feature = "voice-commands")]
+    crate::voice::set_voice_command(phrase, action) => System, Medium, "Binds a spoken phrase to an action.";
+    crate::gestures::list_gestures() => System, ReadOnly, "Lists mouse gestures.";
+    crate::gestures::set_gesture(gesture) => System, Medium, "Binds a mouse gesture to an action.";
+    crate::gestures::delete_gesture(name) => System, Low, "Deletes a mouse gesture.";
+    crate::gestures::train_gesture(name, action, timeout_ms) => System, Medium, "Records a mouse gesture and binds it.";
+    crate::app_window::list_app_windows() => ScreenRead, ReadOnly, "Lists application windows.";
+    #[cfg(feature = "input-automation")]
+    crate::file_drop::drop_files_on_window(AppHandle, title, paths) => InputInjection, High, "Drops files onto a window.";
+    crate::app_window::wait_for_window(criteria, state, timeout_ms) => ScreenRead, ReadOnly, "Waits for a window to appear.";
+    crate::coords::list_monitors() => ScreenRead, ReadOnly, "Lists monitors.";
+    crate::capture::get_capture_backend() => ScreenRead, ReadOnly, "Reads the screenshot backend setting.";
+    crate::capture::set_capture_backend(backend) => ScreenRead, Low, "Picks the GPU or compatible screenshot backend.";
+    crate::affinity::get_display_affinity() => Overlay, ReadOnly, "Reports how overlays keep out of captures, and any fallback.";
+    crate::affinity::set_display_affinity(mode) => Overlay, Low, "Picks exclude-from-capture or monitor-only affinity for overlays.";
+    crate::affinity::protect_app_window(AppHandle, enabled) => Overlay, Low, "Keeps the app's own window out of screen shares.";
+    crate::affinity::exclude_window_from_capture(criteria) => Overlay, Medium, "Excludes one of the app's windows, found by title or process, from capture.";
+    crate::affinity::include_window_in_capture(criteria) => Overlay, Low, "Lets a window excluded from capture back in.";
+    crate::coords::get_virtual_screen() => ScreenRead, ReadOnly, "Reads the virtual desktop bounds.";
+    crate::coords::resolve_layout_bounds(bounds) => ScreenRead, ReadOnly, "Resolves layout bounds to pixels.";
+    crate::coords::resolve_layout_position(position) => ScreenRead, ReadOnly, "Resolves a layout position to pixels.";
+    crate::remote_session::get_session_info() => System, ReadOnly, "Reports the session and remote connections.";
+    crate::remote_session::get_session_policy() => System, ReadOnly, "Reads the remote session policy.";
+    crate::remote_session::set_session_policy(policy) => System, Medium, "Sets the remote session policy.";
+    crate::visual::capture_region_golden(name, bounds) => ScreenRead, Medium, "Saves a screen region as a golden image.";
+    crate::visual::assert_region_matches(name, threshold, bounds, tolerance) => ScreenRead, ReadOnly, "Compares a screen region with its golden image.";
+    crate::visual::list_region_goldens() => ScreenRead, ReadOnly, "Lists golden images.";
+    crate::visual::delete_region_golden(name) => ScreenRead, Low, "Deletes a golden image.";
+    crate::visual::wait_for_region_change(bounds, sensitivity, timeout_ms, until) => ScreenRead, ReadOnly, "Waits for a screen region to change or settle.";
+    crate::region_hash::hash_region(bounds) => ScreenRead, ReadOnly, "Computes a perceptual hash of a screen region.";
+    crate::region_hash::watch_region_hash(AppHandle, bounds) => ScreenRead, Low, "Emits events when a region's hash changes.";
+    crate::region_hash::unwatch_region_hash(id) => ScreenRead, ReadOnly, "Stops a region hash watch.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::save_script(script) => System, Medium, "Saves an automation script.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::get_script(id) => System, ReadOnly, "Reads an automation script.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::list_scripts() => System, ReadOnly, "Lists automation scripts.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::delete_script(id) => System, Low, "Deletes an automation script.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::export_script => System, Medium, "Exports a script to a file.";
+    #[cfg(feature = "scripting")]
+    crate::scripts::import_script => System, Medium, "Imports a script from a file.";
+    #[cfg(feature = "scripting")]
+    crate::runner::run_script => InputInjection, High, "Runs a saved script natively, retrying steps per their policies.";
+    #[cfg(feature = "scripting")]
+    crate::runner::stop_script_runs() => InputInjection, Low, "Stops every running script.";
+    #[cfg(feature = "scripting")]
+    crate::queue::submit_job(request) => InputInjection, High, "Queues a script or macro to run in turn.";
+    #[cfg(feature = "scripting")]
+    crate::queue::list_job_queue() => System, ReadOnly, "Lists queued jobs.";
+    #[cfg(feature = "scripting")]
+    crate::queue::cancel_queued_job(id) => InputInjection, Low, "Cancels a queued job.";
+    #[cfg(feature = "scripting")]
+    crate::recorder::start_recording() => ScreenRead, High, "Starts recording input into a script.";
+    #[cfg(feature = "scripting")]
+    crate::recorder::stop_recording(id, name) => ScreenRead, Low, "Stops recording and saves the script.";
+    #[cfg(feature = "scripting")]
+    crate::recorder::get_recording_status() => ScreenRead, ReadOnly, "Reports the recorder state.";
+    crate::locale::get_format_locale() => System, ReadOnly, "Reports the locale used for native-rendered text.";
+    crate::locale::set_format_locale(tag) => System, Low, "Overrides the locale used for native-rendered text.";
+    crate::locale::format_text(request) => System, ReadOnly, "Formats a number, duration or shortcut like native HUD text.";
+    crate::retention::get_retention_policy() => System, ReadOnly, "Reads how long each kind of stored data is kept.";
+    crate::retention::set_retention_policy(policy) => System, Medium, "Sets how long each kind of stored data is kept.";
+    crate::retention::purge_data(categories) => System, High, "Deletes stored data of the given categories now.";
+    crate::export::export_events => System, Medium, "Writes audit and usage events to a CSV or JSON Lines file.";
+    crate::bug_report::create_bug_report() => System, Medium, "Packs diagnostics, recent logs, redacted config and crash breadcrumbs into a zip to attach to an issue.";
+    crate::audit_log::get_privacy_report(range) => System, ReadOnly, "Summarizes captures, overlays, sensitive apps focused while sharing and protection failures over a time range.";
+    crate::secrets::get_storage_encryption() => System, ReadOnly, "Reports whether stored data, exports and reports are encrypted, and where the key is kept.";
+    crate::secrets::set_storage_encryption(enabled) => System, Medium, "Encrypts newly stored data, exports and reports with a key in the OS secret store, or stops.";
+    crate::secrets::open_sealed_file(path, destination) => System, Medium, "Writes a decrypted copy of an encrypted export or bug report.";
+    crate::palette::get_palette() => System, ReadOnly, "Reports the status color palette.";
+    crate::palette::set_palette(config) => System, Low, "Sets the status color preset and overrides.";
+    crate::motion::get_motion_settings() => System, ReadOnly, "Reports whether the OS asks for reduced motion and whether the app follows it.";
+    crate::motion::set_reduce_motion(enabled) => System, Low, "Reduces or restores motion regardless of the OS setting, or follows it again.";
+    crate::get_app_state => System, ReadOnly, "Reports overlay and subsystem state.";
+}
+
+pub fn lookup(name: &str) -> Option<&'static CommandInfo> {
+    COMMANDS.iter().find(|command| command.name == name)
+}
+
+/// Runs command `name` in-process if the registry knows how to call it.
+pub fn call(name: &str, params: &Value) -> Option<Result<Value, String>> {
+    let call = lookup(name)?.call?;
+    Some(call(params))
+}
+
+/// Markdown reference of every registered command, grouped by category.
+pub fn markdown() -> String {
+    let mut categories: Vec<Category> = COMMANDS.iter().map(|c| c.category).collect();
+    categories.sort();
+    categories.dedup();
+
+    let mut out = String::from("# Commands\n");
+    for category in categories {
+        let title = serde_json::to_value(category)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "\n## {}\n\n| Command | Risk | Description |\n|---|---|---|\n",
+            title
+        ));
+        for command in COMMANDS.iter().filter(|c| c.category == category) {
+            out.push_str(&format!(
+                "| `{}` | {:?} | {} |\n",
+                command.name, command.risk, command.description
+            ));
+        }
+    }
+    out
+}
+
+// ==========================================
+// COMMAND REGISTRY COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn list_commands() -> Vec<CommandInfo> {
+    COMMANDS.to_vec()
+}
@@ -0,0 +1,254 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    app_window::{self, WindowCriteria, WindowInfo},
+    commands,
+};
+
+// ==========================================
+// Context-Sensitive Actions
+// ==========================================
+
+// Actions worth offering for whatever the presenter is working in: "Cover this
+// window" over a browser, "Enable typing privacy" (no keystroke HUD) over a terminal,
+// where passwords aren't typed into password fields. Rules map the focused window's
+// process, and optionally its title, to registered commands; the quick menu lists
+// their suggestions first and the command palette shows them above its results.
+// While one of the app's own windows has focus, e.g. the palette, suggestions are
+// for the window in front of the others.
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContextAction {
+    pub label: String,
+    /// A registered command.
+    pub id: String,
+    /// Arguments for the command. Strings "{pid}" and "{window}" become the window's
+    /// pid and id; "{process}" and "{title}" are replaced within any string.
+    #[serde(default)]
+    pub args: Value,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ContextRule {
+    /// Identifies the rule; adding a rule with the same name replaces it.
+    pub name: String,
+    /// Executable names, as in window criteria; the rule applies to any of them.
+    pub processes: Vec<String>,
+    /// Case-insensitive substring the title must contain as well.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub actions: Vec<ContextAction>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SuggestedAction {
+    pub label: String,
+    pub id: String,
+    /// Filled in for the window; pass them to `execute_action` as they are.
+    pub args: Value,
+    /// The rule that suggested it.
+    pub rule: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ContextSuggestions {
+    pub window: Option<WindowInfo>,
+    pub actions: Vec<SuggestedAction>,
+}
+
+const BROWSERS: &[&str] = &[
+    "chrome",
+    "Google Chrome",
+    "firefox",
+    "msedge",
+    "Microsoft Edge",
+    "Safari",
+    "brave",
+    "Brave Browser",
+    "opera",
+    "Arc",
+];
+const TERMINALS: &[&str] = &[
+    "WindowsTerminal",
+    "cmd",
+    "powershell",
+    "pwsh",
+    "Terminal",
+    "iTerm2",
+    "gnome-terminal-server",
+    "konsole",
+    "alacritty",
+    "kitty",
+    "wezterm-gui",
+];
+
+fn default_rules() -> Vec<ContextRule> {
+    let processes = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+    vec![
+        ContextRule {
+            name: "browser".to_string(),
+            processes: processes(BROWSERS),
+            title: None,
+            actions: vec![ContextAction {
+                label: "Cover this window".to_string(),
+                id: "attach_overlay_to_window".to_string(),
+                args: json!({ "criteria": { "pid": "{pid}", "title": "{title}" } }),
+            }],
+        },
+        ContextRule {
+            name: "terminal".to_string(),
+            processes: processes(TERMINALS),
+            title: None,
+            actions: vec![ContextAction {
+                label: "Enable typing privacy".to_string(),
+                id: "stop_keystroke_visualizer".to_string(),
+                args: Value::Null,
+            }],
+        },
+    ]
+}
+
+static RULES: Lazy<Mutex<Vec<ContextRule>>> = Lazy::new(|| Mutex::new(default_rules()));
+
+impl ContextRule {
+    fn matches(&self, window: &WindowInfo) -> bool {
+        self.processes.iter().any(|process| {
+            WindowCriteria {
+                title: self.title.clone(),
+                process: Some(process.clone()),
+                pid: None,
+            }
+            .matches(window)
+        })
+    }
+}
+
+/// The window suggestions are for: the focused one, unless it's the app's own.
+fn target() -> Option<WindowInfo> {
+    let own_pid = std::process::id();
+    let windows: Vec<WindowInfo> = app_window::list_windows()
+        .into_iter()
+        .filter(|w| w.pid != own_pid)
+        .collect();
+    // Lists are ordered front to back.
+    windows
+        .iter()
+        .find(|w| w.focused)
+        .or_else(|| windows.iter().find(|w| w.visible))
+        .cloned()
+}
+
+/// `args` with the placeholders replaced by what they stand for in `window`.
+fn fill(args: &Value, window: &WindowInfo) -> Value {
+    match args {
+        Value::String(s) if s == "{pid}" => json!(window.pid),
+        Value::String(s) if s == "{window}" => json!(window.id),
+        Value::String(s) => Value::String(
+            s.replace("{process}", &window.process)
+                .replace("{title}", &window.title),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| fill(v, window)).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), fill(v, window)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Suggestions for the focused window, in rule order.
+pub fn suggestions() -> ContextSuggestions {
+    let Some(window) = target() else {
+        return ContextSuggestions {
+            window: None,
+            actions: Vec::new(),
+        };
+    };
+    let actions = RULES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|rule| rule.matches(&window))
+        .flat_map(|rule| {
+            rule.actions.iter().map(|action| SuggestedAction {
+                label: action.label.clone(),
+                id: action.id.clone(),
+                args: fill(&action.args, &window),
+                rule: rule.name.clone(),
+            })
+        })
+        .collect();
+    ContextSuggestions {
+        window: Some(window),
+        actions,
+    }
+}
+
+fn validate(rule: &ContextRule) -> Result<(), String> {
+    if rule.name.trim().is_empty() {
+        return Err("Context rules need a name".to_string());
+    }
+    if rule.processes.is_empty() {
+        return Err(format!("Context rule '{}' needs a process", rule.name));
+    }
+    for action in &rule.actions {
+        if action.label.trim().is_empty() {
+            return Err(format!(
+                "Actions of context rule '{}' need a label",
+                rule.name
+            ));
+        }
+        if commands::lookup(&action.id).is_none() {
+            return Err(format!("Unknown command '{}'", action.id));
+        }
+        if !(action.args.is_null() || action.args.is_object()) {
+            return Err(format!("Arguments for '{}' must be an object", action.id));
+        }
+    }
+    Ok(())
+}
+
+// ==========================================
+// CONTEXT ACTION COMMANDS
+// ==========================================
+
+/// Actions suggested for the focused window, and the window they're for.
+#[tauri::command]
+pub fn suggest_actions() -> ContextSuggestions {
+    suggestions()
+}
+
+#[tauri::command]
+pub fn list_context_rules() -> Vec<ContextRule> {
+    RULES.lock().unwrap().clone()
+}
+
+/// Adds `rule`, replacing the rule with the same name, e.g. the built-in "browser"
+/// or "terminal" rules.
+#[tauri::command]
+pub fn add_context_rule(rule: ContextRule) -> Result<(), String> {
+    validate(&rule)?;
+    let mut rules = RULES.lock().unwrap();
+    match rules.iter_mut().find(|r| r.name == rule.name) {
+        Some(existing) => *existing = rule,
+        None => rules.push(rule),
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_context_rule(name: String) -> Result<(), String> {
+    let mut rules = RULES.lock().unwrap();
+    let before = rules.len();
+    rules.retain(|r| r.name != name);
+    if rules.len() == before {
+        return Err(format!("Context rule '{}' not found", name));
+    }
+    Ok(())
+}
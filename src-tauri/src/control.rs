@@ -0,0 +1,375 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{dispatch, middleware, sessions, OverlayManager};
+
+// ==========================================
+// Control Server
+// ==========================================
+
+// Line-delimited JSON over loopback TCP. Any local process can connect, so every
+// request carries a token, a random secret kept in a file only the user who runs
+// the server can read (on Windows, the user's profile is already closed to other
+// users). The first accepted token fixes whom a connection speaks for. Requests
+// run registered commands through `dispatch`.
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:47800";
+/// Name of the token file in the data directory.
+pub const TOKEN_FILE: &str = "control-token";
+const DISCONNECTED: &str = "client_disconnected";
+/// Longest request line read, in bytes; a longer one drops the connection, so an
+/// unauthenticated client can't make the server buffer without bound.
+const MAX_REQUEST: u64 = 1 << 20;
+/// Longest response line `call` reads, in bytes.
+const MAX_RESPONSE: u64 = 64 << 20;
+
+/// Maps a request's token to whom the connection speaks for, or refuses it.
+pub type Authorize = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn from_result(id: Value, result: Result<Value, String>) -> Self {
+        match result {
+            Ok(value) => Self {
+                id,
+                result: Some(value),
+                error: None,
+            },
+            Err(e) => Self {
+                id,
+                result: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// Request, the connection it came from (`control:<principal>@<peer address>`)
+/// and its reply slot.
+type Job = (Request, String, mpsc::Sender<Response>);
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Reads the token in `path`, creating it if there is none yet.
+pub fn load_token(path: &Path) -> Result<String, String> {
+    if path.exists() {
+        read_private(path)
+    } else {
+        let token = new_token()?;
+        write_private(path, &token)?;
+        Ok(token)
+    }
+}
+
+/// Reads `path`, refusing a file other users could have read.
+pub fn read_private(path: &Path) -> Result<String, String> {
+    let token = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path)
+            .map_err(|e| e.to_string())?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            return Err(format!(
+                "{} must be readable by its owner only",
+                path.display()
+            ));
+        }
+    }
+    Ok(token.trim().to_string())
+}
+
+/// A fresh random token.
+pub fn new_token() -> Result<String, String> {
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret).map_err(|e| format!("No randomness for a token: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(secret))
+}
+
+/// Replaces `path` with `contents`, readable by the current user only.
+pub fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    // A new file, so an existing one can't keep its looser permissions.
+    let _ = fs::remove_file(path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Compares without returning early, so timing doesn't tell how much matched.
+pub fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Accepts `token`, for the user who owns it.
+pub fn token_auth(token: String) -> Authorize {
+    Arc::new(move |given| same_token(given, &token).then(|| "owner".to_string()))
+}
+
+/// Asks a running `run_daemon` loop to return.
+pub fn request_shutdown() {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+}
+
+/// Executes one control request from `client` against the native subsystems.
+pub fn dispatch(
+    manager: &OverlayManager,
+    client: &str,
+    method: &str,
+    params: &Value,
+) -> Result<Value, String> {
+    middleware::run(method, params, || {
+        let _session = sessions::enter(client, params)?;
+        match method {
+            "ping" => Ok(json!("pong")),
+            // Sent by the connection thread itself once the client hangs up.
+            DISCONNECTED => {
+                sessions::end_owned_by(manager, client);
+                Ok(Value::Null)
+            }
+            _ => dispatch::execute(manager, client, method, params),
+        }
+    })
+}
+
+pub fn bind(addr: &str) -> Result<TcpListener, String> {
+    TcpListener::bind(addr).map_err(|e| format!("Failed to bind control server: {}", e))
+}
+
+/// Accepts line-delimited JSON requests on `listener` and forwards those
+/// `authorize` lets through to `jobs`.
+pub fn spawn_server(
+    listener: TcpListener,
+    authorize: Authorize,
+    jobs: mpsc::Sender<Job>,
+) -> Result<(), String> {
+    thread::Builder::new()
+        .name("control-server".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let jobs = jobs.clone();
+                let authorize = authorize.clone();
+                let _ = thread::Builder::new()
+                    .name("control-client".to_string())
+                    .spawn(move || handle_client(stream, &*authorize, jobs));
+            }
+        })
+        .map_err(|e| format!("Failed to spawn control server: {}", e))?;
+
+    Ok(())
+}
+
+fn handle_client(
+    stream: TcpStream,
+    authorize: &(dyn Fn(&str) -> Option<String> + Send + Sync),
+    jobs: mpsc::Sender<Job>,
+) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let peer = match stream.peer_addr() {
+        Ok(addr) => addr.to_string(),
+        Err(_) => "unknown".to_string(),
+    };
+    let mut client: Option<String> = None;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+
+    loop {
+        match read_line(&mut reader, &mut line, MAX_REQUEST) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => {
+                log::warn!("control client {} dropped: {}", peer, e);
+                break;
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let id = request.id.clone();
+                let principal = authorize(request.token.as_deref().unwrap_or_default())
+                    .map(|principal| format!("control:{}@{}", principal, peer));
+                match principal {
+                    Some(principal) if client.get_or_insert(principal.clone()) == &principal => {
+                        let (reply_tx, reply_rx) = mpsc::channel();
+                        if jobs.send((request, principal, reply_tx)).is_err() {
+                            break;
+                        }
+                        reply_rx.recv().unwrap_or_else(|_| {
+                            Response::from_result(id, Err("Control loop stopped".to_string()))
+                        })
+                    }
+                    _ => Response::from_result(id, Err("Unauthorized".to_string())),
+                }
+            }
+            Err(e) => Response::from_result(Value::Null, Err(format!("Invalid request: {}", e))),
+        };
+
+        let Ok(json) = serde_json::to_string(&response) else {
+            break;
+        };
+        if writeln!(writer, "{}", json).is_err() {
+            break;
+        }
+    }
+
+    // Lets the control loop release the sessions this client left open.
+    let Some(client) = client else {
+        return;
+    };
+    let (reply_tx, _) = mpsc::channel();
+    let request = Request {
+        id: Value::Null,
+        method: DISCONNECTED.to_string(),
+        params: Value::Null,
+        token: None,
+    };
+    let _ = jobs.send((request, client, reply_tx));
+}
+
+/// Reads the next line into `line`, at most `limit` bytes of it, or returns `false`
+/// at the end of the stream.
+fn read_line(reader: &mut impl BufRead, line: &mut String, limit: u64) -> Result<bool, String> {
+    line.clear();
+    let read = reader
+        .by_ref()
+        .take(limit + 1)
+        .read_line(line)
+        .map_err(|e| e.to_string())?;
+    if read as u64 > limit {
+        return Err(format!("Line is longer than {} bytes", limit));
+    }
+    Ok(read > 0)
+}
+
+/// Runs the control loop on the calling thread, which must be the main thread:
+/// overlay windows are created here, never on the socket threads.
+pub fn run_daemon<F>(listener: TcpListener, authorize: Authorize, handler: F) -> Result<(), String>
+where
+    F: Fn(&str, &str, &Value) -> Result<Value, String>,
+{
+    let addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let (jobs_tx, jobs_rx) = mpsc::channel::<Job>();
+    spawn_server(listener, authorize, jobs_tx)?;
+    log::info!("control server listening on {}", addr);
+
+    while !SHUTDOWN.load(Ordering::SeqCst) {
+        match jobs_rx.recv_timeout(Duration::from_millis(16)) {
+            Ok((request, client, reply)) => {
+                let result = handler(&client, &request.method, &request.params);
+                let _ = reply.send(Response::from_result(request.id, result));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        #[cfg(all(target_os = "macos", feature = "headless"))]
+        crate::macos_overlay::pump_events();
+    }
+
+    Ok(())
+}
+
+/// Sends a single request with `token` to a running daemon and returns its result.
+pub fn call(addr: &str, token: &str, method: &str, params: Value) -> Result<Value, String> {
+    let mut stream =
+        TcpStream::connect(addr).map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+
+    let request = Request {
+        id: json!(1),
+        method: method.to_string(),
+        params,
+        token: Some(token.to_string()),
+    };
+    let json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", json).map_err(|e| e.to_string())?;
+
+    let mut line = String::new();
+    if !read_line(&mut BufReader::new(stream), &mut line, MAX_RESPONSE)? {
+        return Err("The daemon closed the connection".to_string());
+    }
+
+    let response: Response = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+    match response.error {
+        Some(e) => Err(e),
+        None => Ok(response.result.unwrap_or(Value::Null)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn lines_over_the_limit_are_refused() {
+        let mut reader = Cursor::new(b"{}\n0123456789\n".to_vec());
+        let mut line = String::new();
+        assert_eq!(read_line(&mut reader, &mut line, 8), Ok(true));
+        assert_eq!(line, "{}\n");
+        assert!(read_line(&mut reader, &mut line, 8).is_err());
+
+        let mut reader = Cursor::new(b"last".to_vec());
+        assert_eq!(read_line(&mut reader, &mut line, 8), Ok(true));
+        assert_eq!(read_line(&mut reader, &mut line, 8), Ok(false));
+    }
+}
@@ -0,0 +1,786 @@
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Coordinate Spaces
+// ==========================================
+
+// "Native" coordinates are whatever the OS injection and window APIs take: physical pixels on
+// the Windows/X11 virtual desktop (which may start at negative x/y), points with a top-left
+// origin on macOS. Every subsystem converts into native space through this module.
+
+#[cfg(target_os = "macos")]
+const NATIVE_IS_PIXELS: bool = false;
+#[cfg(not(target_os = "macos"))]
+const NATIVE_IS_PIXELS: bool = true;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoordinateSpace {
+    /// Device pixels on the virtual desktop.
+    #[default]
+    Physical,
+    /// Device-independent pixels (physical divided by the monitor's scale factor).
+    Logical,
+    /// Physical pixel offset from a monitor's top-left corner.
+    MonitorRelative,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Monitor {
+    pub index: usize,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub scale_factor: f64,
+    pub primary: bool,
+}
+
+impl Monitor {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < (self.x + self.width) as f64
+            && y < (self.y + self.height) as f64
+    }
+
+    /// Size in physical pixels regardless of the platform's native unit.
+    pub fn physical_size(&self) -> (i32, i32) {
+        if NATIVE_IS_PIXELS {
+            (self.width, self.height)
+        } else {
+            (
+                (self.width as f64 * self.scale_factor).round() as i32,
+                (self.height as f64 * self.scale_factor).round() as i32,
+            )
+        }
+    }
+
+    /// A length of `dip` device-independent pixels on this monitor, in native units.
+    pub fn native_length(&self, dip: f64) -> f64 {
+        if NATIVE_IS_PIXELS {
+            dip * self.scale_factor
+        } else {
+            dip
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Bounding box of all monitors in native coordinates.
+#[cfg(target_os = "windows")]
+pub fn virtual_screen() -> Rect {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+        SM_YVIRTUALSCREEN,
+    };
+
+    unsafe {
+        Rect {
+            x: GetSystemMetrics(SM_XVIRTUALSCREEN),
+            y: GetSystemMetrics(SM_YVIRTUALSCREEN),
+            width: GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            height: GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn virtual_screen() -> Rect {
+    bounding_rect(&monitors())
+}
+
+/// Maps a native point onto the 0..=65535 range SendInput expects with
+/// MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK. Handles monitors left of/above the primary.
+#[cfg(target_os = "windows")]
+pub fn normalize_absolute(x: i32, y: i32) -> (i32, i32) {
+    let vs = virtual_screen();
+    let scale = |value: i32, origin: i32, extent: i32| -> i32 {
+        if extent <= 1 {
+            return 0;
+        }
+        let offset = (value - origin).clamp(0, extent - 1) as i64;
+        ((offset * 65535) / (extent as i64 - 1)) as i32
+    };
+
+    (scale(x, vs.x, vs.width), scale(y, vs.y, vs.height))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn bounding_rect(monitors: &[Monitor]) -> Rect {
+    if monitors.is_empty() {
+        return Rect {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+    }
+
+    let left = monitors.iter().map(|m| m.x).min().unwrap_or(0);
+    let top = monitors.iter().map(|m| m.y).min().unwrap_or(0);
+    let right = monitors.iter().map(|m| m.x + m.width).max().unwrap_or(0);
+    let bottom = monitors.iter().map(|m| m.y + m.height).max().unwrap_or(0);
+
+    Rect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    }
+}
+
+/// The space whose values are already native coordinates.
+#[cfg(feature = "scripting")]
+pub fn native_space() -> CoordinateSpace {
+    if NATIVE_IS_PIXELS {
+        CoordinateSpace::Physical
+    } else {
+        CoordinateSpace::Logical
+    }
+}
+
+/// Converts a point from `space` into native coordinates.
+pub fn to_native(
+    space: CoordinateSpace,
+    x: f64,
+    y: f64,
+    monitor_index: Option<usize>,
+) -> Result<(i32, i32), String> {
+    to_native_in(&monitors(), space, x, y, monitor_index)
+}
+
+/// `to_native` against the given monitors.
+fn to_native_in(
+    monitors: &[Monitor],
+    space: CoordinateSpace,
+    x: f64,
+    y: f64,
+    monitor_index: Option<usize>,
+) -> Result<(i32, i32), String> {
+    let (nx, ny) = match space {
+        CoordinateSpace::Physical if NATIVE_IS_PIXELS => (x, y),
+        CoordinateSpace::Logical if !NATIVE_IS_PIXELS => (x, y),
+        CoordinateSpace::Physical => {
+            // Native is points: find the monitor whose pixel extent contains the point.
+            let m = monitors
+                .iter()
+                .find(|m| m.contains(x / m.scale_factor, y / m.scale_factor))
+                .ok_or_else(|| format!("Point ({}, {}) is not on any monitor", x, y))?;
+            (x / m.scale_factor, y / m.scale_factor)
+        }
+        CoordinateSpace::Logical => {
+            let m = monitors
+                .iter()
+                .find(|m| m.contains(x * m.scale_factor, y * m.scale_factor))
+                .ok_or_else(|| format!("Point ({}, {}) is not on any monitor", x, y))?;
+            (x * m.scale_factor, y * m.scale_factor)
+        }
+        CoordinateSpace::MonitorRelative => {
+            let index = monitor_index.ok_or("monitor-relative coordinates require a monitor")?;
+            let m = monitors
+                .get(index)
+                .ok_or_else(|| format!("Monitor index {} out of range", index))?;
+            let (width, height) = m.physical_size();
+            if x < 0.0 || y < 0.0 || x >= width as f64 || y >= height as f64 {
+                return Err(format!(
+                    "Point ({}, {}) is outside monitor {} ({}x{})",
+                    x, y, index, width, height
+                ));
+            }
+
+            if NATIVE_IS_PIXELS {
+                (m.x as f64 + x, m.y as f64 + y)
+            } else {
+                (
+                    m.x as f64 + x / m.scale_factor,
+                    m.y as f64 + y / m.scale_factor,
+                )
+            }
+        }
+    };
+
+    Ok((nx.round() as i32, ny.round() as i32))
+}
+
+// ==========================================
+// MONITOR ENUMERATION
+// ==========================================
+
+#[cfg(target_os = "windows")]
+pub fn monitors() -> Vec<Monitor> {
+    use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+    };
+    use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    const MONITORINFOF_PRIMARY: u32 = 1;
+
+    unsafe extern "system" fn callback(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<Monitor>);
+
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+            return BOOL(1);
+        }
+
+        let (mut dpi_x, mut dpi_y) = (96u32, 96u32);
+        let _ = GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y);
+
+        let r = info.rcMonitor;
+        monitors.push(Monitor {
+            index: monitors.len(),
+            x: r.left,
+            y: r.top,
+            width: r.right - r.left,
+            height: r.bottom - r.top,
+            scale_factor: dpi_x as f64 / 96.0,
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+        });
+        BOOL(1)
+    }
+
+    let mut monitors: Vec<Monitor> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(callback),
+            LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+        );
+    }
+    monitors
+}
+
+#[cfg(target_os = "macos")]
+pub fn monitors() -> Vec<Monitor> {
+    use cocoa::base::id;
+    use cocoa::foundation::NSRect;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let mut monitors = Vec::new();
+    unsafe {
+        let screens: id = msg_send![class!(NSScreen), screens];
+        let count: usize = msg_send![screens, count];
+
+        // Cocoa frames are bottom-left based, relative to the primary (first) screen.
+        let mut primary_height = 0.0;
+        for i in 0..count {
+            let screen: id = msg_send![screens, objectAtIndex: i];
+            let frame: NSRect = msg_send![screen, frame];
+            let scale: f64 = msg_send![screen, backingScaleFactor];
+            if i == 0 {
+                primary_height = frame.size.height;
+            }
+
+            monitors.push(Monitor {
+                index: i,
+                x: frame.origin.x.round() as i32,
+                y: (primary_height - frame.origin.y - frame.size.height).round() as i32,
+                width: frame.size.width.round() as i32,
+                height: frame.size.height.round() as i32,
+                scale_factor: scale,
+                primary: i == 0,
+            });
+        }
+    }
+    monitors
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn monitors() -> Vec<Monitor> {
+    use x11rb::connection::Connection;
+
+    let Ok((conn, screen_num)) = x11rb::connect(None) else {
+        return Vec::new();
+    };
+    let screen = &conn.setup().roots[screen_num];
+
+    vec![Monitor {
+        index: 0,
+        x: 0,
+        y: 0,
+        width: screen.width_in_pixels as i32,
+        height: screen.height_in_pixels as i32,
+        scale_factor: 1.0,
+        primary: true,
+    }]
+}
+
+#[tauri::command]
+pub fn list_monitors() -> Vec<Monitor> {
+    monitors()
+}
+
+#[tauri::command]
+pub fn get_virtual_screen() -> Rect {
+    virtual_screen()
+}
+
+// ==========================================
+// RELATIVE & ANCHORED POSITIONING
+// ==========================================
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Fractions of the monitor (and of the placed rect) the anchor sits at.
+    pub(crate) fn fractions(self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+
+    /// Margins push inward from the anchored edge; on a centered axis they shift right/down.
+    fn margin_direction(fraction: f64) -> f64 {
+        if fraction >= 1.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A point resolved against live monitor geometry. Lengths are physical pixels.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Position {
+    Absolute {
+        x: f64,
+        y: f64,
+        #[serde(default)]
+        space: CoordinateSpace,
+        monitor: Option<usize>,
+    },
+    Percent {
+        x: f64,
+        y: f64,
+        monitor: Option<usize>,
+    },
+    Anchored {
+        anchor: Anchor,
+        #[serde(default)]
+        margin_x: f64,
+        #[serde(default)]
+        margin_y: f64,
+        monitor: Option<usize>,
+    },
+}
+
+/// A rectangle resolved against live monitor geometry. Lengths are physical pixels.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Bounds {
+    Absolute {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        #[serde(default)]
+        space: CoordinateSpace,
+        monitor: Option<usize>,
+    },
+    Percent {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+        monitor: Option<usize>,
+    },
+    Anchored {
+        anchor: Anchor,
+        width: f64,
+        height: f64,
+        #[serde(default)]
+        margin_x: f64,
+        #[serde(default)]
+        margin_y: f64,
+        monitor: Option<usize>,
+    },
+    Monitor {
+        monitor: Option<usize>,
+    },
+}
+
+fn monitor_or_primary(monitors: &[Monitor], index: Option<usize>) -> Result<&Monitor, String> {
+    match index {
+        Some(i) => monitors.get(i).ok_or_else(|| {
+            format!(
+                "Monitor index {} out of range (found {})",
+                i,
+                monitors.len()
+            )
+        }),
+        None => monitors
+            .iter()
+            .find(|m| m.primary)
+            .or(monitors.first())
+            .ok_or_else(|| "No monitors found".to_string()),
+    }
+}
+
+/// Physical pixels to native units on `m`.
+fn to_native_length(m: &Monitor, pixels: f64) -> f64 {
+    if NATIVE_IS_PIXELS {
+        pixels
+    } else {
+        pixels / m.scale_factor
+    }
+}
+
+fn check_percent(value: f64, name: &str) -> Result<f64, String> {
+    if (0.0..=100.0).contains(&value) {
+        Ok(value / 100.0)
+    } else {
+        Err(format!(
+            "{} must be between 0 and 100 (got {})",
+            name, value
+        ))
+    }
+}
+
+pub fn resolve_position(position: &Position) -> Result<(i32, i32), String> {
+    resolve_position_in(&monitors(), position)
+}
+
+/// `resolve_position` against the given monitors.
+fn resolve_position_in(monitors: &[Monitor], position: &Position) -> Result<(i32, i32), String> {
+    match *position {
+        Position::Absolute {
+            x,
+            y,
+            space,
+            monitor,
+        } => to_native_in(monitors, space, x, y, monitor),
+        Position::Percent { x, y, monitor } => {
+            let m = monitor_or_primary(monitors, monitor)?;
+            let fx = check_percent(x, "x")?;
+            let fy = check_percent(y, "y")?;
+            Ok((
+                (m.x as f64 + m.width as f64 * fx).round() as i32,
+                (m.y as f64 + m.height as f64 * fy).round() as i32,
+            ))
+        }
+        Position::Anchored {
+            anchor,
+            margin_x,
+            margin_y,
+            monitor,
+        } => {
+            let m = monitor_or_primary(monitors, monitor)?;
+            let (fx, fy) = anchor.fractions();
+            let x = m.x as f64
+                + m.width as f64 * fx
+                + Anchor::margin_direction(fx) * to_native_length(m, margin_x);
+            let y = m.y as f64
+                + m.height as f64 * fy
+                + Anchor::margin_direction(fy) * to_native_length(m, margin_y);
+
+            // Keep anchored points on the monitor; the right/bottom edge itself is off-screen.
+            Ok((
+                (x.round() as i32).clamp(m.x, m.x + m.width - 1),
+                (y.round() as i32).clamp(m.y, m.y + m.height - 1),
+            ))
+        }
+    }
+}
+
+pub fn resolve_bounds(bounds: &Bounds) -> Result<Rect, String> {
+    resolve_bounds_in(&monitors(), bounds)
+}
+
+/// `resolve_bounds` against the given monitors.
+fn resolve_bounds_in(monitors: &[Monitor], bounds: &Bounds) -> Result<Rect, String> {
+    let (x, y, width, height) = match *bounds {
+        Bounds::Absolute {
+            x,
+            y,
+            width,
+            height,
+            space,
+            monitor,
+        } => {
+            let (left, top) = to_native_in(monitors, space, x, y, monitor)?;
+            let m = monitors
+                .iter()
+                .find(|m| m.contains(left as f64, top as f64))
+                .ok_or_else(|| format!("Point ({}, {}) is not on any monitor", x, y))?;
+
+            // Sizes scale the same way the origin did.
+            let factor = match (space, NATIVE_IS_PIXELS) {
+                (CoordinateSpace::Logical, true) => m.scale_factor,
+                (CoordinateSpace::Logical, false) => 1.0,
+                (_, true) => 1.0,
+                (_, false) => 1.0 / m.scale_factor,
+            };
+            (left as f64, top as f64, width * factor, height * factor)
+        }
+        Bounds::Percent {
+            x,
+            y,
+            width,
+            height,
+            monitor,
+        } => {
+            let m = monitor_or_primary(monitors, monitor)?;
+            let (fx, fy) = (check_percent(x, "x")?, check_percent(y, "y")?);
+            let (fw, fh) = (
+                check_percent(width, "width")?,
+                check_percent(height, "height")?,
+            );
+            (
+                m.x as f64 + m.width as f64 * fx,
+                m.y as f64 + m.height as f64 * fy,
+                m.width as f64 * fw,
+                m.height as f64 * fh,
+            )
+        }
+        Bounds::Anchored {
+            anchor,
+            width,
+            height,
+            margin_x,
+            margin_y,
+            monitor,
+        } => {
+            let m = monitor_or_primary(monitors, monitor)?;
+            let (fx, fy) = anchor.fractions();
+            let (w, h) = (to_native_length(m, width), to_native_length(m, height));
+            (
+                m.x as f64
+                    + (m.width as f64 - w) * fx
+                    + Anchor::margin_direction(fx) * to_native_length(m, margin_x),
+                m.y as f64
+                    + (m.height as f64 - h) * fy
+                    + Anchor::margin_direction(fy) * to_native_length(m, margin_y),
+                w,
+                h,
+            )
+        }
+        Bounds::Monitor { monitor } => {
+            let m = monitor_or_primary(monitors, monitor)?;
+            (m.x as f64, m.y as f64, m.width as f64, m.height as f64)
+        }
+    };
+
+    if width <= 0.0 || height <= 0.0 {
+        return Err("Bounds must have a positive width and height".to_string());
+    }
+
+    Ok(Rect {
+        x: x.round() as i32,
+        y: y.round() as i32,
+        width: width.round() as i32,
+        height: height.round() as i32,
+    })
+}
+
+#[tauri::command]
+pub fn resolve_layout_bounds(bounds: Bounds) -> Result<Rect, String> {
+    resolve_bounds(&bounds)
+}
+
+#[tauri::command]
+pub fn resolve_layout_position(position: Position) -> Result<(i32, i32), String> {
+    resolve_position(&position)
+}
+
+// Native units are pixels on Windows and X11; macOS uses points.
+#[cfg(all(test, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+
+    /// A 1080p primary, and a 2x display to its left.
+    fn desk() -> Vec<Monitor> {
+        vec![
+            Monitor {
+                index: 0,
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+                scale_factor: 1.0,
+                primary: true,
+            },
+            Monitor {
+                index: 1,
+                x: -2560,
+                y: 0,
+                width: 2560,
+                height: 1440,
+                scale_factor: 2.0,
+                primary: false,
+            },
+        ]
+    }
+
+    fn anchored(anchor: Anchor, margin: f64) -> Position {
+        Position::Anchored {
+            anchor,
+            margin_x: margin,
+            margin_y: margin,
+            monitor: None,
+        }
+    }
+
+    #[test]
+    fn physical_points_are_native() {
+        let point = to_native_in(&desk(), CoordinateSpace::Physical, 10.4, 20.6, None);
+        assert_eq!(point, Ok((10, 21)));
+    }
+
+    #[test]
+    fn logical_points_scale_by_their_monitor() {
+        let monitors = desk();
+        let on = |x, y| to_native_in(&monitors, CoordinateSpace::Logical, x, y, None);
+        assert_eq!(on(100.0, 100.0), Ok((100, 100)));
+        assert_eq!(on(-1000.0, 100.0), Ok((-2000, 200)));
+        assert!(on(5000.0, 100.0).is_err());
+    }
+
+    #[test]
+    fn monitor_relative_points_offset_from_the_monitor() {
+        let monitors = desk();
+        let on =
+            |x, y, index| to_native_in(&monitors, CoordinateSpace::MonitorRelative, x, y, index);
+        assert_eq!(on(10.0, 20.0, Some(1)), Ok((-2550, 20)));
+        assert!(on(2560.0, 0.0, Some(1)).is_err());
+        assert!(on(-1.0, 0.0, Some(0)).is_err());
+        assert!(on(10.0, 20.0, Some(5)).is_err());
+        assert!(on(10.0, 20.0, None).is_err());
+    }
+
+    #[test]
+    fn percent_positions_default_to_the_primary() {
+        let monitors = desk();
+        let at = |x, y| {
+            resolve_position_in(
+                &monitors,
+                &Position::Percent {
+                    x,
+                    y,
+                    monitor: None,
+                },
+            )
+        };
+        assert_eq!(at(50.0, 50.0), Ok((960, 540)));
+        assert!(at(101.0, 50.0).is_err());
+    }
+
+    #[test]
+    fn anchored_positions_stay_on_the_monitor() {
+        let monitors = desk();
+        let at = |anchor, margin| resolve_position_in(&monitors, &anchored(anchor, margin));
+        assert_eq!(at(Anchor::BottomRight, 10.0), Ok((1910, 1070)));
+        assert_eq!(at(Anchor::Center, 0.0), Ok((960, 540)));
+        // The far edge itself is just off the monitor.
+        assert_eq!(at(Anchor::BottomRight, 0.0), Ok((1919, 1079)));
+    }
+
+    #[test]
+    fn anchored_bounds_keep_their_size_inside_the_margin() {
+        let bounds = Bounds::Anchored {
+            anchor: Anchor::BottomRight,
+            width: 200.0,
+            height: 100.0,
+            margin_x: 10.0,
+            margin_y: 10.0,
+            monitor: None,
+        };
+        let rect = resolve_bounds_in(&desk(), &bounds);
+        assert_eq!(
+            rect,
+            Ok(Rect {
+                x: 1710,
+                y: 970,
+                width: 200,
+                height: 100
+            })
+        );
+    }
+
+    #[test]
+    fn logical_bounds_scale_their_size_too() {
+        let bounds = Bounds::Absolute {
+            x: -1000.0,
+            y: 100.0,
+            width: 100.0,
+            height: 50.0,
+            space: CoordinateSpace::Logical,
+            monitor: None,
+        };
+        let rect = resolve_bounds_in(&desk(), &bounds);
+        assert_eq!(
+            rect,
+            Ok(Rect {
+                x: -2000,
+                y: 200,
+                width: 200,
+                height: 100
+            })
+        );
+    }
+
+    #[test]
+    fn empty_bounds_are_rejected() {
+        let bounds = Bounds::Percent {
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 50.0,
+            monitor: Some(1),
+        };
+        assert!(resolve_bounds_in(&desk(), &bounds).is_err());
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn virtual_screen_spans_every_monitor() {
+        let rect = bounding_rect(&desk());
+        assert_eq!(
+            rect,
+            Rect {
+                x: -2560,
+                y: 0,
+                width: 4480,
+                height: 1440
+            }
+        );
+    }
+}
@@ -0,0 +1,474 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Crash Reporting
+// ==========================================
+
+// Panics are written up by the panic hook. A native exception on Windows can leave
+// the heap or a lock broken, so the SEH filter allocates nothing and takes no lock:
+// it writes a minidump to a file opened at startup, which is deleted on exit unless
+// a crash keeps it. The next start turns such dumps into crash reports.
+
+const MAX_BREADCRUMBS: usize = 64;
+/// Starts the name of a minidump that no report covers yet.
+const DUMP_PREFIX: &str = "native-";
+/// Minidump stream holding the exception that caused the dump.
+const EXCEPTION_STREAM: u32 = 6;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Breadcrumb {
+    pub timestamp_ms: u128,
+    pub category: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CrashReport {
+    pub timestamp_ms: u128,
+    pub kind: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub os: String,
+    pub arch: String,
+    pub app_version: String,
+    pub breadcrumbs: Vec<Breadcrumb>,
+}
+
+struct CrashState {
+    breadcrumbs: VecDeque<Breadcrumb>,
+    report_dir: Option<PathBuf>,
+    #[cfg(feature = "crash-upload")]
+    upload_consent: bool,
+}
+
+static CRASH_STATE: Lazy<Mutex<CrashState>> = Lazy::new(|| {
+    Mutex::new(CrashState {
+        breadcrumbs: VecDeque::with_capacity(MAX_BREADCRUMBS),
+        report_dir: None,
+        #[cfg(feature = "crash-upload")]
+        upload_consent: false,
+    })
+});
+
+pub fn now_ms() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+/// Records a native operation so it shows up in the next crash report.
+pub fn breadcrumb(category: &str, message: impl Into<String>) {
+    // Never panic from here: this runs right before the unsafe code we want to debug.
+    let Ok(mut state) = CRASH_STATE.lock() else {
+        return;
+    };
+
+    if state.breadcrumbs.len() == MAX_BREADCRUMBS {
+        state.breadcrumbs.pop_front();
+    }
+
+    state.breadcrumbs.push_back(Breadcrumb {
+        timestamp_ms: now_ms(),
+        category: category.to_string(),
+        message: message.into(),
+    });
+}
+
+pub fn recent_breadcrumbs() -> Vec<Breadcrumb> {
+    match CRASH_STATE.lock() {
+        Ok(state) => state.breadcrumbs.iter().cloned().collect(),
+        Err(poisoned) => poisoned.into_inner().breadcrumbs.iter().cloned().collect(),
+    }
+}
+
+fn report_dir() -> Option<PathBuf> {
+    match CRASH_STATE.lock() {
+        Ok(state) => state.report_dir.clone(),
+        Err(poisoned) => poisoned.into_inner().report_dir.clone(),
+    }
+}
+
+fn write_report(kind: &str, message: String, location: Option<String>) -> Option<PathBuf> {
+    let dir = report_dir()?;
+    fs::create_dir_all(&dir).ok()?;
+
+    let report = CrashReport {
+        timestamp_ms: now_ms(),
+        kind: kind.to_string(),
+        message,
+        location,
+        thread: std::thread::current().name().map(str::to_string),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        breadcrumbs: recent_breadcrumbs(),
+    };
+    save_report(&dir, &report)
+}
+
+fn save_report(dir: &Path, report: &CrashReport) -> Option<PathBuf> {
+    let path = dir.join(format!("crash-{}.json", report.timestamp_ms));
+    let json = serde_json::to_string_pretty(report).ok()?;
+    // Breadcrumbs say what the user was doing; sealed like other stored data.
+    fs::write(&path, crate::secrets::seal(json.as_bytes()).ok()?).ok()?;
+    Some(path)
+}
+
+/// The exception code and address recorded in minidump `dump`.
+fn dump_exception(dump: &[u8]) -> Option<(u32, u64)> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(dump.get(at..at + 4)?.try_into().ok()?));
+    let u64_at = |at: usize| Some(u64::from_le_bytes(dump.get(at..at + 8)?.try_into().ok()?));
+    if dump.get(..4)? != b"MDMP" {
+        return None;
+    }
+    let (streams, directory) = (u32_at(8)? as usize, u32_at(12)? as usize);
+    (0..streams.min(1024)).find_map(|i| {
+        let entry = directory + i * 12;
+        if u32_at(entry)? != EXCEPTION_STREAM {
+            return None;
+        }
+        // The thread id and padding, then the record: code, flags, nested record, address.
+        let at = u32_at(entry + 8)? as usize;
+        Some((u32_at(at + 8)?, u64_at(at + 24)?))
+    })
+}
+
+/// Writes a report for each minidump a native crash left in `dir`, keeping the
+/// dump beside it under the same name, sealed like the report.
+fn adopt_dumps(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        let pending = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(DUMP_PREFIX) && name.ends_with(".dmp"));
+        // Another instance's dump can't be read until it exits.
+        let Some(dump) = pending.then(|| fs::read(&path).ok()).flatten() else {
+            continue;
+        };
+        if dump.is_empty() {
+            let _ = fs::remove_file(&path);
+            continue;
+        }
+        let timestamp_ms = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(now_ms, |at| at.as_millis());
+        let name = format!("crash-{}.dmp", timestamp_ms);
+        let kept = crate::secrets::seal(&dump)
+            .and_then(|data| fs::write(dir.join(&name), data).map_err(|e| e.to_string()));
+        if let Err(e) = kept {
+            log::warn!("failed to keep minidump {}: {}", path.display(), e);
+            continue;
+        }
+        let _ = fs::remove_file(&path);
+        let message = match dump_exception(&dump) {
+            Some((code, address)) => format!(
+                "unhandled exception 0x{:08X} at 0x{:X}, minidump {}",
+                code, address, name
+            ),
+            None => format!("unhandled native exception, minidump {}", name),
+        };
+        save_report(
+            dir,
+            &CrashReport {
+                timestamp_ms,
+                kind: "native-exception".to_string(),
+                message,
+                location: None,
+                thread: None,
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                breadcrumbs: Vec::new(),
+            },
+        );
+    }
+}
+
+/// Installs the panic hook (and on Windows the SEH filter) that persists crash reports,
+/// and reports the native crashes of earlier runs. Call after `secrets::init`.
+pub fn install(report_dir: PathBuf) {
+    if let Err(e) = fs::create_dir_all(&report_dir) {
+        log::warn!("failed to create the crash report folder: {}", e);
+    }
+    adopt_dumps(&report_dir);
+    #[cfg(target_os = "windows")]
+    win_seh::install(&report_dir);
+    if let Ok(mut state) = CRASH_STATE.lock() {
+        state.report_dir = Some(report_dir);
+    }
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic payload".to_string()
+        };
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+
+        if let Some(path) = write_report("panic", message, location) {
+            log::error!("crash report written to {}", path.display());
+        }
+
+        previous(info);
+    }));
+}
+
+#[cfg(target_os = "windows")]
+mod win_seh {
+    use std::{
+        ffi::c_void,
+        mem::size_of,
+        path::Path,
+        ptr::null_mut,
+        sync::atomic::{AtomicPtr, Ordering},
+    };
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Foundation::{CloseHandle, BOOL, BOOLEAN, GENERIC_WRITE, HANDLE},
+            Storage::FileSystem::{
+                CreateFileW, FileDispositionInfo, SetFileInformationByHandle, CREATE_ALWAYS,
+                DELETE, FILE_ATTRIBUTE_NORMAL, FILE_DISPOSITION_INFO, FILE_SHARE_READ,
+            },
+            System::{
+                Diagnostics::Debug::{
+                    MiniDumpWithThreadInfo, MiniDumpWriteDump, SetUnhandledExceptionFilter,
+                    EXCEPTION_POINTERS, MINIDUMP_EXCEPTION_INFORMATION,
+                },
+                Threading::{GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId},
+            },
+        },
+    };
+
+    const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+    /// The dump file opened at startup; taken by the first crash.
+    static DUMP_FILE: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+    /// Marks `file` to be deleted when it's closed, or not.
+    unsafe fn delete_on_close(file: HANDLE, delete: bool) -> windows::core::Result<()> {
+        let info = FILE_DISPOSITION_INFO {
+            DeleteFile: BOOLEAN(delete as u8),
+        };
+        SetFileInformationByHandle(
+            file,
+            FileDispositionInfo,
+            &info as *const _ as *const c_void,
+            size_of::<FILE_DISPOSITION_INFO>() as u32,
+        )
+    }
+
+    /// Runs on whatever thread crashed, with the heap possibly corrupt: no
+    /// allocations, no locks, nothing that can panic.
+    unsafe extern "system" fn filter(info: *const EXCEPTION_POINTERS) -> i32 {
+        let file = HANDLE(DUMP_FILE.swap(null_mut(), Ordering::SeqCst));
+        if file.is_invalid() {
+            return EXCEPTION_CONTINUE_SEARCH;
+        }
+        if delete_on_close(file, false).is_ok() {
+            let exception = MINIDUMP_EXCEPTION_INFORMATION {
+                ThreadId: GetCurrentThreadId(),
+                ExceptionPointers: info as *mut EXCEPTION_POINTERS,
+                ClientPointers: BOOL(0),
+            };
+            let _ = MiniDumpWriteDump(
+                GetCurrentProcess(),
+                GetCurrentProcessId(),
+                file,
+                MiniDumpWithThreadInfo,
+                (!info.is_null()).then_some(&exception as *const _),
+                None,
+                None,
+            );
+        }
+        let _ = CloseHandle(file);
+
+        EXCEPTION_CONTINUE_SEARCH
+    }
+
+    /// Opens this run's dump file in `dir`, to be deleted on exit, and installs the filter.
+    pub fn install(dir: &Path) {
+        let path = dir.join(format!("{}{}.dmp", super::DUMP_PREFIX, std::process::id()));
+        let wide: Vec<u16> = path
+            .to_string_lossy()
+            .encode_utf16()
+            .chain(Some(0))
+            .collect();
+        unsafe {
+            let opened = CreateFileW(
+                PCWSTR(wide.as_ptr()),
+                GENERIC_WRITE.0 | DELETE.0,
+                FILE_SHARE_READ,
+                None,
+                CREATE_ALWAYS,
+                FILE_ATTRIBUTE_NORMAL,
+                None,
+            );
+            match opened {
+                Ok(file) if delete_on_close(file, true).is_ok() => {
+                    DUMP_FILE.store(file.0, Ordering::SeqCst);
+                }
+                Ok(file) => {
+                    let _ = CloseHandle(file);
+                    log::warn!("native crashes won't leave a minidump this run");
+                }
+                Err(e) => log::warn!("failed to open a minidump file: {}", e),
+            }
+            SetUnhandledExceptionFilter(Some(filter));
+        }
+    }
+}
+
+// ==========================================
+// CRASH REPORT COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn list_crash_reports() -> Result<Vec<String>, String> {
+    let Some(dir) = report_dir() else {
+        return Ok(Vec::new());
+    };
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with("crash-") && name.ends_with(".json"))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[tauri::command]
+pub fn get_crash_report(name: String) -> Result<CrashReport, String> {
+    serde_json::from_slice(&read_report(&name)?).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "crash-upload")]
+#[tauri::command]
+pub fn set_crash_upload_consent(enabled: bool) {
+    if let Ok(mut state) = CRASH_STATE.lock() {
+        state.upload_consent = enabled;
+    }
+}
+
+#[cfg(feature = "crash-upload")]
+#[tauri::command]
+pub fn upload_crash_report(name: String, endpoint: String) -> Result<(), String> {
+    let consent = CRASH_STATE
+        .lock()
+        .map(|s| s.upload_consent)
+        .unwrap_or(false);
+    if !consent {
+        return Err("Crash report upload requires user consent".to_string());
+    }
+
+    let body = read_report(&name)?;
+
+    ureq::post(&endpoint)
+        .set("Content-Type", "application/json")
+        .send_bytes(&body)
+        .map_err(|e| format!("Upload failed: {}", e))?;
+
+    Ok(())
+}
+
+fn report_path(name: &str) -> Result<PathBuf, String> {
+    if name.contains('/') || name.contains('\\') || name.contains("..") {
+        return Err("Invalid crash report name".to_string());
+    }
+    let dir = report_dir().ok_or("Crash reporting is not initialized")?;
+    Ok(dir.join(name))
+}
+
+/// The report named `name`, decrypted if it was sealed.
+fn read_report(name: &str) -> Result<Vec<u8>, String> {
+    let data = fs::read(report_path(name)?).map_err(|e| e.to_string())?;
+    crate::secrets::open(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minidump with a header, a one-entry stream directory and an exception stream.
+    fn minidump(code: u32, address: u64) -> Vec<u8> {
+        let mut dump = b"MDMP".to_vec();
+        dump.extend(0xA793u32.to_le_bytes());
+        dump.extend(1u32.to_le_bytes());
+        dump.extend(32u32.to_le_bytes());
+        dump.resize(32, 0);
+        dump.extend(EXCEPTION_STREAM.to_le_bytes());
+        dump.extend(168u32.to_le_bytes());
+        dump.extend(44u32.to_le_bytes());
+        dump.extend(7u32.to_le_bytes());
+        dump.extend(0u32.to_le_bytes());
+        dump.extend(code.to_le_bytes());
+        dump.extend(0u32.to_le_bytes());
+        dump.extend(0u64.to_le_bytes());
+        dump.extend(address.to_le_bytes());
+        dump
+    }
+
+    #[test]
+    fn minidump_exceptions_are_read() {
+        let dump = minidump(0xC000_0005, 0x7FF6_1234_5678);
+        assert_eq!(dump_exception(&dump), Some((0xC000_0005, 0x7FF6_1234_5678)));
+        assert_eq!(dump_exception(&dump[..40]), None);
+        assert_eq!(dump_exception(b"not a minidump"), None);
+    }
+
+    #[test]
+    fn left_over_minidumps_become_reports() {
+        let dir = std::env::temp_dir().join(format!("crash-adopt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("native-1.dmp"), minidump(0xC000_0005, 0x10)).unwrap();
+        fs::write(dir.join("native-2.dmp"), b"").unwrap();
+        adopt_dumps(&dir);
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 2, "{:?}", names);
+        assert!(names.iter().all(|name| name.starts_with("crash-")));
+        let report = names.iter().find(|name| name.ends_with(".json")).unwrap();
+        // Other tests may have switched storage encryption on.
+        let report = crate::secrets::open(fs::read(dir.join(report)).unwrap()).unwrap();
+        let report: CrashReport = serde_json::from_slice(&report).unwrap();
+        assert_eq!(report.kind, "native-exception");
+        assert!(report
+            .message
+            .starts_with("unhandled exception 0xC0000005 at 0x10"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tauri::AppHandle;
+
+use crate::{coords, hot_corners, shake, spotlight};
+
+// ==========================================
+// Cursor Sampling
+// ==========================================
+
+// One thread polls the cursor position and feeds every cursor-driven trigger, so
+// triggers never install hooks of their own just to watch the pointer.
+
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(16);
+/// Monitor layout changes are rare; re-reading it every sample would be wasteful.
+const SCREEN_REFRESH: Duration = Duration::from_secs(1);
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("cursor-sampler".to_string())
+        .spawn(move || {
+            let Some(sampler) = platform::Sampler::new() else {
+                log::warn!("cursor sampling is unavailable");
+                return;
+            };
+            let mut screen = coords::virtual_screen();
+            let mut screen_read = Instant::now();
+
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(SAMPLE_INTERVAL);
+                if screen_read.elapsed() >= SCREEN_REFRESH {
+                    screen = coords::virtual_screen();
+                    screen_read = Instant::now();
+                }
+                if let Some((x, y)) = sampler.position() {
+                    hot_corners::on_sample(&app, x, y, screen);
+                    shake::on_sample(&app, x, y);
+                    spotlight::on_sample(&app, x, y);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn cursor sampler: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    pub struct Sampler;
+
+    impl Sampler {
+        pub fn new() -> Option<Self> {
+            Some(Sampler)
+        }
+
+        pub fn position(&self) -> Option<(i32, i32)> {
+            let mut point = POINT::default();
+            unsafe { GetCursorPos(&mut point) }.ok()?;
+            Some((point.x, point.y))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreate(source: *const c_void) -> *mut c_void;
+        fn CGEventGetLocation(event: *mut c_void) -> CGPoint;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFRelease(object: *mut c_void);
+    }
+
+    pub struct Sampler;
+
+    impl Sampler {
+        pub fn new() -> Option<Self> {
+            Some(Sampler)
+        }
+
+        /// Top-left based points, matching native coordinates.
+        pub fn position(&self) -> Option<(i32, i32)> {
+            unsafe {
+                let event = CGEventCreate(std::ptr::null());
+                if event.is_null() {
+                    return None;
+                }
+                let location = CGEventGetLocation(event);
+                CFRelease(event);
+                Some((location.x.round() as i32, location.y.round() as i32))
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, Window};
+    use x11rb::rust_connection::RustConnection;
+
+    pub struct Sampler {
+        conn: RustConnection,
+        root: Window,
+    }
+
+    impl Sampler {
+        pub fn new() -> Option<Self> {
+            let (conn, screen_num) = x11rb::connect(None).ok()?;
+            let root = conn.setup().roots[screen_num].root;
+            Some(Sampler { conn, root })
+        }
+
+        pub fn position(&self) -> Option<(i32, i32)> {
+            let pointer = self.conn.query_pointer(self.root).ok()?.reply().ok()?;
+            Some((pointer.root_x as i32, pointer.root_y as i32))
+        }
+    }
+}
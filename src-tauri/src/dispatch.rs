@@ -0,0 +1,363 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use tauri::AppHandle;
+
+use crate::{annotate, commands, coords, crash, export, middleware, sessions, OverlayManager};
+
+// ==========================================
+// Command Dispatch
+// ==========================================
+
+// Runs registered commands in-process from JSON parameters, for callers that aren't
+// the webview: the control server, hotkeys and other triggers, the command palette.
+// The registry calls most commands itself; those below need more than their
+// parameters.
+// Files are named, not given as paths: exports and imports stay in the app's exports
+// directory, so a caller can't have a command read or write elsewhere.
+
+static EXPORT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Keeps files exported and imported by dispatched commands in `dir`.
+pub fn init(dir: PathBuf) {
+    *EXPORT_DIR.lock().unwrap() = Some(dir);
+}
+
+/// Resolves `name` in the exports directory. Only plain file names are accepted.
+fn export_path(name: &str) -> Result<String, String> {
+    let dir = EXPORT_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Exports are not initialized")?;
+    if name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains(['/', '\\', ':'])
+        || name.chars().any(char::is_control)
+    {
+        return Err(format!("'{}' is not a plain file name", name));
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir.join(name).display().to_string())
+}
+
+/// Runs a registered command for a hotkey or other trigger named by `source`. It
+/// passes the same middleware as other requests, under `TRIGGER_ORIGIN`.
+pub fn dispatch_trigger(
+    manager: &OverlayManager,
+    source: &str,
+    method: &str,
+    params: &Value,
+) -> Result<Value, String> {
+    dispatch_as(manager, middleware::TRIGGER_ORIGIN, source, method, params)
+}
+
+/// Runs a registered command for `source` in-process, reported to the middleware
+/// as coming from `origin`.
+pub fn dispatch_as(
+    manager: &OverlayManager,
+    origin: &str,
+    source: &str,
+    method: &str,
+    params: &Value,
+) -> Result<Value, String> {
+    middleware::run_as(origin, method, params, || {
+        execute(manager, source, method, params)
+    })
+}
+
+/// The running app, for commands that open windows or emit events.
+pub fn app() -> Result<AppHandle, String> {
+    crate::OVERLAY_APP
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| "This command needs the app, which is not running".to_string())
+}
+
+/// Runs registered command `method` with `params` for `client`, without the middleware.
+pub fn execute(
+    manager: &OverlayManager,
+    client: &str,
+    method: &str,
+    params: &Value,
+) -> Result<Value, String> {
+    crash::breadcrumb("dispatch", method);
+
+    if let Some(result) = by_hand(manager, client, method, params) {
+        return result;
+    }
+    commands::call(method, params).unwrap_or_else(|| Err(format!("Unknown method '{}'", method)))
+}
+
+/// Whether `execute` can run registered command `name`.
+pub fn dispatchable(name: &str) -> bool {
+    BY_HAND.contains(&name) || commands::lookup(name).is_some_and(|c| c.call.is_some())
+}
+
+macro_rules! by_hand {
+    ($manager:ident, $client:ident, $params:ident; $(
+        $(#[cfg($($cfg:tt)*)])?
+        $name:literal => $body:expr,
+    )*) => {
+        /// Registered commands the registry can't call: they need the overlay manager,
+        /// the caller or the async runtime, or take file names.
+        const BY_HAND: &[&str] = &[$($(#[cfg($($cfg)*)])? $name,)*];
+
+        fn by_hand(
+            $manager: &OverlayManager,
+            $client: &str,
+            method: &str,
+            $params: &Value,
+        ) -> Option<Result<Value, String>> {
+            let run: &dyn Fn() -> Result<Value, String> = match method {
+                $($(#[cfg($($cfg)*)])? $name => &|| $body,)*
+                _ => return None,
+            };
+            Some(run())
+        }
+    };
+}
+
+by_hand! { manager, client, params;
+    "open_session" => to_value(sessions::open(client, opt_param(params, "name")?)),
+    "end_session" => to_value(sessions::end_as(manager, client, param(params, "id")?)?),
+    "get_app_state" => to_value(crate::app_state_in(manager)),
+    "export_events" => to_value(export::export_events(
+        param(params, "channels")?,
+        opt_param(params, "range")?,
+        export_path(&param::<String>(params, "file")?)?,
+        param(params, "format")?,
+    )?),
+    "export_annotations" => {
+        annotate::export(
+            &export_path(&param::<String>(params, "file")?)?,
+            param(params, "format")?,
+        )?;
+        Ok(Value::Null)
+    },
+    "load_annotations" => {
+        to_value(annotate::load(&export_path(&param::<String>(params, "file")?)?)?)
+    },
+    #[cfg(feature = "scripting")]
+    "export_script" => {
+        crate::scripts::export(
+            &param::<String>(params, "id")?,
+            &export_path(&param::<String>(params, "file")?)?,
+        )?;
+        Ok(Value::Null)
+    },
+    #[cfg(feature = "scripting")]
+    "import_script" => to_value(crate::scripts::import(
+        &export_path(&param::<String>(params, "file")?)?,
+        opt_param(params, "overwrite")?.unwrap_or(false),
+    )?),
+    #[cfg(feature = "scripting")]
+    "run_script" => to_value(tauri::async_runtime::block_on(crate::runner::run_script(
+        app()?,
+        param(params, "id")?,
+    ))?),
+    #[cfg(feature = "self-update")]
+    "check_for_update" => to_value(tauri::async_runtime::block_on(
+        crate::updater::check_for_update(app()?),
+    )?),
+    "create_privacy_overlay" => {
+        let bounds: Option<coords::Bounds> = opt_param(params, "bounds")?;
+        let text: Option<String> = opt_param(params, "text")?;
+        let image: Option<crate::OverlayImage> = opt_param(params, "image")?;
+        let cancel_button: Option<String> = opt_param(params, "cancel_button")?;
+        to_value(crate::create_overlay_in(
+            manager,
+            bounds.as_ref(),
+            text.as_deref(),
+            image.as_ref(),
+            opt_param(params, "opacity")?,
+            cancel_button.as_deref(),
+            opt_param(params, "timeout_ms")?,
+        )?)
+    },
+    "create_overlay_for_monitor" => {
+        let text: Option<String> = opt_param(params, "text")?;
+        to_value(crate::create_monitor_overlay_in(
+            manager,
+            param(params, "monitor_index")?,
+            text.as_deref(),
+            opt_param(params, "opacity")?,
+            opt_param(params, "timeout_ms")?,
+        )?)
+    },
+    "toggle_overlay_for_monitor" => to_value(crate::toggle_monitor_overlay_in(
+        manager,
+        param(params, "monitor_index")?,
+        opt_param::<String>(params, "text")?.as_deref(),
+    )?),
+    "create_privacy_region" => {
+        let text: Option<String> = opt_param(params, "text")?;
+        let shape: Option<crate::shape::WindowShape> = opt_param(params, "shape")?;
+        to_value(crate::create_region_overlay_in(
+            manager,
+            param(params, "x")?,
+            param(params, "y")?,
+            param(params, "w")?,
+            param(params, "h")?,
+            opt_param(params, "space")?,
+            opt_param(params, "monitor")?,
+            text.as_deref(),
+            shape.as_ref(),
+            opt_param(params, "opacity")?,
+            opt_param(params, "timeout_ms")?,
+        )?)
+    },
+    "attach_overlay_to_window" => to_value(crate::window_overlays::attach(
+        manager,
+        &param(params, "criteria")?,
+        opt_param::<String>(params, "text")?.as_deref(),
+        opt_param(params, "opacity")?,
+    )?),
+    "destroy_privacy_overlay" => {
+        crate::destroy_overlays_in(manager)?;
+        Ok(Value::Null)
+    },
+    "update_overlay" => {
+        crate::update_overlay_in(
+            manager,
+            param(params, "id")?,
+            param(params, "x")?,
+            param(params, "y")?,
+            param(params, "w")?,
+            param(params, "h")?,
+            opt_param(params, "space")?,
+            opt_param(params, "monitor")?,
+        )?;
+        Ok(Value::Null)
+    },
+    "update_overlay_text" => {
+        crate::update_overlay_text_in(
+            manager,
+            param(params, "id")?,
+            &param::<String>(params, "text")?,
+        )?;
+        Ok(Value::Null)
+    },
+    "set_overlay_progress" => {
+        crate::set_overlay_progress_in(
+            manager,
+            param(params, "id")?,
+            opt_param(params, "percent")?,
+        )?;
+        Ok(Value::Null)
+    },
+    "set_overlay_interactive" => {
+        crate::set_overlay_interactive_in(
+            manager,
+            param(params, "id")?,
+            param(params, "interactive")?,
+        )?;
+        Ok(Value::Null)
+    },
+    "destroy_overlay" => {
+        crate::destroy_overlay_in(manager, param(params, "id")?)?;
+        Ok(Value::Null)
+    },
+    "list_overlays" => to_value(manager.list()),
+    "start_laser_pointer" => to_value(crate::laser::start(
+        manager,
+        client,
+        opt_param(params, "monitor")?.unwrap_or(0),
+        &opt_param(params, "style")?.unwrap_or_default(),
+    )?),
+    "move_laser_pointer" => {
+        crate::laser::move_to(manager, client, param(params, "x")?, param(params, "y")?)?;
+        Ok(Value::Null)
+    },
+    "stop_laser_pointer" => {
+        crate::laser::stop(manager);
+        Ok(Value::Null)
+    },
+    #[cfg(feature = "input-automation")]
+    "run_input_job" => {
+        let job = serde_json::from_value(params.clone())
+            .map_err(|e| format!("Invalid input job: {}", e))?;
+        to_value(crate::injection::run_job(job)?)
+    },
+    "wait_for_vsync" => to_value(crate::vsync::wait(opt_param(params, "count")?.unwrap_or(1))),
+    #[cfg(feature = "input-automation")]
+    "run_multi_pointer_job" => {
+        let job = serde_json::from_value(params.clone())
+            .map_err(|e| format!("Invalid multi-pointer job: {}", e))?;
+        to_value(crate::multi_pointer::run_job(job)?)
+    },
+}
+
+fn param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<T, String> {
+    let value = params
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("Missing parameter '{}'", name))?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid parameter '{}': {}", name, e))
+}
+
+fn opt_param<T: DeserializeOwned>(params: &Value, name: &str) -> Result<Option<T>, String> {
+    match params.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| format!("Invalid parameter '{}': {}", name, e)),
+    }
+}
+
+fn to_value<T: Serialize>(value: T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn every_registered_command_is_dispatchable() {
+        let missing: Vec<&str> = commands::COMMANDS
+            .iter()
+            .map(|command| command.name)
+            .filter(|name| *name != "execute_action" && !dispatchable(name))
+            .collect();
+        assert!(missing.is_empty(), "not dispatchable: {:?}", missing);
+        assert!(BY_HAND.iter().all(|name| commands::lookup(name).is_some()));
+    }
+
+    #[test]
+    fn registry_calls_read_parameters_by_name() {
+        let found = commands::call("search_actions", &json!({ "query": "overlay" }));
+        assert!(found
+            .unwrap()
+            .unwrap()
+            .as_array()
+            .is_some_and(|a| !a.is_empty()));
+        assert_eq!(
+            commands::call("search_actions", &json!({})).unwrap(),
+            Err("Missing parameter 'query'".to_string())
+        );
+        let invalid = commands::call("search_actions", &json!({ "query": 1 })).unwrap();
+        assert!(invalid
+            .unwrap_err()
+            .starts_with("Invalid parameter 'query'"));
+        assert!(commands::call("create_privacy_overlay", &json!({})).is_none());
+    }
+
+    #[test]
+    fn registry_calls_fail_with_the_command_error() {
+        let error = commands::call("set_hotkey", &json!({ "keys": "" }))
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            serde_json::from_str::<Value>(&error).unwrap(),
+            json!({ "kind": "failed", "message": "Hotkey has no keys" })
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{mpsc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+// ==========================================
+// Event Pipeline
+// ==========================================
+
+// Every native event bound for the webview goes through `emit` and a single
+// dispatcher thread, started on first use. A timing policy first holds events back
+// (keeping only the latest payload), then they enter a bounded per-channel queue
+// whose overflow strategy decides what gets dropped when the consumer falls behind.
+// Channels with an in-flight limit pause until the webview acknowledges delivered
+// events. Policies are keyed by full event name or by scheme ("cursor://"), the
+// exact name winning.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum EventPolicy {
+    /// Delivered as soon as it is emitted.
+    Immediate,
+    /// Delivered once the event has been quiet for `ms`.
+    Debounce { ms: u64 },
+    /// Delivered at most once per `ms`; the first right away, then the latest.
+    Coalesce { ms: u64 },
+}
+
+/// What happens when a channel's delivery queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Overflow {
+    /// Drops the oldest queued event (keep-latest).
+    DropOldest,
+    /// Drops the incoming event.
+    DropNewest,
+    /// Never drops; the queue grows past its capacity and the overflow is counted.
+    Lossless,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueuePolicy {
+    pub capacity: usize,
+    pub overflow: Overflow,
+    /// Events delivered but not yet acknowledged through `ack_events` before the
+    /// channel pauses; unlimited when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<u32>,
+}
+
+const DEFAULT_QUEUE: QueuePolicy = QueuePolicy {
+    capacity: 256,
+    overflow: Overflow::DropOldest,
+    max_in_flight: None,
+};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ChannelMetrics {
+    pub emitted: u64,
+    pub delivered: u64,
+    /// Merged into a later event by a debounce or coalesce policy.
+    pub coalesced: u64,
+    pub dropped: u64,
+    /// Events queued past capacity on a lossless channel.
+    pub overflowed: u64,
+    pub queued: usize,
+    pub in_flight: u32,
+}
+
+enum Message {
+    Emit {
+        app: AppHandle,
+        event: String,
+        payload: Value,
+        policy: EventPolicy,
+        queue: QueuePolicy,
+    },
+    Ack {
+        event: String,
+        count: u32,
+    },
+}
+
+struct Pending {
+    payload: Value,
+    due: Instant,
+}
+
+struct Channel {
+    app: AppHandle,
+    queue_policy: QueuePolicy,
+    /// Event held back by its timing policy.
+    pending: Option<Pending>,
+    last_released: Option<Instant>,
+    queue: VecDeque<Value>,
+    in_flight: u32,
+}
+
+impl Channel {
+    fn enqueue(&mut self, payload: Value, metrics: &mut ChannelMetrics) {
+        if self.queue.len() >= self.queue_policy.capacity {
+            match self.queue_policy.overflow {
+                Overflow::DropOldest => {
+                    self.queue.pop_front();
+                    metrics.dropped += 1;
+                }
+                Overflow::DropNewest => {
+                    metrics.dropped += 1;
+                    return;
+                }
+                Overflow::Lossless => metrics.overflowed += 1,
+            }
+        }
+        self.queue.push_back(payload);
+    }
+
+    fn deliver(&mut self, event: &str, metrics: &mut ChannelMetrics) {
+        while self
+            .queue_policy
+            .max_in_flight
+            .map_or(true, |max| self.in_flight < max)
+        {
+            let Some(payload) = self.queue.pop_front() else {
+                break;
+            };
+            if let Err(e) = self.app.emit(event, payload) {
+                log::warn!("failed to emit {}: {}", event, e);
+                metrics.dropped += 1;
+                continue;
+            }
+            metrics.delivered += 1;
+            if self.queue_policy.max_in_flight.is_some() {
+                self.in_flight += 1;
+            }
+        }
+        metrics.queued = self.queue.len();
+        metrics.in_flight = self.in_flight;
+    }
+}
+
+static POLICIES: Lazy<Mutex<BTreeMap<String, EventPolicy>>> = Lazy::new(|| {
+    Mutex::new(BTreeMap::from([(
+        "cursor://".to_string(),
+        EventPolicy::Coalesce { ms: 50 },
+    )]))
+});
+static QUEUES: Lazy<Mutex<BTreeMap<String, QueuePolicy>>> = Lazy::new(|| {
+    Mutex::new(BTreeMap::from([
+        (
+            "cursor://".to_string(),
+            QueuePolicy {
+                capacity: 1,
+                overflow: Overflow::DropOldest,
+                max_in_flight: None,
+            },
+        ),
+        (
+            "toast://".to_string(),
+            QueuePolicy {
+                overflow: Overflow::Lossless,
+                ..DEFAULT_QUEUE
+            },
+        ),
+        (
+            "script://".to_string(),
+            QueuePolicy {
+                overflow: Overflow::Lossless,
+                ..DEFAULT_QUEUE
+            },
+        ),
+        (
+            "queue://".to_string(),
+            QueuePolicy {
+                overflow: Overflow::Lossless,
+                ..DEFAULT_QUEUE
+            },
+        ),
+    ]))
+});
+static METRICS: Lazy<Mutex<BTreeMap<String, ChannelMetrics>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+static DISPATCHER: Lazy<Mutex<Option<mpsc::Sender<Message>>>> = Lazy::new(|| Mutex::new(None));
+
+fn lookup<T: Copy>(map: &BTreeMap<String, T>, event: &str) -> Option<T> {
+    map.get(event)
+        .or_else(|| map.get(&event[..event.find("://")? + 3]))
+        .copied()
+}
+
+fn dispatch(rx: mpsc::Receiver<Message>) {
+    let mut channels: HashMap<String, Channel> = HashMap::new();
+
+    loop {
+        let next_due = channels
+            .values()
+            .filter_map(|c| c.pending.as_ref().map(|p| p.due))
+            .min();
+        let received = match next_due {
+            Some(due) => rx.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+        };
+
+        let mut metrics = METRICS.lock().unwrap();
+        match received {
+            Ok(Message::Emit {
+                app,
+                event,
+                payload,
+                policy,
+                queue,
+            }) => {
+                let now = Instant::now();
+                let stats = metrics.entry(event.clone()).or_default();
+                stats.emitted += 1;
+                let channel = channels.entry(event).or_insert_with(|| Channel {
+                    app: app.clone(),
+                    queue_policy: queue,
+                    pending: None,
+                    last_released: None,
+                    queue: VecDeque::new(),
+                    in_flight: 0,
+                });
+                channel.app = app;
+                channel.queue_policy = queue;
+
+                let due = match policy {
+                    EventPolicy::Immediate => now,
+                    EventPolicy::Debounce { ms } => now + Duration::from_millis(ms),
+                    EventPolicy::Coalesce { ms } => {
+                        match (&channel.pending, channel.last_released) {
+                            (Some(pending), _) => pending.due,
+                            (None, Some(last)) => (last + Duration::from_millis(ms)).max(now),
+                            (None, None) => now,
+                        }
+                    }
+                };
+                if channel.pending.replace(Pending { payload, due }).is_some() {
+                    stats.coalesced += 1;
+                }
+            }
+            Ok(Message::Ack { event, count }) => {
+                if let Some(channel) = channels.get_mut(&event) {
+                    channel.in_flight = channel.in_flight.saturating_sub(count);
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+
+        let now = Instant::now();
+        for (event, channel) in channels.iter_mut() {
+            let stats = metrics.entry(event.clone()).or_default();
+            if channel.pending.as_ref().is_some_and(|p| p.due <= now) {
+                let pending = channel.pending.take().unwrap();
+                channel.last_released = Some(now);
+                channel.enqueue(pending.payload, stats);
+            }
+            channel.deliver(event, stats);
+        }
+    }
+}
+
+fn send(message: Message) -> Result<(), String> {
+    let mut dispatcher = DISPATCHER.lock().unwrap();
+    if dispatcher.is_none() {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("event-pipeline".to_string())
+            .spawn(move || dispatch(rx))
+            .map_err(|e| format!("Failed to spawn event pipeline: {}", e))?;
+        *dispatcher = Some(tx);
+    }
+    dispatcher
+        .as_ref()
+        .unwrap()
+        .send(message)
+        .map_err(|_| "Event pipeline exited".to_string())
+}
+
+/// Sends `event` to the webview, subject to its configured timing and queue policies.
+pub fn emit<S: Serialize>(app: &AppHandle, event: &str, payload: S) {
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            log::warn!("failed to serialize {}: {}", event, e);
+            return;
+        }
+    };
+    let policy = lookup(&POLICIES.lock().unwrap(), event).unwrap_or(EventPolicy::Immediate);
+    let queue = lookup(&QUEUES.lock().unwrap(), event).unwrap_or(DEFAULT_QUEUE);
+
+    let sent = send(Message::Emit {
+        app: app.clone(),
+        event: event.to_string(),
+        payload,
+        policy,
+        queue,
+    });
+    if let Err(e) = sent {
+        log::warn!("failed to queue {}: {}", event, e);
+    }
+}
+
+// ==========================================
+// EVENT POLICY COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_event_policies() -> BTreeMap<String, EventPolicy> {
+    POLICIES.lock().unwrap().clone()
+}
+
+/// Sets the policy for an event name or a scheme such as `cursor://`; `None` clears it.
+#[tauri::command]
+pub fn set_event_policy(event: String, policy: Option<EventPolicy>) -> Result<(), String> {
+    if event.is_empty() {
+        return Err("Event name must not be empty".to_string());
+    }
+    if let Some(EventPolicy::Debounce { ms: 0 } | EventPolicy::Coalesce { ms: 0 }) = policy {
+        return Err("Interval must be at least 1 ms".to_string());
+    }
+    let mut policies = POLICIES.lock().unwrap();
+    match policy {
+        Some(policy) => policies.insert(event, policy),
+        None => policies.remove(&event),
+    };
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_event_queues() -> BTreeMap<String, QueuePolicy> {
+    QUEUES.lock().unwrap().clone()
+}
+
+/// Sets the queue policy for an event name or scheme; `None` restores the default.
+#[tauri::command]
+pub fn set_event_queue(event: String, queue: Option<QueuePolicy>) -> Result<(), String> {
+    if event.is_empty() {
+        return Err("Event name must not be empty".to_string());
+    }
+    if queue.is_some_and(|q| q.capacity == 0 || q.max_in_flight == Some(0)) {
+        return Err("Capacity and in-flight limit must be at least 1".to_string());
+    }
+    let mut queues = QUEUES.lock().unwrap();
+    match queue {
+        Some(queue) => queues.insert(event, queue),
+        None => queues.remove(&event),
+    };
+    Ok(())
+}
+
+/// Acknowledges `count` handled events on a channel with an in-flight limit.
+#[tauri::command]
+pub fn ack_events(event: String, count: u32) -> Result<(), String> {
+    send(Message::Ack { event, count })
+}
+
+#[tauri::command]
+pub fn get_event_metrics() -> BTreeMap<String, ChannelMetrics> {
+    METRICS.lock().unwrap().clone()
+}
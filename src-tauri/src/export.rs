@@ -0,0 +1,302 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    iter::Peekable,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    audit_log::{self, TimeRange},
+    middleware, secrets,
+};
+
+// ==========================================
+// Event Export
+// ==========================================
+
+// Writes recorded events out for analysis in other tools: a spreadsheet, pandas, jq.
+// Each channel yields its events oldest first and the channels are merged by time,
+// one event at a time, so the file is written as the log is read and exports of
+// long periods never hold the whole log in memory. The privacy audit log is read
+// from disk; command usage is the middleware's audit trail, which only holds the
+// last 500 calls since the app started, in memory. When older calls were dropped,
+// the usage channel starts with a `truncated` row saying so, rather than passing
+// for the complete history. With storage encryption on, the export is sealed like
+// stored data (see `secrets`), which needs it whole, so it's built in memory first.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportChannel {
+    /// The privacy audit log: captures, overlays, focus during captures, failures.
+    Audit,
+    /// Calls to state-changing commands and how they ended; only the last
+    /// `middleware::AUDIT_CAPACITY` calls, see above.
+    Usage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    /// One row per event: `at_ms,channel,type,data`, where `data` holds the event's
+    /// other fields as a JSON object.
+    Csv,
+    /// One JSON object per line, with `at_ms`, `channel` and `type` beside the
+    /// event's own fields.
+    Jsonl,
+}
+
+struct Row {
+    at_ms: u64,
+    channel: ExportChannel,
+    /// The event's fields, including its `type`.
+    fields: Map<String, Value>,
+}
+
+type Rows = Peekable<Box<dyn Iterator<Item = Row>>>;
+
+fn rows(channel: ExportChannel, range: TimeRange) -> Result<Rows, String> {
+    let rows: Box<dyn Iterator<Item = Row>> = match channel {
+        ExportChannel::Audit => Box::new(audit_log::records(range)?.filter_map(move |record| {
+            let Ok(Value::Object(fields)) = serde_json::to_value(&record.event) else {
+                return None;
+            };
+            Some(Row {
+                at_ms: record.at_ms,
+                channel,
+                fields,
+            })
+        })),
+        ExportChannel::Usage => Box::new(middleware::get_command_audit().into_iter().filter_map(
+            move |entry| {
+                let Ok(Value::Object(mut fields)) = serde_json::to_value(&entry) else {
+                    return None;
+                };
+                fields.remove("at_ms");
+                fields.insert("type".to_string(), Value::from("command"));
+                Some(Row {
+                    at_ms: entry.at_ms,
+                    channel,
+                    fields,
+                })
+            },
+        )),
+    };
+    let in_range: Box<dyn Iterator<Item = Row>> =
+        Box::new(rows.filter(move |row| range.contains(row.at_ms)));
+    let marker = match channel {
+        ExportChannel::Usage => truncation(range),
+        ExportChannel::Audit => None,
+    };
+    let rows: Box<dyn Iterator<Item = Row>> = Box::new(marker.into_iter().chain(in_range));
+    Ok(rows.peekable())
+}
+
+/// A row marking that usage within `range` was dropped before it could be
+/// exported, timed no later than the first call kept.
+fn truncation(range: TimeRange) -> Option<Row> {
+    let overflow = middleware::audit_overflow()?;
+    if range.from_ms.is_some_and(|from| from > overflow.until_ms) {
+        return None;
+    }
+    let at_ms = range.to_ms.map_or(overflow.until_ms, |to| {
+        overflow.until_ms.min(to.saturating_sub(1))
+    });
+    let mut fields = Map::new();
+    fields.insert("type".to_string(), Value::from("truncated"));
+    fields.insert("dropped".to_string(), Value::from(overflow.dropped));
+    fields.insert(
+        "capacity".to_string(),
+        Value::from(middleware::AUDIT_CAPACITY),
+    );
+    fields.insert(
+        "message".to_string(),
+        Value::from("Older calls were dropped; only the most recent are kept, in memory"),
+    );
+    Some(Row {
+        at_ms,
+        channel: ExportChannel::Usage,
+        fields,
+    })
+}
+
+/// Quotes `field` for CSV if it needs it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn channel_name(channel: ExportChannel) -> &'static str {
+    match channel {
+        ExportChannel::Audit => "audit",
+        ExportChannel::Usage => "usage",
+    }
+}
+
+fn write_row(out: &mut impl Write, mut row: Row, format: ExportFormat) -> std::io::Result<()> {
+    let channel = channel_name(row.channel);
+    match format {
+        ExportFormat::Csv => {
+            let kind = match row.fields.remove("type") {
+                Some(Value::String(kind)) => kind,
+                _ => String::new(),
+            };
+            let data = if row.fields.is_empty() {
+                String::new()
+            } else {
+                Value::Object(row.fields).to_string()
+            };
+            writeln!(
+                out,
+                "{},{},{},{}",
+                row.at_ms,
+                channel,
+                csv_field(&kind),
+                csv_field(&data)
+            )
+        }
+        ExportFormat::Jsonl => {
+            let mut object = Map::new();
+            object.insert("at_ms".to_string(), Value::from(row.at_ms));
+            object.insert("channel".to_string(), Value::from(channel));
+            object.extend(row.fields);
+            writeln!(out, "{}", Value::Object(object))
+        }
+    }
+}
+
+/// Writes the events of `channels` within `range` to `path`, oldest first, and
+/// returns how many were written.
+pub fn export(
+    channels: &[ExportChannel],
+    range: TimeRange,
+    path: &str,
+    format: ExportFormat,
+) -> Result<u64, String> {
+    if channels.is_empty() {
+        return Err("Choose at least one channel to export".to_string());
+    }
+    let mut sources = Vec::new();
+    for channel in channels {
+        if !sources.iter().any(|(c, _)| c == channel) {
+            sources.push((*channel, rows(*channel, range)?));
+        }
+    }
+
+    let failed = |e: std::io::Error| format!("Failed to write {}: {}", path, e);
+    if secrets::enabled() {
+        let mut out = Vec::new();
+        let written = write_rows(&mut sources, &mut out, format).map_err(failed)?;
+        fs::write(path, secrets::seal(&out)?).map_err(failed)?;
+        return Ok(written);
+    }
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut out = BufWriter::new(file);
+    let written = write_rows(&mut sources, &mut out, format).map_err(failed)?;
+    out.flush().map_err(failed)?;
+    Ok(written)
+}
+
+/// Writes the rows of `sources` to `out`, merged by time, and returns how many.
+fn write_rows(
+    sources: &mut [(ExportChannel, Rows)],
+    out: &mut impl Write,
+    format: ExportFormat,
+) -> std::io::Result<u64> {
+    if format == ExportFormat::Csv {
+        writeln!(out, "at_ms,channel,type,data")?;
+    }
+
+    let mut written = 0;
+    // Merges the channels: the next row is the earliest any of them has.
+    while let Some(next) = sources
+        .iter_mut()
+        .filter_map(|(_, rows)| Some((rows.peek()?.at_ms, rows)))
+        .min_by_key(|(at_ms, _)| *at_ms)
+        .and_then(|(_, rows)| rows.next())
+    {
+        write_row(out, next, format)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+// ==========================================
+// EXPORT COMMANDS
+// ==========================================
+
+/// Writes events from `channels` (`audit`, `usage`) within `range` to `path` as
+/// `csv` or `jsonl`, oldest first. Returns the number of rows written. `usage` only
+/// covers the last 500 calls since the app started; a `truncated` row marks where
+/// older ones were dropped.
+#[tauri::command]
+pub fn export_events(
+    channels: Vec<ExportChannel>,
+    range: Option<TimeRange>,
+    path: String,
+    format: ExportFormat,
+) -> Result<u64, String> {
+    export(&channels, range.unwrap_or_default(), &path, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: Value) -> Row {
+        let Value::Object(fields) = fields else {
+            panic!("fields must be an object");
+        };
+        Row {
+            at_ms: 42,
+            channel: ExportChannel::Audit,
+            fields,
+        }
+    }
+
+    fn written(row: Row, format: ExportFormat) -> String {
+        let mut out = Vec::new();
+        write_row(&mut out, row, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("started"), "started");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn csv_rows_hold_the_other_fields_as_json() {
+        let line = written(
+            row(serde_json::json!({ "type": "overlay-closed", "id": 7 })),
+            ExportFormat::Csv,
+        );
+        assert_eq!(line, "42,audit,overlay-closed,\"{\"\"id\"\":7}\"\n");
+        let line = written(
+            row(serde_json::json!({ "type": "started" })),
+            ExportFormat::Csv,
+        );
+        assert_eq!(line, "42,audit,started,\n");
+    }
+
+    #[test]
+    fn jsonl_rows_put_time_and_channel_first() {
+        let line = written(
+            row(serde_json::json!({ "type": "overlay-closed", "id": 7 })),
+            ExportFormat::Jsonl,
+        );
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "at_ms": 42, "channel": "audit", "type": "overlay-closed", "id": 7 })
+        );
+    }
+}
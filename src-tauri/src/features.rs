@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+// ==========================================
+// Build Features
+// ==========================================
+
+#[derive(Serialize)]
+pub struct BuildFeatures {
+    pub version: &'static str,
+    pub target_os: &'static str,
+    pub features: Vec<&'static str>,
+}
+
+const FEATURES: &[(&str, bool)] = &[
+    ("input-automation", cfg!(feature = "input-automation")),
+    ("crash-upload", cfg!(feature = "crash-upload")),
+    ("remote-control", cfg!(feature = "remote-control")),
+    ("voice-commands", cfg!(feature = "voice-commands")),
+    ("scripting", cfg!(feature = "scripting")),
+    ("webdriver", cfg!(feature = "webdriver")),
+    ("headless", cfg!(feature = "headless")),
+    ("service", cfg!(feature = "service")),
+    ("self-update", cfg!(feature = "self-update")),
+    ("encryption", cfg!(feature = "encryption")),
+];
+
+#[tauri::command]
+pub fn get_build_features() -> BuildFeatures {
+    BuildFeatures {
+        version: env!("CARGO_PKG_VERSION"),
+        target_os: std::env::consts::OS,
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect(),
+    }
+}
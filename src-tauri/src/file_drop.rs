@@ -0,0 +1,583 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::{
+    app_window::{self, WindowCriteria},
+    crash,
+};
+
+// ==========================================
+// File Drop Injection
+// ==========================================
+
+// Mouse emulation alone can't carry a file payload, so each platform starts a real
+// drag session owned by this process and steers it onto the target window:
+// - Windows: an OLE `DoDragDrop` loop over a shell data object, driven by injected mouse input
+// - macOS: an `NSDraggingSession` started from a tiny window of ours at the drop point
+// - Linux: the XDND protocol, spoken directly to the target window
+
+/// Drops `paths` onto the centre of the front-most window whose title contains `title`.
+/// The drop lands on whatever is visible at that point, so the window should not be covered.
+pub fn drop_files(app: &AppHandle, title: &str, paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("No files to drop".to_string());
+    }
+    let mut absolute = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = Path::new(path)
+            .canonicalize()
+            .map_err(|e| format!("Cannot drop {}: {}", path, e))?;
+        absolute.push(path.to_string_lossy().into_owned());
+    }
+
+    let criteria = WindowCriteria {
+        title: Some(title.to_string()),
+        ..Default::default()
+    };
+    let window = app_window::find_window(&criteria)
+        .ok_or_else(|| format!("No window titled '{}'", title))?;
+    let x = window.rect.x + window.rect.width / 2;
+    let y = window.rect.y + window.rect.height / 2;
+
+    crash::breadcrumb(
+        "file_drop",
+        format!(
+            "{} files onto '{}' at {},{}",
+            absolute.len(),
+            window.title,
+            x,
+            y
+        ),
+    );
+    platform::drop_files(app, window.id, &absolute, x, y)
+}
+
+/// Wiggles the held cursor over the drop point so the target sees enter and over
+/// notifications, then releases the button to drop.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn drive_drag(x: i32, y: i32) -> Result<(), String> {
+    use std::{thread, time::Duration};
+
+    thread::sleep(Duration::from_millis(150));
+    for (dx, dy) in [(4, 4), (8, 8), (4, 4), (0, 0)] {
+        crate::input::move_cursor(x + dx, y + dy)?;
+        thread::sleep(Duration::from_millis(50));
+    }
+    thread::sleep(Duration::from_millis(100));
+    crate::input::mouse_button("left", false)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::thread;
+
+    use tauri::AppHandle;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{IBindCtx, IDataObject};
+    use windows::Win32::System::Ole::{
+        IDropSource, OleInitialize, OleUninitialize, DROPEFFECT_COPY, DROPEFFECT_NONE,
+    };
+    use windows::Win32::UI::Shell::Common::ITEMIDLIST;
+    use windows::Win32::UI::Shell::{
+        BHID_DataObject, ILCreateFromPathW, ILFree, SHCreateShellItemArrayFromIDLists, SHDoDragDrop,
+    };
+
+    use super::drive_drag;
+    use crate::input;
+
+    /// The same data object Explorer hands out when files are dragged from it.
+    unsafe fn data_object(paths: &[String]) -> Result<IDataObject, String> {
+        let mut pidls: Vec<*const ITEMIDLIST> = Vec::with_capacity(paths.len());
+        let mut missing = None;
+        for path in paths {
+            let wide: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+            let pidl = ILCreateFromPathW(PCWSTR(wide.as_ptr()));
+            if pidl.is_null() {
+                missing = Some(path.clone());
+                break;
+            }
+            pidls.push(pidl);
+        }
+
+        let result = match missing {
+            Some(path) => Err(format!("Shell cannot resolve {}", path)),
+            None => SHCreateShellItemArrayFromIDLists(&pidls)
+                .and_then(|items| items.BindToHandler(None::<&IBindCtx>, &BHID_DataObject))
+                .map_err(|e| format!("Failed to create drag data: {}", e)),
+        };
+        for pidl in pidls {
+            ILFree(Some(pidl));
+        }
+        result
+    }
+
+    unsafe fn drag(paths: &[String], x: i32, y: i32) -> Result<(), String> {
+        let data = data_object(paths)?;
+
+        // The default drop source drops once the left button is released.
+        input::move_cursor(x, y)?;
+        input::mouse_button("left", true)?;
+        let driver = thread::spawn(move || drive_drag(x, y));
+        let effect = SHDoDragDrop(
+            HWND::default(),
+            &data,
+            None::<&IDropSource>,
+            DROPEFFECT_COPY,
+        );
+        let driven = driver
+            .join()
+            .unwrap_or_else(|_| Err("Drag driver panicked".to_string()));
+
+        match effect {
+            Ok(effect) if effect != DROPEFFECT_NONE => driven,
+            Ok(_) => Err("The target window did not accept the files".to_string()),
+            Err(e) => Err(format!("Drag and drop failed: {}", e)),
+        }
+    }
+
+    pub fn drop_files(
+        _app: &AppHandle,
+        _window: u64,
+        paths: &[String],
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        let paths = paths.to_vec();
+        // OLE drag and drop needs a single-threaded apartment of its own.
+        thread::Builder::new()
+            .name("file-drop".to_string())
+            .spawn(move || unsafe {
+                OleInitialize(None).map_err(|e| format!("OleInitialize failed: {}", e))?;
+                let result = drag(&paths, x, y);
+                OleUninitialize();
+                result
+            })
+            .map_err(|e| format!("Failed to spawn drag thread: {}", e))?
+            .join()
+            .map_err(|_| "Drag thread panicked".to_string())?
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Mutex,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use cocoa::appkit::{NSBackingStoreType, NSWindow, NSWindowStyleMask};
+    use cocoa::base::{id, nil, BOOL, NO, YES};
+    use cocoa::foundation::{NSArray, NSPoint, NSRect, NSSize, NSString, NSUInteger};
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Class, Object, Protocol, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+    use once_cell::sync::Lazy;
+    use tauri::AppHandle;
+
+    use super::drive_drag;
+    use crate::input;
+
+    const VIEW_CLASS: &str = "ScreenShareHostFileDropView";
+    const DRAG_OPERATION_COPY: NSUInteger = 1;
+    /// The drag window is a few points wide, centred on the drop point.
+    const GRIP: f64 = 8.0;
+
+    static PATHS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+    static WINDOW: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+    static ENDED: AtomicBool = AtomicBool::new(false);
+    static OPERATION: AtomicU64 = AtomicU64::new(0);
+
+    extern "C" fn accepts_first_mouse(_this: &Object, _sel: Sel, _event: id) -> BOOL {
+        YES
+    }
+
+    extern "C" fn mouse_down(this: &Object, _sel: Sel, event: id) {
+        unsafe {
+            let bounds: NSRect = msg_send![this, bounds];
+            let items: id = msg_send![class!(NSMutableArray), array];
+            for path in PATHS.lock().unwrap().iter() {
+                let path = NSString::alloc(nil).init_str(path);
+                let url: id = msg_send![class!(NSURL), fileURLWithPath: path];
+                let item: id = msg_send![class!(NSDraggingItem), alloc];
+                let item: id = msg_send![item, initWithPasteboardWriter: url];
+                let _: () = msg_send![item, setDraggingFrame: bounds contents: nil];
+                let _: () = msg_send![items, addObject: item];
+                let _: () = msg_send![item, release];
+                let _: () = msg_send![path, release];
+            }
+            let _: id =
+                msg_send![this, beginDraggingSessionWithItems: items event: event source: this];
+        }
+    }
+
+    extern "C" fn source_operation_mask(
+        _this: &Object,
+        _sel: Sel,
+        _session: id,
+        _context: isize,
+    ) -> NSUInteger {
+        DRAG_OPERATION_COPY
+    }
+
+    extern "C" fn session_ended(
+        _this: &Object,
+        _sel: Sel,
+        _session: id,
+        _point: NSPoint,
+        operation: NSUInteger,
+    ) {
+        OPERATION.store(operation as u64, Ordering::SeqCst);
+        ENDED.store(true, Ordering::SeqCst);
+    }
+
+    fn view_class() -> &'static Class {
+        if let Some(class) = Class::get(VIEW_CLASS) {
+            return class;
+        }
+        let mut decl = ClassDecl::new(VIEW_CLASS, class!(NSView)).expect("drag view class");
+        unsafe {
+            decl.add_method(
+                sel!(acceptsFirstMouse:),
+                accepts_first_mouse as extern "C" fn(&Object, Sel, id) -> BOOL,
+            );
+            decl.add_method(
+                sel!(mouseDown:),
+                mouse_down as extern "C" fn(&Object, Sel, id),
+            );
+            decl.add_method(
+                sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+                source_operation_mask as extern "C" fn(&Object, Sel, id, isize) -> NSUInteger,
+            );
+            decl.add_method(
+                sel!(draggingSession:endedAtPoint:operation:),
+                session_ended as extern "C" fn(&Object, Sel, id, NSPoint, NSUInteger),
+            );
+        }
+        if let Some(protocol) = Protocol::get("NSDraggingSource") {
+            decl.add_protocol(protocol);
+        }
+        decl.register()
+    }
+
+    /// Runs on the main thread. `x`, `y` are top-left based points.
+    unsafe fn open_grip(x: i32, y: i32) {
+        let screens: id = msg_send![class!(NSScreen), screens];
+        let primary: NSRect = msg_send![screens.objectAtIndex(0), frame];
+        let frame = NSRect::new(
+            NSPoint::new(
+                x as f64 - GRIP / 2.0,
+                primary.size.height - y as f64 - GRIP / 2.0,
+            ),
+            NSSize::new(GRIP, GRIP),
+        );
+
+        let window: id = msg_send![class!(NSWindow), alloc];
+        let window: id = window.initWithContentRect_styleMask_backing_defer_(
+            frame,
+            NSWindowStyleMask::NSBorderlessWindowMask,
+            NSBackingStoreType::NSBackingStoreBuffered,
+            false,
+        );
+        let view: id = msg_send![view_class(), alloc];
+        let view: id =
+            msg_send![view, initWithFrame: NSRect::new(NSPoint::new(0.0, 0.0), frame.size)];
+        window.setContentView_(view);
+        let _: () = msg_send![view, release];
+
+        // Nearly transparent, but clickable: fully clear windows let clicks fall through.
+        let _: () = msg_send![window, setOpaque: NO];
+        let _: () = msg_send![window, setAlphaValue: 0.02f64];
+        let _: () = msg_send![window, setIgnoresMouseEvents: NO];
+        window.setLevel_(i32::MAX as i64);
+        window.orderFrontRegardless();
+        *WINDOW.lock().unwrap() = window as usize;
+    }
+
+    unsafe fn close_grip() {
+        let window = std::mem::take(&mut *WINDOW.lock().unwrap());
+        if window != 0 {
+            let window = window as id;
+            window.orderOut_(nil);
+            let _: () = msg_send![window, close];
+        }
+    }
+
+    pub fn drop_files(
+        app: &AppHandle,
+        _window: u64,
+        paths: &[String],
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        *PATHS.lock().unwrap() = paths.to_vec();
+        ENDED.store(false, Ordering::SeqCst);
+        OPERATION.store(0, Ordering::SeqCst);
+
+        app.run_on_main_thread(move || unsafe { open_grip(x, y) })
+            .map_err(|e| e.to_string())?;
+        thread::sleep(Duration::from_millis(100));
+
+        // Pressing on the grip starts the session; the rest of the gesture steers it.
+        input::move_cursor(x, y)?;
+        input::mouse_button("left", true)?;
+        let driven = drive_drag(x, y);
+
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while !ENDED.load(Ordering::SeqCst) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        let _ = app.run_on_main_thread(|| unsafe { close_grip() });
+        driven?;
+
+        if !ENDED.load(Ordering::SeqCst) {
+            return Err("Drag session did not finish".to_string());
+        }
+        if OPERATION.load(Ordering::SeqCst) == 0 {
+            return Err("The target window did not accept the files".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use tauri::AppHandle;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        AtomEnum, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, PropMode,
+        SelectionNotifyEvent, WindowClass, SELECTION_NOTIFY_EVENT,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::{CURRENT_TIME, NONE};
+
+    /// Highest XDND version spoken here.
+    const XDND_VERSION: u32 = 5;
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(3);
+
+    struct Atoms {
+        aware: u32,
+        enter: u32,
+        position: u32,
+        status: u32,
+        leave: u32,
+        drop: u32,
+        finished: u32,
+        selection: u32,
+        action_copy: u32,
+        uri_list: u32,
+    }
+
+    fn atom(conn: &RustConnection, name: &str) -> Result<u32, String> {
+        conn.intern_atom(false, name.as_bytes())
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map(|reply| reply.atom)
+            .map_err(|e| e.to_string())
+    }
+
+    fn atoms(conn: &RustConnection) -> Result<Atoms, String> {
+        Ok(Atoms {
+            aware: atom(conn, "XdndAware")?,
+            enter: atom(conn, "XdndEnter")?,
+            position: atom(conn, "XdndPosition")?,
+            status: atom(conn, "XdndStatus")?,
+            leave: atom(conn, "XdndLeave")?,
+            drop: atom(conn, "XdndDrop")?,
+            finished: atom(conn, "XdndFinished")?,
+            selection: atom(conn, "XdndSelection")?,
+            action_copy: atom(conn, "XdndActionCopy")?,
+            uri_list: atom(conn, "text/uri-list")?,
+        })
+    }
+
+    fn uri_list(paths: &[String]) -> String {
+        paths
+            .iter()
+            .map(|path| {
+                let encoded: Vec<String> = path
+                    .split('/')
+                    .map(|segment| urlencoding::encode(segment).into_owned())
+                    .collect();
+                format!("file://{}\r\n", encoded.join("/"))
+            })
+            .collect()
+    }
+
+    fn send(conn: &RustConnection, target: u32, kind: u32, data: [u32; 5]) -> Result<(), String> {
+        let event = ClientMessageEvent::new(32, target, kind, data);
+        conn.send_event(false, target, EventMask::NO_EVENT, event)
+            .map_err(|e| e.to_string())?;
+        conn.flush().map_err(|e| e.to_string())
+    }
+
+    /// Answers selection requests for the dropped data while waiting for a client
+    /// message of `kind`.
+    fn wait_for(
+        conn: &RustConnection,
+        atoms: &Atoms,
+        kind: u32,
+        data: &[u8],
+    ) -> Result<Option<ClientMessageEvent>, String> {
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+        while Instant::now() < deadline {
+            match conn.poll_for_event().map_err(|e| e.to_string())? {
+                Some(Event::ClientMessage(event)) if event.type_ == kind => {
+                    return Ok(Some(event));
+                }
+                Some(Event::SelectionRequest(request)) => {
+                    let property = if request.target == atoms.uri_list {
+                        conn.change_property8(
+                            PropMode::REPLACE,
+                            request.requestor,
+                            request.property,
+                            atoms.uri_list,
+                            data,
+                        )
+                        .map_err(|e| e.to_string())?;
+                        request.property
+                    } else {
+                        NONE
+                    };
+                    let notify = SelectionNotifyEvent {
+                        response_type: SELECTION_NOTIFY_EVENT,
+                        sequence: 0,
+                        time: request.time,
+                        requestor: request.requestor,
+                        selection: request.selection,
+                        target: request.target,
+                        property,
+                    };
+                    conn.send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+                        .map_err(|e| e.to_string())?;
+                    conn.flush().map_err(|e| e.to_string())?;
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// `position` is the root position packed as XDND expects it: `x << 16 | y`.
+    fn drag(
+        conn: &RustConnection,
+        atoms: &Atoms,
+        source: u32,
+        target: u32,
+        version: u32,
+        data: &str,
+        position: u32,
+    ) -> Result<(), String> {
+        conn.set_selection_owner(source, atoms.selection, CURRENT_TIME)
+            .map_err(|e| e.to_string())?;
+
+        // Three or fewer types fit in the enter message itself.
+        send(
+            conn,
+            target,
+            atoms.enter,
+            [source, version << 24, atoms.uri_list, 0, 0],
+        )?;
+        send(
+            conn,
+            target,
+            atoms.position,
+            [source, 0, position, CURRENT_TIME, atoms.action_copy],
+        )?;
+
+        let accepted = wait_for(conn, atoms, atoms.status, data.as_bytes())?
+            .is_some_and(|status| status.data.as_data32()[1] & 1 != 0);
+        if !accepted {
+            send(conn, target, atoms.leave, [source, 0, 0, 0, 0])?;
+            return Err("The target window did not accept the files".to_string());
+        }
+
+        send(conn, target, atoms.drop, [source, 0, CURRENT_TIME, 0, 0])?;
+        // Version 1 targets never send XdndFinished; the data request is all we get.
+        let finished = wait_for(conn, atoms, atoms.finished, data.as_bytes())?;
+        if finished.is_none() && version >= 2 {
+            return Err("The target window did not finish the drop".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn drop_files(
+        _app: &AppHandle,
+        window: u64,
+        paths: &[String],
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = atoms(&conn)?;
+        let target = window as u32;
+
+        let version = conn
+            .get_property(false, target, atoms.aware, AtomEnum::ATOM, 0, 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .ok()
+            .and_then(|reply| reply.value32().and_then(|mut values| values.next()))
+            .ok_or("The target window does not accept dropped files")?
+            .min(XDND_VERSION);
+
+        let source = conn.generate_id().map_err(|e| e.to_string())?;
+        conn.create_window(
+            0,
+            source,
+            root,
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let position = ((x.max(0) as u32) << 16) | (y.max(0) as u32 & 0xffff);
+        let result = drag(
+            &conn,
+            &atoms,
+            source,
+            target,
+            version,
+            &uri_list(paths),
+            position,
+        );
+        let _ = conn.destroy_window(source);
+        let _ = conn.flush();
+        result
+    }
+}
+
+// ==========================================
+// FILE DROP COMMANDS
+// ==========================================
+
+/// Runs off the main thread: the drop waits on the target application.
+#[tauri::command(async)]
+pub fn drop_files_on_window(
+    app: AppHandle,
+    title: String,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    drop_files(&app, &title, &paths)
+}
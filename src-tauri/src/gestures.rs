@@ -0,0 +1,641 @@
+use std::{
+    sync::{mpsc, Mutex},
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::actions::Action;
+
+// ==========================================
+// Mouse Gestures
+// ==========================================
+
+// Gestures are drawn while holding the right button and reduced to the directions
+// they moved in ("R", "DR", "LDR", ...), so size and speed do not matter. The press
+// is held back from applications until release; if nothing was drawn, a plain right
+// click is replayed at the release point.
+
+/// Distance the cursor must travel before a movement counts as a stroke, in native units.
+const SEGMENT_MIN: i32 = 24;
+const MAX_DIRECTIONS: usize = 8;
+const DEFAULT_TRAINING_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Gesture {
+    pub name: String,
+    /// One of U, D, L or R per stroke, e.g. "DR" for an L shape.
+    pub directions: String,
+    pub action: Action,
+}
+
+/// Native cursor positions, in the order they were sampled.
+type Stroke = Vec<(i32, i32)>;
+
+static GESTURES: Lazy<Mutex<Vec<Gesture>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Points of the gesture being drawn, if the right button is held.
+static STROKE: Lazy<Mutex<Option<Stroke>>> = Lazy::new(|| Mutex::new(None));
+/// Set while the engine runs; actions need it.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+/// The next drawn gesture goes here instead of being dispatched.
+static TRAINING: Lazy<Mutex<Option<mpsc::Sender<String>>>> = Lazy::new(|| Mutex::new(None));
+
+fn begin(x: i32, y: i32) {
+    *STROKE.lock().unwrap() = Some(vec![(x, y)]);
+}
+
+fn track(x: i32, y: i32) {
+    if let Some(points) = STROKE.lock().unwrap().as_mut() {
+        points.push((x, y));
+    }
+}
+
+/// Ends the stroke. Returns false if nothing was drawn, in which case the press
+/// should reach applications as a normal right click.
+fn finish(x: i32, y: i32) -> bool {
+    let Some(mut points) = STROKE.lock().unwrap().take() else {
+        return false;
+    };
+    points.push((x, y));
+
+    let drawn = directions(&points);
+    if drawn.is_empty() {
+        return false;
+    }
+    recognized(drawn);
+    true
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn is_tracking() -> bool {
+    STROKE.lock().unwrap().is_some()
+}
+
+fn directions(points: &[(i32, i32)]) -> String {
+    let mut out = String::new();
+    let mut from = points[0];
+    for &point in points {
+        let (dx, dy) = (point.0 - from.0, point.1 - from.1);
+        if dx.abs().max(dy.abs()) < SEGMENT_MIN {
+            continue;
+        }
+        let direction = if dx.abs() > dy.abs() {
+            if dx > 0 {
+                'R'
+            } else {
+                'L'
+            }
+        } else if dy > 0 {
+            'D'
+        } else {
+            'U'
+        };
+        if !out.ends_with(direction) {
+            out.push(direction);
+        }
+        from = point;
+    }
+    out
+}
+
+fn recognized(drawn: String) {
+    if let Some(training) = TRAINING.lock().unwrap().take() {
+        let _ = training.send(drawn);
+        return;
+    }
+
+    let gesture = GESTURES
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|g| g.directions == drawn)
+        .cloned();
+    let app = APP.lock().unwrap().clone();
+    match (gesture, app) {
+        (Some(gesture), Some(app)) => {
+            crate::actions::run(&app, &gesture.action, &format!("gesture:{}", gesture.name))
+        }
+        _ => log::debug!("unrecognized gesture {}", drawn),
+    }
+}
+
+fn validate(gesture: &Gesture, gestures: &[Gesture]) -> Result<(), String> {
+    if gesture.name.trim().is_empty() {
+        return Err("Gesture name must not be empty".to_string());
+    }
+    if gesture.directions.is_empty() || gesture.directions.chars().count() > MAX_DIRECTIONS {
+        return Err(format!(
+            "Gesture directions must have 1 to {} strokes",
+            MAX_DIRECTIONS
+        ));
+    }
+    if let Some(c) = gesture
+        .directions
+        .chars()
+        .find(|c| !matches!(c, 'U' | 'D' | 'L' | 'R'))
+    {
+        return Err(format!("Invalid gesture direction '{}'", c));
+    }
+    let chars: Vec<char> = gesture.directions.chars().collect();
+    if chars.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("Consecutive strokes must change direction".to_string());
+    }
+    if let Some(other) = gestures
+        .iter()
+        .find(|g| g.directions == gesture.directions && g.name != gesture.name)
+    {
+        return Err(format!(
+            "Gesture '{}' is already drawn as {}",
+            other.name, other.directions
+        ));
+    }
+    Ok(())
+}
+
+fn save(gesture: Gesture) -> Result<(), String> {
+    let mut gestures = GESTURES.lock().unwrap();
+    validate(&gesture, &gestures)?;
+    match gestures.iter_mut().find(|g| g.name == gesture.name) {
+        Some(existing) => *existing = gesture,
+        None => gestures.push(gesture),
+    }
+    Ok(())
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    *APP.lock().unwrap() = Some(app);
+    platform::start().inspect_err(|_| *APP.lock().unwrap() = None)
+}
+
+pub fn stop() -> Result<(), String> {
+    platform::stop();
+    *APP.lock().unwrap() = None;
+    *STROKE.lock().unwrap() = None;
+    Ok(())
+}
+
+// ==========================================
+// GESTURE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn list_gestures() -> Vec<Gesture> {
+    GESTURES.lock().unwrap().clone()
+}
+
+/// Adds `gesture`, replacing any gesture with the same name.
+#[tauri::command]
+pub fn set_gesture(gesture: Gesture) -> Result<(), String> {
+    save(gesture)
+}
+
+#[tauri::command]
+pub fn delete_gesture(name: String) -> Result<(), String> {
+    let mut gestures = GESTURES.lock().unwrap();
+    let before = gestures.len();
+    gestures.retain(|g| g.name != name);
+    if gestures.len() == before {
+        return Err(format!("Gesture '{}' not found", name));
+    }
+    Ok(())
+}
+
+/// Waits for the user to draw a gesture and saves it under `name`.
+#[tauri::command(async)]
+pub fn train_gesture(
+    name: String,
+    action: Action,
+    timeout_ms: Option<u64>,
+) -> Result<Gesture, String> {
+    if APP.lock().unwrap().is_none() {
+        return Err("Gestures are not running; enable the gestures subsystem".to_string());
+    }
+    let (tx, rx) = mpsc::channel();
+    {
+        let mut training = TRAINING.lock().unwrap();
+        if training.is_some() {
+            return Err("A gesture is already being trained".to_string());
+        }
+        *training = Some(tx);
+    }
+
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_TRAINING_TIMEOUT);
+    let directions = rx.recv_timeout(timeout).map_err(|_| {
+        *TRAINING.lock().unwrap() = None;
+        "No gesture was drawn".to_string()
+    })?;
+
+    let gesture = Gesture {
+        name,
+        directions,
+        action,
+    };
+    save(gesture.clone())?;
+    Ok(gesture)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        mem::size_of,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+        MOUSEINPUT,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, PostThreadMessageW, SetTimer, SetWindowsHookExW,
+        UnhookWindowsHookEx, HHOOK, LLMHF_INJECTED, MSG, MSLLHOOKSTRUCT, WH_MOUSE_LL, WM_APP,
+        WM_MOUSEMOVE, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_TIMER,
+    };
+
+    use super::{begin, finish, is_tracking, track};
+
+    /// Posted by the hook; input is not injected from inside it.
+    const WM_REPLAY_CLICK: u32 = WM_APP + 1;
+
+    /// Watchdog entry of the hook thread.
+    const WATCHED: &str = "gesture-hook";
+
+    static THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code >= 0 {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let (x, y) = (info.pt.x, info.pt.y);
+            // The replayed click is injected and passes through untouched.
+            if info.flags & LLMHF_INJECTED == 0 {
+                match wparam.0 as u32 {
+                    WM_RBUTTONDOWN => {
+                        begin(x, y);
+                        return LRESULT(1);
+                    }
+                    WM_RBUTTONUP if is_tracking() => {
+                        if !finish(x, y) {
+                            let _ = PostThreadMessageW(
+                                THREAD_ID.load(Ordering::SeqCst),
+                                WM_REPLAY_CLICK,
+                                WPARAM(0),
+                                LPARAM(0),
+                            );
+                        }
+                        return LRESULT(1);
+                    }
+                    WM_MOUSEMOVE => track(x, y),
+                    _ => {}
+                }
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    fn replay_click() {
+        let input = |flags| INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dwFlags: flags,
+                    ..Default::default()
+                },
+            },
+        };
+        let inputs = [input(MOUSEEVENTF_RIGHTDOWN), input(MOUSEEVENTF_RIGHTUP)];
+        unsafe { SendInput(&inputs, size_of::<INPUT>() as i32) };
+    }
+
+    /// Installs the hook and has the watchdog replace the hook thread if it stops
+    /// pumping messages.
+    pub fn start() -> Result<(), String> {
+        spawn()?;
+        crate::watchdog::register(
+            WATCHED,
+            crate::watchdog::THREAD_TIMEOUT,
+            Some(Arc::new(|| {
+                quit();
+                spawn()
+            })),
+        );
+        Ok(())
+    }
+
+    fn spawn() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("gestures".to_string())
+            .spawn(move || unsafe {
+                THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+                let hook = GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| e.to_string())
+                    .and_then(|module| {
+                        SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), HINSTANCE(module.0), 0)
+                            .map_err(|e| format!("Failed to install mouse hook: {}", e))
+                    });
+                let hook = match hook {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                let _ = SetTimer(HWND::default(), 0, crate::watchdog::HEARTBEAT_MS, None);
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    match msg.message {
+                        WM_REPLAY_CLICK => replay_click(),
+                        WM_TIMER => crate::watchdog::heartbeat(WATCHED),
+                        _ => {}
+                    }
+                }
+
+                let _ = UnhookWindowsHookEx(hook);
+            })
+            .map_err(|e| format!("Failed to spawn gesture hook: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Gesture hook thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        crate::watchdog::unregister(WATCHED);
+        quit();
+    }
+
+    /// Ends the hook thread, or a stalled one once it recovers.
+    fn quit() {
+        unsafe {
+            let _ = PostThreadMessageW(
+                THREAD_ID.load(Ordering::SeqCst),
+                WM_QUIT,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use super::{begin, finish, is_tracking, track};
+
+    type CFTypeRef = *mut c_void;
+    type TapCallback =
+        extern "C" fn(proxy: CFTypeRef, kind: u32, event: CFTypeRef, user: CFTypeRef) -> CFTypeRef;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HID_EVENT_TAP: u32 = 0;
+    const HEAD_INSERT: u32 = 0;
+    const DEFAULT_OPTIONS: u32 = 0;
+
+    const RIGHT_DOWN: u32 = 3;
+    const RIGHT_UP: u32 = 4;
+    const RIGHT_DRAGGED: u32 = 7;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+
+    const FIELD_SOURCE_PID: u32 = 41;
+    const BUTTON_RIGHT: u32 = 1;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            mask: u64,
+            callback: TapCallback,
+            user: CFTypeRef,
+        ) -> CFTypeRef;
+        fn CGEventTapEnable(tap: CFTypeRef, enable: bool);
+        fn CGEventGetLocation(event: CFTypeRef) -> CGPoint;
+        fn CGEventGetIntegerValueField(event: CFTypeRef, field: u32) -> i64;
+        fn CGEventCreateMouseEvent(
+            source: CFTypeRef,
+            kind: u32,
+            location: CGPoint,
+            button: u32,
+        ) -> CFTypeRef;
+        fn CGEventPost(tap: u32, event: CFTypeRef);
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: CFTypeRef;
+        fn CFMachPortCreateRunLoopSource(
+            alloc: CFTypeRef,
+            port: CFTypeRef,
+            order: isize,
+        ) -> CFTypeRef;
+        fn CFRunLoopGetCurrent() -> CFTypeRef;
+        fn CFRunLoopAddSource(run_loop: CFTypeRef, source: CFTypeRef, mode: CFTypeRef);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: CFTypeRef);
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    static RUN_LOOP: AtomicUsize = AtomicUsize::new(0);
+    static TAP: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe fn replay_click(location: CGPoint) {
+        for kind in [RIGHT_DOWN, RIGHT_UP] {
+            let event = CGEventCreateMouseEvent(std::ptr::null_mut(), kind, location, BUTTON_RIGHT);
+            if !event.is_null() {
+                CGEventPost(HID_EVENT_TAP, event);
+                CFRelease(event);
+            }
+        }
+    }
+
+    extern "C" fn callback(
+        _proxy: CFTypeRef,
+        kind: u32,
+        event: CFTypeRef,
+        _user: CFTypeRef,
+    ) -> CFTypeRef {
+        unsafe {
+            if kind == TAP_DISABLED_BY_TIMEOUT {
+                CGEventTapEnable(TAP.load(Ordering::SeqCst) as CFTypeRef, true);
+                return event;
+            }
+            // The replayed click is posted by this process and passes through untouched.
+            if CGEventGetIntegerValueField(event, FIELD_SOURCE_PID) == std::process::id() as i64 {
+                return event;
+            }
+
+            let location = CGEventGetLocation(event);
+            let (x, y) = (location.x.round() as i32, location.y.round() as i32);
+            match kind {
+                RIGHT_DOWN => {
+                    begin(x, y);
+                    std::ptr::null_mut()
+                }
+                RIGHT_UP if is_tracking() => {
+                    if !finish(x, y) {
+                        replay_click(location);
+                    }
+                    std::ptr::null_mut()
+                }
+                RIGHT_DRAGGED => {
+                    track(x, y);
+                    event
+                }
+                _ => event,
+            }
+        }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("gestures".to_string())
+            .spawn(move || unsafe {
+                let mask = (1u64 << RIGHT_DOWN) | (1 << RIGHT_UP) | (1 << RIGHT_DRAGGED);
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    DEFAULT_OPTIONS,
+                    mask,
+                    callback,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = ready_tx.send(Err(
+                        "Failed to create event tap (grant Accessibility permission)".to_string(),
+                    ));
+                    return;
+                }
+                TAP.store(tap as usize, Ordering::SeqCst);
+
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null_mut(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                RUN_LOOP.store(run_loop as usize, Ordering::SeqCst);
+                let _ = ready_tx.send(Ok(()));
+
+                CFRunLoopRun();
+
+                CGEventTapEnable(tap, false);
+                RUN_LOOP.store(0, Ordering::SeqCst);
+                CFRelease(source);
+                CFRelease(tap);
+            })
+            .map_err(|e| format!("Failed to spawn gesture tap: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Gesture tap thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        let run_loop = RUN_LOOP.load(Ordering::SeqCst);
+        if run_loop != 0 {
+            unsafe { CFRunLoopStop(run_loop as CFTypeRef) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, KeyButMask};
+
+    use super::{begin, finish, track};
+
+    // The core protocol has no way to hold back a press without grabbing the pointer
+    // from every application, so the button is polled and the right click still
+    // reaches the window underneath.
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+    pub fn start() -> Result<(), String> {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("gestures".to_string())
+            .spawn(move || {
+                let (conn, screen) = match x11rb::connect(None) {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("Failed to connect to X server: {}", e)));
+                        return;
+                    }
+                };
+                let root = conn.setup().roots[screen].root;
+                let _ = ready_tx.send(Ok(()));
+
+                let mut held = false;
+                while GENERATION.load(Ordering::SeqCst) == generation {
+                    thread::sleep(POLL_INTERVAL);
+                    let Some(pointer) = conn.query_pointer(root).ok().and_then(|c| c.reply().ok())
+                    else {
+                        continue;
+                    };
+                    let (x, y) = (pointer.root_x as i32, pointer.root_y as i32);
+                    let down = u16::from(pointer.mask) & u16::from(KeyButMask::BUTTON3) != 0;
+                    match (held, down) {
+                        (false, true) => begin(x, y),
+                        (true, true) => track(x, y),
+                        (true, false) => {
+                            finish(x, y);
+                        }
+                        _ => {}
+                    }
+                    held = down;
+                }
+            })
+            .map_err(|e| format!("Failed to spawn gesture poller: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Gesture poller thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, sync::Mutex, time::Instant};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{actions::Action, coords::Rect};
+
+// ==========================================
+// Hot Corners
+// ==========================================
+
+// Fed by the cursor sampler. Corners are those of the whole virtual screen, so the
+// cursor is stopped by the screen edges on both axes and lands there reliably.
+
+/// Size of the corner zone, in native units.
+const CORNER_SIZE: i32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HotCorner {
+    pub action: Action,
+    /// How long the cursor must rest in the corner before the action fires.
+    pub dwell_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HotCornerConfig {
+    pub enabled: bool,
+    pub corners: BTreeMap<Corner, HotCorner>,
+}
+
+/// The corner the cursor is resting in, since when, and whether it already fired.
+struct Dwell {
+    corner: Corner,
+    since: Instant,
+    fired: bool,
+}
+
+static CONFIG: Lazy<Mutex<HotCornerConfig>> = Lazy::new(|| Mutex::new(HotCornerConfig::default()));
+static DWELL: Lazy<Mutex<Option<Dwell>>> = Lazy::new(|| Mutex::new(None));
+
+fn corner_at(x: i32, y: i32, screen: Rect) -> Option<Corner> {
+    let left = x < screen.x + CORNER_SIZE;
+    let right = x >= screen.x + screen.width - CORNER_SIZE;
+    let top = y < screen.y + CORNER_SIZE;
+    let bottom = y >= screen.y + screen.height - CORNER_SIZE;
+    match (left, right, top, bottom) {
+        (true, _, true, _) => Some(Corner::TopLeft),
+        (_, true, true, _) => Some(Corner::TopRight),
+        (true, _, _, true) => Some(Corner::BottomLeft),
+        (_, true, _, true) => Some(Corner::BottomRight),
+        _ => None,
+    }
+}
+
+/// Called by the cursor sampler with a native cursor position. Each dwell fires once;
+/// the cursor has to leave the corner to re-arm it.
+pub fn on_sample(app: &AppHandle, x: i32, y: i32, screen: Rect) {
+    let fire = {
+        let config = CONFIG.lock().unwrap();
+        let mut dwell = DWELL.lock().unwrap();
+
+        let corner = corner_at(x, y, screen).filter(|_| config.enabled);
+        match (corner, dwell.as_mut()) {
+            (None, _) => {
+                *dwell = None;
+                None
+            }
+            (Some(corner), Some(current)) if current.corner == corner => {
+                let hot = config.corners.get(&corner);
+                match hot {
+                    Some(hot)
+                        if !current.fired
+                            && current.since.elapsed().as_millis() as u64 >= hot.dwell_ms =>
+                    {
+                        current.fired = true;
+                        Some((corner, hot.action.clone()))
+                    }
+                    _ => None,
+                }
+            }
+            (Some(corner), _) => {
+                *dwell = Some(Dwell {
+                    corner,
+                    since: Instant::now(),
+                    fired: false,
+                });
+                None
+            }
+        }
+    };
+
+    if let Some((corner, action)) = fire {
+        crate::actions::run(app, &action, &format!("hot-corner:{}", corner.name()));
+    }
+}
+
+// ==========================================
+// HOT CORNER COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_hot_corners() -> HotCornerConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// Assigns an action to `corner`, or clears it when `hot_corner` is omitted.
+#[tauri::command]
+pub fn set_hot_corner(corner: Corner, hot_corner: Option<HotCorner>) {
+    let mut config = CONFIG.lock().unwrap();
+    match hot_corner {
+        Some(hot_corner) => {
+            config.corners.insert(corner, hot_corner);
+        }
+        None => {
+            config.corners.remove(&corner);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_hot_corners_enabled(enabled: bool) {
+    CONFIG.lock().unwrap().enabled = enabled;
+    *DWELL.lock().unwrap() = None;
+}
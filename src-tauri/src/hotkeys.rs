@@ -0,0 +1,1186 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    actions::Action,
+    coords::{self, Anchor, Bounds},
+    crash,
+    hud::{self, HudStyle},
+};
+
+// ==========================================
+// Global Hotkeys
+// ==========================================
+
+// System-wide key combinations bound to trigger actions, like hot corners. A binding
+// is written as modifiers and a key joined by "+", e.g. "Ctrl+Alt+S", and fires even
+// while another app has focus. Windows registers them from a thread of its own that
+// pumps their `WM_HOTKEY` messages; macOS registers Carbon hot keys, which the app's
+// run loop delivers on the main thread. Bindings outlive the subsystem being
+// switched off and are registered again when it comes back on.
+//
+// A binding can also be a sequence of steps separated by spaces, like an editor's
+// leader key: "Ctrl+Alt+P M" is Ctrl+Alt+P, then M. Only first steps are registered
+// all the time. Pressing one registers the keys that can follow it, bare keys
+// included, and shows them in a hint HUD hidden from capture; the next key runs a
+// binding or narrows the choice, and Escape or a pause of `SEQUENCE_TIMEOUT` gives
+// the keys back to other apps.
+//
+// Binding keys that can't work, because another binding, the OS or another app
+// already has them, fails with a conflict naming the holder and suggesting nearby
+// free combinations.
+
+/// How long a sequence waits for its next step.
+const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Device-independent pixels.
+const HINT_FONT_SIZE: f64 = 20.0;
+const HINT_WIDTH: f64 = 360.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Modifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// Command on macOS, the Windows key on Windows.
+    meta: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+enum Key {
+    /// b'A' to b'Z'.
+    Letter(u8),
+    /// 0 to 9.
+    Digit(u8),
+    /// F1 to F24.
+    Function(u8),
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Space", Key::Space),
+    ("Enter", Key::Enter),
+    ("Escape", Key::Escape),
+    ("Tab", Key::Tab),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Chord {
+    modifiers: Modifiers,
+    key: Key,
+}
+
+const ESCAPE: Chord = Chord {
+    modifiers: Modifiers {
+        ctrl: false,
+        alt: false,
+        shift: false,
+        meta: false,
+    },
+    key: Key::Escape,
+};
+
+impl Chord {
+    /// Parses a first step, which needs a modifier unless it's a function key.
+    fn parse(keys: &str) -> Result<Chord, String> {
+        let chord = Chord::parse_step(keys)?;
+        // A bare key would be swallowed from every app's typing.
+        if chord.modifiers == Modifiers::default() && !matches!(chord.key, Key::Function(_)) {
+            return Err(format!("Hotkey '{}' needs a modifier", keys));
+        }
+        Ok(chord)
+    }
+
+    /// Modifier and key names are case-insensitive; "Alt" and "Option", "Ctrl" and
+    /// "Control", "Meta", "Cmd", "Command", "Win" and "Super" are synonyms.
+    fn parse_step(keys: &str) -> Result<Chord, String> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+        for part in keys.split('+').map(str::trim) {
+            let flag = match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" | "option" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "meta" | "cmd" | "command" | "win" | "super" => &mut modifiers.meta,
+                _ => {
+                    if key.is_some() {
+                        return Err(format!("Hotkey '{}' has more than one key", keys));
+                    }
+                    key =
+                        Some(parse_key(part).ok_or_else(|| {
+                            format!("Unknown key '{}' in hotkey '{}'", part, keys)
+                        })?);
+                    continue;
+                }
+            };
+            *flag = true;
+        }
+        let key = key.ok_or_else(|| format!("Hotkey '{}' has no key", keys))?;
+        Ok(Chord { modifiers, key })
+    }
+}
+
+/// Parses "Ctrl+Alt+P M" into its steps. Steps after the first may be bare keys,
+/// since they're only taken while their sequence waits.
+fn parse_steps(keys: &str) -> Result<Vec<Chord>, String> {
+    let mut parts = keys.split_whitespace();
+    let first = parts
+        .next()
+        .ok_or_else(|| "Hotkey has no keys".to_string())?;
+    let mut steps = vec![Chord::parse(first)?];
+    for part in parts {
+        steps.push(Chord::parse_step(part)?);
+    }
+    Ok(steps)
+}
+
+fn spell(steps: &[Chord]) -> String {
+    steps
+        .iter()
+        .map(Chord::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let upper = name.to_ascii_uppercase();
+    match upper.as_bytes() {
+        [letter @ b'A'..=b'Z'] => return Some(Key::Letter(*letter)),
+        [digit @ b'0'..=b'9'] => return Some(Key::Digit(digit - b'0')),
+        [b'F', number @ ..] => {
+            if let Some(n) = std::str::from_utf8(number)
+                .ok()
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| (1..=24).contains(n))
+            {
+                return Some(Key::Function(n));
+            }
+        }
+        _ => {}
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(named, _)| named.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// The canonical spelling, e.g. "Ctrl+Alt+S", so differently written bindings of the
+/// same keys are one binding.
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.modifiers;
+        for (on, name) in [
+            (m.ctrl, "Ctrl"),
+            (m.alt, "Alt"),
+            (m.shift, "Shift"),
+            (m.meta, "Meta"),
+        ] {
+            if on {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.key {
+            Key::Letter(letter) => write!(f, "{}", letter as char),
+            Key::Digit(digit) => write!(f, "{}", digit),
+            Key::Function(n) => write!(f, "F{}", n),
+            key => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, named)| *named == key)
+                    .map_or("?", |(name, _)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// Shortcuts the OS keeps for itself, in canonical spelling. Registering one either
+/// fails or takes it away from the user.
+#[cfg(target_os = "windows")]
+const SYSTEM_SHORTCUTS: &[(&str, &str)] = &[
+    ("Alt+Tab", "Switch windows"),
+    ("Alt+F4", "Close the window"),
+    ("Ctrl+Escape", "Open Start"),
+    ("Ctrl+Alt+Delete", "Security options"),
+    ("Ctrl+Shift+Escape", "Open Task Manager"),
+    ("Meta+A", "Open quick settings"),
+    ("Meta+D", "Show the desktop"),
+    ("Meta+E", "Open File Explorer"),
+    ("Meta+I", "Open Settings"),
+    ("Meta+L", "Lock the screen"),
+    ("Meta+R", "Open Run"),
+    ("Meta+V", "Open clipboard history"),
+    ("Meta+X", "Open the quick link menu"),
+    ("Meta+Tab", "Open Task View"),
+    ("Meta+Space", "Switch input language"),
+    ("Shift+Meta+S", "Take a screenshot"),
+];
+#[cfg(target_os = "macos")]
+const SYSTEM_SHORTCUTS: &[(&str, &str)] = &[
+    ("Ctrl+Up", "Mission Control"),
+    ("Ctrl+Down", "Application windows"),
+    ("Ctrl+Left", "Move a space left"),
+    ("Ctrl+Right", "Move a space right"),
+    ("Ctrl+Meta+Q", "Lock the screen"),
+    ("Ctrl+Meta+Space", "Character viewer"),
+    ("Alt+Meta+Escape", "Force quit"),
+    ("Shift+Meta+3", "Screenshot"),
+    ("Shift+Meta+4", "Screenshot of a region"),
+    ("Shift+Meta+5", "Screenshot and recording options"),
+    ("Meta+H", "Hide the app"),
+    ("Meta+M", "Minimize the window"),
+    ("Meta+Q", "Quit the app"),
+    ("Meta+W", "Close the window"),
+    ("Meta+Tab", "Switch apps"),
+    ("Meta+Space", "Spotlight"),
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SYSTEM_SHORTCUTS: &[(&str, &str)] = &[];
+/// How many alternatives a conflict suggests.
+const SUGGESTIONS: usize = 3;
+
+/// Who holds a key combination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeySource {
+    /// A binding of this app.
+    App,
+    /// A shortcut of the OS.
+    System,
+    /// Another app registered it first.
+    OtherApp,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum HotkeyError {
+    /// `keys` are taken by `source`; `detail` says by what.
+    Conflict {
+        keys: String,
+        source: HotkeySource,
+        detail: String,
+        /// Free combinations of the same key with other modifiers.
+        suggestions: Vec<String>,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl From<String> for HotkeyError {
+    fn from(message: String) -> Self {
+        HotkeyError::Failed { message }
+    }
+}
+
+/// Why the OS didn't register a hotkey.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+enum RegisterError {
+    /// Another app holds the combination.
+    InUse,
+    Failed(String),
+}
+
+impl fmt::Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterError::InUse => f.write_str("the combination is already in use by another app"),
+            RegisterError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// A binding, an OS shortcut or a combination another app holds.
+#[derive(Clone, Debug, Serialize)]
+pub struct HotkeyEntry {
+    pub keys: String,
+    pub source: HotkeySource,
+    /// What the app runs, for its own bindings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<Action>,
+    /// What the OS does, for its shortcuts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// For the app's bindings, whether the OS currently delivers them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registered: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Hotkey {
+    /// Canonical spelling, e.g. "Ctrl+Alt+S", or "Ctrl+Alt+P M" for a sequence.
+    pub keys: String,
+    pub action: Action,
+}
+
+struct Binding {
+    steps: Vec<Chord>,
+    action: Action,
+}
+
+/// A sequence waiting for its next step.
+struct Pending {
+    steps: Vec<Chord>,
+    /// Registrations of the keys that may come next.
+    keys: Vec<(u32, Chord)>,
+    hint: Option<u64>,
+}
+
+/// Keys a menu took while it's open, and the function they go to.
+struct Grab {
+    keys: Vec<(u32, Chord)>,
+    on_key: fn(&AppHandle, &str),
+}
+
+/// By canonical keys.
+static BINDINGS: Lazy<Mutex<BTreeMap<String, Binding>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+/// OS registration of each first step, shared by the bindings starting with it.
+static LEADERS: Lazy<Mutex<BTreeMap<String, (u32, Chord)>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+/// First steps another app held when the OS was asked to register them.
+static HELD: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
+/// Only touched on the main thread.
+static PENDING: Lazy<Mutex<Option<Pending>>> = Lazy::new(|| Mutex::new(None));
+static GRAB: Lazy<Mutex<Option<Grab>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped whenever a sequence advances or ends, so stale timeouts do nothing.
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+/// Set while the subsystem is on.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+/// Handles registration `id` being pressed. Called by the platform layer; the
+/// sequence state lives on the main thread.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn fire(id: u32) {
+    let Some(app) = APP.lock().unwrap().clone() else {
+        return;
+    };
+    let grabbed = GRAB.lock().unwrap().as_ref().and_then(|grab| {
+        let (_, chord) = grab.keys.iter().find(|(key, _)| *key == id)?;
+        Some((*chord, grab.on_key))
+    });
+    if let Some((chord, on_key)) = grabbed {
+        let handle = app.clone();
+        let _ = app.run_on_main_thread(move || on_key(&handle, &chord.to_string()));
+        return;
+    }
+    let next = PENDING.lock().unwrap().as_ref().and_then(|pending| {
+        let (_, chord) = pending.keys.iter().find(|(key, _)| *key == id)?;
+        Some(*chord)
+    });
+    let (chord, continuing) = match next {
+        Some(chord) => (chord, true),
+        None => {
+            let leaders = LEADERS.lock().unwrap();
+            let Some((_, chord)) = leaders.values().find(|(leader, _)| *leader == id) else {
+                return;
+            };
+            (*chord, false)
+        }
+    };
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || advance(&handle, chord, continuing));
+}
+
+/// Adds `chord` to the sequence under way, or starts one, then runs the binding it
+/// completes or waits for the next step. Runs on the main thread.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn advance(app: &AppHandle, chord: Chord, continuing: bool) {
+    let mut steps = match end_sequence() {
+        Some(steps) if continuing => steps,
+        _ => Vec::new(),
+    };
+    steps.push(chord);
+
+    let bindings = BINDINGS.lock().unwrap();
+    if let Some((keys, binding)) = bindings.iter().find(|(_, b)| b.steps == steps) {
+        let (source, action) = (format!("hotkey:{}", keys), binding.action.clone());
+        drop(bindings);
+        crate::actions::run(app, &action, &source);
+        return;
+    }
+    // Each key that may come next, with what it runs if it completes a binding.
+    let mut next: Vec<(Chord, Option<String>)> = Vec::new();
+    for binding in bindings.values() {
+        if binding.steps.len() <= steps.len() || !binding.steps.starts_with(&steps) {
+            continue;
+        }
+        let chord = binding.steps[steps.len()];
+        let completes = binding.steps.len() == steps.len() + 1;
+        if !next.iter().any(|(known, _)| *known == chord) {
+            next.push((chord, completes.then(|| describe(&binding.action))));
+        }
+    }
+    drop(bindings);
+    // Escape, or any key with nothing bound after it, just ends the sequence.
+    if !next.is_empty() {
+        begin_sequence(app, steps, next);
+    }
+}
+
+/// Takes the keys that may follow `steps` and shows them. Runs on the main thread.
+fn begin_sequence(app: &AppHandle, steps: Vec<Chord>, next: Vec<(Chord, Option<String>)>) {
+    let generation = SEQUENCE.fetch_add(1, Ordering::SeqCst) + 1;
+    let mut hint = format!("{} …", spell(&steps));
+    let mut keys = Vec::new();
+    let cancel = (!next.iter().any(|(chord, _)| *chord == ESCAPE)).then_some((ESCAPE, None));
+    for (chord, completes) in next.iter().cloned().chain(cancel) {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        if let Err(e) = platform::register(id, chord) {
+            log::warn!("sequence key {} not registered: {}", chord, e);
+            continue;
+        }
+        keys.push((id, chord));
+        let label = match completes {
+            Some(action) => action,
+            None if chord == ESCAPE => "cancel".to_string(),
+            None => "…".to_string(),
+        };
+        hint.push_str(&format!("\n{}  {}", chord, label));
+    }
+    let hint = open_hint(&hint)
+        .inspect_err(|e| log::warn!("hotkey hint failed: {}", e))
+        .ok();
+    *PENDING.lock().unwrap() = Some(Pending { steps, keys, hint });
+
+    let handle = app.clone();
+    thread::spawn(move || {
+        thread::sleep(SEQUENCE_TIMEOUT);
+        if SEQUENCE.load(Ordering::SeqCst) == generation {
+            let _ = handle.run_on_main_thread(move || {
+                if SEQUENCE.load(Ordering::SeqCst) == generation {
+                    end_sequence();
+                }
+            });
+        }
+    });
+}
+
+/// Gives the waiting keys back and hides the hint. Returns the steps taken so far.
+/// Runs on the main thread.
+fn end_sequence() -> Option<Vec<Chord>> {
+    SEQUENCE.fetch_add(1, Ordering::SeqCst);
+    let pending = PENDING.lock().unwrap().take()?;
+    for (id, _) in pending.keys {
+        platform::unregister(id);
+    }
+    if let Some(hint) = pending.hint {
+        hud::close(hint);
+    }
+    Some(pending.steps)
+}
+
+/// What a binding runs, for the hint: the command name, or the action's type.
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Command { command, .. } => command.clone(),
+        action => serde_json::to_value(action)
+            .ok()
+            .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    }
+}
+
+/// Opens the hint in the middle of the primary monitor, hidden from capture.
+fn open_hint(text: &str) -> Result<u64, String> {
+    let monitors = coords::monitors();
+    let monitor = monitors
+        .iter()
+        .find(|m| m.primary)
+        .ok_or_else(|| "Monitor not found".to_string())?;
+    let scale = monitor.scale_factor;
+    let lines = text.lines().count() as f64;
+    let rect = coords::resolve_bounds(&Bounds::Anchored {
+        anchor: Anchor::Center,
+        width: HINT_WIDTH * scale,
+        height: HINT_FONT_SIZE * scale * (lines * 1.4 + 1.0),
+        margin_x: 0.0,
+        margin_y: 0.0,
+        monitor: None,
+    })?;
+    let style = HudStyle {
+        font_size: HINT_FONT_SIZE,
+        ..HudStyle::default()
+    };
+    hud::open(rect, text, &style, true)
+}
+
+fn system_shortcut(chord: Chord) -> Option<&'static str> {
+    let keys = chord.to_string();
+    SYSTEM_SHORTCUTS
+        .iter()
+        .find(|(shortcut, _)| *shortcut == keys)
+        .map(|(_, description)| *description)
+}
+
+/// Whether `chord` could be bound as a first step without a known conflict.
+fn free(chord: Chord) -> bool {
+    let keys = chord.to_string();
+    system_shortcut(chord).is_none()
+        && !LEADERS.lock().unwrap().contains_key(&keys)
+        && !HELD.lock().unwrap().contains(&keys)
+        && !BINDINGS.lock().unwrap().contains_key(&keys)
+}
+
+/// Free combinations of `chord`'s key, those closest to its modifiers first.
+fn suggest(chord: Chord) -> Vec<String> {
+    let wanted = chord.modifiers;
+    let mut candidates: Vec<(u32, Chord)> = (1..16u8)
+        .map(|bits| Modifiers {
+            ctrl: bits & 1 != 0,
+            alt: bits & 2 != 0,
+            shift: bits & 4 != 0,
+            meta: bits & 8 != 0,
+        })
+        .filter(|modifiers| *modifiers != wanted)
+        .map(|modifiers| {
+            let distance = [
+                modifiers.ctrl != wanted.ctrl,
+                modifiers.alt != wanted.alt,
+                modifiers.shift != wanted.shift,
+                modifiers.meta != wanted.meta,
+            ]
+            .iter()
+            .filter(|differs| **differs)
+            .count() as u32;
+            (distance, Chord { modifiers, ..chord })
+        })
+        .collect();
+    candidates.sort_by_key(|(distance, _)| *distance);
+    candidates
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .filter(|candidate| free(*candidate))
+        .take(SUGGESTIONS)
+        .map(|candidate| candidate.to_string())
+        .collect()
+}
+
+fn conflict(steps: &[Chord], source: HotkeySource, detail: String) -> HotkeyError {
+    HotkeyError::Conflict {
+        keys: spell(steps),
+        source,
+        detail,
+        suggestions: suggest(steps[0]),
+    }
+}
+
+/// Binds `keys` to `action`, replacing its previous action, or unbinds it when
+/// `action` is `None`. Returns the canonical spelling.
+// The bindings lock is never held across platform calls: Windows registers on the
+// hotkey thread, which takes the lock itself when a hotkey fires.
+pub fn set(keys: &str, action: Option<Action>) -> Result<String, HotkeyError> {
+    let steps = parse_steps(keys)?;
+    let name = spell(&steps);
+    let leader = steps[0].to_string();
+    let active = APP.lock().unwrap().is_some();
+
+    let Some(action) = action else {
+        let mut bindings = BINDINGS.lock().unwrap();
+        bindings.remove(&name);
+        let orphaned = !bindings.values().any(|b| b.steps[0] == steps[0]);
+        drop(bindings);
+        if orphaned {
+            let removed = LEADERS.lock().unwrap().remove(&leader);
+            HELD.lock().unwrap().remove(&leader);
+            if let Some((id, _)) = removed.filter(|_| active) {
+                platform::unregister(id);
+            }
+        }
+        return Ok(name);
+    };
+    action.check()?;
+    {
+        let mut bindings = BINDINGS.lock().unwrap();
+        if let Some(binding) = bindings.get_mut(&name) {
+            binding.action = action;
+            return Ok(name);
+        }
+        // Otherwise the shorter one would always win.
+        let overlap = bindings.iter().find_map(|(other, b)| {
+            if b.steps.starts_with(&steps) {
+                Some(format!("{} starts with {}", other, name))
+            } else if steps.starts_with(&b.steps) {
+                Some(format!("{} starts with {}", name, other))
+            } else {
+                None
+            }
+        });
+        drop(bindings);
+        if let Some(detail) = overlap {
+            return Err(conflict(&steps, HotkeySource::App, detail));
+        }
+    }
+    if let Some(description) = system_shortcut(steps[0]) {
+        let detail = format!("{} is the system shortcut for \"{}\"", leader, description);
+        return Err(conflict(&steps, HotkeySource::System, detail));
+    }
+
+    let registered = LEADERS.lock().unwrap().contains_key(&leader);
+    if !registered {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        if active {
+            match platform::register(id, steps[0]) {
+                Ok(()) => {}
+                Err(RegisterError::InUse) => {
+                    let detail = format!("Another app already registered {}", leader);
+                    return Err(conflict(&steps, HotkeySource::OtherApp, detail));
+                }
+                Err(e) => return Err(format!("Can't bind {}: {}", name, e).into()),
+            }
+        }
+        LEADERS.lock().unwrap().insert(leader, (id, steps[0]));
+    }
+    crash::breadcrumb("hotkeys", format!("bind {}", name));
+    BINDINGS
+        .lock()
+        .unwrap()
+        .insert(name.clone(), Binding { steps, action });
+    Ok(name)
+}
+
+pub fn list() -> Vec<Hotkey> {
+    BINDINGS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(keys, binding)| Hotkey {
+            keys: keys.clone(),
+            action: binding.action.clone(),
+        })
+        .collect()
+}
+
+/// The app's bindings, the OS's shortcuts and the combinations other apps hold.
+pub fn entries() -> Vec<HotkeyEntry> {
+    let active = APP.lock().unwrap().is_some();
+    let held = HELD.lock().unwrap().clone();
+    let mut entries: Vec<HotkeyEntry> = BINDINGS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(keys, binding)| HotkeyEntry {
+            keys: keys.clone(),
+            source: HotkeySource::App,
+            action: Some(binding.action.clone()),
+            description: None,
+            registered: Some(active && !held.contains(&binding.steps[0].to_string())),
+        })
+        .collect();
+    entries.extend(held.into_iter().map(|keys| HotkeyEntry {
+        keys,
+        source: HotkeySource::OtherApp,
+        action: None,
+        description: None,
+        registered: None,
+    }));
+    entries.extend(
+        SYSTEM_SHORTCUTS
+            .iter()
+            .map(|(keys, description)| HotkeyEntry {
+                keys: keys.to_string(),
+                source: HotkeySource::System,
+                action: None,
+                description: Some(description.to_string()),
+                registered: None,
+            }),
+    );
+    entries
+}
+
+/// Registers every binding with the OS. A binding another app holds stays
+/// unregistered until the subsystem restarts.
+pub fn start(app: AppHandle) -> Result<(), String> {
+    *APP.lock().unwrap() = Some(app);
+    let leaders: Vec<(String, u32, Chord)> = LEADERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(keys, (id, chord))| (keys.clone(), *id, *chord))
+        .collect();
+    let mut held = HELD.lock().unwrap();
+    held.clear();
+    for (keys, id, chord) in leaders {
+        if let Err(e) = platform::register(id, chord) {
+            log::warn!("hotkey {} not registered: {}", keys, e);
+            if matches!(e, RegisterError::InUse) {
+                held.insert(keys);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Takes `keys`, which may be bare, from other apps and hands each press to `on_key`
+/// with the key's canonical spelling, until `release`. Keys that can't be taken are
+/// skipped. Runs on the main thread.
+pub fn grab(keys: &[&str], on_key: fn(&AppHandle, &str)) -> Result<(), String> {
+    if APP.lock().unwrap().is_none() {
+        return Err("Global hotkeys are off".to_string());
+    }
+    release();
+    let mut taken = Vec::new();
+    for keys in keys {
+        let chord = Chord::parse_step(keys)?;
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        match platform::register(id, chord) {
+            Ok(()) => taken.push((id, chord)),
+            Err(e) => log::warn!("key {} not grabbed: {}", chord, e),
+        }
+    }
+    *GRAB.lock().unwrap() = Some(Grab {
+        keys: taken,
+        on_key,
+    });
+    Ok(())
+}
+
+/// Gives the keys `grab` took back. Runs on the main thread.
+pub fn release() {
+    let Some(grab) = GRAB.lock().unwrap().take() else {
+        return;
+    };
+    for (id, _) in grab.keys {
+        platform::unregister(id);
+    }
+}
+
+pub fn stop() -> Result<(), String> {
+    *APP.lock().unwrap() = None;
+    end_sequence();
+    release();
+    let ids: Vec<u32> = LEADERS
+        .lock()
+        .unwrap()
+        .values()
+        .map(|(id, _)| *id)
+        .collect();
+    for id in ids {
+        platform::unregister(id);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::sync::mpsc;
+
+    use once_cell::sync::Lazy;
+    use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+        MOD_SHIFT, MOD_WIN,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_APP, WM_HOTKEY,
+    };
+
+    use super::{Chord, Key, RegisterError};
+
+    /// Posted to the hotkey thread when a request is waiting in its channel.
+    const WM_HOTKEY_REQUEST: u32 = WM_APP + 1;
+
+    /// Hotkeys registered without a window belong to the registering thread, so
+    /// registration happens on the thread that receives them.
+    enum Request {
+        Register {
+            id: u32,
+            modifiers: HOT_KEY_MODIFIERS,
+            vk: u32,
+            reply: mpsc::Sender<Result<(), RegisterError>>,
+        },
+        Unregister {
+            id: u32,
+        },
+    }
+
+    struct Listener {
+        thread: u32,
+        requests: mpsc::Sender<Request>,
+    }
+
+    static LISTENER: Lazy<Result<Listener, String>> = Lazy::new(|| {
+        let (requests, inbox) = mpsc::channel::<Request>();
+        let (ready, started) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("hotkeys".to_string())
+            .spawn(move || unsafe {
+                let mut msg = MSG::default();
+                // Creates the thread's message queue before anyone posts to it.
+                let _ = PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE);
+                let _ = ready.send(GetCurrentThreadId());
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    match msg.message {
+                        WM_HOTKEY => super::fire(msg.wParam.0 as u32),
+                        WM_HOTKEY_REQUEST => {
+                            while let Ok(request) = inbox.try_recv() {
+                                handle(request);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn hotkey thread: {}", e))?;
+        let thread = started
+            .recv()
+            .map_err(|_| "Hotkey thread failed to start".to_string())?;
+        Ok(Listener { thread, requests })
+    });
+
+    fn handle(request: Request) {
+        match request {
+            Request::Register {
+                id,
+                modifiers,
+                vk,
+                reply,
+            } => {
+                let registered =
+                    unsafe { RegisterHotKey(HWND::default(), id as i32, modifiers, vk) };
+                let _ = reply.send(registered.map_err(|_| RegisterError::InUse));
+            }
+            Request::Unregister { id } => {
+                let _ = unsafe { UnregisterHotKey(HWND::default(), id as i32) };
+            }
+        }
+    }
+
+    fn send(request: Request) -> Result<(), String> {
+        let listener = LISTENER.as_ref().map_err(Clone::clone)?;
+        listener
+            .requests
+            .send(request)
+            .map_err(|_| "Hotkey thread has stopped".to_string())?;
+        unsafe { PostThreadMessageW(listener.thread, WM_HOTKEY_REQUEST, WPARAM(0), LPARAM(0)) }
+            .map_err(|e| format!("Failed to reach hotkey thread: {}", e))
+    }
+
+    fn virtual_key(key: Key) -> u32 {
+        match key {
+            Key::Letter(letter) => letter as u32,
+            Key::Digit(digit) => b'0' as u32 + digit as u32,
+            Key::Function(n) => 0x70 + n as u32 - 1,
+            Key::Space => 0x20,
+            Key::Enter => 0x0D,
+            Key::Escape => 0x1B,
+            Key::Tab => 0x09,
+            Key::Backspace => 0x08,
+            Key::Delete => 0x2E,
+            Key::Insert => 0x2D,
+            Key::Home => 0x24,
+            Key::End => 0x23,
+            Key::PageUp => 0x21,
+            Key::PageDown => 0x22,
+            Key::Left => 0x25,
+            Key::Up => 0x26,
+            Key::Right => 0x27,
+            Key::Down => 0x28,
+        }
+    }
+
+    pub fn register(id: u32, chord: Chord) -> Result<(), RegisterError> {
+        let m = chord.modifiers;
+        // Holding the keys down fires once.
+        let mut modifiers = MOD_NOREPEAT;
+        for (on, flag) in [
+            (m.ctrl, MOD_CONTROL),
+            (m.alt, MOD_ALT),
+            (m.shift, MOD_SHIFT),
+            (m.meta, MOD_WIN),
+        ] {
+            if on {
+                modifiers |= flag;
+            }
+        }
+        let (reply, answer) = mpsc::channel();
+        send(Request::Register {
+            id,
+            modifiers,
+            vk: virtual_key(chord.key),
+            reply,
+        })
+        .map_err(RegisterError::Failed)?;
+        answer
+            .recv()
+            .map_err(|_| RegisterError::Failed("Hotkey thread has stopped".to_string()))?
+    }
+
+    pub fn unregister(id: u32) {
+        let _ = send(Request::Unregister { id });
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{collections::HashMap, ffi::c_void, sync::Mutex};
+
+    use once_cell::sync::Lazy;
+
+    use super::{Chord, Key, RegisterError};
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct EventHotKeyID {
+        signature: u32,
+        id: u32,
+    }
+
+    #[repr(C)]
+    struct EventTypeSpec {
+        event_class: u32,
+        event_kind: u32,
+    }
+
+    type EventHandler = extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> i32;
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn GetApplicationEventTarget() -> *mut c_void;
+        fn InstallEventHandler(
+            target: *mut c_void,
+            handler: EventHandler,
+            type_count: usize,
+            types: *const EventTypeSpec,
+            user_data: *mut c_void,
+            out_ref: *mut *mut c_void,
+        ) -> i32;
+        fn RegisterEventHotKey(
+            key_code: u32,
+            modifiers: u32,
+            id: EventHotKeyID,
+            target: *mut c_void,
+            options: u32,
+            out_ref: *mut *mut c_void,
+        ) -> i32;
+        fn UnregisterEventHotKey(hot_key: *mut c_void) -> i32;
+        fn GetEventParameter(
+            event: *mut c_void,
+            name: u32,
+            desired_type: u32,
+            actual_type: *mut u32,
+            size: usize,
+            actual_size: *mut usize,
+            data: *mut c_void,
+        ) -> i32;
+    }
+
+    const SIGNATURE: u32 = u32::from_be_bytes(*b"SSHK");
+    const EVENT_CLASS_KEYBOARD: u32 = u32::from_be_bytes(*b"keyb");
+    const EVENT_HOT_KEY_PRESSED: u32 = 5;
+    const PARAM_DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+    const TYPE_HOT_KEY_ID: u32 = u32::from_be_bytes(*b"hkid");
+    const CMD_KEY: u32 = 0x0100;
+    const SHIFT_KEY: u32 = 0x0200;
+    const OPTION_KEY: u32 = 0x0800;
+    const CONTROL_KEY: u32 = 0x1000;
+
+    /// Carbon's reference per registration id.
+    static HOT_KEYS: Lazy<Mutex<HashMap<u32, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    extern "C" fn hot_key_pressed(
+        _call: *mut c_void,
+        event: *mut c_void,
+        _data: *mut c_void,
+    ) -> i32 {
+        let mut hot_key = EventHotKeyID {
+            signature: 0,
+            id: 0,
+        };
+        let status = unsafe {
+            GetEventParameter(
+                event,
+                PARAM_DIRECT_OBJECT,
+                TYPE_HOT_KEY_ID,
+                std::ptr::null_mut(),
+                std::mem::size_of::<EventHotKeyID>(),
+                std::ptr::null_mut(),
+                &mut hot_key as *mut EventHotKeyID as *mut c_void,
+            )
+        };
+        if status == 0 && hot_key.signature == SIGNATURE {
+            super::fire(hot_key.id);
+        }
+        0
+    }
+
+    /// Installed once, on first registration.
+    static HANDLER: Lazy<Result<(), String>> = Lazy::new(|| {
+        let spec = EventTypeSpec {
+            event_class: EVENT_CLASS_KEYBOARD,
+            event_kind: EVENT_HOT_KEY_PRESSED,
+        };
+        let status = unsafe {
+            InstallEventHandler(
+                GetApplicationEventTarget(),
+                hot_key_pressed,
+                1,
+                &spec,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if status != 0 {
+            return Err(format!(
+                "Failed to install hotkey handler (status {})",
+                status
+            ));
+        }
+        Ok(())
+    });
+
+    /// Virtual key codes of the ANSI layout, as in HIToolbox's Events.h.
+    fn key_code(key: Key) -> Option<u32> {
+        const LETTERS: [u32; 26] = [
+            0x00, 0x0B, 0x08, 0x02, 0x0E, 0x03, 0x05, 0x04, 0x22, 0x26, 0x28, 0x25, 0x2E, 0x2D,
+            0x1F, 0x23, 0x0C, 0x0F, 0x01, 0x11, 0x20, 0x09, 0x0D, 0x07, 0x10, 0x06,
+        ];
+        const DIGITS: [u32; 10] = [0x1D, 0x12, 0x13, 0x14, 0x15, 0x17, 0x16, 0x1A, 0x1C, 0x19];
+        const FUNCTIONS: [u32; 20] = [
+            0x7A, 0x78, 0x63, 0x76, 0x60, 0x61, 0x62, 0x64, 0x65, 0x6D, 0x67, 0x6F, 0x69, 0x6B,
+            0x71, 0x6A, 0x40, 0x4F, 0x50, 0x5A,
+        ];
+        Some(match key {
+            Key::Letter(letter) => LETTERS[(letter - b'A') as usize],
+            Key::Digit(digit) => DIGITS[digit as usize],
+            Key::Function(n) => *FUNCTIONS.get(n as usize - 1)?,
+            Key::Space => 0x31,
+            Key::Enter => 0x24,
+            Key::Escape => 0x35,
+            Key::Tab => 0x30,
+            Key::Backspace => 0x33,
+            Key::Delete => 0x75,
+            // Mac keyboards have no Insert key.
+            Key::Insert => return None,
+            Key::Home => 0x73,
+            Key::End => 0x77,
+            Key::PageUp => 0x74,
+            Key::PageDown => 0x79,
+            Key::Left => 0x7B,
+            Key::Right => 0x7C,
+            Key::Down => 0x7D,
+            Key::Up => 0x7E,
+        })
+    }
+
+    /// Runs on the main thread.
+    pub fn register(id: u32, chord: Chord) -> Result<(), RegisterError> {
+        HANDLER.clone().map_err(RegisterError::Failed)?;
+        let code = key_code(chord.key)
+            .ok_or_else(|| RegisterError::Failed("the key doesn't exist on macOS".to_string()))?;
+        let m = chord.modifiers;
+        let modifiers = [
+            (m.ctrl, CONTROL_KEY),
+            (m.alt, OPTION_KEY),
+            (m.shift, SHIFT_KEY),
+            (m.meta, CMD_KEY),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .fold(0, |all, (_, flag)| all | flag);
+        let mut hot_key = std::ptr::null_mut();
+        let status = unsafe {
+            RegisterEventHotKey(
+                code,
+                modifiers,
+                EventHotKeyID {
+                    signature: SIGNATURE,
+                    id,
+                },
+                GetApplicationEventTarget(),
+                0,
+                &mut hot_key,
+            )
+        };
+        if status != 0 {
+            return Err(RegisterError::InUse);
+        }
+        HOT_KEYS.lock().unwrap().insert(id, hot_key as usize);
+        Ok(())
+    }
+
+    pub fn unregister(id: u32) {
+        if let Some(hot_key) = HOT_KEYS.lock().unwrap().remove(&id) {
+            unsafe {
+                UnregisterEventHotKey(hot_key as *mut c_void);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::{Chord, RegisterError};
+
+    pub fn register(_id: u32, _chord: Chord) -> Result<(), RegisterError> {
+        Err(RegisterError::Failed(
+            "global hotkeys are only supported on macOS and Windows".to_string(),
+        ))
+    }
+
+    pub fn unregister(_id: u32) {}
+}
+
+// ==========================================
+// HOTKEY COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_hotkeys() -> Vec<Hotkey> {
+    list()
+}
+
+/// Binds `keys` (e.g. "Ctrl+Alt+S", or "Ctrl+Alt+P M" for Ctrl+Alt+P then M) to
+/// `action`, or unbinds them when `action` is omitted. A `command` action runs any registered command with arguments, e.g.
+/// `toggle_overlay_for_monitor` for one display. Returns the canonical spelling of
+/// the keys. Keys another binding, the OS or another app holds fail with a
+/// `conflict` error suggesting free alternatives.
+#[tauri::command]
+pub fn set_hotkey(keys: String, action: Option<Action>) -> Result<String, HotkeyError> {
+    set(&keys, action)
+}
+
+/// Lists the app's bindings and whether the OS delivers them, the OS's own
+/// shortcuts, and the combinations other apps hold.
+#[tauri::command]
+pub fn list_hotkeys() -> Vec<HotkeyEntry> {
+    entries()
+}
@@ -0,0 +1,696 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    coords::Rect,
+    palette::{self, ColorRole},
+    shape::{Shape, WindowShape},
+};
+
+// ==========================================
+// HUD Windows
+// ==========================================
+
+// Click-through, always-on-top windows that show a block of text, optionally hidden
+// from screen capture so only the presenter sees them. Like the privacy overlay,
+// every function here must run on the main thread.
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HudStyle {
+    /// Device-independent pixels.
+    #[serde(default = "default_font_size")]
+    pub font_size: f64,
+    /// "#RRGGBB"; the palette's text color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// "#RRGGBB"; the palette's background color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    /// Window opacity from 0 to 1.
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    #[serde(flatten)]
+    pub shape: WindowShape,
+    #[serde(default)]
+    pub shadow: HudShadow,
+}
+
+/// Keeps text legible over busy content showing through a translucent HUD.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HudShadow {
+    #[default]
+    None,
+    /// A shadow under the window and its text.
+    Drop,
+    /// A halo of the background color around the text.
+    Glow,
+}
+
+fn default_font_size() -> f64 {
+    48.0
+}
+
+fn default_opacity() -> f64 {
+    0.9
+}
+
+impl Default for HudStyle {
+    fn default() -> Self {
+        Self {
+            font_size: default_font_size(),
+            color: None,
+            background: None,
+            opacity: default_opacity(),
+            shape: WindowShape::default(),
+            shadow: HudShadow::default(),
+        }
+    }
+}
+
+/// A validated style, ready for the platform layer.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+struct Paint {
+    font_size: f64,
+    color: (u8, u8, u8),
+    background: (u8, u8, u8),
+    opacity: f64,
+    shape: Shape,
+    shadow: HudShadow,
+}
+
+impl HudStyle {
+    /// Fails the way opening a HUD with this style would.
+    pub fn check(&self) -> Result<(), String> {
+        self.paint().map(|_| ())
+    }
+
+    fn paint(&self) -> Result<Paint, String> {
+        if !(self.font_size > 0.0 && self.font_size <= 400.0) {
+            return Err(format!("Font size {} is out of range", self.font_size));
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err(format!("Opacity {} is not between 0 and 1", self.opacity));
+        }
+        Ok(Paint {
+            font_size: self.font_size,
+            color: self
+                .color
+                .as_deref()
+                .map_or(Ok(palette::color(ColorRole::Text)), palette::parse)?,
+            background: self
+                .background
+                .as_deref()
+                .map_or(Ok(palette::color(ColorRole::Background)), palette::parse)?,
+            opacity: self.opacity,
+            shape: self.shape.shape()?,
+            shadow: self.shadow,
+        })
+    }
+}
+
+/// Error from an update that names the revision it was based on. Stale updates are
+/// rejected rather than overwriting whatever another client wrote since.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum UpdateError<T> {
+    /// The state moved past `expected`; `current` is what the caller should rebase on.
+    Conflict {
+        expected: u64,
+        current: T,
+    },
+    Failed {
+        message: String,
+    },
+}
+
+impl<T> From<String> for UpdateError<T> {
+    fn from(message: String) -> Self {
+        UpdateError::Failed { message }
+    }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+/// HUD id → platform window handle.
+static HUDS: Lazy<Mutex<HashMap<u64, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Opens a HUD covering `rect` (native coordinates) and returns its id.
+pub fn open(
+    rect: Rect,
+    text: &str,
+    style: &HudStyle,
+    exclude_from_capture: bool,
+) -> Result<u64, String> {
+    let paint = style.paint()?;
+    let handle = platform::open(rect, text, paint, exclude_from_capture)?;
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    HUDS.lock().unwrap().insert(id, handle);
+    Ok(id)
+}
+
+/// Replaces the text of HUD `id`.
+pub fn set_text(id: u64, text: &str) -> Result<(), String> {
+    let handle = *HUDS
+        .lock()
+        .unwrap()
+        .get(&id)
+        .ok_or_else(|| format!("HUD {} not found", id))?;
+    platform::set_text(handle, text);
+    Ok(())
+}
+
+/// Moves HUD `id` to cover `rect` (native coordinates).
+pub fn set_rect(id: u64, rect: Rect) -> Result<(), String> {
+    let handle = *HUDS
+        .lock()
+        .unwrap()
+        .get(&id)
+        .ok_or_else(|| format!("HUD {} not found", id))?;
+    platform::set_rect(handle, rect);
+    Ok(())
+}
+
+/// Scales HUD `id`'s opacity by `visibility` (0 to 1), e.g. to fade it out.
+pub fn set_fade(id: u64, visibility: f64) -> Result<(), String> {
+    let handle = *HUDS
+        .lock()
+        .unwrap()
+        .get(&id)
+        .ok_or_else(|| format!("HUD {} not found", id))?;
+    platform::set_fade(handle, visibility.clamp(0.0, 1.0));
+    Ok(())
+}
+
+pub fn close(id: u64) {
+    if let Some(handle) = HUDS.lock().unwrap().remove(&id) {
+        platform::close(handle);
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{collections::HashMap, ffi::c_void, sync::Mutex};
+
+    use once_cell::sync::Lazy;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows::Win32::Graphics::Dwm::{
+        DwmExtendFrameIntoClientArea, DwmSetWindowAttribute, DWMNCRENDERINGPOLICY, DWMNCRP_ENABLED,
+        DWMWA_NCRENDERING_POLICY,
+    };
+    use windows::Win32::Graphics::Gdi::{
+        BeginPaint, CreateFontW, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint, FillRect,
+        InvalidateRect, SelectObject, SetBkMode, SetTextColor, CLEARTYPE_QUALITY,
+        CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DT_CALCRECT, DT_CENTER, DT_WORDBREAK, FW_BOLD,
+        OUT_DEFAULT_PRECIS, PAINTSTRUCT, TRANSPARENT,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Controls::MARGINS;
+    use windows::Win32::UI::HiDpi::GetDpiForWindow;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, GetClientRect, RegisterClassW,
+        SetLayeredWindowAttributes, SetWindowDisplayAffinity, SetWindowPos, ShowWindow, LWA_ALPHA,
+        SWP_NOACTIVATE, SWP_NOZORDER, SW_SHOWNOACTIVATE, WDA_NONE, WM_PAINT, WM_SIZE, WNDCLASSW,
+        WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT,
+        WS_POPUP,
+    };
+
+    use super::{HudShadow, Paint};
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+    use crate::{palette, shape};
+
+    const CLASS_NAME: PCWSTR = w!("ScreenShareHostHud");
+
+    /// Text and paint per window, read by `WM_PAINT`.
+    static CONTENT: Lazy<Mutex<HashMap<isize, (String, Paint)>>> =
+        Lazy::new(|| Mutex::new(HashMap::new()));
+    static REGISTERED: Lazy<bool> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(wnd_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        RegisterClassW(&class) != 0
+    });
+
+    unsafe fn paint(hwnd: HWND) {
+        let Some((text, paint)) = CONTENT.lock().unwrap().get(&(hwnd.0 as isize)).cloned() else {
+            return;
+        };
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+        let brush = CreateSolidBrush(palette::colorref(paint.background));
+        FillRect(hdc, &client, brush);
+        let _ = DeleteObject(brush);
+        shape::draw_border(hwnd, hdc, &paint.shape);
+
+        let scale = GetDpiForWindow(hwnd) as f64 / 96.0;
+        let font = CreateFontW(
+            -(paint.font_size * scale).round() as i32,
+            0,
+            0,
+            0,
+            FW_BOLD.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_DEFAULT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            CLEARTYPE_QUALITY.0 as u32,
+            0,
+            w!("Segoe UI"),
+        );
+        let previous = SelectObject(hdc, font);
+        SetBkMode(hdc, TRANSPARENT);
+
+        // Word-wrapped and centered on both axes.
+        let padding = (paint.font_size * scale / 2.0).round() as i32;
+        let mut area = RECT {
+            left: client.left + padding,
+            top: client.top,
+            right: client.right - padding,
+            bottom: client.bottom,
+        };
+        let mut text: Vec<u16> = text.encode_utf16().collect();
+        let mut measured = area;
+        DrawTextW(
+            hdc,
+            &mut text,
+            &mut measured,
+            DT_CENTER | DT_WORDBREAK | DT_CALCRECT,
+        );
+        area.top += ((area.bottom - area.top) - (measured.bottom - measured.top)).max(0) / 2;
+
+        // GDI has no text effects, so shadows are the text drawn again underneath,
+        // offset once for a drop shadow and all around for a glow.
+        let d = (2.0 * scale).round().max(1.0) as i32;
+        let (color, offsets): (_, &[(i32, i32)]) = match paint.shadow {
+            HudShadow::None => ((0, 0, 0), &[]),
+            HudShadow::Drop => ((0, 0, 0), &[(d, d)]),
+            HudShadow::Glow => (
+                paint.background,
+                &[
+                    (-d, -d),
+                    (0, -d),
+                    (d, -d),
+                    (-d, 0),
+                    (d, 0),
+                    (-d, d),
+                    (0, d),
+                    (d, d),
+                ],
+            ),
+        };
+        SetTextColor(hdc, palette::colorref(color));
+        for &(dx, dy) in offsets {
+            let mut shifted = RECT {
+                left: area.left + dx,
+                top: area.top + dy,
+                right: area.right + dx,
+                bottom: area.bottom + dy,
+            };
+            DrawTextW(hdc, &mut text, &mut shifted, DT_CENTER | DT_WORDBREAK);
+        }
+        SetTextColor(hdc, palette::colorref(paint.color));
+        DrawTextW(hdc, &mut text, &mut area, DT_CENTER | DT_WORDBREAK);
+
+        SelectObject(hdc, previous);
+        let _ = DeleteObject(font);
+        let _ = EndPaint(hwnd, &ps);
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_PAINT => {
+                paint(hwnd);
+                LRESULT(0)
+            }
+            WM_SIZE => {
+                let content = CONTENT.lock().unwrap().get(&(hwnd.0 as isize)).cloned();
+                if let Some((_, paint)) = content {
+                    shape::clip(hwnd, &paint.shape);
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Has DWM draw its window shadow around the borderless window. Best effort: a
+    /// HUD without a shadow is still usable.
+    unsafe fn drop_shadow(hwnd: HWND) {
+        let policy = DWMNCRP_ENABLED;
+        let _ = DwmSetWindowAttribute(
+            hwnd,
+            DWMWA_NCRENDERING_POLICY,
+            &policy as *const _ as *const c_void,
+            std::mem::size_of::<DWMNCRENDERINGPOLICY>() as u32,
+        );
+        // DWM only shadows windows whose frame reaches into the client area.
+        let margins = MARGINS {
+            cxLeftWidth: 1,
+            cxRightWidth: 1,
+            cyTopHeight: 1,
+            cyBottomHeight: 1,
+        };
+        let _ = DwmExtendFrameIntoClientArea(hwnd, &margins);
+    }
+
+    pub fn open(
+        rect: Rect,
+        text: &str,
+        paint: Paint,
+        exclude_from_capture: bool,
+    ) -> Result<usize, String> {
+        if !*REGISTERED {
+            return Err("Failed to register HUD window class".to_string());
+        }
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST
+                    | WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TRANSPARENT
+                    | WS_EX_NOACTIVATE,
+                CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null()).map_err(|e| e.to_string())?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create HUD window: {}", e))?;
+
+            let setup = SetLayeredWindowAttributes(
+                hwnd,
+                COLORREF(0),
+                (paint.opacity * 255.0).round() as u8,
+                LWA_ALPHA,
+            )
+            .and_then(|_| {
+                if exclude_from_capture {
+                    crate::affinity::apply(hwnd)
+                } else {
+                    SetWindowDisplayAffinity(hwnd, WDA_NONE)
+                }
+            });
+            if let Err(e) = setup {
+                let _ = DestroyWindow(hwnd);
+                return Err(format!("Failed to configure HUD window: {}", e));
+            }
+
+            CONTENT
+                .lock()
+                .unwrap()
+                .insert(hwnd.0 as isize, (text.to_string(), paint));
+            shape::clip(hwnd, &paint.shape);
+            if paint.shadow == HudShadow::Drop {
+                drop_shadow(hwnd);
+            }
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            layers::add(Layer::Hud, hwnd.0 as usize);
+            Ok(hwnd.0 as usize)
+        }
+    }
+
+    pub fn set_text(handle: usize, text: &str) {
+        if let Some(content) = CONTENT.lock().unwrap().get_mut(&(handle as isize)) {
+            content.0 = text.to_string();
+        }
+        unsafe {
+            let _ = InvalidateRect(HWND(handle as *mut _), None, true);
+        }
+    }
+
+    pub fn set_rect(handle: usize, rect: Rect) {
+        unsafe {
+            let _ = SetWindowPos(
+                HWND(handle as *mut _),
+                HWND::default(),
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                SWP_NOACTIVATE | SWP_NOZORDER,
+            );
+        }
+    }
+
+    pub fn set_fade(handle: usize, visibility: f64) {
+        let Some(opacity) = CONTENT
+            .lock()
+            .unwrap()
+            .get(&(handle as isize))
+            .map(|(_, paint)| paint.opacity)
+        else {
+            return;
+        };
+        unsafe {
+            let _ = SetLayeredWindowAttributes(
+                HWND(handle as *mut _),
+                COLORREF(0),
+                (opacity * visibility * 255.0).round() as u8,
+                LWA_ALPHA,
+            );
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        CONTENT.lock().unwrap().remove(&(handle as isize));
+        unsafe {
+            let _ = DestroyWindow(HWND(handle as *mut _));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{collections::HashMap, ffi::c_void, sync::Mutex};
+
+    use cocoa::appkit::{NSBackingStoreType, NSWindow, NSWindowStyleMask};
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSArray, NSPoint, NSRect, NSSize, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+    use once_cell::sync::Lazy;
+
+    use super::{HudShadow, Paint};
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+    use crate::{palette, shape};
+
+    const SHARING_NONE: u64 = 0;
+    const SHARING_READ_ONLY: u64 = 1;
+    const TEXT_ALIGNMENT_CENTER: u64 = 2;
+    const CAN_JOIN_ALL_SPACES: u64 = 1;
+
+    /// Window → label.
+    static LABELS: Lazy<Mutex<HashMap<usize, usize>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+    /// Cocoa frames are bottom-left based, relative to the primary screen.
+    unsafe fn cocoa_frame(rect: Rect) -> NSRect {
+        let screens: id = msg_send![class!(NSScreen), screens];
+        let primary: NSRect = msg_send![screens.objectAtIndex(0), frame];
+        NSRect::new(
+            NSPoint::new(
+                rect.x as f64,
+                primary.size.height - (rect.y + rect.height) as f64,
+            ),
+            NSSize::new(rect.width as f64, rect.height as f64),
+        )
+    }
+
+    /// Word-wraps the label and centers it vertically in the window.
+    unsafe fn layout(window: id, label: id) {
+        let content: id = msg_send![window, contentView];
+        let bounds: NSRect = msg_send![content, bounds];
+        let font: id = msg_send![label, font];
+        let size: f64 = msg_send![font, pointSize];
+        let padding = size / 2.0;
+        let width = (bounds.size.width - 2.0 * padding).max(1.0);
+
+        let cell: id = msg_send![label, cell];
+        let fitting: NSSize = msg_send![cell, cellSizeForBounds: NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(width, f64::MAX),
+        )];
+        let height = fitting.height.min(bounds.size.height);
+        let frame = NSRect::new(
+            NSPoint::new(padding, (bounds.size.height - height) / 2.0),
+            NSSize::new(width, height),
+        );
+        let _: () = msg_send![label, setFrame: frame];
+    }
+
+    /// A window shadow and a text shadow for `Drop`; a halo of the background color
+    /// around the text for `Glow`.
+    unsafe fn apply_shadow(window: id, label: id, paint: &Paint) {
+        let (color, offset, radius) = match paint.shadow {
+            HudShadow::None => return,
+            HudShadow::Drop => ((0, 0, 0), NSSize::new(1.0, -2.0), 2.0),
+            HudShadow::Glow => (paint.background, NSSize::new(0.0, 0.0), 6.0),
+        };
+        if paint.shadow == HudShadow::Drop {
+            let _: () = msg_send![window, setHasShadow: YES];
+        }
+        let _: () = msg_send![label, setWantsLayer: YES];
+        let layer: id = msg_send![label, layer];
+        if layer == nil {
+            return;
+        }
+        let color: *const c_void = msg_send![palette::ns_color(color, 1.0), CGColor];
+        let _: () = msg_send![layer, setShadowColor: color];
+        let _: () = msg_send![layer, setShadowOffset: offset];
+        let _: () = msg_send![layer, setShadowRadius: radius];
+        let _: () = msg_send![layer, setShadowOpacity: 1.0f32];
+    }
+
+    pub fn open(
+        rect: Rect,
+        text: &str,
+        paint: Paint,
+        exclude_from_capture: bool,
+    ) -> Result<usize, String> {
+        unsafe {
+            let frame = cocoa_frame(rect);
+            let window: id = msg_send![class!(NSWindow), alloc];
+            let window: id = window.initWithContentRect_styleMask_backing_defer_(
+                frame,
+                NSWindowStyleMask::NSBorderlessWindowMask,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                false,
+            );
+            if window == nil {
+                return Err("Failed to create HUD window".to_string());
+            }
+            let _: () = msg_send![window, setReleasedWhenClosed: NO];
+            let _: () = msg_send![window, setOpaque: NO];
+            let background = palette::ns_color(paint.background, paint.opacity);
+            window.setBackgroundColor_(background);
+            if !paint.shape.is_plain() {
+                shape::apply(window, &paint.shape, background);
+            }
+            let _: () = msg_send![window, setIgnoresMouseEvents: YES];
+            let _: () = msg_send![window, setCollectionBehavior: CAN_JOIN_ALL_SPACES];
+            let sharing = if exclude_from_capture {
+                SHARING_NONE
+            } else {
+                SHARING_READ_ONLY
+            };
+            let _: () = msg_send![window, setSharingType: sharing];
+            layers::add(Layer::Hud, window as usize);
+
+            let string = NSString::alloc(nil).init_str(text);
+            let label: id = msg_send![class!(NSTextField), labelWithString: string];
+            let _: () = msg_send![string, release];
+            let font: id = msg_send![class!(NSFont), boldSystemFontOfSize: paint.font_size];
+            let _: () = msg_send![label, setFont: font];
+            let _: () = msg_send![label, setTextColor: palette::ns_color(paint.color, 1.0)];
+            let _: () = msg_send![label, setAlignment: TEXT_ALIGNMENT_CENTER];
+            let _: () = msg_send![label, setMaximumNumberOfLines: 0isize];
+            let content: id = msg_send![window, contentView];
+            let _: () = msg_send![content, addSubview: label];
+            layout(window, label);
+            apply_shadow(window, label, &paint);
+
+            window.orderFrontRegardless();
+            LABELS
+                .lock()
+                .unwrap()
+                .insert(window as usize, label as usize);
+            Ok(window as usize)
+        }
+    }
+
+    pub fn set_text(handle: usize, text: &str) {
+        let Some(label) = LABELS.lock().unwrap().get(&handle).copied() else {
+            return;
+        };
+        unsafe {
+            let string = NSString::alloc(nil).init_str(text);
+            let _: () = msg_send![label as id, setStringValue: string];
+            let _: () = msg_send![string, release];
+            layout(handle as id, label as id);
+        }
+    }
+
+    pub fn set_rect(handle: usize, rect: Rect) {
+        let Some(label) = LABELS.lock().unwrap().get(&handle).copied() else {
+            return;
+        };
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, setFrame: cocoa_frame(rect) display: YES];
+            layout(window, label as id);
+        }
+    }
+
+    /// The paint's opacity is in the background color, so the window's own alpha is
+    /// free for fading.
+    pub fn set_fade(handle: usize, visibility: f64) {
+        unsafe {
+            let _: () = msg_send![handle as id, setAlphaValue: visibility];
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        LABELS.lock().unwrap().remove(&handle);
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, close];
+            let _: () = msg_send![window, release];
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::Paint;
+    use crate::coords::Rect;
+
+    // X11 has no way to keep a window out of other clients' captures.
+    pub fn open(
+        _rect: Rect,
+        _text: &str,
+        _paint: Paint,
+        _exclude_from_capture: bool,
+    ) -> Result<usize, String> {
+        Err("HUD windows are not supported on this platform".to_string())
+    }
+
+    pub fn set_text(_handle: usize, _text: &str) {}
+
+    pub fn set_rect(_handle: usize, _rect: Rect) {}
+
+    pub fn set_fade(_handle: usize, _visibility: f64) {}
+
+    pub fn close(_handle: usize) {}
+}
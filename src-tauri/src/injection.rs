@@ -0,0 +1,332 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    coords, crash, input,
+    locks::{self, LockResource},
+    sessions::{self, Resource},
+    timing::{Scheduler, TimingMetrics},
+    vsync,
+};
+
+// ==========================================
+// Input Jobs & Injection Profiles
+// ==========================================
+
+/// Game profile default gap between events when the job does not set one.
+const GAME_DEFAULT_GAP_US: u64 = 500;
+
+/// Bumped by `stop_all`; jobs started before the bump stop at their next event.
+static STOP_GENERATION: AtomicU64 = AtomicU64::new(0);
+static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+/// Running jobs asked to stop at their next event.
+static CANCELLED: Lazy<Mutex<HashSet<u64>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum InjectionProfile {
+    /// Virtual-key text entry and unmodified pointer moves.
+    #[default]
+    Standard,
+    /// Scancode keys, acceleration-compensated relative mouse, precise pacing.
+    Game,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum InputEvent {
+    MouseMove {
+        target: coords::Position,
+    },
+    MouseMoveRelative {
+        dx: i32,
+        dy: i32,
+    },
+    MouseClick {
+        button: String,
+        /// Optional `anchor:app/name` to click at.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        at: Option<String>,
+    },
+    MouseDown {
+        button: String,
+    },
+    MouseUp {
+        button: String,
+    },
+    KeyPress {
+        text: String,
+    },
+    Wait {
+        micros: u64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputJob {
+    pub events: Vec<InputEvent>,
+    #[serde(default)]
+    pub profile: InjectionProfile,
+    /// Gap inserted between consecutive events, in microseconds.
+    pub inter_event_us: Option<u64>,
+    /// Holds each event until the first display refresh after its scheduled time.
+    #[serde(default)]
+    pub align_to_vsync: bool,
+    /// Locked for the whole job on top of the mouse and keyboard its events use,
+    /// e.g. the window it types into.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locks: Vec<LockResource>,
+}
+
+impl InputJob {
+    /// Resources the job drives.
+    fn resources(&self) -> Vec<LockResource> {
+        let mut resources = self.locks.clone();
+        for event in &self.events {
+            resources.push(match event {
+                InputEvent::KeyPress { .. } => LockResource::Keyboard,
+                InputEvent::Wait { .. } => continue,
+                _ => LockResource::Mouse,
+            });
+        }
+        resources
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InputJobReport {
+    pub profile: InjectionProfile,
+    pub events: usize,
+    pub elapsed_us: u64,
+    /// Requested-vs-actual event timing.
+    pub timing: TimingMetrics,
+}
+
+pub fn run_job(job: InputJob) -> Result<InputJobReport, String> {
+    crash::breadcrumb(
+        "input",
+        format!(
+            "input job {:?} with {} events",
+            job.profile,
+            job.events.len()
+        ),
+    );
+
+    let id = begin();
+
+    // Pacing runs on its own thread so the caller's thread (often the UI thread)
+    // never spins, and scheduling is not disturbed by other work queued there.
+    let result = thread::Builder::new()
+        .name("input-job".to_string())
+        .spawn(move || execute(job, id))
+        .map_err(|e| format!("Failed to spawn input job: {}", e))
+        .and_then(|handle| handle.join().map_err(|_| "Input job panicked".to_string()));
+
+    finish(id);
+    result?
+}
+
+/// Allocates a job id and tags it to the current session.
+pub fn begin() -> u64 {
+    let id = NEXT_JOB.fetch_add(1, Ordering::SeqCst);
+    sessions::track(Resource::InputJob(id));
+    id
+}
+
+pub fn finish(id: u64) {
+    sessions::untrack(|r| *r == Resource::InputJob(id));
+    CANCELLED.lock().unwrap().remove(&id);
+}
+
+pub fn stop_generation() -> u64 {
+    STOP_GENERATION.load(Ordering::SeqCst)
+}
+
+/// Whether job `id`, started under stop `generation`, must stop before its next event.
+pub fn stopped(id: u64, generation: u64) -> bool {
+    STOP_GENERATION.load(Ordering::SeqCst) != generation || CANCELLED.lock().unwrap().contains(&id)
+}
+
+/// Stops job `id` before its next event.
+pub fn cancel(id: u64) {
+    CANCELLED.lock().unwrap().insert(id);
+}
+
+/// Emergency stop: aborts every running input job before its next event.
+pub fn stop_all() {
+    STOP_GENERATION.fetch_add(1, Ordering::SeqCst);
+    crash::breadcrumb("input", "stopped all input jobs");
+}
+
+fn execute(job: InputJob, id: u64) -> Result<InputJobReport, String> {
+    let _locks = locks::acquire(
+        &format!("input job {}", id),
+        job.resources(),
+        locks::JOB_WAIT,
+    )?;
+    let generation = stop_generation();
+    let game = job.profile == InjectionProfile::Game;
+    let gap = Duration::from_micros(job.inter_event_us.unwrap_or(if game {
+        GAME_DEFAULT_GAP_US
+    } else {
+        0
+    }));
+
+    // Held for the whole job so every relative move sees the same 1:1 mapping.
+    #[cfg(target_os = "windows")]
+    let _normalized = if game {
+        Some(input::acceleration::Normalized::apply()?)
+    } else {
+        None
+    };
+
+    let mut scheduler = Scheduler::new();
+    let started = Instant::now();
+    let mut next = started;
+
+    for (i, event) in job.events.iter().enumerate() {
+        if i > 0 {
+            next += gap;
+        }
+        scheduler.wait_until(next);
+        if job.align_to_vsync {
+            vsync::wait(1);
+        }
+        if stopped(id, generation) {
+            return Err(format!(
+                "Input job stopped after {} of {} events",
+                i,
+                job.events.len()
+            ));
+        }
+
+        match event {
+            InputEvent::MouseMove { target } => {
+                let (x, y) = coords::resolve_position(target)?;
+                input::move_cursor(x, y)?;
+            }
+            InputEvent::MouseMoveRelative { dx, dy } => relative(*dx, *dy)?,
+            InputEvent::MouseClick { button, at } => {
+                input::mouse_click(button.clone(), at.clone())?
+            }
+            InputEvent::MouseDown { button } => input::mouse_button(button, true)?,
+            InputEvent::MouseUp { button } => input::mouse_button(button, false)?,
+            InputEvent::KeyPress { text } if game => scancode::type_text(text)?,
+            InputEvent::KeyPress { text } => input::key_press(text.clone())?,
+            InputEvent::Wait { micros } => next += Duration::from_micros(*micros),
+        }
+    }
+    scheduler.wait_until(next);
+
+    Ok(InputJobReport {
+        profile: job.profile,
+        events: job.events.len(),
+        elapsed_us: started.elapsed().as_micros() as u64,
+        timing: scheduler.metrics(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn relative(dx: i32, dy: i32) -> Result<(), String> {
+    input::send_relative(dx, dy)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn relative(dx: i32, dy: i32) -> Result<(), String> {
+    input::mouse_move_relative(dx, dy, None)
+}
+
+#[cfg(target_os = "windows")]
+mod scancode {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        MapVirtualKeyW, SendInput, VkKeyScanW, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT,
+        KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, MAPVK_VK_TO_VSC, VIRTUAL_KEY,
+    };
+
+    const SCAN_LSHIFT: u16 = 0x2A;
+
+    fn key(scan: u16, up: bool) -> INPUT {
+        let mut flags = KEYEVENTF_SCANCODE;
+        if up {
+            flags |= KEYEVENTF_KEYUP;
+        }
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(0),
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    /// Games reading DirectInput/raw input ignore virtual-key and unicode events,
+    /// so each character is sent as the physical key that produces it.
+    pub fn type_text(text: &str) -> Result<(), String> {
+        for unit in text.encode_utf16() {
+            let mapping = unsafe { VkKeyScanW(unit) };
+            if mapping == -1 {
+                return Err(format!(
+                    "Character U+{:04X} has no key on the current layout",
+                    unit
+                ));
+            }
+
+            let vk = (mapping as u16) & 0xFF;
+            let shift = (mapping as u16) & 0x100 != 0;
+            let scan = unsafe { MapVirtualKeyW(vk as u32, MAPVK_VK_TO_VSC) } as u16;
+
+            let mut inputs = Vec::with_capacity(4);
+            if shift {
+                inputs.push(key(SCAN_LSHIFT, false));
+            }
+            inputs.push(key(scan, false));
+            inputs.push(key(scan, true));
+            if shift {
+                inputs.push(key(SCAN_LSHIFT, true));
+            }
+
+            let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+            if sent as usize != inputs.len() {
+                return Err("SendInput was blocked (UIPI or secure desktop)".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod scancode {
+    /// CGEvent and XTest already inject at the hardware-key level.
+    pub fn type_text(text: &str) -> Result<(), String> {
+        crate::input::key_press(text.to_string())
+    }
+}
+
+// ==========================================
+// INPUT JOB COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn run_input_job(job: InputJob) -> Result<InputJobReport, String> {
+    run_job(job)
+}
+
+#[tauri::command]
+pub fn stop_input_jobs() {
+    stop_all()
+}
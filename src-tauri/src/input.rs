@@ -0,0 +1,270 @@
+use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
+
+use std::time::Duration;
+
+use crate::{
+    anchors,
+    coords::{self, CoordinateSpace, Position},
+    crash,
+    locks::{self, LockResource, Locks},
+};
+
+/// Single commands fail at once rather than wait behind a running job.
+fn lock(command: &str, resource: LockResource) -> Result<Locks, String> {
+    locks::acquire(command, [resource], Duration::ZERO)
+}
+
+// ==========================================
+// MOUSE & KEYBOARD COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn mouse_move(
+    x: f64,
+    y: f64,
+    space: Option<CoordinateSpace>,
+    monitor: Option<usize>,
+) -> Result<(), String> {
+    let _lock = lock("mouse_move", LockResource::Mouse)?;
+    let (x, y) = coords::to_native(space.unwrap_or_default(), x, y, monitor)?;
+    crash::breadcrumb("input", format!("mouse_move {},{}", x, y));
+    move_cursor(x, y)
+}
+
+/// Moves to a percentage or anchored target resolved against the current monitor layout.
+#[tauri::command]
+pub fn mouse_move_to(target: Position) -> Result<(), String> {
+    let _lock = lock("mouse_move_to", LockResource::Mouse)?;
+    let (x, y) = coords::resolve_position(&target)?;
+    crash::breadcrumb("input", format!("mouse_move_to {},{}", x, y));
+    move_cursor(x, y)
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn move_cursor(x: i32, y: i32) -> Result<(), String> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_MOVE,
+        MOUSEEVENTF_VIRTUALDESK, MOUSEINPUT,
+    };
+
+    let (dx, dy) = coords::normalize_absolute(x, y);
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx,
+                dy,
+                mouseData: 0,
+                dwFlags: MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+
+    let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+    if sent == 0 {
+        return Err("SendInput was blocked (UIPI or secure desktop)".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn move_cursor(x: i32, y: i32) -> Result<(), String> {
+    let mut enigo = Enigo::new();
+    enigo.mouse_move_to(x, y);
+    Ok(())
+}
+
+/// Moves the cursor by a delta. With `compensate_acceleration`, pointer speed and
+/// acceleration are normalized for the duration of the move so `dx`/`dy` map 1:1 to pixels.
+#[tauri::command]
+pub fn mouse_move_relative(
+    dx: i32,
+    dy: i32,
+    compensate_acceleration: Option<bool>,
+) -> Result<(), String> {
+    let _lock = lock("mouse_move_relative", LockResource::Mouse)?;
+    crash::breadcrumb("input", format!("mouse_move_relative {},{}", dx, dy));
+
+    #[cfg(target_os = "windows")]
+    {
+        let _guard = if compensate_acceleration.unwrap_or(false) {
+            Some(acceleration::Normalized::apply()?)
+        } else {
+            None
+        };
+        send_relative(dx, dy)
+    }
+
+    // Synthetic relative moves bypass the pointer-acceleration curve on macOS and X11.
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = compensate_acceleration;
+        let mut enigo = Enigo::new();
+        enigo.mouse_move_relative(dx, dy);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn send_relative(dx: i32, dy: i32) -> Result<(), String> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_MOVE, MOUSEINPUT,
+    };
+
+    let input = INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx,
+                dy,
+                mouseData: 0,
+                dwFlags: MOUSEEVENTF_MOVE,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+
+    let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+    if sent == 0 {
+        return Err("SendInput was blocked (UIPI or secure desktop)".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) mod acceleration {
+    use std::ffi::c_void;
+
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETMOUSE, SPI_GETMOUSESPEED, SPI_SETMOUSE, SPI_SETMOUSESPEED,
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    /// Speed 10 with thresholds/acceleration zeroed is the unscaled 1:1 mapping.
+    const NEUTRAL_SPEED: u32 = 10;
+
+    // Changes are never written to the user profile, so a crash mid-move
+    // does not leave the user's mouse settings altered after logoff.
+    const NO_PERSIST: SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS = SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0);
+
+    /// Restores the user's pointer settings when dropped.
+    pub struct Normalized {
+        mouse: [i32; 3],
+        speed: u32,
+    }
+
+    impl Normalized {
+        pub fn apply() -> Result<Self, String> {
+            let mut mouse = [0i32; 3];
+            let mut speed = 0u32;
+            unsafe {
+                SystemParametersInfoW(
+                    SPI_GETMOUSE,
+                    0,
+                    Some(mouse.as_mut_ptr() as *mut c_void),
+                    NO_PERSIST,
+                )
+                .map_err(|e| format!("Failed to read mouse acceleration: {}", e))?;
+                SystemParametersInfoW(
+                    SPI_GETMOUSESPEED,
+                    0,
+                    Some(&mut speed as *mut u32 as *mut c_void),
+                    NO_PERSIST,
+                )
+                .map_err(|e| format!("Failed to read mouse speed: {}", e))?;
+            }
+
+            let saved = Self { mouse, speed };
+            set(&mut [0, 0, 0], NEUTRAL_SPEED)?;
+            Ok(saved)
+        }
+    }
+
+    impl Drop for Normalized {
+        fn drop(&mut self) {
+            let mut mouse = self.mouse;
+            if let Err(e) = set(&mut mouse, self.speed) {
+                log::warn!("failed to restore pointer settings: {}", e);
+            }
+        }
+    }
+
+    fn set(mouse: &mut [i32; 3], speed: u32) -> Result<(), String> {
+        unsafe {
+            SystemParametersInfoW(
+                SPI_SETMOUSE,
+                0,
+                Some(mouse.as_mut_ptr() as *mut c_void),
+                NO_PERSIST,
+            )
+            .map_err(|e| format!("Failed to set mouse acceleration: {}", e))?;
+            // SPI_SETMOUSESPEED takes the value itself in pvParam.
+            SystemParametersInfoW(
+                SPI_SETMOUSESPEED,
+                0,
+                Some(speed as usize as *mut c_void),
+                NO_PERSIST,
+            )
+            .map_err(|e| format!("Failed to set mouse speed: {}", e))
+        }
+    }
+}
+
+fn parse_button(button: &str) -> Result<MouseButton, String> {
+    match button.to_lowercase().as_str() {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        _ => Err("Unknown mouse button".to_string()),
+    }
+}
+
+/// Clicks at the cursor, or first moves to `at` (`anchor:app/name`).
+#[tauri::command]
+pub fn mouse_click(button: String, at: Option<String>) -> Result<(), String> {
+    let _lock = lock("mouse_click", LockResource::Mouse)?;
+    let mut enigo = Enigo::new();
+    let btn = parse_button(&button)?;
+
+    if let Some(target) = &at {
+        let (x, y) = anchors::resolve_target(target)?;
+        move_cursor(x, y)?;
+    }
+
+    crash::breadcrumb("input", format!("mouse_click {} at {:?}", button, at));
+    enigo.mouse_click(btn);
+    Ok(())
+}
+
+/// Presses or releases a button without clicking, for drags.
+pub(crate) fn mouse_button(button: &str, down: bool) -> Result<(), String> {
+    let mut enigo = Enigo::new();
+    let btn = parse_button(button)?;
+
+    crash::breadcrumb("input", format!("mouse_button {} down={}", button, down));
+    if down {
+        enigo.mouse_down(btn);
+    } else {
+        enigo.mouse_up(btn);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn key_press(text: String) -> Result<(), String> {
+    let _lock = lock("key_press", LockResource::Keyboard)?;
+    crash::breadcrumb(
+        "input",
+        format!("key_press batch of {} chars", text.chars().count()),
+    );
+    let mut enigo = Enigo::new();
+
+    for ch in text.chars() {
+        enigo.key_click(Key::Layout(ch));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,797 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    coords::{self, Anchor, Bounds},
+    crash,
+    hud::{self, HudStyle},
+    motion,
+};
+
+// ==========================================
+// Keystroke Visualizer
+// ==========================================
+
+// Shows the keys being pressed as badges in a HUD along the bottom of a monitor, for
+// tutorials and screencasts: shortcuts get a badge each ("Ctrl+Shift+P") and plain
+// typing collects into one. Unlike the presenter's HUDs it stays visible in screen
+// captures, since showing the recording what's typed is the point. Nothing is shown
+// while a password field has focus. The platform hooks only queue badges; the HUD is
+// redrawn from the main thread, where badges expire and the HUD fades out once the
+// typing stops.
+
+/// A pause this long ends a run of typing; the next character starts a new badge.
+const TYPING_GAP: Duration = Duration::from_millis(1000);
+/// Longer runs of typing show only their end.
+const MAX_TYPED: usize = 24;
+const MAX_BADGES: usize = 4;
+const FADE: Duration = Duration::from_millis(400);
+const FADE_TICK: Duration = Duration::from_millis(33);
+/// HUD height in lines of text, including padding.
+const BANNER_LINES: f64 = 2.0;
+/// Gap between the HUD and the bottom of the monitor, in device-independent pixels.
+const EDGE_MARGIN: f64 = 80.0;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KeystrokeStyle {
+    #[serde(flatten)]
+    pub text: HudStyle,
+    /// Monitor index; the primary monitor when omitted.
+    pub monitor: Option<usize>,
+    /// How long a badge stays before fading.
+    #[serde(default = "default_duration_ms")]
+    pub duration_ms: u64,
+}
+
+fn default_duration_ms() -> u64 {
+    2000
+}
+
+impl Default for KeystrokeStyle {
+    fn default() -> Self {
+        Self {
+            text: HudStyle::default(),
+            monitor: None,
+            duration_ms: default_duration_ms(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+struct Modifiers {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    /// Command on macOS, the Windows key on Windows.
+    meta: bool,
+}
+
+/// A key as the platform hooks report it.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+enum Pressed {
+    /// A key that types `char`, already shifted where the platform does that.
+    Char(char),
+    /// Any other key, e.g. "Enter" or "F5".
+    Named(&'static str),
+}
+
+struct Badge {
+    text: String,
+    /// Plain typing, which later characters are appended to.
+    typing: bool,
+    at: Instant,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct KeystrokeStatus {
+    pub active: bool,
+    pub style: KeystrokeStyle,
+}
+
+/// Newest last.
+static BADGES: Lazy<Mutex<VecDeque<Badge>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static STYLE: Lazy<Mutex<KeystrokeStyle>> = Lazy::new(|| Mutex::new(KeystrokeStyle::default()));
+/// The HUD and the text it shows; only touched on the main thread.
+static SHOWING: Lazy<Mutex<Option<(u64, String)>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped by `stop`, so the fade thread exits.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Called by the platform hooks for every key press, modifiers excluded. `modifiers`
+/// leaves out Shift when the character already reflects it.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn pressed(modifiers: Modifiers, key: Pressed) {
+    if platform::secure_input() {
+        return;
+    }
+    let now = Instant::now();
+    let mut badges = BADGES.lock().unwrap();
+    let chord = modifiers != Modifiers::default();
+    let text = match key {
+        Pressed::Char(c) if !chord && !c.is_control() => {
+            if let Some(last) = badges
+                .back_mut()
+                .filter(|last| last.typing && now - last.at < TYPING_GAP)
+            {
+                last.text.push(c);
+                let excess = last.text.chars().count().saturating_sub(MAX_TYPED);
+                if excess > 0 {
+                    last.text = last.text.chars().skip(excess).collect();
+                }
+                last.at = now;
+                return;
+            }
+            badges.push_back(Badge {
+                text: c.to_string(),
+                typing: true,
+                at: now,
+            });
+            trim(&mut badges);
+            return;
+        }
+        Pressed::Char(' ') => chord_name(modifiers, "Space"),
+        Pressed::Char(c) => chord_name(modifiers, &c.to_uppercase().to_string()),
+        Pressed::Named(name) => chord_name(modifiers, name),
+    };
+    badges.push_back(Badge {
+        text,
+        typing: false,
+        at: now,
+    });
+    trim(&mut badges);
+}
+
+fn trim(badges: &mut VecDeque<Badge>) {
+    while badges.len() > MAX_BADGES {
+        badges.pop_front();
+    }
+}
+
+/// Spelled like hotkeys, e.g. "Ctrl+Shift+P".
+fn chord_name(m: Modifiers, key: &str) -> String {
+    let mut name = String::new();
+    for (on, modifier) in [
+        (m.ctrl, "Ctrl+"),
+        (m.alt, "Alt+"),
+        (m.shift, "Shift+"),
+        (m.meta, "Meta+"),
+    ] {
+        if on {
+            name.push_str(modifier);
+        }
+    }
+    name.push_str(key);
+    name
+}
+
+/// Drops expired badges and brings the HUD up to date. Runs on the main thread.
+fn tick() {
+    let style = STYLE.lock().unwrap().clone();
+    let duration = Duration::from_millis(style.duration_ms);
+    let fade = motion::fade(FADE);
+    let now = Instant::now();
+    let (text, newest) = {
+        let mut badges = BADGES.lock().unwrap();
+        badges.retain(|badge| now - badge.at < duration + fade);
+        let text = badges
+            .iter()
+            .map(|badge| badge.text.as_str())
+            .collect::<Vec<_>>()
+            .join("   ");
+        (text, badges.back().map(|badge| badge.at))
+    };
+
+    let mut showing = SHOWING.lock().unwrap();
+    let Some(newest) = newest else {
+        if let Some((hud, _)) = showing.take() {
+            hud::close(hud);
+        }
+        return;
+    };
+    let hud = match showing.as_mut() {
+        Some((hud, shown)) => {
+            if *shown != text {
+                let _ = hud::set_text(*hud, &text);
+                *shown = text;
+            }
+            *hud
+        }
+        None => match open(&text, &style) {
+            Ok(hud) => showing.insert((hud, text)).0,
+            Err(e) => {
+                log::warn!("keystroke HUD failed: {}", e);
+                BADGES.lock().unwrap().clear();
+                return;
+            }
+        },
+    };
+    // The whole HUD fades once the newest badge is about to go.
+    if !fade.is_zero() {
+        let fading = (now - newest).saturating_sub(duration);
+        let _ = hud::set_fade(hud, 1.0 - fading.as_secs_f64() / fade.as_secs_f64());
+    }
+}
+
+fn open(text: &str, style: &KeystrokeStyle) -> Result<u64, String> {
+    let monitors = coords::monitors();
+    let monitor = match style.monitor {
+        Some(i) => monitors.get(i),
+        None => monitors.iter().find(|m| m.primary),
+    }
+    .ok_or_else(|| "Monitor not found".to_string())?;
+    let scale = monitor.scale_factor;
+    let (width, _) = monitor.physical_size();
+    let rect = coords::resolve_bounds(&Bounds::Anchored {
+        anchor: Anchor::Bottom,
+        width: width as f64 * 0.6,
+        height: style.text.font_size * scale * BANNER_LINES,
+        margin_x: 0.0,
+        margin_y: EDGE_MARGIN * scale,
+        monitor: style.monitor,
+    })?;
+    hud::open(rect, text, &style.text, false)
+}
+
+/// Starts showing keystrokes, or restyles the running visualizer. Runs on the main
+/// thread.
+pub fn start(app: AppHandle, style: KeystrokeStyle) -> Result<(), String> {
+    if style.duration_ms == 0 {
+        return Err("Badge duration must be at least 1 ms".to_string());
+    }
+    // A bad style should fail here rather than at the first key press.
+    style.text.check()?;
+    if let Some(index) = style.monitor {
+        let count = coords::monitors().len();
+        if index >= count {
+            return Err(format!(
+                "Monitor index {} out of range (found {})",
+                index, count
+            ));
+        }
+    }
+    *STYLE.lock().unwrap() = style;
+    // Restyled badges get a new HUD on the next tick.
+    if let Some((hud, _)) = SHOWING.lock().unwrap().take() {
+        hud::close(hud);
+    }
+    if ACTIVE.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    platform::start()?;
+    crash::breadcrumb("keystrokes", "start");
+    ACTIVE.store(true, Ordering::SeqCst);
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::Builder::new()
+        .name("keystroke-fade".to_string())
+        .spawn(move || {
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(FADE_TICK);
+                if !BADGES.lock().unwrap().is_empty() {
+                    let _ = app.run_on_main_thread(tick);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn keystroke fade thread: {}", e))?;
+    Ok(())
+}
+
+/// Runs on the main thread.
+pub fn stop() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    crash::breadcrumb("keystrokes", "stop");
+    platform::stop();
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    BADGES.lock().unwrap().clear();
+    if let Some((hud, _)) = SHOWING.lock().unwrap().take() {
+        hud::close(hud);
+    }
+}
+
+/// Starts with the last style used, or stops. Runs on the main thread.
+pub fn toggle(app: AppHandle) -> Result<(), String> {
+    if ACTIVE.load(Ordering::SeqCst) {
+        stop();
+        Ok(())
+    } else {
+        let style = STYLE.lock().unwrap().clone();
+        start(app, style)
+    }
+}
+
+pub fn status() -> KeystrokeStatus {
+    KeystrokeStatus {
+        active: ACTIVE.load(Ordering::SeqCst),
+        style: STYLE.lock().unwrap().clone(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+    };
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, GetKeyState, MapVirtualKeyW, MAPVK_VK_TO_CHAR,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetGUIThreadInfo, GetMessageW, GetWindowLongW, PostThreadMessageW,
+        SetTimer, SetWindowsHookExW, UnhookWindowsHookEx, ES_PASSWORD, GUITHREADINFO, GWL_STYLE,
+        HHOOK, KBDLLHOOKSTRUCT, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_QUIT, WM_SYSKEYDOWN,
+        WM_SYSKEYUP, WM_TIMER,
+    };
+
+    use super::{pressed, Modifiers, Pressed};
+
+    /// Watchdog entry of the hook thread.
+    const WATCHED: &str = "keystroke-hook";
+
+    static THREAD_ID: AtomicU32 = AtomicU32::new(0);
+    /// The key last pressed and not yet released; the hook reports auto-repeat as
+    /// more presses.
+    static HELD: AtomicU32 = AtomicU32::new(0);
+
+    const VK_BACK: u32 = 0x08;
+    const VK_TAB: u32 = 0x09;
+    const VK_RETURN: u32 = 0x0D;
+    const VK_CAPITAL: i32 = 0x14;
+    const VK_ESCAPE: u32 = 0x1B;
+    const VK_SPACE: u32 = 0x20;
+    const VK_F1: u32 = 0x70;
+    const VK_F24: u32 = 0x87;
+
+    const NAMED: &[(u32, &str)] = &[
+        (VK_BACK, "Backspace"),
+        (VK_TAB, "Tab"),
+        (VK_RETURN, "Enter"),
+        (VK_ESCAPE, "Escape"),
+        (0x21, "PageUp"),
+        (0x22, "PageDown"),
+        (0x23, "End"),
+        (0x24, "Home"),
+        (0x25, "Left"),
+        (0x26, "Up"),
+        (0x27, "Right"),
+        (0x28, "Down"),
+        (0x2D, "Insert"),
+        (0x2E, "Delete"),
+    ];
+    const FUNCTION: [&str; 24] = [
+        "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12", "F13", "F14",
+        "F15", "F16", "F17", "F18", "F19", "F20", "F21", "F22", "F23", "F24",
+    ];
+
+    /// Shift, Ctrl, Alt (both sides and generic), the Windows keys and Caps Lock.
+    fn is_modifier(vk: u32) -> bool {
+        matches!(vk, 0x10..=0x12 | 0x14 | 0x5B | 0x5C | 0xA0..=0xA5)
+    }
+
+    unsafe fn down(vk: i32) -> bool {
+        GetAsyncKeyState(vk) as u16 & 0x8000 != 0
+    }
+
+    unsafe fn key(vk: u32, shift: bool) -> Option<Pressed> {
+        if vk == VK_SPACE {
+            return Some(Pressed::Char(' '));
+        }
+        if let Some((_, name)) = NAMED.iter().find(|(named, _)| *named == vk) {
+            return Some(Pressed::Named(name));
+        }
+        if (VK_F1..=VK_F24).contains(&vk) {
+            return Some(Pressed::Named(FUNCTION[(vk - VK_F1) as usize]));
+        }
+        // Unshifted, with letters in upper case; dead keys set the top bit.
+        let mapped = MapVirtualKeyW(vk, MAPVK_VK_TO_CHAR);
+        let c = char::from_u32(mapped & 0x7FFF_FFFF).filter(|c| *c != '\0')?;
+        if c.is_alphabetic() {
+            let caps = GetKeyState(VK_CAPITAL) & 1 != 0;
+            return Some(Pressed::Char(if shift != caps {
+                c
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }));
+        }
+        Some(Pressed::Char(c))
+    }
+
+    unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code >= 0 {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            match wparam.0 as u32 {
+                WM_KEYDOWN | WM_SYSKEYDOWN
+                    if !is_modifier(info.vkCode)
+                        && HELD.swap(info.vkCode, Ordering::SeqCst) != info.vkCode =>
+                {
+                    let modifiers = Modifiers {
+                        ctrl: down(0x11),
+                        alt: down(0x12),
+                        shift: down(0x10),
+                        meta: down(0x5B) || down(0x5C),
+                    };
+                    if let Some(key) = key(info.vkCode, modifiers.shift) {
+                        // Letters carry Shift in their case. Other shifted characters
+                        // aren't worked out, so they show as chords, e.g. "Shift+1".
+                        let letter = matches!(key, Pressed::Char(c) if c.is_alphabetic());
+                        let modifiers = Modifiers {
+                            shift: modifiers.shift && !letter,
+                            ..modifiers
+                        };
+                        pressed(modifiers, key);
+                    }
+                }
+                WM_KEYUP | WM_SYSKEYUP => {
+                    let _ =
+                        HELD.compare_exchange(info.vkCode, 0, Ordering::SeqCst, Ordering::SeqCst);
+                }
+                _ => {}
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    /// Whether the focused control is a password box. Only classic edit controls
+    /// say so; browsers and other custom-drawn fields can't be detected.
+    pub fn secure_input() -> bool {
+        unsafe {
+            let mut info = GUITHREADINFO {
+                cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+                ..Default::default()
+            };
+            if GetGUIThreadInfo(0, &mut info).is_err() || info.hwndFocus == HWND::default() {
+                return false;
+            }
+            GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0
+        }
+    }
+
+    /// Installs the hook and has the watchdog replace the hook thread if it stops
+    /// pumping messages.
+    pub fn start() -> Result<(), String> {
+        spawn()?;
+        crate::watchdog::register(
+            WATCHED,
+            crate::watchdog::THREAD_TIMEOUT,
+            Some(Arc::new(|| {
+                quit();
+                spawn()
+            })),
+        );
+        Ok(())
+    }
+
+    fn spawn() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("keystrokes".to_string())
+            .spawn(move || unsafe {
+                THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+                let hook = GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| e.to_string())
+                    .and_then(|module| {
+                        SetWindowsHookExW(
+                            WH_KEYBOARD_LL,
+                            Some(keyboard_proc),
+                            HINSTANCE(module.0),
+                            0,
+                        )
+                        .map_err(|e| format!("Failed to install keyboard hook: {}", e))
+                    });
+                let hook = match hook {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                let _ = SetTimer(HWND::default(), 0, crate::watchdog::HEARTBEAT_MS, None);
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    if msg.message == WM_TIMER {
+                        crate::watchdog::heartbeat(WATCHED);
+                    }
+                }
+
+                let _ = UnhookWindowsHookEx(hook);
+            })
+            .map_err(|e| format!("Failed to spawn keystroke hook: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Keystroke hook thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        crate::watchdog::unregister(WATCHED);
+        quit();
+    }
+
+    /// Ends the hook thread, or a stalled one once it recovers.
+    fn quit() {
+        unsafe {
+            let _ = PostThreadMessageW(
+                THREAD_ID.load(Ordering::SeqCst),
+                WM_QUIT,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        ffi::{c_void, CStr},
+        os::raw::c_char,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::{pressed, Modifiers, Pressed};
+
+    type CFTypeRef = *mut c_void;
+    type TapCallback =
+        extern "C" fn(proxy: CFTypeRef, kind: u32, event: CFTypeRef, user: CFTypeRef) -> CFTypeRef;
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT: u32 = 0;
+    const LISTEN_ONLY: u32 = 1;
+
+    const KEY_DOWN: u32 = 10;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+
+    const FIELD_AUTOREPEAT: u32 = 8;
+    const FIELD_KEYCODE: u32 = 9;
+
+    const FLAG_SHIFT: u64 = 0x0002_0000;
+    const FLAG_CONTROL: u64 = 0x0004_0000;
+    const FLAG_OPTION: u64 = 0x0008_0000;
+    const FLAG_COMMAND: u64 = 0x0010_0000;
+
+    /// Virtual key codes of keys that don't type anything.
+    const NAMED: &[(i64, &str)] = &[
+        (36, "Enter"),
+        (48, "Tab"),
+        (51, "Backspace"),
+        (53, "Escape"),
+        (115, "Home"),
+        (116, "PageUp"),
+        (117, "Delete"),
+        (119, "End"),
+        (121, "PageDown"),
+        (123, "Left"),
+        (124, "Right"),
+        (125, "Down"),
+        (126, "Up"),
+        (122, "F1"),
+        (120, "F2"),
+        (99, "F3"),
+        (118, "F4"),
+        (96, "F5"),
+        (97, "F6"),
+        (98, "F7"),
+        (100, "F8"),
+        (101, "F9"),
+        (109, "F10"),
+        (103, "F11"),
+        (111, "F12"),
+    ];
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            mask: u64,
+            callback: TapCallback,
+            user: CFTypeRef,
+        ) -> CFTypeRef;
+        fn CGEventTapEnable(tap: CFTypeRef, enable: bool);
+        fn CGEventGetIntegerValueField(event: CFTypeRef, field: u32) -> i64;
+        fn CGEventGetFlags(event: CFTypeRef) -> u64;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: CFTypeRef;
+        fn CFMachPortCreateRunLoopSource(
+            alloc: CFTypeRef,
+            port: CFTypeRef,
+            order: isize,
+        ) -> CFTypeRef;
+        fn CFRunLoopGetCurrent() -> CFTypeRef;
+        fn CFRunLoopAddSource(run_loop: CFTypeRef, source: CFTypeRef, mode: CFTypeRef);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: CFTypeRef);
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+
+    static RUN_LOOP: AtomicUsize = AtomicUsize::new(0);
+    static TAP: AtomicUsize = AtomicUsize::new(0);
+
+    /// The key's character with Shift applied but not the other modifiers.
+    unsafe fn character(event: CFTypeRef) -> Option<char> {
+        let ns_event: id = msg_send![class!(NSEvent), eventWithCGEvent: event];
+        if ns_event == nil {
+            return None;
+        }
+        let chars: id = msg_send![ns_event, charactersIgnoringModifiers];
+        if chars == nil {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![chars, UTF8String];
+        if utf8.is_null() {
+            return None;
+        }
+        CStr::from_ptr(utf8).to_str().ok()?.chars().next()
+    }
+
+    extern "C" fn callback(
+        _proxy: CFTypeRef,
+        kind: u32,
+        event: CFTypeRef,
+        _user: CFTypeRef,
+    ) -> CFTypeRef {
+        unsafe {
+            if kind == TAP_DISABLED_BY_TIMEOUT {
+                CGEventTapEnable(TAP.load(Ordering::SeqCst) as CFTypeRef, true);
+                return event;
+            }
+            if kind != KEY_DOWN || CGEventGetIntegerValueField(event, FIELD_AUTOREPEAT) != 0 {
+                return event;
+            }
+            let flags = CGEventGetFlags(event);
+            let keycode = CGEventGetIntegerValueField(event, FIELD_KEYCODE);
+            let key = match NAMED.iter().find(|(code, _)| *code == keycode) {
+                Some((_, name)) => Some(Pressed::Named(name)),
+                None => character(event).map(Pressed::Char),
+            };
+            if let Some(key) = key {
+                // Typed characters carry Shift already.
+                let typed = matches!(key, Pressed::Char(c) if c != ' ');
+                pressed(
+                    Modifiers {
+                        ctrl: flags & FLAG_CONTROL != 0,
+                        alt: flags & FLAG_OPTION != 0,
+                        shift: flags & FLAG_SHIFT != 0 && !typed,
+                        meta: flags & FLAG_COMMAND != 0,
+                    },
+                    key,
+                );
+            }
+            event
+        }
+    }
+
+    /// Set by password fields; the tap hears nothing from the keyboard meanwhile
+    /// anyway.
+    pub fn secure_input() -> bool {
+        unsafe { IsSecureEventInputEnabled() != 0 }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("keystrokes".to_string())
+            .spawn(move || unsafe {
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    LISTEN_ONLY,
+                    1u64 << KEY_DOWN,
+                    callback,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = ready_tx.send(Err(
+                        "Failed to create event tap (grant Input Monitoring permission)"
+                            .to_string(),
+                    ));
+                    return;
+                }
+                TAP.store(tap as usize, Ordering::SeqCst);
+
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null_mut(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                RUN_LOOP.store(run_loop as usize, Ordering::SeqCst);
+                let _ = ready_tx.send(Ok(()));
+
+                CFRunLoopRun();
+
+                CGEventTapEnable(tap, false);
+                RUN_LOOP.store(0, Ordering::SeqCst);
+                CFRelease(source);
+                CFRelease(tap);
+            })
+            .map_err(|e| format!("Failed to spawn keystroke tap: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Keystroke tap thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        let run_loop = RUN_LOOP.load(Ordering::SeqCst);
+        if run_loop != 0 {
+            unsafe { CFRunLoopStop(run_loop as CFTypeRef) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub fn secure_input() -> bool {
+        false
+    }
+
+    pub fn start() -> Result<(), String> {
+        Err("The keystroke visualizer is only supported on macOS and Windows".to_string())
+    }
+
+    pub fn stop() {}
+}
+
+// ==========================================
+// KEYSTROKE COMMANDS
+// ==========================================
+
+/// Shows pressed keys in a HUD that screen recordings pick up. Calling it again
+/// while running applies the new style.
+#[tauri::command]
+pub fn start_keystroke_visualizer(
+    app: AppHandle,
+    style: Option<KeystrokeStyle>,
+) -> Result<(), String> {
+    start(app, style.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn stop_keystroke_visualizer() {
+    stop();
+}
+
+#[tauri::command]
+pub fn get_keystroke_visualizer() -> KeystrokeStatus {
+    status()
+}
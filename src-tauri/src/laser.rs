@@ -0,0 +1,246 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{State, Webview};
+
+use crate::{
+    coords::{self, CoordinateSpace},
+    crash, shape, OverlayManager,
+};
+
+// ==========================================
+// Laser Pointer
+// ==========================================
+
+// A large colored dot a remote client (typically a phone driving the control server)
+// moves over the presenter's screen without touching the real cursor. The dot is a
+// click-through overlay shaped into a circle and filled by its border. Positions are
+// fractions of the monitor, so a phone can map its touchpad straight onto them. Only
+// the client that started the laser may move it, so two remotes can't fight over it.
+
+/// Device-independent pixels.
+const DEFAULT_SIZE: f64 = 28.0;
+const DEFAULT_OPACITY: f64 = 0.85;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LaserStyle {
+    /// "#RRGGBB"; the palette's accent color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Diameter in device-independent pixels.
+    #[serde(default = "default_size")]
+    pub size: f64,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+}
+
+fn default_size() -> f64 {
+    DEFAULT_SIZE
+}
+
+fn default_opacity() -> f64 {
+    DEFAULT_OPACITY
+}
+
+impl Default for LaserStyle {
+    fn default() -> Self {
+        Self {
+            color: None,
+            size: DEFAULT_SIZE,
+            opacity: DEFAULT_OPACITY,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LaserInfo {
+    pub overlay: u64,
+    pub owner: String,
+    pub monitor: usize,
+    /// Fractions of the monitor's width and height.
+    pub x: f64,
+    pub y: f64,
+}
+
+struct Laser {
+    info: LaserInfo,
+    /// Diameter in physical pixels.
+    size: f64,
+}
+
+static LASER: Lazy<Mutex<Option<Laser>>> = Lazy::new(|| Mutex::new(None));
+
+fn monitor(index: usize) -> Result<coords::Monitor, String> {
+    let monitors = coords::monitors();
+    monitors.get(index).cloned().ok_or_else(|| {
+        format!(
+            "Monitor index {} out of range (found {})",
+            index,
+            monitors.len()
+        )
+    })
+}
+
+/// The dot's top-left corner when centered on (`x`, `y`), in physical pixels.
+fn origin(m: &coords::Monitor, size: f64, x: f64, y: f64) -> (f64, f64) {
+    (
+        m.x as f64 + x * m.width as f64 - size / 2.0,
+        m.y as f64 + y * m.height as f64 - size / 2.0,
+    )
+}
+
+fn check_position(x: f64, y: f64) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+        return Err(format!("Laser position {},{} is not within 0 and 1", x, y));
+    }
+    Ok(())
+}
+
+/// Shows the dot at the center of `monitor` on behalf of `owner`, replacing any
+/// laser. Runs on the main thread.
+pub fn start(
+    manager: &OverlayManager,
+    owner: &str,
+    monitor_index: usize,
+    style: &LaserStyle,
+) -> Result<LaserInfo, String> {
+    if !(4.0..=200.0).contains(&style.size) {
+        return Err(format!("Laser size {} is out of range", style.size));
+    }
+    let m = monitor(monitor_index)?;
+    stop_in(manager);
+
+    crash::breadcrumb("laser", format!("start for {}", owner));
+    let size = (style.size * m.scale_factor).round();
+    let (x, y) = origin(&m, size, 0.5, 0.5);
+    // A border half the diameter wide fills the circle with its color.
+    let dot = shape::WindowShape {
+        corner_radius: style.size / 2.0,
+        border_width: style.size / 2.0,
+        border_color: style.color.clone(),
+    };
+    let overlay = crate::create_region_overlay_in(
+        manager,
+        x,
+        y,
+        size,
+        size,
+        Some(CoordinateSpace::Physical),
+        None,
+        None,
+        Some(&dot),
+        Some(style.opacity),
+        None,
+    )?;
+    if let Err(e) = crate::set_overlay_interactive_in(manager, overlay, false) {
+        let _ = crate::destroy_overlay_in(manager, overlay);
+        return Err(e);
+    }
+
+    let info = LaserInfo {
+        overlay,
+        owner: owner.to_string(),
+        monitor: monitor_index,
+        x: 0.5,
+        y: 0.5,
+    };
+    *LASER.lock().unwrap() = Some(Laser {
+        info: info.clone(),
+        size,
+    });
+    Ok(info)
+}
+
+/// Centers the dot on (`x`, `y`), fractions of its monitor. Only the laser's owner
+/// may move it.
+pub fn move_to(manager: &OverlayManager, owner: &str, x: f64, y: f64) -> Result<(), String> {
+    check_position(x, y)?;
+    let mut laser = LASER.lock().unwrap();
+    let current = laser.as_mut().ok_or("Laser pointer is not active")?;
+    if current.info.owner != owner {
+        return Err("The laser pointer belongs to another client".to_string());
+    }
+    let m = monitor(current.info.monitor)?;
+    let (left, top) = origin(&m, current.size, x, y);
+    let moved = crate::update_overlay_in(
+        manager,
+        current.info.overlay,
+        left,
+        top,
+        current.size,
+        current.size,
+        Some(CoordinateSpace::Physical),
+        None,
+    );
+    if moved.is_err() && manager.handle(current.info.overlay).is_none() {
+        // Its overlay was closed some other way, e.g. with its session.
+        *laser = None;
+        return Err("Laser pointer is not active".to_string());
+    }
+    moved?;
+    current.info.x = x;
+    current.info.y = y;
+    Ok(())
+}
+
+fn stop_in(manager: &OverlayManager) {
+    let laser = LASER.lock().unwrap().take();
+    if let Some(laser) = laser {
+        let _ = crate::destroy_overlay_in(manager, laser.info.overlay);
+    }
+}
+
+/// Hides the dot. Anyone may stop it, so a stuck remote can't leave it on screen.
+pub fn stop(manager: &OverlayManager) {
+    crash::breadcrumb("laser", "stop");
+    stop_in(manager);
+}
+
+pub fn info() -> Option<LaserInfo> {
+    LASER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|laser| laser.info.clone())
+}
+
+// ==========================================
+// LASER COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn start_laser_pointer(
+    state: State<'_, OverlayManager>,
+    webview: Webview,
+    monitor: Option<usize>,
+    style: Option<LaserStyle>,
+) -> Result<LaserInfo, String> {
+    start(
+        &state,
+        webview.label(),
+        monitor.unwrap_or(0),
+        &style.unwrap_or_default(),
+    )
+}
+
+/// Centers the dot on `x`, `y`, given as fractions (0–1) of its monitor.
+#[tauri::command]
+pub fn move_laser_pointer(
+    state: State<'_, OverlayManager>,
+    webview: Webview,
+    x: f64,
+    y: f64,
+) -> Result<(), String> {
+    move_to(&state, webview.label(), x, y)
+}
+
+#[tauri::command]
+pub fn stop_laser_pointer(state: State<'_, OverlayManager>) {
+    stop(&state);
+}
+
+#[tauri::command]
+pub fn get_laser_pointer() -> Option<LaserInfo> {
+    info()
+}
@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// ==========================================
+// Overlay Layers
+// ==========================================
+
+// Every always-on-top window the app opens belongs to a named layer, and layers
+// stack in a fixed order: privacy covers at the bottom, then the spotlight, HUDs,
+// annotation drawings and click effects, with layout previews above everything. macOS maps each layer to its own
+// window level. Windows has a single topmost band, so whenever a window joins it
+// the whole set is restacked, bottom layer first, instead of leaving the order to
+// whichever window went topmost last.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layer {
+    Privacy,
+    /// Dims everything but the cursor, under HUDs so those stay readable.
+    Spotlight,
+    Hud,
+    /// Drawings over shared content, between HUDs and previews.
+    Annotation,
+    /// Click effects, over drawings so clicks on them show.
+    Pointer,
+    Preview,
+}
+
+/// Layer and native handle of every open layered window, oldest first.
+static WINDOWS: Lazy<Mutex<Vec<(Layer, usize)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Puts a newly opened window in `layer`, above older windows of the same layer.
+/// Runs on the main thread.
+pub fn add(layer: Layer, handle: usize) {
+    let mut windows = WINDOWS.lock().unwrap();
+    windows.push((layer, handle));
+    platform::place(layer, handle);
+
+    let mut order = windows.clone();
+    // Stable, so windows keep their age order within a layer.
+    order.sort_by_key(|(layer, _)| *layer);
+    platform::restack(&order);
+}
+
+/// Forgets a window that is closing.
+pub fn remove(handle: usize) {
+    WINDOWS.lock().unwrap().retain(|(_, h)| *h != handle);
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SetWindowPos, HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE,
+    };
+
+    use super::Layer;
+
+    pub fn place(_layer: Layer, _handle: usize) {}
+
+    /// Each window moved to the top of the topmost band ends up above those moved
+    /// before it, so going bottom-up leaves the layers in order.
+    pub fn restack(windows: &[(Layer, usize)]) {
+        for &(_, handle) in windows {
+            unsafe {
+                let _ = SetWindowPos(
+                    HWND(handle as *mut _),
+                    HWND_TOPMOST,
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, NO};
+    use objc::{msg_send, sel, sel_impl};
+
+    use super::Layer;
+
+    /// NSScreenSaverWindowLevel: above the menu bar, the Dock and full-screen apps.
+    const BASE_LEVEL: i64 = 1000;
+    /// NSWindowCollectionBehaviorCanJoinAllSpaces | Stationary | IgnoresCycle |
+    /// FullScreenAuxiliary: on every Space, including a full-screen app's, staying
+    /// put while Spaces switch and out of the Cmd-` window cycle.
+    const COLLECTION_BEHAVIOR: u64 = 0x1 | 0x10 | 0x40 | 0x100;
+
+    pub fn place(layer: Layer, handle: usize) {
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, setCollectionBehavior: COLLECTION_BEHAVIOR];
+            let _: () = msg_send![window, setLevel: BASE_LEVEL + layer as i64];
+            let _: () = msg_send![window, setHidesOnDeactivate: NO];
+        }
+    }
+
+    /// Levels already keep the layers apart.
+    pub fn restack(_windows: &[(Layer, usize)]) {}
+}
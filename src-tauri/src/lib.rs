@@ -2,19 +2,146 @@
 
 use std::{
     ffi::c_void,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
-use tauri::State;
-use enigo::{Enigo, MouseControllable, KeyboardControllable, MouseButton, Key};
+use tauri::{webview::PageLoadEvent, AppHandle, Manager, State, WindowEvent};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use overlay_backend::{Backend, OverlayBackend};
+
+mod actions;
+mod affinity;
+#[cfg(feature = "input-automation")]
+mod anchors;
+mod annotate;
+mod app_window;
+mod audit_log;
+mod auto_cover;
+mod bug_report;
+mod capture;
+mod captions;
+mod clicks;
+mod command_palette;
+mod commands;
+mod context_actions;
+#[cfg(feature = "input-automation")]
+mod clipboard;
+#[cfg(feature = "remote-control")]
+mod control;
+mod coords;
+mod crash;
+mod cursor;
+mod dispatch;
+mod events;
+mod export;
+mod features;
+#[cfg(feature = "input-automation")]
+mod file_drop;
+mod gestures;
+mod hot_corners;
+mod hotkeys;
+mod hud;
+#[cfg(feature = "input-automation")]
+mod injection;
+mod keystrokes;
+#[cfg(feature = "input-automation")]
+mod locks;
+mod laser;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+mod layers;
+#[cfg(target_os = "linux")]
+mod linux_privacy;
+mod locale;
+mod middleware;
+mod motion;
+#[cfg(feature = "input-automation")]
+mod multi_pointer;
+mod overlay_backend;
+mod palette;
+mod presenter;
+#[cfg(feature = "scripting")]
+mod queue;
+mod quick_menu;
+mod remote_session;
+#[cfg(feature = "scripting")]
+mod recorder;
+mod region_hash;
+mod retention;
+#[cfg(feature = "scripting")]
+mod runner;
+#[cfg(feature = "scripting")]
+mod scripts;
+mod secrets;
+#[cfg(feature = "service")]
+mod service;
+mod sessions;
+mod shape;
+mod share_guard;
+mod shake;
+mod speech;
+mod spotlight;
+#[cfg(feature = "input-automation")]
+mod input;
+mod subsystems;
+#[cfg(feature = "input-automation")]
+mod timing;
+mod toasts;
+#[cfg(feature = "self-update")]
+mod updater;
+mod visual;
+mod vsync;
+#[cfg(feature = "voice-commands")]
+mod voice;
+mod watchdog;
+#[cfg(feature = "webdriver")]
+mod webdriver;
+mod window_overlays;
 
 // ==========================================
 // Overlay Manager
 // ==========================================
 
+struct Overlay {
+    /// Stable for the life of the overlay, unlike handles, which the OS may reuse.
+    id: u64,
+    handle: *mut c_void,
+    /// Native coordinates.
+    rect: coords::Rect,
+    /// Set when the overlay covers a whole monitor.
+    monitor: Option<usize>,
+    /// Part of a cover over every monitor, which a newly connected monitor joins.
+    all_monitors: bool,
+    /// Shown centered on the overlay, e.g. "Screen paused".
+    text: Option<String>,
+    /// Percent complete, drawn as a bar under the text.
+    progress: Option<f64>,
+    /// Whether the overlay takes clicks rather than passing them through.
+    interactive: bool,
+    created_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct OverlayInfo {
+    id: u64,
+    rect: coords::Rect,
+    monitor: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    progress: Option<f64>,
+    interactive: bool,
+    created_ms: u64,
+}
+
 struct OverlayManager {
-    overlays: Arc<Mutex<Vec<*mut c_void>>>,
+    overlays: Arc<Mutex<Vec<Overlay>>>,
 }
 
 impl OverlayManager {
@@ -24,14 +151,99 @@ impl OverlayManager {
         }
     }
 
-    fn add_overlay(&self, overlay: *mut c_void) {
+    /// Another handle to the same overlays, for threads that outlive a borrow.
+    fn share(&self) -> Self {
+        Self {
+            overlays: Arc::clone(&self.overlays),
+        }
+    }
+
+    /// Records a new overlay and returns its id.
+    fn add_overlay(
+        &self,
+        overlay: *mut c_void,
+        rect: coords::Rect,
+        monitor: Option<usize>,
+        text: Option<String>,
+    ) -> u64 {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let mut overlays = self.overlays.lock().unwrap();
+        overlays.push(Overlay {
+            id,
+            handle: overlay,
+            rect,
+            monitor,
+            all_monitors: false,
+            text,
+            progress: None,
+            interactive: true,
+            created_ms: crash::now_ms() as u64,
+        });
+        id
+    }
+
+    /// Records that overlay `id` now covers `rect` and returns its handle.
+    fn set_rect(&self, id: u64, rect: coords::Rect) -> Option<*mut c_void> {
+        let mut overlays = self.overlays.lock().unwrap();
+        let overlay = overlays.iter_mut().find(|o| o.id == id)?;
+        overlay.rect = rect;
+        overlay.monitor = None;
+        overlay.all_monitors = false;
+        Some(overlay.handle)
+    }
+
+    /// Marks overlays `ids` as one cover over every monitor.
+    fn set_all_monitors(&self, ids: &[u64]) {
+        let mut overlays = self.overlays.lock().unwrap();
+        for overlay in overlays.iter_mut().filter(|o| ids.contains(&o.id)) {
+            overlay.all_monitors = true;
+        }
+    }
+
+    /// Records overlay `id`'s new text and returns its handle.
+    fn set_text(&self, id: u64, text: Option<String>) -> Option<*mut c_void> {
         let mut overlays = self.overlays.lock().unwrap();
-        overlays.push(overlay);
+        let overlay = overlays.iter_mut().find(|o| o.id == id)?;
+        overlay.text = text;
+        Some(overlay.handle)
     }
 
-    fn destroy_all(&self) {
+    /// Records overlay `id`'s progress and returns its handle.
+    fn set_progress(&self, id: u64, progress: Option<f64>) -> Option<*mut c_void> {
         let mut overlays = self.overlays.lock().unwrap();
-        overlays.clear();
+        let overlay = overlays.iter_mut().find(|o| o.id == id)?;
+        overlay.progress = progress;
+        Some(overlay.handle)
+    }
+
+    /// Records whether overlay `id` takes clicks.
+    fn set_interactive(&self, id: u64, interactive: bool) {
+        let mut overlays = self.overlays.lock().unwrap();
+        if let Some(overlay) = overlays.iter_mut().find(|o| o.id == id) {
+            overlay.interactive = interactive;
+        }
+    }
+
+    fn handle(&self, id: u64) -> Option<*mut c_void> {
+        let overlays = self.overlays.lock().unwrap();
+        overlays.iter().find(|o| o.id == id).map(|o| o.handle)
+    }
+
+    fn list(&self) -> Vec<OverlayInfo> {
+        let overlays = self.overlays.lock().unwrap();
+        overlays
+            .iter()
+            .map(|o| OverlayInfo {
+                id: o.id,
+                rect: o.rect,
+                monitor: o.monitor,
+                text: o.text.clone(),
+                progress: o.progress,
+                interactive: o.interactive,
+                created_ms: o.created_ms,
+            })
+            .collect()
     }
 }
 
@@ -40,43 +252,175 @@ unsafe impl Sync for OverlayManager {}
 
 static OVERLAY_MANAGER: Lazy<OverlayManager> = Lazy::new(OverlayManager::new);
 
-// ==========================================
-// MOUSE & KEYBOARD COMMANDS
-// ==========================================
+/// Emitted with the overlay's id when its cancel button is clicked.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const OVERLAY_CANCELLED_EVENT: &str = "overlay://cancelled";
 
-#[tauri::command]
-fn mouse_move(x: i32, y: i32) -> Result<(), String> {
-    let mut enigo = Enigo::new();
-    enigo.mouse_move_to(x, y);
-    Ok(())
+/// Emitted with the overlay's id when it closes itself after its timeout.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const OVERLAY_EXPIRED_EVENT: &str = "overlay://expired";
+
+/// Set in `run`; overlays report button clicks and timeouts to the frontend
+/// through it.
+static OVERLAY_APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+struct OverlayEvent {
+    id: u64,
 }
 
-#[tauri::command]
-fn mouse_click(button: String) -> Result<(), String> {
-    let mut enigo = Enigo::new();
-
-    let btn = match button.to_lowercase().as_str() {
-        "left" => MouseButton::Left,
-        "right" => MouseButton::Right,
-        "middle" => MouseButton::Middle,
-        _ => return Err("Unknown mouse button".to_string()),
-    };
+/// Called by the platform layers when overlay `id`'s cancel button is clicked.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+fn overlay_cancelled(id: u64) {
+    crash::breadcrumb("overlay", format!("cancelled {}", id));
+    if let Some(app) = OVERLAY_APP.lock().unwrap().as_ref() {
+        events::emit(app, OVERLAY_CANCELLED_EVENT, OverlayEvent { id });
+    }
+}
 
-    enigo.mouse_click(btn);
-    Ok(())
+/// Called by the platform layers when overlay `id`'s timeout elapses. The overlay
+/// may belong to the app's manager or to the control server's.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+fn overlay_expired(id: u64) {
+    let app = OVERLAY_APP.lock().unwrap().clone();
+    let closed = app
+        .as_ref()
+        .is_some_and(|app| destroy_overlay_in(&app.state::<OverlayManager>(), id).is_ok())
+        || destroy_overlay_in(&OVERLAY_MANAGER, id).is_ok();
+    // Not found means it was closed before its time ran out.
+    if !closed {
+        return;
+    }
+    crash::breadcrumb("overlay", format!("expired {}", id));
+    if let Some(app) = app {
+        events::emit(&app, OVERLAY_EXPIRED_EVENT, OverlayEvent { id });
+    }
 }
 
-#[tauri::command]
-fn key_press(text: String) -> Result<(), String> {
-    let mut enigo = Enigo::new();
+/// Set while a relayout waits for the main thread; every overlay window hears about
+/// the same display change.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+static RELAYOUT_PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Called by the platform layers when monitors are added, removed, moved or change
+/// resolution. Refits the overlays of both managers on the main thread.
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn overlay_displays_changed() {
+    if RELAYOUT_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let Some(app) = OVERLAY_APP.lock().unwrap().clone() else {
+        RELAYOUT_PENDING.store(false, Ordering::SeqCst);
+        return;
+    };
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        RELAYOUT_PENDING.store(false, Ordering::SeqCst);
+        crash::breadcrumb("overlay", "displays changed");
+        relayout_overlays_in(&handle.state::<OverlayManager>());
+        relayout_overlays_in(&OVERLAY_MANAGER);
+    });
+}
 
-    for ch in text.chars() {
-        enigo.key_click(Key::Layout(ch));
+/// Gives the overlays of both managers the display affinity now in force, after
+/// it changes.
+fn overlay_affinity_changed() {
+    let mut handles: Vec<(u64, *mut c_void)> = OVERLAY_MANAGER
+        .overlays
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|o| (o.id, o.handle))
+        .collect();
+    if let Some(app) = OVERLAY_APP.lock().unwrap().clone() {
+        let manager = app.state::<OverlayManager>();
+        let overlays = manager.overlays.lock().unwrap();
+        handles.extend(overlays.iter().map(|o| (o.id, o.handle)));
+    }
+    for (id, handle) in handles {
+        if let Err(e) = Backend::apply_privacy(handle) {
+            log::warn!("failed to update overlay {}: {}", id, e);
+        }
     }
+}
 
+/// Called by the platform layers once the thread owning the overlay windows was
+/// replaced. Reopens the overlays of both managers in new windows.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn overlays_restarted() -> Result<(), String> {
+    crash::breadcrumb("overlay", "recreating overlays");
+    reopen_overlays_in(&OVERLAY_MANAGER)?;
+    if let Some(app) = OVERLAY_APP.lock().unwrap().clone() {
+        reopen_overlays_in(&app.state::<OverlayManager>())?;
+    }
     Ok(())
 }
 
+/// Frame thickness of preview outlines, in points (pixels at 96 DPI).
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+const OUTLINE_WIDTH: i32 = 3;
+
+/// Size of overlay progress bars, in points (scaled to the DPI on Windows).
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const PROGRESS_WIDTH: f64 = 320.0;
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const PROGRESS_HEIGHT: f64 = 6.0;
+/// The unfilled part of a progress bar; the filled part uses the palette's accent.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const PROGRESS_TRACK: palette::Rgb = (0x40, 0x40, 0x40);
+
+/// Size of overlay cancel buttons and their distance from the bottom edge, in points.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const BUTTON_WIDTH: f64 = 160.0;
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const BUTTON_HEIGHT: f64 = 44.0;
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+const BUTTON_MARGIN: f64 = 48.0;
+
+/// `size` scaled down, keeping its aspect ratio, to fit within `bounds`.
+#[cfg_attr(
+    not(any(target_os = "macos", target_os = "windows", target_os = "linux")),
+    allow(dead_code)
+)]
+fn fit((width, height): (f64, f64), (max_width, max_height): (f64, f64)) -> (f64, f64) {
+    let scale = (max_width / width).min(max_height / height).clamp(0.0, 1.0);
+    (width * scale, height * scale)
+}
+
 // ==========================================
 // PRIVACY OVERLAY (macOS-specific)
 // ==========================================
@@ -84,103 +428,2944 @@ fn key_press(text: String) -> Result<(), String> {
 #[cfg(target_os = "macos")]
 mod macos_overlay {
     use super::*;
+    use crate::layers::Layer;
+    use crate::palette::{self, ColorRole};
+    use crate::shape::Shape;
     use cocoa::appkit::{
-        NSApp, NSApplication, NSBackingStoreType, NSColor, NSView, NSWindow, NSWindowStyleMask,
+        NSApp, NSApplication, NSApplicationActivationPolicy, NSBackingStoreType, NSColor,
+        NSEventMask, NSView, NSWindow, NSWindowStyleMask,
     };
-    use cocoa::base::{id, nil};
-    use cocoa::foundation::{NSAutoreleasePool, NSRect};
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSArray, NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString};
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Object, Sel};
     use objc::{class, msg_send, sel, sel_impl};
 
-    pub fn create_privacy_overlay() -> Result<*mut c_void, String> {
+    const TARGET_CLASS: &str = "ScreenShareHostOverlayTarget";
+    /// NSBezelStyleRegularSquare, which unlike the rounded style can be any height.
+    const BEZEL_REGULAR_SQUARE: u64 = 2;
+
+    /// Default opacity; matches the alpha of the Windows overlay.
+    const OPACITY: f64 = 0.6;
+    const TEXT_SIZE: f64 = 28.0;
+    const TEXT_ALIGNMENT_CENTER: u64 = 2;
+
+    /// Overlay window → text label, for overlays showing text.
+    static LABELS: Lazy<Mutex<std::collections::HashMap<usize, usize>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Overlay window → progress bar, for overlays showing progress.
+    static BARS: Lazy<Mutex<std::collections::HashMap<usize, Bar>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+
+    /// Overlay window → image view, for overlays showing an image.
+    static IMAGES: Lazy<Mutex<std::collections::HashMap<usize, Picture>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Overlay window → cancel button, for overlays that have one.
+    static BUTTONS: Lazy<Mutex<std::collections::HashMap<usize, usize>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+
+    #[derive(Clone, Copy)]
+    struct Picture {
+        view: usize,
+        /// Natural size, in points.
+        size: NSSize,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Bar {
+        track: usize,
+        /// Subview of the track, sized to the percentage.
+        fill: usize,
+        percent: f64,
+    }
+
+    /// `bounds` is in native top-left points; `None` covers every screen, one window
+    /// each. Screen frames are in points, so Retina screens are covered whole whatever
+    /// their backing scale factor.
+    pub fn create_privacy_overlay(
+        bounds: Option<crate::coords::Rect>,
+    ) -> Result<Vec<*mut c_void>, String> {
         unsafe {
             let _pool = NSAutoreleasePool::new(nil);
 
             let app: id = NSApp();
             app.activateIgnoringOtherApps_(true);
 
-            let screen: id = msg_send![class!(NSScreen), mainScreen];
-            let frame: NSRect = msg_send![screen, frame];
+            let screens: id = msg_send![class!(NSScreen), screens];
+            let count = screens.count();
+            if count == 0 {
+                return Err("No screens found".to_string());
+            }
+            let frames: Vec<NSRect> = match bounds {
+                Some(rect) => vec![cocoa_frame(screens, rect)],
+                None => (0..count)
+                    .map(|i| -> NSRect { msg_send![screens.objectAtIndex(i), frame] })
+                    .collect(),
+            };
+
+            Ok(frames.into_iter().map(|frame| open(frame)).collect())
+        }
+    }
 
-            let window: id = msg_send![class!(NSWindow), alloc];
-            let style_mask = NSWindowStyleMask::NSBorderlessWindowMask;
+    /// Cocoa frames are bottom-left based, relative to the primary (first) screen.
+    unsafe fn cocoa_frame(screens: id, rect: crate::coords::Rect) -> NSRect {
+        let primary: NSRect = msg_send![screens.objectAtIndex(0), frame];
+        NSRect::new(
+            NSPoint::new(
+                rect.x as f64,
+                primary.size.height - (rect.y + rect.height) as f64,
+            ),
+            NSSize::new(rect.width as f64, rect.height as f64),
+        )
+    }
 
-            let overlay: id = window
-                .initWithContentRect_styleMask_backing_defer_(
-                    frame,
-                    style_mask,
-                    NSBackingStoreType::NSBackingStoreBuffered,
-                    false,
-                );
+    /// Moves and resizes an open overlay; `rect` is in native top-left points.
+    pub fn move_overlay(overlay: *mut c_void, rect: crate::coords::Rect) -> Result<(), String> {
+        unsafe {
+            let screens: id = msg_send![class!(NSScreen), screens];
+            if screens.count() == 0 {
+                return Err("No screens found".to_string());
+            }
+            let frame = cocoa_frame(screens, rect);
+            let _: () = msg_send![overlay as id, setFrame: frame display: YES];
+            layout(overlay as id);
+        }
+        Ok(())
+    }
 
-            overlay.setBackgroundColor_(NSColor::colorWithCalibratedRed_green_blue_alpha_(
-                nil, 0.0, 0.0, 0.0, 0.6,
-            ));
-            overlay.setLevel_((i32::MAX as i64)); // fixed type for macOS
-            overlay.makeKeyAndOrderFront_(nil);
+    /// Stacks the image, the word-wrapped label and the progress bar, whichever the
+    /// overlay has, top to bottom and centers them in the window.
+    unsafe fn layout(window: id) {
+        let picture = IMAGES.lock().unwrap().get(&(window as usize)).copied();
+        let label = LABELS.lock().unwrap().get(&(window as usize)).copied();
+        let bar = BARS.lock().unwrap().get(&(window as usize)).copied();
+        let content: id = msg_send![window, contentView];
+        let bounds: NSRect = msg_send![content, bounds];
+        let padding = TEXT_SIZE / 2.0;
+        let width = (bounds.size.width - 2.0 * padding).max(1.0);
 
-            Ok(overlay as *mut c_void)
+        let (image_width, image_height) = picture.map_or((0.0, 0.0), |picture| {
+            fit(
+                (picture.size.width, picture.size.height),
+                (width, bounds.size.height / 2.0),
+            )
+        });
+        let text_height = match label {
+            Some(label) => {
+                let cell: id = msg_send![label as id, cell];
+                let fitting: NSSize = msg_send![cell, cellSizeForBounds: NSRect::new(
+                    NSPoint::new(0.0, 0.0),
+                    NSSize::new(width, f64::MAX),
+                )];
+                fitting.height.min(bounds.size.height)
+            }
+            None => 0.0,
+        };
+        let bar_height = bar.map_or(0.0, |_| PROGRESS_HEIGHT);
+        let parts = [picture.is_some(), label.is_some(), bar.is_some()]
+            .iter()
+            .filter(|shown| **shown)
+            .count();
+        let gaps = padding * parts.saturating_sub(1) as f64;
+        let total = image_height + text_height + bar_height + gaps;
+        // Cocoa's y axis points up, so parts are placed bottom first.
+        let mut y = ((bounds.size.height - total) / 2.0).max(0.0);
+
+        if let Some(bar) = bar {
+            let bar_width = PROGRESS_WIDTH.min(width);
+            let track = NSRect::new(
+                NSPoint::new((bounds.size.width - bar_width) / 2.0, y),
+                NSSize::new(bar_width, PROGRESS_HEIGHT),
+            );
+            let fill = NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(bar_width * bar.percent / 100.0, PROGRESS_HEIGHT),
+            );
+            let _: () = msg_send![bar.track as id, setFrame: track];
+            let _: () = msg_send![bar.fill as id, setFrame: fill];
+            y += bar_height + padding;
+        }
+        if let Some(label) = label {
+            let frame = NSRect::new(NSPoint::new(padding, y), NSSize::new(width, text_height));
+            let _: () = msg_send![label as id, setFrame: frame];
+            y += text_height + padding;
+        }
+        if let Some(picture) = picture {
+            let frame = NSRect::new(
+                NSPoint::new((bounds.size.width - image_width) / 2.0, y),
+                NSSize::new(image_width, image_height),
+            );
+            let _: () = msg_send![picture.view as id, setFrame: frame];
+        }
+        // The button sits near the bottom, outside the centered stack.
+        let button = BUTTONS.lock().unwrap().get(&(window as usize)).copied();
+        if let Some(button) = button {
+            let frame = NSRect::new(
+                NSPoint::new((bounds.size.width - BUTTON_WIDTH) / 2.0, BUTTON_MARGIN),
+                NSSize::new(BUTTON_WIDTH, BUTTON_HEIGHT),
+            );
+            let _: () = msg_send![button as id, setFrame: frame];
         }
     }
 
-    pub fn destroy_privacy_overlay(manager: &super::OverlayManager) {
+    extern "C" fn button_clicked(_this: &Object, _sel: Sel, sender: id) {
+        let tag: isize = unsafe { msg_send![sender, tag] };
+        super::overlay_cancelled(tag as u64);
+    }
+
+    extern "C" fn timer_fired(_this: &Object, _sel: Sel, timer: id) {
+        let overlay_id: u64 = unsafe {
+            let number: id = msg_send![timer, userInfo];
+            msg_send![number, unsignedLongLongValue]
+        };
+        super::overlay_expired(overlay_id);
+    }
+
+    extern "C" fn screens_changed(_this: &Object, _sel: Sel, _notification: id) {
+        super::overlay_displays_changed();
+    }
+
+    /// One target shared by every cancel button and timeout timer; a button's tag
+    /// and a timer's user info hold the overlay id. It also observes screen changes
+    /// from the first overlay on.
+    fn target() -> id {
+        static TARGET: Lazy<usize> = Lazy::new(|| {
+            let mut decl =
+                ClassDecl::new(TARGET_CLASS, class!(NSObject)).expect("overlay target class");
+            unsafe {
+                decl.add_method(
+                    sel!(cancel:),
+                    button_clicked as extern "C" fn(&Object, Sel, id),
+                );
+                decl.add_method(
+                    sel!(expire:),
+                    timer_fired as extern "C" fn(&Object, Sel, id),
+                );
+                decl.add_method(
+                    sel!(screensChanged:),
+                    screens_changed as extern "C" fn(&Object, Sel, id),
+                );
+                let target: id = msg_send![decl.register(), new];
+                let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+                let name = NSString::alloc(nil)
+                    .init_str("NSApplicationDidChangeScreenParametersNotification");
+                let _: () = msg_send![center,
+                    addObserver: target
+                    selector: sel!(screensChanged:)
+                    name: name
+                    object: nil];
+                let _: () = msg_send![name, release];
+                target as usize
+            }
+        });
+        *TARGET as id
+    }
+
+    /// Closes overlay `overlay_id` after `ms`. A timer outliving its overlay finds
+    /// nothing to close.
+    pub fn set_timeout(overlay_id: u64, ms: u64) {
+        unsafe {
+            let number: id = msg_send![class!(NSNumber), numberWithUnsignedLongLong: overlay_id];
+            let _: id = msg_send![class!(NSTimer),
+                scheduledTimerWithTimeInterval: ms as f64 / 1000.0
+                target: target()
+                selector: sel!(expire:)
+                userInfo: number
+                repeats: NO];
+        }
+    }
+
+    /// Shows a cancel button labeled `label` that reports clicks as overlay
+    /// `overlay_id`, replacing any button it had.
+    pub fn set_button(overlay: *mut c_void, overlay_id: u64, label: &str) {
+        let window = overlay as id;
         unsafe {
-            let overlays = manager.overlays.lock().unwrap();
-            for overlay_ptr in overlays.iter() {
-                let window: id = *overlay_ptr as id;
-                let _: () = msg_send![window, close];
+            let title = NSString::alloc(nil).init_str(label);
+            let button: id = msg_send![class!(NSButton),
+                buttonWithTitle: title
+                target: target()
+                action: sel!(cancel:)];
+            let _: () = msg_send![title, release];
+            let _: () = msg_send![button, setTag: overlay_id as isize];
+            let _: () = msg_send![button, setBezelStyle: BEZEL_REGULAR_SQUARE];
+            let content: id = msg_send![window, contentView];
+            let _: () = msg_send![content, addSubview: button];
+            let previous = BUTTONS
+                .lock()
+                .unwrap()
+                .insert(overlay as usize, button as usize);
+            if let Some(previous) = previous {
+                let _: () = msg_send![previous as id, removeFromSuperview];
             }
+            layout(window);
         }
     }
-}
 
-#[tauri::command]
-fn create_privacy_overlay(state: State<'_, OverlayManager>) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        match macos_overlay::create_privacy_overlay() {
-            Ok(ptr) => {
-                state.add_overlay(ptr);
-                Ok(())
+    /// Shows `text` centered on the overlay, adding the label on first use; `None`
+    /// removes it.
+    pub fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        let window = overlay as id;
+        let mut labels = LABELS.lock().unwrap();
+        unsafe {
+            let Some(text) = text else {
+                if let Some(label) = labels.remove(&(overlay as usize)) {
+                    let _: () = msg_send![label as id, removeFromSuperview];
+                }
+                drop(labels);
+                layout(window);
+                return;
+            };
+            let string = NSString::alloc(nil).init_str(text);
+            match labels.get(&(overlay as usize)) {
+                Some(label) => {
+                    let _: () = msg_send![*label as id, setStringValue: string];
+                }
+                None => {
+                    let label: id = msg_send![class!(NSTextField), labelWithString: string];
+                    let font: id = msg_send![class!(NSFont), boldSystemFontOfSize: TEXT_SIZE];
+                    let _: () = msg_send![label, setFont: font];
+                    let color = palette::ns_color(palette::color(ColorRole::Text), 1.0);
+                    let _: () = msg_send![label, setTextColor: color];
+                    let _: () = msg_send![label, setAlignment: TEXT_ALIGNMENT_CENTER];
+                    let _: () = msg_send![label, setMaximumNumberOfLines: 0isize];
+                    let content: id = msg_send![window, contentView];
+                    let _: () = msg_send![content, addSubview: label];
+                    labels.insert(overlay as usize, label as usize);
+                }
             }
-            Err(e) => Err(e),
+            let _: () = msg_send![string, release];
+            drop(labels);
+            layout(window);
         }
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        Err("Privacy overlay is only supported on macOS".to_string())
+    /// Shows a bar `percent` full below the overlay's text, adding it on first use;
+    /// `None` removes it.
+    pub fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        let window = overlay as id;
+        let mut bars = BARS.lock().unwrap();
+        unsafe {
+            let Some(percent) = percent else {
+                if let Some(bar) = bars.remove(&(overlay as usize)) {
+                    let _: () = msg_send![bar.track as id, removeFromSuperview];
+                }
+                drop(bars);
+                layout(window);
+                return;
+            };
+            match bars.get_mut(&(overlay as usize)) {
+                Some(bar) => bar.percent = percent,
+                None => {
+                    let track = bar_view(palette::ns_color(PROGRESS_TRACK, 1.0));
+                    let accent = palette::color(ColorRole::Accent);
+                    let fill = bar_view(palette::ns_color(accent, 1.0));
+                    let _: () = msg_send![track, addSubview: fill];
+                    let _: () = msg_send![fill, release];
+                    let content: id = msg_send![window, contentView];
+                    let _: () = msg_send![content, addSubview: track];
+                    let _: () = msg_send![track, release];
+                    bars.insert(
+                        overlay as usize,
+                        Bar {
+                            track: track as usize,
+                            fill: fill as usize,
+                            percent,
+                        },
+                    );
+                }
+            }
+            drop(bars);
+            layout(window);
+        }
     }
-}
 
-#[tauri::command]
-fn destroy_privacy_overlay(state: State<'_, OverlayManager>) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        macos_overlay::destroy_privacy_overlay(&state);
+    /// Shows `image` (premultiplied RGBA) above the overlay's text, one pixel per
+    /// point, replacing any image it had.
+    pub fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        let window = overlay as id;
+        let (width, height) = image.dimensions();
+        unsafe {
+            let color_space = NSString::alloc(nil)
+                .init_str("NSDeviceRGBColorSpace")
+                .autorelease();
+            let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+            let rep: id = msg_send![rep,
+                initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>()
+                pixelsWide: width as isize
+                pixelsHigh: height as isize
+                bitsPerSample: 8isize
+                samplesPerPixel: 4isize
+                hasAlpha: YES
+                isPlanar: NO
+                colorSpaceName: color_space
+                bytesPerRow: (width * 4) as isize
+                bitsPerPixel: 32isize];
+            if rep == nil {
+                return Err("Failed to create overlay image".to_string());
+            }
+            let data: *mut u8 = msg_send![rep, bitmapData];
+            std::ptr::copy_nonoverlapping(image.as_raw().as_ptr(), data, image.as_raw().len());
+            let size = NSSize::new(width as f64, height as f64);
+            let picture: id = msg_send![class!(NSImage), alloc];
+            let picture: id = msg_send![picture, initWithSize: size];
+            let _: () = msg_send![picture, addRepresentation: rep];
+            let _: () = msg_send![rep, release];
+
+            let mut images = IMAGES.lock().unwrap();
+            let view = match images.get(&(overlay as usize)) {
+                Some(existing) => existing.view as id,
+                None => {
+                    let view: id = msg_send![class!(NSImageView), alloc];
+                    let view: id = msg_send![view, initWithFrame: NSRect::new(
+                        NSPoint::new(0.0, 0.0),
+                        size,
+                    )];
+                    // NSImageScaleProportionallyUpOrDown; `layout` sizes the view.
+                    let _: () = msg_send![view, setImageScaling: 3u64];
+                    let content: id = msg_send![window, contentView];
+                    let _: () = msg_send![content, addSubview: view];
+                    let _: () = msg_send![view, release];
+                    view
+                }
+            };
+            let _: () = msg_send![view, setImage: picture];
+            let _: () = msg_send![picture, release];
+            images.insert(
+                overlay as usize,
+                Picture {
+                    view: view as usize,
+                    size,
+                },
+            );
+            drop(images);
+            layout(window);
+        }
         Ok(())
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        Err("Privacy overlay is only supported on macOS".to_string())
+    /// A layer-backed view filled with `color`; owned by the caller.
+    unsafe fn bar_view(color: id) -> id {
+        let view: id = msg_send![class!(NSView), alloc];
+        let view: id = msg_send![view, initWithFrame: NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(0.0, 0.0),
+        )];
+        let _: () = msg_send![view, setWantsLayer: YES];
+        let layer: id = msg_send![view, layer];
+        let color: *const c_void = msg_send![color, CGColor];
+        let _: () = msg_send![layer, setBackgroundColor: color];
+        view
+    }
+
+    /// Opaque black; the window's alpha value makes it translucent, as on Windows.
+    unsafe fn background() -> id {
+        NSColor::colorWithCalibratedRed_green_blue_alpha_(nil, 0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// 1 hides what the overlay covers; lower values only dim it.
+    pub fn set_opacity(overlay: *mut c_void, opacity: f64) {
+        unsafe {
+            let _: () = msg_send![overlay as id, setAlphaValue: opacity];
+        }
+    }
+
+    /// Interactive overlays take clicks; the others pass them to what they cover.
+    pub fn set_interactive(overlay: *mut c_void, interactive: bool) {
+        unsafe {
+            let ignores = if interactive { NO } else { YES };
+            let _: () = msg_send![overlay as id, setIgnoresMouseEvents: ignores];
+        }
+    }
+
+    /// Hides an overlay without closing it, or shows it again.
+    pub fn set_visible(overlay: *mut c_void, visible: bool) {
+        unsafe {
+            if visible {
+                let _: () = msg_send![overlay as id, orderFrontRegardless];
+            } else {
+                let _: () = msg_send![overlay as id, orderOut: nil];
+            }
+        }
+    }
+
+    /// Rounds the overlay's corners and strokes its border.
+    pub fn set_shape(overlay: *mut c_void, shape: &Shape) {
+        unsafe {
+            crate::shape::apply(overlay as id, shape, background());
+        }
+    }
+
+    unsafe fn open(frame: NSRect) -> *mut c_void {
+        let window: id = msg_send![class!(NSWindow), alloc];
+        let style_mask = NSWindowStyleMask::NSBorderlessWindowMask;
+
+        let overlay: id = window.initWithContentRect_styleMask_backing_defer_(
+            frame,
+            style_mask,
+            NSBackingStoreType::NSBackingStoreBuffered,
+            false,
+        );
+
+        // Released by `dispose`, not by closing, so no other path frees it under us.
+        let _: () = msg_send![overlay, setReleasedWhenClosed: NO];
+        overlay.setBackgroundColor_(background());
+        let _: () = msg_send![overlay, setOpaque: NO];
+        let _: () = msg_send![overlay, setAlphaValue: OPACITY];
+        crate::layers::add(Layer::Privacy, overlay as usize);
+        overlay.makeKeyAndOrderFront_(nil);
+        target();
+
+        overlay as *mut c_void
+    }
+
+    /// A click-through frame with a faint fill, hidden from capture, marking where
+    /// an overlay would go.
+    pub fn open_outline(rect: crate::coords::Rect) -> Result<*mut c_void, String> {
+        unsafe {
+            let screens: id = msg_send![class!(NSScreen), screens];
+            if screens.count() == 0 {
+                return Err("No screens found".to_string());
+            }
+            let frame = cocoa_frame(screens, rect);
+
+            let window: id = msg_send![class!(NSWindow), alloc];
+            let window: id = window.initWithContentRect_styleMask_backing_defer_(
+                frame,
+                NSWindowStyleMask::NSBorderlessWindowMask,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                false,
+            );
+            if window == nil {
+                return Err("Failed to create outline window".to_string());
+            }
+            let accent = palette::color(ColorRole::Accent);
+            let _: () = msg_send![window, setReleasedWhenClosed: NO];
+            let _: () = msg_send![window, setOpaque: NO];
+            window.setBackgroundColor_(palette::ns_color(accent, 0.12));
+            let _: () = msg_send![window, setIgnoresMouseEvents: YES];
+            let _: () = msg_send![window, setSharingType: 0u64];
+            crate::layers::add(Layer::Preview, window as usize);
+
+            let view: id = msg_send![window, contentView];
+            let _: () = msg_send![view, setWantsLayer: YES];
+            let layer: id = msg_send![view, layer];
+            let border = palette::ns_color(accent, 0.9);
+            let border: *const c_void = msg_send![border, CGColor];
+            let _: () = msg_send![layer, setBorderColor: border];
+            let _: () = msg_send![layer, setBorderWidth: OUTLINE_WIDTH as f64];
+
+            window.orderFrontRegardless();
+            Ok(window as *mut c_void)
+        }
+    }
+
+    pub fn close_outline(outline: *mut c_void) {
+        crate::layers::remove(outline as usize);
+        unsafe {
+            let window = outline as id;
+            let _: () = msg_send![window, close];
+            let _: () = msg_send![window, release];
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn init_headless() {
+        unsafe {
+            let app = NSApplication::sharedApplication(nil);
+            app.setActivationPolicy_(
+                NSApplicationActivationPolicy::NSApplicationActivationPolicyAccessory,
+            );
+            app.finishLaunching();
+        }
+    }
+
+    /// Drains pending AppKit events when there is no Tauri event loop to do it.
+    #[cfg(feature = "headless")]
+    pub fn pump_events() {
+        unsafe {
+            let pool = NSAutoreleasePool::new(nil);
+            let app: id = NSApp();
+            let mode = NSString::alloc(nil)
+                .init_str("kCFRunLoopDefaultMode")
+                .autorelease();
+
+            loop {
+                let until: id = msg_send![class!(NSDate), distantPast];
+                let event = app.nextEventMatchingMask_untilDate_inMode_dequeue_(
+                    NSEventMask::NSAnyEventMask.bits(),
+                    until,
+                    mode,
+                    YES,
+                );
+                if event == nil {
+                    break;
+                }
+                app.sendEvent_(event);
+            }
+
+            pool.drain();
+        }
+    }
+
+    /// Takes an overlay off screen and frees it along with its subviews.
+    unsafe fn dispose(overlay: *mut c_void) {
+        let pool = NSAutoreleasePool::new(nil);
+        crate::layers::remove(overlay as usize);
+        LABELS.lock().unwrap().remove(&(overlay as usize));
+        BARS.lock().unwrap().remove(&(overlay as usize));
+        IMAGES.lock().unwrap().remove(&(overlay as usize));
+        BUTTONS.lock().unwrap().remove(&(overlay as usize));
+        let window = overlay as id;
+        let _: () = msg_send![window, orderOut: nil];
+        let _: () = msg_send![window, close];
+        let _: () = msg_send![window, release];
+        pool.drain();
+    }
+
+    // The manager's lock is released before disposing: closing a window can run
+    // AppKit callbacks that reach back into the manager.
+
+    pub fn destroy_overlay(manager: &super::OverlayManager, overlay: *mut c_void) {
+        let removed = {
+            let mut overlays = manager.overlays.lock().unwrap();
+            overlays
+                .iter()
+                .position(|o| o.handle == overlay)
+                .map(|index| overlays.remove(index))
+        };
+        if removed.is_some() {
+            unsafe { dispose(overlay) };
+        }
+    }
+
+    pub fn destroy_privacy_overlay(manager: &super::OverlayManager) {
+        let overlays: Vec<_> = manager.overlays.lock().unwrap().drain(..).collect();
+        for overlay in overlays {
+            unsafe { dispose(overlay.handle) };
+        }
     }
 }
 
 // ==========================================
-// TAURI ENTRY
+// PRIVACY OVERLAY (Windows-specific)
 // ==========================================
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .manage(OverlayManager::new())
-        .invoke_handler(tauri::generate_handler![
-            mouse_move,
-            mouse_click,
-            key_press,
-            create_privacy_overlay,
-            destroy_privacy_overlay
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+// Overlay windows live on a dedicated thread that runs a `GetMessage` loop, so their
+// window procedures, timers and repaints don't depend on whichever thread invoked
+// the command that opened them. Windows can only be destroyed, and their timers
+// set, by the thread that created them, so creating, moving, timing and closing
+// overlays are requests sent to that thread over a channel; the caller blocks until
+// the reply. Updates that only change what an overlay paints are posted to the
+// window directly.
+
+#[cfg(target_os = "windows")]
+mod windows_overlay {
+    use super::*;
+    use crate::layers::Layer;
+    use crate::palette::{self, ColorRole};
+    use crate::shape::{self, Shape};
+    use std::sync::mpsc;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{
+        COLORREF, ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM,
+    };
+    use windows::Win32::Graphics::Gdi::{
+        BeginPaint, CreateFontW, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint, FillRect,
+        GetStockObject, InvalidateRect, PtInRect, RoundRect, SelectObject, SetBkMode,
+        SetStretchBltMode, SetTextColor, StretchDIBits, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+        BLACK_BRUSH, CLEARTYPE_QUALITY, CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DIB_RGB_COLORS,
+        DT_CALCRECT, DT_CENTER, DT_SINGLELINE, DT_VCENTER, DT_WORDBREAK, FW_SEMIBOLD, HALFTONE,
+        HBRUSH, HDC, NULL_PEN, OUT_DEFAULT_PRECIS, PAINTSTRUCT, SRCCOPY, TRANSPARENT,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::HiDpi::GetDpiForWindow;
+    #[cfg(feature = "headless")]
+    use windows::Win32::UI::HiDpi::{
+        SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect,
+        GetMessageW, GetWindowLongPtrW, KillTimer, PeekMessageW, PostMessageW, PostThreadMessageW,
+        RegisterClassW, SetLayeredWindowAttributes, SetTimer, SetWindowLongPtrW, SetWindowPos,
+        ShowWindow, ShowWindowAsync, TranslateMessage, CS_HREDRAW, CS_VREDRAW, GWL_EXSTYLE,
+        LWA_ALPHA, LWA_COLORKEY, MSG, PM_NOREMOVE, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE,
+        SWP_NOSIZE, SWP_NOZORDER, SW_HIDE, SW_SHOWNOACTIVATE, WM_APP, WM_DISPLAYCHANGE,
+        WM_DPICHANGED, WM_LBUTTONUP, WM_NCDESTROY, WM_PAINT, WM_QUIT, WM_SIZE, WM_TIMER, WNDCLASSW,
+        WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_EX_TRANSPARENT,
+        WS_POPUP,
+    };
+
+    const CLASS_NAME: PCWSTR = w!("ScreenShareHostPrivacyOverlay");
+    const OUTLINE_CLASS_NAME: PCWSTR = w!("ScreenShareHostOverlayOutline");
+    /// Default alpha; matches the 0.6 opacity of the macOS overlay.
+    const ALPHA: u8 = 153;
+    const OUTLINE_ALPHA: u8 = 230;
+    /// Painted inside the frame and keyed out, so only the frame shows.
+    const OUTLINE_KEY: COLORREF = COLORREF(0x00FF_00FF);
+    /// Points at 96 DPI, scaled to the window's DPI.
+    const TEXT_SIZE: f64 = 28.0;
+    const BUTTON_TEXT_SIZE: f64 = 18.0;
+    /// Posted to an overlay whose text or progress changed, so it repaints on its own
+    /// thread.
+    const WM_OVERLAY_UPDATE: u32 = WM_APP + 1;
+    /// Posted to the overlay thread when a request is waiting in its channel.
+    const WM_OVERLAY_REQUEST: u32 = WM_APP + 2;
+
+    /// Text per overlay window, read by `WM_PAINT`.
+    static TEXTS: Lazy<Mutex<std::collections::HashMap<isize, String>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Progress percentage per overlay window, read by `WM_PAINT`.
+    static PROGRESS: Lazy<Mutex<std::collections::HashMap<isize, f64>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Image per overlay window, read by `WM_PAINT`.
+    static IMAGES: Lazy<Mutex<std::collections::HashMap<isize, Picture>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Corner radius and border per overlay window, for those that have one.
+    static SHAPES: Lazy<Mutex<std::collections::HashMap<isize, Shape>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+    /// Cancel button label and overlay id per overlay window, for those that have one.
+    static BUTTONS: Lazy<Mutex<std::collections::HashMap<isize, (String, u64)>>> =
+        Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+
+    struct Picture {
+        width: i32,
+        height: i32,
+        /// Premultiplied, which over the black background is the color to draw.
+        bgra: Vec<u8>,
+    }
+
+    /// Work for the overlay thread. Window handles travel as `isize`, since raw
+    /// pointers can't cross threads.
+    enum Request {
+        Open {
+            rects: Vec<crate::coords::Rect>,
+            reply: mpsc::Sender<Result<Vec<isize>, String>>,
+        },
+        OpenOutline {
+            rect: crate::coords::Rect,
+            reply: mpsc::Sender<Result<isize, String>>,
+        },
+        Move {
+            overlay: isize,
+            rect: crate::coords::Rect,
+            reply: mpsc::Sender<Result<(), String>>,
+        },
+        SetTimeout {
+            overlay: isize,
+            id: u64,
+            ms: u32,
+            reply: mpsc::Sender<Result<(), String>>,
+        },
+        Close {
+            window: isize,
+            reply: mpsc::Sender<()>,
+        },
+    }
+
+    struct Worker {
+        thread: u32,
+        requests: mpsc::Sender<Request>,
+    }
+
+    /// Watchdog entry of the overlay thread.
+    const WATCHED: &str = "overlay-windows";
+
+    /// Started by the first request, and replaced if the watchdog finds it stalled.
+    static WORKER: Lazy<Mutex<Option<Worker>>> = Lazy::new(|| Mutex::new(None));
+
+    fn spawn_worker() -> Result<Worker, String> {
+        let (requests, inbox) = mpsc::channel::<Request>();
+        let (ready, started) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("overlay-windows".to_string())
+            .spawn(move || unsafe {
+                let mut msg = MSG::default();
+                // Creates the thread's message queue before anyone posts to it.
+                let _ = PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE);
+                let _ = SetTimer(HWND::default(), 0, watchdog::HEARTBEAT_MS, None);
+                let _ = ready.send(GetCurrentThreadId());
+                // GetMessageW returns -1 on failure, which must end the loop too.
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    if msg.hwnd == HWND::default() {
+                        match msg.message {
+                            WM_OVERLAY_REQUEST => {
+                                while let Ok(request) = inbox.try_recv() {
+                                    handle(request);
+                                }
+                                continue;
+                            }
+                            WM_TIMER => {
+                                watchdog::heartbeat(WATCHED);
+                                continue;
+                            }
+                            _ => {}
+                        }
+                    }
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn overlay thread: {}", e))?;
+        let thread = started
+            .recv()
+            .map_err(|_| "Overlay thread failed to start".to_string())?;
+        Ok(Worker { thread, requests })
+    }
+
+    /// The overlay thread's id and inbox, starting it if need be.
+    fn worker() -> Result<(u32, mpsc::Sender<Request>), String> {
+        let mut worker = WORKER.lock().unwrap();
+        if worker.is_none() {
+            *worker = Some(spawn_worker()?);
+            watchdog::register(
+                WATCHED,
+                watchdog::THREAD_TIMEOUT,
+                Some(Arc::new(restart_worker)),
+            );
+        }
+        let worker = worker.as_ref().expect("started above");
+        Ok((worker.thread, worker.requests.clone()))
+    }
+
+    /// Replaces a stalled overlay thread and reopens the overlays in windows of the
+    /// new one. The stalled thread is told to quit, which takes its windows along
+    /// once it recovers.
+    fn restart_worker() -> Result<(), String> {
+        let fresh = spawn_worker()?;
+        if let Some(stalled) = WORKER.lock().unwrap().replace(fresh) {
+            let _ = unsafe { PostThreadMessageW(stalled.thread, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+        super::overlays_restarted()
+    }
+
+    /// Runs a request on the overlay thread and waits for its reply. Requests made
+    /// on that thread itself, e.g. closing an overlay whose timeout fired, run in
+    /// place.
+    fn request<T>(make: impl FnOnce(mpsc::Sender<T>) -> Request) -> Result<T, String> {
+        let (thread, requests) = worker()?;
+        let (reply, answer) = mpsc::channel();
+        let request = make(reply);
+        if unsafe { GetCurrentThreadId() } == thread {
+            handle(request);
+        } else {
+            requests
+                .send(request)
+                .map_err(|_| "Overlay thread has stopped".to_string())?;
+            unsafe { PostThreadMessageW(thread, WM_OVERLAY_REQUEST, WPARAM(0), LPARAM(0)) }
+                .map_err(|e| format!("Failed to reach overlay thread: {}", e))?;
+        }
+        answer
+            .recv()
+            .map_err(|_| "Overlay thread has stopped".to_string())
+    }
+
+    /// Carries out a request on the overlay thread.
+    fn handle(request: Request) {
+        match request {
+            Request::Open { rects, reply } => {
+                let _ = reply.send(open_all(rects));
+            }
+            Request::OpenOutline { rect, reply } => {
+                let _ = reply.send(open_outline_window(rect).map(|hwnd| hwnd as isize));
+            }
+            Request::Move {
+                overlay,
+                rect,
+                reply,
+            } => {
+                let moved = unsafe {
+                    SetWindowPos(
+                        HWND(overlay as *mut c_void),
+                        HWND::default(),
+                        rect.x,
+                        rect.y,
+                        rect.width,
+                        rect.height,
+                        SWP_NOACTIVATE | SWP_NOZORDER,
+                    )
+                };
+                let _ = reply.send(moved.map_err(|e| format!("Failed to move overlay: {}", e)));
+            }
+            Request::SetTimeout {
+                overlay,
+                id,
+                ms,
+                reply,
+            } => {
+                let set = unsafe { SetTimer(HWND(overlay as *mut c_void), id as usize, ms, None) };
+                let _ = reply.send(if set == 0 {
+                    Err(format!(
+                        "Failed to start overlay timeout: {}",
+                        windows::core::Error::from_win32()
+                    ))
+                } else {
+                    Ok(())
+                });
+            }
+            Request::Close { window, reply } => {
+                let _ = unsafe { DestroyWindow(HWND(window as *mut c_void)) };
+                let _ = reply.send(());
+            }
+        }
+    }
+
+    /// Registers a window class, with the reason if Windows refuses. A class that
+    /// is already registered, e.g. by an earlier copy of this module, is kept.
+    unsafe fn register(class: &WNDCLASSW, name: &str) -> Result<(), String> {
+        if RegisterClassW(class) != 0 {
+            return Ok(());
+        }
+        let error = windows::core::Error::from_win32();
+        if error.code() == ERROR_CLASS_ALREADY_EXISTS.to_hresult() {
+            return Ok(());
+        }
+        Err(format!(
+            "Failed to register {} window class: {}",
+            name, error
+        ))
+    }
+
+    static REGISTERED: Lazy<Result<(), String>> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            // Text stays centered as the overlay is resized.
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(wnd_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            hbrBackground: HBRUSH(GetStockObject(BLACK_BRUSH).0),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        register(&class, "overlay")
+    });
+
+    static OUTLINE_REGISTERED: Lazy<Result<(), String>> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(outline_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            lpszClassName: OUTLINE_CLASS_NAME,
+            ..Default::default()
+        };
+        register(&class, "outline")
+    });
+
+    /// Draws the overlay's image, word-wrapped text and progress bar, whichever it
+    /// has, stacked top to bottom and centered on both axes.
+    unsafe fn paint(hwnd: HWND) {
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+        let key = hwnd.0 as isize;
+        if let Some(shape) = SHAPES.lock().unwrap().get(&key) {
+            shape::draw_border(hwnd, hdc, shape);
+        }
+        let text = TEXTS.lock().unwrap().get(&key).cloned();
+        let progress = PROGRESS.lock().unwrap().get(&key).copied();
+        let images = IMAGES.lock().unwrap();
+        let picture = images.get(&key);
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+        let (width, height) = (client.right - client.left, client.bottom - client.top);
+        let scale = GetDpiForWindow(hwnd) as f64 / 96.0;
+        let padding = (TEXT_SIZE * scale / 2.0).round() as i32;
+
+        let picture_size = picture.map(|picture| {
+            let (w, h) = fit(
+                (picture.width as f64 * scale, picture.height as f64 * scale),
+                ((width - 2 * padding) as f64, height as f64 / 2.0),
+            );
+            (w.round() as i32, h.round() as i32)
+        });
+        let font = text.as_ref().map(|_| {
+            CreateFontW(
+                -(TEXT_SIZE * scale).round() as i32,
+                0,
+                0,
+                0,
+                FW_SEMIBOLD.0 as i32,
+                0,
+                0,
+                0,
+                DEFAULT_CHARSET.0 as u32,
+                OUT_DEFAULT_PRECIS.0 as u32,
+                CLIP_DEFAULT_PRECIS.0 as u32,
+                CLEARTYPE_QUALITY.0 as u32,
+                0,
+                w!("Segoe UI"),
+            )
+        });
+        let previous = font.map(|font| SelectObject(hdc, font));
+        let mut text: Vec<u16> = text.map_or_else(Vec::new, |text| text.encode_utf16().collect());
+        let mut area = RECT {
+            left: client.left + padding,
+            top: client.top,
+            right: client.right - padding,
+            bottom: client.bottom,
+        };
+        let text_height = if text.is_empty() {
+            0
+        } else {
+            let mut measured = area;
+            DrawTextW(
+                hdc,
+                &mut text,
+                &mut measured,
+                DT_CENTER | DT_WORDBREAK | DT_CALCRECT,
+            );
+            measured.bottom - measured.top
+        };
+        let bar_height = progress.map_or(0, |_| (PROGRESS_HEIGHT * scale).round() as i32);
+
+        let parts = [picture.is_some(), !text.is_empty(), progress.is_some()]
+            .iter()
+            .filter(|shown| **shown)
+            .count() as i32;
+        let gaps = padding * (parts - 1).max(0);
+        let total = picture_size.map_or(0, |(_, h)| h) + text_height + bar_height + gaps;
+        let mut top = client.top + (height - total).max(0) / 2;
+
+        if let (Some(picture), Some((w, h))) = (picture, picture_size) {
+            draw_picture(hdc, picture, client.left + (width - w) / 2, top, w, h);
+            top += h + padding;
+        }
+        if !text.is_empty() {
+            SetBkMode(hdc, TRANSPARENT);
+            SetTextColor(hdc, palette::colorref(palette::color(ColorRole::Text)));
+            area.top = top;
+            DrawTextW(hdc, &mut text, &mut area, DT_CENTER | DT_WORDBREAK);
+            top += text_height + padding;
+        }
+        if let Some(percent) = progress {
+            let bar_width = ((PROGRESS_WIDTH * scale).round() as i32)
+                .min(width - 2 * padding)
+                .max(0);
+            let left = client.left + (width - bar_width) / 2;
+            let track = RECT {
+                left,
+                top,
+                right: left + bar_width,
+                bottom: top + bar_height,
+            };
+            draw_progress(hdc, track, percent);
+        }
+
+        if let (Some(font), Some(previous)) = (font, previous) {
+            SelectObject(hdc, previous);
+            let _ = DeleteObject(font);
+        }
+        if let Some((label, _)) = BUTTONS.lock().unwrap().get(&key) {
+            draw_button(hdc, button_rect(&client, scale), label, scale);
+        }
+        drop(images);
+        let _ = EndPaint(hwnd, &ps);
+    }
+
+    /// Stretches `picture` into the given rectangle.
+    unsafe fn draw_picture(hdc: HDC, picture: &Picture, x: i32, y: i32, w: i32, h: i32) {
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: picture.width,
+                // Negative height for top-down rows.
+                biHeight: -picture.height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        SetStretchBltMode(hdc, HALFTONE);
+        StretchDIBits(
+            hdc,
+            x,
+            y,
+            w,
+            h,
+            0,
+            0,
+            picture.width,
+            picture.height,
+            Some(picture.bgra.as_ptr() as *const c_void),
+            &info,
+            DIB_RGB_COLORS,
+            SRCCOPY,
+        );
+    }
+
+    /// Where the cancel button sits: centered near the bottom of the client area.
+    fn button_rect(client: &RECT, scale: f64) -> RECT {
+        let width = (BUTTON_WIDTH * scale).round() as i32;
+        let height = (BUTTON_HEIGHT * scale).round() as i32;
+        let left = client.left + (client.right - client.left - width) / 2;
+        let bottom = client.bottom - (BUTTON_MARGIN * scale).round() as i32;
+        RECT {
+            left,
+            top: bottom - height,
+            right: left + width,
+            bottom,
+        }
+    }
+
+    /// An accent-filled rounded button with `label` centered on it.
+    unsafe fn draw_button(hdc: HDC, rect: RECT, label: &str, scale: f64) {
+        let brush = CreateSolidBrush(palette::colorref(palette::color(ColorRole::Accent)));
+        let previous_brush = SelectObject(hdc, brush);
+        let previous_pen = SelectObject(hdc, GetStockObject(NULL_PEN));
+        let diameter = (12.0 * scale).round() as i32;
+        let _ = RoundRect(
+            hdc,
+            rect.left,
+            rect.top,
+            rect.right,
+            rect.bottom,
+            diameter,
+            diameter,
+        );
+        SelectObject(hdc, previous_pen);
+        SelectObject(hdc, previous_brush);
+        let _ = DeleteObject(brush);
+
+        let font = CreateFontW(
+            -(BUTTON_TEXT_SIZE * scale).round() as i32,
+            0,
+            0,
+            0,
+            FW_SEMIBOLD.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            OUT_DEFAULT_PRECIS.0 as u32,
+            CLIP_DEFAULT_PRECIS.0 as u32,
+            CLEARTYPE_QUALITY.0 as u32,
+            0,
+            w!("Segoe UI"),
+        );
+        let previous = SelectObject(hdc, font);
+        SetBkMode(hdc, TRANSPARENT);
+        SetTextColor(hdc, palette::colorref(palette::color(ColorRole::Text)));
+        let mut text: Vec<u16> = label.encode_utf16().collect();
+        let mut area = rect;
+        DrawTextW(
+            hdc,
+            &mut text,
+            &mut area,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE,
+        );
+        SelectObject(hdc, previous);
+        let _ = DeleteObject(font);
+    }
+
+    /// Fills `track` with the track color, then its first `percent` with the accent.
+    unsafe fn draw_progress(hdc: HDC, track: RECT, percent: f64) {
+        let fill = RECT {
+            right: track.left
+                + ((track.right - track.left) as f64 * percent / 100.0).round() as i32,
+            ..track
+        };
+        let brush = CreateSolidBrush(palette::colorref(PROGRESS_TRACK));
+        FillRect(hdc, &track, brush);
+        let _ = DeleteObject(brush);
+        let brush = CreateSolidBrush(palette::colorref(palette::color(ColorRole::Accent)));
+        FillRect(hdc, &fill, brush);
+        let _ = DeleteObject(brush);
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match msg {
+            WM_PAINT => {
+                paint(hwnd);
+                LRESULT(0)
+            }
+            WM_OVERLAY_UPDATE => {
+                let _ = InvalidateRect(hwnd, None, true);
+                LRESULT(0)
+            }
+            WM_SIZE => {
+                let shape = SHAPES.lock().unwrap().get(&(hwnd.0 as isize)).copied();
+                if let Some(shape) = shape {
+                    shape::clip(hwnd, &shape);
+                }
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            WM_DISPLAYCHANGE => {
+                super::overlay_displays_changed();
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            // The suggested rect is ignored: overlays keep the native-pixel bounds
+            // they were given, and only their contents follow the new scale.
+            WM_DPICHANGED => {
+                let shape = SHAPES.lock().unwrap().get(&(hwnd.0 as isize)).copied();
+                if let Some(shape) = shape {
+                    shape::clip(hwnd, &shape);
+                }
+                let _ = InvalidateRect(hwnd, None, true);
+                LRESULT(0)
+            }
+            WM_TIMER => {
+                // The timer id is the overlay id; each overlay has at most one.
+                let _ = KillTimer(hwnd, wparam.0);
+                super::overlay_expired(wparam.0 as u64);
+                LRESULT(0)
+            }
+            WM_LBUTTONUP => {
+                let button = BUTTONS.lock().unwrap().get(&(hwnd.0 as isize)).map(|b| b.1);
+                if let Some(id) = button {
+                    let mut client = RECT::default();
+                    let _ = GetClientRect(hwnd, &mut client);
+                    let scale = GetDpiForWindow(hwnd) as f64 / 96.0;
+                    // Client coordinates, signed 16-bit each.
+                    let point = POINT {
+                        x: (lparam.0 & 0xFFFF) as i16 as i32,
+                        y: ((lparam.0 >> 16) & 0xFFFF) as i16 as i32,
+                    };
+                    if PtInRect(&button_rect(&client, scale), point).as_bool() {
+                        super::overlay_cancelled(id);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_NCDESTROY => {
+                TEXTS.lock().unwrap().remove(&(hwnd.0 as isize));
+                PROGRESS.lock().unwrap().remove(&(hwnd.0 as isize));
+                IMAGES.lock().unwrap().remove(&(hwnd.0 as isize));
+                SHAPES.lock().unwrap().remove(&(hwnd.0 as isize));
+                BUTTONS.lock().unwrap().remove(&(hwnd.0 as isize));
+                DefWindowProcW(hwnd, msg, wparam, lparam)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+
+    /// Sets the text shown on an overlay; `None` clears it. Safe from any thread:
+    /// the window repaints when it handles the posted message.
+    pub fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        let key = overlay as isize;
+        match text {
+            Some(text) => TEXTS.lock().unwrap().insert(key, text.to_string()),
+            None => TEXTS.lock().unwrap().remove(&key),
+        };
+        unsafe {
+            let _ = PostMessageW(HWND(overlay), WM_OVERLAY_UPDATE, WPARAM(0), LPARAM(0));
+        }
+    }
+
+    /// Shows `image` (premultiplied RGBA) above the overlay's text, one pixel per
+    /// point, replacing any image it had. Safe from any thread, like `set_text`.
+    pub fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        let bgra = image.pixels().flat_map(|p| [p[2], p[1], p[0], 0]).collect();
+        IMAGES.lock().unwrap().insert(
+            overlay as isize,
+            Picture {
+                width: image.width() as i32,
+                height: image.height() as i32,
+                bgra,
+            },
+        );
+        unsafe {
+            let _ = PostMessageW(HWND(overlay), WM_OVERLAY_UPDATE, WPARAM(0), LPARAM(0));
+        }
+        Ok(())
+    }
+
+    /// Rounds the overlay's corners and strokes its border.
+    pub fn set_shape(overlay: *mut c_void, shape: &Shape) {
+        SHAPES.lock().unwrap().insert(overlay as isize, *shape);
+        shape::clip(HWND(overlay), shape);
+        unsafe {
+            let _ = PostMessageW(HWND(overlay), WM_OVERLAY_UPDATE, WPARAM(0), LPARAM(0));
+        }
+    }
+
+    /// Closes the overlay after `ms`, reporting it as overlay `id`. The timer belongs
+    /// to the overlay thread and dies with the window.
+    pub fn set_timeout(overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String> {
+        request(|reply| Request::SetTimeout {
+            overlay: overlay as isize,
+            id,
+            // USER_TIMER_MAXIMUM, about 24.8 days.
+            ms: ms.min(0x7FFF_FFFF) as u32,
+            reply,
+        })?
+    }
+
+    /// Shows a cancel button labeled `label` that reports clicks as overlay `id`. Safe
+    /// from any thread, like `set_text`.
+    pub fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+        BUTTONS
+            .lock()
+            .unwrap()
+            .insert(overlay as isize, (label.to_string(), id));
+        unsafe {
+            let _ = PostMessageW(HWND(overlay), WM_OVERLAY_UPDATE, WPARAM(0), LPARAM(0));
+        }
+    }
+
+    /// Interactive overlays take clicks; the others are `WS_EX_TRANSPARENT` and pass
+    /// them to what they cover. Safe from any thread in this process.
+    pub fn set_interactive(overlay: *mut c_void, interactive: bool) -> Result<(), String> {
+        let hwnd = HWND(overlay);
+        unsafe {
+            let style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+            let transparent = WS_EX_TRANSPARENT.0 as isize;
+            let style = if interactive {
+                style & !transparent
+            } else {
+                style | transparent
+            };
+            // Overlays always have extended styles, so the previous value is never 0.
+            if SetWindowLongPtrW(hwnd, GWL_EXSTYLE, style) == 0 {
+                return Err(format!(
+                    "Failed to change overlay click-through: {}",
+                    windows::core::Error::from_win32()
+                ));
+            }
+            // Extended styles are cached until the frame is refreshed.
+            SetWindowPos(
+                hwnd,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+            .map_err(|e| format!("Failed to change overlay click-through: {}", e))
+        }
+    }
+
+    /// Hides an overlay without closing it, or shows it again. Asynchronous, so the
+    /// caller never waits on the overlay thread.
+    pub fn set_visible(overlay: *mut c_void, visible: bool) {
+        let command = if visible { SW_SHOWNOACTIVATE } else { SW_HIDE };
+        unsafe {
+            let _ = ShowWindowAsync(HWND(overlay), command);
+        }
+    }
+
+    /// 1 hides what the overlay covers; lower values only dim it.
+    pub fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String> {
+        let alpha = (opacity * 255.0).round() as u8;
+        unsafe { SetLayeredWindowAttributes(HWND(overlay), COLORREF(0), alpha, LWA_ALPHA) }
+            .map_err(|e| format!("Failed to set overlay opacity: {}", e))
+    }
+
+    /// Sets the progress bar shown on an overlay; `None` removes it. Safe from any
+    /// thread, like `set_text`.
+    pub fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        let key = overlay as isize;
+        match percent {
+            Some(percent) => PROGRESS.lock().unwrap().insert(key, percent),
+            None => PROGRESS.lock().unwrap().remove(&key),
+        };
+        unsafe {
+            let _ = PostMessageW(HWND(overlay), WM_OVERLAY_UPDATE, WPARAM(0), LPARAM(0));
+        }
+    }
+
+    unsafe extern "system" fn outline_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if msg == WM_DPICHANGED {
+            let _ = InvalidateRect(hwnd, None, true);
+            return LRESULT(0);
+        }
+        if msg != WM_PAINT {
+            return DefWindowProcW(hwnd, msg, wparam, lparam);
+        }
+        let mut ps = PAINTSTRUCT::default();
+        let hdc = BeginPaint(hwnd, &mut ps);
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+        let width = (OUTLINE_WIDTH as f64 * GetDpiForWindow(hwnd) as f64 / 96.0).round() as i32;
+
+        let frame = CreateSolidBrush(palette::colorref(palette::color(ColorRole::Accent)));
+        FillRect(hdc, &client, frame);
+        let _ = DeleteObject(frame);
+        let inside = RECT {
+            left: client.left + width,
+            top: client.top + width,
+            right: client.right - width,
+            bottom: client.bottom - width,
+        };
+        if inside.right > inside.left && inside.bottom > inside.top {
+            let key = CreateSolidBrush(OUTLINE_KEY);
+            FillRect(hdc, &inside, key);
+            let _ = DeleteObject(key);
+        }
+        let _ = EndPaint(hwnd, &ps);
+        LRESULT(0)
+    }
+
+    fn open(rect: crate::coords::Rect) -> Result<*mut c_void, String> {
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_NOACTIVATE,
+                CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| format!("Failed to get module handle: {}", e))?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create overlay window: {}", e))?;
+
+            let setup = SetLayeredWindowAttributes(hwnd, COLORREF(0), ALPHA, LWA_ALPHA)
+                .and_then(|_| crate::affinity::apply(hwnd));
+            if let Err(e) = setup {
+                let _ = DestroyWindow(hwnd);
+                return Err(format!("Failed to set up overlay window: {}", e));
+            }
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            Ok(hwnd.0)
+        }
+    }
+
+    /// Opens one overlay per rect on the overlay thread, or none if any fails.
+    fn open_all(rects: Vec<crate::coords::Rect>) -> Result<Vec<isize>, String> {
+        REGISTERED.as_ref().map_err(Clone::clone)?;
+        let mut overlays = Vec::new();
+        for rect in rects {
+            match open(rect) {
+                Ok(overlay) => overlays.push(overlay as isize),
+                Err(e) => {
+                    for overlay in overlays {
+                        let _ = unsafe { DestroyWindow(HWND(overlay as *mut c_void)) };
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(overlays)
+    }
+
+    /// `bounds` is in native pixels; `None` covers every monitor, one window each,
+    /// so no display of a multi-monitor setup is left exposed.
+    pub fn create_privacy_overlay(
+        bounds: Option<crate::coords::Rect>,
+    ) -> Result<Vec<*mut c_void>, String> {
+        let rects = match bounds {
+            Some(rect) => vec![rect],
+            None => crate::coords::monitors()
+                .iter()
+                .map(|m| crate::coords::Rect {
+                    x: m.x,
+                    y: m.y,
+                    width: m.width,
+                    height: m.height,
+                })
+                .collect(),
+        };
+        if rects.is_empty() {
+            return Err("No monitors found".to_string());
+        }
+
+        let overlays = request(|reply| Request::Open { rects, reply })??;
+        // Stacked from here rather than the overlay thread: restacking touches
+        // windows of other threads, which may be waiting on this request.
+        for overlay in &overlays {
+            crate::layers::add(Layer::Privacy, *overlay as usize);
+        }
+        Ok(overlays
+            .into_iter()
+            .map(|overlay| overlay as *mut c_void)
+            .collect())
+    }
+
+    /// Opts the process into per-monitor DPI awareness, which the Tauri event loop
+    /// does otherwise. Without it Windows hands out scaled monitor rects and stretches
+    /// the overlays' bitmaps, so text blurs and bounds miss on scaled displays.
+    #[cfg(feature = "headless")]
+    pub fn init_headless() {
+        unsafe {
+            if let Err(e) =
+                SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2)
+            {
+                log::warn!("Failed to enable per-monitor DPI awareness: {}", e);
+            }
+        }
+    }
+
+    /// A click-through frame, hidden from capture, marking where an overlay would go.
+    pub fn open_outline(rect: crate::coords::Rect) -> Result<*mut c_void, String> {
+        let outline = request(|reply| Request::OpenOutline { rect, reply })??;
+        crate::layers::add(Layer::Preview, outline as usize);
+        Ok(outline as *mut c_void)
+    }
+
+    fn open_outline_window(rect: crate::coords::Rect) -> Result<*mut c_void, String> {
+        OUTLINE_REGISTERED.as_ref().map_err(Clone::clone)?;
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST
+                    | WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TRANSPARENT
+                    | WS_EX_NOACTIVATE,
+                OUTLINE_CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| format!("Failed to get module handle: {}", e))?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create outline window: {}", e))?;
+
+            let setup = SetLayeredWindowAttributes(
+                hwnd,
+                OUTLINE_KEY,
+                OUTLINE_ALPHA,
+                LWA_COLORKEY | LWA_ALPHA,
+            )
+            .and_then(|_| crate::affinity::apply(hwnd));
+            if let Err(e) = setup {
+                let _ = DestroyWindow(hwnd);
+                return Err(format!("Failed to set up outline window: {}", e));
+            }
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            Ok(hwnd.0)
+        }
+    }
+
+    /// Closes an overlay or outline on the overlay thread.
+    fn close(window: *mut c_void) {
+        crate::layers::remove(window as usize);
+        let _ = request(|reply| Request::Close {
+            window: window as isize,
+            reply,
+        });
+    }
+
+    pub fn close_outline(outline: *mut c_void) {
+        close(outline);
+    }
+
+    /// Moves and resizes an open overlay without changing its place in the stack.
+    pub fn move_overlay(overlay: *mut c_void, rect: crate::coords::Rect) -> Result<(), String> {
+        request(|reply| Request::Move {
+            overlay: overlay as isize,
+            rect,
+            reply,
+        })?
+    }
+
+    // The manager's lock is released before closing: the overlay thread may be
+    // waiting for it, e.g. to drop an overlay whose timeout fired.
+
+    pub fn destroy_overlay(manager: &super::OverlayManager, overlay: *mut c_void) {
+        let removed = {
+            let mut overlays = manager.overlays.lock().unwrap();
+            overlays
+                .iter()
+                .position(|o| o.handle == overlay)
+                .map(|index| overlays.remove(index))
+        };
+        if removed.is_some() {
+            close(overlay);
+        }
+    }
+
+    pub fn destroy_privacy_overlay(manager: &super::OverlayManager) {
+        let overlays: Vec<_> = manager.overlays.lock().unwrap().drain(..).collect();
+        for overlay in overlays {
+            close(overlay.handle);
+        }
+    }
+}
+
+/// A picture drawn centered on privacy overlays, e.g. a company logo.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum OverlayImage {
+    /// A PNG file.
+    Path(std::path::PathBuf),
+    /// PNG bytes, base64-encoded.
+    Base64(String),
+}
+
+impl OverlayImage {
+    /// Decodes the PNG with premultiplied alpha, as both platforms draw it.
+    fn load(&self) -> Result<image::RgbaImage, String> {
+        let bytes = match self {
+            OverlayImage::Path(path) => std::fs::read(path)
+                .map_err(|e| format!("Failed to read overlay image {}: {}", path.display(), e))?,
+            OverlayImage::Base64(data) => STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("Invalid base64 overlay image: {}", e))?,
+        };
+        let mut image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png)
+            .map_err(|e| format!("Invalid PNG overlay image: {}", e))?
+            .to_rgba8();
+        for pixel in image.pixels_mut() {
+            let alpha = pixel[3] as u16;
+            for channel in &mut pixel.0[..3] {
+                *channel = (*channel as u16 * alpha / 255) as u8;
+            }
+        }
+        Ok(image)
+    }
+}
+
+fn create_overlay_in(
+    manager: &OverlayManager,
+    bounds: Option<&coords::Bounds>,
+    text: Option<&str>,
+    image: Option<&OverlayImage>,
+    opacity: Option<f64>,
+    cancel_button: Option<&str>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<u64>, String> {
+    crash::breadcrumb("overlay", "create_privacy_overlay");
+    check_options(opacity, timeout_ms)?;
+    let rect = bounds.map(coords::resolve_bounds).transpose()?;
+    let image = image.map(OverlayImage::load).transpose()?;
+    let ids = open_overlays_in(manager, rect, text)?;
+    let cancel_button = cancel_button.filter(|label| !label.is_empty());
+    style_overlays_in(
+        manager,
+        &ids,
+        opacity,
+        image.as_ref(),
+        None,
+        cancel_button,
+        timeout_ms,
+    )?;
+    Ok(ids)
+}
+
+fn check_options(opacity: Option<f64>, timeout_ms: Option<u64>) -> Result<(), String> {
+    if let Some(opacity) = opacity.filter(|opacity| !(0.0..=1.0).contains(opacity)) {
+        return Err(format!("Opacity {} is not between 0 and 1", opacity));
+    }
+    if timeout_ms == Some(0) {
+        return Err("Timeout must be at least 1 ms".to_string());
+    }
+    Ok(())
+}
+
+/// Applies the optional opacity, image (premultiplied RGBA), shape, cancel button and
+/// timeout to freshly opened overlays. If any fails they are all closed, so no
+/// display is left with a cover that looks or behaves differently from the one
+/// asked for.
+fn style_overlays_in(
+    manager: &OverlayManager,
+    ids: &[u64],
+    opacity: Option<f64>,
+    image: Option<&image::RgbaImage>,
+    shape: Option<&shape::Shape>,
+    cancel_button: Option<&str>,
+    timeout_ms: Option<u64>,
+) -> Result<(), String> {
+    style_overlays_with::<Backend>(
+        manager,
+        ids,
+        opacity,
+        image,
+        shape,
+        cancel_button,
+        timeout_ms,
+    )
+}
+
+fn style_overlays_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    ids: &[u64],
+    opacity: Option<f64>,
+    image: Option<&image::RgbaImage>,
+    shape: Option<&shape::Shape>,
+    cancel_button: Option<&str>,
+    timeout_ms: Option<u64>,
+) -> Result<(), String> {
+    let styled = ids.iter().try_for_each(|&id| {
+        let handle = manager
+            .handle(id)
+            .ok_or_else(|| format!("Overlay {} not found", id))?;
+
+        if let Some(opacity) = opacity {
+            B::set_opacity(handle, opacity)?;
+        }
+        if let Some(image) = image {
+            B::set_image(handle, image)?;
+        }
+        if let Some(shape) = shape {
+            B::set_shape(handle, shape)?;
+        }
+        if let Some(label) = cancel_button {
+            B::set_button(handle, id, label);
+        }
+        if let Some(ms) = timeout_ms {
+            B::set_timeout(handle, id, ms)?;
+        }
+
+        Ok(())
+    });
+    if styled.is_err() {
+        for &id in ids {
+            let _ = destroy_overlay_with::<B>(manager, id);
+        }
+    }
+    styled
+}
+
+/// Covers only the monitor at `index` in `list_monitors` order.
+fn create_monitor_overlay_in(
+    manager: &OverlayManager,
+    index: usize,
+    text: Option<&str>,
+    opacity: Option<f64>,
+    timeout_ms: Option<u64>,
+) -> Result<u64, String> {
+    crash::breadcrumb("overlay", format!("create_overlay_for_monitor {}", index));
+    check_options(opacity, timeout_ms)?;
+    let monitors = coords::monitors();
+    let monitor = monitors.get(index).ok_or_else(|| {
+        format!(
+            "Monitor index {} out of range (found {})",
+            index,
+            monitors.len()
+        )
+    })?;
+    let rect = coords::Rect {
+        x: monitor.x,
+        y: monitor.y,
+        width: monitor.width,
+        height: monitor.height,
+    };
+    let id = open_overlay_in(manager, rect, Some(index), text)?;
+    style_overlays_in(manager, &[id], opacity, None, None, None, timeout_ms)?;
+    Ok(id)
+}
+
+/// Removes the overlays covering monitor `index`, or covers it when there are
+/// none. Returns the new overlay's id, or `None` when it was removed.
+fn toggle_monitor_overlay_in(
+    manager: &OverlayManager,
+    index: usize,
+    text: Option<&str>,
+) -> Result<Option<u64>, String> {
+    let covering: Vec<u64> = manager
+        .overlays
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|overlay| overlay.monitor == Some(index))
+        .map(|overlay| overlay.id)
+        .collect();
+    if covering.is_empty() {
+        return create_monitor_overlay_in(manager, index, text, None, None).map(Some);
+    }
+    for id in covering {
+        destroy_overlay_in(manager, id)?;
+    }
+    Ok(None)
+}
+
+/// Covers only the given rectangle, e.g. a chat panel.
+#[allow(clippy::too_many_arguments)]
+fn create_region_overlay_in(
+    manager: &OverlayManager,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    space: Option<coords::CoordinateSpace>,
+    monitor: Option<usize>,
+    text: Option<&str>,
+    shape: Option<&shape::WindowShape>,
+    opacity: Option<f64>,
+    timeout_ms: Option<u64>,
+) -> Result<u64, String> {
+    crash::breadcrumb(
+        "overlay",
+        format!("create_privacy_region {},{} {}x{}", x, y, w, h),
+    );
+    let rect = region_rect(x, y, w, h, space, monitor)?;
+    check_options(opacity, timeout_ms)?;
+    let shape = shape
+        .map(shape::WindowShape::shape)
+        .transpose()?
+        .filter(|shape| !shape.is_plain());
+    let id = open_overlay_in(manager, rect, None, text)?;
+    style_overlays_in(
+        manager,
+        &[id],
+        opacity,
+        None,
+        shape.as_ref(),
+        None,
+        timeout_ms,
+    )?;
+    Ok(id)
+}
+
+/// Resolves an overlay region given as x/y/w/h to native coordinates.
+fn region_rect(
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    space: Option<coords::CoordinateSpace>,
+    monitor: Option<usize>,
+) -> Result<coords::Rect, String> {
+    if w <= 0.0 || h <= 0.0 {
+        return Err("Region width and height must be positive".to_string());
+    }
+    coords::resolve_bounds(&coords::Bounds::Absolute {
+        x,
+        y,
+        width: w,
+        height: h,
+        space: space.unwrap_or_default(),
+        monitor,
+    })
+}
+
+/// Opens overlays over `rect`, or over every monitor, and returns their ids.
+fn open_overlays_in(
+    manager: &OverlayManager,
+    rect: Option<coords::Rect>,
+    text: Option<&str>,
+) -> Result<Vec<u64>, String> {
+    open_overlays_with::<Backend>(manager, rect, text)
+}
+
+fn open_overlays_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    rect: Option<coords::Rect>,
+    text: Option<&str>,
+) -> Result<Vec<u64>, String> {
+    let targets: Vec<(coords::Rect, Option<usize>)> = match rect {
+        Some(rect) => vec![(rect, None)],
+        None => coords::monitors()
+            .iter()
+            .map(|m| {
+                let rect = coords::Rect {
+                    x: m.x,
+                    y: m.y,
+                    width: m.width,
+                    height: m.height,
+                };
+                (rect, Some(m.index))
+            })
+            .collect(),
+    };
+    if targets.is_empty() {
+        return Err("No monitors found".to_string());
+    }
+
+    let mut ids = Vec::new();
+    for (rect, monitor) in targets {
+        match open_overlay_with::<B>(manager, rect, monitor, text) {
+            Ok(id) => ids.push(id),
+            Err(e) => {
+                // All or nothing, so no display is left half covered.
+                for id in ids {
+                    let _ = destroy_overlay_with::<B>(manager, id);
+                }
+                return Err(e);
+            }
+        }
+    }
+    if rect.is_none() {
+        manager.set_all_monitors(&ids);
+    }
+    Ok(ids)
+}
+
+/// Opens a single overlay over `rect` and returns its id.
+fn open_overlay_in(
+    manager: &OverlayManager,
+    rect: coords::Rect,
+    monitor: Option<usize>,
+    text: Option<&str>,
+) -> Result<u64, String> {
+    open_overlay_with::<Backend>(manager, rect, monitor, text)
+}
+
+fn open_overlay_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    rect: coords::Rect,
+    monitor: Option<usize>,
+    text: Option<&str>,
+) -> Result<u64, String> {
+    let text = text.filter(|text| !text.is_empty());
+    let handle = B::create(rect)?;
+    if text.is_some() {
+        B::set_text(handle, text);
+    }
+    let id = manager.add_overlay(handle, rect, monitor, text.map(str::to_string));
+    sessions::track(sessions::Resource::Overlay(id));
+    audit_log::record(audit_log::AuditEvent::OverlayOpened {
+        id,
+        monitor,
+        text: text.map(str::to_string),
+    });
+    Ok(id)
+}
+
+fn destroy_overlays_in(manager: &OverlayManager) -> Result<(), String> {
+    destroy_overlays_with::<Backend>(manager)
+}
+
+fn destroy_overlays_with<B: OverlayBackend>(manager: &OverlayManager) -> Result<(), String> {
+    crash::breadcrumb("overlay", "destroy_privacy_overlay");
+    sessions::untrack(|r| matches!(r, sessions::Resource::Overlay(_)));
+    for overlay in manager.list() {
+        audit_log::record(audit_log::AuditEvent::OverlayClosed { id: overlay.id });
+    }
+    B::destroy_all(manager)
+}
+
+/// Moves and resizes overlay `id` in place, e.g. to follow a window.
+#[allow(clippy::too_many_arguments)]
+fn update_overlay_in(
+    manager: &OverlayManager,
+    id: u64,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    space: Option<coords::CoordinateSpace>,
+    monitor: Option<usize>,
+) -> Result<(), String> {
+    crash::breadcrumb("overlay", format!("update_overlay {}", id));
+    let rect = region_rect(x, y, w, h, space, monitor)?;
+    move_overlay_in(manager, id, rect)
+}
+
+/// Moves and resizes overlay `id` to `rect`, in native coordinates.
+fn move_overlay_in(manager: &OverlayManager, id: u64, rect: coords::Rect) -> Result<(), String> {
+    move_overlay_with::<Backend>(manager, id, rect)
+}
+
+fn move_overlay_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+    rect: coords::Rect,
+) -> Result<(), String> {
+    let handle = manager
+        .handle(id)
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    B::move_to(handle, rect)?;
+    manager.set_rect(id, rect);
+    Ok(())
+}
+
+/// Replaces the text shown on overlay `id`; an empty string clears it.
+fn update_overlay_text_in(manager: &OverlayManager, id: u64, text: &str) -> Result<(), String> {
+    update_overlay_text_with::<Backend>(manager, id, text)
+}
+
+fn update_overlay_text_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+    text: &str,
+) -> Result<(), String> {
+    crash::breadcrumb("overlay", format!("update_overlay_text {}", id));
+    let text = Some(text).filter(|text| !text.is_empty());
+    let handle = manager
+        .set_text(id, text.map(str::to_string))
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    B::set_text(handle, text);
+    Ok(())
+}
+
+/// Shows `percent` (0–100) as a bar on overlay `id`; `None` removes the bar.
+fn set_overlay_progress_in(
+    manager: &OverlayManager,
+    id: u64,
+    percent: Option<f64>,
+) -> Result<(), String> {
+    set_overlay_progress_with::<Backend>(manager, id, percent)
+}
+
+fn set_overlay_progress_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+    percent: Option<f64>,
+) -> Result<(), String> {
+    if percent.is_some_and(|percent| !(0.0..=100.0).contains(&percent)) {
+        return Err("Progress must be between 0 and 100".to_string());
+    }
+    let handle = manager
+        .set_progress(id, percent)
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    B::set_progress(handle, percent);
+    Ok(())
+}
+
+/// Hides overlay `id` without closing it, or shows it again.
+fn set_overlay_visible_in(manager: &OverlayManager, id: u64, visible: bool) -> Result<(), String> {
+    set_overlay_visible_with::<Backend>(manager, id, visible)
+}
+
+fn set_overlay_visible_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+    visible: bool,
+) -> Result<(), String> {
+    let handle = manager
+        .handle(id)
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    B::set_visible(handle, visible);
+    Ok(())
+}
+
+/// Switches overlay `id` between taking clicks and passing them through to what it
+/// covers.
+fn set_overlay_interactive_in(
+    manager: &OverlayManager,
+    id: u64,
+    interactive: bool,
+) -> Result<(), String> {
+    set_overlay_interactive_with::<Backend>(manager, id, interactive)
+}
+
+fn set_overlay_interactive_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+    interactive: bool,
+) -> Result<(), String> {
+    let handle = manager
+        .handle(id)
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    B::click_through(handle, !interactive)?;
+    manager.set_interactive(id, interactive);
+    Ok(())
+}
+
+/// Opens a new window for every overlay, under the same id, with its rect, text,
+/// progress and interactivity. Other styling isn't recorded, so the new windows get
+/// the default look. The old windows are left to the thread that owns them.
+fn reopen_overlays_in(manager: &OverlayManager) -> Result<(), String> {
+    reopen_overlays_with::<Backend>(manager)
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn reopen_overlays_with<B: OverlayBackend>(manager: &OverlayManager) -> Result<(), String> {
+    for overlay in manager.list() {
+        let handle = B::create(overlay.rect)?;
+        if overlay.text.is_some() {
+            B::set_text(handle, overlay.text.as_deref());
+        }
+        if overlay.progress.is_some() {
+            B::set_progress(handle, overlay.progress);
+        }
+        if !overlay.interactive {
+            B::click_through(handle, true)?;
+        }
+        let mut overlays = manager.overlays.lock().unwrap();
+        match overlays.iter_mut().find(|o| o.id == overlay.id) {
+            Some(reopened) => reopened.handle = handle,
+            // Closed meanwhile; recorded only so the usual path closes the window.
+            None => {
+                drop(overlays);
+                manager.add_overlay(handle, overlay.rect, None, None);
+                B::destroy(manager, handle);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Closes overlay `id`, e.g. when the session that created it ends.
+fn destroy_overlay_in(manager: &OverlayManager, id: u64) -> Result<(), String> {
+    destroy_overlay_with::<Backend>(manager, id)
+}
+
+fn destroy_overlay_with<B: OverlayBackend>(
+    manager: &OverlayManager,
+    id: u64,
+) -> Result<(), String> {
+    let handle = manager
+        .handle(id)
+        .ok_or_else(|| format!("Overlay {} not found", id))?;
+    sessions::untrack(|r| *r == sessions::Resource::Overlay(id));
+    audit_log::record(audit_log::AuditEvent::OverlayClosed { id });
+    B::destroy(manager, handle);
+    Ok(())
+}
+
+/// Fits the overlays covering whole monitors to the current layout. Each moves onto
+/// its monitor's new bounds and is closed if that monitor is gone. A cover over
+/// every monitor also spreads to monitors that were added, with the same text and
+/// interactivity; other styling isn't recorded, so those get the default look.
+/// Runs on the main thread.
+fn relayout_overlays_in(manager: &OverlayManager) {
+    let monitors = coords::monitors();
+    let covering: Vec<_> = manager
+        .overlays
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|o| Some((o.id, o.handle, o.monitor?, o.rect, o.all_monitors)))
+        .collect();
+    if covering.is_empty() {
+        return;
+    }
+
+    let mut covered = Vec::new();
+    let mut cover = None;
+    for (id, handle, index, rect, all_monitors) in covering {
+        let Some(m) = monitors.get(index) else {
+            crash::breadcrumb("overlay", format!("monitor {} gone, closing {}", index, id));
+            let _ = destroy_overlay_in(manager, id);
+            continue;
+        };
+        covered.push(index);
+        if all_monitors && cover.is_none() {
+            cover = Some(id);
+        }
+        let fitted = coords::Rect {
+            x: m.x,
+            y: m.y,
+            width: m.width,
+            height: m.height,
+        };
+        if fitted == rect {
+            continue;
+        }
+        match Backend::move_to(handle, fitted) {
+            Ok(()) => {
+                let mut overlays = manager.overlays.lock().unwrap();
+                if let Some(overlay) = overlays.iter_mut().find(|o| o.id == id) {
+                    overlay.rect = fitted;
+                }
+            }
+            Err(e) => log::warn!("failed to refit overlay {}: {}", id, e),
+        }
+    }
+
+    let Some(template) = cover.and_then(|id| manager.list().into_iter().find(|o| o.id == id))
+    else {
+        return;
+    };
+    for m in monitors.iter().filter(|m| !covered.contains(&m.index)) {
+        let rect = coords::Rect {
+            x: m.x,
+            y: m.y,
+            width: m.width,
+            height: m.height,
+        };
+        let opened = open_overlay_in(manager, rect, Some(m.index), template.text.as_deref())
+            .and_then(|id| {
+                manager.set_all_monitors(&[id]);
+                if !template.interactive {
+                    set_overlay_interactive_in(manager, id, false)?;
+                }
+                Ok(id)
+            });
+        match opened {
+            Ok(id) => crash::breadcrumb(
+                "overlay",
+                format!("covered monitor {} with {}", m.index, id),
+            ),
+            Err(e) => log::warn!("failed to cover monitor {}: {}", m.index, e),
+        }
+    }
+}
+
+// ==========================================
+// LAYOUT PREVIEW
+// ==========================================
+
+// Outlines drawn where a layout's overlays would go, so placement can be checked
+// before anything is covered. They are hidden from capture, let clicks through and
+// go away on their own; a new preview replaces the previous one.
+
+const PREVIEW_DURATION: Duration = Duration::from_secs(3);
+
+/// Bumped by each preview, so an older preview's timer leaves a newer one alone.
+static PREVIEW_GENERATION: AtomicU64 = AtomicU64::new(0);
+static PREVIEW_OUTLINES: Lazy<Mutex<Vec<usize>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn close_preview() {
+    for outline in PREVIEW_OUTLINES.lock().unwrap().drain(..) {
+        #[cfg(target_os = "macos")]
+        macos_overlay::close_outline(outline as *mut c_void);
+        #[cfg(target_os = "windows")]
+        windows_overlay::close_outline(outline as *mut c_void);
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        let _ = outline;
+    }
+}
+
+/// Outlines every region of `layout` for `duration` and returns how many were drawn.
+/// Runs on the main thread.
+fn preview_layout_in(
+    app: &AppHandle,
+    layout: &[coords::Bounds],
+    duration: Duration,
+) -> Result<usize, String> {
+    crash::breadcrumb("overlay", format!("preview_layout {}", layout.len()));
+    if layout.is_empty() {
+        return Err("Layout has no regions".to_string());
+    }
+    // Resolved up front, so a bad region leaves the previous preview in place.
+    let rects = layout
+        .iter()
+        .map(coords::resolve_bounds)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    close_preview();
+    let generation = PREVIEW_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    for rect in rects {
+        #[cfg(target_os = "macos")]
+        let outline = macos_overlay::open_outline(rect);
+        #[cfg(target_os = "windows")]
+        let outline = windows_overlay::open_outline(rect);
+        match outline {
+            Ok(outline) => PREVIEW_OUTLINES.lock().unwrap().push(outline as usize),
+            Err(e) => {
+                close_preview();
+                return Err(e);
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = (app, rects, generation, duration);
+        Err("Layout preview is only supported on macOS and Windows".to_string())
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = app.run_on_main_thread(move || {
+                if PREVIEW_GENERATION.load(Ordering::SeqCst) == generation {
+                    close_preview();
+                }
+            });
+        });
+        Ok(layout.len())
+    }
+}
+
+/// Draws temporary outlines where the overlays of `layout` would go.
+#[tauri::command]
+fn preview_layout(
+    app: AppHandle,
+    layout: Vec<coords::Bounds>,
+    duration_ms: Option<u64>,
+) -> Result<usize, String> {
+    let duration = duration_ms.map_or(PREVIEW_DURATION, Duration::from_millis);
+    preview_layout_in(&app, &layout, duration)
+}
+
+/// Removes the layout preview before it times out.
+#[tauri::command]
+fn clear_layout_preview() {
+    PREVIEW_GENERATION.fetch_add(1, Ordering::SeqCst);
+    close_preview();
+}
+
+/// Returns the ids of the overlays opened, one per monitor when `bounds` is omitted.
+/// `text` and `image` (`{ "path": .. }` or `{ "base64": .. }`, a PNG) are shown
+/// centered on each. `opacity` runs from 0 to 1: below 1 the overlay dims what it
+/// covers instead of hiding it. `cancel_button` labels a button near the bottom that
+/// emits `overlay://cancelled` with the overlay's id when clicked. After `timeout_ms`
+/// each overlay closes itself and emits `overlay://expired`, so a frontend that
+/// never calls destroy can't leave the screen covered.
+#[tauri::command]
+fn create_privacy_overlay(
+    state: State<'_, OverlayManager>,
+    bounds: Option<coords::Bounds>,
+    text: Option<String>,
+    image: Option<OverlayImage>,
+    opacity: Option<f64>,
+    cancel_button: Option<String>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<u64>, String> {
+    create_overlay_in(
+        &state,
+        bounds.as_ref(),
+        text.as_deref(),
+        image.as_ref(),
+        opacity,
+        cancel_button.as_deref(),
+        timeout_ms,
+    )
+}
+
+/// Shows the privacy overlay on one monitor and returns its id.
+#[tauri::command]
+fn create_overlay_for_monitor(
+    state: State<'_, OverlayManager>,
+    monitor_index: usize,
+    text: Option<String>,
+    opacity: Option<f64>,
+    timeout_ms: Option<u64>,
+) -> Result<u64, String> {
+    create_monitor_overlay_in(&state, monitor_index, text.as_deref(), opacity, timeout_ms)
+}
+
+/// Shows the privacy overlay on one monitor, or removes it if it is showing there.
+/// Returns the new overlay's id when one was opened.
+#[tauri::command]
+fn toggle_overlay_for_monitor(
+    state: State<'_, OverlayManager>,
+    monitor_index: usize,
+    text: Option<String>,
+) -> Result<Option<u64>, String> {
+    toggle_monitor_overlay_in(&state, monitor_index, text.as_deref())
+}
+
+/// Shows the privacy overlay over a region only and returns its id. `shape` rounds
+/// its corners and adds a border.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+fn create_privacy_region(
+    state: State<'_, OverlayManager>,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    space: Option<coords::CoordinateSpace>,
+    monitor: Option<usize>,
+    text: Option<String>,
+    shape: Option<shape::WindowShape>,
+    opacity: Option<f64>,
+    timeout_ms: Option<u64>,
+) -> Result<u64, String> {
+    create_region_overlay_in(
+        &state,
+        x,
+        y,
+        w,
+        h,
+        space,
+        monitor,
+        text.as_deref(),
+        shape.as_ref(),
+        opacity,
+        timeout_ms,
+    )
+}
+
+#[tauri::command]
+fn destroy_privacy_overlay(state: State<'_, OverlayManager>) -> Result<(), String> {
+    destroy_overlays_in(&state)
+}
+
+/// Moves and resizes an overlay without recreating it.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+fn update_overlay(
+    state: State<'_, OverlayManager>,
+    id: u64,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    space: Option<coords::CoordinateSpace>,
+    monitor: Option<usize>,
+) -> Result<(), String> {
+    update_overlay_in(&state, id, x, y, w, h, space, monitor)
+}
+
+/// Changes the text on an open overlay; an empty string clears it.
+#[tauri::command]
+fn update_overlay_text(
+    state: State<'_, OverlayManager>,
+    id: u64,
+    text: String,
+) -> Result<(), String> {
+    update_overlay_text_in(&state, id, &text)
+}
+
+/// Shows determinate progress on an overlay; omit `percent` to remove the bar.
+#[tauri::command]
+fn set_overlay_progress(
+    state: State<'_, OverlayManager>,
+    id: u64,
+    percent: Option<f64>,
+) -> Result<(), String> {
+    set_overlay_progress_in(&state, id, percent)
+}
+
+/// Makes an overlay clickable, or click-through so the content under it stays
+/// usable. Overlays start out clickable.
+#[tauri::command]
+fn set_overlay_interactive(
+    state: State<'_, OverlayManager>,
+    id: u64,
+    interactive: bool,
+) -> Result<(), String> {
+    set_overlay_interactive_in(&state, id, interactive)
+}
+
+#[tauri::command]
+fn destroy_overlay(state: State<'_, OverlayManager>, id: u64) -> Result<(), String> {
+    crash::breadcrumb("overlay", format!("destroy_overlay {}", id));
+    destroy_overlay_in(&state, id)
+}
+
+/// Open privacy overlays with their geometry, oldest first.
+#[tauri::command]
+fn list_overlays(state: State<'_, OverlayManager>) -> Vec<OverlayInfo> {
+    state.list()
+}
+
+// ==========================================
+// APP STATE
+// ==========================================
+
+#[derive(Serialize)]
+struct AppStateSnapshot {
+    overlay_count: usize,
+    subsystems: Vec<subsystems::SubsystemState>,
+    watchdog: Vec<watchdog::SubsystemHealth>,
+    /// Input resources held by running jobs.
+    #[cfg(feature = "input-automation")]
+    locks: Vec<locks::HeldLock>,
+}
+
+fn app_state_in(manager: &OverlayManager) -> AppStateSnapshot {
+    AppStateSnapshot {
+        overlay_count: manager.overlays.lock().unwrap().len(),
+        subsystems: subsystems::states(),
+        watchdog: watchdog::get_watchdog_status(),
+        #[cfg(feature = "input-automation")]
+        locks: locks::held(),
+    }
+}
+
+#[tauri::command]
+fn get_app_state(state: State<'_, OverlayManager>) -> AppStateSnapshot {
+    app_state_in(&state)
+}
+
+#[cfg(feature = "webdriver")]
+fn webdriver_addr() -> String {
+    std::env::var("APP_WEBDRIVER_ADDR").unwrap_or_else(|_| webdriver::DEFAULT_ADDR.to_string())
+}
+
+// ==========================================
+// HEADLESS ENTRY
+// ==========================================
+
+/// Entry point for the daemon build: native subsystems and the control server, no webview.
+#[cfg(feature = "headless")]
+pub fn run_headless(args: Vec<String>) -> i32 {
+    let addr = std::env::var("APP_CONTROL_ADDR").unwrap_or_else(|_| control::DEFAULT_ADDR.to_string());
+    let dir = match data_dir() {
+        Ok(dir) => dir,
+        Err(e) => return exit_code(Err(e)),
+    };
+
+    match args.first().map(String::as_str) {
+        None | Some("serve") => {
+            if let Err(e) = init_storage(&dir) {
+                return exit_code(Err(e));
+            }
+            // Only on request: it accepts unauthenticated input from any local process.
+            #[cfg(feature = "webdriver")]
+            if args.iter().any(|arg| arg == "--webdriver") {
+                if let Err(e) = webdriver::start(&webdriver_addr()) {
+                    log::warn!("{}", e);
+                }
+            }
+            exit_code(control::bind(&addr).and_then(|listener| {
+                let token = control::load_token(&dir.join(control::TOKEN_FILE))?;
+                serve(listener, control::token_auth(token))
+            }))
+        }
+        #[cfg(feature = "service")]
+        Some("companion") => {
+            if let Err(e) = init_storage(&dir) {
+                return exit_code(Err(e));
+            }
+            // The user's token, which the service takes from them as a caller.
+            let companion = || {
+                control::load_token(&dir.join(control::TOKEN_FILE))?;
+                let listener = control::bind("127.0.0.1:0")?;
+                let token = service::register_companion(&dir, &listener)?;
+                serve(listener, control::token_auth(token))
+            };
+            exit_code(companion())
+        }
+        #[cfg(feature = "service")]
+        Some("run-service") => exit_code(service::run()),
+        #[cfg(feature = "service")]
+        Some("install-service") => exit_code(service::install()),
+        #[cfg(feature = "service")]
+        Some("uninstall-service") => exit_code(service::uninstall()),
+        Some("commands") => {
+            print!("{}", commands::markdown());
+            0
+        }
+        Some("call") if args.len() >= 2 => {
+            let params = match args.get(2) {
+                Some(raw) => match serde_json::from_str(raw) {
+                    Ok(params) => params,
+                    Err(e) => {
+                        eprintln!("Invalid params JSON: {}", e);
+                        return 2;
+                    }
+                },
+                None => serde_json::Value::Null,
+            };
+
+            let token = std::env::var_os("APP_CONTROL_TOKEN_FILE")
+                .map_or_else(|| dir.join(control::TOKEN_FILE), std::path::PathBuf::from);
+            let result = control::read_private(&token)
+                .and_then(|token| control::call(&addr, &token, &args[1], params));
+            match result {
+                Ok(result) => {
+                    println!("{}", result);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+        _ => {
+            eprintln!("usage: app-daemon [serve [--webdriver]] | call <method> [params-json] | commands");
+            #[cfg(feature = "service")]
+            eprintln!("       app-daemon companion | run-service | install-service | uninstall-service");
+            2
+        }
+    }
+}
+
+/// The bundle identifier, which names the app's data directory.
+#[cfg(feature = "headless")]
+const APP_IDENTIFIER: &str = "com.example.screensharehost";
+
+/// The app's data directory, the one the windowed build gets from Tauri.
+#[cfg(feature = "headless")]
+fn data_dir() -> Result<std::path::PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| "No data directory for this user".to_string())
+}
+
+/// Points the stores at `dir` and starts what the daemon runs besides the control
+/// server.
+#[cfg(feature = "headless")]
+fn init_storage(dir: &std::path::Path) -> Result<(), String> {
+    bug_report::install_logger(Box::new(bug_report::StderrLog), log::LevelFilter::Info);
+    secrets::init(dir.join("encryption.json"));
+    crash::install(dir.join("crash-reports"));
+    bug_report::init(dir.join("bug-reports"));
+    visual::init(dir.join("visual-goldens"));
+    palette::init(dir.join("palette.json"));
+    audit_log::init(dir.join("audit-log.jsonl"));
+    retention::init(dir.join("retention.json"));
+    #[cfg(feature = "scripting")]
+    scripts::init(dir.join("scripts"));
+    #[cfg(feature = "input-automation")]
+    anchors::init(dir.join("anchors.json"));
+    dispatch::init(dir.join("exports"));
+    #[cfg(target_os = "macos")]
+    macos_overlay::init_headless();
+    #[cfg(target_os = "windows")]
+    windows_overlay::init_headless();
+
+    subsystems::register("retention-janitor", true, retention::start, retention::stop)
+}
+
+/// Runs the control server on `listener` until shutdown.
+#[cfg(feature = "headless")]
+fn serve(listener: std::net::TcpListener, authorize: control::Authorize) -> Result<(), String> {
+    control::run_daemon(listener, authorize, |client, method, params| {
+        control::dispatch(&OVERLAY_MANAGER, client, method, params)
+    })
+}
+
+#[cfg(feature = "headless")]
+fn exit_code(result: Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+// ==========================================
+// TAURI ENTRY
+// ==========================================
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .manage(OverlayManager::new())
+        .setup(|app| {
+            let stderr = tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stderr);
+            let (log_plugin, log_level, logger) = tauri_plugin_log::Builder::new()
+                .clear_targets()
+                .target(stderr)
+                .level(log::LevelFilter::Info)
+                .split(app.handle())?;
+            app.handle().plugin(log_plugin)?;
+            bug_report::install_logger(logger, log_level);
+            secrets::init(app.path().app_data_dir()?.join("encryption.json"));
+            crash::install(app.path().app_data_dir()?.join("crash-reports"));
+            bug_report::init(app.path().app_data_dir()?.join("bug-reports"));
+            visual::init(app.path().app_data_dir()?.join("visual-goldens"));
+            palette::init(app.path().app_data_dir()?.join("palette.json"));
+            audit_log::init(app.path().app_data_dir()?.join("audit-log.jsonl"));
+            retention::init(app.path().app_data_dir()?.join("retention.json"));
+            *OVERLAY_APP.lock().unwrap() = Some(app.handle().clone());
+            #[cfg(feature = "scripting")]
+            scripts::init(app.path().app_data_dir()?.join("scripts"));
+            #[cfg(feature = "input-automation")]
+            anchors::init(app.path().app_data_dir()?.join("anchors.json"));
+            #[cfg(feature = "scripting")]
+            queue::init(app.path().app_data_dir()?.join("job-queue.json"));
+            dispatch::init(app.path().app_data_dir()?.join("exports"));
+            #[cfg(feature = "self-update")]
+            updater::init(app.path().app_data_dir()?);
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "watchdog",
+                true,
+                move || watchdog::start(handle.clone()),
+                watchdog::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "session-watcher",
+                true,
+                move || remote_session::start(handle.clone()),
+                remote_session::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "cursor-sampler",
+                true,
+                move || cursor::start(handle.clone()),
+                cursor::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "hotkeys",
+                true,
+                move || hotkeys::start(handle.clone()),
+                hotkeys::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "share-guard",
+                true,
+                move || share_guard::start(handle.clone()),
+                share_guard::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "auto-cover",
+                true,
+                move || auto_cover::start(handle.clone()),
+                auto_cover::stop,
+            )?;
+
+            let handle = app.handle().clone();
+            subsystems::register(
+                "toast-interceptor",
+                true,
+                move || toasts::start(handle.clone()),
+                toasts::stop,
+            )?;
+
+            subsystems::register("retention-janitor", true, retention::start, retention::stop)?;
+
+            // Off by default: while it runs, right clicks are held back until release.
+            let handle = app.handle().clone();
+            subsystems::register(
+                "gestures",
+                false,
+                move || gestures::start(handle.clone()),
+                gestures::stop,
+            )?;
+
+            // Off by default: it hooks every mouse click.
+            let handle = app.handle().clone();
+            subsystems::register(
+                "click-effects",
+                false,
+                move || clicks::start(handle.clone()),
+                clicks::stop,
+            )?;
+
+            #[cfg(feature = "scripting")]
+            {
+                // On by default, so jobs queued before a restart resume.
+                let handle = app.handle().clone();
+                subsystems::register(
+                    "job-queue",
+                    true,
+                    move || queue::start(handle.clone()),
+                    queue::stop,
+                )?;
+            }
+
+            #[cfg(feature = "voice-commands")]
+            {
+                // Off by default: the microphone stays closed until the user opts in.
+                let handle = app.handle().clone();
+                subsystems::register(
+                    "voice-commands",
+                    false,
+                    move || voice::start(handle.clone()),
+                    voice::stop,
+                )?;
+            }
+
+            // Off by default: it accepts unauthenticated input from any local process.
+            #[cfg(feature = "webdriver")]
+            subsystems::register(
+                "webdriver",
+                false,
+                || webdriver::start(&webdriver_addr()),
+                webdriver::stop,
+            )?;
+
+            // After the subsystems are registered, so flags can switch them on.
+            #[cfg(feature = "self-update")]
+            updater::start(app.handle().clone());
+            Ok(())
+        })
+        // A reloaded or closed webview cannot end its sessions itself.
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Started {
+                sessions::end_owned_by(&webview.state::<OverlayManager>(), webview.label());
+            }
+        })
+        .on_window_event(|window, event| {
+            if let WindowEvent::Destroyed = event {
+                sessions::end_owned_by(&window.state::<OverlayManager>(), window.label());
+            }
+        })
+        .invoke_handler(middleware::wrap(commands::handler()))
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use overlay_backend::MockBackend;
+
+    const RECT: coords::Rect = coords::Rect {
+        x: 0,
+        y: 0,
+        width: 800,
+        height: 600,
+    };
+
+    fn open(manager: &OverlayManager, text: Option<&str>) -> u64 {
+        open_overlay_with::<MockBackend>(manager, RECT, Some(0), text).unwrap()
+    }
+
+    fn handle(manager: &OverlayManager, id: u64) -> usize {
+        manager.handle(id).unwrap() as usize
+    }
+
+    #[test]
+    fn create_records_the_overlay() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let id = open(&manager, Some("Paused"));
+
+        let overlays = manager.list();
+        assert_eq!(overlays.len(), 1);
+        assert_eq!(overlays[0].id, id);
+        assert_eq!(overlays[0].rect, RECT);
+        assert_eq!(overlays[0].monitor, Some(0));
+        assert_eq!(overlays[0].text.as_deref(), Some("Paused"));
+        assert_eq!(
+            MockBackend::take_calls(),
+            [
+                format!("create 1 {:?}", RECT),
+                "set_text 1 Some(\"Paused\")".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_without_text_draws_none() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        open(&manager, Some(""));
+
+        assert_eq!(manager.list()[0].text, None);
+        assert_eq!(MockBackend::take_calls(), [format!("create 1 {:?}", RECT)]);
+    }
+
+    #[test]
+    fn failed_create_records_nothing() {
+        MockBackend::reset();
+        MockBackend::fail("create");
+        let manager = OverlayManager::new();
+
+        assert!(open_overlay_with::<MockBackend>(&manager, RECT, None, None).is_err());
+        assert!(manager.list().is_empty());
+    }
+
+    #[test]
+    fn move_updates_the_rect() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let id = open(&manager, None);
+        MockBackend::take_calls();
+
+        let moved = coords::Rect {
+            x: 10,
+            y: 20,
+            width: 300,
+            height: 200,
+        };
+        move_overlay_with::<MockBackend>(&manager, id, moved).unwrap();
+        let overlay = &manager.list()[0];
+        assert_eq!(overlay.rect, moved);
+        // A moved overlay no longer covers a whole monitor.
+        assert_eq!(overlay.monitor, None);
+        assert_eq!(
+            MockBackend::take_calls(),
+            [format!("move_to 1 {:?}", moved)]
+        );
+    }
+
+    #[test]
+    fn move_of_unknown_overlay_fails() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+
+        assert_eq!(
+            move_overlay_with::<MockBackend>(&manager, u64::MAX, RECT),
+            Err(format!("Overlay {} not found", u64::MAX))
+        );
+        assert!(MockBackend::take_calls().is_empty());
+    }
+
+    #[test]
+    fn destroy_forgets_only_that_overlay() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let first = open(&manager, None);
+        let second = open(&manager, None);
+        let first_handle = handle(&manager, first);
+        MockBackend::take_calls();
+
+        destroy_overlay_with::<MockBackend>(&manager, first).unwrap();
+        let ids: Vec<u64> = manager.list().iter().map(|o| o.id).collect();
+        assert_eq!(ids, [second]);
+        assert_eq!(
+            MockBackend::take_calls(),
+            [format!("destroy {}", first_handle)]
+        );
+        assert!(destroy_overlay_with::<MockBackend>(&manager, first).is_err());
+    }
+
+    #[test]
+    fn reopen_keeps_ids_text_and_interactivity() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let id = open(&manager, Some("Paused"));
+        set_overlay_interactive_with::<MockBackend>(&manager, id, false).unwrap();
+        MockBackend::take_calls();
+
+        reopen_overlays_with::<MockBackend>(&manager).unwrap();
+        let overlay = &manager.list()[0];
+        assert_eq!(overlay.id, id);
+        assert_eq!(overlay.text.as_deref(), Some("Paused"));
+        assert!(!overlay.interactive);
+        assert_eq!(handle(&manager, id), 2);
+        assert_eq!(
+            MockBackend::take_calls(),
+            [
+                format!("create 2 {:?}", RECT),
+                "set_text 2 Some(\"Paused\")".to_string(),
+                "click_through 2 true".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn destroy_all_empties_the_manager() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        open(&manager, None);
+        open(&manager, None);
+        MockBackend::take_calls();
+
+        destroy_overlays_with::<MockBackend>(&manager).unwrap();
+        assert!(manager.list().is_empty());
+        assert_eq!(MockBackend::take_calls(), ["destroy_all 2"]);
+    }
+
+    #[test]
+    fn set_timeout_passes_the_overlay_id() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let id = open(&manager, None);
+        MockBackend::take_calls();
+
+        style_overlays_with::<MockBackend>(&manager, &[id], None, None, None, None, Some(500))
+            .unwrap();
+        assert_eq!(manager.list().len(), 1);
+        assert_eq!(
+            MockBackend::take_calls(),
+            [format!("set_timeout 1 {} 500", id)]
+        );
+    }
+
+    #[test]
+    fn failed_styling_closes_every_overlay() {
+        MockBackend::reset();
+        let manager = OverlayManager::new();
+        let ids = [open(&manager, None), open(&manager, None)];
+        MockBackend::fail("set_timeout");
+
+        let styled = style_overlays_with::<MockBackend>(
+            &manager,
+            &ids,
+            Some(0.5),
+            None,
+            None,
+            None,
+            Some(500),
+        );
+        assert!(styled.is_err());
+        assert!(manager.list().is_empty());
+    }
 }
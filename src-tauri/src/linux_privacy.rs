@@ -0,0 +1,619 @@
+use std::{collections::HashMap, ffi::c_void, sync::Mutex, thread, time::Duration};
+
+use once_cell::sync::Lazy;
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::shape::{ConnectionExt as _, SK, SO};
+use x11rb::protocol::xproto::{
+    AtomEnum, ChangeGCAux, Char2b, ClipOrdering, ConfigureWindowAux, ConnectionExt as _,
+    CreateGCAux, CreateWindowAux, EventMask, Font, Gcontext, ImageFormat, MapState, PropMode,
+    Rectangle, StackMode, Visibility, Window, WindowClass,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+use crate::coords::Rect;
+use crate::palette::{self, ColorRole};
+use crate::shape::Shape;
+
+// ==========================================
+// PRIVACY OVERLAY (Linux/X11)
+// ==========================================
+
+// Overlays are override-redirect windows, which the window manager leaves alone: no
+// frame, no focus, no taskbar entry. Nothing keeps such a window above the others,
+// so each overlay watches its visibility and raises itself when some other
+// application's window comes over it; overlays never fight each other that way.
+// Click-through empties the window's input region with the SHAPE extension.
+// Opacity is a hint to the compositor; without one overlays are opaque, which
+// still hides what they cover. Text uses the server's core fonts.
+//
+// One connection serves every overlay. A thread reads its events, redrawing on
+// Expose and reporting cancel-button clicks; drawing happens on whichever thread
+// changed the overlay, since the connection is shared safely between threads.
+
+/// Default opacity, as on macOS and Windows.
+const OPACITY: f64 = 0.6;
+/// Core font patterns, tried in order; "fixed" exists on every server.
+const FONTS: &[&str] = &[
+    "-*-dejavu sans-bold-r-normal--28-*-*-*-p-*-iso10646-1",
+    "-*-*-bold-r-normal--28-*-*-*-p-*-iso10646-1",
+    "fixed",
+];
+const TEXT_SIZE: i32 = 28;
+/// ImageText16 draws at most this many characters.
+const MAX_CHARS: usize = 255;
+
+struct X11 {
+    conn: RustConnection,
+    root: Window,
+    depth: u8,
+    black: u32,
+    font: Font,
+    opacity_atom: u32,
+}
+
+/// What an overlay window draws, read on every redraw.
+struct Look {
+    gc: Gcontext,
+    width: u16,
+    height: u16,
+    text: Option<String>,
+    progress: Option<f64>,
+    image: Option<image::RgbaImage>,
+    /// Label and overlay id of the cancel button, for overlays with one.
+    button: Option<(String, u64)>,
+}
+
+static X11: Lazy<Result<X11, String>> = Lazy::new(connect);
+static LOOKS: Lazy<Mutex<HashMap<Window, Look>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn connect() -> Result<X11, String> {
+    let (conn, screen_num) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+    let screen = &conn.setup().roots[screen_num];
+    let (root, depth, black) = (screen.root, screen.root_depth, screen.black_pixel);
+
+    let font = conn.generate_id().map_err(|e| e.to_string())?;
+    let opened = FONTS.iter().any(|name| {
+        conn.open_font(font, name.as_bytes())
+            .is_ok_and(|cookie| cookie.check().is_ok())
+    });
+    if !opened {
+        return Err("No usable X font found".to_string());
+    }
+    let opacity_atom = conn
+        .intern_atom(false, b"_NET_WM_WINDOW_OPACITY")
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom;
+
+    thread::Builder::new()
+        .name("x11-overlays".to_string())
+        .spawn(events)
+        .map_err(|e| format!("Failed to spawn X11 event thread: {}", e))?;
+
+    Ok(X11 {
+        conn,
+        root,
+        depth,
+        black,
+        font,
+        opacity_atom,
+    })
+}
+
+fn x11() -> Result<&'static X11, String> {
+    X11.as_ref().map_err(Clone::clone)
+}
+
+fn window(overlay: *mut c_void) -> Window {
+    overlay as usize as Window
+}
+
+/// Assumes a 24-bit TrueColor visual, the default of every current X server.
+fn pixel((r, g, b): palette::Rgb) -> u32 {
+    (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+/// Reads overlay events until the connection closes.
+fn events() {
+    let Ok(x) = x11() else {
+        return;
+    };
+    while let Ok(event) = x.conn.wait_for_event() {
+        match event {
+            Event::Expose(e) if e.count == 0 => redraw(x, e.window),
+            Event::VisibilityNotify(e)
+                if e.state != Visibility::UNOBSCURED && covered_by_others(x, e.window) =>
+            {
+                let _ = x.conn.configure_window(
+                    e.window,
+                    &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+                );
+                let _ = x.conn.flush();
+            }
+            Event::ButtonRelease(e) => {
+                let looks = LOOKS.lock().unwrap();
+                let clicked = looks.get(&e.event).and_then(|look| {
+                    let (_, id) = look.button.as_ref()?;
+                    let b = button_rect(look);
+                    let inside = (b.x..b.x + b.width as i16).contains(&e.event_x)
+                        && (b.y..b.y + b.height as i16).contains(&e.event_y);
+                    inside.then_some(*id)
+                });
+                drop(looks);
+                if let Some(id) = clicked {
+                    crate::overlay_cancelled(id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether a mapped window of another application is stacked above `window`.
+fn covered_by_others(x: &X11, window: Window) -> bool {
+    let Some(tree) = x
+        .conn
+        .query_tree(x.root)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+    else {
+        return false;
+    };
+    // Children come bottom to top.
+    let Some(position) = tree.children.iter().position(|w| *w == window) else {
+        return false;
+    };
+    let ours = LOOKS.lock().unwrap().keys().copied().collect::<Vec<_>>();
+    tree.children[position + 1..]
+        .iter()
+        .filter(|w| !ours.contains(w))
+        .any(|w| {
+            x.conn
+                .get_window_attributes(*w)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .is_some_and(|attributes| attributes.map_state == MapState::VIEWABLE)
+        })
+}
+
+fn button_rect(look: &Look) -> Rectangle {
+    let width = crate::BUTTON_WIDTH.round() as u16;
+    let height = crate::BUTTON_HEIGHT.round() as u16;
+    let bottom = look.height as i32 - crate::BUTTON_MARGIN.round() as i32;
+    Rectangle {
+        x: ((look.width as i32 - width as i32) / 2) as i16,
+        y: (bottom - height as i32) as i16,
+        width,
+        height,
+    }
+}
+
+fn chars(text: &str) -> Vec<Char2b> {
+    text.encode_utf16()
+        .take(MAX_CHARS)
+        .map(|unit| Char2b {
+            byte1: (unit >> 8) as u8,
+            byte2: unit as u8,
+        })
+        .collect()
+}
+
+/// Width, ascent and descent of `text` in the overlay font.
+fn measure(x: &X11, text: &[Char2b]) -> (i32, i32, i32) {
+    x.conn
+        .query_text_extents(x.font, text)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .map_or((0, TEXT_SIZE, 0), |extents| {
+            (
+                extents.overall_width,
+                extents.font_ascent as i32,
+                extents.font_descent as i32,
+            )
+        })
+}
+
+/// Draws `text` centered horizontally on `center_x`, with its top at `top`.
+fn draw_text(x: &X11, window: Window, gc: Gcontext, text: &[Char2b], center_x: i32, top: i32) {
+    let (width, ascent, _) = measure(x, text);
+    let _ = x.conn.image_text16(
+        window,
+        gc,
+        (center_x - width / 2) as i16,
+        (top + ascent) as i16,
+        text,
+    );
+}
+
+/// Copies `picture` (premultiplied RGBA) to `window` at `(left, top)`, in bands
+/// that fit the server's request size.
+fn draw_picture(
+    x: &X11,
+    window: Window,
+    gc: Gcontext,
+    picture: &image::RgbaImage,
+    left: i32,
+    top: i32,
+) {
+    // Premultiplied over the black background, the color channels are the result.
+    let data: Vec<u8> = picture
+        .pixels()
+        .flat_map(|p| [p[2], p[1], p[0], 0])
+        .collect();
+    let row = picture.width() as usize * 4;
+    let rows_per_band = ((x.conn.maximum_request_bytes() - 64) / row).max(1);
+    for (band, chunk) in data.chunks(row * rows_per_band).enumerate() {
+        let _ = x.conn.put_image(
+            ImageFormat::Z_PIXMAP,
+            window,
+            gc,
+            picture.width() as u16,
+            (chunk.len() / row) as u16,
+            left as i16,
+            (top + (band * rows_per_band) as i32) as i16,
+            0,
+            x.depth,
+            chunk,
+        );
+    }
+}
+
+/// Draws the overlay's image, text lines and progress bar, whichever it has,
+/// stacked top to bottom and centered on both axes, and its cancel button.
+fn redraw(x: &X11, window: Window) {
+    let looks = LOOKS.lock().unwrap();
+    let Some(look) = looks.get(&window) else {
+        return;
+    };
+    let (width, height) = (look.width as i32, look.height as i32);
+    let padding = TEXT_SIZE / 2;
+    let text = pixel(palette::color(ColorRole::Text));
+    let accent = pixel(palette::color(ColorRole::Accent));
+    let _ = x.conn.clear_area(false, window, 0, 0, 0, 0);
+
+    let picture = look.image.as_ref().map(|image| {
+        let (w, h) = crate::fit(
+            (image.width() as f64, image.height() as f64),
+            ((width - 2 * padding) as f64, height as f64 / 2.0),
+        );
+        let (w, h) = ((w.round() as u32).max(1), (h.round() as u32).max(1));
+        if (w, h) == image.dimensions() {
+            image.clone()
+        } else {
+            image::imageops::resize(image, w, h, image::imageops::FilterType::Triangle)
+        }
+    });
+    let lines: Vec<Vec<Char2b>> = look
+        .text
+        .as_deref()
+        .map_or_else(Vec::new, |text| text.lines().map(chars).collect());
+    let line_height = lines.first().map_or(0, |line| {
+        let (_, ascent, descent) = measure(x, line);
+        ascent + descent
+    });
+    let text_height = line_height * lines.len() as i32;
+    let bar_height = look
+        .progress
+        .map_or(0, |_| crate::PROGRESS_HEIGHT.round() as i32);
+
+    let parts = [
+        picture.is_some(),
+        !lines.is_empty(),
+        look.progress.is_some(),
+    ]
+    .iter()
+    .filter(|shown| **shown)
+    .count() as i32;
+    let gaps = padding * (parts - 1).max(0);
+    let total = picture.as_ref().map_or(0, |p| p.height() as i32) + text_height + bar_height + gaps;
+    let mut top = (height - total).max(0) / 2;
+
+    if let Some(picture) = &picture {
+        draw_picture(
+            x,
+            window,
+            look.gc,
+            picture,
+            (width - picture.width() as i32) / 2,
+            top,
+        );
+        top += picture.height() as i32 + padding;
+    }
+    if !lines.is_empty() {
+        let _ = x.conn.change_gc(
+            look.gc,
+            &ChangeGCAux::new().foreground(text).background(x.black),
+        );
+        for line in &lines {
+            draw_text(x, window, look.gc, line, width / 2, top);
+            top += line_height;
+        }
+        top += padding;
+    }
+    if let Some(percent) = look.progress {
+        let bar_width = (crate::PROGRESS_WIDTH.round() as i32)
+            .min(width - 2 * padding)
+            .max(0);
+        let left = (width - bar_width) / 2;
+        let filled = (bar_width as f64 * percent / 100.0).round() as i32;
+        let bar = |w: i32| Rectangle {
+            x: left as i16,
+            y: top as i16,
+            width: w as u16,
+            height: bar_height as u16,
+        };
+        let _ = x.conn.change_gc(
+            look.gc,
+            &ChangeGCAux::new().foreground(pixel(crate::PROGRESS_TRACK)),
+        );
+        let _ = x
+            .conn
+            .poly_fill_rectangle(window, look.gc, &[bar(bar_width)]);
+        let _ = x
+            .conn
+            .change_gc(look.gc, &ChangeGCAux::new().foreground(accent));
+        let _ = x.conn.poly_fill_rectangle(window, look.gc, &[bar(filled)]);
+    }
+    if let Some((label, _)) = &look.button {
+        let rect = button_rect(look);
+        let label = chars(label);
+        let (_, ascent, descent) = measure(x, &label);
+        let _ = x
+            .conn
+            .change_gc(look.gc, &ChangeGCAux::new().foreground(accent));
+        let _ = x.conn.poly_fill_rectangle(window, look.gc, &[rect]);
+        let _ = x.conn.change_gc(
+            look.gc,
+            &ChangeGCAux::new().foreground(text).background(accent),
+        );
+        let label_top = rect.y as i32 + (rect.height as i32 - ascent - descent) / 2;
+        draw_text(x, window, look.gc, &label, width / 2, label_top);
+    }
+    let _ = x.conn.flush();
+}
+
+/// Changes what overlay `overlay` draws and redraws it.
+fn update(overlay: *mut c_void, change: impl FnOnce(&mut Look)) {
+    let Ok(x) = x11() else {
+        return;
+    };
+    if let Some(look) = LOOKS.lock().unwrap().get_mut(&window(overlay)) {
+        change(look);
+    }
+    redraw(x, window(overlay));
+}
+
+fn open(x: &X11, rect: Rect) -> Result<Window, String> {
+    let window = x.conn.generate_id().map_err(|e| e.to_string())?;
+    let gc = x.conn.generate_id().map_err(|e| e.to_string())?;
+    let (width, height) = (rect.width.max(1) as u16, rect.height.max(1) as u16);
+    x.conn
+        .create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            x.root,
+            rect.x as i16,
+            rect.y as i16,
+            width,
+            height,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new()
+                .override_redirect(1)
+                .background_pixel(x.black)
+                .event_mask(
+                    EventMask::EXPOSURE | EventMask::VISIBILITY_CHANGE | EventMask::BUTTON_RELEASE,
+                ),
+        )
+        .map_err(|e| e.to_string())
+        .and_then(|cookie| cookie.check().map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to create overlay window: {}", e))?;
+    let _ = x
+        .conn
+        .create_gc(gc, window, &CreateGCAux::new().font(x.font));
+    LOOKS.lock().unwrap().insert(
+        window,
+        Look {
+            gc,
+            width,
+            height,
+            text: None,
+            progress: None,
+            image: None,
+            button: None,
+        },
+    );
+    set_opacity(window as usize as *mut c_void, OPACITY);
+    let _ = x.conn.map_window(window);
+    let _ = x.conn.configure_window(
+        window,
+        &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+    );
+    x.conn.flush().map_err(|e| e.to_string())?;
+    Ok(window)
+}
+
+fn close(overlay: *mut c_void) {
+    let Ok(x) = x11() else {
+        return;
+    };
+    if let Some(look) = LOOKS.lock().unwrap().remove(&window(overlay)) {
+        let _ = x.conn.free_gc(look.gc);
+    }
+    let _ = x.conn.destroy_window(window(overlay));
+    let _ = x.conn.flush();
+}
+
+/// `bounds` is in native pixels; `None` covers every monitor, one window each.
+pub fn create_privacy_overlay(bounds: Option<Rect>) -> Result<Vec<*mut c_void>, String> {
+    let x = x11()?;
+    let rects = match bounds {
+        Some(rect) => vec![rect],
+        None => crate::coords::monitors()
+            .iter()
+            .map(|m| Rect {
+                x: m.x,
+                y: m.y,
+                width: m.width,
+                height: m.height,
+            })
+            .collect(),
+    };
+    if rects.is_empty() {
+        return Err("No monitors found".to_string());
+    }
+
+    let mut overlays = Vec::new();
+    for rect in rects {
+        match open(x, rect) {
+            Ok(window) => overlays.push(window as usize as *mut c_void),
+            Err(e) => {
+                overlays.into_iter().for_each(close);
+                return Err(e);
+            }
+        }
+    }
+    Ok(overlays)
+}
+
+pub fn move_overlay(overlay: *mut c_void, rect: Rect) -> Result<(), String> {
+    let x = x11()?;
+    let (width, height) = (rect.width.max(1) as u16, rect.height.max(1) as u16);
+    x.conn
+        .configure_window(
+            window(overlay),
+            &ConfigureWindowAux::new()
+                .x(rect.x)
+                .y(rect.y)
+                .width(width as u32)
+                .height(height as u32),
+        )
+        .map_err(|e| format!("Failed to move overlay: {}", e))?;
+    // Shrinking exposes nothing, so the layout is redrawn here.
+    update(overlay, |look| {
+        look.width = width;
+        look.height = height;
+    });
+    Ok(())
+}
+
+pub fn set_text(overlay: *mut c_void, text: Option<&str>) {
+    update(overlay, |look| look.text = text.map(str::to_string));
+}
+
+pub fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+    update(overlay, |look| look.progress = percent);
+}
+
+pub fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+    x11()?;
+    update(overlay, |look| look.image = Some(image.clone()));
+    Ok(())
+}
+
+pub fn set_shape(_overlay: *mut c_void, _shape: &Shape) -> Result<(), String> {
+    Err("Overlay shapes aren't supported on X11".to_string())
+}
+
+/// Shows a cancel button labeled `label` that reports clicks as overlay `id`.
+pub fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+    update(overlay, |look| look.button = Some((label.to_string(), id)));
+}
+
+/// Closes overlay `id` after `ms`. A timer outliving its overlay finds nothing to
+/// close.
+pub fn set_timeout(id: u64, ms: u64) {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(ms));
+        crate::overlay_expired(id);
+    });
+}
+
+/// A compositor hint; see the module notes.
+pub fn set_opacity(overlay: *mut c_void, opacity: f64) {
+    let Ok(x) = x11() else {
+        return;
+    };
+    let value = (opacity.clamp(0.0, 1.0) * u32::MAX as f64).round() as u32;
+    let _ = x.conn.change_property32(
+        PropMode::REPLACE,
+        window(overlay),
+        x.opacity_atom,
+        AtomEnum::CARDINAL,
+        &[value],
+    );
+    let _ = x.conn.flush();
+}
+
+/// Interactive overlays take clicks; the others have an empty input region and pass
+/// them to what they cover.
+pub fn set_interactive(overlay: *mut c_void, interactive: bool) -> Result<(), String> {
+    let x = x11()?;
+    if x.conn
+        .extension_information(x11rb::protocol::shape::X11_EXTENSION_NAME)
+        .ok()
+        .flatten()
+        .is_none()
+    {
+        return Err("Click-through needs the X SHAPE extension".to_string());
+    }
+    let changed = if interactive {
+        // No mask restores the default input region, the whole window.
+        x.conn
+            .shape_mask(SO::SET, SK::INPUT, window(overlay), 0, 0, x11rb::NONE)
+    } else {
+        x.conn.shape_rectangles(
+            SO::SET,
+            SK::INPUT,
+            ClipOrdering::UNSORTED,
+            window(overlay),
+            0,
+            0,
+            &[],
+        )
+    };
+    changed
+        .and_then(|_| x.conn.flush())
+        .map_err(|e| format!("Failed to change overlay click-through: {}", e))
+}
+
+pub fn set_visible(overlay: *mut c_void, visible: bool) {
+    let Ok(x) = x11() else {
+        return;
+    };
+    if visible {
+        let _ = x.conn.map_window(window(overlay));
+        let _ = x.conn.configure_window(
+            window(overlay),
+            &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+        );
+    } else {
+        let _ = x.conn.unmap_window(window(overlay));
+    }
+    let _ = x.conn.flush();
+}
+
+pub fn destroy_overlay(manager: &crate::OverlayManager, overlay: *mut c_void) {
+    let removed = {
+        let mut overlays = manager.overlays.lock().unwrap();
+        overlays
+            .iter()
+            .position(|o| o.handle == overlay)
+            .map(|index| overlays.remove(index))
+    };
+    if removed.is_some() {
+        close(overlay);
+    }
+}
+
+pub fn destroy_privacy_overlay(manager: &crate::OverlayManager) {
+    let overlays: Vec<_> = manager.overlays.lock().unwrap().drain(..).collect();
+    for overlay in overlays {
+        close(overlay.handle);
+    }
+}
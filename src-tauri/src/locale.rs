@@ -0,0 +1,356 @@
+use std::{sync::Mutex, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Locale Formatting
+// ==========================================
+
+// Numbers, durations and keyboard shortcuts drawn by the native renderers (HUDs,
+// overlay text, the presenter clock) are formatted here, so they follow the user's
+// locale and platform: "1.234,5" in German, "⌘⇧P" on macOS but "Ctrl+Shift+P" on
+// Windows and "Strg+Umschalt+P" on a German Windows. The locale is read from the OS
+// unless one is set explicitly; only separators and key names are localized.
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FormatLocale {
+    /// BCP 47 tag, e.g. "de-DE".
+    pub tag: String,
+    pub decimal: char,
+    pub group: char,
+    /// Set with `set_format_locale` rather than read from the OS.
+    pub overridden: bool,
+}
+
+/// A value to format the way native-rendered text shows it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum FormatRequest {
+    Number {
+        value: f64,
+        #[serde(default)]
+        decimals: usize,
+    },
+    Duration {
+        ms: u64,
+    },
+    /// e.g. "CmdOrCtrl+Shift+P".
+    Shortcut {
+        keys: String,
+    },
+}
+
+static OVERRIDE: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+static SYSTEM: Lazy<String> = Lazy::new(|| {
+    platform::system_tag()
+        .map(|tag| normalize(&tag))
+        .filter(|tag| !tag.is_empty())
+        .unwrap_or_else(|| "en-US".to_string())
+});
+
+/// "de_DE.UTF-8" or "de_DE@euro" → "de-DE"; the C locale counts as US English.
+fn normalize(tag: &str) -> String {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default().trim();
+    match tag {
+        "C" | "POSIX" => "en-US".to_string(),
+        _ => tag.replace('_', "-"),
+    }
+}
+
+fn tag() -> String {
+    OVERRIDE
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| SYSTEM.clone())
+}
+
+fn language(tag: &str) -> String {
+    tag.split('-')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Decimal and group separators.
+fn separators(tag: &str) -> (char, char) {
+    let language = language(tag);
+    if tag.eq_ignore_ascii_case("de-CH") || tag.eq_ignore_ascii_case("it-CH") {
+        return ('.', '’');
+    }
+    match language.as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+        | "sr" => (',', '.'),
+        "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg"
+        | "lt" | "lv" | "et" => (',', '\u{202F}'),
+        _ => ('.', ','),
+    }
+}
+
+pub fn current() -> FormatLocale {
+    let tag = tag();
+    let (decimal, group) = separators(&tag);
+    FormatLocale {
+        overridden: OVERRIDE.lock().unwrap().is_some(),
+        tag,
+        decimal,
+        group,
+    }
+}
+
+/// `value` rounded to `decimals` places, with the locale's separators.
+pub fn number(value: f64, decimals: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let (decimal, group) = separators(&tag());
+    let fixed = format!("{:.*}", decimals, value.abs());
+    let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+
+    let mut out = String::new();
+    if value < 0.0 && fixed.chars().any(|c| c != '0' && c != '.') {
+        out.push('-');
+    }
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            out.push(group);
+        }
+        out.push(digit);
+    }
+    if !fraction.is_empty() {
+        out.push(decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// Clock-style elapsed time: "04:05", or "1:04:05" from an hour on.
+pub fn duration(elapsed: Duration) -> String {
+    let separator = if language(&tag()) == "fi" { '.' } else { ':' };
+    let seconds = elapsed.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}{s}{:02}{s}{:02}", hours, minutes, seconds, s = separator)
+    } else {
+        format!("{:02}{s}{:02}", minutes, seconds, s = separator)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    /// Command on macOS, the Windows key elsewhere.
+    Super,
+}
+
+fn modifier(name: &str) -> Option<Modifier> {
+    match name.to_ascii_lowercase().as_str() {
+        "cmdorctrl" | "commandorcontrol" | "cmdorcontrol" | "commandorctrl" => {
+            Some(if cfg!(target_os = "macos") {
+                Modifier::Super
+            } else {
+                Modifier::Ctrl
+            })
+        }
+        "ctrl" | "control" => Some(Modifier::Ctrl),
+        "alt" | "option" | "opt" => Some(Modifier::Alt),
+        "shift" => Some(Modifier::Shift),
+        "cmd" | "command" | "super" | "meta" | "win" => Some(Modifier::Super),
+        _ => None,
+    }
+}
+
+fn modifier_name(modifier: Modifier, language: &str) -> &'static str {
+    if cfg!(target_os = "macos") {
+        return match modifier {
+            Modifier::Ctrl => "⌃",
+            Modifier::Alt => "⌥",
+            Modifier::Shift => "⇧",
+            Modifier::Super => "⌘",
+        };
+    }
+    // The labels printed on the keys of local keyboards.
+    match (modifier, language) {
+        (Modifier::Ctrl, "de") => "Strg",
+        (Modifier::Ctrl, _) => "Ctrl",
+        (Modifier::Alt, _) => "Alt",
+        (Modifier::Shift, "de") => "Umschalt",
+        (Modifier::Shift, "fr") => "Maj",
+        (Modifier::Shift, "es") => "Mayús",
+        (Modifier::Shift, _) => "Shift",
+        (Modifier::Super, _) if cfg!(target_os = "windows") => "Win",
+        (Modifier::Super, _) => "Super",
+    }
+}
+
+fn key_name(key: &str) -> String {
+    let mac = cfg!(target_os = "macos");
+    let name = match key.to_ascii_lowercase().as_str() {
+        "enter" | "return" => {
+            if mac {
+                "↩"
+            } else {
+                "Enter"
+            }
+        }
+        "tab" => {
+            if mac {
+                "⇥"
+            } else {
+                "Tab"
+            }
+        }
+        "backspace" => {
+            if mac {
+                "⌫"
+            } else {
+                "Backspace"
+            }
+        }
+        "delete" | "del" => {
+            if mac {
+                "⌦"
+            } else {
+                "Del"
+            }
+        }
+        "escape" | "esc" => {
+            if mac {
+                "⎋"
+            } else {
+                "Esc"
+            }
+        }
+        "space" => "Space",
+        "up" | "arrowup" => "↑",
+        "down" | "arrowdown" => "↓",
+        "left" | "arrowleft" => "←",
+        "right" | "arrowright" => "→",
+        _ => return key.to_uppercase(),
+    };
+    name.to_string()
+}
+
+/// An accelerator like "CmdOrCtrl+Shift+P" as the platform shows it: modifier
+/// symbols in Apple's order on macOS, "+"-joined key labels elsewhere.
+pub fn shortcut(keys: &str) -> String {
+    // "Ctrl++" binds the plus key itself.
+    let (keys, plus) = match keys.strip_suffix("++") {
+        Some(rest) => (rest, true),
+        None => (keys, false),
+    };
+    let mut modifiers = Vec::new();
+    let mut rest = Vec::new();
+    for part in keys
+        .split('+')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        match modifier(part) {
+            Some(m) if !modifiers.contains(&m) => modifiers.push(m),
+            Some(_) => {}
+            None => rest.push(key_name(part)),
+        }
+    }
+    if plus {
+        rest.push("+".to_string());
+    }
+    modifiers.sort();
+
+    let language = language(&tag());
+    let names = modifiers
+        .into_iter()
+        .map(|m| modifier_name(m, &language).to_string())
+        .chain(rest);
+    if cfg!(target_os = "macos") {
+        names.collect()
+    } else {
+        names.collect::<Vec<_>>().join("+")
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+    use windows::Win32::System::SystemServices::LOCALE_NAME_MAX_LENGTH;
+
+    pub fn system_tag() -> Option<String> {
+        let mut name = [0u16; LOCALE_NAME_MAX_LENGTH as usize];
+        let len = unsafe { GetUserDefaultLocaleName(&mut name) };
+        // The length includes the terminating null.
+        (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    pub fn system_tag() -> Option<String> {
+        unsafe {
+            let locale: id = msg_send![class!(NSLocale), currentLocale];
+            let identifier: id = msg_send![locale, localeIdentifier];
+            if identifier == nil {
+                return None;
+            }
+            let utf8: *const c_char = msg_send![identifier, UTF8String];
+            (!utf8.is_null()).then(|| CStr::from_ptr(utf8).to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub fn system_tag() -> Option<String> {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+    }
+}
+
+// ==========================================
+// LOCALE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_format_locale() -> FormatLocale {
+    current()
+}
+
+/// Formats native-rendered text for `tag` (e.g. "fr-FR") instead of the OS locale;
+/// `None` goes back to the OS locale.
+#[tauri::command]
+pub fn set_format_locale(tag: Option<String>) -> Result<FormatLocale, String> {
+    if let Some(tag) = &tag {
+        let valid = !tag.is_empty()
+            && tag
+                .split(['-', '_'])
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()));
+        if !valid {
+            return Err(format!(
+                "Invalid locale '{}' (expected e.g. \"de-DE\")",
+                tag
+            ));
+        }
+    }
+    *OVERRIDE.lock().unwrap() = tag.map(|tag| normalize(&tag));
+    Ok(current())
+}
+
+/// Formats a number, duration or shortcut the way HUDs and overlays show it, so
+/// text passed to them matches natively formatted text.
+#[tauri::command]
+pub fn format_text(request: FormatRequest) -> String {
+    match request {
+        FormatRequest::Number { value, decimals } => number(value, decimals),
+        FormatRequest::Duration { ms } => duration(Duration::from_millis(ms)),
+        FormatRequest::Shortcut { keys } => shortcut(&keys),
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Condvar, Mutex},
+    thread::{self, ThreadId},
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::crash;
+
+// ==========================================
+// Resource Locks
+// ==========================================
+
+// Automation jobs run concurrently, so each one takes locks on the input resources
+// it drives (the pointer, the keyboard, the clipboard, particular windows) for as
+// long as it runs. A screenshot job takes none and runs alongside a typing job; two
+// jobs that both move the pointer take turns. Locks are all-or-nothing, so jobs
+// can't deadlock each other, and reentrant per thread, so a job's own steps can
+// call commands that lock the same resource.
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum LockResource {
+    Mouse,
+    Keyboard,
+    Clipboard,
+    /// A window, by its `list_app_windows` id.
+    Window {
+        id: u64,
+    },
+}
+
+impl LockResource {
+    fn describe(&self) -> String {
+        match self {
+            LockResource::Mouse => "mouse".to_string(),
+            LockResource::Keyboard => "keyboard".to_string(),
+            LockResource::Clipboard => "clipboard".to_string(),
+            LockResource::Window { id } => format!("window {}", id),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HeldLock {
+    pub resource: LockResource,
+    pub holder: String,
+    pub since_ms: u64,
+}
+
+struct Holder {
+    name: String,
+    thread: ThreadId,
+    depth: u32,
+    since_ms: u64,
+}
+
+/// How long a job waits for another job's locks before giving up.
+pub const JOB_WAIT: Duration = Duration::from_secs(30);
+
+static HELD: Lazy<Mutex<BTreeMap<LockResource, Holder>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+static RELEASED: Condvar = Condvar::new();
+
+/// Releases its locks when dropped.
+pub struct Locks(Vec<LockResource>);
+
+impl Drop for Locks {
+    fn drop(&mut self) {
+        let mut held = HELD.lock().unwrap();
+        for resource in &self.0 {
+            if let Some(holder) = held.get_mut(resource) {
+                holder.depth -= 1;
+                if holder.depth == 0 {
+                    held.remove(resource);
+                }
+            }
+        }
+        RELEASED.notify_all();
+    }
+}
+
+/// Takes every lock in `resources` for `holder`, waiting up to `timeout` for other
+/// holders to release them. With a zero timeout it fails at once if any is taken.
+pub fn acquire(
+    holder: &str,
+    resources: impl IntoIterator<Item = LockResource>,
+    timeout: Duration,
+) -> Result<Locks, String> {
+    let mut resources: Vec<LockResource> = resources.into_iter().collect();
+    resources.sort();
+    resources.dedup();
+
+    let me = thread::current().id();
+    let deadline = Instant::now() + timeout;
+    let mut held = HELD.lock().unwrap();
+    loop {
+        let busy = resources
+            .iter()
+            .find_map(|r| held.get(r).filter(|h| h.thread != me).map(|h| (r, h)));
+        let Some((resource, other)) = busy else {
+            break;
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(format!(
+                "The {} is in use by {}",
+                resource.describe(),
+                other.name
+            ));
+        }
+        held = RELEASED.wait_timeout(held, remaining).unwrap().0;
+    }
+
+    let since_ms = crash::now_ms() as u64;
+    for resource in &resources {
+        held.entry(resource.clone())
+            .or_insert_with(|| Holder {
+                name: holder.to_string(),
+                thread: me,
+                depth: 0,
+                since_ms,
+            })
+            .depth += 1;
+    }
+    Ok(Locks(resources))
+}
+
+pub fn held() -> Vec<HeldLock> {
+    HELD.lock()
+        .unwrap()
+        .iter()
+        .map(|(resource, holder)| HeldLock {
+            resource: resource.clone(),
+            holder: holder.name.clone(),
+            since_ms: holder.since_ms,
+        })
+        .collect()
+}
@@ -0,0 +1,524 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::ipc::{Invoke, InvokeBody};
+
+use crate::{
+    commands::{self, Category, Risk},
+    sessions,
+};
+
+// ==========================================
+// Command Middleware
+// ==========================================
+
+// Every webview command and control-server request runs through one pipeline of
+// middleware: metrics, audit logging, permission checks, rate limiting and dry-run
+// interception. Each middleware sees the call before dispatch (and may reject it or
+// answer in its place) and the outcome after. Async commands return as soon as they
+// are scheduled, so their timings only cover dispatch.
+
+/// Origin reported for requests arriving through the control server.
+pub const CONTROL_ORIGIN: &str = "control";
+/// Origin reported for commands run by hotkeys and other user triggers.
+pub const TRIGGER_ORIGIN: &str = "trigger";
+/// Origin reported for commands run from the command palette.
+pub const PALETTE_ORIGIN: &str = "palette";
+/// Origin reported for requests arriving through the WebDriver endpoint.
+#[cfg(feature = "webdriver")]
+pub const WEBDRIVER_ORIGIN: &str = "webdriver";
+/// Calls kept in the audit trail, which lives in memory only; older ones are dropped.
+pub const AUDIT_CAPACITY: usize = 500;
+/// Commands that configure the pipeline itself; never denied, limited or dry-run.
+const PIPELINE_COMMANDS: &[&str] = &[
+    "get_command_policy",
+    "set_command_policy",
+    "get_command_audit",
+    "get_command_metrics",
+];
+
+pub struct Call<'a> {
+    pub command: &'a str,
+    /// Webview label, `CONTROL_ORIGIN`, `TRIGGER_ORIGIN`, `PALETTE_ORIGIN` or
+    /// `WEBDRIVER_ORIGIN`.
+    pub origin: &'a str,
+    pub args: &'a Value,
+}
+
+pub enum Verdict {
+    Continue,
+    Reject(String),
+    Respond(Value),
+}
+
+pub enum Outcome<'a> {
+    /// Ran and succeeded.
+    Succeeded,
+    /// Handed to a webview command whose result only reaches the webview: async
+    /// commands and ones that take window state.
+    Dispatched,
+    /// Ran, and the command returned this error.
+    Failed(&'a str),
+    Rejected(&'a str),
+    Responded,
+    /// No handler knows the command.
+    Unknown,
+}
+
+pub trait Middleware: Send + Sync {
+    fn before(&self, _call: &Call) -> Verdict {
+        Verdict::Continue
+    }
+
+    fn after(&self, _call: &Call, _outcome: &Outcome, _elapsed: Duration) {}
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CommandPolicy {
+    /// Commands rejected from every origin.
+    #[serde(default)]
+    pub denied: BTreeSet<String>,
+    /// Categories of registered commands rejected from every origin.
+    #[serde(default)]
+    pub denied_categories: BTreeSet<Category>,
+    /// Command → origins allowed to call it; commands not listed are open to all.
+    #[serde(default)]
+    pub origins: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    pub rate_limits: BTreeMap<String, RateLimit>,
+    /// Answers commands that change state with a description instead of running them.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+    pub command: String,
+    pub origin: String,
+    pub outcome: String,
+}
+
+/// Calls the audit trail dropped to stay within `AUDIT_CAPACITY`.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct AuditOverflow {
+    pub dropped: u64,
+    /// When the newest dropped call was made; every call kept is from then on.
+    pub until_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct CommandMetrics {
+    pub calls: u64,
+    pub rejected: u64,
+    pub dry_run: u64,
+    pub failed: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+static POLICY: Lazy<Mutex<CommandPolicy>> = Lazy::new(|| Mutex::new(CommandPolicy::default()));
+static AUDIT: Lazy<Mutex<VecDeque<AuditEntry>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static AUDIT_OVERFLOW: Lazy<Mutex<Option<AuditOverflow>>> = Lazy::new(|| Mutex::new(None));
+static METRICS: Lazy<Mutex<BTreeMap<String, CommandMetrics>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Outermost first; `after` runs in reverse.
+static PIPELINE: Lazy<Vec<Box<dyn Middleware>>> = Lazy::new(|| {
+    vec![
+        Box::new(Metrics),
+        Box::new(Audit),
+        Box::new(Permissions),
+        Box::new(RateLimiter::default()),
+        Box::new(DryRun),
+    ]
+});
+
+/// Queries, which dry-run lets through and the audit log skips. Control-server
+/// methods missing from the registry fall back to their name.
+fn read_only(command: &str) -> bool {
+    match commands::lookup(command) {
+        Some(info) => info.risk == Risk::ReadOnly,
+        None => {
+            command == "ping"
+                || ["get_", "list_", "resolve_"]
+                    .iter()
+                    .any(|prefix| command.starts_with(prefix))
+        }
+    }
+}
+
+fn exempt(command: &str) -> bool {
+    PIPELINE_COMMANDS.contains(&command)
+}
+
+struct Metrics;
+
+impl Middleware for Metrics {
+    fn after(&self, call: &Call, outcome: &Outcome, elapsed: Duration) {
+        let mut metrics = METRICS.lock().unwrap();
+        let entry = metrics.entry(call.command.to_string()).or_default();
+        entry.calls += 1;
+        match outcome {
+            Outcome::Rejected(_) => entry.rejected += 1,
+            Outcome::Responded => entry.dry_run += 1,
+            Outcome::Failed(_) => entry.failed += 1,
+            Outcome::Succeeded | Outcome::Dispatched | Outcome::Unknown => {}
+        }
+        let micros = elapsed.as_micros() as u64;
+        entry.total_us += micros;
+        entry.max_us = entry.max_us.max(micros);
+    }
+}
+
+struct Audit;
+
+impl Middleware for Audit {
+    fn after(&self, call: &Call, outcome: &Outcome, _elapsed: Duration) {
+        if read_only(call.command) {
+            return;
+        }
+        let outcome = match outcome {
+            Outcome::Succeeded => "ok".to_string(),
+            Outcome::Dispatched => "dispatched".to_string(),
+            Outcome::Failed(error) => format!("failed: {}", error),
+            Outcome::Rejected(reason) => format!("rejected: {}", reason),
+            Outcome::Responded => "dry-run".to_string(),
+            Outcome::Unknown => "unknown command".to_string(),
+        };
+        log::info!("command {} from {}: {}", call.command, call.origin, outcome);
+
+        let mut audit = AUDIT.lock().unwrap();
+        if audit.len() == AUDIT_CAPACITY {
+            if let Some(oldest) = audit.pop_front() {
+                let mut overflow = AUDIT_OVERFLOW.lock().unwrap();
+                let dropped = overflow.map_or(0, |o| o.dropped) + 1;
+                *overflow = Some(AuditOverflow {
+                    dropped,
+                    until_ms: oldest.at_ms,
+                });
+            }
+        }
+        audit.push_back(AuditEntry {
+            at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            command: call.command.to_string(),
+            origin: call.origin.to_string(),
+            outcome,
+        });
+    }
+}
+
+struct Permissions;
+
+impl Middleware for Permissions {
+    fn before(&self, call: &Call) -> Verdict {
+        if exempt(call.command) {
+            return Verdict::Continue;
+        }
+        let policy = POLICY.lock().unwrap();
+        let category = commands::lookup(call.command).map(|info| info.category);
+        if policy.denied.contains(call.command)
+            || category.is_some_and(|c| policy.denied_categories.contains(&c))
+        {
+            return Verdict::Reject(format!("Command '{}' is disabled", call.command));
+        }
+        match policy.origins.get(call.command) {
+            Some(origins) if !origins.contains(call.origin) => Verdict::Reject(format!(
+                "Command '{}' is not allowed from '{}'",
+                call.command, call.origin
+            )),
+            _ => Verdict::Continue,
+        }
+    }
+}
+
+/// Token buckets per command: (tokens, last refill).
+#[derive(Default)]
+struct RateLimiter {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl Middleware for RateLimiter {
+    fn before(&self, call: &Call) -> Verdict {
+        if exempt(call.command) {
+            return Verdict::Continue;
+        }
+        let Some(limit) = POLICY
+            .lock()
+            .unwrap()
+            .rate_limits
+            .get(call.command)
+            .copied()
+        else {
+            return Verdict::Continue;
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, refilled) = buckets
+            .entry(call.command.to_string())
+            .or_insert((limit.burst as f64, now));
+        *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * limit.per_second)
+            .min(limit.burst as f64);
+        *refilled = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Verdict::Continue
+        } else {
+            Verdict::Reject(format!("Command '{}' is rate limited", call.command))
+        }
+    }
+}
+
+struct DryRun;
+
+impl Middleware for DryRun {
+    fn before(&self, call: &Call) -> Verdict {
+        if !POLICY.lock().unwrap().dry_run || read_only(call.command) || exempt(call.command) {
+            return Verdict::Continue;
+        }
+        Verdict::Respond(json!({
+            "dry_run": true,
+            "command": call.command,
+            "args": call.args,
+        }))
+    }
+}
+
+fn before(call: &Call) -> Verdict {
+    for middleware in PIPELINE.iter() {
+        match middleware.before(call) {
+            Verdict::Continue => {}
+            verdict => return verdict,
+        }
+    }
+    Verdict::Continue
+}
+
+fn after(call: &Call, outcome: &Outcome, elapsed: Duration) {
+    for middleware in PIPELINE.iter().rev() {
+        middleware.after(call, outcome, elapsed);
+    }
+}
+
+/// Webview argument keys are camelCase; registry calls read the snake_case parameter names.
+fn snake_case_keys(args: &Value) -> Value {
+    let Value::Object(map) = args else {
+        return args.clone();
+    };
+    let mut renamed = serde_json::Map::new();
+    for (key, value) in map {
+        let mut name = String::with_capacity(key.len() + 4);
+        for ch in key.chars() {
+            if ch.is_ascii_uppercase() {
+                name.push('_');
+                name.push(ch.to_ascii_lowercase());
+            } else {
+                name.push(ch);
+            }
+        }
+        renamed.insert(name, value.clone());
+    }
+    Value::Object(renamed)
+}
+
+/// Registry calls encode structured errors as JSON text; the webview gets them back as
+/// objects, as the command would have sent them.
+fn error_value(error: String) -> Value {
+    match serde_json::from_str(&error) {
+        Ok(Value::Object(object)) => Value::Object(object),
+        _ => Value::String(error),
+    }
+}
+
+/// Wraps a `generate_handler!` handler so every webview command runs through the pipeline.
+/// Commands the registry can call in-process run here, so their real outcome is recorded;
+/// the rest go to `handler`.
+pub fn wrap<F>(handler: F) -> impl Fn(Invoke) -> bool + Send + Sync + 'static
+where
+    F: Fn(Invoke) -> bool + Send + Sync + 'static,
+{
+    move |invoke: Invoke| {
+        let command = invoke.message.command().to_string();
+        let origin = invoke.message.webview_ref().label().to_string();
+        let args = match invoke.message.payload() {
+            InvokeBody::Json(args) => args.clone(),
+            InvokeBody::Raw(_) => Value::Null,
+        };
+        let call = Call {
+            command: &command,
+            origin: &origin,
+            args: &args,
+        };
+
+        let started = Instant::now();
+        let mut verdict = before(&call);
+        let mut _session = None;
+        if let Verdict::Continue = verdict {
+            match sessions::enter(&origin, &args) {
+                Ok(entered) => _session = Some(entered),
+                Err(reason) => verdict = Verdict::Reject(reason),
+            }
+        }
+
+        let registered = commands::lookup(&command).and_then(|info| info.call);
+        match (verdict, registered) {
+            (Verdict::Continue, Some(run)) => {
+                let result = run(&snake_case_keys(&args));
+                let outcome = match &result {
+                    Ok(_) => Outcome::Succeeded,
+                    Err(e) => Outcome::Failed(e),
+                };
+                after(&call, &outcome, started.elapsed());
+                match result {
+                    Ok(value) => invoke.resolver.resolve(value),
+                    Err(error) => invoke.resolver.reject(error_value(error)),
+                }
+                true
+            }
+            (Verdict::Continue, None) => {
+                let handled = handler(invoke);
+                let outcome = if handled {
+                    Outcome::Dispatched
+                } else {
+                    Outcome::Unknown
+                };
+                after(&call, &outcome, started.elapsed());
+                handled
+            }
+            (Verdict::Reject(reason), _) => {
+                after(&call, &Outcome::Rejected(&reason), started.elapsed());
+                invoke.resolver.reject(reason);
+                true
+            }
+            (Verdict::Respond(value), _) => {
+                after(&call, &Outcome::Responded, started.elapsed());
+                invoke.resolver.resolve(value);
+                true
+            }
+        }
+    }
+}
+
+/// Runs a control-server request through the pipeline.
+pub fn run(
+    method: &str,
+    params: &Value,
+    dispatch: impl FnOnce() -> Result<Value, String>,
+) -> Result<Value, String> {
+    run_as(CONTROL_ORIGIN, method, params, dispatch)
+}
+
+/// Runs a request from `origin` through the pipeline.
+pub fn run_as(
+    origin: &str,
+    method: &str,
+    params: &Value,
+    dispatch: impl FnOnce() -> Result<Value, String>,
+) -> Result<Value, String> {
+    let call = Call {
+        command: method,
+        origin,
+        args: params,
+    };
+
+    let started = Instant::now();
+    match before(&call) {
+        Verdict::Continue => {
+            let result = dispatch();
+            let outcome = match &result {
+                Ok(_) => Outcome::Succeeded,
+                Err(e) => Outcome::Failed(e),
+            };
+            after(&call, &outcome, started.elapsed());
+            result
+        }
+        Verdict::Reject(reason) => {
+            after(&call, &Outcome::Rejected(&reason), started.elapsed());
+            Err(reason)
+        }
+        Verdict::Respond(value) => {
+            after(&call, &Outcome::Responded, started.elapsed());
+            Ok(value)
+        }
+    }
+}
+
+// ==========================================
+// MIDDLEWARE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_command_policy() -> CommandPolicy {
+    POLICY.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_command_policy(policy: CommandPolicy) -> Result<(), String> {
+    if let Some((command, _)) = policy
+        .rate_limits
+        .iter()
+        .find(|(_, limit)| limit.burst == 0 || limit.per_second <= 0.0)
+    {
+        return Err(format!(
+            "Rate limit for '{}' needs a positive rate and burst",
+            command
+        ));
+    }
+    *POLICY.lock().unwrap() = policy;
+    Ok(())
+}
+
+/// Drops audited calls older than `cutoff_ms`; without one, clears the audit trail
+/// and the metrics. Returns how many calls were dropped.
+pub fn prune_usage(cutoff_ms: Option<u64>) -> u64 {
+    let mut audit = AUDIT.lock().unwrap();
+    let before = audit.len();
+    // Calls that were dropped anyway aren't missing from what's left.
+    let mut overflow = AUDIT_OVERFLOW.lock().unwrap();
+    if cutoff_ms.map_or(true, |cutoff| overflow.is_some_and(|o| o.until_ms < cutoff)) {
+        *overflow = None;
+    }
+    match cutoff_ms {
+        Some(cutoff) => audit.retain(|entry| entry.at_ms >= cutoff),
+        None => {
+            audit.clear();
+            METRICS.lock().unwrap().clear();
+        }
+    }
+    (before - audit.len()) as u64
+}
+
+/// What the audit trail dropped to stay within `AUDIT_CAPACITY`, if anything.
+pub fn audit_overflow() -> Option<AuditOverflow> {
+    *AUDIT_OVERFLOW.lock().unwrap()
+}
+
+/// Audited calls to state-changing commands, oldest first: the last
+/// `AUDIT_CAPACITY` (500) since the app started.
+#[tauri::command]
+pub fn get_command_audit() -> Vec<AuditEntry> {
+    AUDIT.lock().unwrap().iter().cloned().collect()
+}
+
+#[tauri::command]
+pub fn get_command_metrics() -> BTreeMap<String, CommandMetrics> {
+    METRICS.lock().unwrap().clone()
+}
@@ -0,0 +1,110 @@
+use std::{sync::Mutex, time::Duration};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// ==========================================
+// Reduced Motion
+// ==========================================
+
+// Follows the OS "reduce motion" accessibility setting (Windows' "Show animations",
+// macOS' "Reduce motion"), which the app can override either way. With motion
+// reduced, click effects show a still mark instead of spreading and fading, and
+// the keystroke HUD and expiring annotations disappear at once instead of fading.
+// Linux has no common setting, so there only the override applies.
+
+/// `None` follows the OS.
+static OVERRIDE: Lazy<Mutex<Option<bool>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MotionSettings {
+    /// What the OS asks for.
+    pub os_reduce_motion: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduce_motion_override: Option<bool>,
+    /// What the app does.
+    pub reduce_motion: bool,
+}
+
+/// Whether animations should be left out.
+pub fn reduced() -> bool {
+    OVERRIDE
+        .lock()
+        .unwrap()
+        .unwrap_or_else(platform::reduce_motion)
+}
+
+/// How long a fade of `full` should take: not at all with motion reduced.
+pub fn fade(full: Duration) -> Duration {
+    if reduced() {
+        Duration::ZERO
+    } else {
+        full
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    pub fn reduce_motion() -> bool {
+        let mut animate = BOOL(1);
+        unsafe {
+            SystemParametersInfoW(
+                SPI_GETCLIENTAREAANIMATION,
+                0,
+                Some(&mut animate as *mut BOOL as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )
+        }
+        .is_ok_and(|_| !animate.as_bool())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, BOOL, NO};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    pub fn reduce_motion() -> bool {
+        unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let reduce: BOOL = msg_send![workspace, accessibilityDisplayShouldReduceMotion];
+            reduce != NO
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub fn reduce_motion() -> bool {
+        false
+    }
+}
+
+// ==========================================
+// MOTION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_motion_settings() -> MotionSettings {
+    let reduce_motion_override = *OVERRIDE.lock().unwrap();
+    let os_reduce_motion = platform::reduce_motion();
+    MotionSettings {
+        os_reduce_motion,
+        reduce_motion_override,
+        reduce_motion: reduce_motion_override.unwrap_or(os_reduce_motion),
+    }
+}
+
+/// Reduces motion (`true`) or animates (`false`) whatever the OS says; `None`
+/// follows the OS again.
+#[tauri::command]
+pub fn set_reduce_motion(enabled: Option<bool>) {
+    *OVERRIDE.lock().unwrap() = enabled;
+}
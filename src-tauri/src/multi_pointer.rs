@@ -0,0 +1,403 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    coords, crash, injection,
+    locks::{self, LockResource},
+    timing::{Scheduler, TimingMetrics},
+    vsync,
+};
+
+// ==========================================
+// Multi-Pointer Simulation (experimental)
+// ==========================================
+
+// Several synthetic touch contacts acting at once, for testing collaborative,
+// whiteboard-style applications. Each track drives one contact; tracks run in
+// parallel on a shared clock and are sampled into frames, every frame reporting all
+// contacts currently touching, as touch injection requires. Only Windows, whose
+// touch injection accepts multiple pointer ids, supports it so far.
+
+/// Touch digitizers commonly report at most ten contacts.
+const MAX_POINTERS: usize = 10;
+const DEFAULT_FRAME_US: u64 = 8_000;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PointerEvent {
+    Down {
+        target: coords::Position,
+    },
+    /// Glides to `target` over `duration_ms`, or jumps there when it is 0.
+    Move {
+        target: coords::Position,
+        #[serde(default)]
+        duration_ms: u64,
+    },
+    Up,
+    Wait {
+        micros: u64,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PointerTrack {
+    pub events: Vec<PointerEvent>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MultiPointerJob {
+    /// One contact per track; the track index is its pointer id.
+    pub tracks: Vec<PointerTrack>,
+    /// Interval between injected frames, in microseconds.
+    pub frame_us: Option<u64>,
+    /// Injects each frame on the first display refresh after it is due.
+    #[serde(default)]
+    pub align_to_vsync: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MultiPointerReport {
+    pub pointers: usize,
+    pub frames: u64,
+    pub elapsed_us: u64,
+    pub timing: TimingMetrics,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Down,
+    Update,
+    Up,
+}
+
+/// One contact's state in a frame.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+struct Contact {
+    id: u32,
+    x: i32,
+    y: i32,
+    phase: Phase,
+}
+
+struct Track<'a> {
+    events: &'a [PointerEvent],
+    next: usize,
+    /// When the current event became current.
+    since: Instant,
+    /// Where the current move started.
+    from: (i32, i32),
+    position: (i32, i32),
+    down: bool,
+}
+
+impl Track<'_> {
+    fn done(&self) -> bool {
+        self.next == self.events.len()
+    }
+
+    /// Advances to `now` and returns the contact's phase in this frame. Down and Up
+    /// each take a frame of their own, so a contact never starts and ends together.
+    fn advance(&mut self, now: Instant) -> Result<Option<Phase>, String> {
+        while let Some(event) = self.events.get(self.next) {
+            match event {
+                PointerEvent::Wait { micros } => {
+                    let end = self.since + Duration::from_micros(*micros);
+                    if now < end {
+                        break;
+                    }
+                    self.since = end;
+                }
+                PointerEvent::Move {
+                    target,
+                    duration_ms,
+                } => {
+                    let to = coords::resolve_position(target)?;
+                    let duration = Duration::from_millis(*duration_ms);
+                    let elapsed = now.saturating_duration_since(self.since);
+                    if elapsed < duration {
+                        let t = elapsed.as_secs_f64() / duration.as_secs_f64();
+                        let lerp = |a: i32, b: i32| a + ((b - a) as f64 * t).round() as i32;
+                        self.position = (lerp(self.from.0, to.0), lerp(self.from.1, to.1));
+                        break;
+                    }
+                    self.position = to;
+                    self.since += duration;
+                }
+                PointerEvent::Down { target } => {
+                    if self.down {
+                        return Err("Pointer is already down".to_string());
+                    }
+                    self.position = coords::resolve_position(target)?;
+                    self.down = true;
+                    self.step(now);
+                    return Ok(Some(Phase::Down));
+                }
+                PointerEvent::Up => {
+                    if !self.down {
+                        return Err("Pointer is not down".to_string());
+                    }
+                    self.down = false;
+                    self.step(now);
+                    return Ok(Some(Phase::Up));
+                }
+            }
+            self.next += 1;
+            self.from = self.position;
+        }
+        Ok(self.down.then_some(Phase::Update))
+    }
+
+    fn step(&mut self, now: Instant) {
+        self.next += 1;
+        self.since = now;
+        self.from = self.position;
+    }
+}
+
+pub fn validate(job: &MultiPointerJob) -> Result<(), String> {
+    if job.tracks.is_empty() || job.tracks.len() > MAX_POINTERS {
+        return Err(format!("A job needs 1 to {} tracks", MAX_POINTERS));
+    }
+    if job.frame_us == Some(0) {
+        return Err("Frame interval must be at least 1 µs".to_string());
+    }
+    for (i, track) in job.tracks.iter().enumerate() {
+        let downs = track
+            .events
+            .iter()
+            .filter(|e| matches!(e, PointerEvent::Down { .. }))
+            .count();
+        let ups = track
+            .events
+            .iter()
+            .filter(|e| matches!(e, PointerEvent::Up))
+            .count();
+        if downs != ups {
+            return Err(format!("Track {} leaves its pointer down", i + 1));
+        }
+    }
+    Ok(())
+}
+
+pub fn run_job(job: MultiPointerJob) -> Result<MultiPointerReport, String> {
+    validate(&job)?;
+    crash::breadcrumb(
+        "input",
+        format!("multi-pointer job with {} tracks", job.tracks.len()),
+    );
+
+    let id = injection::begin();
+    let result = thread::Builder::new()
+        .name("multi-pointer-job".to_string())
+        .spawn(move || execute(&job, id))
+        .map_err(|e| format!("Failed to spawn multi-pointer job: {}", e))
+        .and_then(|handle| {
+            handle
+                .join()
+                .map_err(|_| "Multi-pointer job panicked".to_string())
+        });
+    injection::finish(id);
+    result?
+}
+
+fn execute(job: &MultiPointerJob, id: u64) -> Result<MultiPointerReport, String> {
+    let _locks = locks::acquire(
+        &format!("multi-pointer job {}", id),
+        [LockResource::Mouse],
+        locks::JOB_WAIT,
+    )?;
+    let generation = injection::stop_generation();
+    let frame = Duration::from_micros(job.frame_us.unwrap_or(DEFAULT_FRAME_US));
+    let mut device = platform::Device::open(job.tracks.len())?;
+
+    let mut scheduler = Scheduler::new();
+    let started = Instant::now();
+    let mut tracks: Vec<Track> = job
+        .tracks
+        .iter()
+        .map(|track| Track {
+            events: &track.events,
+            next: 0,
+            since: started,
+            from: (0, 0),
+            position: (0, 0),
+            down: false,
+        })
+        .collect();
+
+    let mut next = started;
+    let mut frames = 0;
+    loop {
+        scheduler.wait_until(next);
+        if job.align_to_vsync {
+            vsync::wait(1);
+        }
+        let now = Instant::now();
+        if injection::stopped(id, generation) {
+            device.lift_all();
+            return Err(format!("Multi-pointer job stopped after {} frames", frames));
+        }
+
+        let mut contacts = Vec::new();
+        for (i, track) in tracks.iter_mut().enumerate() {
+            let phase = match track.advance(now) {
+                Ok(phase) => phase,
+                Err(e) => {
+                    device.lift_all();
+                    return Err(format!("Track {}: {}", i + 1, e));
+                }
+            };
+            if let Some(phase) = phase {
+                contacts.push(Contact {
+                    id: i as u32,
+                    x: track.position.0,
+                    y: track.position.1,
+                    phase,
+                });
+            }
+        }
+        if !contacts.is_empty() {
+            if let Err(e) = device.inject(&contacts) {
+                device.lift_all();
+                return Err(e);
+            }
+            frames += 1;
+        }
+        if tracks.iter().all(Track::done) {
+            break;
+        }
+        next += frame;
+    }
+
+    Ok(MultiPointerReport {
+        pointers: job.tracks.len(),
+        frames,
+        elapsed_us: started.elapsed().as_micros() as u64,
+        timing: scheduler.metrics(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::{POINT, RECT};
+    use windows::Win32::UI::Input::Pointer::{
+        InitializeTouchInjection, InjectTouchInput, POINTER_FLAGS, POINTER_FLAG_DOWN,
+        POINTER_FLAG_INCONTACT, POINTER_FLAG_INRANGE, POINTER_FLAG_UP, POINTER_FLAG_UPDATE,
+        POINTER_INFO, POINTER_TOUCH_INFO, TOUCH_FEEDBACK_DEFAULT,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        PT_TOUCH, TOUCH_FLAG_NONE, TOUCH_MASK_CONTACTAREA, TOUCH_MASK_PRESSURE,
+    };
+
+    use super::{Contact, Phase};
+
+    /// Half the side of the reported contact area, in pixels.
+    const CONTACT_RADIUS: i32 = 2;
+    const PRESSURE: u32 = 512;
+
+    pub struct Device {
+        /// Contacts touching after the last frame, lifted if the job fails midway.
+        touching: Vec<Contact>,
+    }
+
+    impl Device {
+        pub fn open(pointers: usize) -> Result<Self, String> {
+            unsafe { InitializeTouchInjection(pointers as u32, TOUCH_FEEDBACK_DEFAULT) }
+                .map_err(|e| format!("InitializeTouchInjection failed: {}", e))?;
+            Ok(Self {
+                touching: Vec::new(),
+            })
+        }
+
+        pub fn inject(&mut self, contacts: &[Contact]) -> Result<(), String> {
+            let infos: Vec<POINTER_TOUCH_INFO> = contacts.iter().map(info).collect();
+            unsafe { InjectTouchInput(&infos) }
+                .map_err(|e| format!("InjectTouchInput failed: {}", e))?;
+            self.touching = contacts
+                .iter()
+                .filter(|c| c.phase != Phase::Up)
+                .copied()
+                .collect();
+            Ok(())
+        }
+
+        pub fn lift_all(&mut self) {
+            let lifted: Vec<Contact> = self
+                .touching
+                .iter()
+                .map(|c| Contact {
+                    phase: Phase::Up,
+                    ..*c
+                })
+                .collect();
+            if !lifted.is_empty() {
+                let _ = self.inject(&lifted);
+            }
+        }
+    }
+
+    fn info(contact: &Contact) -> POINTER_TOUCH_INFO {
+        let flags: POINTER_FLAGS = match contact.phase {
+            Phase::Down => POINTER_FLAG_DOWN | POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT,
+            Phase::Update => POINTER_FLAG_UPDATE | POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT,
+            Phase::Up => POINTER_FLAG_UP,
+        };
+        POINTER_TOUCH_INFO {
+            pointerInfo: POINTER_INFO {
+                pointerType: PT_TOUCH,
+                pointerId: contact.id,
+                pointerFlags: flags,
+                ptPixelLocation: POINT {
+                    x: contact.x,
+                    y: contact.y,
+                },
+                ..Default::default()
+            },
+            touchFlags: TOUCH_FLAG_NONE,
+            touchMask: TOUCH_MASK_CONTACTAREA | TOUCH_MASK_PRESSURE,
+            rcContact: RECT {
+                left: contact.x - CONTACT_RADIUS,
+                top: contact.y - CONTACT_RADIUS,
+                right: contact.x + CONTACT_RADIUS,
+                bottom: contact.y + CONTACT_RADIUS,
+            },
+            pressure: PRESSURE,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::Contact;
+
+    pub struct Device;
+
+    impl Device {
+        pub fn open(_pointers: usize) -> Result<Self, String> {
+            Err("Multi-pointer input is only supported on Windows".to_string())
+        }
+
+        pub fn inject(&mut self, _contacts: &[Contact]) -> Result<(), String> {
+            Ok(())
+        }
+
+        pub fn lift_all(&mut self) {}
+    }
+}
+
+// ==========================================
+// MULTI-POINTER COMMANDS
+// ==========================================
+
+/// Plays parallel pointer tracks as concurrent touch contacts.
+#[tauri::command]
+pub async fn run_multi_pointer_job(job: MultiPointerJob) -> Result<MultiPointerReport, String> {
+    run_job(job)
+}
@@ -0,0 +1,428 @@
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
+
+use crate::{coords::Rect, shape::Shape, OverlayManager};
+
+// ==========================================
+// Overlay Backends
+// ==========================================
+
+// What a platform has to provide for privacy overlays. The overlay commands keep
+// their bookkeeping (ids, sessions, the audit log) and reach the windows only
+// through `Backend`, the implementation picked for the target at compile time, so
+// a new platform, or a stand-in that draws nothing, is one more implementation
+// rather than another `cfg` arm in every command. Handles are the platform's
+// window pointers: an NSWindow, an HWND, or an X11 window id. The bookkeeping is
+// generic over the backend, so tests run it against `MockBackend`.
+
+pub trait OverlayBackend {
+    /// Opens an overlay over `rect`, in native coordinates.
+    fn create(rect: Rect) -> Result<*mut c_void, String>;
+    /// Gives the overlay the capture treatment of the current display affinity.
+    fn apply_privacy(overlay: *mut c_void) -> Result<(), String>;
+    /// Passes clicks through to what the overlay covers, or takes them.
+    fn click_through(overlay: *mut c_void, through: bool) -> Result<(), String>;
+    fn move_to(overlay: *mut c_void, rect: Rect) -> Result<(), String>;
+    /// Closes the overlay and forgets it in `manager`.
+    fn destroy(manager: &OverlayManager, overlay: *mut c_void);
+    /// Closes every overlay of `manager`.
+    fn destroy_all(manager: &OverlayManager) -> Result<(), String>;
+
+    fn set_text(overlay: *mut c_void, text: Option<&str>);
+    fn set_progress(overlay: *mut c_void, percent: Option<f64>);
+    fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String>;
+    fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String>;
+    fn set_shape(overlay: *mut c_void, shape: &Shape) -> Result<(), String>;
+    /// Adds a button that reports overlay `id` as cancelled.
+    fn set_button(overlay: *mut c_void, id: u64, label: &str);
+    /// Closes overlay `id` after `ms`.
+    fn set_timeout(overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String>;
+    fn set_visible(overlay: *mut c_void, visible: bool);
+}
+
+#[cfg(target_os = "macos")]
+pub type Backend = MacBackend;
+#[cfg(target_os = "windows")]
+pub type Backend = WindowsBackend;
+#[cfg(target_os = "linux")]
+pub type Backend = X11Backend;
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub type Backend = Unsupported;
+
+/// Takes the one overlay a backend opened for a single rect.
+#[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
+fn single(overlays: Vec<*mut c_void>) -> Result<*mut c_void, String> {
+    overlays
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No overlay was created".to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub struct MacBackend;
+
+#[cfg(target_os = "macos")]
+impl OverlayBackend for MacBackend {
+    fn create(rect: Rect) -> Result<*mut c_void, String> {
+        single(crate::macos_overlay::create_privacy_overlay(Some(rect))?)
+    }
+
+    /// Overlays are shared like any window; only outlines are kept out of captures.
+    fn apply_privacy(_overlay: *mut c_void) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn click_through(overlay: *mut c_void, through: bool) -> Result<(), String> {
+        crate::macos_overlay::set_interactive(overlay, !through);
+        Ok(())
+    }
+
+    fn move_to(overlay: *mut c_void, rect: Rect) -> Result<(), String> {
+        crate::macos_overlay::move_overlay(overlay, rect)
+    }
+
+    fn destroy(manager: &OverlayManager, overlay: *mut c_void) {
+        crate::macos_overlay::destroy_overlay(manager, overlay);
+    }
+
+    fn destroy_all(manager: &OverlayManager) -> Result<(), String> {
+        crate::macos_overlay::destroy_privacy_overlay(manager);
+        Ok(())
+    }
+
+    fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        crate::macos_overlay::set_text(overlay, text);
+    }
+
+    fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        crate::macos_overlay::set_progress(overlay, percent);
+    }
+
+    fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String> {
+        crate::macos_overlay::set_opacity(overlay, opacity);
+        Ok(())
+    }
+
+    fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        crate::macos_overlay::set_image(overlay, image)
+    }
+
+    fn set_shape(overlay: *mut c_void, shape: &Shape) -> Result<(), String> {
+        crate::macos_overlay::set_shape(overlay, shape);
+        Ok(())
+    }
+
+    fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+        crate::macos_overlay::set_button(overlay, id, label);
+    }
+
+    fn set_timeout(_overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String> {
+        crate::macos_overlay::set_timeout(id, ms);
+        Ok(())
+    }
+
+    fn set_visible(overlay: *mut c_void, visible: bool) {
+        crate::macos_overlay::set_visible(overlay, visible);
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub struct WindowsBackend;
+
+#[cfg(target_os = "windows")]
+impl OverlayBackend for WindowsBackend {
+    fn create(rect: Rect) -> Result<*mut c_void, String> {
+        single(crate::windows_overlay::create_privacy_overlay(Some(rect))?)
+    }
+
+    fn apply_privacy(overlay: *mut c_void) -> Result<(), String> {
+        crate::affinity::apply(windows::Win32::Foundation::HWND(overlay))
+            .map_err(|e| format!("Failed to set display affinity: {}", e))
+    }
+
+    fn click_through(overlay: *mut c_void, through: bool) -> Result<(), String> {
+        crate::windows_overlay::set_interactive(overlay, !through)
+    }
+
+    fn move_to(overlay: *mut c_void, rect: Rect) -> Result<(), String> {
+        crate::windows_overlay::move_overlay(overlay, rect)
+    }
+
+    fn destroy(manager: &OverlayManager, overlay: *mut c_void) {
+        crate::windows_overlay::destroy_overlay(manager, overlay);
+    }
+
+    fn destroy_all(manager: &OverlayManager) -> Result<(), String> {
+        crate::windows_overlay::destroy_privacy_overlay(manager);
+        Ok(())
+    }
+
+    fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        crate::windows_overlay::set_text(overlay, text);
+    }
+
+    fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        crate::windows_overlay::set_progress(overlay, percent);
+    }
+
+    fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String> {
+        crate::windows_overlay::set_opacity(overlay, opacity)
+    }
+
+    fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        crate::windows_overlay::set_image(overlay, image)
+    }
+
+    fn set_shape(overlay: *mut c_void, shape: &Shape) -> Result<(), String> {
+        crate::windows_overlay::set_shape(overlay, shape);
+        Ok(())
+    }
+
+    fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+        crate::windows_overlay::set_button(overlay, id, label);
+    }
+
+    fn set_timeout(overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String> {
+        crate::windows_overlay::set_timeout(overlay, id, ms)
+    }
+
+    fn set_visible(overlay: *mut c_void, visible: bool) {
+        crate::windows_overlay::set_visible(overlay, visible);
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub struct X11Backend;
+
+#[cfg(target_os = "linux")]
+impl OverlayBackend for X11Backend {
+    fn create(rect: Rect) -> Result<*mut c_void, String> {
+        single(crate::linux_privacy::create_privacy_overlay(Some(rect))?)
+    }
+
+    /// X11 has no way to keep a window out of captures.
+    fn apply_privacy(_overlay: *mut c_void) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn click_through(overlay: *mut c_void, through: bool) -> Result<(), String> {
+        crate::linux_privacy::set_interactive(overlay, !through)
+    }
+
+    fn move_to(overlay: *mut c_void, rect: Rect) -> Result<(), String> {
+        crate::linux_privacy::move_overlay(overlay, rect)
+    }
+
+    fn destroy(manager: &OverlayManager, overlay: *mut c_void) {
+        crate::linux_privacy::destroy_overlay(manager, overlay);
+    }
+
+    fn destroy_all(manager: &OverlayManager) -> Result<(), String> {
+        crate::linux_privacy::destroy_privacy_overlay(manager);
+        Ok(())
+    }
+
+    fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        crate::linux_privacy::set_text(overlay, text);
+    }
+
+    fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        crate::linux_privacy::set_progress(overlay, percent);
+    }
+
+    fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String> {
+        crate::linux_privacy::set_opacity(overlay, opacity);
+        Ok(())
+    }
+
+    fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        crate::linux_privacy::set_image(overlay, image)
+    }
+
+    fn set_shape(overlay: *mut c_void, shape: &Shape) -> Result<(), String> {
+        crate::linux_privacy::set_shape(overlay, shape)
+    }
+
+    fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+        crate::linux_privacy::set_button(overlay, id, label);
+    }
+
+    fn set_timeout(_overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String> {
+        crate::linux_privacy::set_timeout(id, ms);
+        Ok(())
+    }
+
+    fn set_visible(overlay: *mut c_void, visible: bool) {
+        crate::linux_privacy::set_visible(overlay, visible);
+    }
+}
+
+/// Targets without overlays: nothing opens, so there is never a handle to act on.
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub struct Unsupported;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+impl OverlayBackend for Unsupported {
+    fn create(_rect: Rect) -> Result<*mut c_void, String> {
+        Err("Privacy overlay is only supported on macOS, Windows and Linux".to_string())
+    }
+
+    fn apply_privacy(_overlay: *mut c_void) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn click_through(_overlay: *mut c_void, _through: bool) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn move_to(_overlay: *mut c_void, _rect: Rect) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn destroy(_manager: &OverlayManager, _overlay: *mut c_void) {}
+
+    fn destroy_all(_manager: &OverlayManager) -> Result<(), String> {
+        Err("Privacy overlay is only supported on macOS, Windows and Linux".to_string())
+    }
+
+    fn set_text(_overlay: *mut c_void, _text: Option<&str>) {}
+
+    fn set_progress(_overlay: *mut c_void, _percent: Option<f64>) {}
+
+    fn set_opacity(_overlay: *mut c_void, _opacity: f64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_image(_overlay: *mut c_void, _image: &image::RgbaImage) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_shape(_overlay: *mut c_void, _shape: &Shape) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_button(_overlay: *mut c_void, _id: u64, _label: &str) {}
+
+    fn set_timeout(_overlay: *mut c_void, _id: u64, _ms: u64) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_visible(_overlay: *mut c_void, _visible: bool) {}
+}
+
+/// Opens nothing and records each call, for tests of the overlay bookkeeping.
+/// Handles count up from 1. Calls are kept per thread, as tests run side by side.
+#[cfg(test)]
+pub struct MockBackend;
+
+#[cfg(test)]
+thread_local! {
+    static MOCK_CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static MOCK_NEXT: Cell<usize> = const { Cell::new(1) };
+    static MOCK_FAIL: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+#[cfg(test)]
+impl MockBackend {
+    /// Forgets this thread's calls and failures and counts handles from 1 again.
+    pub fn reset() {
+        MOCK_CALLS.with(|calls| calls.take());
+        MOCK_NEXT.with(|next| next.set(1));
+        MOCK_FAIL.with(|fail| fail.set(None));
+    }
+
+    /// The calls made on this thread since the last `take_calls`.
+    pub fn take_calls() -> Vec<String> {
+        MOCK_CALLS.with(|calls| calls.take())
+    }
+
+    /// Makes calls named `call` fail from now on, on this thread.
+    pub fn fail(call: &'static str) {
+        MOCK_FAIL.with(|fail| fail.set(Some(call)));
+    }
+
+    fn record(call: &str, details: String) -> Result<(), String> {
+        MOCK_CALLS.with(|calls| calls.borrow_mut().push(format!("{} {}", call, details)));
+        match MOCK_FAIL.with(Cell::get) {
+            Some(failing) if failing == call => Err(format!("{} failed", call)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl OverlayBackend for MockBackend {
+    fn create(rect: Rect) -> Result<*mut c_void, String> {
+        let handle = MOCK_NEXT.with(|next| next.replace(next.get() + 1));
+        Self::record("create", format!("{} {:?}", handle, rect))?;
+        Ok(handle as *mut c_void)
+    }
+
+    fn apply_privacy(overlay: *mut c_void) -> Result<(), String> {
+        Self::record("apply_privacy", format!("{}", overlay as usize))
+    }
+
+    fn click_through(overlay: *mut c_void, through: bool) -> Result<(), String> {
+        Self::record("click_through", format!("{} {}", overlay as usize, through))
+    }
+
+    fn move_to(overlay: *mut c_void, rect: Rect) -> Result<(), String> {
+        Self::record("move_to", format!("{} {:?}", overlay as usize, rect))
+    }
+
+    fn destroy(manager: &OverlayManager, overlay: *mut c_void) {
+        manager
+            .overlays
+            .lock()
+            .unwrap()
+            .retain(|o| o.handle != overlay);
+        let _ = Self::record("destroy", format!("{}", overlay as usize));
+    }
+
+    fn destroy_all(manager: &OverlayManager) -> Result<(), String> {
+        let count = manager.overlays.lock().unwrap().drain(..).count();
+        Self::record("destroy_all", format!("{}", count))
+    }
+
+    fn set_text(overlay: *mut c_void, text: Option<&str>) {
+        let _ = Self::record("set_text", format!("{} {:?}", overlay as usize, text));
+    }
+
+    fn set_progress(overlay: *mut c_void, percent: Option<f64>) {
+        let _ = Self::record(
+            "set_progress",
+            format!("{} {:?}", overlay as usize, percent),
+        );
+    }
+
+    fn set_opacity(overlay: *mut c_void, opacity: f64) -> Result<(), String> {
+        Self::record("set_opacity", format!("{} {}", overlay as usize, opacity))
+    }
+
+    fn set_image(overlay: *mut c_void, image: &image::RgbaImage) -> Result<(), String> {
+        Self::record(
+            "set_image",
+            format!("{} {}x{}", overlay as usize, image.width(), image.height()),
+        )
+    }
+
+    fn set_shape(overlay: *mut c_void, _shape: &Shape) -> Result<(), String> {
+        Self::record("set_shape", format!("{}", overlay as usize))
+    }
+
+    fn set_button(overlay: *mut c_void, id: u64, label: &str) {
+        let _ = Self::record(
+            "set_button",
+            format!("{} {} {}", overlay as usize, id, label),
+        );
+    }
+
+    fn set_timeout(overlay: *mut c_void, id: u64, ms: u64) -> Result<(), String> {
+        Self::record("set_timeout", format!("{} {} {}", overlay as usize, id, ms))
+    }
+
+    fn set_visible(overlay: *mut c_void, visible: bool) {
+        let _ = Self::record("set_visible", format!("{} {}", overlay as usize, visible));
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Status Palette
+// ==========================================
+
+// Every color the native renderers draw with (overlay text, preview outlines, HUD
+// text and backgrounds, visual diff highlights) is looked up here by role, so a
+// preset chosen for the user's color vision applies everywhere at once. Any role can
+// be overridden on top of the preset. The choice is saved and survives restarts;
+// windows already open keep their colors until they are next drawn.
+
+pub type Rgb = (u8, u8, u8);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PalettePreset {
+    #[default]
+    Standard,
+    /// Okabe–Ito hues, distinct with deuteranopia and protanopia.
+    RedGreenSafe,
+    /// Red and cyan hues, distinct with tritanopia.
+    BlueYellowSafe,
+    HighContrast,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorRole {
+    /// Text on overlays and HUDs.
+    Text,
+    /// HUD backgrounds.
+    Background,
+    /// Frames marking a region, e.g. layout previews.
+    Accent,
+    /// Pixels flagged as wrong, e.g. in visual diffs.
+    Alert,
+}
+
+impl PalettePreset {
+    fn color(self, role: ColorRole) -> Rgb {
+        use ColorRole::*;
+        match (self, role) {
+            (_, Text) => (0xFF, 0xFF, 0xFF),
+            (_, Background) => (0x00, 0x00, 0x00),
+            (PalettePreset::Standard, Accent) => (0x00, 0xB4, 0xFF),
+            (PalettePreset::Standard, Alert) => (0xFF, 0x00, 0x00),
+            (PalettePreset::RedGreenSafe, Accent) => (0x56, 0xB4, 0xE9),
+            (PalettePreset::RedGreenSafe, Alert) => (0xE6, 0x9F, 0x00),
+            (PalettePreset::BlueYellowSafe, Accent) => (0x00, 0xC8, 0xC8),
+            (PalettePreset::BlueYellowSafe, Alert) => (0xE8, 0x38, 0x4F),
+            (PalettePreset::HighContrast, Accent) => (0xFF, 0xFF, 0x00),
+            (PalettePreset::HighContrast, Alert) => (0xFF, 0x00, 0xFF),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PaletteConfig {
+    #[serde(default)]
+    pub preset: PalettePreset,
+    /// "#RRGGBB" per role, replacing the preset's color.
+    #[serde(default)]
+    pub overrides: BTreeMap<ColorRole, String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Palette {
+    #[serde(flatten)]
+    pub config: PaletteConfig,
+    /// Every role's color after overrides, as "#RRGGBB".
+    pub colors: BTreeMap<ColorRole, String>,
+}
+
+const ROLES: [ColorRole; 4] = [
+    ColorRole::Text,
+    ColorRole::Background,
+    ColorRole::Accent,
+    ColorRole::Alert,
+];
+
+static CONFIG: Lazy<Mutex<PaletteConfig>> = Lazy::new(|| Mutex::new(PaletteConfig::default()));
+static PALETTE_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Loads the palette saved at `path`, if any.
+pub fn init(path: PathBuf) {
+    if let Ok(data) = fs::read_to_string(&path) {
+        match serde_json::from_str::<PaletteConfig>(&data) {
+            Ok(config) if validate(&config).is_ok() => *CONFIG.lock().unwrap() = config,
+            _ => log::warn!("palette file is invalid, using the standard palette"),
+        }
+    }
+    *PALETTE_FILE.lock().unwrap() = Some(path);
+}
+
+/// Parses "#RRGGBB" (the "#" is optional).
+pub fn parse(value: &str) -> Result<Rgb, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .ok_or_else(|| format!("Invalid color '{}' (expected #RRGGBB)", value))
+    };
+    if hex.len() != 6 {
+        return Err(format!("Invalid color '{}' (expected #RRGGBB)", value));
+    }
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Formats a color as "#RRGGBB", the form `parse` reads.
+pub fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+fn validate(config: &PaletteConfig) -> Result<(), String> {
+    config
+        .overrides
+        .values()
+        .try_for_each(|value| parse(value).map(|_| ()))
+}
+
+/// The current color for `role`.
+pub fn color(role: ColorRole) -> Rgb {
+    let config = CONFIG.lock().unwrap();
+    config
+        .overrides
+        .get(&role)
+        .and_then(|value| parse(value).ok())
+        .unwrap_or_else(|| config.preset.color(role))
+}
+
+pub fn current() -> Palette {
+    Palette {
+        config: CONFIG.lock().unwrap().clone(),
+        colors: ROLES.iter().map(|&role| (role, hex(color(role)))).collect(),
+    }
+}
+
+pub fn set(config: PaletteConfig) -> Result<Palette, String> {
+    validate(&config)?;
+    if let Some(path) = PALETTE_FILE.lock().unwrap().as_ref() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to save palette: {}", e))?;
+    }
+    *CONFIG.lock().unwrap() = config;
+    Ok(current())
+}
+
+#[cfg(target_os = "windows")]
+pub fn colorref((r, g, b): Rgb) -> windows::Win32::Foundation::COLORREF {
+    windows::Win32::Foundation::COLORREF(r as u32 | (g as u32) << 8 | (b as u32) << 16)
+}
+
+#[cfg(target_os = "macos")]
+pub fn ns_color((r, g, b): Rgb, alpha: f64) -> cocoa::base::id {
+    unsafe {
+        cocoa::appkit::NSColor::colorWithCalibratedRed_green_blue_alpha_(
+            cocoa::base::nil,
+            r as f64 / 255.0,
+            g as f64 / 255.0,
+            b as f64 / 255.0,
+            alpha,
+        )
+    }
+}
+
+// ==========================================
+// PALETTE COMMANDS
+// ==========================================
+
+/// The palette settings with every role's resolved color.
+#[tauri::command]
+pub fn get_palette() -> Palette {
+    current()
+}
+
+/// Picks a preset and per-role overrides for all native-rendered colors.
+#[tauri::command]
+pub fn set_palette(config: PaletteConfig) -> Result<Palette, String> {
+    set(config)
+}
@@ -0,0 +1,222 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    coords::{self, Bounds},
+    hud::{self, HudStyle, UpdateError},
+    locale,
+    sessions::{self, Resource},
+};
+
+// ==========================================
+// Presenter Console
+// ==========================================
+
+// Turns one monitor into a private presenter screen: notes, an elapsed-time clock and
+// the next slide, each in a HUD hidden from capture, so any other monitor can be
+// shared as-is. Notes and the next slide are pushed by the frontend, or by several
+// clients at once; each push bumps a revision, and a push based on an older revision
+// is rejected with the current content instead of overwriting it.
+
+const DEFAULT_NOTES_FONT: f64 = 32.0;
+const TIMER_TICK: Duration = Duration::from_secs(1);
+
+struct Console {
+    monitor: usize,
+    notes: u64,
+    timer: u64,
+    next_slide: u64,
+    started: Instant,
+    content: PresenterContent,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PresenterContent {
+    /// Bumped by every successful update.
+    pub revision: u64,
+    pub notes: String,
+    pub next_slide: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct PresenterStatus {
+    pub monitor: usize,
+    pub elapsed_ms: u64,
+    #[serde(flatten)]
+    pub content: PresenterContent,
+}
+
+static CONSOLE: Lazy<Mutex<Option<Console>>> = Lazy::new(|| Mutex::new(None));
+/// Bumped when the console closes so its timer thread exits.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn panel(monitor: usize, x: f64, y: f64, width: f64, height: f64) -> Result<coords::Rect, String> {
+    coords::resolve_bounds(&Bounds::Percent {
+        x,
+        y,
+        width,
+        height,
+        monitor: Some(monitor),
+    })
+}
+
+/// Runs on the main thread.
+fn close() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(console) = CONSOLE.lock().unwrap().take() {
+        for id in [console.notes, console.timer, console.next_slide] {
+            hud::close(id);
+        }
+        sessions::untrack(|r| *r == Resource::PresenterConsole);
+    }
+}
+
+/// Runs on the main thread.
+fn open(monitor: usize, style: &HudStyle) -> Result<(), String> {
+    let count = coords::monitors().len();
+    if monitor >= count {
+        return Err(format!(
+            "Monitor index {} out of range (found {})",
+            monitor, count
+        ));
+    }
+    let scaled = |factor: f64| HudStyle {
+        font_size: style.font_size * factor,
+        ..style.clone()
+    };
+
+    close();
+    let clock = locale::duration(Duration::ZERO);
+    let panels = [
+        ((2.0, 3.0, 62.0, 94.0), String::new(), style.clone()),
+        ((66.0, 3.0, 32.0, 20.0), clock, scaled(2.0)),
+        ((66.0, 26.0, 32.0, 71.0), String::new(), scaled(0.75)),
+    ];
+    let mut ids = Vec::new();
+    for ((x, y, width, height), text, style) in panels {
+        match panel(monitor, x, y, width, height)
+            .and_then(|rect| hud::open(rect, &text, &style, true))
+        {
+            Ok(id) => ids.push(id),
+            Err(e) => {
+                ids.into_iter().for_each(hud::close);
+                return Err(e);
+            }
+        }
+    }
+    let (notes, timer, next_slide) = (ids[0], ids[1], ids[2]);
+
+    *CONSOLE.lock().unwrap() = Some(Console {
+        monitor,
+        notes,
+        timer,
+        next_slide,
+        started: Instant::now(),
+        content: PresenterContent::default(),
+    });
+    sessions::track(Resource::PresenterConsole);
+    Ok(())
+}
+
+fn tick() {
+    if let Some(console) = CONSOLE.lock().unwrap().as_ref() {
+        let _ = hud::set_text(console.timer, &locale::duration(console.started.elapsed()));
+    }
+}
+
+// ==========================================
+// PRESENTER COMMANDS
+// ==========================================
+
+/// Opens the console on `monitor`, replacing any open console.
+#[tauri::command]
+pub fn start_presenter_console(
+    app: AppHandle,
+    monitor: usize,
+    style: Option<HudStyle>,
+) -> Result<(), String> {
+    let style = style.unwrap_or(HudStyle {
+        font_size: DEFAULT_NOTES_FONT,
+        ..HudStyle::default()
+    });
+    open(monitor, &style)?;
+
+    let generation = GENERATION.load(Ordering::SeqCst);
+    thread::Builder::new()
+        .name("presenter-timer".to_string())
+        .spawn(move || {
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(TIMER_TICK);
+                let _ = app.run_on_main_thread(tick);
+            }
+        })
+        .map_err(|e| format!("Failed to spawn presenter timer: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_presenter_console() {
+    close();
+}
+
+/// Updates the notes and/or next-slide panels; omitted fields are left unchanged.
+/// With `revision`, the update only applies if nobody changed the content since.
+/// Returns the new revision.
+#[tauri::command]
+pub fn update_presenter_console(
+    notes: Option<String>,
+    next_slide: Option<String>,
+    revision: Option<u64>,
+) -> Result<u64, UpdateError<PresenterContent>> {
+    let mut console = CONSOLE.lock().unwrap();
+    let console = console
+        .as_mut()
+        .ok_or_else(|| "Presenter console is not running".to_string())?;
+    if let Some(expected) = revision.filter(|r| *r != console.content.revision) {
+        return Err(UpdateError::Conflict {
+            expected,
+            current: console.content.clone(),
+        });
+    }
+    if let Some(notes) = notes {
+        hud::set_text(console.notes, &notes)?;
+        console.content.notes = notes;
+    }
+    if let Some(next_slide) = next_slide {
+        hud::set_text(console.next_slide, &next_slide)?;
+        console.content.next_slide = next_slide;
+    }
+    console.content.revision += 1;
+    Ok(console.content.revision)
+}
+
+#[tauri::command]
+pub fn reset_presenter_timer() -> Result<(), String> {
+    let mut console = CONSOLE.lock().unwrap();
+    let console = console.as_mut().ok_or("Presenter console is not running")?;
+    console.started = Instant::now();
+    hud::set_text(console.timer, &locale::duration(Duration::ZERO))
+}
+
+#[tauri::command]
+pub fn get_presenter_status() -> Option<PresenterStatus> {
+    CONSOLE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|console| PresenterStatus {
+            monitor: console.monitor,
+            elapsed_ms: console.started.elapsed().as_millis() as u64,
+            content: console.content.clone(),
+        })
+}
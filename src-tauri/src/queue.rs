@@ -0,0 +1,412 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{crash, events, injection::InputJob, runner, scripts, watchdog};
+
+// ==========================================
+// Job Queue
+// ==========================================
+
+// Scripts and macros submitted here run one at a time on a scheduler thread, so jobs
+// that drive the mouse and keyboard never interleave. The next job is the
+// highest-priority one whose earliest start has come, ties going to the oldest; a
+// job still waiting at its latest finish expires. The queue is written to disk on
+// every change and reloaded at startup. Position changes arrive as
+// `queue://position` events and status changes as `queue://job`.
+
+const POSITION_EVENT: &str = "queue://position";
+const JOB_EVENT: &str = "queue://job";
+/// Longest the scheduler sleeps before checking start times and deadlines again.
+const IDLE_POLL: Duration = Duration::from_secs(1);
+/// Watchdog entry of the worker, which beats on every pass and is exempt while a job
+/// runs.
+const WATCHED: &str = "job-queue";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum JobTask {
+    /// A saved script, by id.
+    Script { id: String },
+    /// An input job, e.g. a recorded macro.
+    Macro { job: InputJob },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JobRequest {
+    pub task: JobTask,
+    /// Higher runs first.
+    #[serde(default)]
+    pub priority: i32,
+    /// Unix ms before which the job doesn't start.
+    pub earliest_start_ms: Option<u64>,
+    /// Unix ms by which the job must be done. A job that hasn't started expires; a
+    /// running script stops at its next step.
+    pub latest_finish_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobStatus {
+    Waiting,
+    Running,
+    Passed,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QueuedJob {
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: JobRequest,
+    pub submitted_ms: u64,
+    pub status: JobStatus,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct QueuePosition {
+    pub job: u64,
+    /// 1-based among waiting jobs.
+    pub position: usize,
+    pub waiting: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JobUpdate {
+    pub job: u64,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Queue {
+    /// Persisted, so ids aren't reused across restarts.
+    next_id: u64,
+    jobs: Vec<QueuedJob>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Queue {
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(self).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::warn!("failed to save job queue: {}", e);
+        }
+    }
+
+    /// Waiting jobs in the order they will run, start times permitting.
+    fn waiting(&self) -> Vec<&QueuedJob> {
+        let mut waiting: Vec<&QueuedJob> = self
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Waiting)
+            .collect();
+        waiting.sort_by_key(|job| (Reverse(job.request.priority), job.id));
+        waiting
+    }
+
+    /// Marks the first waiting job that may start at `now` as running.
+    fn take_next(&mut self, now: u64) -> Option<QueuedJob> {
+        let id = self
+            .waiting()
+            .into_iter()
+            .find(|job| job.request.earliest_start_ms.map_or(true, |ms| ms <= now))?
+            .id;
+        let job = self.jobs.iter_mut().find(|job| job.id == id)?;
+        job.status = JobStatus::Running;
+        let job = job.clone();
+        self.save();
+        Some(job)
+    }
+
+    /// Time until the next waiting job's earliest start.
+    fn next_start(&self, now: u64) -> Option<Duration> {
+        self.waiting()
+            .iter()
+            .filter_map(|job| job.request.earliest_start_ms)
+            .min()
+            .map(|ms| Duration::from_millis(ms.saturating_sub(now)))
+    }
+}
+
+static QUEUE: Lazy<Mutex<Queue>> = Lazy::new(|| Mutex::new(Queue::default()));
+/// Signalled when jobs are submitted or cancelled.
+static WAKE: Condvar = Condvar::new();
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Loads the queue saved at `path`. Jobs that were running when the app quit are
+/// dropped rather than replayed from the start.
+pub fn init(path: PathBuf) {
+    let mut queue = match fs::read_to_string(&path) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+            log::warn!("job queue file is corrupt, starting empty: {}", e);
+            Queue::default()
+        }),
+        Err(_) => Queue::default(),
+    };
+    queue.jobs.retain(|job| {
+        let interrupted = job.status == JobStatus::Running;
+        if interrupted {
+            log::warn!("dropping job {}, interrupted by shutdown", job.id);
+        }
+        !interrupted
+    });
+    queue.next_id = queue.next_id.max(1);
+    queue.path = Some(path);
+    queue.save();
+    *QUEUE.lock().unwrap() = queue;
+}
+
+fn now_ms() -> u64 {
+    crash::now_ms() as u64
+}
+
+pub fn submit(request: JobRequest) -> Result<QueuedJob, String> {
+    let now = now_ms();
+    if let Some(finish) = request.latest_finish_ms {
+        if finish <= now {
+            return Err("Latest finish is already past".to_string());
+        }
+        if request
+            .earliest_start_ms
+            .is_some_and(|start| start >= finish)
+        {
+            return Err("Earliest start must come before latest finish".to_string());
+        }
+    }
+    if let JobTask::Script { id } = &request.task {
+        scripts::load(id)?;
+    }
+
+    let mut queue = QUEUE.lock().unwrap();
+    let job = QueuedJob {
+        id: queue.next_id,
+        request,
+        submitted_ms: now,
+        status: JobStatus::Waiting,
+    };
+    queue.next_id += 1;
+    queue.jobs.push(job.clone());
+    queue.save();
+    crash::breadcrumb("queue", format!("submit job {}", job.id));
+    WAKE.notify_all();
+    Ok(job)
+}
+
+/// Cancels a job that hasn't started yet.
+pub fn cancel(id: u64) -> Result<(), String> {
+    let mut queue = QUEUE.lock().unwrap();
+    let job = queue
+        .jobs
+        .iter_mut()
+        .find(|job| job.id == id)
+        .ok_or_else(|| format!("Job {} not found", id))?;
+    match job.status {
+        JobStatus::Waiting => job.status = JobStatus::Cancelled,
+        JobStatus::Running => {
+            return Err(format!(
+                "Job {} is already running; stop it with stop_script_runs",
+                id
+            ))
+        }
+        _ => return Err(format!("Job {} not found", id)),
+    }
+    queue.save();
+    WAKE.notify_all();
+    Ok(())
+}
+
+/// The running job first, then waiting jobs in the order they will run.
+pub fn list() -> Vec<QueuedJob> {
+    let queue = QUEUE.lock().unwrap();
+    queue
+        .jobs
+        .iter()
+        .filter(|job| job.status == JobStatus::Running)
+        .chain(queue.waiting())
+        .cloned()
+        .collect()
+}
+
+fn update(app: &AppHandle, job: u64, status: JobStatus, error: Option<String>) {
+    events::emit(app, JOB_EVENT, JobUpdate { job, status, error });
+}
+
+/// Expires waiting jobs past their latest finish and removes every job that
+/// finished, announcing each.
+fn sweep(app: &AppHandle, queue: &mut Queue, now: u64) {
+    for job in &mut queue.jobs {
+        if job.status == JobStatus::Waiting
+            && job.request.latest_finish_ms.is_some_and(|ms| ms <= now)
+        {
+            job.status = JobStatus::Expired;
+        }
+    }
+    let before = queue.jobs.len();
+    queue.jobs.retain(|job| match job.status {
+        JobStatus::Waiting | JobStatus::Running => true,
+        status => {
+            update(app, job.id, status, None);
+            false
+        }
+    });
+    if queue.jobs.len() != before {
+        queue.save();
+    }
+}
+
+/// Emits a position event for each waiting job whose position changed.
+fn announce(app: &AppHandle, queue: &Queue, positions: &mut HashMap<u64, (usize, usize)>) {
+    let waiting = queue.waiting();
+    let current: HashMap<u64, (usize, usize)> = waiting
+        .iter()
+        .enumerate()
+        .map(|(i, job)| (job.id, (i + 1, waiting.len())))
+        .collect();
+    for (&job, &(position, waiting)) in &current {
+        if positions.get(&job) != Some(&(position, waiting)) {
+            events::emit(
+                app,
+                POSITION_EVENT,
+                QueuePosition {
+                    job,
+                    position,
+                    waiting,
+                },
+            );
+        }
+    }
+    *positions = current;
+}
+
+fn execute(app: &AppHandle, job: &QueuedJob) -> Result<(), String> {
+    match &job.request.task {
+        JobTask::Script { id } => {
+            let script = scripts::load(id)?;
+            let deadline = job
+                .request
+                .latest_finish_ms
+                .map(|ms| Instant::now() + Duration::from_millis(ms.saturating_sub(now_ms())));
+            let report = runner::run(app, &script, deadline);
+            match report.error {
+                None => Ok(()),
+                Some(e) => Err(e),
+            }
+        }
+        JobTask::Macro { job } => crate::injection::run_job(job.clone()).map(|_| ()),
+    }
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    spawn(app.clone())?;
+    watchdog::register(
+        WATCHED,
+        watchdog::THREAD_TIMEOUT,
+        Some(Arc::new(move || {
+            // Stalls are only detected between jobs, and the stalled worker exits
+            // once it recovers.
+            spawn(app.clone())?;
+            WAKE.notify_all();
+            Ok(())
+        })),
+    );
+    Ok(())
+}
+
+fn spawn(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("job-queue".to_string())
+        .spawn(move || {
+            let mut positions = HashMap::new();
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                watchdog::heartbeat(WATCHED);
+                let job = {
+                    let mut queue = QUEUE.lock().unwrap();
+                    let now = now_ms();
+                    sweep(&app, &mut queue, now);
+                    announce(&app, &queue, &mut positions);
+                    match queue.take_next(now) {
+                        Some(job) => job,
+                        None => {
+                            let idle = queue
+                                .next_start(now)
+                                .map_or(IDLE_POLL, |d| d.min(IDLE_POLL));
+                            drop(WAKE.wait_timeout(queue, idle).unwrap());
+                            continue;
+                        }
+                    }
+                };
+
+                crash::breadcrumb("queue", format!("run job {}", job.id));
+                watchdog::suspend(WATCHED);
+                update(&app, job.id, JobStatus::Running, None);
+                let result = execute(&app, &job);
+
+                let mut queue = QUEUE.lock().unwrap();
+                queue.jobs.retain(|queued| queued.id != job.id);
+                queue.save();
+                match result {
+                    Ok(()) => update(&app, job.id, JobStatus::Passed, None),
+                    Err(e) => update(&app, job.id, JobStatus::Failed, Some(e)),
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn job queue: {}", e))?;
+
+    Ok(())
+}
+
+/// Stops scheduling after the running job; waiting jobs stay queued.
+pub fn stop() -> Result<(), String> {
+    watchdog::unregister(WATCHED);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    WAKE.notify_all();
+    Ok(())
+}
+
+// ==========================================
+// JOB QUEUE COMMANDS
+// ==========================================
+
+/// Queues a script or macro and returns it with its id.
+#[tauri::command]
+pub fn submit_job(request: JobRequest) -> Result<QueuedJob, String> {
+    submit(request)
+}
+
+#[tauri::command]
+pub fn list_job_queue() -> Vec<QueuedJob> {
+    list()
+}
+
+#[tauri::command]
+pub fn cancel_queued_job(id: u64) -> Result<(), String> {
+    cancel(id)
+}
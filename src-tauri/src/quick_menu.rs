@@ -0,0 +1,597 @@
+use std::{f64::consts::TAU, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    actions::Action,
+    context_actions,
+    coords::{self, Rect},
+    hotkeys,
+    hud::{self, HudStyle},
+};
+
+// ==========================================
+// Quick Menu
+// ==========================================
+
+// The presenter's most used actions (the overlay, scenes, macros) on screen at once,
+// summoned with the `quick-menu` action, usually from a hotkey, so the main window
+// never has to come up mid-meeting. Items are HUDs hidden from capture, in a column
+// or a ring in the middle of the primary monitor. While the menu is open the arrow
+// keys, Tab, Enter, Escape and the item numbers are taken from other apps, and a
+// mouse hook picks the item under the pointer and swallows clicks on items; a click
+// anywhere else closes the menu and goes through. Everything here except the hook
+// callbacks runs on the main thread.
+
+/// Items are chosen with the digit keys.
+const MAX_ITEMS: usize = 9;
+/// Device-independent pixels.
+const FONT_SIZE: f64 = 22.0;
+const ITEM_WIDTH: f64 = 240.0;
+const ITEM_GAP: f64 = 8.0;
+/// Distance from the middle of the ring to the middle of each item.
+const RING_RADIUS: f64 = 180.0;
+/// Visibility of the items not selected.
+const DIMMED: f64 = 0.55;
+const NAVIGATION_KEYS: &[&str] = &["Up", "Down", "Left", "Right", "Tab", "Enter", "Escape"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuickMenuLayout {
+    #[default]
+    List,
+    /// Clockwise from the top.
+    Radial,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuickMenuItem {
+    pub label: String,
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct QuickMenuConfig {
+    #[serde(default)]
+    pub layout: QuickMenuLayout,
+    pub items: Vec<QuickMenuItem>,
+}
+
+impl Default for QuickMenuConfig {
+    fn default() -> Self {
+        let item = |label: &str, action| QuickMenuItem {
+            label: label.to_string(),
+            action,
+        };
+        Self {
+            layout: QuickMenuLayout::default(),
+            items: vec![
+                item("Toggle overlay", Action::ToggleOverlay),
+                item("Spotlight", Action::ToggleSpotlight),
+                item("Keystrokes", Action::ToggleKeystrokes),
+            ],
+        }
+    }
+}
+
+/// An open menu.
+struct Menu {
+    app: AppHandle,
+    items: Vec<QuickMenuItem>,
+    huds: Vec<u64>,
+    /// Where each item is, in native coordinates.
+    rects: Vec<Rect>,
+    selected: usize,
+}
+
+static CONFIG: Lazy<Mutex<QuickMenuConfig>> = Lazy::new(|| Mutex::new(QuickMenuConfig::default()));
+static MENU: Lazy<Mutex<Option<Menu>>> = Lazy::new(|| Mutex::new(None));
+
+/// Where `count` items go on `monitor`.
+fn layout(layout: QuickMenuLayout, count: usize, monitor: &coords::Monitor) -> Vec<Rect> {
+    let width = monitor.native_length(ITEM_WIDTH);
+    let height = monitor.native_length(FONT_SIZE * 2.2);
+    let gap = monitor.native_length(ITEM_GAP);
+    let center_x = monitor.x as f64 + monitor.width as f64 / 2.0;
+    let center_y = monitor.y as f64 + monitor.height as f64 / 2.0;
+    let rect = |x: f64, y: f64| Rect {
+        x: (x - width / 2.0).round() as i32,
+        y: (y - height / 2.0).round() as i32,
+        width: width.round() as i32,
+        height: height.round() as i32,
+    };
+    match layout {
+        QuickMenuLayout::List => {
+            let top = center_y - (count as f64 * (height + gap) - gap) / 2.0 + height / 2.0;
+            (0..count)
+                .map(|i| rect(center_x, top + i as f64 * (height + gap)))
+                .collect()
+        }
+        QuickMenuLayout::Radial if count == 1 => vec![rect(center_x, center_y)],
+        QuickMenuLayout::Radial => {
+            let radius = monitor.native_length(RING_RADIUS);
+            (0..count)
+                .map(|i| {
+                    let angle = TAU * i as f64 / count as f64;
+                    rect(
+                        center_x + radius * angle.sin(),
+                        center_y - radius * angle.cos(),
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+fn validate(config: &QuickMenuConfig) -> Result<(), String> {
+    if config.items.is_empty() {
+        return Err("The quick menu needs at least one item".to_string());
+    }
+    if config.items.len() > MAX_ITEMS {
+        return Err(format!("The quick menu holds at most {} items", MAX_ITEMS));
+    }
+    for item in &config.items {
+        if item.label.trim().is_empty() {
+            return Err("Quick menu items need a label".to_string());
+        }
+        item.action.check()?;
+    }
+    Ok(())
+}
+
+/// Opens the menu, or closes it if it's open.
+pub fn toggle(app: &AppHandle) -> Result<(), String> {
+    if MENU.lock().unwrap().is_some() {
+        close();
+        return Ok(());
+    }
+    open(app)
+}
+
+/// The configured items, after those suggested for the focused window, e.g. "Cover
+/// this window" over a browser.
+fn items(config: &QuickMenuConfig) -> Vec<QuickMenuItem> {
+    let mut items: Vec<QuickMenuItem> = context_actions::suggestions()
+        .actions
+        .into_iter()
+        .map(|suggested| QuickMenuItem {
+            label: suggested.label,
+            action: Action::Command {
+                command: suggested.id,
+                args: suggested.args,
+            },
+        })
+        .collect();
+
+    items.extend(config.items.iter().cloned());
+    items.truncate(MAX_ITEMS);
+    items
+}
+
+fn open(app: &AppHandle) -> Result<(), String> {
+    let config = CONFIG.lock().unwrap().clone();
+    let items = items(&config);
+    let monitors = coords::monitors();
+    let monitor = monitors
+        .iter()
+        .find(|m| m.primary)
+        .ok_or_else(|| "Monitor not found".to_string())?;
+    let count = items.len();
+    let rects = layout(config.layout, count, monitor);
+    let style = HudStyle {
+        font_size: FONT_SIZE,
+        ..HudStyle::default()
+    };
+
+    let mut huds = Vec::new();
+    for (i, (item, rect)) in items.iter().zip(&rects).enumerate() {
+        match hud::open(*rect, &format!("{}  {}", i + 1, item.label), &style, true) {
+            Ok(id) => huds.push(id),
+            Err(e) => {
+                huds.into_iter().for_each(hud::close);
+                return Err(format!("Failed to open the quick menu: {}", e));
+            }
+        }
+    }
+    for id in huds.iter().skip(1) {
+        let _ = hud::set_fade(*id, DIMMED);
+    }
+    *MENU.lock().unwrap() = Some(Menu {
+        app: app.clone(),
+        items,
+        huds,
+        rects,
+        selected: 0,
+    });
+
+    let digits: Vec<String> = (1..=count).map(|n| n.to_string()).collect();
+    let keys: Vec<&str> = NAVIGATION_KEYS
+        .iter()
+        .copied()
+        .chain(digits.iter().map(String::as_str))
+        .collect();
+    if let Err(e) = hotkeys::grab(&keys, on_key) {
+        log::warn!("quick menu keys unavailable: {}", e);
+    }
+    if let Err(e) = platform::start() {
+        log::warn!("quick menu mouse unavailable: {}", e);
+    }
+    Ok(())
+}
+
+fn close() {
+    let Some(menu) = MENU.lock().unwrap().take() else {
+        return;
+    };
+    platform::stop();
+    hotkeys::release();
+    for id in menu.huds {
+        hud::close(id);
+    }
+}
+
+fn select(index: usize) {
+    let mut menu = MENU.lock().unwrap();
+    let Some(menu) = menu.as_mut().filter(|m| index < m.huds.len()) else {
+        return;
+    };
+    if index == menu.selected {
+        return;
+    }
+    let _ = hud::set_fade(menu.huds[menu.selected], DIMMED);
+    let _ = hud::set_fade(menu.huds[index], 1.0);
+    menu.selected = index;
+}
+
+/// Closes the menu and runs item `index`.
+fn choose(index: usize) {
+    let chosen = MENU
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|menu| Some((menu.app.clone(), menu.items.get(index)?.action.clone())));
+    let Some((app, action)) = chosen else {
+        return;
+    };
+    close();
+    crate::actions::run(&app, &action, "quick-menu");
+}
+
+fn on_key(_app: &AppHandle, key: &str) {
+    let Some((selected, count)) = MENU
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|menu| (menu.selected, menu.items.len()))
+    else {
+        return;
+    };
+    match key {
+        "Escape" => close(),
+        "Enter" => choose(selected),
+        "Up" | "Left" => select((selected + count - 1) % count),
+        "Down" | "Right" | "Tab" => select((selected + 1) % count),
+        digit => {
+            if let Some(n) = digit
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (1..=count).contains(n))
+            {
+                choose(n - 1);
+            }
+        }
+    }
+}
+
+fn item_at(menu: &Menu, x: i32, y: i32) -> Option<usize> {
+    menu.rects
+        .iter()
+        .position(|r| x >= r.x && y >= r.y && x < r.x + r.width && y < r.y + r.height)
+}
+
+/// Called by the mouse hook with the native pointer position.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn moved(x: i32, y: i32) {
+    let menu = MENU.lock().unwrap();
+    let Some(menu) = menu.as_ref() else {
+        return;
+    };
+    if let Some(index) = item_at(menu, x, y).filter(|i| *i != menu.selected) {
+        let _ = menu.app.run_on_main_thread(move || select(index));
+    }
+}
+
+/// Called by the mouse hook when the left button goes down. Returns whether the
+/// press was on an item and should be swallowed.
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+fn pressed(x: i32, y: i32) -> bool {
+    let menu = MENU.lock().unwrap();
+    let Some(menu) = menu.as_ref() else {
+        return false;
+    };
+    match item_at(menu, x, y) {
+        Some(index) => {
+            let _ = menu.app.run_on_main_thread(move || choose(index));
+            true
+        }
+        None => {
+            let _ = menu.app.run_on_main_thread(close);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicU32, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+        HHOOK, LLMHF_INJECTED, MSG, MSLLHOOKSTRUCT, WH_MOUSE_LL, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MOUSEMOVE, WM_QUIT,
+    };
+
+    use super::{moved, pressed};
+
+    static THREAD_ID: AtomicU32 = AtomicU32::new(0);
+    /// Set when a press was swallowed, so its release is swallowed too.
+    static SWALLOWED: AtomicBool = AtomicBool::new(false);
+
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code >= 0 {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let (x, y) = (info.pt.x, info.pt.y);
+            if info.flags & LLMHF_INJECTED == 0 {
+                match wparam.0 as u32 {
+                    WM_MOUSEMOVE => moved(x, y),
+                    WM_LBUTTONDOWN if pressed(x, y) => {
+                        SWALLOWED.store(true, Ordering::SeqCst);
+                        return LRESULT(1);
+                    }
+                    WM_LBUTTONUP if SWALLOWED.swap(false, Ordering::SeqCst) => {
+                        return LRESULT(1);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("quick-menu".to_string())
+            .spawn(move || unsafe {
+                THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+                let hook = GetModuleHandleW(PCWSTR::null())
+                    .map_err(|e| e.to_string())
+                    .and_then(|module| {
+                        SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), HINSTANCE(module.0), 0)
+                            .map_err(|e| format!("Failed to install mouse hook: {}", e))
+                    });
+                let hook = match hook {
+                    Ok(hook) => hook,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {}
+
+                let _ = UnhookWindowsHookEx(hook);
+            })
+            .map_err(|e| format!("Failed to spawn quick menu hook: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Quick menu hook thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        unsafe {
+            let _ = PostThreadMessageW(
+                THREAD_ID.load(Ordering::SeqCst),
+                WM_QUIT,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use super::{moved, pressed};
+
+    type CFTypeRef = *mut c_void;
+    type TapCallback =
+        extern "C" fn(proxy: CFTypeRef, kind: u32, event: CFTypeRef, user: CFTypeRef) -> CFTypeRef;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT: u32 = 0;
+    const DEFAULT_OPTIONS: u32 = 0;
+
+    const LEFT_DOWN: u32 = 1;
+    const LEFT_UP: u32 = 2;
+    const MOUSE_MOVED: u32 = 5;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            mask: u64,
+            callback: TapCallback,
+            user: CFTypeRef,
+        ) -> CFTypeRef;
+        fn CGEventTapEnable(tap: CFTypeRef, enable: bool);
+        fn CGEventGetLocation(event: CFTypeRef) -> CGPoint;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: CFTypeRef;
+        fn CFMachPortCreateRunLoopSource(
+            alloc: CFTypeRef,
+            port: CFTypeRef,
+            order: isize,
+        ) -> CFTypeRef;
+        fn CFRunLoopGetCurrent() -> CFTypeRef;
+        fn CFRunLoopAddSource(run_loop: CFTypeRef, source: CFTypeRef, mode: CFTypeRef);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: CFTypeRef);
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    static RUN_LOOP: AtomicUsize = AtomicUsize::new(0);
+    static TAP: AtomicUsize = AtomicUsize::new(0);
+    /// Set when a press was swallowed, so its release is swallowed too.
+    static SWALLOWED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn callback(
+        _proxy: CFTypeRef,
+        kind: u32,
+        event: CFTypeRef,
+        _user: CFTypeRef,
+    ) -> CFTypeRef {
+        unsafe {
+            if kind == TAP_DISABLED_BY_TIMEOUT {
+                CGEventTapEnable(TAP.load(Ordering::SeqCst) as CFTypeRef, true);
+                return event;
+            }
+            let location = CGEventGetLocation(event);
+            let (x, y) = (location.x.round() as i32, location.y.round() as i32);
+            match kind {
+                MOUSE_MOVED => {
+                    moved(x, y);
+                    event
+                }
+                LEFT_DOWN if pressed(x, y) => {
+                    SWALLOWED.store(true, Ordering::SeqCst);
+                    std::ptr::null_mut()
+                }
+                LEFT_UP if SWALLOWED.swap(false, Ordering::SeqCst) => std::ptr::null_mut(),
+                _ => event,
+            }
+        }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("quick-menu".to_string())
+            .spawn(move || unsafe {
+                let mask = (1u64 << LEFT_DOWN) | (1 << LEFT_UP) | (1 << MOUSE_MOVED);
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    DEFAULT_OPTIONS,
+                    mask,
+                    callback,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = ready_tx.send(Err(
+                        "Failed to create event tap (grant Accessibility permission)".to_string(),
+                    ));
+                    return;
+                }
+                TAP.store(tap as usize, Ordering::SeqCst);
+
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null_mut(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                RUN_LOOP.store(run_loop as usize, Ordering::SeqCst);
+                let _ = ready_tx.send(Ok(()));
+
+                CFRunLoopRun();
+
+                CGEventTapEnable(tap, false);
+                CFRelease(source);
+                CFRelease(tap);
+            })
+            .map_err(|e| format!("Failed to spawn quick menu tap: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Quick menu tap thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        let run_loop = RUN_LOOP.swap(0, Ordering::SeqCst);
+        if run_loop != 0 {
+            unsafe { CFRunLoopStop(run_loop as CFTypeRef) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    pub fn start() -> Result<(), String> {
+        Err("The quick menu's mouse support is only available on macOS and Windows".to_string())
+    }
+
+    pub fn stop() {}
+}
+
+// ==========================================
+// QUICK MENU COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_quick_menu() -> QuickMenuConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// Sets the menu's items, at most nine, and layout. An open menu keeps its items
+/// until it's next opened.
+#[tauri::command]
+pub fn set_quick_menu(config: QuickMenuConfig) -> Result<(), String> {
+    validate(&config)?;
+    *CONFIG.lock().unwrap() = config;
+    Ok(())
+}
+
+/// Opens the quick menu, or closes it if it's open. Bind the `quick-menu` action to a
+/// hotkey to summon it without the main window.
+#[tauri::command]
+pub fn toggle_quick_menu(app: AppHandle) -> Result<(), String> {
+    toggle(&app)
+}
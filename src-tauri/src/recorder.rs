@@ -0,0 +1,935 @@
+use std::{sync::Mutex, time::Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{
+    app_window::WindowInfo,
+    coords::{self, Position},
+    crash,
+    injection::{InjectionProfile, InputEvent, InputJob},
+    scripts::{self, Script, Step},
+    sessions::{self, Resource},
+};
+
+// ==========================================
+// Demonstration Recording
+// ==========================================
+
+// Platform hooks capture raw button and key events from the user (skipping injected
+// events where the platform marks them), and `stop` turns them into editable steps.
+
+/// Gaps shorter than this between steps are treated as human latency, not intent.
+const WAIT_THRESHOLD_MS: u64 = 300;
+/// A pause this long while typing starts a new text step.
+const TYPING_GAP_MS: u64 = 1000;
+/// Max movement, in native units, between press and release to count as a click.
+const CLICK_SLOP: i32 = 4;
+/// Starts the description of every recorded script.
+const DESCRIPTION: &str = "Recorded demonstration";
+
+#[derive(Clone, Debug)]
+enum RawKind {
+    ButtonDown {
+        button: &'static str,
+        x: i32,
+        y: i32,
+    },
+    ButtonUp {
+        button: &'static str,
+        x: i32,
+        y: i32,
+    },
+    Char(char),
+    Backspace,
+}
+
+#[derive(Clone, Debug)]
+struct RawEvent {
+    at_ms: u64,
+    kind: RawKind,
+    window: Option<WindowInfo>,
+}
+
+struct Recording {
+    started: Instant,
+    events: Vec<RawEvent>,
+}
+
+static RECORDING: Lazy<Mutex<Option<Recording>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Serialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    pub events: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Whether the next key event begins a new run of typing, which needs its window.
+fn typing_starts() -> bool {
+    let Ok(recording) = RECORDING.lock() else {
+        return false;
+    };
+    recording.as_ref().is_some_and(|recording| {
+        !matches!(
+            recording.events.last().map(|e| &e.kind),
+            Some(RawKind::Char(_) | RawKind::Backspace)
+        )
+    })
+}
+
+/// Called from the platform hooks.
+fn push(kind: RawKind, window: Option<WindowInfo>) {
+    let Ok(mut recording) = RECORDING.lock() else {
+        return;
+    };
+    if let Some(recording) = recording.as_mut() {
+        recording.events.push(RawEvent {
+            at_ms: recording.started.elapsed().as_millis() as u64,
+            kind,
+            window,
+        });
+    }
+}
+
+pub fn start() -> Result<(), String> {
+    {
+        let mut recording = RECORDING.lock().unwrap();
+        if recording.is_some() {
+            return Err("A recording is already in progress".to_string());
+        }
+        *recording = Some(Recording {
+            started: Instant::now(),
+            events: Vec::new(),
+        });
+    }
+
+    crash::breadcrumb("recorder", "start");
+    if let Err(e) = platform::start() {
+        *RECORDING.lock().unwrap() = None;
+        return Err(e);
+    }
+    sessions::track(Resource::Recording);
+    Ok(())
+}
+
+/// Discards the recording in progress, if any.
+pub fn cancel() {
+    if RECORDING.lock().unwrap().take().is_some() {
+        platform::stop();
+        sessions::untrack(|r| *r == Resource::Recording);
+        crash::breadcrumb("recorder", "cancel");
+    }
+}
+
+pub fn stop(id: &str, name: &str) -> Result<Script, String> {
+    let recording = RECORDING
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or("No recording is in progress")?;
+    platform::stop();
+    sessions::untrack(|r| *r == Resource::Recording);
+    crash::breadcrumb(
+        "recorder",
+        format!("stop with {} events", recording.events.len()),
+    );
+
+    let script = Script {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: format!("{} ({} raw events)", DESCRIPTION, recording.events.len()),
+        steps: build_steps(&recording.events),
+    };
+    scripts::save(&script)?;
+    Ok(script)
+}
+
+/// Whether `script` was recorded from a demonstration, rather than written.
+pub fn is_recorded(script: &Script) -> bool {
+    script.description.starts_with(DESCRIPTION)
+}
+
+pub fn status() -> RecordingStatus {
+    match RECORDING.lock().unwrap().as_ref() {
+        Some(recording) => RecordingStatus {
+            recording: true,
+            events: recording.events.len(),
+            elapsed_ms: recording.started.elapsed().as_millis() as u64,
+        },
+        None => RecordingStatus {
+            recording: false,
+            events: 0,
+            elapsed_ms: 0,
+        },
+    }
+}
+
+// ==========================================
+// POST-PROCESSING
+// ==========================================
+
+fn input_step(events: Vec<InputEvent>, target_window: Option<WindowInfo>) -> Step {
+    Step::Input {
+        job: InputJob {
+            events,
+            profile: InjectionProfile::Standard,
+            inter_event_us: None,
+            align_to_vsync: false,
+            locks: Vec::new(),
+        },
+        target_window,
+    }
+}
+
+fn point(x: i32, y: i32) -> InputEvent {
+    InputEvent::MouseMove {
+        target: Position::Absolute {
+            x: x as f64,
+            y: y as f64,
+            space: coords::native_space(),
+            monitor: None,
+        },
+    }
+}
+
+fn push_wait(steps: &mut Vec<Step>, gap_ms: u64) {
+    if gap_ms >= WAIT_THRESHOLD_MS {
+        // Recorded waits are rounded; exact human timing is noise.
+        steps.push(Step::Wait {
+            ms: gap_ms.div_ceil(50) * 50,
+        });
+    }
+}
+
+fn flush_text(steps: &mut Vec<Step>, text: &mut String, window: &mut Option<WindowInfo>) {
+    if !text.is_empty() {
+        steps.push(input_step(
+            vec![InputEvent::KeyPress {
+                text: std::mem::take(text),
+            }],
+            window.take(),
+        ));
+    }
+}
+
+/// Turns raw events into clicks, drags, typed strings and waits.
+fn build_steps(events: &[RawEvent]) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut text = String::new();
+    let mut text_window = None;
+    let mut pending_down: Option<&RawEvent> = None;
+    let mut last_ms = 0u64;
+
+    for event in events {
+        let gap = event.at_ms.saturating_sub(last_ms);
+        last_ms = event.at_ms;
+
+        match &event.kind {
+            RawKind::Char(_) | RawKind::Backspace => {
+                if !text.is_empty() && gap >= TYPING_GAP_MS {
+                    flush_text(&mut steps, &mut text, &mut text_window);
+                }
+                if text.is_empty() {
+                    push_wait(&mut steps, gap);
+                    text_window = event.window.clone();
+                }
+                match event.kind {
+                    RawKind::Char(ch) => text.push(ch),
+                    // Corrections before the text is flushed are simply edited out.
+                    _ => {
+                        text.pop();
+                    }
+                }
+            }
+            RawKind::ButtonDown { .. } => {
+                flush_text(&mut steps, &mut text, &mut text_window);
+                push_wait(&mut steps, gap);
+                pending_down = Some(event);
+            }
+            RawKind::ButtonUp { button, x, y } => {
+                let Some(down) = pending_down.take() else {
+                    continue;
+                };
+                let RawKind::ButtonDown {
+                    button: down_button,
+                    x: down_x,
+                    y: down_y,
+                } = down.kind
+                else {
+                    continue;
+                };
+                if down_button != *button {
+                    continue;
+                }
+
+                let button = button.to_string();
+                let moved = (x - down_x).abs() > CLICK_SLOP || (y - down_y).abs() > CLICK_SLOP;
+                let events = if moved {
+                    vec![
+                        point(down_x, down_y),
+                        InputEvent::MouseDown {
+                            button: button.clone(),
+                        },
+                        point(*x, *y),
+                        InputEvent::MouseUp { button },
+                    ]
+                } else {
+                    vec![
+                        point(down_x, down_y),
+                        InputEvent::MouseClick { button, at: None },
+                    ]
+                };
+                steps.push(input_step(events, down.window.clone()));
+            }
+        }
+    }
+
+    flush_text(&mut steps, &mut text, &mut text_window);
+    steps
+}
+
+// ==========================================
+// PLATFORM HOOKS
+// ==========================================
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::Threading::GetCurrentThreadId;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, GetKeyState, ToUnicode, VK_BACK, VK_CAPITAL, VK_CONTROL, VK_LWIN,
+        VK_MENU, VK_RETURN, VK_RWIN, VK_SHIFT, VK_TAB,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, PostThreadMessageW, SetWindowsHookExW, UnhookWindowsHookEx,
+        HHOOK, KBDLLHOOKSTRUCT, LLKHF_INJECTED, LLMHF_INJECTED, MSG, MSLLHOOKSTRUCT,
+        WH_KEYBOARD_LL, WH_MOUSE_LL, WM_APP, WM_KEYDOWN, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MBUTTONDOWN, WM_MBUTTONUP, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN,
+    };
+
+    use super::{push, typing_starts, RawKind, RECORDING};
+    use crate::app_window;
+
+    /// Posted by the mouse hook; window lookups send messages to other processes,
+    /// so they run from the message loop instead of inside the hook.
+    const WM_RESOLVE_WINDOW: u32 = WM_APP + 1;
+
+    static THREAD_ID: AtomicU32 = AtomicU32::new(0);
+
+    fn held(vk: u16) -> bool {
+        unsafe { GetAsyncKeyState(vk as i32) < 0 }
+    }
+
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code >= 0 {
+            let info = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let (x, y) = (info.pt.x, info.pt.y);
+            let kind = match wparam.0 as u32 {
+                WM_LBUTTONDOWN => Some(RawKind::ButtonDown {
+                    button: "left",
+                    x,
+                    y,
+                }),
+                WM_LBUTTONUP => Some(RawKind::ButtonUp {
+                    button: "left",
+                    x,
+                    y,
+                }),
+                WM_RBUTTONDOWN => Some(RawKind::ButtonDown {
+                    button: "right",
+                    x,
+                    y,
+                }),
+                WM_RBUTTONUP => Some(RawKind::ButtonUp {
+                    button: "right",
+                    x,
+                    y,
+                }),
+                WM_MBUTTONDOWN => Some(RawKind::ButtonDown {
+                    button: "middle",
+                    x,
+                    y,
+                }),
+                WM_MBUTTONUP => Some(RawKind::ButtonUp {
+                    button: "middle",
+                    x,
+                    y,
+                }),
+                _ => None,
+            };
+
+            if let Some(kind) = kind.filter(|_| info.flags & LLMHF_INJECTED == 0) {
+                let is_down = matches!(kind, RawKind::ButtonDown { .. });
+                push(kind, None);
+                if is_down {
+                    let _ = PostThreadMessageW(
+                        THREAD_ID.load(Ordering::SeqCst),
+                        WM_RESOLVE_WINDOW,
+                        WPARAM(0),
+                        LPARAM(0),
+                    );
+                }
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        let down = matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN);
+        if code >= 0 && down {
+            let info = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            // Shortcuts are not text; they are left out of typed strings.
+            let shortcut =
+                held(VK_CONTROL.0) || held(VK_MENU.0) || held(VK_LWIN.0) || held(VK_RWIN.0);
+
+            if info.flags.0 & LLKHF_INJECTED.0 == 0 && !shortcut {
+                if typing_starts() {
+                    let _ = PostThreadMessageW(
+                        THREAD_ID.load(Ordering::SeqCst),
+                        WM_RESOLVE_WINDOW,
+                        WPARAM(0),
+                        LPARAM(0),
+                    );
+                }
+                match info.vkCode as u16 {
+                    vk if vk == VK_BACK.0 => push(RawKind::Backspace, None),
+                    vk if vk == VK_RETURN.0 => push(RawKind::Char('\n'), None),
+                    vk if vk == VK_TAB.0 => push(RawKind::Char('\t'), None),
+                    vk => {
+                        let mut state = [0u8; 256];
+                        if held(VK_SHIFT.0) {
+                            state[VK_SHIFT.0 as usize] = 0x80;
+                        }
+                        state[VK_CAPITAL.0 as usize] = (GetKeyState(VK_CAPITAL.0 as i32) & 1) as u8;
+
+                        // Flag 4: do not change the keyboard's dead-key state.
+                        let mut buffer = [0u16; 8];
+                        let len = ToUnicode(vk as u32, info.scanCode, Some(&state), &mut buffer, 4);
+                        if len > 0 {
+                            for ch in char::decode_utf16(buffer[..len as usize].iter().copied())
+                                .flatten()
+                                .filter(|ch| !ch.is_control())
+                            {
+                                push(RawKind::Char(ch), None);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+    }
+
+    /// Clicks resolve to the window under the cursor; the first key of a typing run
+    /// resolves to the focused window.
+    fn resolve_windows() {
+        let pending: Vec<(usize, Option<(i32, i32)>)> = {
+            let recording = RECORDING.lock().unwrap();
+            let Some(recording) = recording.as_ref() else {
+                return;
+            };
+            recording
+                .events
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.window.is_none())
+                .filter_map(|(i, e)| match e.kind {
+                    RawKind::ButtonDown { x, y, .. } => Some((i, Some((x, y)))),
+                    RawKind::Char(_) | RawKind::Backspace
+                        if i == 0
+                            || !matches!(
+                                recording.events[i - 1].kind,
+                                RawKind::Char(_) | RawKind::Backspace
+                            ) =>
+                    {
+                        Some((i, None))
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        for (index, point) in pending {
+            let window = match point {
+                Some((x, y)) => app_window::window_at(x, y),
+                None => app_window::focused_window(),
+            };
+            if let Some(recording) = RECORDING.lock().unwrap().as_mut() {
+                if let Some(event) = recording.events.get_mut(index) {
+                    event.window = window;
+                }
+            }
+        }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("input-recorder".to_string())
+            .spawn(move || unsafe {
+                THREAD_ID.store(GetCurrentThreadId(), Ordering::SeqCst);
+                let module = match GetModuleHandleW(PCWSTR::null()) {
+                    Ok(module) => HINSTANCE(module.0),
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+
+                let mouse = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), module, 0);
+                let keyboard = SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), module, 0);
+                let (mouse, keyboard) = match (mouse, keyboard) {
+                    (Ok(mouse), Ok(keyboard)) => (mouse, keyboard),
+                    (mouse, keyboard) => {
+                        if let Ok(hook) = mouse {
+                            let _ = UnhookWindowsHookEx(hook);
+                        }
+                        if let Ok(hook) = keyboard {
+                            let _ = UnhookWindowsHookEx(hook);
+                        }
+                        let _ = ready_tx.send(Err("Failed to install input hooks".to_string()));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                // Low-level hooks are called from this thread's message loop.
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).0 > 0 {
+                    if msg.message == WM_RESOLVE_WINDOW {
+                        resolve_windows();
+                    }
+                }
+
+                resolve_windows();
+                let _ = UnhookWindowsHookEx(mouse);
+                let _ = UnhookWindowsHookEx(keyboard);
+            })
+            .map_err(|e| format!("Failed to spawn recorder: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Recorder thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        unsafe {
+            let _ = PostThreadMessageW(
+                THREAD_ID.load(Ordering::SeqCst),
+                WM_QUIT,
+                WPARAM(0),
+                LPARAM(0),
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use super::{push, typing_starts, RawKind};
+    use crate::app_window;
+
+    type CFTypeRef = *mut c_void;
+    type TapCallback =
+        extern "C" fn(proxy: CFTypeRef, kind: u32, event: CFTypeRef, user: CFTypeRef) -> CFTypeRef;
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    const SESSION_EVENT_TAP: u32 = 1;
+    const HEAD_INSERT: u32 = 0;
+    const LISTEN_ONLY: u32 = 1;
+
+    const LEFT_DOWN: u32 = 1;
+    const LEFT_UP: u32 = 2;
+    const RIGHT_DOWN: u32 = 3;
+    const RIGHT_UP: u32 = 4;
+    const KEY_DOWN: u32 = 10;
+    const OTHER_DOWN: u32 = 25;
+    const OTHER_UP: u32 = 26;
+    const TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+
+    const FIELD_KEYCODE: u32 = 9;
+    const FIELD_SOURCE_PID: u32 = 41;
+    const FLAGS_SHORTCUT: u64 = (1 << 18) | (1 << 20); // control | command
+
+    const KEYCODE_RETURN: i64 = 36;
+    const KEYCODE_TAB: i64 = 48;
+    const KEYCODE_DELETE: i64 = 51;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventTapCreate(
+            tap: u32,
+            place: u32,
+            options: u32,
+            mask: u64,
+            callback: TapCallback,
+            user: CFTypeRef,
+        ) -> CFTypeRef;
+        fn CGEventTapEnable(tap: CFTypeRef, enable: bool);
+        fn CGEventGetLocation(event: CFTypeRef) -> CGPoint;
+        fn CGEventGetIntegerValueField(event: CFTypeRef, field: u32) -> i64;
+        fn CGEventGetFlags(event: CFTypeRef) -> u64;
+        fn CGEventKeyboardGetUnicodeString(
+            event: CFTypeRef,
+            max: usize,
+            actual: *mut usize,
+            buffer: *mut u16,
+        );
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopCommonModes: CFTypeRef;
+        fn CFMachPortCreateRunLoopSource(
+            alloc: CFTypeRef,
+            port: CFTypeRef,
+            order: isize,
+        ) -> CFTypeRef;
+        fn CFRunLoopGetCurrent() -> CFTypeRef;
+        fn CFRunLoopAddSource(run_loop: CFTypeRef, source: CFTypeRef, mode: CFTypeRef);
+        fn CFRunLoopRun();
+        fn CFRunLoopStop(run_loop: CFTypeRef);
+        fn CFRelease(object: CFTypeRef);
+    }
+
+    static RUN_LOOP: AtomicUsize = AtomicUsize::new(0);
+    static TAP: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn callback(
+        _proxy: CFTypeRef,
+        kind: u32,
+        event: CFTypeRef,
+        _user: CFTypeRef,
+    ) -> CFTypeRef {
+        unsafe {
+            if kind == TAP_DISABLED_BY_TIMEOUT {
+                CGEventTapEnable(TAP.load(Ordering::SeqCst) as CFTypeRef, true);
+                return event;
+            }
+            if CGEventGetIntegerValueField(event, FIELD_SOURCE_PID) == std::process::id() as i64 {
+                return event;
+            }
+
+            let location = CGEventGetLocation(event);
+            let (x, y) = (location.x.round() as i32, location.y.round() as i32);
+            let button = match kind {
+                LEFT_DOWN | LEFT_UP => "left",
+                RIGHT_DOWN | RIGHT_UP => "right",
+                _ => "middle",
+            };
+
+            match kind {
+                LEFT_DOWN | RIGHT_DOWN | OTHER_DOWN => push(
+                    RawKind::ButtonDown { button, x, y },
+                    app_window::window_at(x, y),
+                ),
+                LEFT_UP | RIGHT_UP | OTHER_UP => push(RawKind::ButtonUp { button, x, y }, None),
+                KEY_DOWN if CGEventGetFlags(event) & FLAGS_SHORTCUT == 0 => {
+                    let mut window = if typing_starts() {
+                        app_window::focused_window()
+                    } else {
+                        None
+                    };
+                    match CGEventGetIntegerValueField(event, FIELD_KEYCODE) {
+                        KEYCODE_DELETE => push(RawKind::Backspace, window),
+                        KEYCODE_RETURN => push(RawKind::Char('\n'), window),
+                        KEYCODE_TAB => push(RawKind::Char('\t'), window),
+                        _ => {
+                            let mut buffer = [0u16; 8];
+                            let mut len = 0usize;
+                            CGEventKeyboardGetUnicodeString(
+                                event,
+                                buffer.len(),
+                                &mut len,
+                                buffer.as_mut_ptr(),
+                            );
+                            for ch in char::decode_utf16(buffer[..len.min(8)].iter().copied())
+                                .flatten()
+                                .filter(|ch| !ch.is_control())
+                            {
+                                push(RawKind::Char(ch), window.take());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        event
+    }
+
+    pub fn start() -> Result<(), String> {
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("input-recorder".to_string())
+            .spawn(move || unsafe {
+                let mask = [
+                    LEFT_DOWN, LEFT_UP, RIGHT_DOWN, RIGHT_UP, KEY_DOWN, OTHER_DOWN, OTHER_UP,
+                ]
+                .iter()
+                .fold(0u64, |mask, kind| mask | (1 << kind));
+                let tap = CGEventTapCreate(
+                    SESSION_EVENT_TAP,
+                    HEAD_INSERT,
+                    LISTEN_ONLY,
+                    mask,
+                    callback,
+                    std::ptr::null_mut(),
+                );
+                if tap.is_null() {
+                    let _ = ready_tx.send(Err(
+                        "Failed to create event tap (grant Input Monitoring permission)"
+                            .to_string(),
+                    ));
+                    return;
+                }
+                TAP.store(tap as usize, Ordering::SeqCst);
+
+                let source = CFMachPortCreateRunLoopSource(std::ptr::null_mut(), tap, 0);
+                let run_loop = CFRunLoopGetCurrent();
+                CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                CGEventTapEnable(tap, true);
+                RUN_LOOP.store(run_loop as usize, Ordering::SeqCst);
+                let _ = ready_tx.send(Ok(()));
+
+                CFRunLoopRun();
+
+                CGEventTapEnable(tap, false);
+                RUN_LOOP.store(0, Ordering::SeqCst);
+                CFRelease(source);
+                CFRelease(tap);
+            })
+            .map_err(|e| format!("Failed to spawn recorder: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Recorder thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        let run_loop = RUN_LOOP.load(Ordering::SeqCst);
+        if run_loop != 0 {
+            unsafe { CFRunLoopStop(run_loop as CFTypeRef) };
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+        time::Duration,
+    };
+
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, KeyButMask};
+
+    use super::{push, typing_starts, RawKind};
+    use crate::app_window;
+
+    // X11 has no listen-only global hook in the core protocol, so pointer buttons and
+    // the keymap are polled. Injected input cannot be told apart here.
+    const POLL_INTERVAL: Duration = Duration::from_millis(8);
+
+    const KEYSYM_BACKSPACE: u32 = 0xff08;
+    const KEYSYM_TAB: u32 = 0xff09;
+    const KEYSYM_RETURN: u32 = 0xff0d;
+
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+    const BUTTONS: [(KeyButMask, &str); 3] = [
+        (KeyButMask::BUTTON1, "left"),
+        (KeyButMask::BUTTON2, "middle"),
+        (KeyButMask::BUTTON3, "right"),
+    ];
+
+    fn keysym_char(keysym: u32) -> Option<char> {
+        match keysym {
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+            0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+            _ => None,
+        }
+    }
+
+    pub fn start() -> Result<(), String> {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("input-recorder".to_string())
+            .spawn(move || {
+                let setup =
+                    x11rb::connect(None)
+                        .map_err(|e| e.to_string())
+                        .and_then(|(conn, screen)| {
+                            let root = conn.setup().roots[screen].root;
+                            let (min, max) = (conn.setup().min_keycode, conn.setup().max_keycode);
+                            let mapping = conn
+                                .get_keyboard_mapping(min, max - min + 1)
+                                .map_err(|e| e.to_string())?
+                                .reply()
+                                .map_err(|e| e.to_string())?;
+                            Ok((conn, root, min, mapping))
+                        });
+                let (conn, root, min_keycode, mapping) = match setup {
+                    Ok(setup) => setup,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("Failed to connect to X server: {}", e)));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+
+                let per_keycode = mapping.keysyms_per_keycode as usize;
+                let mut buttons = 0u16;
+                let mut keys = [0u8; 32];
+
+                while GENERATION.load(Ordering::SeqCst) == generation {
+                    thread::sleep(POLL_INTERVAL);
+                    let Some(pointer) = conn.query_pointer(root).ok().and_then(|c| c.reply().ok())
+                    else {
+                        continue;
+                    };
+                    let Some(keymap) = conn.query_keymap().ok().and_then(|c| c.reply().ok()) else {
+                        continue;
+                    };
+                    let (x, y) = (pointer.root_x as i32, pointer.root_y as i32);
+                    let mask = u16::from(pointer.mask);
+
+                    for (bit, button) in BUTTONS {
+                        let bit = u16::from(bit);
+                        match (buttons & bit != 0, mask & bit != 0) {
+                            (false, true) => push(
+                                RawKind::ButtonDown { button, x, y },
+                                app_window::window_at(x, y),
+                            ),
+                            (true, false) => push(RawKind::ButtonUp { button, x, y }, None),
+                            _ => {}
+                        }
+                    }
+                    buttons = mask;
+
+                    // Shortcuts are not text; they are left out of typed strings.
+                    let shortcut =
+                        u16::from(KeyButMask::CONTROL | KeyButMask::MOD1 | KeyButMask::MOD4);
+                    let shift = mask & u16::from(KeyButMask::SHIFT) != 0;
+                    let caps = mask & u16::from(KeyButMask::LOCK) != 0;
+
+                    for keycode in 0..256usize {
+                        let pressed = keymap.keys[keycode / 8] & (1 << (keycode % 8)) != 0;
+                        let was_pressed = keys[keycode / 8] & (1 << (keycode % 8)) != 0;
+                        if !pressed
+                            || was_pressed
+                            || mask & shortcut != 0
+                            || keycode < min_keycode as usize
+                        {
+                            continue;
+                        }
+
+                        let base = (keycode - min_keycode as usize) * per_keycode;
+                        let keysym = |index: usize| {
+                            mapping
+                                .keysyms
+                                .get(base + index)
+                                .copied()
+                                .filter(|&k| k != 0)
+                        };
+                        let Some(plain) = keysym(0) else {
+                            continue;
+                        };
+                        let kind = match plain {
+                            KEYSYM_BACKSPACE => RawKind::Backspace,
+                            KEYSYM_RETURN => RawKind::Char('\n'),
+                            KEYSYM_TAB => RawKind::Char('\t'),
+                            _ => {
+                                let keysym = if shift {
+                                    keysym(1).unwrap_or(plain)
+                                } else {
+                                    plain
+                                };
+                                let Some(mut ch) = keysym_char(keysym) else {
+                                    continue;
+                                };
+                                if caps && ch.is_alphabetic() {
+                                    ch = if ch.is_lowercase() {
+                                        ch.to_uppercase().next().unwrap_or(ch)
+                                    } else {
+                                        ch.to_lowercase().next().unwrap_or(ch)
+                                    };
+                                }
+                                RawKind::Char(ch)
+                            }
+                        };
+
+                        let window = if typing_starts() {
+                            app_window::focused_window()
+                        } else {
+                            None
+                        };
+                        push(kind, window);
+                    }
+                    keys = keymap.keys;
+                }
+            })
+            .map_err(|e| format!("Failed to spawn recorder: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Recorder thread exited".to_string())?
+    }
+
+    pub fn stop() {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// ==========================================
+// RECORDING COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn start_recording() -> Result<(), String> {
+    start()
+}
+
+/// Stops recording and saves the demonstration as script `id`.
+#[tauri::command]
+pub fn stop_recording(id: String, name: String) -> Result<Script, String> {
+    stop(&id, &name)
+}
+
+#[tauri::command]
+pub fn get_recording_status() -> RecordingStatus {
+    status()
+}
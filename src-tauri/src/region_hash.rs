@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::imageops::{self, FilterType};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    capture,
+    coords::{self, Bounds, Rect},
+    crash, events,
+};
+
+// ==========================================
+// Region Hashing
+// ==========================================
+
+// A region's perceptual hash is a 64-bit difference hash: the region shrunk to 9×8
+// gray cells, one bit per pair of horizontal neighbors, set where the left cell is
+// brighter. Noise and compression leave it alone; real content changes flip bits.
+// Watched regions are hashed by one shared sampling thread through the capture
+// backend (the GPU one where available), so waits sleep until a hash moves instead
+// of re-running full comparisons on a timer. Frontend watches get `region://hash`
+// events.
+
+const HASH_EVENT: &str = "region://hash";
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RegionHash {
+    /// 16 hex digits; a JavaScript number can't hold all 64 bits.
+    pub hash: String,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RegionWatch {
+    pub id: u64,
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HashChange {
+    pub watch: u64,
+    pub hash: String,
+    /// Bits that differ from the previous hash.
+    pub distance: u32,
+}
+
+pub fn hash_rect(rect: Rect) -> Result<u64, String> {
+    let image = capture::capture_rect(rect)?;
+    let gray = imageops::grayscale(&imageops::resize(&image, 9, 8, FilterType::Triangle));
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if gray.get_pixel(x, y)[0] > gray.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+fn hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+struct Watch {
+    rect: Rect,
+    hash: u64,
+    /// Bumped on every hash change or sampling error.
+    serial: u64,
+    error: Option<String>,
+    /// Set for frontend watches, which are notified by event.
+    app: Option<AppHandle>,
+}
+
+#[derive(Default)]
+struct Watches {
+    next_id: u64,
+    watches: HashMap<u64, Watch>,
+    sampling: bool,
+}
+
+static WATCHES: Lazy<Mutex<Watches>> = Lazy::new(|| Mutex::new(Watches::default()));
+/// Signalled whenever a watched hash changes.
+static CHANGED: Condvar = Condvar::new();
+/// Watches opened by the frontend, closed with `unwatch_region_hash`.
+static FRONTEND: Lazy<Mutex<HashMap<u64, Watcher>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A subscription to one region's hash changes; unsubscribes when dropped.
+pub struct Watcher {
+    id: u64,
+    seen: u64,
+}
+
+impl Watcher {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Waits up to `timeout` for the hash to change since the last call and returns
+    /// the new hash, or `None` if it didn't.
+    pub fn wait_change(&mut self, timeout: Duration) -> Result<Option<u64>, String> {
+        let deadline = Instant::now() + timeout;
+        let mut watches = WATCHES.lock().unwrap();
+        loop {
+            let watch = watches
+                .watches
+                .get(&self.id)
+                .ok_or_else(|| "Region watch was closed".to_string())?;
+            if watch.serial != self.seen {
+                self.seen = watch.serial;
+                return match &watch.error {
+                    Some(e) => Err(e.clone()),
+                    None => Ok(Some(watch.hash)),
+                };
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            watches = CHANGED.wait_timeout(watches, remaining).unwrap().0;
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        WATCHES.lock().unwrap().watches.remove(&self.id);
+    }
+}
+
+/// Starts watching `rect`. The first hash is taken before this returns, so every
+/// change reported afterwards is relative to it.
+pub fn watch(rect: Rect) -> Result<Watcher, String> {
+    open(rect, None).map(|(watcher, _)| watcher)
+}
+
+fn open(rect: Rect, app: Option<AppHandle>) -> Result<(Watcher, u64), String> {
+    let hash = hash_rect(rect)?;
+    let mut watches = WATCHES.lock().unwrap();
+    watches.next_id += 1;
+    let id = watches.next_id;
+    watches.watches.insert(
+        id,
+        Watch {
+            rect,
+            hash,
+            serial: 0,
+            error: None,
+            app,
+        },
+    );
+    if !watches.sampling {
+        watches.sampling = true;
+        let spawned = thread::Builder::new()
+            .name("region-hash".to_string())
+            .spawn(sample);
+        if let Err(e) = spawned {
+            watches.sampling = false;
+            watches.watches.remove(&id);
+            return Err(format!("Failed to spawn region hashing: {}", e));
+        }
+    }
+    Ok((Watcher { id, seen: 0 }, hash))
+}
+
+/// Hashes every watched region until none are left. Regions watched more than once
+/// are captured once.
+fn sample() {
+    loop {
+        let rects: Vec<Rect> = {
+            let mut watches = WATCHES.lock().unwrap();
+            if watches.watches.is_empty() {
+                watches.sampling = false;
+                return;
+            }
+            let mut rects: Vec<Rect> = Vec::new();
+            for watch in watches.watches.values() {
+                if !rects.contains(&watch.rect) {
+                    rects.push(watch.rect);
+                }
+            }
+            rects
+        };
+
+        let results: Vec<(Rect, Result<u64, String>)> = rects
+            .into_iter()
+            .map(|rect| (rect, hash_rect(rect)))
+            .collect();
+
+        let mut watches = WATCHES.lock().unwrap();
+        let mut changed = false;
+        for (&id, watch) in watches.watches.iter_mut() {
+            let Some((_, result)) = results.iter().find(|(rect, _)| *rect == watch.rect) else {
+                continue;
+            };
+            match result {
+                Ok(hash) if *hash != watch.hash || watch.error.is_some() => {
+                    let distance = (*hash ^ watch.hash).count_ones();
+                    watch.hash = *hash;
+                    watch.error = None;
+                    watch.serial += 1;
+                    changed = true;
+                    if let Some(app) = &watch.app {
+                        events::emit(
+                            app,
+                            HASH_EVENT,
+                            HashChange {
+                                watch: id,
+                                hash: hex(*hash),
+                                distance,
+                            },
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(e) if watch.error.as_ref() != Some(e) => {
+                    watch.error = Some(e.clone());
+                    watch.serial += 1;
+                    changed = true;
+                }
+                Err(_) => {}
+            }
+        }
+        drop(watches);
+        if changed {
+            CHANGED.notify_all();
+        }
+        thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+// ==========================================
+// REGION HASH COMMANDS
+// ==========================================
+
+/// Perceptual hash of a screen region, for cheap "has it changed" checks.
+#[tauri::command]
+pub fn hash_region(bounds: Bounds) -> Result<RegionHash, String> {
+    let rect = coords::resolve_bounds(&bounds)?;
+    Ok(RegionHash {
+        hash: hex(hash_rect(rect)?),
+        rect,
+    })
+}
+
+/// Emits `region://hash` whenever the region's hash changes, until unwatched.
+#[tauri::command]
+pub fn watch_region_hash(app: AppHandle, bounds: Bounds) -> Result<RegionWatch, String> {
+    let rect = coords::resolve_bounds(&bounds)?;
+    let (watcher, hash) = open(rect, Some(app))?;
+    let id = watcher.id();
+    crash::breadcrumb("visual", format!("watch region hash {}", id));
+    FRONTEND.lock().unwrap().insert(id, watcher);
+    Ok(RegionWatch {
+        id,
+        hash: hex(hash),
+    })
+}
+
+#[tauri::command]
+pub fn unwatch_region_hash(id: u64) -> Result<(), String> {
+    FRONTEND
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .map(|_| ())
+        .ok_or_else(|| format!("Region watch {} not found", id))
+}
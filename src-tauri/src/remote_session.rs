@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    crash, events,
+    speech::{self, Announcement},
+    OverlayManager,
+};
+
+// ==========================================
+// Remote Session Awareness
+// ==========================================
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RemoteConnection {
+    pub session_id: u32,
+    pub protocol: String,
+    pub user: String,
+    pub client_name: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SessionInfo {
+    /// "console", "rdp", "citrix" or "screen-sharing".
+    pub protocol: String,
+    pub is_remote: bool,
+    pub session_id: Option<u32>,
+    /// Bounds of the (possibly virtual) desktop the session renders to.
+    pub virtual_screen: Option<(i32, i32, i32, i32)>,
+    /// Other sessions with a remote client attached to this machine.
+    pub remote_connections: Vec<RemoteConnection>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SessionPolicy {
+    pub auto_overlay_on_remote_connect: bool,
+    pub auto_destroy_on_remote_disconnect: bool,
+}
+
+static POLICY: Lazy<Mutex<SessionPolicy>> = Lazy::new(|| Mutex::new(SessionPolicy::default()));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Whether the watcher last saw any remote client attached.
+static REMOTE_CONNECTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::PWSTR;
+    use windows::Win32::System::RemoteDesktop::{
+        ProcessIdToSessionId, WTSActive, WTSClientName, WTSClientProtocolType,
+        WTSEnumerateSessionsW, WTSFreeMemory, WTSQuerySessionInformationW, WTSUserName,
+        WTS_CURRENT_SERVER_HANDLE, WTS_INFO_CLASS, WTS_SESSION_INFOW,
+    };
+    use windows::Win32::System::Threading::GetCurrentProcessId;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_REMOTESESSION,
+        SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+    };
+
+    use super::{RemoteConnection, SessionInfo};
+
+    pub fn current_session_id() -> u32 {
+        let mut session = 0u32;
+        unsafe {
+            let _ = ProcessIdToSessionId(GetCurrentProcessId(), &mut session);
+        }
+        session
+    }
+
+    unsafe fn query(session: u32, class: WTS_INFO_CLASS) -> Option<(PWSTR, u32)> {
+        let mut buffer = PWSTR::null();
+        let mut bytes = 0u32;
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session,
+            class,
+            &mut buffer,
+            &mut bytes,
+        )
+        .ok()?;
+        Some((buffer, bytes))
+    }
+
+    fn query_string(session: u32, class: WTS_INFO_CLASS) -> String {
+        unsafe {
+            let Some((buffer, _)) = query(session, class) else {
+                return String::new();
+            };
+            let value = buffer.to_string().unwrap_or_default();
+            WTSFreeMemory(buffer.0 as _);
+            value
+        }
+    }
+
+    /// WTSClientProtocolType: 0 = console, 1 = ICA (Citrix), 2 = RDP.
+    fn protocol(session: u32) -> &'static str {
+        unsafe {
+            let Some((buffer, bytes)) = query(session, WTSClientProtocolType) else {
+                return "console";
+            };
+            let kind = if bytes >= 2 {
+                *(buffer.0 as *const u16)
+            } else {
+                0
+            };
+            WTSFreeMemory(buffer.0 as _);
+            match kind {
+                1 => "citrix",
+                2 => "rdp",
+                _ => "console",
+            }
+        }
+    }
+
+    pub fn session_info() -> SessionInfo {
+        let current = current_session_id();
+        let own_protocol = protocol(current);
+        let is_remote =
+            unsafe { GetSystemMetrics(SM_REMOTESESSION) } != 0 || own_protocol != "console";
+
+        let virtual_screen = unsafe {
+            (
+                GetSystemMetrics(SM_XVIRTUALSCREEN),
+                GetSystemMetrics(SM_YVIRTUALSCREEN),
+                GetSystemMetrics(SM_CXVIRTUALSCREEN),
+                GetSystemMetrics(SM_CYVIRTUALSCREEN),
+            )
+        };
+
+        let mut remote_connections = Vec::new();
+        unsafe {
+            let mut sessions: *mut WTS_SESSION_INFOW = std::ptr::null_mut();
+            let mut count = 0u32;
+            if WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut sessions, &mut count)
+                .is_ok()
+            {
+                for info in std::slice::from_raw_parts(sessions, count as usize) {
+                    if info.SessionId == current || info.State != WTSActive {
+                        continue;
+                    }
+                    let protocol = protocol(info.SessionId);
+                    if protocol == "console" {
+                        continue;
+                    }
+                    remote_connections.push(RemoteConnection {
+                        session_id: info.SessionId,
+                        protocol: protocol.to_string(),
+                        user: query_string(info.SessionId, WTSUserName),
+                        client_name: query_string(info.SessionId, WTSClientName),
+                    });
+                }
+                WTSFreeMemory(sessions as _);
+            }
+        }
+
+        SessionInfo {
+            protocol: if is_remote && own_protocol == "console" {
+                "rdp"
+            } else {
+                own_protocol
+            }
+            .to_string(),
+            is_remote,
+            session_id: Some(current),
+            virtual_screen: Some(virtual_screen),
+            remote_connections,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{RemoteConnection, SessionInfo};
+
+    /// Screen Sharing / Apple Remote Desktop run `screensharingd` while a viewer is attached.
+    pub fn session_info() -> SessionInfo {
+        let viewing = std::process::Command::new("pgrep")
+            .args(["-x", "screensharingd"])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false);
+
+        let remote_connections = if viewing {
+            vec![RemoteConnection {
+                session_id: 0,
+                protocol: "screen-sharing".to_string(),
+                user: String::new(),
+                client_name: String::new(),
+            }]
+        } else {
+            Vec::new()
+        };
+
+        SessionInfo {
+            protocol: "console".to_string(),
+            is_remote: false,
+            session_id: None,
+            virtual_screen: None,
+            remote_connections,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::SessionInfo;
+
+    pub fn session_info() -> SessionInfo {
+        let is_remote = std::env::var_os("SSH_CONNECTION").is_some();
+        SessionInfo {
+            protocol: if is_remote { "ssh" } else { "console" }.to_string(),
+            is_remote,
+            session_id: None,
+            virtual_screen: None,
+            remote_connections: Vec::new(),
+        }
+    }
+}
+
+// ==========================================
+// WATCHER
+// ==========================================
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("session-watcher".to_string())
+        .spawn(move || {
+            let mut known: BTreeSet<RemoteConnection> = BTreeSet::new();
+
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                let current: BTreeSet<RemoteConnection> = platform::session_info()
+                    .remote_connections
+                    .into_iter()
+                    .collect();
+
+                for connection in current.difference(&known) {
+                    crash::breadcrumb("session", format!("remote connect {}", connection.protocol));
+                    events::emit(&app, "session://remote-connected", connection);
+                    speech::announce(
+                        Announcement::RemoteConnected,
+                        &format!("Remote {} session connected", connection.protocol),
+                    );
+                }
+                for connection in known.difference(&current) {
+                    events::emit(&app, "session://remote-disconnected", connection);
+                    speech::announce(
+                        Announcement::RemoteDisconnected,
+                        &format!("Remote {} session ended", connection.protocol),
+                    );
+                }
+
+                let policy = POLICY.lock().unwrap().clone();
+                if known.is_empty() && !current.is_empty() && policy.auto_overlay_on_remote_connect
+                {
+                    apply_overlay(&app, true);
+                } else if !known.is_empty()
+                    && current.is_empty()
+                    && policy.auto_destroy_on_remote_disconnect
+                {
+                    apply_overlay(&app, false);
+                }
+
+                REMOTE_CONNECTED.store(!current.is_empty(), Ordering::SeqCst);
+                known = current;
+                thread::sleep(POLL_INTERVAL);
+            }
+        })
+        .map_err(|e| format!("Failed to spawn session watcher: {}", e))?;
+
+    Ok(())
+}
+
+/// As of the watcher's last poll; false while the watcher is stopped.
+pub fn remote_connected() -> bool {
+    REMOTE_CONNECTED.load(Ordering::SeqCst)
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    REMOTE_CONNECTED.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Overlay windows must be created on the UI thread.
+fn apply_overlay(app: &AppHandle, create: bool) {
+    let handle = app.clone();
+    let _ = app.run_on_main_thread(move || {
+        let manager = handle.state::<OverlayManager>();
+        let result = if create {
+            crate::create_overlay_in(&manager, None, None, None, None, None, None).map(|_| ())
+        } else {
+            crate::destroy_overlays_in(&manager)
+        };
+        if let Err(e) = result {
+            log::warn!("session policy overlay action failed: {}", e);
+        }
+    });
+}
+
+// ==========================================
+// SESSION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_session_info() -> SessionInfo {
+    platform::session_info()
+}
+
+#[tauri::command]
+pub fn get_session_policy() -> SessionPolicy {
+    POLICY.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_session_policy(policy: SessionPolicy) {
+    *POLICY.lock().unwrap() = policy;
+}
@@ -0,0 +1,285 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "scripting")]
+use crate::scripts;
+use crate::{audit_log, crash, middleware};
+
+// ==========================================
+// Data Retention
+// ==========================================
+
+// How long each kind of data the app keeps about its user stays around. A janitor
+// deletes whatever has outlived its category's retention period, shortly after
+// start and then hourly; `purge_data` deletes a category outright. Usage lives in
+// memory and is gone on exit anyway, but a long-running session keeps it too.
+// Scripts are aged by when they were last saved; recordings are the scripts
+// recorded from demonstrations, macros the ones written or imported.
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+const FIRST_SWEEP: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DataCategory {
+    /// The privacy audit log.
+    AuditLog,
+    /// The audit trail of commands run and their metrics.
+    Usage,
+    Recordings,
+    Macros,
+}
+
+/// Days to keep each category; `None` keeps it until purged.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub audit_log: Option<u32>,
+    #[serde(default)]
+    pub usage: Option<u32>,
+    #[serde(default)]
+    pub recordings: Option<u32>,
+    #[serde(default)]
+    pub macros: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            audit_log: Some(90),
+            usage: Some(30),
+            recordings: None,
+            macros: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    fn days(&self, category: DataCategory) -> Option<u32> {
+        match category {
+            DataCategory::AuditLog => self.audit_log,
+            DataCategory::Usage => self.usage,
+            DataCategory::Recordings => self.recordings,
+            DataCategory::Macros => self.macros,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PurgedData {
+    pub category: DataCategory,
+    /// Records, calls or scripts deleted.
+    pub removed: u64,
+}
+
+const CATEGORIES: [DataCategory; 4] = [
+    DataCategory::AuditLog,
+    DataCategory::Usage,
+    DataCategory::Recordings,
+    DataCategory::Macros,
+];
+
+static POLICY: Lazy<Mutex<RetentionPolicy>> = Lazy::new(|| Mutex::new(RetentionPolicy::default()));
+static POLICY_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Loads the policy saved at `path`, if any.
+pub fn init(path: PathBuf) {
+    if let Ok(data) = fs::read_to_string(&path) {
+        match serde_json::from_str::<RetentionPolicy>(&data) {
+            Ok(policy) if validate(&policy).is_ok() => *POLICY.lock().unwrap() = policy,
+            _ => log::warn!("retention policy file is invalid, using the defaults"),
+        }
+    }
+    *POLICY_FILE.lock().unwrap() = Some(path);
+}
+
+fn validate(policy: &RetentionPolicy) -> Result<(), String> {
+    match CATEGORIES.iter().find(|c| policy.days(**c) == Some(0)) {
+        Some(category) => Err(format!(
+            "Retention for {:?} must be at least a day; purge it instead",
+            category
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Deletes `category`'s data older than `cutoff_ms`, or all of it without one.
+fn remove(category: DataCategory, cutoff_ms: Option<u64>) -> Result<u64, String> {
+    match category {
+        DataCategory::AuditLog => audit_log::prune(cutoff_ms),
+        DataCategory::Usage => Ok(middleware::prune_usage(cutoff_ms)),
+        #[cfg(feature = "scripting")]
+        DataCategory::Recordings | DataCategory::Macros => {
+            let cutoff = cutoff_ms.map(|ms| std::time::UNIX_EPOCH + Duration::from_millis(ms));
+            scripts::prune(category == DataCategory::Recordings, cutoff)
+        }
+        // Without scripting there's nowhere scripts are kept.
+        #[cfg(not(feature = "scripting"))]
+        DataCategory::Recordings | DataCategory::Macros => Ok(0),
+    }
+}
+
+/// The time, at `now_ms`, before which each category's data has expired; the
+/// categories kept until purged have none.
+fn cutoffs(policy: &RetentionPolicy, now_ms: u64) -> Vec<(DataCategory, u64)> {
+    CATEGORIES
+        .iter()
+        .filter_map(|&category| {
+            let days = policy.days(category)?;
+            Some((category, now_ms.saturating_sub(days as u64 * DAY_MS)))
+        })
+        .collect()
+}
+
+/// Deletes what the policy no longer keeps.
+fn sweep() {
+    let policy = POLICY.lock().unwrap().clone();
+    for (category, cutoff) in cutoffs(&policy, crash::now_ms() as u64) {
+        match remove(category, Some(cutoff)) {
+            Ok(0) => {}
+            Ok(removed) => log::info!("retention removed {} from {:?}", removed, category),
+            Err(e) => log::warn!("retention failed for {:?}: {}", category, e),
+        }
+    }
+}
+
+pub fn start() -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("retention-janitor".to_string())
+        .spawn(move || {
+            let mut wait = FIRST_SWEEP;
+            loop {
+                // Sleeps in short steps so stopping doesn't wait out the hour.
+                let mut waited = Duration::ZERO;
+                while waited < wait {
+                    if GENERATION.load(Ordering::SeqCst) != generation {
+                        return;
+                    }
+                    thread::sleep(Duration::from_secs(1));
+                    waited += Duration::from_secs(1);
+                }
+                sweep();
+                wait = SWEEP_INTERVAL;
+            }
+        })
+        .map_err(|e| format!("Failed to spawn retention janitor: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+// ==========================================
+// RETENTION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_retention_policy() -> RetentionPolicy {
+    POLICY.lock().unwrap().clone()
+}
+
+/// Sets how many days each category is kept and saves the policy. Data it no
+/// longer keeps is deleted at the janitor's next sweep.
+#[tauri::command]
+pub fn set_retention_policy(policy: RetentionPolicy) -> Result<(), String> {
+    validate(&policy)?;
+    if let Some(path) = POLICY_FILE.lock().unwrap().as_ref() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&policy).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to save retention policy: {}", e))?;
+    }
+    *POLICY.lock().unwrap() = policy;
+    Ok(())
+}
+
+/// Deletes everything in `categories` now, whatever the policy keeps.
+#[tauri::command]
+pub fn purge_data(categories: Vec<DataCategory>) -> Result<Vec<PurgedData>, String> {
+    if categories.is_empty() {
+        return Err("Choose at least one category to purge".to_string());
+    }
+    let mut purged: Vec<PurgedData> = Vec::new();
+    for category in categories {
+        if purged.iter().any(|p| p.category == category) {
+            continue;
+        }
+        let removed = remove(category, None)?;
+        crash::breadcrumb("retention", format!("purged {:?}", category));
+        purged.push(PurgedData { category, removed });
+    }
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW_MS: u64 = 1_700_000_000_000;
+
+    #[test]
+    fn the_default_policy_is_valid() {
+        assert!(validate(&RetentionPolicy::default()).is_ok());
+    }
+
+    #[test]
+    fn zero_days_is_rejected() {
+        let policy = RetentionPolicy {
+            macros: Some(0),
+            ..RetentionPolicy::default()
+        };
+        assert!(validate(&policy).is_err());
+        let policy = RetentionPolicy {
+            macros: Some(1),
+            ..RetentionPolicy::default()
+        };
+        assert!(validate(&policy).is_ok());
+    }
+
+    #[test]
+    fn categories_left_out_are_kept_until_purged() {
+        let policy: RetentionPolicy = serde_json::from_str(r#"{ "usage": 7 }"#).unwrap();
+        assert_eq!(policy.audit_log, None);
+        assert_eq!(policy.usage, Some(7));
+    }
+
+    #[test]
+    fn cutoffs_count_back_whole_days() {
+        let cutoffs = cutoffs(&RetentionPolicy::default(), NOW_MS);
+        assert_eq!(
+            cutoffs,
+            vec![
+                (DataCategory::AuditLog, NOW_MS - 90 * DAY_MS),
+                (DataCategory::Usage, NOW_MS - 30 * DAY_MS),
+            ]
+        );
+    }
+
+    #[test]
+    fn cutoffs_before_the_epoch_keep_everything() {
+        let policy = RetentionPolicy {
+            recordings: Some(u32::MAX),
+            ..RetentionPolicy::default()
+        };
+        assert!(cutoffs(&policy, DAY_MS).contains(&(DataCategory::Recordings, 0)));
+    }
+}
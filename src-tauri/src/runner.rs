@@ -0,0 +1,398 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    app_window, crash, events, injection, multi_pointer, region_hash,
+    scripts::{self, Condition, FailureKind, RetryPolicy, Step},
+    visual,
+};
+
+// ==========================================
+// Script Runner
+// ==========================================
+
+// Runs saved scripts natively, step by step, so retries and waits don't depend on a
+// webview staying responsive. Every attempt of every step is reported as a
+// `script://progress` event; a step wrapped in `retry` runs again, with growing
+// delays, after the failures its policy names.
+
+const PROGRESS_EVENT: &str = "script://progress";
+/// Longest a region wait goes without a full comparison while the hash holds still.
+const REGION_RECHECK: Duration = Duration::from_secs(1);
+/// Longest uninterrupted sleep, so a stop request is noticed promptly.
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+static NEXT_RUN: AtomicU64 = AtomicU64::new(1);
+/// Bumped by `stop_script_runs`; runs started before the bump stop at their next step.
+static STOP_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StepStatus {
+    Started,
+    Passed,
+    Failed,
+    /// Failed, and will run again after `retry_in_ms`.
+    Retrying,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StepProgress {
+    pub run: u64,
+    pub script: String,
+    /// 1-based position, with branches spelled out: "4", "6.then.2".
+    pub step: String,
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub status: StepStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<FailureKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScriptRunReport {
+    pub run: u64,
+    pub script: String,
+    pub passed: bool,
+    /// Attempts made across all steps, retries included.
+    pub attempts: u32,
+    pub retries: u32,
+    pub elapsed_ms: u64,
+    /// Step and reason of the failure that ended the run.
+    pub error: Option<String>,
+}
+
+struct StepError {
+    kind: FailureKind,
+    message: String,
+}
+
+impl StepError {
+    fn new(kind: FailureKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+struct Run<'a> {
+    app: &'a AppHandle,
+    id: u64,
+    script: &'a str,
+    generation: u64,
+    /// The run stops at this point as if stopped by `stop_script_runs`.
+    deadline: Option<Instant>,
+    attempts: u32,
+    retries: u32,
+}
+
+impl Run<'_> {
+    fn stopped(&self) -> bool {
+        STOP_GENERATION.load(Ordering::SeqCst) != self.generation
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    fn sleep(&self, duration: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + duration;
+        loop {
+            if self.stopped() {
+                return Err("Script run stopped".to_string());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            thread::sleep(remaining.min(SLEEP_SLICE));
+        }
+    }
+
+    fn report(
+        &self,
+        step: &str,
+        attempt: u32,
+        max_attempts: u32,
+        status: StepStatus,
+    ) -> StepProgress {
+        StepProgress {
+            run: self.id,
+            script: self.script.to_string(),
+            step: step.to_string(),
+            attempt,
+            max_attempts,
+            status,
+            failure: None,
+            error: None,
+            retry_in_ms: None,
+        }
+    }
+
+    fn steps(&mut self, steps: &[Step], path: &str) -> Result<(), String> {
+        for (i, step) in steps.iter().enumerate() {
+            let path = if path.is_empty() {
+                (i + 1).to_string()
+            } else {
+                format!("{}.{}", path, i + 1)
+            };
+            self.step(step, &path)?;
+        }
+        Ok(())
+    }
+
+    /// Runs one step, with its retry policy if it has one.
+    fn step(&mut self, step: &Step, path: &str) -> Result<(), String> {
+        let (step, policy) = match step {
+            Step::Retry { retry, step } => (step.as_ref(), Some(retry)),
+            step => (step, None),
+        };
+        // Branching steps report through their children; validation keeps them out
+        // of retries.
+        if let Step::If {
+            condition,
+            then,
+            otherwise,
+        } = step
+        {
+            return match evaluate(condition) {
+                Ok(true) => self.steps(then, &format!("{}.then", path)),
+                Ok(false) => self.steps(otherwise, &format!("{}.else", path)),
+                Err(e) => Err(format!("Step {}: {}", path, e)),
+            };
+        }
+
+        let max_attempts = policy.map_or(1, |p| p.max_attempts);
+        let mut attempt = 1;
+        loop {
+            if self.stopped() {
+                return Err("Script run stopped".to_string());
+            }
+            self.attempts += 1;
+            events::emit(
+                self.app,
+                PROGRESS_EVENT,
+                self.report(path, attempt, max_attempts, StepStatus::Started),
+            );
+
+            let error = match execute(self, step) {
+                Ok(()) => {
+                    events::emit(
+                        self.app,
+                        PROGRESS_EVENT,
+                        self.report(path, attempt, max_attempts, StepStatus::Passed),
+                    );
+                    return Ok(());
+                }
+                Err(error) => error,
+            };
+
+            let retry = policy
+                .filter(|p: &&RetryPolicy| attempt < p.max_attempts && p.retries(error.kind))
+                .map(|p| p.delay(attempt));
+            events::emit(
+                self.app,
+                PROGRESS_EVENT,
+                StepProgress {
+                    failure: Some(error.kind),
+                    error: Some(error.message.clone()),
+                    retry_in_ms: retry.map(|delay| delay.as_millis() as u64),
+                    ..self.report(
+                        path,
+                        attempt,
+                        max_attempts,
+                        if retry.is_some() {
+                            StepStatus::Retrying
+                        } else {
+                            StepStatus::Failed
+                        },
+                    )
+                },
+            );
+
+            match retry {
+                Some(delay) => {
+                    self.retries += 1;
+                    attempt += 1;
+                    self.sleep(delay)?;
+                }
+                None if attempt > 1 => {
+                    return Err(format!(
+                        "Step {} failed after {} attempts: {}",
+                        path, attempt, error.message
+                    ))
+                }
+                None => return Err(format!("Step {}: {}", path, error.message)),
+            }
+        }
+    }
+}
+
+fn evaluate(condition: &Condition) -> Result<bool, String> {
+    match condition {
+        Condition::RegionMatches {
+            golden,
+            threshold,
+            bounds,
+        } => Ok(visual::region_diff(golden, bounds.as_ref())? <= *threshold),
+        Condition::Os { os } => Ok(os == std::env::consts::OS),
+        Condition::Not { condition } => evaluate(condition).map(|matches| !matches),
+    }
+}
+
+fn execute(run: &Run, step: &Step) -> Result<(), StepError> {
+    match step {
+        Step::Input { job, .. } => injection::run_job(job.clone())
+            .map(|_| ())
+            .map_err(|e| StepError::new(FailureKind::Input, e)),
+        Step::MultiPointer { job } => multi_pointer::run_job(job.clone())
+            .map(|_| ())
+            .map_err(|e| StepError::new(FailureKind::Input, e)),
+        Step::Wait { ms } => run
+            .sleep(Duration::from_millis(*ms))
+            .map_err(|e| StepError::new(FailureKind::Timeout, e)),
+        Step::AssertRegion {
+            golden,
+            threshold,
+            bounds,
+            tolerance,
+        } => {
+            let assertion = visual::assert_region(golden, *threshold, bounds.as_ref(), *tolerance)
+                .map_err(|e| StepError::new(FailureKind::PixelMismatch, e))?;
+            if assertion.matches {
+                Ok(())
+            } else {
+                Err(StepError::new(
+                    FailureKind::PixelMismatch,
+                    format!(
+                        "Region differs from '{}' by {:.4} (threshold {})",
+                        golden, assertion.diff_ratio, threshold
+                    ),
+                ))
+            }
+        }
+        Step::WaitForRegion {
+            golden,
+            threshold,
+            timeout_ms,
+            bounds,
+        } => {
+            let deadline = Instant::now() + Duration::from_millis(*timeout_ms);
+            let mismatch = |e| StepError::new(FailureKind::PixelMismatch, e);
+            let mut watcher = visual::golden_region(golden, bounds.as_ref())
+                .and_then(region_hash::watch)
+                .map_err(mismatch)?;
+            let mut ratio = visual::region_diff(golden, bounds.as_ref()).map_err(mismatch)?;
+            let mut compared = Instant::now();
+            loop {
+                if ratio <= *threshold {
+                    return Ok(());
+                }
+                if Instant::now() >= deadline {
+                    return Err(StepError::new(
+                        FailureKind::PixelMismatch,
+                        format!(
+                            "Timed out after {}ms waiting for '{}' (diff {:.4})",
+                            timeout_ms, golden, ratio
+                        ),
+                    ));
+                }
+                if run.stopped() {
+                    return Err(StepError::new(
+                        FailureKind::Timeout,
+                        "Script run stopped".to_string(),
+                    ));
+                }
+                // The full comparison reruns only once the region's hash moves, or
+                // now and then in case a change too small to flip a bit matters.
+                let slice = SLEEP_SLICE.min(deadline.saturating_duration_since(Instant::now()));
+                let changed = watcher.wait_change(slice).map_err(mismatch)?.is_some();
+                if changed || compared.elapsed() >= REGION_RECHECK {
+                    ratio = visual::region_diff(golden, bounds.as_ref()).map_err(mismatch)?;
+                    compared = Instant::now();
+                }
+            }
+        }
+        Step::WaitForRegionChange {
+            bounds,
+            sensitivity,
+            timeout_ms,
+            until,
+        } => visual::wait_for_change(
+            bounds,
+            *sensitivity,
+            Duration::from_millis(*timeout_ms),
+            *until,
+        )
+        .map(|_| ())
+        .map_err(|e| StepError::new(FailureKind::Timeout, e)),
+        Step::WaitForWindow {
+            window,
+            state,
+            timeout_ms,
+        } => app_window::wait_for_state(window, *state, Duration::from_millis(*timeout_ms))
+            .map(|_| ())
+            .map_err(|e| StepError::new(FailureKind::ElementNotFound, e)),
+        // Handled by `Run::step`.
+        Step::If { .. } | Step::Retry { .. } => Ok(()),
+    }
+}
+
+pub fn run(
+    app: &AppHandle,
+    script: &scripts::Script,
+    deadline: Option<Instant>,
+) -> ScriptRunReport {
+    let id = NEXT_RUN.fetch_add(1, Ordering::SeqCst);
+    crash::breadcrumb("script", format!("run {} of '{}'", id, script.id));
+
+    let started = Instant::now();
+    let mut run = Run {
+        app,
+        id,
+        script: &script.id,
+        generation: STOP_GENERATION.load(Ordering::SeqCst),
+        deadline,
+        attempts: 0,
+        retries: 0,
+    };
+    let result = run.steps(&script.steps, "");
+
+    ScriptRunReport {
+        run: id,
+        script: script.id.clone(),
+        passed: result.is_ok(),
+        attempts: run.attempts,
+        retries: run.retries,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        error: result.err(),
+    }
+}
+
+// ==========================================
+// SCRIPT RUNNER COMMANDS
+// ==========================================
+
+/// Runs a saved script natively; progress arrives as `script://progress` events.
+#[tauri::command]
+pub async fn run_script(app: AppHandle, id: String) -> Result<ScriptRunReport, String> {
+    let script = scripts::load(&id)?;
+    Ok(run(&app, &script, None))
+}
+
+/// Stops every running script before its next step, and any input it is playing.
+#[tauri::command]
+pub fn stop_script_runs() {
+    STOP_GENERATION.fetch_add(1, Ordering::SeqCst);
+    injection::stop_all();
+}
@@ -0,0 +1,541 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    slice,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_window::{WindowCriteria, WindowInfo, WindowState},
+    coords::Bounds,
+    crash,
+    injection::InputJob,
+    multi_pointer::{self, MultiPointerJob},
+    recorder, secrets,
+    visual::{self, ChangeTrigger},
+};
+
+// ==========================================
+// Automation Scripts
+// ==========================================
+
+// Portable script files are described in SCRIPT_FORMAT.md at the repository root.
+
+pub const FORMAT_ID: &str = "screensharehost-script";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Guards against pathological (or hostile) imported files.
+const MAX_NESTING: usize = 16;
+const MAX_ATTEMPTS: u32 = 20;
+
+static SCRIPT_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Condition {
+    RegionMatches {
+        golden: String,
+        threshold: f64,
+        bounds: Option<Bounds>,
+    },
+    /// "windows", "macos" or "linux".
+    Os {
+        os: String,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+}
+
+/// Why a step failed, for choosing which failures a retry policy retries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailureKind {
+    /// A window never appeared or reached the awaited state.
+    ElementNotFound,
+    /// A region did not match its golden image.
+    PixelMismatch,
+    /// A region did not change or settle in time.
+    Timeout,
+    /// Input could not be injected.
+    Input,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first.
+    pub max_attempts: u32,
+    /// Delay before the second attempt.
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Each later delay is the previous one times this.
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    pub max_backoff_ms: Option<u64>,
+    /// Failures worth retrying; any failure when empty.
+    #[serde(default)]
+    pub retry_on: Vec<FailureKind>,
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+impl RetryPolicy {
+    pub fn retries(&self, kind: FailureKind) -> bool {
+        self.retry_on.is_empty() || self.retry_on.contains(&kind)
+    }
+
+    /// Delay after failed attempt `attempt` (1-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ms = self.backoff_ms as f64 * self.multiplier.powi(attempt as i32 - 1);
+        let ms = match self.max_backoff_ms {
+            Some(max) => ms.min(max as f64),
+            None => ms,
+        };
+        Duration::from_millis(ms as u64)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Step {
+    Input {
+        job: InputJob,
+        /// Window the input was aimed at when recorded; informational for editors.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        target_window: Option<WindowInfo>,
+    },
+    /// Concurrent touch contacts, one per track.
+    MultiPointer {
+        job: MultiPointerJob,
+    },
+    Wait {
+        ms: u64,
+    },
+    AssertRegion {
+        golden: String,
+        threshold: f64,
+        bounds: Option<Bounds>,
+        tolerance: Option<u8>,
+    },
+    WaitForRegion {
+        golden: String,
+        threshold: f64,
+        timeout_ms: u64,
+        bounds: Option<Bounds>,
+    },
+    WaitForRegionChange {
+        bounds: Bounds,
+        sensitivity: f64,
+        timeout_ms: u64,
+        #[serde(default)]
+        until: ChangeTrigger,
+    },
+    WaitForWindow {
+        window: WindowCriteria,
+        state: WindowState,
+        timeout_ms: u64,
+    },
+    If {
+        condition: Condition,
+        then: Vec<Step>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<Step>,
+    },
+    /// Runs `step` again after matching failures, backing off between attempts.
+    Retry {
+        retry: RetryPolicy,
+        step: Box<Step>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Script {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BundledAsset {
+    pub golden: visual::Golden,
+    pub png_base64: String,
+}
+
+/// On-disk exchange format: the script plus every asset it references.
+#[derive(Serialize, Deserialize)]
+pub struct ScriptFile {
+    pub format: String,
+    pub version: u32,
+    pub script: Script,
+    #[serde(default)]
+    pub assets: BTreeMap<String, BundledAsset>,
+}
+
+pub fn init(dir: PathBuf) {
+    *SCRIPT_DIR.lock().unwrap() = Some(dir);
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn script_path(id: &str) -> Result<PathBuf, String> {
+    if !valid_id(id) {
+        return Err("Script ids may only contain letters, digits, '-' and '_'".to_string());
+    }
+    let dir = SCRIPT_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Script storage is not initialized")?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir.join(format!("{}.json", id)))
+}
+
+fn condition_goldens<'a>(condition: &'a Condition, out: &mut Vec<&'a str>) {
+    match condition {
+        Condition::RegionMatches { golden, .. } => out.push(golden),
+        Condition::Os { .. } => {}
+        Condition::Not { condition } => condition_goldens(condition, out),
+    }
+}
+
+/// Names of every golden image the steps reference.
+fn step_goldens<'a>(steps: &'a [Step], out: &mut Vec<&'a str>) {
+    for step in steps {
+        match step {
+            Step::Input { .. }
+            | Step::MultiPointer { .. }
+            | Step::Wait { .. }
+            | Step::WaitForRegionChange { .. }
+            | Step::WaitForWindow { .. } => {}
+            Step::AssertRegion { golden, .. } | Step::WaitForRegion { golden, .. } => {
+                out.push(golden)
+            }
+            Step::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                condition_goldens(condition, out);
+                step_goldens(then, out);
+                step_goldens(otherwise, out);
+            }
+            Step::Retry { step, .. } => step_goldens(slice::from_ref(step), out),
+        }
+    }
+}
+
+fn check_threshold(threshold: f64) -> Result<(), String> {
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(())
+    } else {
+        Err(format!("Threshold {} is not between 0 and 1", threshold))
+    }
+}
+
+fn validate_condition(condition: &Condition, depth: usize) -> Result<(), String> {
+    if depth > MAX_NESTING {
+        return Err(format!("Conditions nest deeper than {}", MAX_NESTING));
+    }
+    match condition {
+        Condition::RegionMatches { threshold, .. } => check_threshold(*threshold),
+        Condition::Os { os } => match os.as_str() {
+            "windows" | "macos" | "linux" => Ok(()),
+            _ => Err(format!("Unknown os '{}'", os)),
+        },
+        Condition::Not { condition } => validate_condition(condition, depth + 1),
+    }
+}
+
+fn validate_steps(steps: &[Step], depth: usize) -> Result<(), String> {
+    if depth > MAX_NESTING {
+        return Err(format!("Steps nest deeper than {}", MAX_NESTING));
+    }
+
+    for (i, step) in steps.iter().enumerate() {
+        let result = match step {
+            Step::Input { job, .. } if job.events.is_empty() => {
+                Err("Input step has no events".to_string())
+            }
+            Step::Input { .. } | Step::Wait { .. } => Ok(()),
+            Step::MultiPointer { job } => multi_pointer::validate(job),
+            Step::AssertRegion { threshold, .. } => check_threshold(*threshold),
+            Step::WaitForRegion {
+                threshold,
+                timeout_ms,
+                ..
+            } => check_threshold(*threshold).and_then(|_| {
+                if *timeout_ms == 0 {
+                    Err("Wait step needs a timeout".to_string())
+                } else {
+                    Ok(())
+                }
+            }),
+            Step::WaitForRegionChange { sensitivity, .. } if !(0.0..=1.0).contains(sensitivity) => {
+                Err(format!(
+                    "Sensitivity {} is not between 0 and 1",
+                    sensitivity
+                ))
+            }
+            Step::WaitForRegionChange { timeout_ms: 0, .. } => {
+                Err("Wait step needs a timeout".to_string())
+            }
+            Step::WaitForRegionChange { .. } => Ok(()),
+            Step::WaitForWindow { window, .. } if window.is_empty() => {
+                Err("Window criteria must set a title, process or pid".to_string())
+            }
+            Step::WaitForWindow { timeout_ms: 0, .. } => {
+                Err("Wait step needs a timeout".to_string())
+            }
+            Step::WaitForWindow { .. } => Ok(()),
+            Step::If {
+                condition,
+                then,
+                otherwise,
+            } => validate_condition(condition, depth + 1)
+                .and_then(|_| validate_steps(then, depth + 1))
+                .and_then(|_| validate_steps(otherwise, depth + 1)),
+            Step::Retry { retry, .. } if !(1..=MAX_ATTEMPTS).contains(&retry.max_attempts) => {
+                Err(format!("Retry needs 1 to {} attempts", MAX_ATTEMPTS))
+            }
+            Step::Retry { retry, .. } if retry.multiplier < 1.0 => {
+                Err("Retry multiplier must be at least 1".to_string())
+            }
+            Step::Retry { step, .. } if matches!(**step, Step::If { .. } | Step::Retry { .. }) => {
+                Err("Retry must wrap a single action or wait".to_string())
+            }
+            Step::Retry { step, .. } => validate_steps(slice::from_ref(step), depth + 1),
+        };
+        result.map_err(|e| format!("Step {} (depth {}): {}", i + 1, depth, e))?;
+    }
+    Ok(())
+}
+
+/// Checks structure and that every referenced golden is available via `has_golden`.
+pub fn validate(script: &Script, has_golden: impl Fn(&str) -> bool) -> Result<(), String> {
+    if !valid_id(&script.id) {
+        return Err("Script ids may only contain letters, digits, '-' and '_'".to_string());
+    }
+    if script.name.trim().is_empty() {
+        return Err("Script name must not be empty".to_string());
+    }
+    validate_steps(&script.steps, 0)?;
+
+    let mut goldens = Vec::new();
+    step_goldens(&script.steps, &mut goldens);
+    if let Some(missing) = goldens.into_iter().find(|name| !has_golden(name)) {
+        return Err(format!("Script references missing golden '{}'", missing));
+    }
+    Ok(())
+}
+
+fn local_golden_exists(name: &str) -> bool {
+    visual::read_golden_files(name).is_ok()
+}
+
+pub fn save(script: &Script) -> Result<(), String> {
+    validate(script, local_golden_exists)?;
+    let json = serde_json::to_string_pretty(script).map_err(|e| e.to_string())?;
+    let data = secrets::seal(json.as_bytes())?;
+    fs::write(script_path(&script.id)?, data).map_err(|e| e.to_string())
+}
+
+pub fn load(id: &str) -> Result<Script, String> {
+    let data = fs::read(script_path(id)?).map_err(|_| format!("No script with id '{}'", id))?;
+    let data = secrets::open(data).map_err(|e| format!("Script '{}': {}", id, e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Script '{}' is corrupt: {}", id, e))
+}
+
+/// The script saved at `path`, if it reads as one.
+fn read_saved(path: &Path) -> Option<Script> {
+    let data = secrets::open(fs::read(path).ok()?).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+pub fn list() -> Result<Vec<Script>, String> {
+    let Some(dir) = SCRIPT_DIR.lock().unwrap().clone() else {
+        return Ok(Vec::new());
+    };
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut scripts: Vec<Script> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| read_saved(&entry.path()))
+        .collect();
+    scripts.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(scripts)
+}
+
+/// Deletes the recorded scripts, or those written by hand, last saved before
+/// `cutoff` (all of them without one). Returns how many were deleted.
+pub fn prune(recorded: bool, cutoff: Option<SystemTime>) -> Result<u64, String> {
+    let Some(dir) = SCRIPT_DIR.lock().unwrap().clone() else {
+        return Ok(0);
+    };
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut deleted = 0;
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        let Some(script) = read_saved(&path) else {
+            continue;
+        };
+        let expired = cutoff.map_or(true, |cutoff| {
+            entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .is_ok_and(|modified| modified < cutoff)
+        });
+        if recorder::is_recorded(&script) == recorded && expired {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete script '{}': {}", script.id, e))?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+pub fn export(id: &str, path: &str) -> Result<(), String> {
+    let script = load(id)?;
+
+    let mut names = Vec::new();
+    step_goldens(&script.steps, &mut names);
+    let mut assets = BTreeMap::new();
+    for name in names {
+        if assets.contains_key(name) {
+            continue;
+        }
+        let (golden, png) = visual::read_golden_files(name)?;
+        assets.insert(
+            name.to_string(),
+            BundledAsset {
+                golden,
+                png_base64: STANDARD.encode(png),
+            },
+        );
+    }
+
+    let file = ScriptFile {
+        format: FORMAT_ID.to_string(),
+        version: FORMAT_VERSION,
+        script,
+        assets,
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    let data = secrets::seal(json.as_bytes())?;
+    fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    crash::breadcrumb("scripts", format!("exported {}", id));
+    Ok(())
+}
+
+pub fn import(path: &str, overwrite: bool) -> Result<Script, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let data = secrets::open(data).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let file: ScriptFile =
+        serde_json::from_slice(&data).map_err(|e| format!("Not a valid script file: {}", e))?;
+
+    if file.format != FORMAT_ID {
+        return Err(format!("Unknown script format '{}'", file.format));
+    }
+    if file.version > FORMAT_VERSION {
+        return Err(format!(
+            "Script file version {} is newer than supported version {}",
+            file.version, FORMAT_VERSION
+        ));
+    }
+
+    // Bundled assets win over local goldens with the same name.
+    validate(&file.script, |name| {
+        file.assets.contains_key(name) || local_golden_exists(name)
+    })?;
+    if !overwrite && script_path(&file.script.id)?.exists() {
+        return Err(format!(
+            "A script with id '{}' already exists",
+            file.script.id
+        ));
+    }
+
+    // Decode everything before writing anything, so a bad asset leaves no partial import.
+    let mut decoded = Vec::with_capacity(file.assets.len());
+    for (name, asset) in &file.assets {
+        if *name != asset.golden.name {
+            return Err(format!(
+                "Asset '{}' is labelled '{}'",
+                name, asset.golden.name
+            ));
+        }
+        let png = STANDARD
+            .decode(&asset.png_base64)
+            .map_err(|e| format!("Asset '{}' is not valid base64: {}", name, e))?;
+        decoded.push((&asset.golden, png));
+    }
+    for (golden, png) in decoded {
+        visual::write_golden_files(golden, &png)?;
+    }
+
+    save(&file.script)?;
+    crash::breadcrumb("scripts", format!("imported {}", file.script.id));
+    Ok(file.script)
+}
+
+// ==========================================
+// SCRIPT COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn save_script(script: Script) -> Result<(), String> {
+    save(&script)
+}
+
+#[tauri::command]
+pub fn get_script(id: String) -> Result<Script, String> {
+    load(&id)
+}
+
+#[tauri::command]
+pub fn list_scripts() -> Result<Vec<Script>, String> {
+    list()
+}
+
+#[tauri::command]
+pub fn delete_script(id: String) -> Result<(), String> {
+    fs::remove_file(script_path(&id)?).map_err(|_| format!("No script with id '{}'", id))
+}
+
+#[tauri::command]
+pub fn export_script(id: String, path: String) -> Result<(), String> {
+    export(&id, &path)
+}
+
+#[tauri::command]
+pub fn import_script(path: String, overwrite: Option<bool>) -> Result<Script, String> {
+    import(&path, overwrite.unwrap_or(false))
+}
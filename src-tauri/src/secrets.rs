@@ -0,0 +1,533 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Stored Data Encryption
+// ==========================================
+
+// Optionally encrypts what the app keeps about the user on disk: the privacy audit
+// log, saved scripts (recorded demonstrations among them), named anchors, visual
+// goldens and crash reports, and what it writes out for the user: exported scripts
+// and events and bug reports. The
+// 256-bit key never touches the disk in the clear: it is protected with DPAPI on
+// Windows, kept in the login keychain on macOS and in the Secret Service (libsecret)
+// on Linux. Each file, or each audit log line, is sealed with AES-256-GCM under a
+// fresh nonce. Reads take sealed and plain data alike, so switching encryption on
+// or off only changes how new data is written. Sealed exports and bug reports only
+// open where the key is; `open_sealed_file` writes out a decrypted copy there.
+
+/// Starts every sealed file; plain JSON never does.
+const MAGIC: &[u8] = b"SSH-ENC1";
+/// Starts every sealed audit log line, followed by the sealed line in base64.
+const LINE_PREFIX: &str = "enc1:";
+/// Keeps the DPAPI-protected key on Windows, next to the config file.
+#[cfg(all(feature = "encryption", target_os = "windows"))]
+const KEY_FILE: &str = "storage-key.dpapi";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct EncryptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    /// Where the key is kept; `None` if this build can't encrypt.
+    pub key_store: Option<&'static str>,
+}
+
+static CONFIG: Lazy<Mutex<EncryptionConfig>> =
+    Lazy::new(|| Mutex::new(EncryptionConfig::default()));
+static CONFIG_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+/// Loads the setting saved at `path`, if any. Call before anything reads or
+/// writes stored data.
+pub fn init(path: PathBuf) {
+    if let Ok(data) = fs::read_to_string(&path) {
+        match serde_json::from_str::<EncryptionConfig>(&data) {
+            Ok(config) => *CONFIG.lock().unwrap() = config,
+            Err(_) => log::warn!("encryption settings are invalid, writing data unencrypted"),
+        }
+    }
+    *CONFIG_FILE.lock().unwrap() = Some(path);
+}
+
+/// Whether newly written data is sealed. Never panics, as crash reports ask too.
+pub fn enabled() -> bool {
+    match CONFIG.lock() {
+        Ok(config) => config.enabled,
+        Err(poisoned) => poisoned.into_inner().enabled,
+    }
+}
+
+/// `data` as it should be written: sealed if encryption is on.
+pub fn seal(data: &[u8]) -> Result<Vec<u8>, String> {
+    if !enabled() {
+        return Ok(data.to_vec());
+    }
+    let mut sealed = MAGIC.to_vec();
+    sealed.extend(cipher::seal(data)?);
+    Ok(sealed)
+}
+
+/// The contents of a file written through `seal`, decrypted if sealed.
+pub fn open(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    match data.strip_prefix(MAGIC) {
+        Some(sealed) => cipher::open(sealed),
+        None => Ok(data),
+    }
+}
+
+/// A line of a line-based log as it should be written: sealed if encryption is on.
+pub fn seal_line(line: String) -> Result<String, String> {
+    if !enabled() {
+        return Ok(line);
+    }
+    Ok(format!(
+        "{}{}",
+        LINE_PREFIX,
+        STANDARD.encode(cipher::seal(line.as_bytes())?)
+    ))
+}
+
+/// A line written through `seal_line`, decrypted if sealed.
+pub fn open_line(line: String) -> Result<String, String> {
+    let Some(sealed) = line.strip_prefix(LINE_PREFIX) else {
+        return Ok(line);
+    };
+    let sealed = STANDARD
+        .decode(sealed)
+        .map_err(|_| "Encrypted line is not valid base64".to_string())?;
+    String::from_utf8(cipher::open(&sealed)?).map_err(|_| "Decrypted line is not text".to_string())
+}
+
+fn status() -> EncryptionStatus {
+    EncryptionStatus {
+        enabled: enabled(),
+        key_store: cipher::KEY_STORE,
+    }
+}
+
+/// Turns encryption of newly written data on or off. Turning it on creates the
+/// key if the OS store doesn't hold one yet.
+pub fn set(enabled: bool) -> Result<EncryptionStatus, String> {
+    if enabled {
+        cipher::ensure_key()?;
+    }
+    let config = EncryptionConfig { enabled };
+    if let Some(path) = CONFIG_FILE.lock().unwrap().as_ref() {
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to save encryption settings: {}", e))?;
+    }
+    *CONFIG.lock().unwrap() = config;
+    crate::crash::breadcrumb("secrets", format!("storage encryption {}", enabled));
+    Ok(status())
+}
+
+#[cfg(feature = "encryption")]
+mod cipher {
+    use std::sync::Mutex;
+
+    use once_cell::sync::Lazy;
+    use ring::{
+        aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+        rand::{SecureRandom, SystemRandom},
+    };
+
+    pub const KEY_STORE: Option<&str> = Some(super::store::NAME);
+
+    /// The key, read from the OS store on first use.
+    pub(super) static KEY: Lazy<Mutex<Option<Vec<u8>>>> = Lazy::new(|| Mutex::new(None));
+
+    /// The key; with `create`, made and stored first if the OS store has none.
+    fn key(create: bool) -> Result<LessSafeKey, String> {
+        // Crash reports are sealed from the panic hook, so a poisoned lock is no reason
+        // to panic again.
+        let mut cached = KEY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if cached.is_none() {
+            *cached = match super::store::load()? {
+                Some(key) => Some(key),
+                None if create => {
+                    let mut key = vec![0; AES_256_GCM.key_len()];
+                    SystemRandom::new()
+                        .fill(&mut key)
+                        .map_err(|_| "Failed to generate a storage key".to_string())?;
+                    super::store::save(&key)?;
+                    Some(key)
+                }
+                None => {
+                    return Err(format!(
+                        "The storage key is missing from {}",
+                        super::store::NAME
+                    ))
+                }
+            };
+        }
+        let bytes = cached.as_deref().unwrap_or_default();
+        UnboundKey::new(&AES_256_GCM, bytes)
+            .map(LessSafeKey::new)
+            .map_err(|_| "The stored storage key is invalid".to_string())
+    }
+
+    pub fn ensure_key() -> Result<(), String> {
+        key(true).map(|_| ())
+    }
+
+    /// The nonce followed by the ciphertext and its tag.
+    pub fn seal(data: &[u8]) -> Result<Vec<u8>, String> {
+        let key = key(true)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Failed to generate a nonce".to_string())?;
+        let mut sealed = data.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut sealed,
+        )
+        .map_err(|_| "Failed to encrypt".to_string())?;
+        let mut out = nonce.to_vec();
+        out.extend(sealed);
+        Ok(out)
+    }
+
+    pub fn open(sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < NONCE_LEN {
+            return Err("Encrypted data is truncated".to_string());
+        }
+        let (nonce, data) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| "Encrypted data is truncated".to_string())?;
+        let mut data = data.to_vec();
+        let len = key(false)?
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| "Encrypted data is corrupt or was sealed with another key".to_string())?
+            .len();
+        data.truncate(len);
+        Ok(data)
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+mod cipher {
+    pub const KEY_STORE: Option<&str> = None;
+
+    const UNSUPPORTED: &str = "This build has no storage encryption";
+
+    pub fn ensure_key() -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn seal(_data: &[u8]) -> Result<Vec<u8>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn open(_sealed: &[u8]) -> Result<Vec<u8>, String> {
+        Err(format!("{}, but this data is encrypted", UNSUPPORTED))
+    }
+}
+
+#[cfg(all(feature = "encryption", target_os = "windows"))]
+mod store {
+    use std::{fs, path::PathBuf};
+
+    use windows::{
+        core::PCWSTR,
+        Win32::{
+            Foundation::{LocalFree, HLOCAL},
+            Security::Cryptography::{
+                CryptProtectData, CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+            },
+        },
+    };
+
+    pub const NAME: &str = "DPAPI";
+
+    fn path() -> Result<PathBuf, String> {
+        super::CONFIG_FILE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|path| path.with_file_name(super::KEY_FILE))
+            .ok_or_else(|| "Encryption is not initialized".to_string())
+    }
+
+    pub fn load() -> Result<Option<Vec<u8>>, String> {
+        match fs::read(path()?) {
+            Ok(protected) => transform(&protected, false).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read the storage key: {}", e)),
+        }
+    }
+
+    pub fn save(key: &[u8]) -> Result<(), String> {
+        fs::write(path()?, transform(key, true)?)
+            .map_err(|e| format!("Failed to save the storage key: {}", e))
+    }
+
+    /// Protects `data` for the current user, or unprotects it.
+    fn transform(data: &[u8], protect: bool) -> Result<Vec<u8>, String> {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB::default();
+        unsafe {
+            let result = if protect {
+                CryptProtectData(
+                    &input,
+                    PCWSTR::null(),
+                    None,
+                    None,
+                    None,
+                    CRYPTPROTECT_UI_FORBIDDEN,
+                    &mut output,
+                )
+            } else {
+                CryptUnprotectData(
+                    &input,
+                    None,
+                    None,
+                    None,
+                    None,
+                    CRYPTPROTECT_UI_FORBIDDEN,
+                    &mut output,
+                )
+            };
+            result.map_err(|e| format!("DPAPI failed: {}", e))?;
+            let out = std::slice::from_raw_parts(output.pbData, output.cbData as usize).to_vec();
+            let _ = LocalFree(HLOCAL(output.pbData as _));
+            Ok(out)
+        }
+    }
+}
+
+#[cfg(all(feature = "encryption", target_os = "macos"))]
+mod store {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    use base64::Engine;
+
+    pub const NAME: &str = "the login keychain";
+
+    const SERVICE: &str = "com.example.screensharehost";
+    const ACCOUNT: &str = "storage-key";
+    /// `security`'s exit code for an item that doesn't exist.
+    const NOT_FOUND: i32 = 44;
+
+    pub fn load() -> Result<Option<Vec<u8>>, String> {
+        let out = Command::new("security")
+            .args(["find-generic-password", "-s", SERVICE, "-a", ACCOUNT, "-w"])
+            .output()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        match out.status.code() {
+            Some(0) => super::decode_key(&out.stdout).map(Some),
+            Some(NOT_FOUND) => Ok(None),
+            _ => Err(format!(
+                "Failed to read the storage key: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            )),
+        }
+    }
+
+    /// Runs `security` interactively so the key is passed on stdin, never in argv.
+    pub fn save(key: &[u8]) -> Result<(), String> {
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to run security: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(
+                stdin,
+                "add-generic-password -U -s {} -a {} -w {}",
+                SERVICE,
+                ACCOUNT,
+                super::STANDARD.encode(key)
+            )
+            .map_err(|e| format!("Failed to save the storage key: {}", e))?;
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err("The keychain refused the storage key".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "encryption", target_os = "linux"))]
+mod store {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    use base64::Engine;
+
+    pub const NAME: &str = "the Secret Service";
+
+    const ATTRIBUTES: [&str; 4] = [
+        "service",
+        "com.example.screensharehost",
+        "account",
+        "storage-key",
+    ];
+
+    pub fn load() -> Result<Option<Vec<u8>>, String> {
+        let out = Command::new("secret-tool")
+            .arg("lookup")
+            .args(ATTRIBUTES)
+            .output()
+            .map_err(|e| format!("Failed to run secret-tool (is libsecret installed?): {}", e))?;
+        if out.status.success() {
+            return super::decode_key(&out.stdout).map(Some);
+        }
+        // A lookup that finds nothing fails quietly; anything else says why.
+        if out.stderr.is_empty() {
+            return Ok(None);
+        }
+        Err(format!(
+            "Failed to read the storage key: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ))
+    }
+
+    /// The key is passed on stdin, never in argv.
+    pub fn save(key: &[u8]) -> Result<(), String> {
+        let mut child = Command::new("secret-tool")
+            .args(["store", "--label=Screenshare Host storage key"])
+            .args(ATTRIBUTES)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run secret-tool (is libsecret installed?): {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(super::STANDARD.encode(key).as_bytes())
+                .map_err(|e| format!("Failed to save the storage key: {}", e))?;
+        }
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err("The Secret Service refused the storage key".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(
+    feature = "encryption",
+    not(any(target_os = "windows", target_os = "macos", target_os = "linux"))
+))]
+mod store {
+    pub const NAME: &str = "no secret store";
+
+    pub fn load() -> Result<Option<Vec<u8>>, String> {
+        Err("This platform has no secret store for the storage key".to_string())
+    }
+
+    pub fn save(_key: &[u8]) -> Result<(), String> {
+        load().map(|_| ())
+    }
+}
+
+/// A key as the keychain and Secret Service hand it back: base64 text.
+#[cfg(all(feature = "encryption", any(target_os = "macos", target_os = "linux")))]
+fn decode_key(text: &[u8]) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(String::from_utf8_lossy(text).trim())
+        .map_err(|_| "The stored storage key is invalid".to_string())
+}
+
+// ==========================================
+// ENCRYPTION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_storage_encryption() -> EncryptionStatus {
+    status()
+}
+
+/// Encrypts the stored data, exports and reports written from now on, or stops.
+#[tauri::command]
+pub fn set_storage_encryption(enabled: bool) -> Result<EncryptionStatus, String> {
+    set(enabled)
+}
+
+/// Writes the decrypted contents of `path`, an export, bug report or other file
+/// sealed on this machine, to `destination`.
+#[tauri::command]
+pub fn open_sealed_file(path: String, destination: String) -> Result<(), String> {
+    let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !data.starts_with(MAGIC) {
+        return Err(format!("{} is not encrypted", path));
+    }
+    fs::write(&destination, open(data)?)
+        .map_err(|e| format!("Failed to write {}: {}", destination, e))
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    fn enable_with_test_key() {
+        *cipher::KEY.lock().unwrap() = Some(vec![7; 32]);
+        CONFIG.lock().unwrap().enabled = true;
+    }
+
+    #[test]
+    fn plain_data_passes_through() {
+        let json = br#"{"id":"demo"}"#.to_vec();
+        assert_eq!(open(json.clone()).unwrap(), json);
+        assert_eq!(open_line("{}".to_string()).unwrap(), "{}");
+    }
+
+    #[test]
+    fn sealed_file_round_trips() {
+        enable_with_test_key();
+        let sealed = seal(b"secret script").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed.windows(6).any(|w| w == b"secret"));
+        assert_eq!(open(sealed).unwrap(), b"secret script");
+    }
+
+    #[test]
+    fn sealed_line_round_trips() {
+        enable_with_test_key();
+        let sealed = seal_line(r#"{"at_ms":1}"#.to_string()).unwrap();
+        assert!(sealed.starts_with(LINE_PREFIX));
+        assert!(!sealed.contains('\n'));
+        assert_eq!(open_line(sealed).unwrap(), r#"{"at_ms":1}"#);
+    }
+
+    #[test]
+    fn tampered_data_is_rejected() {
+        enable_with_test_key();
+        let mut sealed = seal(b"anchors").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(open(sealed).is_err());
+    }
+
+    #[test]
+    fn sealed_exports_open_to_a_copy() {
+        enable_with_test_key();
+        let dir = std::env::temp_dir().join(format!("sealed-export-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (sealed, opened) = (dir.join("events.csv"), dir.join("events-open.csv"));
+        let path = |path: &PathBuf| path.display().to_string();
+        fs::write(&sealed, seal(b"at_ms,channel").unwrap()).unwrap();
+        open_sealed_file(path(&sealed), path(&opened)).unwrap();
+        assert_eq!(fs::read(&opened).unwrap(), b"at_ms,channel");
+        // Plain files are refused rather than copied.
+        assert!(open_sealed_file(path(&opened), path(&dir.join("again.csv"))).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
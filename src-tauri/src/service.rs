@@ -0,0 +1,500 @@
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::control;
+
+// ==========================================
+// System Service
+// ==========================================
+
+// The service runs outside any user's desktop (session 0 on Windows, no window server for a
+// LaunchDaemon), so anything that touches the screen is forwarded to the per-user companion
+// running in the active console session. The service's own token, kept in the service
+// account's data directory, may act in whichever session is at the console; a user's token
+// (the one in their data directory) only in their own session, and only while it is at the
+// console. The companion listens on a port the OS picks and records it in its user's data
+// directory, with a token made for that run: the service presents it, and a process that
+// takes the port after the companion exits learns nothing the next companion accepts.
+
+pub const SERVICE_NAME: &str = "ScreenshareHostService";
+#[cfg(target_os = "windows")]
+const COMPANION_NAME: &str = "ScreenshareHostCompanion";
+#[cfg(target_os = "macos")]
+const LAUNCHD_LABEL: &str = "com.example.screensharehost.daemon";
+#[cfg(target_os = "macos")]
+const LAUNCHD_COMPANION_LABEL: &str = "com.example.screensharehost.companion";
+/// Where a running companion records how to reach it, in its user's data directory.
+const COMPANION_FILE: &str = "companion.json";
+/// Whom the service's own token speaks for.
+const ADMIN: &str = "admin";
+
+#[derive(Serialize, Deserialize)]
+struct CompanionLink {
+    port: u16,
+    token: String,
+}
+
+/// Records how the service reaches the companion listening on `listener`, and
+/// returns the token the service will present.
+pub fn register_companion(dir: &Path, listener: &TcpListener) -> Result<String, String> {
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let token = control::new_token()?;
+    let link = CompanionLink {
+        port,
+        token: token.clone(),
+    };
+    let json = serde_json::to_string(&link).map_err(|e| e.to_string())?;
+    control::write_private(&dir.join(COMPANION_FILE), &json)?;
+    Ok(token)
+}
+
+/// The session whose user is at the physical console, if anyone is logged in.
+#[cfg(target_os = "windows")]
+pub fn active_console_session() -> Option<u32> {
+    use windows::Win32::System::RemoteDesktop::WTSGetActiveConsoleSessionId;
+
+    match unsafe { WTSGetActiveConsoleSessionId() } {
+        0xFFFF_FFFF => None,
+        session => Some(session),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn active_console_session() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    // /dev/console is owned by the logged-in GUI user; root means the login window.
+    match std::fs::metadata("/dev/console").map(|m| m.uid()) {
+        Ok(0) | Err(_) => None,
+        Ok(uid) => Some(uid),
+    }
+}
+
+/// The data directory of the user logged into `session`.
+#[cfg(target_os = "windows")]
+fn session_data_dir(session: u32) -> Result<PathBuf, String> {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::RemoteDesktop::WTSQueryUserToken;
+    use windows::Win32::UI::Shell::GetUserProfileDirectoryW;
+
+    unsafe {
+        let mut token = HANDLE::default();
+        WTSQueryUserToken(session, &mut token)
+            .map_err(|e| format!("No user token for session {}: {}", session, e))?;
+        let mut profile = [0u16; 260];
+        let mut len = profile.len() as u32;
+        let found = GetUserProfileDirectoryW(token, PWSTR(profile.as_mut_ptr()), &mut len);
+        let _ = CloseHandle(token);
+        found.map_err(|e| format!("No profile for session {}: {}", session, e))?;
+
+        let profile = String::from_utf16_lossy(&profile[..len.saturating_sub(1) as usize]);
+        Ok(PathBuf::from(profile)
+            .join("AppData")
+            .join("Roaming")
+            .join(crate::APP_IDENTIFIER))
+    }
+}
+
+/// The data directory of the user `session` (a uid) names.
+#[cfg(not(target_os = "windows"))]
+fn session_data_dir(session: u32) -> Result<PathBuf, String> {
+    use std::{ffi::CStr, mem::MaybeUninit};
+
+    let mut entry = MaybeUninit::<libc::passwd>::uninit();
+    let mut found = std::ptr::null_mut();
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let home = unsafe {
+        libc::getpwuid_r(
+            session,
+            entry.as_mut_ptr(),
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut found,
+        );
+        if found.is_null() {
+            return Err(format!("No user with uid {}", session));
+        }
+        CStr::from_ptr((*found).pw_dir)
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    #[cfg(target_os = "macos")]
+    let data = PathBuf::from(home)
+        .join("Library")
+        .join("Application Support");
+    #[cfg(not(target_os = "macos"))]
+    let data = PathBuf::from(home).join(".local").join("share");
+    Ok(data.join(crate::APP_IDENTIFIER))
+}
+
+/// Refuses a file in the data directory of `session` that its user doesn't own.
+#[cfg(not(target_os = "windows"))]
+fn check_owner(path: &Path, session: u32) -> Result<(), String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let owner = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .uid();
+    if owner == session {
+        Ok(())
+    } else {
+        Err(format!("{} is not owned by uid {}", path.display(), session))
+    }
+}
+
+/// Profiles are closed to other users, so whatever is in one is its user's.
+#[cfg(target_os = "windows")]
+fn check_owner(_path: &Path, _session: u32) -> Result<(), String> {
+    Ok(())
+}
+
+/// Reads a file from the data directory of the user in `session`.
+fn read_session_file(session: u32, name: &str) -> Result<String, String> {
+    let path = session_data_dir(session)?.join(name);
+    check_owner(&path, session)?;
+    control::read_private(&path)
+}
+
+fn companion_link(session: u32) -> Result<CompanionLink, String> {
+    let json = read_session_file(session, COMPANION_FILE)
+        .map_err(|e| format!("No companion is registered: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", COMPANION_FILE, e))
+}
+
+/// Sends one request to the companion of `session`.
+fn forward(session: u32, method: &str, params: Value) -> Result<Value, String> {
+    let link = companion_link(session)?;
+    control::call(
+        &format!("127.0.0.1:{}", link.port),
+        &link.token,
+        method,
+        params,
+    )
+}
+
+/// Takes the service's token, or the token of the user at the console.
+fn authorize(token: String) -> control::Authorize {
+    Arc::new(move |given| {
+        if control::same_token(given, &token) {
+            return Some(ADMIN.to_string());
+        }
+        let session = active_console_session()?;
+        let own = read_session_file(session, control::TOKEN_FILE).ok()?;
+        control::same_token(given, &own).then(|| format!("session-{}", session))
+    })
+}
+
+/// Lets the service's token act in `session`, and a user's token only while
+/// their own session is the one at the console.
+fn check_caller(client: &str, session: u32) -> Result<(), String> {
+    let principal = client
+        .strip_prefix("control:")
+        .and_then(|client| client.split_once('@'))
+        .map(|(principal, _)| principal);
+    match principal {
+        Some(ADMIN) => Ok(()),
+        Some(principal) if principal == format!("session-{}", session) => Ok(()),
+        _ => Err(format!("{} may not act in session {}", client, session)),
+    }
+}
+
+/// Runs the control server for the service.
+fn serve() -> Result<(), String> {
+    let token = control::load_token(&crate::data_dir()?.join(control::TOKEN_FILE))?;
+    control::run_daemon(
+        control::bind(control::DEFAULT_ADDR)?,
+        authorize(token),
+        dispatch,
+    )
+}
+
+/// Control handler used while running as a service. Each forwarded request uses its
+/// own companion connection, so sessions do not outlive the request that opened them.
+pub fn dispatch(client: &str, method: &str, params: &Value) -> Result<Value, String> {
+    match method {
+        "ping" => Ok(json!("pong")),
+        "service_status" => {
+            let session = active_console_session();
+            let port = session
+                .and_then(|s| companion_link(s).ok())
+                .map(|link| link.port);
+            let reachable = session
+                .map(|s| forward(s, "ping", Value::Null).is_ok())
+                .unwrap_or(false);
+            Ok(json!({
+                "service": SERVICE_NAME,
+                "active_session": session,
+                "companion_port": port,
+                "companion_reachable": reachable,
+            }))
+        }
+        _ => {
+            let session =
+                active_console_session().ok_or("No interactive user session is active")?;
+            check_caller(client, session)?;
+            forward(session, method, params.clone())
+                .map_err(|e| format!("Companion for session {} unavailable: {}", session, e))
+        }
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn run_tool(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = std::process::Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} {} failed with {}", program, args.join(" "), status))
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn current_exe() -> Result<String, String> {
+    std::env::current_exe()
+        .map_err(|e| e.to_string())
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+// ==========================================
+// WINDOWS SERVICE
+// ==========================================
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = r"HKLM\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(target_os = "windows")]
+pub fn install() -> Result<(), String> {
+    let exe = current_exe()?;
+    let service_cmd = format!("\"{}\" run-service", exe);
+    let companion_cmd = format!("\"{}\" companion", exe);
+
+    run_tool(
+        "sc.exe",
+        &[
+            "create",
+            SERVICE_NAME,
+            "binPath=",
+            &service_cmd,
+            "start=",
+            "auto",
+            "DisplayName=",
+            "Screenshare Host",
+        ],
+    )?;
+    run_tool(
+        "reg.exe",
+        &["add", RUN_KEY, "/v", COMPANION_NAME, "/t", "REG_SZ", "/d", &companion_cmd, "/f"],
+    )?;
+    run_tool("sc.exe", &["start", SERVICE_NAME])
+}
+
+#[cfg(target_os = "windows")]
+pub fn uninstall() -> Result<(), String> {
+    // Stopping fails if the service is already stopped; that is fine.
+    let _ = run_tool("sc.exe", &["stop", SERVICE_NAME]);
+    let _ = run_tool("reg.exe", &["delete", RUN_KEY, "/v", COMPANION_NAME, "/f"]);
+    run_tool("sc.exe", &["delete", SERVICE_NAME])
+}
+
+#[cfg(target_os = "windows")]
+pub fn run() -> Result<(), String> {
+    win_service::run()
+}
+
+#[cfg(target_os = "windows")]
+mod win_service {
+    use std::{ffi::c_void, sync::Mutex};
+
+    use windows::core::{w, PWSTR};
+    use windows::Win32::Foundation::{ERROR_CALL_NOT_IMPLEMENTED, NO_ERROR};
+    use windows::Win32::System::Services::{
+        RegisterServiceCtrlHandlerExW, SetServiceStatus, StartServiceCtrlDispatcherW,
+        SERVICE_ACCEPT_SESSIONCHANGE, SERVICE_ACCEPT_SHUTDOWN, SERVICE_ACCEPT_STOP,
+        SERVICE_CONTROL_INTERROGATE, SERVICE_CONTROL_SESSIONCHANGE, SERVICE_CONTROL_SHUTDOWN,
+        SERVICE_CONTROL_STOP, SERVICE_RUNNING, SERVICE_STATUS, SERVICE_STATUS_CURRENT_STATE,
+        SERVICE_STATUS_HANDLE, SERVICE_STOPPED, SERVICE_STOP_PENDING, SERVICE_TABLE_ENTRYW,
+        SERVICE_WIN32_OWN_PROCESS,
+    };
+
+    use crate::control;
+
+    // SERVICE_STATUS_HANDLE wraps a raw pointer; keep it as an address so the static is Sync.
+    static STATUS_HANDLE: Mutex<usize> = Mutex::new(0);
+
+    pub fn run() -> Result<(), String> {
+        let mut name: Vec<u16> = super::SERVICE_NAME.encode_utf16().chain(Some(0)).collect();
+        let table = [
+            SERVICE_TABLE_ENTRYW {
+                lpServiceName: PWSTR(name.as_mut_ptr()),
+                lpServiceProc: Some(service_main),
+            },
+            SERVICE_TABLE_ENTRYW::default(),
+        ];
+
+        // Blocks until the service stops.
+        unsafe { StartServiceCtrlDispatcherW(table.as_ptr()) }
+            .map_err(|e| format!("Not started by the service control manager: {}", e))
+    }
+
+    fn set_state(state: SERVICE_STATUS_CURRENT_STATE) {
+        let handle = *STATUS_HANDLE.lock().unwrap();
+        if handle == 0 {
+            return;
+        }
+
+        let accepted = if state == SERVICE_RUNNING {
+            SERVICE_ACCEPT_STOP | SERVICE_ACCEPT_SHUTDOWN | SERVICE_ACCEPT_SESSIONCHANGE
+        } else {
+            0
+        };
+        let status = SERVICE_STATUS {
+            dwServiceType: SERVICE_WIN32_OWN_PROCESS,
+            dwCurrentState: state,
+            dwControlsAccepted: accepted,
+            ..Default::default()
+        };
+
+        unsafe {
+            let _ = SetServiceStatus(SERVICE_STATUS_HANDLE(handle as *mut c_void), &status);
+        }
+    }
+
+    unsafe extern "system" fn control_handler(
+        control: u32,
+        _event_type: u32,
+        _event_data: *mut c_void,
+        _context: *mut c_void,
+    ) -> u32 {
+        match control {
+            SERVICE_CONTROL_STOP | SERVICE_CONTROL_SHUTDOWN => {
+                set_state(SERVICE_STOP_PENDING);
+                control::request_shutdown();
+                NO_ERROR.0
+            }
+            SERVICE_CONTROL_SESSIONCHANGE => {
+                // Requests are routed to the active console session on each call.
+                log::info!("session change; active session is now {:?}", super::active_console_session());
+                NO_ERROR.0
+            }
+            SERVICE_CONTROL_INTERROGATE => NO_ERROR.0,
+            _ => ERROR_CALL_NOT_IMPLEMENTED.0,
+        }
+    }
+
+    unsafe extern "system" fn service_main(_argc: u32, _argv: *mut PWSTR) {
+        let handle = match RegisterServiceCtrlHandlerExW(
+            w!("ScreenshareHostService"),
+            Some(control_handler),
+            None,
+        ) {
+            Ok(handle) => handle,
+            Err(e) => {
+                log::error!("failed to register service control handler: {}", e);
+                return;
+            }
+        };
+        *STATUS_HANDLE.lock().unwrap() = handle.0 as usize;
+
+        set_state(SERVICE_RUNNING);
+        if let Err(e) = super::serve() {
+            log::error!("service control loop failed: {}", e);
+        }
+        set_state(SERVICE_STOPPED);
+    }
+}
+
+// ==========================================
+// MACOS LAUNCHD
+// ==========================================
+
+#[cfg(target_os = "macos")]
+fn plist(label: &str, exe: &str, mode: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        label, exe, mode
+    )
+}
+
+#[cfg(target_os = "macos")]
+fn daemon_plist_path() -> String {
+    format!("/Library/LaunchDaemons/{}.plist", LAUNCHD_LABEL)
+}
+
+#[cfg(target_os = "macos")]
+fn agent_plist_path() -> String {
+    format!("/Library/LaunchAgents/{}.plist", LAUNCHD_COMPANION_LABEL)
+}
+
+#[cfg(target_os = "macos")]
+pub fn install() -> Result<(), String> {
+    let exe = current_exe()?;
+
+    std::fs::write(daemon_plist_path(), plist(LAUNCHD_LABEL, &exe, "run-service"))
+        .map_err(|e| format!("Failed to write launch daemon (run as root?): {}", e))?;
+    std::fs::write(agent_plist_path(), plist(LAUNCHD_COMPANION_LABEL, &exe, "companion"))
+        .map_err(|e| format!("Failed to write launch agent: {}", e))?;
+
+    // The agent is loaded by launchd for each user at their next login.
+    run_tool("launchctl", &["load", "-w", &daemon_plist_path()])
+}
+
+#[cfg(target_os = "macos")]
+pub fn uninstall() -> Result<(), String> {
+    let _ = run_tool("launchctl", &["unload", "-w", &daemon_plist_path()]);
+    let _ = std::fs::remove_file(agent_plist_path());
+    std::fs::remove_file(daemon_plist_path()).map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn run() -> Result<(), String> {
+    // launchd delivers SIGTERM on unload; the default disposition ends the process.
+    serve()
+}
+
+// ==========================================
+// UNSUPPORTED PLATFORMS
+// ==========================================
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn install() -> Result<(), String> {
+    Err("Service installation is only supported on Windows and macOS".to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn uninstall() -> Result<(), String> {
+    Err("Service installation is only supported on Windows and macOS".to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+pub fn run() -> Result<(), String> {
+    serve()
+}
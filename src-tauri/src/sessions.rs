@@ -0,0 +1,248 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{State, Webview};
+
+use crate::OverlayManager;
+
+// ==========================================
+// Sessions
+// ==========================================
+
+// A client (a webview, or one control-server connection) opens a session and passes
+// its id as the `session` argument of any command. Overlays, captions, the presenter
+// console, input jobs, recordings and subsystems started inside it are tagged to it
+// and released when the session ends: explicitly, when its webview reloads or
+// closes, or when its control connection drops. Singletons (the caption, the
+// console) belong to whichever session last took them. Sessions end on the main
+// thread, since releasing overlays touches windows.
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resource {
+    /// Privacy overlay, by id.
+    #[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+    Overlay(u64),
+    Caption,
+    PresenterConsole,
+    #[cfg(feature = "input-automation")]
+    InputJob(u64),
+    #[cfg(feature = "scripting")]
+    Recording,
+    /// A subsystem the session switched on.
+    Subsystem(String),
+}
+
+impl Resource {
+    fn describe(&self) -> String {
+        match self {
+            Resource::Overlay(id) => format!("overlay {}", id),
+            Resource::Caption => "caption".to_string(),
+            Resource::PresenterConsole => "presenter console".to_string(),
+            #[cfg(feature = "input-automation")]
+            Resource::InputJob(id) => format!("input job {}", id),
+            #[cfg(feature = "scripting")]
+            Resource::Recording => "recording".to_string(),
+            Resource::Subsystem(name) => format!("subsystem {}", name),
+        }
+    }
+
+    /// Runs on the main thread.
+    fn release(self, manager: &OverlayManager) {
+        match self {
+            Resource::Overlay(id) => {
+                let _ = crate::destroy_overlay_in(manager, id);
+            }
+            Resource::Caption => crate::captions::hide_caption(),
+            Resource::PresenterConsole => crate::presenter::stop_presenter_console(),
+            #[cfg(feature = "input-automation")]
+            Resource::InputJob(id) => crate::injection::cancel(id),
+            #[cfg(feature = "scripting")]
+            Resource::Recording => crate::recorder::cancel(),
+            Resource::Subsystem(name) => {
+                if let Err(e) = crate::subsystems::set_subsystem_enabled(name, false) {
+                    log::warn!("failed to stop session subsystem: {}", e);
+                }
+            }
+        }
+    }
+}
+
+struct Session {
+    owner: String,
+    name: Option<String>,
+    opened_ms: u64,
+    resources: Vec<Resource>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: u64,
+    /// Webview label, or `control:<peer address>`.
+    pub owner: String,
+    pub name: Option<String>,
+    pub opened_ms: u64,
+    pub resources: Vec<String>,
+}
+
+static SESSIONS: Lazy<Mutex<HashMap<u64, Session>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Session of the command running on this thread.
+    static CURRENT: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+pub fn open(owner: &str, name: Option<String>) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+    SESSIONS.lock().unwrap().insert(
+        id,
+        Session {
+            owner: owner.to_string(),
+            name,
+            opened_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            resources: Vec::new(),
+        },
+    );
+    crate::crash::breadcrumb("session", format!("open {} for {}", id, owner));
+    id
+}
+
+/// Restores the previous current session when dropped.
+pub struct Entered(Option<u64>);
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.set(self.0));
+    }
+}
+
+/// Makes the session named by the call's `session` argument, if any, current on
+/// this thread until the guard drops. Only the session's owner may use it.
+pub fn enter(owner: &str, args: &Value) -> Result<Entered, String> {
+    let session = args.get("session").and_then(Value::as_u64);
+    if let Some(id) = session {
+        match SESSIONS.lock().unwrap().get(&id) {
+            Some(s) if s.owner == owner => {}
+            Some(_) => return Err(format!("Session {} belongs to another client", id)),
+            None => return Err(format!("Session {} not found", id)),
+        }
+    }
+    Ok(Entered(CURRENT.with(|current| current.replace(session))))
+}
+
+/// Tags `resource` to the current session. It is taken away from any other session
+/// holding it, so ending that one leaves it alone.
+pub fn track(resource: Resource) {
+    let current = CURRENT.with(Cell::get);
+    let mut sessions = SESSIONS.lock().unwrap();
+    for session in sessions.values_mut() {
+        session.resources.retain(|r| *r != resource);
+    }
+    if let Some(session) = current.and_then(|id| sessions.get_mut(&id)) {
+        session.resources.push(resource);
+    }
+}
+
+/// Forgets resources that were released some other way.
+pub fn untrack(released: impl Fn(&Resource) -> bool) {
+    for session in SESSIONS.lock().unwrap().values_mut() {
+        session.resources.retain(|r| !released(r));
+    }
+}
+
+/// Ends session `id`, releasing everything tagged to it. Runs on the main thread.
+pub fn end(manager: &OverlayManager, id: u64) -> Result<usize, String> {
+    let session = SESSIONS
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or_else(|| format!("Session {} not found", id))?;
+    crate::crash::breadcrumb(
+        "session",
+        format!("end {} with {} resources", id, session.resources.len()),
+    );
+
+    let count = session.resources.len();
+    // Newest first, so later resources built on earlier ones go away first.
+    for resource in session.resources.into_iter().rev() {
+        resource.release(manager);
+    }
+    Ok(count)
+}
+
+/// Ends session `id` on behalf of `owner`, which must have opened it.
+pub fn end_as(manager: &OverlayManager, owner: &str, id: u64) -> Result<usize, String> {
+    let opened_by = SESSIONS.lock().unwrap().get(&id).map(|s| s.owner.clone());
+    if opened_by.is_some_and(|opened_by| opened_by != owner) {
+        return Err(format!("Session {} belongs to another client", id));
+    }
+    end(manager, id)
+}
+
+/// Ends every session opened by `owner`, after it disconnected or reloaded.
+pub fn end_owned_by(manager: &OverlayManager, owner: &str) {
+    let ids: Vec<u64> = SESSIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, session)| session.owner == owner)
+        .map(|(id, _)| *id)
+        .collect();
+    for id in ids {
+        let _ = end(manager, id);
+    }
+}
+
+pub fn list() -> Vec<SessionInfo> {
+    let mut sessions: Vec<SessionInfo> = SESSIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, session)| SessionInfo {
+            id: *id,
+            owner: session.owner.clone(),
+            name: session.name.clone(),
+            opened_ms: session.opened_ms,
+            resources: session.resources.iter().map(Resource::describe).collect(),
+        })
+        .collect();
+    sessions.sort_by_key(|session| session.id);
+    sessions
+}
+
+// ==========================================
+// SESSION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn open_session(webview: Webview, name: Option<String>) -> u64 {
+    open(webview.label(), name)
+}
+
+/// Ends a session and returns how many resources were released.
+#[tauri::command]
+pub fn end_session(
+    state: State<'_, OverlayManager>,
+    webview: Webview,
+    id: u64,
+) -> Result<usize, String> {
+    end_as(&state, webview.label(), id)
+}
+
+#[tauri::command]
+pub fn list_sessions() -> Vec<SessionInfo> {
+    list()
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::AppHandle;
+
+use crate::{actions::Action, events};
+
+// ==========================================
+// Cursor Shake
+// ==========================================
+
+// Fed by the cursor sampler. A shake is a burst of quick horizontal reversals; each
+// swing between reversals must cover some distance so jitter and slow back-and-forth
+// movement are ignored. Every shake emits `cursor://shake` with the native position
+// (for a locate highlight in the UI) and then runs the configured action, if any.
+
+/// Reversals must all fall within this window.
+const SHAKE_WINDOW: Duration = Duration::from_millis(600);
+/// Quiet period after a shake so one long shake fires once.
+const COOLDOWN: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sensitivity {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Sensitivity {
+    /// Direction reversals required, and the minimum swing between them in native units.
+    fn thresholds(self) -> (usize, i32) {
+        match self {
+            Sensitivity::Low => (6, 120),
+            Sensitivity::Medium => (4, 80),
+            Sensitivity::High => (3, 50),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ShakeConfig {
+    pub enabled: bool,
+    #[serde(default)]
+    pub sensitivity: Sensitivity,
+    /// Runs on each shake, e.g. `stop-automation` as an emergency stop.
+    pub action: Option<Action>,
+}
+
+/// Horizontal motion since the last reversal.
+struct Motion {
+    last_x: i32,
+    /// -1, 0 or 1.
+    direction: i32,
+    swing: i32,
+    reversals: VecDeque<Instant>,
+    cooldown_until: Option<Instant>,
+}
+
+static CONFIG: Lazy<Mutex<ShakeConfig>> = Lazy::new(|| Mutex::new(ShakeConfig::default()));
+static MOTION: Lazy<Mutex<Option<Motion>>> = Lazy::new(|| Mutex::new(None));
+
+/// Called by the cursor sampler with a native cursor position.
+pub fn on_sample(app: &AppHandle, x: i32, y: i32) {
+    let fire = {
+        let config = CONFIG.lock().unwrap();
+        let mut motion = MOTION.lock().unwrap();
+        if !config.enabled {
+            *motion = None;
+            return;
+        }
+        let (needed, min_swing) = config.sensitivity.thresholds();
+        let now = Instant::now();
+
+        let Some(motion) = motion.as_mut() else {
+            *motion = Some(Motion {
+                last_x: x,
+                direction: 0,
+                swing: 0,
+                reversals: VecDeque::new(),
+                cooldown_until: None,
+            });
+            return;
+        };
+
+        let dx = x - motion.last_x;
+        motion.last_x = x;
+        let direction = dx.signum();
+        if direction != 0 && direction != motion.direction {
+            if motion.direction != 0 && motion.swing >= min_swing {
+                motion.reversals.push_back(now);
+            }
+            motion.direction = direction;
+            motion.swing = 0;
+        }
+        motion.swing += dx.abs();
+        while motion
+            .reversals
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > SHAKE_WINDOW)
+        {
+            motion.reversals.pop_front();
+        }
+
+        let cooling = motion.cooldown_until.is_some_and(|until| now < until);
+        if !cooling && motion.reversals.len() >= needed {
+            motion.reversals.clear();
+            motion.cooldown_until = Some(now + COOLDOWN);
+            Some(config.action.clone())
+        } else {
+            None
+        }
+    };
+
+    if let Some(action) = fire {
+        events::emit(app, "cursor://shake", json!({ "x": x, "y": y }));
+        if let Some(action) = action {
+            crate::actions::run(app, &action, "cursor-shake");
+        }
+    }
+}
+
+// ==========================================
+// SHAKE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_shake_config() -> ShakeConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_shake_config(config: ShakeConfig) {
+    *CONFIG.lock().unwrap() = config;
+    *MOTION.lock().unwrap() = None;
+}
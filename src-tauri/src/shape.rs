@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+
+use crate::palette::{self, ColorRole, Rgb};
+
+// ==========================================
+// Window Shapes
+// ==========================================
+
+// Rounded corners and borders for privacy overlays and HUDs, so a cover over part of
+// the screen reads as deliberate rather than as a rendering glitch. Windows clips the
+// window to a rounded region (reapplied whenever it is resized) and paints the border
+// itself; macOS rounds and strokes the content view's layer.
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WindowShape {
+    /// Device-independent pixels; 0 keeps square corners.
+    #[serde(default)]
+    pub corner_radius: f64,
+    /// Device-independent pixels; 0 draws no border.
+    #[serde(default)]
+    pub border_width: f64,
+    /// "#RRGGBB"; the palette's accent color when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<String>,
+}
+
+/// A validated shape, ready for the platform layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+pub struct Shape {
+    pub radius: f64,
+    pub border_width: f64,
+    pub border_color: Rgb,
+}
+
+impl WindowShape {
+    pub fn shape(&self) -> Result<Shape, String> {
+        if !(0.0..=500.0).contains(&self.corner_radius) {
+            return Err(format!(
+                "Corner radius {} is out of range",
+                self.corner_radius
+            ));
+        }
+        if !(0.0..=100.0).contains(&self.border_width) {
+            return Err(format!(
+                "Border width {} is out of range",
+                self.border_width
+            ));
+        }
+        Ok(Shape {
+            radius: self.corner_radius,
+            border_width: self.border_width,
+            border_color: self
+                .border_color
+                .as_deref()
+                .map_or(Ok(palette::color(ColorRole::Accent)), palette::parse)?,
+        })
+    }
+}
+
+impl Shape {
+    #[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))]
+    pub fn is_plain(&self) -> bool {
+        self.radius == 0.0 && self.border_width == 0.0
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub use platform::{clip, draw_border};
+
+#[cfg(target_os = "macos")]
+pub use platform::apply;
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::{HWND, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        CreatePen, CreateRoundRectRgn, DeleteObject, GetStockObject, RoundRect, SelectObject,
+        SetWindowRgn, HDC, HRGN, NULL_BRUSH, PS_INSIDEFRAME,
+    };
+    use windows::Win32::UI::HiDpi::GetDpiForWindow;
+    use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
+
+    use super::Shape;
+    use crate::palette;
+
+    fn scaled(value: f64, hwnd: HWND) -> i32 {
+        let scale = unsafe { GetDpiForWindow(hwnd) } as f64 / 96.0;
+        (value * scale).round() as i32
+    }
+
+    /// Clips `hwnd` to its rounded shape, or back to a rectangle. Call again after
+    /// every resize, since the region doesn't scale with the window.
+    pub fn clip(hwnd: HWND, shape: &Shape) {
+        unsafe {
+            if shape.radius == 0.0 {
+                SetWindowRgn(hwnd, HRGN::default(), true);
+                return;
+            }
+            let mut client = RECT::default();
+            let _ = GetClientRect(hwnd, &mut client);
+            let diameter = 2 * scaled(shape.radius, hwnd);
+            // The region owns the right and bottom edges exclusively, hence the +1.
+            let region = CreateRoundRectRgn(
+                0,
+                0,
+                client.right + 1,
+                client.bottom + 1,
+                diameter,
+                diameter,
+            );
+            // The window owns the region from here on.
+            SetWindowRgn(hwnd, region, true);
+        }
+    }
+
+    /// Strokes the border, if any, inside the client area along the rounded edge.
+    pub unsafe fn draw_border(hwnd: HWND, hdc: HDC, shape: &Shape) {
+        if shape.border_width == 0.0 {
+            return;
+        }
+        let mut client = RECT::default();
+        let _ = GetClientRect(hwnd, &mut client);
+        let diameter = 2 * scaled(shape.radius, hwnd);
+        let width = scaled(shape.border_width, hwnd).max(1);
+        let pen = CreatePen(PS_INSIDEFRAME, width, palette::colorref(shape.border_color));
+        let previous_pen = SelectObject(hdc, pen);
+        let previous_brush = SelectObject(hdc, GetStockObject(NULL_BRUSH));
+        let _ = RoundRect(
+            hdc,
+            client.left,
+            client.top,
+            client.right,
+            client.bottom,
+            diameter,
+            diameter,
+        );
+        SelectObject(hdc, previous_brush);
+        SelectObject(hdc, previous_pen);
+        let _ = DeleteObject(pen);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    use cocoa::appkit::NSWindow;
+    use cocoa::base::{id, nil, NO, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::Shape;
+    use crate::palette;
+
+    /// Rounds and strokes the window's content layer, which then draws `background`
+    /// in place of the window, since a window background can't be rounded.
+    pub unsafe fn apply(window: id, shape: &Shape, background: id) {
+        let content: id = msg_send![window, contentView];
+        let _: () = msg_send![content, setWantsLayer: YES];
+        let layer: id = msg_send![content, layer];
+        if layer == nil {
+            return;
+        }
+        let clear: id = msg_send![class!(NSColor), clearColor];
+        window.setBackgroundColor_(clear);
+        let _: () = msg_send![window, setOpaque: NO];
+        let _: () = msg_send![window, setHasShadow: NO];
+
+        let fill: *const c_void = msg_send![background, CGColor];
+        let _: () = msg_send![layer, setBackgroundColor: fill];
+        let _: () = msg_send![layer, setCornerRadius: shape.radius];
+        let _: () = msg_send![layer, setMasksToBounds: YES];
+        let _: () = msg_send![layer, setBorderWidth: shape.border_width];
+        let border = palette::ns_color(shape.border_color, 1.0);
+        let border: *const c_void = msg_send![border, CGColor];
+        let _: () = msg_send![layer, setBorderColor: border];
+    }
+}
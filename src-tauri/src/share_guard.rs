@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    app_window::{self, WindowCriteria, WindowInfo},
+    audit_log::{self, AuditEvent, FailureKind},
+    auto_cover,
+    coords::{self, Rect},
+    events,
+    hud::{self, HudStyle},
+    remote_session,
+};
+
+// ==========================================
+// Share Guard
+// ==========================================
+
+// While a screen capture is active, every window not on the allow-list gets an
+// opaque HUD pinned over it, so popups that appear mid-share never show up in it.
+// A capture counts as active when a remote client is attached, when the frontend
+// says so, or when one of the configured indicator windows (a meeting app's
+// "you are sharing" toolbar, say) is on screen. Covers sit above everything, so a
+// partly hidden window is covered in full, including what overlaps it.
+//
+// Each capture source is a display share or a single-window share, and each kind
+// has its own policy: a window share of an allow-listed window shows nothing else,
+// so by default it needs no covers. The OS doesn't say what another app captures,
+// so the kind comes from whoever reports the capture: the frontend, which knows
+// what it picked, or the indicator's configuration. Remote clients see the whole
+// display; sources of unknown kind are treated like display shares.
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+const COVER_TEXT: &str = "Hidden while sharing";
+/// Emitted with `CaptureStatus` whenever captures start, stop or change source.
+const CAPTURE_EVENT: &str = "share-guard://capture";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureKind {
+    /// A whole monitor or the whole desktop.
+    Display,
+    /// A single application window.
+    Window,
+    #[default]
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SharePolicy {
+    /// Covers every window not on the allow-list.
+    #[default]
+    Cover,
+    /// Covers nothing when the shared window is on the allow-list; otherwise, or if
+    /// it isn't known, like `Cover`.
+    CoverUnlessAllowed,
+    /// Covers nothing.
+    Ignore,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CaptureIndicator {
+    #[serde(flatten)]
+    pub criteria: WindowCriteria,
+    /// What is being captured while the indicator shows.
+    #[serde(default)]
+    pub kind: CaptureKind,
+    /// For window shares, the window being shared, if the indicator implies one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared: Option<WindowCriteria>,
+}
+
+/// What the frontend says is being captured.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CaptureTarget {
+    #[serde(default)]
+    pub kind: CaptureKind,
+    /// The shared window, for window shares.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<WindowCriteria>,
+    /// The shared monitor, for display shares of one monitor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureOrigin {
+    /// A remote desktop client is attached.
+    Remote,
+    /// Reported with `set_capture_active`.
+    Frontend,
+    /// A capture indicator window is on screen.
+    Indicator,
+}
+
+/// One capture the guard knows of.
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptureSource {
+    pub origin: CaptureOrigin,
+    pub kind: CaptureKind,
+    /// The shared window, if known and open.
+    pub window: Option<WindowInfo>,
+    pub monitor: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptureStatus {
+    pub active: bool,
+    pub sources: Vec<CaptureSource>,
+    /// Whether the policies call for covers.
+    pub covering: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ShareGuardConfig {
+    pub enabled: bool,
+    /// Windows that stay visible during a capture.
+    #[serde(default)]
+    pub allow: Vec<WindowCriteria>,
+    /// Windows whose presence means a capture is running.
+    #[serde(default)]
+    pub capture_indicators: Vec<CaptureIndicator>,
+    /// Applies to display shares and shares of unknown kind.
+    #[serde(default)]
+    pub display_share: SharePolicy,
+    #[serde(default = "default_window_share")]
+    pub window_share: SharePolicy,
+    #[serde(default = "default_style")]
+    pub style: HudStyle,
+}
+
+fn default_window_share() -> SharePolicy {
+    SharePolicy::CoverUnlessAllowed
+}
+
+fn default_style() -> HudStyle {
+    HudStyle {
+        font_size: 20.0,
+        color: None,
+        background: Some("#202020".to_string()),
+        opacity: 1.0,
+        ..HudStyle::default()
+    }
+}
+
+impl Default for ShareGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow: Vec::new(),
+            capture_indicators: Vec::new(),
+            display_share: SharePolicy::default(),
+            window_share: default_window_share(),
+            style: default_style(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct ShareGuardStatus {
+    pub capture_active: bool,
+    pub sources: Vec<CaptureSource>,
+    pub covered: Vec<WindowInfo>,
+}
+
+static CONFIG: Lazy<Mutex<ShareGuardConfig>> =
+    Lazy::new(|| Mutex::new(ShareGuardConfig::default()));
+/// Set by the frontend when it knows a capture is running.
+static FRONTEND_CAPTURE: Lazy<Mutex<Option<CaptureTarget>>> = Lazy::new(|| Mutex::new(None));
+/// The captures last seen.
+static SOURCES: Lazy<Mutex<Vec<CaptureSource>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Covered window id → (HUD id, window as last seen).
+static COVERS: Lazy<Mutex<HashMap<u64, (u64, WindowInfo)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static CAPTURE_ACTIVE: AtomicBool = AtomicBool::new(false);
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn contains(outer: &Rect, inner: &Rect) -> bool {
+    inner.x >= outer.x
+        && inner.y >= outer.y
+        && inner.x + inner.width <= outer.x + outer.width
+        && inner.y + inner.height <= outer.y + outer.height
+}
+
+fn on_screen(rect: &Rect, screen: &Rect) -> bool {
+    rect.width > 0
+        && rect.height > 0
+        && rect.x < screen.x + screen.width
+        && rect.y < screen.y + screen.height
+        && rect.x + rect.width > screen.x
+        && rect.y + rect.height > screen.y
+}
+
+/// Windows to cover, given windows listed front to back.
+fn exposed(config: &ShareGuardConfig, windows: &[WindowInfo]) -> Vec<WindowInfo> {
+    let own_pid = std::process::id();
+    let screen = coords::virtual_screen();
+    let shown: Vec<&WindowInfo> = windows
+        .iter()
+        .filter(|w| w.visible && w.pid != own_pid && on_screen(&w.rect, &screen))
+        .collect();
+
+    shown
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| !config.allow.iter().any(|c| c.matches(w)))
+        // Windows entirely behind a single window above them cannot leak.
+        .filter(|(i, w)| {
+            !shown[..*i]
+                .iter()
+                .any(|above| contains(&above.rect, &w.rect))
+        })
+        .map(|(_, w)| (*w).clone())
+        .collect()
+}
+
+/// The captures running, given windows listed front to back.
+fn sources(config: &ShareGuardConfig, windows: &[WindowInfo]) -> Vec<CaptureSource> {
+    let find = |criteria: &Option<WindowCriteria>| {
+        let criteria = criteria.as_ref()?;
+        windows.iter().find(|w| criteria.matches(w)).cloned()
+    };
+    let mut sources = Vec::new();
+    if remote_session::remote_connected() {
+        sources.push(CaptureSource {
+            origin: CaptureOrigin::Remote,
+            kind: CaptureKind::Display,
+            window: None,
+            monitor: None,
+        });
+    }
+    if let Some(target) = FRONTEND_CAPTURE.lock().unwrap().as_ref() {
+        sources.push(CaptureSource {
+            origin: CaptureOrigin::Frontend,
+            kind: target.kind,
+            window: find(&target.window),
+            monitor: target.monitor,
+        });
+    }
+    for indicator in &config.capture_indicators {
+        if windows
+            .iter()
+            .any(|w| w.visible && indicator.criteria.matches(w))
+        {
+            sources.push(CaptureSource {
+                origin: CaptureOrigin::Indicator,
+                kind: indicator.kind,
+                window: find(&indicator.shared),
+                monitor: None,
+            });
+        }
+    }
+    sources
+}
+
+/// Whether any of `sources` calls for covers under the configured policies.
+fn needs_covers(config: &ShareGuardConfig, sources: &[CaptureSource]) -> bool {
+    sources.iter().any(|source| {
+        let policy = match source.kind {
+            CaptureKind::Window => config.window_share,
+            CaptureKind::Display | CaptureKind::Unknown => config.display_share,
+        };
+        match policy {
+            SharePolicy::Cover => true,
+            SharePolicy::CoverUnlessAllowed => !source
+                .window
+                .as_ref()
+                .is_some_and(|w| config.allow.iter().any(|c| c.matches(w))),
+            SharePolicy::Ignore => false,
+        }
+    })
+}
+
+/// Identifies a source across polls; shared windows may move without changing it.
+fn key(source: &CaptureSource) -> (CaptureOrigin, CaptureKind, Option<u64>, Option<usize>) {
+    (
+        source.origin,
+        source.kind,
+        source.window.as_ref().map(|w| w.id),
+        source.monitor,
+    )
+}
+
+/// Runs on the main thread.
+fn apply(targets: Vec<WindowInfo>, style: &HudStyle) {
+    let mut covers = COVERS.lock().unwrap();
+
+    covers.retain(|id, (hud_id, _)| {
+        let keep = targets.iter().any(|w| w.id == *id);
+        if !keep {
+            hud::close(*hud_id);
+        }
+        keep
+    });
+
+    for window in targets {
+        match covers.get_mut(&window.id) {
+            Some((hud_id, seen)) => {
+                if seen.rect != window.rect {
+                    let _ = hud::set_rect(*hud_id, window.rect);
+                }
+                *seen = window;
+            }
+            None => match hud::open(window.rect, COVER_TEXT, style, false) {
+                Ok(hud_id) => {
+                    crate::crash::breadcrumb(
+                        "share-guard",
+                        format!("covered {} ({})", window.process, window.id),
+                    );
+                    covers.insert(window.id, (hud_id, window));
+                }
+                Err(e) => {
+                    log::warn!("failed to cover window {}: {}", window.id, e);
+                    audit_log::record(AuditEvent::VerificationFailed {
+                        check: FailureKind::Cover,
+                        message: format!("{} ({}): {}", window.process, window.id, e),
+                    });
+                }
+            },
+        }
+    }
+}
+
+/// Whether the guard last saw a capture running.
+pub fn capture_active() -> bool {
+    CAPTURE_ACTIVE.load(Ordering::SeqCst)
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("share-guard".to_string())
+        .spawn(move || {
+            let own_pid = std::process::id();
+            // The app last focused during a capture, for the audit log.
+            let mut focused_process: Option<String> = None;
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(POLL_INTERVAL);
+                let config = CONFIG.lock().unwrap().clone();
+
+                let (sources, covering, targets, focused) = if config.enabled {
+                    let windows = app_window::list_windows();
+                    let sources = sources(&config, &windows);
+                    let covering = needs_covers(&config, &sources);
+                    let targets = if covering {
+                        exposed(&config, &windows)
+                    } else {
+                        Vec::new()
+                    };
+                    let focused = windows.into_iter().find(|w| w.focused && w.pid != own_pid);
+                    (sources, covering, targets, focused)
+                } else {
+                    (Vec::new(), false, Vec::new(), None)
+                };
+                CAPTURE_ACTIVE.store(!sources.is_empty(), Ordering::SeqCst);
+                let changed = {
+                    let mut seen = SOURCES.lock().unwrap();
+                    let changed = !seen.iter().map(key).eq(sources.iter().map(key));
+                    *seen = sources.clone();
+                    changed
+                };
+                if changed {
+                    audit_log::record(AuditEvent::Capture {
+                        sources: sources.iter().map(|source| source.kind).collect(),
+                    });
+                }
+                match focused {
+                    Some(window)
+                        if !sources.is_empty()
+                            && focused_process.as_deref() != Some(window.process.as_str()) =>
+                    {
+                        audit_log::record(AuditEvent::Focused {
+                            process: window.process.clone(),
+                            rule: auto_cover::rule_name(&window),
+                        });
+                        focused_process = Some(window.process);
+                    }
+                    _ if sources.is_empty() => focused_process = None,
+                    _ => {}
+                }
+                if changed {
+                    let status = CaptureStatus {
+                        active: !sources.is_empty(),
+                        sources,
+                        covering,
+                    };
+                    events::emit(&app, CAPTURE_EVENT, status);
+                }
+
+                if targets.is_empty() && COVERS.lock().unwrap().is_empty() {
+                    continue;
+                }
+                let style = config.style;
+                let _ = app.run_on_main_thread(move || apply(targets, &style));
+            }
+
+            let _ = app.run_on_main_thread(|| apply(Vec::new(), &default_style()));
+        })
+        .map_err(|e| format!("Failed to spawn share guard: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    CAPTURE_ACTIVE.store(false, Ordering::SeqCst);
+    let mut sources = SOURCES.lock().unwrap();
+    if !sources.is_empty() {
+        audit_log::record(AuditEvent::Capture {
+            sources: Vec::new(),
+        });
+    }
+    sources.clear();
+    Ok(())
+}
+
+// ==========================================
+// SHARE GUARD COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_share_guard() -> ShareGuardConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_share_guard(config: ShareGuardConfig) -> Result<(), String> {
+    if config
+        .allow
+        .iter()
+        .chain(
+            config.capture_indicators.iter().flat_map(|indicator| {
+                std::iter::once(&indicator.criteria).chain(&indicator.shared)
+            }),
+        )
+        .any(WindowCriteria::is_empty)
+    {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    *CONFIG.lock().unwrap() = config;
+    Ok(())
+}
+
+/// Tells the guard whether the frontend is capturing (or knows a capture is running),
+/// and what: a window share of an allow-listed window needs no covers by default.
+/// Without `source` the capture counts as a display share.
+#[tauri::command]
+pub fn set_capture_active(active: bool, source: Option<CaptureTarget>) -> Result<(), String> {
+    if source
+        .as_ref()
+        .and_then(|source| source.window.as_ref())
+        .is_some_and(WindowCriteria::is_empty)
+    {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    *FRONTEND_CAPTURE.lock().unwrap() = active.then(|| source.unwrap_or_default());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_share_guard_status() -> ShareGuardStatus {
+    ShareGuardStatus {
+        capture_active: capture_active(),
+        sources: SOURCES.lock().unwrap().clone(),
+        covered: COVERS
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(_, window)| window.clone())
+            .collect(),
+    }
+}
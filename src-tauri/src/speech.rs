@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeSet,
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+// ==========================================
+// Text To Speech
+// ==========================================
+
+// One worker thread owns the platform voice and speaks queued text in order, so
+// announcements never talk over each other. It starts on first use.
+
+/// Events that can be announced aloud when the user is not looking at the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Announcement {
+    RemoteConnected,
+    RemoteDisconnected,
+    SubsystemRestarted,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AnnouncementConfig {
+    pub enabled: bool,
+    pub events: BTreeSet<Announcement>,
+}
+
+static CONFIG: Lazy<Mutex<AnnouncementConfig>> =
+    Lazy::new(|| Mutex::new(AnnouncementConfig::default()));
+static QUEUE: Lazy<Mutex<Option<mpsc::Sender<String>>>> = Lazy::new(|| Mutex::new(None));
+
+fn queue() -> Result<mpsc::Sender<String>, String> {
+    let mut queue = QUEUE.lock().unwrap();
+    if let Some(sender) = queue.as_ref() {
+        return Ok(sender.clone());
+    }
+
+    let (tx, rx) = mpsc::channel::<String>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("speech".to_string())
+        .spawn(move || {
+            let speaker = match platform::Speaker::new() {
+                Ok(speaker) => {
+                    let _ = ready_tx.send(Ok(()));
+                    speaker
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            for text in rx {
+                if let Err(e) = speaker.speak(&text) {
+                    log::warn!("speech failed: {}", e);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn speech worker: {}", e))?;
+
+    ready_rx
+        .recv()
+        .map_err(|_| "Speech worker exited".to_string())??;
+    *queue = Some(tx.clone());
+    Ok(tx)
+}
+
+/// Queues `text` to be spoken with the system voice.
+pub fn say(text: &str) -> Result<(), String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    queue()?
+        .send(text.to_string())
+        .map_err(|_| "Speech worker exited".to_string())
+}
+
+/// Speaks `text` if announcements are on for `event`.
+pub fn announce(event: Announcement, text: &str) {
+    let wanted = {
+        let config = CONFIG.lock().unwrap();
+        config.enabled && config.events.contains(&event)
+    };
+    if wanted {
+        if let Err(e) = say(text) {
+            log::warn!("announcement failed: {}", e);
+        }
+    }
+}
+
+// ==========================================
+// SPEECH COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn speak(text: String) -> Result<(), String> {
+    say(&text)
+}
+
+#[tauri::command]
+pub fn get_announcements() -> AnnouncementConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_announcements(config: AnnouncementConfig) {
+    *CONFIG.lock().unwrap() = config;
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::HSTRING;
+    use windows::Win32::Media::Speech::{ISpVoice, SpVoice, SPF_IS_NOT_XML};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_APARTMENTTHREADED,
+    };
+
+    /// SAPI voice; lives on the speech worker's COM apartment.
+    pub struct Speaker(ISpVoice);
+
+    impl Speaker {
+        pub fn new() -> Result<Self, String> {
+            unsafe {
+                let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+                CoCreateInstance(&SpVoice, None, CLSCTX_ALL)
+                    .map(Speaker)
+                    .map_err(|e| format!("Failed to create SAPI voice: {}", e))
+            }
+        }
+
+        /// Blocks until the text has been spoken.
+        pub fn speak(&self, text: &str) -> Result<(), String> {
+            unsafe {
+                self.0
+                    .Speak(&HSTRING::from(text), SPF_IS_NOT_XML.0 as u32, None)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {}
+
+    /// AVSpeechSynthesizer queues utterances itself and speaks them in order.
+    pub struct Speaker(id);
+
+    impl Speaker {
+        pub fn new() -> Result<Self, String> {
+            let synthesizer: id = unsafe { msg_send![class!(AVSpeechSynthesizer), new] };
+            if synthesizer == nil {
+                return Err("Speech synthesis is unavailable".to_string());
+            }
+            Ok(Speaker(synthesizer))
+        }
+
+        pub fn speak(&self, text: &str) -> Result<(), String> {
+            unsafe {
+                let pool = NSAutoreleasePool::new(nil);
+                let text = NSString::alloc(nil).init_str(text);
+                let utterance: id =
+                    msg_send![class!(AVSpeechUtterance), speechUtteranceWithString: text];
+                let _: () = msg_send![self.0, speakUtterance: utterance];
+                let _: () = msg_send![text, release];
+                let _: () = msg_send![pool, drain];
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::process::Command;
+
+    /// Speech Dispatcher, through its command-line client.
+    pub struct Speaker;
+
+    impl Speaker {
+        pub fn new() -> Result<Self, String> {
+            Command::new("spd-say")
+                .arg("--version")
+                .output()
+                .map_err(|_| "Text to speech needs speech-dispatcher (spd-say)".to_string())?;
+            Ok(Speaker)
+        }
+
+        /// Blocks until the text has been spoken.
+        pub fn speak(&self, text: &str) -> Result<(), String> {
+            let status = Command::new("spd-say")
+                .args(["--wait", "--", text])
+                .status()
+                .map_err(|e| e.to_string())?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(format!("spd-say exited with {}", status))
+            }
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{
+    coords::{self, Rect},
+    crash,
+};
+
+// ==========================================
+// Spotlight
+// ==========================================
+
+// Dims the monitor under the cursor except for a circle around it, so an audience can
+// follow the presenter's pointer. Fed by the cursor sampler: positions are smoothed
+// so the circle glides after the cursor instead of jittering with it, and the window
+// is only touched on the main thread, at most one update in flight. Unlike privacy
+// overlays the spotlight shows in screen captures, since it's meant for viewers.
+
+/// Device-independent pixels.
+const DEFAULT_RADIUS: f64 = 120.0;
+const DEFAULT_SMOOTHING: f64 = 0.6;
+const DEFAULT_DIM: f64 = 0.6;
+/// Smaller moves of the circle aren't worth a window update, in native units.
+const MIN_STEP: f64 = 0.5;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpotlightConfig {
+    /// Device-independent pixels.
+    #[serde(default = "default_radius")]
+    pub radius: f64,
+    /// 0 keeps the circle on the cursor; values towards 1 make it trail further.
+    #[serde(default = "default_smoothing")]
+    pub smoothing: f64,
+    /// Opacity of the dimmed area.
+    #[serde(default = "default_dim")]
+    pub dim: f64,
+}
+
+fn default_radius() -> f64 {
+    DEFAULT_RADIUS
+}
+
+fn default_smoothing() -> f64 {
+    DEFAULT_SMOOTHING
+}
+
+fn default_dim() -> f64 {
+    DEFAULT_DIM
+}
+
+impl Default for SpotlightConfig {
+    fn default() -> Self {
+        Self {
+            radius: DEFAULT_RADIUS,
+            smoothing: DEFAULT_SMOOTHING,
+            dim: DEFAULT_DIM,
+        }
+    }
+}
+
+impl SpotlightConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !(10.0..=2000.0).contains(&self.radius) {
+            return Err(format!("Spotlight radius {} is out of range", self.radius));
+        }
+        if !(0.0..1.0).contains(&self.smoothing) {
+            return Err(format!(
+                "Spotlight smoothing {} is not between 0 and 1",
+                self.smoothing
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.dim) {
+            return Err(format!("Spotlight dim {} is not between 0 and 1", self.dim));
+        }
+        Ok(())
+    }
+}
+
+struct Spotlight {
+    handle: usize,
+    monitor: coords::Monitor,
+}
+
+/// The sampler's smoothed position and the last one sent to the window.
+struct Follow {
+    smoothed: (f64, f64),
+    sent: (f64, f64),
+}
+
+static CONFIG: Lazy<Mutex<SpotlightConfig>> = Lazy::new(|| Mutex::new(SpotlightConfig::default()));
+static SPOTLIGHT: Lazy<Mutex<Option<Spotlight>>> = Lazy::new(|| Mutex::new(None));
+/// Mirrors `SPOTLIGHT` for the sampler, which checks it every sample.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static FOLLOW: Lazy<Mutex<Option<Follow>>> = Lazy::new(|| Mutex::new(None));
+/// Set while an update waits for the main thread.
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+fn monitor_rect(m: &coords::Monitor) -> Rect {
+    Rect {
+        x: m.x,
+        y: m.y,
+        width: m.width,
+        height: m.height,
+    }
+}
+
+/// Called by the cursor sampler with a native cursor position.
+pub fn on_sample(app: &AppHandle, x: i32, y: i32) {
+    if !ACTIVE.load(Ordering::SeqCst) {
+        return;
+    }
+    let smoothing = CONFIG.lock().unwrap().smoothing;
+    let (x, y) = (x as f64, y as f64);
+    let mut follow = FOLLOW.lock().unwrap();
+    let follow = match follow.as_mut() {
+        Some(follow) => {
+            let (sx, sy) = follow.smoothed;
+            follow.smoothed = (
+                sx + (x - sx) * (1.0 - smoothing),
+                sy + (y - sy) * (1.0 - smoothing),
+            );
+            follow
+        }
+        // The first sample jumps straight to the cursor.
+        None => follow.insert(Follow {
+            smoothed: (x, y),
+            sent: (f64::NAN, f64::NAN),
+        }),
+    };
+    let (sx, sy) = follow.smoothed;
+    let (px, py) = follow.sent;
+    // NaN never compares as close, so the first position is always sent.
+    let close = (sx - px).abs() < MIN_STEP && (sy - py).abs() < MIN_STEP;
+    if close || PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    follow.sent = (sx, sy);
+    let _ = app.run_on_main_thread(move || {
+        PENDING.store(false, Ordering::SeqCst);
+        move_to(sx, sy);
+    });
+}
+
+/// Centers the circle on (`x`, `y`), moving the window to another monitor if that's
+/// where the point is. Runs on the main thread.
+fn move_to(x: f64, y: f64) {
+    let mut spotlight = SPOTLIGHT.lock().unwrap();
+    let Some(spotlight) = spotlight.as_mut() else {
+        return;
+    };
+    if !spotlight.monitor.contains(x, y) {
+        let Some(monitor) = coords::monitors().into_iter().find(|m| m.contains(x, y)) else {
+            return;
+        };
+        platform::place(spotlight.handle, monitor_rect(&monitor));
+        spotlight.monitor = monitor;
+    }
+    let m = &spotlight.monitor;
+    let radius = CONFIG.lock().unwrap().radius;
+    platform::set_hole(
+        spotlight.handle,
+        monitor_rect(m),
+        (x - m.x as f64, y - m.y as f64),
+        radius,
+        m.scale_factor,
+    );
+}
+
+/// Dims the primary monitor until the first cursor sample moves the circle into
+/// place. Runs on the main thread.
+pub fn start() -> Result<(), String> {
+    if SPOTLIGHT.lock().unwrap().is_some() {
+        return Ok(());
+    }
+    let monitors = coords::monitors();
+    let monitor = monitors
+        .iter()
+        .find(|m| m.primary)
+        .or(monitors.first())
+        .cloned()
+        .ok_or("No monitors found")?;
+    crash::breadcrumb("spotlight", "start");
+    let dim = CONFIG.lock().unwrap().dim;
+    let handle = platform::open(monitor_rect(&monitor), dim)?;
+    *FOLLOW.lock().unwrap() = None;
+    *SPOTLIGHT.lock().unwrap() = Some(Spotlight { handle, monitor });
+    ACTIVE.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Runs on the main thread.
+pub fn stop() {
+    ACTIVE.store(false, Ordering::SeqCst);
+    let spotlight = SPOTLIGHT.lock().unwrap().take();
+    if let Some(spotlight) = spotlight {
+        crash::breadcrumb("spotlight", "stop");
+        platform::close(spotlight.handle);
+    }
+}
+
+/// Runs on the main thread.
+pub fn toggle() -> Result<(), String> {
+    if ACTIVE.load(Ordering::SeqCst) {
+        stop();
+        Ok(())
+    } else {
+        start()
+    }
+}
+
+/// Applies `config`, to the open spotlight too. Runs on the main thread.
+pub fn set_config(config: SpotlightConfig) -> Result<(), String> {
+    config.validate()?;
+    let dim = config.dim;
+    *CONFIG.lock().unwrap() = config;
+    if let Some(spotlight) = SPOTLIGHT.lock().unwrap().as_ref() {
+        platform::set_dim(spotlight.handle, dim);
+    }
+    // Resent on the next sample, with the new radius.
+    if let Some(follow) = FOLLOW.lock().unwrap().as_mut() {
+        follow.sent = (f64::NAN, f64::NAN);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use once_cell::sync::Lazy;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{
+        CombineRgn, CreateEllipticRgn, CreateRectRgn, DeleteObject, GetStockObject, SetWindowRgn,
+        BLACK_BRUSH, HBRUSH, RGN_DIFF,
+    };
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, RegisterClassW, SetLayeredWindowAttributes,
+        SetWindowPos, ShowWindow, LWA_ALPHA, SWP_NOACTIVATE, SWP_NOZORDER, SW_SHOWNOACTIVATE,
+        WNDCLASSW, WS_EX_LAYERED, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
+        WS_EX_TRANSPARENT, WS_POPUP,
+    };
+
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    const CLASS_NAME: PCWSTR = w!("ScreenShareHostSpotlight");
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    static REGISTERED: Lazy<bool> = Lazy::new(|| unsafe {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: GetModuleHandleW(PCWSTR::null())
+                .map(|m| m.into())
+                .unwrap_or_default(),
+            hbrBackground: HBRUSH(GetStockObject(BLACK_BRUSH).0),
+            lpszClassName: CLASS_NAME,
+            ..Default::default()
+        };
+        RegisterClassW(&class) != 0
+    });
+
+    /// A black, click-through window over `rect`.
+    pub fn open(rect: Rect, dim: f64) -> Result<usize, String> {
+        if !*REGISTERED {
+            return Err("Failed to register spotlight window class".to_string());
+        }
+        unsafe {
+            let hwnd = CreateWindowExW(
+                WS_EX_TOPMOST
+                    | WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TRANSPARENT
+                    | WS_EX_NOACTIVATE,
+                CLASS_NAME,
+                w!(""),
+                WS_POPUP,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                HWND::default(),
+                None,
+                GetModuleHandleW(PCWSTR::null()).map_err(|e| e.to_string())?,
+                None,
+            )
+            .map_err(|e| format!("Failed to create spotlight window: {}", e))?;
+            let alpha = (dim * 255.0).round() as u8;
+            if let Err(e) = SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA) {
+                let _ = DestroyWindow(hwnd);
+                return Err(format!("Failed to set up spotlight window: {}", e));
+            }
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            layers::add(Layer::Spotlight, hwnd.0 as usize);
+            Ok(hwnd.0 as usize)
+        }
+    }
+
+    pub fn place(handle: usize, rect: Rect) {
+        unsafe {
+            let _ = SetWindowPos(
+                HWND(handle as *mut _),
+                HWND::default(),
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                SWP_NOACTIVATE | SWP_NOZORDER,
+            );
+        }
+    }
+
+    /// Cuts a circle of `radius` (device-independent) out of the window around
+    /// `center`, in pixels from its top left.
+    pub fn set_hole(handle: usize, rect: Rect, (x, y): (f64, f64), radius: f64, scale: f64) {
+        let r = radius * scale;
+        unsafe {
+            // Regions own their right and bottom edges exclusively, hence the +1s.
+            let region = CreateRectRgn(0, 0, rect.width + 1, rect.height + 1);
+            let hole = CreateEllipticRgn(
+                (x - r).round() as i32,
+                (y - r).round() as i32,
+                (x + r).round() as i32 + 1,
+                (y + r).round() as i32 + 1,
+            );
+            CombineRgn(region, region, hole, RGN_DIFF);
+            let _ = DeleteObject(hole);
+            // The window owns the region from here on.
+            SetWindowRgn(HWND(handle as *mut _), region, true);
+        }
+    }
+
+    pub fn set_dim(handle: usize, dim: f64) {
+        let alpha = (dim * 255.0).round() as u8;
+        unsafe {
+            let _ =
+                SetLayeredWindowAttributes(HWND(handle as *mut _), COLORREF(0), alpha, LWA_ALPHA);
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        unsafe {
+            let _ = DestroyWindow(HWND(handle as *mut _));
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    use cocoa::appkit::{NSBackingStoreType, NSWindow, NSWindowStyleMask};
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use crate::coords::Rect;
+    use crate::layers::{self, Layer};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPathCreateMutable() -> *mut c_void;
+        fn CGPathAddRect(path: *mut c_void, transform: *const c_void, rect: NSRect);
+        fn CGPathAddEllipseInRect(path: *mut c_void, transform: *const c_void, rect: NSRect);
+        fn CGPathRelease(path: *mut c_void);
+    }
+
+    /// `rect` in Cocoa's bottom-left based screen coordinates.
+    unsafe fn frame(rect: Rect) -> Option<NSRect> {
+        let screens: id = msg_send![class!(NSScreen), screens];
+        let count: usize = msg_send![screens, count];
+        if count == 0 {
+            return None;
+        }
+        let primary: id = msg_send![screens, objectAtIndex: 0usize];
+        let primary: NSRect = msg_send![primary, frame];
+        Some(NSRect::new(
+            NSPoint::new(
+                rect.x as f64,
+                primary.size.height - (rect.y + rect.height) as f64,
+            ),
+            NSSize::new(rect.width as f64, rect.height as f64),
+        ))
+    }
+
+    /// A black, click-through window over `rect`, its content masked by an even-odd
+    /// shape layer so the circle in the mask's path stays clear.
+    pub fn open(rect: Rect, dim: f64) -> Result<usize, String> {
+        unsafe {
+            let frame = frame(rect).ok_or("No screens found")?;
+            let window: id = msg_send![class!(NSWindow), alloc];
+            let window: id = window.initWithContentRect_styleMask_backing_defer_(
+                frame,
+                NSWindowStyleMask::NSBorderlessWindowMask,
+                NSBackingStoreType::NSBackingStoreBuffered,
+                false,
+            );
+            if window == nil {
+                return Err("Failed to create spotlight window".to_string());
+            }
+            let clear: id = msg_send![class!(NSColor), clearColor];
+            window.setBackgroundColor_(clear);
+            let _: () = msg_send![window, setReleasedWhenClosed: NO];
+            let _: () = msg_send![window, setOpaque: NO];
+            let _: () = msg_send![window, setHasShadow: NO];
+            let _: () = msg_send![window, setIgnoresMouseEvents: YES];
+            let _: () = msg_send![window, setAlphaValue: dim];
+
+            let view: id = window.contentView();
+            let _: () = msg_send![view, setWantsLayer: YES];
+            let layer: id = msg_send![view, layer];
+            let black: id = msg_send![class!(NSColor), blackColor];
+            let black: *mut c_void = msg_send![black, CGColor];
+            let _: () = msg_send![layer, setBackgroundColor: black];
+            let mask: id = msg_send![class!(CAShapeLayer), layer];
+            let even_odd = NSString::alloc(nil).init_str("even-odd");
+            let _: () = msg_send![mask, setFillRule: even_odd];
+            let _: () = msg_send![even_odd, release];
+            let _: () = msg_send![layer, setMask: mask];
+
+            layers::add(Layer::Spotlight, window as usize);
+            window.orderFrontRegardless();
+            Ok(window as usize)
+        }
+    }
+
+    pub fn place(handle: usize, rect: Rect) {
+        unsafe {
+            if let Some(frame) = frame(rect) {
+                let _: () = msg_send![handle as id, setFrame: frame display: YES];
+            }
+        }
+    }
+
+    /// Points are device-independent already, so `_scale` isn't needed.
+    pub fn set_hole(handle: usize, rect: Rect, (x, y): (f64, f64), radius: f64, _scale: f64) {
+        unsafe {
+            let view: id = msg_send![handle as id, contentView];
+            let layer: id = msg_send![view, layer];
+            let mask: id = msg_send![layer, mask];
+            let bounds = NSRect::new(
+                NSPoint::new(0.0, 0.0),
+                NSSize::new(rect.width as f64, rect.height as f64),
+            );
+            // Layer coordinates start at the bottom left.
+            let circle = NSRect::new(
+                NSPoint::new(x - radius, rect.height as f64 - y - radius),
+                NSSize::new(radius * 2.0, radius * 2.0),
+            );
+            let path = CGPathCreateMutable();
+            CGPathAddRect(path, std::ptr::null(), bounds);
+            CGPathAddEllipseInRect(path, std::ptr::null(), circle);
+            // Without this the mask animates to each new path and lags behind.
+            let _: () = msg_send![class!(CATransaction), begin];
+            let _: () = msg_send![class!(CATransaction), setDisableActions: YES];
+            let _: () = msg_send![mask, setFrame: bounds];
+            let _: () = msg_send![mask, setPath: path];
+            let _: () = msg_send![class!(CATransaction), commit];
+            CGPathRelease(path);
+        }
+    }
+
+    pub fn set_dim(handle: usize, dim: f64) {
+        unsafe {
+            let _: () = msg_send![handle as id, setAlphaValue: dim];
+        }
+    }
+
+    pub fn close(handle: usize) {
+        layers::remove(handle);
+        unsafe {
+            let window = handle as id;
+            let _: () = msg_send![window, close];
+            let _: () = msg_send![window, release];
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use crate::coords::Rect;
+
+    pub fn open(_rect: Rect, _dim: f64) -> Result<usize, String> {
+        Err("Spotlight is only supported on macOS and Windows".to_string())
+    }
+
+    pub fn place(_handle: usize, _rect: Rect) {}
+
+    pub fn set_hole(_handle: usize, _rect: Rect, _center: (f64, f64), _radius: f64, _scale: f64) {}
+
+    pub fn set_dim(_handle: usize, _dim: f64) {}
+
+    pub fn close(_handle: usize) {}
+}
+
+// ==========================================
+// SPOTLIGHT COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn start_spotlight() -> Result<(), String> {
+    start()
+}
+
+#[tauri::command]
+pub fn stop_spotlight() {
+    stop();
+}
+
+#[tauri::command]
+pub fn get_spotlight_config() -> SpotlightConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// Changes the radius, smoothing and dimming, including of an open spotlight.
+#[tauri::command]
+pub fn set_spotlight_config(config: SpotlightConfig) -> Result<(), String> {
+    set_config(config)
+}
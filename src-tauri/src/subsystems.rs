@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, sync::Arc, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::{
+    crash,
+    sessions::{self, Resource},
+};
+
+// ==========================================
+// Subsystem Registry
+// ==========================================
+
+type ToggleFn = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+struct Subsystem {
+    enabled: bool,
+    start: ToggleFn,
+    stop: ToggleFn,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SubsystemState {
+    pub name: String,
+    pub enabled: bool,
+}
+
+static SUBSYSTEMS: Lazy<Mutex<BTreeMap<String, Subsystem>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Registers a subsystem that can be switched on and off at runtime.
+/// `start` is invoked immediately when `enabled` is true.
+pub fn register<S, T>(name: &str, enabled: bool, start: S, stop: T) -> Result<(), String>
+where
+    S: Fn() -> Result<(), String> + Send + Sync + 'static,
+    T: Fn() -> Result<(), String> + Send + Sync + 'static,
+{
+    let start: ToggleFn = Arc::new(start);
+    if enabled {
+        start()?;
+    }
+
+    SUBSYSTEMS.lock().unwrap().insert(
+        name.to_string(),
+        Subsystem {
+            enabled,
+            start,
+            stop: Arc::new(stop),
+        },
+    );
+    Ok(())
+}
+
+pub fn states() -> Vec<SubsystemState> {
+    SUBSYSTEMS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, s)| SubsystemState {
+            name: name.clone(),
+            enabled: s.enabled,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn set_subsystem_enabled(name: String, enabled: bool) -> Result<(), String> {
+    let toggle = {
+        let subsystems = SUBSYSTEMS.lock().unwrap();
+        let Some(subsystem) = subsystems.get(&name) else {
+            let known: Vec<&str> = subsystems.keys().map(String::as_str).collect();
+            return Err(format!(
+                "Unknown subsystem '{}' (known: {})",
+                name,
+                known.join(", ")
+            ));
+        };
+
+        if subsystem.enabled == enabled {
+            return Ok(());
+        }
+
+        if enabled {
+            subsystem.start.clone()
+        } else {
+            subsystem.stop.clone()
+        }
+    };
+
+    // Start/stop may spawn or join threads, so run them without holding the registry lock.
+    crash::breadcrumb("subsystem", format!("{} -> {}", name, enabled));
+    toggle()?;
+
+    if let Some(subsystem) = SUBSYSTEMS.lock().unwrap().get_mut(&name) {
+        subsystem.enabled = enabled;
+    }
+    if enabled {
+        sessions::track(Resource::Subsystem(name));
+    } else {
+        sessions::untrack(|r| *r == Resource::Subsystem(name.clone()));
+    }
+    Ok(())
+}
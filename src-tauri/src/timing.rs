@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+// ==========================================
+// High-Resolution Timing
+// ==========================================
+
+// Each wait is split in two: a coarse OS wait that stops `SPIN_MARGIN` short of the
+// deadline, then a busy spin for the rest. The margin is sized to the worst-case
+// oversleep of the platform primitive, so the spin absorbs it without burning a core.
+
+#[cfg(target_os = "windows")]
+const SPIN_MARGIN: Duration = Duration::from_micros(500);
+#[cfg(target_os = "macos")]
+const SPIN_MARGIN: Duration = Duration::from_micros(100);
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const SPIN_MARGIN: Duration = Duration::from_micros(200);
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TimingMetrics {
+    pub samples: usize,
+    /// How late each wake-up was relative to its requested deadline.
+    pub mean_jitter_us: f64,
+    pub p99_jitter_us: f64,
+    pub max_jitter_us: f64,
+}
+
+/// Schedules wake-ups against absolute deadlines and records how close it got.
+/// Holds OS timer resources, so create one per job and keep it on a single thread.
+pub struct Scheduler {
+    lateness_ns: Vec<u64>,
+    #[cfg(target_os = "windows")]
+    timer: platform::Timer,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            lateness_ns: Vec::new(),
+            #[cfg(target_os = "windows")]
+            timer: platform::Timer::new(),
+        }
+    }
+
+    pub fn wait_until(&mut self, deadline: Instant) {
+        let now = Instant::now();
+        if deadline > now + SPIN_MARGIN {
+            let coarse = deadline - now - SPIN_MARGIN;
+            #[cfg(target_os = "windows")]
+            self.timer.sleep(coarse);
+            #[cfg(not(target_os = "windows"))]
+            platform::sleep(coarse);
+        }
+
+        let mut woke = Instant::now();
+        while woke < deadline {
+            std::hint::spin_loop();
+            woke = Instant::now();
+        }
+
+        self.lateness_ns
+            .push(woke.duration_since(deadline).as_nanos() as u64);
+    }
+
+    pub fn metrics(&self) -> TimingMetrics {
+        if self.lateness_ns.is_empty() {
+            return TimingMetrics::default();
+        }
+
+        let mut sorted = self.lateness_ns.clone();
+        sorted.sort_unstable();
+        let to_us = |ns: u64| ns as f64 / 1000.0;
+        let p99 = sorted[(sorted.len() * 99 / 100).min(sorted.len() - 1)];
+
+        TimingMetrics {
+            samples: sorted.len(),
+            mean_jitter_us: to_us(sorted.iter().sum::<u64>()) / sorted.len() as f64,
+            p99_jitter_us: to_us(p99),
+            max_jitter_us: to_us(sorted[sorted.len() - 1]),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::time::Duration;
+
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Media::{timeBeginPeriod, timeEndPeriod};
+    use windows::Win32::System::Threading::{
+        CreateWaitableTimerExW, SetWaitableTimer, WaitForSingleObject,
+        CREATE_WAITABLE_TIMER_HIGH_RESOLUTION, INFINITE, TIMER_ALL_ACCESS,
+    };
+
+    /// A high-resolution waitable timer (Windows 10 1803+), with the system timer
+    /// resolution raised to 1ms while it exists as a fallback for older builds.
+    pub struct Timer {
+        handle: Option<HANDLE>,
+    }
+
+    impl Timer {
+        pub fn new() -> Self {
+            unsafe {
+                timeBeginPeriod(1);
+                let handle = CreateWaitableTimerExW(
+                    None,
+                    PCWSTR::null(),
+                    CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+                    TIMER_ALL_ACCESS.0,
+                )
+                .ok();
+                Self { handle }
+            }
+        }
+
+        pub fn sleep(&self, duration: Duration) {
+            let Some(handle) = self.handle else {
+                std::thread::sleep(duration);
+                return;
+            };
+
+            // Negative due times are relative, in 100ns units.
+            let due = -((duration.as_nanos() / 100) as i64).max(1);
+            unsafe {
+                if SetWaitableTimer(handle, &due, 0, None, None, false).is_ok() {
+                    WaitForSingleObject(handle, INFINITE);
+                } else {
+                    std::thread::sleep(duration);
+                }
+            }
+        }
+    }
+
+    impl Drop for Timer {
+        fn drop(&mut self) {
+            unsafe {
+                if let Some(handle) = self.handle.take() {
+                    let _ = CloseHandle(handle);
+                }
+                timeEndPeriod(1);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::time::Duration;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct MachTimebaseInfo {
+        numer: u32,
+        denom: u32,
+    }
+
+    extern "C" {
+        fn mach_absolute_time() -> u64;
+        fn mach_timebase_info(info: *mut MachTimebaseInfo) -> i32;
+        fn mach_wait_until(deadline: u64) -> i32;
+    }
+
+    /// Mach absolute-time waits wake far more precisely than `nanosleep`.
+    pub fn sleep(duration: Duration) {
+        unsafe {
+            let mut timebase = MachTimebaseInfo::default();
+            if mach_timebase_info(&mut timebase) != 0 || timebase.numer == 0 {
+                std::thread::sleep(duration);
+                return;
+            }
+            let ticks = duration.as_nanos() as u64 * timebase.denom as u64 / timebase.numer as u64;
+            mach_wait_until(mach_absolute_time() + ticks);
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::time::Duration;
+
+    /// `nanosleep` on Linux already has tens-of-microseconds slack.
+    pub fn sleep(duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
@@ -0,0 +1,446 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    app_window::{self, WindowCriteria, WindowInfo},
+    coords::{self, Rect},
+    events,
+    hud::{self, HudStyle},
+    share_guard, OverlayManager,
+};
+
+// ==========================================
+// Toast Interceptor
+// ==========================================
+
+// While privacy mode is on (a privacy overlay is up or a capture is active), system
+// notification banners and other configured popups are covered or pushed off-screen
+// the moment they appear, and each one is logged. Moved windows are put back when
+// privacy mode ends. macOS cannot move other apps' windows and Linux has no HUDs, so
+// those platforms always cover and always move respectively.
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const COVER_TEXT: &str = "Notification hidden";
+/// How far past the right edge of the desktop moved toasts are parked.
+const OFFSCREEN_GAP: i32 = 10_000;
+const LOG_CAPACITY: usize = 200;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ToastMode {
+    #[default]
+    Cover,
+    MoveOffscreen,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ToastGuardConfig {
+    pub enabled: bool,
+    #[serde(default)]
+    pub mode: ToastMode,
+    /// Popups to treat as toasts besides the system's own (chat apps' custom banners).
+    #[serde(default)]
+    pub extra: Vec<WindowCriteria>,
+    #[serde(default = "default_style")]
+    pub style: HudStyle,
+}
+
+fn default_style() -> HudStyle {
+    HudStyle {
+        font_size: 16.0,
+        color: None,
+        background: Some("#202020".to_string()),
+        opacity: 1.0,
+        ..HudStyle::default()
+    }
+}
+
+impl Default for ToastGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: ToastMode::default(),
+            extra: Vec::new(),
+            style: default_style(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SuppressedToast {
+    /// Milliseconds since the Unix epoch.
+    pub at_ms: u64,
+    pub window: WindowInfo,
+    pub mode: ToastMode,
+}
+
+enum Handled {
+    Covered {
+        hud: u64,
+        window: WindowInfo,
+    },
+    /// Holds the window's rect from before it was moved.
+    Moved {
+        original: Rect,
+    },
+}
+
+static CONFIG: Lazy<Mutex<ToastGuardConfig>> =
+    Lazy::new(|| Mutex::new(ToastGuardConfig::default()));
+/// Toast window id → how it was dealt with.
+static HANDLED: Lazy<Mutex<HashMap<u64, Handled>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static LOG: Lazy<Mutex<VecDeque<SuppressedToast>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn privacy_active(app: &AppHandle) -> bool {
+    share_guard::capture_active()
+        || !app
+            .state::<OverlayManager>()
+            .overlays
+            .lock()
+            .unwrap()
+            .is_empty()
+}
+
+fn toasts(config: &ToastGuardConfig) -> Vec<WindowInfo> {
+    let mut found: Vec<WindowInfo> = platform::toasts()
+        .into_iter()
+        .filter(|w| w.visible)
+        .collect();
+    if !config.extra.is_empty() {
+        let own_pid = std::process::id();
+        for window in app_window::list_windows() {
+            if window.visible
+                && window.pid != own_pid
+                && config.extra.iter().any(|c| c.matches(&window))
+                && !found.iter().any(|w| w.id == window.id)
+            {
+                found.push(window);
+            }
+        }
+    }
+    found
+}
+
+fn effective_mode(mode: ToastMode) -> ToastMode {
+    if cfg!(target_os = "macos") {
+        ToastMode::Cover
+    } else if cfg!(target_os = "windows") {
+        mode
+    } else {
+        ToastMode::MoveOffscreen
+    }
+}
+
+fn record(app: &AppHandle, window: WindowInfo, mode: ToastMode) {
+    crate::crash::breadcrumb(
+        "toasts",
+        format!("suppressed {} ({})", window.process, window.id),
+    );
+    let entry = SuppressedToast {
+        at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        window,
+        mode,
+    };
+    events::emit(app, "toast://suppressed", &entry);
+
+    let mut log = LOG.lock().unwrap();
+    if log.len() == LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(entry);
+}
+
+/// Runs on the main thread. An empty `targets` releases everything.
+fn apply(app: &AppHandle, targets: Vec<WindowInfo>, config: &ToastGuardConfig) {
+    let mut handled = HANDLED.lock().unwrap();
+
+    handled.retain(|id, entry| {
+        if targets.iter().any(|w| w.id == *id) {
+            return true;
+        }
+        match entry {
+            Handled::Covered { hud, .. } => hud::close(*hud),
+            // Still open but no longer suppressed: put it back where it was.
+            Handled::Moved { original } => {
+                let _ = platform::move_window(*id, original.x, original.y);
+            }
+        }
+        false
+    });
+
+    let screen = coords::virtual_screen();
+    for window in targets {
+        match handled.get_mut(&window.id) {
+            Some(Handled::Covered { hud, window: seen }) => {
+                if seen.rect != window.rect {
+                    let _ = hud::set_rect(*hud, window.rect);
+                }
+                *seen = window;
+            }
+            Some(Handled::Moved { .. }) => {}
+            None => {
+                let mode = effective_mode(config.mode);
+                let result = match mode {
+                    ToastMode::Cover => hud::open(window.rect, COVER_TEXT, &config.style, false)
+                        .map(|hud| Handled::Covered {
+                            hud,
+                            window: window.clone(),
+                        }),
+                    ToastMode::MoveOffscreen => platform::move_window(
+                        window.id,
+                        screen.x + screen.width + OFFSCREEN_GAP,
+                        window.rect.y,
+                    )
+                    .map(|()| Handled::Moved {
+                        original: window.rect,
+                    }),
+                };
+                match result {
+                    Ok(entry) => {
+                        handled.insert(window.id, entry);
+                        record(app, window, mode);
+                    }
+                    Err(e) => log::warn!("failed to suppress toast {}: {}", window.id, e),
+                }
+            }
+        }
+    }
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("toast-interceptor".to_string())
+        .spawn(move || {
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                thread::sleep(POLL_INTERVAL);
+                let config = CONFIG.lock().unwrap().clone();
+
+                let targets = if config.enabled && privacy_active(&app) {
+                    toasts(&config)
+                } else {
+                    Vec::new()
+                };
+                if targets.is_empty() && HANDLED.lock().unwrap().is_empty() {
+                    continue;
+                }
+                let handle = app.clone();
+                let _ = app.run_on_main_thread(move || apply(&handle, targets, &config));
+            }
+
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                apply(&handle, Vec::new(), &ToastGuardConfig::default())
+            });
+        })
+        .map_err(|e| format!("Failed to spawn toast interceptor: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+// ==========================================
+// TOAST COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_toast_guard() -> ToastGuardConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_toast_guard(config: ToastGuardConfig) -> Result<(), String> {
+    if config.extra.iter().any(WindowCriteria::is_empty) {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    *CONFIG.lock().unwrap() = config;
+    Ok(())
+}
+
+/// Suppressed toasts, oldest first.
+#[tauri::command]
+pub fn get_suppressed_toasts() -> Vec<SuppressedToast> {
+    LOG.lock().unwrap().iter().cloned().collect()
+}
+
+#[tauri::command]
+pub fn clear_suppressed_toasts() {
+    LOG.lock().unwrap().clear();
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetClassNameW, SetWindowPos, SWP_NOACTIVATE, SWP_NOSIZE, SWP_NOZORDER,
+    };
+
+    use crate::app_window::{self, WindowInfo};
+
+    /// (window class, title) pairs of the shell's own notification windows.
+    const TOAST_WINDOWS: &[(&str, &str)] = &[
+        // Windows 10/11 toasts, hosted by ShellExperienceHost.
+        ("Windows.UI.Core.CoreWindow", "New notification"),
+    ];
+
+    fn class_name(id: u64) -> String {
+        let mut buffer = [0u16; 256];
+        let len = unsafe { GetClassNameW(HWND(id as *mut _), &mut buffer) };
+        String::from_utf16_lossy(&buffer[..len.max(0) as usize])
+    }
+
+    pub fn toasts() -> Vec<WindowInfo> {
+        app_window::list_windows()
+            .into_iter()
+            .filter(|w| {
+                TOAST_WINDOWS
+                    .iter()
+                    .any(|(class, title)| w.title == *title && class_name(w.id) == *class)
+            })
+            .collect()
+    }
+
+    pub fn move_window(id: u64, x: i32, y: i32) -> Result<(), String> {
+        unsafe {
+            SetWindowPos(
+                HWND(id as *mut _),
+                HWND::default(),
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+            .map_err(|e| format!("Failed to move window: {}", e))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use crate::app_window::{self, WindowInfo};
+
+    /// Processes that draw notification banners (names vary across macOS releases).
+    const TOAST_OWNERS: &[&str] = &["NotificationCenter", "Notification Center"];
+
+    pub fn toasts() -> Vec<WindowInfo> {
+        app_window::list_floating_windows()
+            .into_iter()
+            .filter(|w| TOAST_OWNERS.contains(&w.process.as_str()))
+            .collect()
+    }
+
+    /// Other apps' windows can only be moved through the Accessibility API.
+    pub fn move_window(_id: u64, _x: i32, _y: i32) -> Result<(), String> {
+        Err("Moving other applications' windows is not supported on macOS".to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConfigureWindowAux, ConnectionExt, MapState};
+
+    use crate::app_window::WindowInfo;
+    use crate::coords::Rect;
+
+    /// Notification daemons map override-redirect windows typed as notifications,
+    /// which never appear in the window manager's client list.
+    pub fn toasts() -> Vec<WindowInfo> {
+        let Ok((conn, screen_num)) = x11rb::connect(None) else {
+            return Vec::new();
+        };
+        let root = conn.setup().roots[screen_num].root;
+        let atom = |name: &str| {
+            conn.intern_atom(false, name.as_bytes())
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .map(|reply| reply.atom)
+        };
+        let (Some(window_type), Some(notification), Some(wm_name), Some(utf8), Some(wm_pid)) = (
+            atom("_NET_WM_WINDOW_TYPE"),
+            atom("_NET_WM_WINDOW_TYPE_NOTIFICATION"),
+            atom("_NET_WM_NAME"),
+            atom("UTF8_STRING"),
+            atom("_NET_WM_PID"),
+        ) else {
+            return Vec::new();
+        };
+        let Some(tree) = conn.query_tree(root).ok().and_then(|c| c.reply().ok()) else {
+            return Vec::new();
+        };
+
+        let property = |window: u32, name: u32, kind: u32| {
+            conn.get_property(false, window, name, kind, 0, 1024)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+        };
+
+        // Children are listed bottom to top.
+        tree.children
+            .into_iter()
+            .rev()
+            .filter_map(|window| {
+                let types: Vec<u32> = property(window, window_type, AtomEnum::ATOM.into())?
+                    .value32()?
+                    .collect();
+                if !types.contains(&notification) {
+                    return None;
+                }
+                let attributes = conn.get_window_attributes(window).ok()?.reply().ok()?;
+                let geometry = conn.get_geometry(window).ok()?.reply().ok()?;
+                let title = property(window, wm_name, utf8)
+                    .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+                    .unwrap_or_default();
+                let pid = property(window, wm_pid, AtomEnum::CARDINAL.into())
+                    .and_then(|reply| reply.value32().and_then(|mut values| values.next()))
+                    .unwrap_or(0);
+                let process = std::fs::read_to_string(format!("/proc/{}/comm", pid))
+                    .map(|name| name.trim().to_string())
+                    .unwrap_or_default();
+
+                Some(WindowInfo {
+                    id: window as u64,
+                    title,
+                    process,
+                    pid,
+                    rect: Rect {
+                        x: geometry.x as i32,
+                        y: geometry.y as i32,
+                        width: geometry.width as i32,
+                        height: geometry.height as i32,
+                    },
+                    visible: attributes.map_state == MapState::VIEWABLE,
+                    focused: false,
+                })
+            })
+            .collect()
+    }
+
+    pub fn move_window(id: u64, x: i32, y: i32) -> Result<(), String> {
+        let (conn, _) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        conn.configure_window(id as u32, &ConfigureWindowAux::new().x(x).y(y))
+            .map_err(|e| e.to_string())?;
+        conn.flush().map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,1163 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use ring::{
+    digest::{digest, Context, SHA256},
+    rand::{SecureRandom, SystemRandom},
+    signature::{UnparsedPublicKey, ED25519},
+};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::{crash, events, subsystems};
+
+// ==========================================
+// Self-Update
+// ==========================================
+
+// Updates come from an endpoint the deployment controls rather than an app store, so
+// administrators decide what is rolled out and when. The endpoint serves a manifest
+// naming the latest version and a package per platform; a newer package is
+// downloaded in the background, hashed as it streams to disk and only kept if its
+// Ed25519 signature checks out against the public key pinned at build time
+// (`APP_UPDATE_PUBLIC_KEY`). Packages are signed over "<version>\n<platform>\n"
+// followed by their SHA-256 digest, so a signed package can't be passed off as
+// another version or platform, and a version at or below the running one is never
+// downloaded or installed. Installing hands the verified package to the platform
+// installer and quits. The endpoint comes from updater.json only; the webview can
+// change the channel and auto-download but not where updates come from.
+//
+// Verified packages are kept under `updates/<version>/`, so the next version can
+// ship a delta against the running version's package: a list of COPY (offset and
+// length in the old package) and INSERT (literal bytes) instructions. The rebuilt
+// package has to match the full package's signature; if there's no kept package,
+// the delta fails or the result doesn't verify, the full package is downloaded.
+//
+// Each install follows a channel. The manifest's own release is stable; beta and
+// nightly releases sit under `channels`, and an install takes the newest release
+// of its channel or a steadier one, so beta never lags behind stable. Switching
+// to a steadier channel doesn't downgrade; it waits for that channel to catch up.
+//
+// The manifest also carries feature flags, fetched at startup and with every
+// check and kept for the next start. A flag is on, off, or rolled out to a
+// percentage of installs, optionally on some channels only; installs are bucketed
+// by a random id kept beside the config, so each stays in or out of a rollout
+// across restarts. A `subsystem:<name>` flag switches that subsystem on when it
+// turns on, but only for the subsystems in `FLAGGABLE_SUBSYSTEMS`. The manifest
+// isn't signed, so whoever answers for the endpoint must not be able to open
+// the microphone, hook input or expose the WebDriver port: opt-in subsystems
+// stay opt-in. Flags never switch a subsystem off.
+//
+// Manifest:
+//   { "version": "1.2.0", "notes": "...",
+//     "platforms": { "windows-x86_64": { "url": "...", "size": 123,
+//                                        "signature": "<base64>",
+//                                        "deltas": [{ "from": "1.1.0", "url": "...",
+//                                                     "size": 12 }] } },
+//     "channels": { "beta": { "version": "1.3.0-beta.1", "platforms": { ... } } },
+//     "flags": { "new-picker": true,
+//                "subsystem:auto-cover": { "rollout": 10, "channels": ["beta"] } } }
+
+/// Emitted with `DownloadProgress` as a package downloads.
+const PROGRESS_EVENT: &str = "updater://progress";
+/// Emitted with `UpdateInfo` once a package is downloaded and verified.
+const READY_EVENT: &str = "updater://ready";
+/// Emitted with the error message when a download or its verification fails.
+const FAILED_EVENT: &str = "updater://failed";
+/// Base64 Ed25519 public key packages must be signed with, set when building.
+const PUBLIC_KEY: Option<&str> = option_env!("APP_UPDATE_PUBLIC_KEY");
+/// First bytes of a delta package.
+const DELTA_MAGIC: &[u8; 4] = b"DLT1";
+const DELTA_COPY: u8 = b'C';
+const DELTA_INSERT: u8 = b'I';
+/// Progress is reported at most this often, in bytes.
+const PROGRESS_STEP: u64 = 512 * 1024;
+/// Prefix of the flags that switch on the subsystem they name.
+const SUBSYSTEM_FLAG: &str = "subsystem:";
+/// The subsystems flags may switch on: ones that protect a share and are on by
+/// default anyway. Never the opt-in ones (gestures, click-effects, voice-commands,
+/// webdriver), which hook input, open the microphone or accept local commands.
+const FLAGGABLE_SUBSYSTEMS: &[&str] = &[
+    "share-guard",
+    "auto-cover",
+    "toast-interceptor",
+    "retention-janitor",
+];
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct UpdateConfig {
+    /// URL of the manifest; no checks are made without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Downloads an update as soon as a check finds one.
+    #[serde(default)]
+    pub auto_download: bool,
+    #[serde(default)]
+    pub channel: UpdateChannel,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Package {
+    url: String,
+    #[serde(default)]
+    size: Option<u64>,
+    signature: String,
+    /// Patches that rebuild this package from an older version's.
+    #[serde(default)]
+    deltas: Vec<Delta>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Delta {
+    /// Version whose package the delta applies to.
+    from: String,
+    url: String,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Release {
+    version: String,
+    #[serde(default)]
+    notes: Option<String>,
+    platforms: HashMap<String, Package>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum FlagRule {
+    On(bool),
+    Staged {
+        /// Percentage of installs that get the flag.
+        #[serde(default = "full_rollout")]
+        rollout: u8,
+        /// Channels it's limited to; all of them when empty.
+        #[serde(default)]
+        channels: Vec<UpdateChannel>,
+    },
+}
+
+fn full_rollout() -> u8 {
+    100
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Manifest {
+    /// The stable release.
+    #[serde(flatten)]
+    stable: Release,
+    #[serde(default)]
+    channels: HashMap<UpdateChannel, Release>,
+    #[serde(default)]
+    flags: HashMap<String, FlagRule>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct FeatureFlags {
+    /// When the endpoint last sent them; `None` if it never has.
+    pub fetched_ms: Option<u64>,
+    /// The channel they were worked out for.
+    pub channel: UpdateChannel,
+    pub flags: BTreeMap<String, bool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UpdateCheck {
+    pub current: String,
+    /// A newer version for this platform, if there is one.
+    pub update: Option<UpdateInfo>,
+    /// Whether that version is downloaded, verified and ready to install.
+    pub ready: bool,
+    pub downloading: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DownloadProgress {
+    pub version: String,
+    pub downloaded: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+#[derive(Default)]
+struct UpdateState {
+    /// The newer version the last check found, and its package.
+    available: Option<(UpdateInfo, Package)>,
+    /// Verified package of `available`.
+    ready: Option<PathBuf>,
+    downloading: bool,
+}
+
+static CONFIG: Lazy<Mutex<UpdateConfig>> = Lazy::new(|| Mutex::new(UpdateConfig::default()));
+static CONFIG_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static DOWNLOAD_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+static STATE: Lazy<Mutex<UpdateState>> = Lazy::new(|| Mutex::new(UpdateState::default()));
+static FLAGS: Lazy<Mutex<FeatureFlags>> = Lazy::new(|| Mutex::new(FeatureFlags::default()));
+static FLAGS_FILE: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+/// Random per-install id that rollouts are bucketed by.
+static INSTALL_ID: Lazy<Mutex<Vec<u8>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Loads the configuration and flags saved in `dir`, which also holds downloaded
+/// packages.
+pub fn init(dir: PathBuf) {
+    let path = dir.join("updater.json");
+    if let Ok(data) = fs::read_to_string(&path) {
+        match serde_json::from_str::<UpdateConfig>(&data) {
+            Ok(config) if validate(&config).is_ok() => *CONFIG.lock().unwrap() = config,
+            _ => log::warn!("updater config is invalid, updates are off"),
+        }
+    }
+    let flags_path = dir.join("feature-flags.json");
+    if let Ok(data) = fs::read_to_string(&flags_path) {
+        match serde_json::from_str::<FeatureFlags>(&data) {
+            Ok(flags) => *FLAGS.lock().unwrap() = flags,
+            Err(e) => log::warn!("feature flags file is invalid: {}", e),
+        }
+    }
+    *INSTALL_ID.lock().unwrap() = install_id(&dir.join("install-id"));
+    *CONFIG_FILE.lock().unwrap() = Some(path);
+    *FLAGS_FILE.lock().unwrap() = Some(flags_path);
+    *DOWNLOAD_DIR.lock().unwrap() = Some(dir.join("updates"));
+}
+
+/// Reads the install id at `path`, or makes one up and saves it there.
+fn install_id(path: &Path) -> Vec<u8> {
+    if let Ok(id) = fs::read_to_string(path) {
+        if let Ok(id) = STANDARD.decode(id.trim()) {
+            if !id.is_empty() {
+                return id;
+            }
+        }
+    }
+    let mut id = vec![0; 16];
+    if SystemRandom::new().fill(&mut id).is_err() {
+        log::warn!("no randomness for an install id, rollouts are all or nothing");
+        return Vec::new();
+    }
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, STANDARD.encode(&id)));
+    if let Err(e) = saved {
+        log::warn!("failed to save install id: {}", e);
+    }
+    id
+}
+
+/// Checks for an update and refreshes the flags in the background, after
+/// switching on the subsystems the flags kept from last time name.
+pub fn start(app: AppHandle) {
+    if CONFIG.lock().unwrap().endpoint.is_none() {
+        return;
+    }
+    let spawned = thread::Builder::new()
+        .name("updater".to_string())
+        .spawn(move || {
+            let flags = FLAGS.lock().unwrap().clone();
+            enable_flagged(&flags, None);
+            if let Err(e) = check(app) {
+                log::warn!("update check at startup failed: {}", e);
+            }
+        });
+    if let Err(e) = spawned {
+        log::warn!("failed to spawn updater: {}", e);
+    }
+}
+
+fn public_key() -> Result<Vec<u8>, String> {
+    decode_key(PUBLIC_KEY.ok_or("This build has no update signing key")?)
+}
+
+fn decode_key(key: &str) -> Result<Vec<u8>, String> {
+    let key = STANDARD
+        .decode(key.trim())
+        .map_err(|e| format!("Invalid update signing key: {}", e))?;
+    if key.len() != 32 {
+        return Err("Update signing keys are 32-byte Ed25519 public keys".to_string());
+    }
+    Ok(key)
+}
+
+fn validate(config: &UpdateConfig) -> Result<(), String> {
+    if let Some(endpoint) = &config.endpoint {
+        if !endpoint.starts_with("https://") {
+            return Err("Update endpoints must use https".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Dotted version numbers and the pre-release identifiers after '-'; build
+/// metadata after '+' is ignored.
+fn version_parts(version: &str) -> Option<(Vec<u64>, Vec<&str>)> {
+    let version = version.trim_start_matches('v').split('+').next()?;
+    let (numbers, pre) = version.split_once('-').unwrap_or((version, ""));
+    let numbers = numbers
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let pre = if pre.is_empty() {
+        Vec::new()
+    } else {
+        pre.split('.').collect()
+    };
+    Some((numbers, pre))
+}
+
+/// Orders pre-release identifiers the semver way: numbers numerically and
+/// before words, which compare as text.
+fn compare_pre(a: &[&str], b: &[&str]) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let order = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let (a_numbers, a_pre) = version_parts(a)?;
+    let (b_numbers, b_pre) = version_parts(b)?;
+    // A release comes after its pre-releases.
+    Some(
+        a_numbers
+            .cmp(&b_numbers)
+            .then_with(|| match (a_pre.is_empty(), b_pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre(&a_pre, &b_pre),
+            }),
+    )
+}
+
+fn newer(candidate: &str, current: &str) -> bool {
+    compare_versions(candidate, current) == Some(Ordering::Greater)
+}
+
+/// Refuses `version` unless it's newer than the running one and safe to name a
+/// directory after.
+fn check_upgrade(version: &str) -> Result<(), String> {
+    let current = current_version();
+    if !newer(version, &current) {
+        return Err(format!("Version {} is not newer than {}", version, current));
+    }
+    if !version
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'))
+    {
+        return Err(format!("Invalid update version '{}'", version));
+    }
+    Ok(())
+}
+
+impl Manifest {
+    /// The newest release on `channel` or a steadier one with a package for
+    /// this platform.
+    fn release(&self, channel: UpdateChannel) -> Option<(&Release, &Package)> {
+        let platform = platform();
+        std::iter::once(&self.stable)
+            .chain(
+                self.channels
+                    .iter()
+                    .filter(|(c, _)| **c <= channel)
+                    .map(|(_, release)| release),
+            )
+            .filter(|release| version_parts(&release.version).is_some())
+            .filter_map(|release| Some((release, release.platforms.get(&platform)?)))
+            .max_by(|(a, _), (b, _)| {
+                compare_versions(&a.version, &b.version).unwrap_or(Ordering::Equal)
+            })
+    }
+}
+
+impl FlagRule {
+    fn enabled(&self, name: &str, channel: UpdateChannel, install_id: &[u8]) -> bool {
+        match self {
+            FlagRule::On(on) => *on,
+            FlagRule::Staged { rollout, channels } => {
+                (channels.is_empty() || channels.contains(&channel))
+                    && (*rollout >= 100 || rollout_bucket(name, install_id) < *rollout as u32)
+            }
+        }
+    }
+}
+
+/// Where this install falls, 0 to 99, in the rollout of flag `name`. Each flag
+/// buckets installs differently, so the same installs aren't always first.
+fn rollout_bucket(name: &str, install_id: &[u8]) -> u32 {
+    if install_id.is_empty() {
+        return 99;
+    }
+    let hash = digest(&SHA256, &[install_id, name.as_bytes()].concat());
+    let bytes = hash.as_ref();
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) % 100
+}
+
+/// Switches on the subsystems named by flags that are on now and weren't in
+/// `before`.
+fn enable_flagged(flags: &FeatureFlags, before: Option<&FeatureFlags>) {
+    for (name, _) in flags.flags.iter().filter(|(_, on)| **on) {
+        let Some(subsystem) = name.strip_prefix(SUBSYSTEM_FLAG) else {
+            continue;
+        };
+        if before.is_some_and(|before| before.flags.get(name) == Some(&true)) {
+            continue;
+        }
+        if !FLAGGABLE_SUBSYSTEMS.contains(&subsystem) {
+            log::warn!("flag {} names a subsystem flags can't switch on", name);
+            continue;
+        }
+        match subsystems::set_subsystem_enabled(subsystem.to_string(), true) {
+            Ok(()) => crash::breadcrumb("updater", format!("flag {} is on", name)),
+            Err(e) => log::warn!("flag {} failed: {}", name, e),
+        }
+    }
+}
+
+/// Works out the manifest's flags for this install and keeps them.
+fn update_flags(manifest: &Manifest, channel: UpdateChannel) {
+    let install_id = INSTALL_ID.lock().unwrap().clone();
+    let flags = FeatureFlags {
+        fetched_ms: Some(crash::now_ms() as u64),
+        channel,
+        flags: manifest
+            .flags
+            .iter()
+            .map(|(name, rule)| (name.clone(), rule.enabled(name, channel, &install_id)))
+            .collect(),
+    };
+    if let Some(path) = FLAGS_FILE.lock().unwrap().as_ref() {
+        let saved = serde_json::to_string_pretty(&flags)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(path, json).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            log::warn!("failed to save feature flags: {}", e);
+        }
+    }
+    let before = std::mem::replace(&mut *FLAGS.lock().unwrap(), flags.clone());
+    enable_flagged(&flags, Some(&before));
+}
+
+fn platform() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn current_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// What a package of `version` for `platform` is signed over.
+fn signed_message(version: &str, platform: &str, digest: &[u8]) -> Vec<u8> {
+    [
+        version.as_bytes(),
+        b"\n",
+        platform.as_bytes(),
+        b"\n",
+        digest,
+    ]
+    .concat()
+}
+
+/// Checks `signature`, made with the key matching `key`, over the SHA-256 `digest`
+/// of a package of `version` for this platform.
+fn verify(key: &[u8], version: &str, digest: &[u8], signature: &[u8]) -> Result<(), String> {
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(&signed_message(version, &platform(), digest), signature)
+        .map_err(|_| "The update package's signature is invalid".to_string())
+}
+
+/// A download that reports its progress as events.
+struct Progress<'a, R> {
+    inner: R,
+    app: &'a AppHandle,
+    version: &'a str,
+    total: Option<u64>,
+    downloaded: u64,
+    reported: u64,
+}
+
+impl<R: Read> Read for Progress<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.downloaded += n as u64;
+        if self.downloaded - self.reported >= PROGRESS_STEP {
+            self.reported = self.downloaded;
+            events::emit(
+                self.app,
+                PROGRESS_EVENT,
+                DownloadProgress {
+                    version: self.version.to_string(),
+                    downloaded: self.downloaded,
+                    total: self.total,
+                },
+            );
+        }
+        Ok(n)
+    }
+}
+
+/// Writes a package, hashing it on the way.
+struct Hashing<W> {
+    inner: W,
+    digest: Context,
+}
+
+impl<W> Hashing<W> {
+    fn new(inner: W) -> Self {
+        Hashing {
+            inner,
+            digest: Context::new(&SHA256),
+        }
+    }
+}
+
+impl<W: Write> Write for Hashing<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, String> {
+    let mut bytes = [0; 8];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|e| format!("Truncated delta: {}", e))?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Rebuilds a package from `base`, the older version's, and `delta`, writing at
+/// most `limit` bytes to `out`. After `DELTA_MAGIC` come instructions of a byte
+/// each: `C` with a big-endian u64 offset and length copies that range of `base`,
+/// `I` with a u64 length inserts that many of the bytes that follow.
+fn apply_delta(
+    base: &mut (impl Read + Seek),
+    delta: &mut impl Read,
+    out: &mut impl Write,
+    limit: u64,
+) -> Result<u64, String> {
+    let mut magic = [0; 4];
+    delta
+        .read_exact(&mut magic)
+        .map_err(|e| format!("Truncated delta: {}", e))?;
+    if &magic != DELTA_MAGIC {
+        return Err("Not a delta package".to_string());
+    }
+    let mut written = 0u64;
+    loop {
+        let mut op = [0; 1];
+        match delta.read(&mut op) {
+            Ok(0) => return Ok(written),
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Download failed: {}", e)),
+        }
+        let (len, from_base) = match op[0] {
+            DELTA_COPY => {
+                let offset = read_u64(delta)?;
+                base.seek(SeekFrom::Start(offset))
+                    .map_err(|e| format!("Failed to read the old package: {}", e))?;
+                (read_u64(delta)?, true)
+            }
+            DELTA_INSERT => (read_u64(delta)?, false),
+            other => return Err(format!("Unknown delta instruction 0x{:02x}", other)),
+        };
+        if len > limit - written {
+            return Err("The delta makes a package larger than the manifest says".to_string());
+        }
+        let copied = if from_base {
+            io::copy(&mut base.by_ref().take(len), out)
+        } else {
+            io::copy(&mut delta.by_ref().take(len), out)
+        }
+        .map_err(|e| format!("Failed to apply delta: {}", e))?;
+        if copied != len {
+            return Err("The delta reaches past the end of its data".to_string());
+        }
+        written += len;
+    }
+}
+
+/// The package of `version` kept in `dir`, if it was downloaded here.
+fn kept_package(dir: &Path, version: &str) -> Option<PathBuf> {
+    fs::read_dir(dir.join(version))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| path.is_file() && path.extension().map_or(true, |ext| ext != "partial"))
+}
+
+/// Removes the packages in `dir` of versions other than `keep`.
+fn prune(dir: &Path, keep: &[&str]) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let kept = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| keep.contains(&name));
+        if path.is_dir() && !kept {
+            if let Err(e) = fs::remove_dir_all(&path) {
+                log::warn!("failed to remove old update {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// A package being saved to `path`.
+struct Download<'a> {
+    app: &'a AppHandle,
+    info: &'a UpdateInfo,
+    key: &'a [u8],
+    signature: Vec<u8>,
+    path: &'a Path,
+}
+
+impl Download<'_> {
+    fn progress<R>(&self, inner: R, total: Option<u64>) -> Progress<'_, R> {
+        Progress {
+            inner,
+            app: self.app,
+            version: &self.info.version,
+            total,
+            downloaded: 0,
+            reported: 0,
+        }
+    }
+
+    fn partial(&self) -> Result<(PathBuf, Hashing<File>), String> {
+        let partial = self.path.with_extension("partial");
+        let out = File::create(&partial).map_err(|e| format!("Failed to save update: {}", e))?;
+        Ok((partial, Hashing::new(out)))
+    }
+
+    /// Checks the signature of what was written to `partial` and moves it into place.
+    fn finish(&self, out: Hashing<File>, partial: &Path) -> Result<(), String> {
+        let Hashing { mut inner, digest } = out;
+        let flushed = inner.flush();
+        drop(inner);
+        let verified = flushed
+            .map_err(|e| format!("Failed to save update: {}", e))
+            .and_then(|_| {
+                verify(
+                    self.key,
+                    &self.info.version,
+                    digest.finish().as_ref(),
+                    &self.signature,
+                )
+            });
+        if let Err(e) = verified {
+            let _ = fs::remove_file(partial);
+            return Err(e);
+        }
+        fs::rename(partial, self.path).map_err(|e| format!("Failed to save update: {}", e))
+    }
+
+    /// Streams the whole package to disk.
+    fn full(&self, package: &Package) -> Result<(), String> {
+        let response = ureq::get(&package.url)
+            .call()
+            .map_err(|e| format!("Download failed: {}", e))?;
+        let total = package.size.or_else(|| {
+            response
+                .header("Content-Length")
+                .and_then(|len| len.parse().ok())
+        });
+        let mut reader = self.progress(response.into_reader(), total);
+        let (partial, mut out) = self.partial()?;
+        if let Err(e) = io::copy(&mut reader, &mut out) {
+            drop(out);
+            let _ = fs::remove_file(&partial);
+            return Err(format!("Download failed: {}", e));
+        }
+        self.finish(out, &partial)
+    }
+
+    /// Rebuilds the package from `base` and `delta`; it must come out at `size`
+    /// bytes or fewer and match the full package's signature.
+    fn delta(&self, delta: &Delta, size: u64, base: &Path) -> Result<(), String> {
+        let mut base =
+            File::open(base).map_err(|e| format!("Failed to read the old package: {}", e))?;
+        let response = ureq::get(&delta.url)
+            .call()
+            .map_err(|e| format!("Download failed: {}", e))?;
+        let mut reader = self.progress(response.into_reader(), delta.size);
+        let (partial, mut out) = self.partial()?;
+        if let Err(e) = apply_delta(&mut base, &mut reader, &mut out, size) {
+            drop(out);
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+        self.finish(out, &partial)
+    }
+}
+
+/// Saves the package of `info` to `path` in `dir`, from a delta against the running
+/// version's kept package where the manifest has one, or else whole.
+fn download(
+    app: &AppHandle,
+    info: &UpdateInfo,
+    package: &Package,
+    key: &[u8],
+    dir: &Path,
+    path: &Path,
+) -> Result<(), String> {
+    check_upgrade(&info.version)?;
+    let signature = STANDARD
+        .decode(package.signature.trim())
+        .map_err(|e| format!("Invalid package signature: {}", e))?;
+    let download = Download {
+        app,
+        info,
+        key,
+        signature,
+        path,
+    };
+    let current = current_version();
+    let delta = package.deltas.iter().find(|delta| delta.from == current);
+    if let (Some(delta), Some(size), Some(base)) =
+        (delta, package.size, kept_package(dir, &current))
+    {
+        match download.delta(delta, size, &base) {
+            Ok(()) => return Ok(()),
+            Err(e) => log::warn!("delta from {} failed, downloading whole: {}", current, e),
+        }
+    }
+    download.full(package)
+}
+
+/// Downloads the available update on a background thread, unless one is running
+/// or it's already downloaded.
+fn start_download(app: AppHandle) -> Result<(), String> {
+    let key = public_key()?;
+    let dir = DOWNLOAD_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("The updater isn't available")?;
+    let (info, package) = {
+        let mut state = STATE.lock().unwrap();
+        if state.downloading || state.ready.is_some() {
+            return Ok(());
+        }
+        let (info, package) = state.available.clone().ok_or("No update is available")?;
+        check_upgrade(&info.version)?;
+        state.downloading = true;
+        (info, package)
+    };
+    let file = package
+        .url
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.split(['?', '#']).next())
+        .filter(|name| !name.is_empty() && !name.contains(".."))
+        .unwrap_or("update")
+        .to_string();
+    let path = dir.join(&info.version).join(file);
+    if let Err(e) = fs::create_dir_all(dir.join(&info.version)) {
+        STATE.lock().unwrap().downloading = false;
+        return Err(e.to_string());
+    }
+
+    thread::Builder::new()
+        .name("updater".to_string())
+        .spawn(move || {
+            crash::breadcrumb("updater", format!("downloading {}", info.version));
+            let result = download(&app, &info, &package, &key, &dir, &path);
+            if result.is_ok() {
+                // The running version's package stays for the next delta.
+                prune(&dir, &[&current_version(), &info.version]);
+            }
+            let mut state = STATE.lock().unwrap();
+            state.downloading = false;
+            match result {
+                // A later check may have found a newer version meanwhile.
+                Ok(())
+                    if state
+                        .available
+                        .as_ref()
+                        .is_some_and(|(available, _)| available.version == info.version) =>
+                {
+                    state.ready = Some(path);
+                    drop(state);
+                    events::emit(&app, READY_EVENT, info);
+                }
+                Ok(()) => {}
+                Err(e) => {
+                    drop(state);
+                    log::warn!("update {} failed: {}", info.version, e);
+                    events::emit(&app, FAILED_EVENT, e);
+                }
+            }
+        })
+        .map_err(|e| {
+            STATE.lock().unwrap().downloading = false;
+            format!("Failed to spawn updater: {}", e)
+        })?;
+    Ok(())
+}
+
+fn check(app: AppHandle) -> Result<UpdateCheck, String> {
+    let config = CONFIG.lock().unwrap().clone();
+    let endpoint = config
+        .endpoint
+        .clone()
+        .ok_or("No update endpoint is configured")?;
+    public_key()?;
+    let response = ureq::get(&endpoint)
+        .call()
+        .map_err(|e| format!("Update check failed: {}", e))?;
+    let manifest: Manifest = serde_json::from_reader(response.into_reader())
+        .map_err(|e| format!("Invalid update manifest: {}", e))?;
+    update_flags(&manifest, config.channel);
+
+    let current = current_version();
+    let update = match manifest.release(config.channel) {
+        Some((release, package)) if newer(&release.version, &current) => Some((
+            UpdateInfo {
+                version: release.version.clone(),
+                notes: release.notes.clone(),
+                size: package.size,
+            },
+            package.clone(),
+        )),
+        _ => None,
+    };
+    {
+        let mut state = STATE.lock().unwrap();
+        let version = |found: &Option<(UpdateInfo, Package)>| {
+            found.as_ref().map(|(info, _)| info.version.clone())
+        };
+        if version(&state.available) != version(&update) {
+            state.ready = None;
+        }
+        state.available = update.clone();
+    }
+    if update.is_some() && config.auto_download {
+        start_download(app)?;
+    }
+
+    let state = STATE.lock().unwrap();
+    Ok(UpdateCheck {
+        current,
+        update: update.map(|(info, _)| info),
+        ready: state.ready.is_some(),
+        downloading: state.downloading,
+    })
+}
+
+/// Opens the verified package with the platform's installer.
+fn launch(path: &Path) -> Result<(), String> {
+    let is_msi = path.extension().is_some_and(|ext| ext == "msi");
+    let mut command = if cfg!(target_os = "windows") && is_msi {
+        let mut command = std::process::Command::new("msiexec");
+        command.arg("/i").arg(path).arg("/passive");
+        command
+    } else if cfg!(target_os = "windows") {
+        std::process::Command::new(path)
+    } else if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("open");
+        command.arg(path);
+        command
+    } else {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(path);
+        command
+    };
+    command
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start the installer: {}", e))
+}
+
+// ==========================================
+// UPDATER COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_update_config() -> UpdateConfig {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// Changes the channel and auto-download. The endpoint is left to updater.json, so
+/// the webview can't point the app somewhere else for its updates.
+#[tauri::command]
+pub fn set_update_config(config: UpdateConfig) -> Result<(), String> {
+    if config.endpoint != CONFIG.lock().unwrap().endpoint {
+        return Err("The update endpoint can only be changed in updater.json".to_string());
+    }
+    if let Some(path) = CONFIG_FILE.lock().unwrap().as_ref() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Failed to save updater config: {}", e))?;
+    }
+    *CONFIG.lock().unwrap() = config;
+    Ok(())
+}
+
+/// Asks the endpoint for a newer version on the configured channel and refreshes
+/// the feature flags, and downloads the update in the background if
+/// `auto_download` is set.
+#[tauri::command]
+pub async fn check_for_update(app: AppHandle) -> Result<UpdateCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || check(app))
+        .await
+        .map_err(|e| format!("Update check failed to run: {}", e))?
+}
+
+/// Downloads the update the last check found; progress arrives as events.
+#[tauri::command]
+pub fn download_update(app: AppHandle) -> Result<(), String> {
+    start_download(app)
+}
+
+/// The feature flags the endpoint last sent, as they apply to this install.
+#[tauri::command]
+pub fn get_feature_flags() -> FeatureFlags {
+    FLAGS.lock().unwrap().clone()
+}
+
+/// Starts the installer for the downloaded update and quits so it can replace
+/// the app.
+#[tauri::command]
+pub fn install_update(app: AppHandle) -> Result<(), String> {
+    let (path, version) = {
+        let state = STATE.lock().unwrap();
+        let path = state
+            .ready
+            .clone()
+            .ok_or("No verified update is ready to install")?;
+        let version = state
+            .available
+            .as_ref()
+            .map(|(info, _)| info.version.clone());
+        (path, version.unwrap_or_default())
+    };
+    check_upgrade(&version)?;
+    crash::breadcrumb("updater", format!("installing {}", path.display()));
+    launch(&path)?;
+    app.exit(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use super::*;
+
+    fn key_pair() -> Ed25519KeyPair {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn staged(rollout: u8, channels: Vec<UpdateChannel>) -> FlagRule {
+        FlagRule::Staged { rollout, channels }
+    }
+
+    fn delta(ops: &[(u8, u64, &[u8])]) -> Vec<u8> {
+        let mut delta = DELTA_MAGIC.to_vec();
+        for (op, offset, data) in ops {
+            delta.push(*op);
+            if *op == DELTA_COPY {
+                delta.extend(offset.to_be_bytes());
+                delta.extend((data.len() as u64).to_be_bytes());
+            } else {
+                delta.extend((data.len() as u64).to_be_bytes());
+                delta.extend(*data);
+            }
+        }
+        delta
+    }
+
+    #[test]
+    fn signatures_cover_version_platform_and_digest() {
+        let signer = key_pair();
+        let hash = digest(&SHA256, b"package");
+        let message = signed_message("9.0.0", &platform(), hash.as_ref());
+        let signature = signer.sign(&message);
+        let key = signer.public_key().as_ref();
+        assert!(verify(key, "9.0.0", hash.as_ref(), signature.as_ref()).is_ok());
+
+        let other = digest(&SHA256, b"tampered package");
+        assert!(verify(key, "9.0.0", other.as_ref(), signature.as_ref()).is_err());
+        assert!(verify(key, "9.0.1", hash.as_ref(), signature.as_ref()).is_err());
+        let elsewhere = signer.sign(&signed_message("9.0.0", "plan9-mips", hash.as_ref()));
+        assert!(verify(key, "9.0.0", hash.as_ref(), elsewhere.as_ref()).is_err());
+        let digest_only = signer.sign(hash.as_ref());
+        assert!(verify(key, "9.0.0", hash.as_ref(), digest_only.as_ref()).is_err());
+        let stranger = key_pair();
+        let foreign = stranger.public_key().as_ref();
+        assert!(verify(foreign, "9.0.0", hash.as_ref(), signature.as_ref()).is_err());
+    }
+
+    #[test]
+    fn signing_keys_must_be_ed25519_public_keys() {
+        assert!(decode_key(&STANDARD.encode([1; 32])).is_ok());
+        assert!(decode_key(&STANDARD.encode([1; 31])).is_err());
+        assert!(decode_key("not base64!").is_err());
+    }
+
+    #[test]
+    fn only_newer_versions_are_accepted() {
+        let current = current_version();
+        assert!(check_upgrade(&current).is_err());
+        assert!(check_upgrade("0.0.1").is_err());
+        assert!(check_upgrade("999.0.0").is_ok());
+        assert!(check_upgrade("999.0.0-beta.1").is_ok());
+        assert!(check_upgrade("999.0.0-a/../../x").is_err());
+    }
+
+    #[test]
+    fn deltas_copy_and_insert() {
+        let mut base = io::Cursor::new(b"hello old world".to_vec());
+        let patch = delta(&[
+            (DELTA_COPY, 0, b"hello "),
+            (DELTA_INSERT, 0, b"new"),
+            (DELTA_COPY, 9, b" world"),
+        ]);
+        let mut out = Vec::new();
+        let written = apply_delta(&mut base, &mut patch.as_slice(), &mut out, 100).unwrap();
+        assert_eq!(out, b"hello new world");
+        assert_eq!(written, 15);
+    }
+
+    #[test]
+    fn deltas_stay_within_their_data_and_limit() {
+        let base = b"short".to_vec();
+        let apply = |patch: Vec<u8>, limit| {
+            let mut out = Vec::new();
+            apply_delta(
+                &mut io::Cursor::new(&base),
+                &mut patch.as_slice(),
+                &mut out,
+                limit,
+            )
+        };
+        assert!(apply(delta(&[(DELTA_COPY, 3, b"long")]), 100).is_err());
+        assert!(apply(delta(&[(DELTA_INSERT, 0, b"too long")]), 4).is_err());
+        let mut truncated = delta(&[(DELTA_INSERT, 0, b"data")]);
+        truncated.pop();
+        assert!(apply(truncated, 100).is_err());
+        assert!(apply(b"NOPE".to_vec(), 100).is_err());
+        assert!(apply(delta(&[(b'X', 0, b"")]), 100).is_err());
+    }
+
+    #[test]
+    fn old_packages_are_pruned_but_kept_ones_stay() {
+        let dir = std::env::temp_dir().join(format!("updater-prune-{}", std::process::id()));
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            fs::create_dir_all(dir.join(version)).unwrap();
+            fs::write(dir.join(version).join("app.msi"), version).unwrap();
+        }
+        fs::write(dir.join("1.2.0").join("app.partial"), "").unwrap();
+        prune(&dir, &["1.1.0", "1.2.0"]);
+        assert!(kept_package(&dir, "1.0.0").is_none());
+        assert_eq!(
+            kept_package(&dir, "1.1.0"),
+            Some(dir.join("1.1.0").join("app.msi"))
+        );
+        assert_eq!(
+            kept_package(&dir, "1.2.0"),
+            Some(dir.join("1.2.0").join("app.msi"))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn endpoints_must_use_https() {
+        let config = |endpoint: &str| UpdateConfig {
+            endpoint: Some(endpoint.to_string()),
+            ..UpdateConfig::default()
+        };
+        assert!(validate(&config("https://updates.example.com/manifest.json")).is_ok());
+        assert!(validate(&config("http://updates.example.com/manifest.json")).is_err());
+    }
+
+    #[test]
+    fn rollout_buckets_are_stable_and_in_range() {
+        let id = b"install-1";
+        assert_eq!(rollout_bucket("picker", id), rollout_bucket("picker", id));
+        assert!((0..1000u32)
+            .map(|n| rollout_bucket("picker", &n.to_be_bytes()))
+            .all(|bucket| bucket < 100));
+        // Without an id an install is last in every rollout.
+        assert_eq!(rollout_bucket("picker", &[]), 99);
+    }
+
+    #[test]
+    fn rollouts_reach_about_their_share_of_installs() {
+        let rule = staged(30, Vec::new());
+        let reached = (0..1000u32)
+            .filter(|n| rule.enabled("picker", UpdateChannel::Stable, &n.to_be_bytes()))
+            .count();
+        assert!((230..370).contains(&reached), "reached {}", reached);
+    }
+
+    #[test]
+    fn rollout_extremes_and_channels() {
+        let id = b"install-1";
+        assert!(!staged(0, Vec::new()).enabled("picker", UpdateChannel::Stable, id));
+        assert!(staged(100, Vec::new()).enabled("picker", UpdateChannel::Stable, &[]));
+        let beta_only = staged(100, vec![UpdateChannel::Beta]);
+        assert!(beta_only.enabled("picker", UpdateChannel::Beta, id));
+        assert!(!beta_only.enabled("picker", UpdateChannel::Stable, id));
+        assert!(FlagRule::On(true).enabled("picker", UpdateChannel::Nightly, &[]));
+    }
+
+    #[test]
+    fn versions_compare_the_semver_way() {
+        assert!(newer("1.10.0", "1.9.0"));
+        assert!(newer("v1.2.0", "1.2.0-beta.2"));
+        assert!(newer("1.2.0-beta.10", "1.2.0-beta.2"));
+        assert!(newer("1.2.0-beta", "1.2.0-alpha.1"));
+        assert!(!newer("1.2.0+build.5", "1.2.0"));
+        assert!(!newer("garbage", "1.0.0"));
+    }
+}
@@ -0,0 +1,470 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    io::Cursor,
+    path::PathBuf,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    capture,
+    coords::{self, Bounds, Rect},
+    crash,
+    palette::{self, ColorRole},
+    secrets,
+};
+
+// ==========================================
+// Visual Regression
+// ==========================================
+
+/// Per-channel difference below which pixels are considered equal (absorbs
+/// anti-aliasing and color-profile noise).
+const DEFAULT_TOLERANCE: u8 = 16;
+
+static GOLDEN_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Golden {
+    pub name: String,
+    /// Native region the golden was captured from; asserts re-capture it by default.
+    pub rect: Rect,
+    pub width: u32,
+    pub height: u32,
+    pub captured_ms: u128,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RegionAssertion {
+    pub name: String,
+    pub matches: bool,
+    /// Fraction of pixels that differ beyond the tolerance.
+    pub diff_ratio: f64,
+    pub threshold: f64,
+    /// Written only on failure.
+    pub actual_path: Option<String>,
+    pub diff_path: Option<String>,
+}
+
+pub fn init(dir: PathBuf) {
+    *GOLDEN_DIR.lock().unwrap() = Some(dir);
+}
+
+fn golden_dir() -> Result<PathBuf, String> {
+    let dir = GOLDEN_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Visual regression storage is not initialized")?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn golden_path(name: &str, suffix: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err("Golden names may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(golden_dir()?.join(format!("{}{}", name, suffix)))
+}
+
+/// A golden file, decrypted if it was sealed.
+fn read_golden_file(name: &str, suffix: &str) -> Result<Vec<u8>, String> {
+    let data = fs::read(golden_path(name, suffix)?).map_err(|e| e.to_string())?;
+    secrets::open(data)
+}
+
+/// Writes a golden file, sealed if storage encryption is on.
+fn write_golden_file(name: &str, suffix: &str, data: &[u8]) -> Result<(), String> {
+    fs::write(golden_path(name, suffix)?, secrets::seal(data)?).map_err(|e| e.to_string())
+}
+
+fn load_golden(name: &str) -> Result<(Golden, RgbaImage), String> {
+    let meta =
+        read_golden_file(name, ".json").map_err(|_| format!("No golden named '{}'", name))?;
+    let golden: Golden = serde_json::from_slice(&meta).map_err(|e| e.to_string())?;
+    let image = image::load_from_memory(&read_golden_file(name, ".png")?)
+        .map_err(|e| format!("Failed to read golden image: {}", e))?
+        .to_rgba8();
+    Ok((golden, image))
+}
+
+/// Returns the fraction of differing pixels and an image highlighting them in red
+/// over a dimmed copy of the expected image.
+fn diff(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (f64, RgbaImage) {
+    let mut highlighted = RgbaImage::new(expected.width(), expected.height());
+    let mut differing = 0u64;
+    let alert = palette::color(ColorRole::Alert);
+
+    for (x, y, e) in expected.enumerate_pixels() {
+        let a = actual.get_pixel(x, y);
+        let differs = e.0[..3]
+            .iter()
+            .zip(&a.0[..3])
+            .any(|(e, a)| e.abs_diff(*a) > tolerance);
+
+        let out = if differs {
+            differing += 1;
+            let (r, g, b) = alert;
+            Rgba([r, g, b, 255])
+        } else {
+            Rgba([e[0] / 3, e[1] / 3, e[2] / 3, 255])
+        };
+        highlighted.put_pixel(x, y, out);
+    }
+
+    let total = (expected.width() as u64 * expected.height() as u64).max(1);
+    (differing as f64 / total as f64, highlighted)
+}
+
+pub fn capture_golden(name: &str, bounds: &Bounds) -> Result<Golden, String> {
+    let rect = coords::resolve_bounds(bounds)?;
+    let image = capture::capture_rect(rect)?;
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| format!("Failed to save golden image: {}", e))?;
+    write_golden_file(name, ".png", png.get_ref())?;
+
+    let golden = Golden {
+        name: name.to_string(),
+        rect,
+        width: image.width(),
+        height: image.height(),
+        captured_ms: crash::now_ms(),
+    };
+    let meta = serde_json::to_string_pretty(&golden).map_err(|e| e.to_string())?;
+    write_golden_file(name, ".json", meta.as_bytes())?;
+
+    Ok(golden)
+}
+
+/// Captures the golden's region (or `bounds`) and diffs it against the golden.
+/// Returns the capture, the differing fraction and the highlighted diff.
+fn compare(
+    name: &str,
+    bounds: Option<&Bounds>,
+    tolerance: Option<u8>,
+) -> Result<(RgbaImage, f64, Option<RgbaImage>), String> {
+    let (golden, expected) = load_golden(name)?;
+    let rect = match bounds {
+        Some(bounds) => coords::resolve_bounds(bounds)?,
+        None => golden.rect,
+    };
+    let actual = capture::capture_rect(rect)?;
+
+    if actual.dimensions() == expected.dimensions() {
+        let (ratio, highlighted) = diff(&expected, &actual, tolerance.unwrap_or(DEFAULT_TOLERANCE));
+        Ok((actual, ratio, Some(highlighted)))
+    } else {
+        // A size change (different monitor scale, resized region) is always a failure.
+        Ok((actual, 1.0, None))
+    }
+}
+
+/// Fraction of the region differing from golden `name`, without saving failure images;
+/// for polling.
+#[cfg(feature = "scripting")]
+pub fn region_diff(name: &str, bounds: Option<&Bounds>) -> Result<f64, String> {
+    compare(name, bounds, None).map(|(_, ratio, _)| ratio)
+}
+
+/// The region `region_diff` compares: `bounds`, or where the golden was captured.
+#[cfg(feature = "scripting")]
+pub fn golden_region(name: &str, bounds: Option<&Bounds>) -> Result<Rect, String> {
+    match bounds {
+        Some(bounds) => coords::resolve_bounds(bounds),
+        None => load_golden(name).map(|(golden, _)| golden.rect),
+    }
+}
+
+pub fn assert_region(
+    name: &str,
+    threshold: f64,
+    bounds: Option<&Bounds>,
+    tolerance: Option<u8>,
+) -> Result<RegionAssertion, String> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err("Threshold must be between 0 and 1".to_string());
+    }
+
+    let (actual, diff_ratio, highlighted) = compare(name, bounds, tolerance)?;
+
+    let matches = diff_ratio <= threshold;
+    let mut assertion = RegionAssertion {
+        name: name.to_string(),
+        matches,
+        diff_ratio,
+        threshold,
+        actual_path: None,
+        diff_path: None,
+    };
+
+    if !matches {
+        let actual_path = golden_path(name, ".actual.png")?;
+        actual
+            .save(&actual_path)
+            .map_err(|e| format!("Failed to save actual image: {}", e))?;
+        assertion.actual_path = Some(actual_path.to_string_lossy().into_owned());
+
+        if let Some(highlighted) = highlighted {
+            let diff_path = golden_path(name, ".diff.png")?;
+            highlighted
+                .save(&diff_path)
+                .map_err(|e| format!("Failed to save diff image: {}", e))?;
+            assertion.diff_path = Some(diff_path.to_string_lossy().into_owned());
+        }
+    }
+
+    crash::breadcrumb(
+        "visual",
+        format!("assert {} diff {:.4}", name, assertion.diff_ratio),
+    );
+    Ok(assertion)
+}
+
+pub fn list_goldens() -> Result<Vec<Golden>, String> {
+    let mut goldens: Vec<Golden> = fs::read_dir(golden_dir()?)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| secrets::open(fs::read(entry.path()).ok()?).ok())
+        .filter_map(|data| serde_json::from_slice(&data).ok())
+        .collect();
+    goldens.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(goldens)
+}
+
+/// Raw golden files, for bundling into exported scripts.
+#[cfg(feature = "scripting")]
+pub fn read_golden_files(name: &str) -> Result<(Golden, Vec<u8>), String> {
+    let (golden, _) = load_golden(name)?;
+    Ok((golden, read_golden_file(name, ".png")?))
+}
+
+#[cfg(feature = "scripting")]
+pub fn write_golden_files(golden: &Golden, png: &[u8]) -> Result<(), String> {
+    let image = image::load_from_memory(png)
+        .map_err(|e| format!("Golden '{}' is not a valid image: {}", golden.name, e))?;
+    if (image.width(), image.height()) != (golden.width, golden.height) {
+        return Err(format!(
+            "Golden '{}' image size does not match its metadata",
+            golden.name
+        ));
+    }
+
+    write_golden_file(&golden.name, ".png", png)?;
+    let meta = serde_json::to_string_pretty(golden).map_err(|e| e.to_string())?;
+    write_golden_file(&golden.name, ".json", meta.as_bytes())
+}
+
+pub fn delete_golden(name: &str) -> Result<(), String> {
+    for suffix in [".png", ".json", ".actual.png", ".diff.png"] {
+        let path = golden_path(name, suffix)?;
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+// ==========================================
+// CHANGE DETECTION
+// ==========================================
+
+// Successive captures are reduced to a small grid of mean luminance values, so
+// comparing them is cheap and immune to single-pixel noise such as a blinking caret.
+// Identical grids are recognized by hash without comparing cells.
+
+/// Fingerprint grid size along the longer side of the region.
+const FINGERPRINT_CELLS: u32 = 32;
+/// Luminance difference (0–255) for a cell to count as changed.
+const CELL_TOLERANCE: u8 = 12;
+const CHANGE_POLL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ChangeTrigger {
+    /// The region differs from how it looked when the wait began.
+    #[default]
+    Changed,
+    /// The region stopped changing for `ms`, e.g. a spinner that went away.
+    Settled { ms: u64 },
+}
+
+#[derive(Clone, Serialize)]
+pub struct RegionChange {
+    /// Fraction of cells that differed in the last meaningful change; 0 if none was seen.
+    pub changed_ratio: f64,
+    pub samples: u32,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Clone)]
+struct Fingerprint {
+    cells: Vec<u8>,
+    hash: u64,
+}
+
+impl Fingerprint {
+    fn capture(rect: Rect) -> Result<Self, String> {
+        let image = capture::capture_rect(rect)?;
+        let longer = image.width().max(image.height()) as f64;
+        let scale = (FINGERPRINT_CELLS as f64 / longer).min(1.0);
+        let (width, height) = (
+            ((image.width() as f64 * scale).round() as u32).max(1),
+            ((image.height() as f64 * scale).round() as u32).max(1),
+        );
+        let cells: Vec<u8> = imageops::thumbnail(&image, width, height)
+            .pixels()
+            .map(|p| ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8)
+            .collect();
+
+        let mut hasher = DefaultHasher::new();
+        cells.hash(&mut hasher);
+        Ok(Self {
+            hash: hasher.finish(),
+            cells,
+        })
+    }
+
+    /// Fraction of cells that differ beyond `CELL_TOLERANCE`.
+    fn diff(&self, other: &Fingerprint) -> f64 {
+        if self.hash == other.hash {
+            return 0.0;
+        }
+        if self.cells.len() != other.cells.len() {
+            return 1.0;
+        }
+        let changed = self
+            .cells
+            .iter()
+            .zip(&other.cells)
+            .filter(|(a, b)| a.abs_diff(**b) > CELL_TOLERANCE)
+            .count();
+        changed as f64 / self.cells.len().max(1) as f64
+    }
+}
+
+/// Polls `bounds` until it changes, or settles, per `until`. `sensitivity` (0–1)
+/// sets how small a change counts: at 0.9, at least 10% of the region must change.
+pub fn wait_for_change(
+    bounds: &Bounds,
+    sensitivity: f64,
+    timeout: Duration,
+    until: ChangeTrigger,
+) -> Result<RegionChange, String> {
+    if !(0.0..=1.0).contains(&sensitivity) {
+        return Err("Sensitivity must be between 0 and 1".to_string());
+    }
+    let rect = coords::resolve_bounds(bounds)?;
+    let meaningful = |ratio: f64| ratio > 0.0 && ratio >= 1.0 - sensitivity;
+
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let baseline = Fingerprint::capture(rect)?;
+    let mut previous = baseline.clone();
+    let mut stable_since = started;
+    let mut samples = 1;
+    let mut changed_ratio = 0.0;
+
+    loop {
+        thread::sleep(CHANGE_POLL.min(deadline.saturating_duration_since(Instant::now())));
+        let current = Fingerprint::capture(rect)?;
+        samples += 1;
+
+        let done = match until {
+            ChangeTrigger::Changed => {
+                let ratio = current.diff(&baseline);
+                if meaningful(ratio) {
+                    changed_ratio = ratio;
+                }
+                meaningful(ratio)
+            }
+            ChangeTrigger::Settled { ms } => {
+                let ratio = current.diff(&previous);
+                if meaningful(ratio) {
+                    changed_ratio = ratio;
+                    stable_since = Instant::now();
+                }
+                stable_since.elapsed() >= Duration::from_millis(ms)
+            }
+        };
+        if done {
+            crash::breadcrumb("visual", format!("region change after {} samples", samples));
+            return Ok(RegionChange {
+                changed_ratio,
+                samples,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Timed out after {}ms waiting for the region to {}",
+                timeout.as_millis(),
+                match until {
+                    ChangeTrigger::Changed => "change",
+                    ChangeTrigger::Settled { .. } => "settle",
+                }
+            ));
+        }
+        previous = current;
+    }
+}
+
+// ==========================================
+// VISUAL REGRESSION COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn capture_region_golden(name: String, bounds: Bounds) -> Result<Golden, String> {
+    capture_golden(&name, &bounds)
+}
+
+#[tauri::command]
+pub fn assert_region_matches(
+    name: String,
+    threshold: f64,
+    bounds: Option<Bounds>,
+    tolerance: Option<u8>,
+) -> Result<RegionAssertion, String> {
+    assert_region(&name, threshold, bounds.as_ref(), tolerance)
+}
+
+#[tauri::command]
+pub fn list_region_goldens() -> Result<Vec<Golden>, String> {
+    list_goldens()
+}
+
+#[tauri::command]
+pub fn delete_region_golden(name: String) -> Result<(), String> {
+    delete_golden(&name)
+}
+
+/// Runs off the main thread: it can block for the whole timeout.
+#[tauri::command(async)]
+pub fn wait_for_region_change(
+    bounds: Bounds,
+    sensitivity: f64,
+    timeout_ms: u64,
+    until: Option<ChangeTrigger>,
+) -> Result<RegionChange, String> {
+    wait_for_change(
+        &bounds,
+        sensitivity,
+        Duration::from_millis(timeout_ms),
+        until.unwrap_or_default(),
+    )
+}
@@ -0,0 +1,334 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+use tauri::AppHandle;
+
+use crate::actions::Action;
+
+// ==========================================
+// Voice Commands
+// ==========================================
+
+// Listens for a fixed set of phrases ("privacy on", "next scene") with the OS's
+// offline command recognizers: SAPI's in-process recognizer on Windows and
+// NSSpeechRecognizer on macOS. Nothing leaves the machine, and the "voice-commands"
+// subsystem is off by default so the microphone is only opened on request.
+
+/// Phrase (lowercase) → action.
+static COMMANDS: Lazy<Mutex<BTreeMap<String, Action>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+/// Set while listening; the recognizer restarts with it when phrases change.
+static APP: Lazy<Mutex<Option<AppHandle>>> = Lazy::new(|| Mutex::new(None));
+
+fn phrases() -> Vec<String> {
+    COMMANDS.lock().unwrap().keys().cloned().collect()
+}
+
+/// Called by the recognizer with the phrase it heard.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn heard(text: &str) {
+    let phrase = text.trim().to_lowercase();
+    let action = COMMANDS.lock().unwrap().get(&phrase).cloned();
+    let app = APP.lock().unwrap().clone();
+    if let (Some(action), Some(app)) = (action, app) {
+        crate::actions::run(&app, &action, &format!("voice:{}", phrase));
+    }
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    platform::start(&app, phrases())?;
+    *APP.lock().unwrap() = Some(app);
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    if let Some(app) = APP.lock().unwrap().take() {
+        platform::stop(&app);
+    }
+    Ok(())
+}
+
+/// Recognizers compile their grammar up front, so a running one is rebuilt.
+fn reload() -> Result<(), String> {
+    let app = APP.lock().unwrap().clone();
+    match app {
+        Some(app) => {
+            platform::stop(&app);
+            platform::start(&app, phrases())
+        }
+        None => Ok(()),
+    }
+}
+
+// ==========================================
+// VOICE COMMANDS
+// ==========================================
+
+#[tauri::command]
+pub fn get_voice_commands() -> BTreeMap<String, Action> {
+    COMMANDS.lock().unwrap().clone()
+}
+
+/// Maps `phrase` to `action`, or removes it when `action` is omitted.
+#[tauri::command]
+pub fn set_voice_command(phrase: String, action: Option<Action>) -> Result<(), String> {
+    let phrase = phrase.trim().to_lowercase();
+    if phrase.is_empty() {
+        return Err("Phrase must not be empty".to_string());
+    }
+    {
+        let mut commands = COMMANDS.lock().unwrap();
+        match action {
+            Some(action) => {
+                commands.insert(phrase, action);
+            }
+            None => {
+                commands.remove(&phrase);
+            }
+        }
+    }
+    reload()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            mpsc,
+        },
+        thread,
+    };
+
+    use tauri::AppHandle;
+    use windows::core::{w, Interface, HSTRING, PCWSTR, PWSTR};
+    use windows::Win32::Media::Speech::{
+        ISpRecoResult, ISpRecognizer, SPRAF_Active, SPRAF_TopLevel, SpInprocRecognizer,
+        SpMMAudioIn, SPEI_RECOGNITION, SPEI_RESERVED1, SPEI_RESERVED2, SPEVENT, SPRS_ACTIVE,
+        SPSTATEHANDLE, SPWT_LEXICAL,
+    };
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL,
+        COINIT_APARTMENTTHREADED,
+    };
+
+    use super::heard;
+
+    /// Retrieves the whole phrase from `ISpPhrase::GetText`.
+    const WHOLE_PHRASE: u32 = u32::MAX;
+    const POLL_MS: u32 = 250;
+
+    static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+    unsafe fn listen(phrases: &[String], generation: u64, ready: mpsc::Sender<Result<(), String>>) {
+        let setup = || -> windows::core::Result<_> {
+            let recognizer: ISpRecognizer =
+                CoCreateInstance(&SpInprocRecognizer, None, CLSCTX_ALL)?;
+            let audio: windows::core::IUnknown = CoCreateInstance(&SpMMAudioIn, None, CLSCTX_ALL)?;
+            recognizer.SetInput(&audio, true)?;
+
+            let context = recognizer.CreateRecoContext()?;
+            let grammar = context.CreateGrammar(0)?;
+            let mut state = SPSTATEHANDLE::default();
+            grammar.GetRule(
+                w!("commands"),
+                0,
+                (SPRAF_TopLevel.0 | SPRAF_Active.0) as u32,
+                true,
+                &mut state,
+            )?;
+            for phrase in phrases {
+                grammar.AddWordTransition(
+                    state,
+                    SPSTATEHANDLE::default(),
+                    &HSTRING::from(phrase.as_str()),
+                    w!(" "),
+                    SPWT_LEXICAL,
+                    1.0,
+                    std::ptr::null(),
+                )?;
+            }
+            grammar.Commit(0)?;
+            grammar.SetRuleState(PCWSTR::null(), std::ptr::null_mut(), SPRS_ACTIVE)?;
+
+            let interest = [SPEI_RECOGNITION, SPEI_RESERVED1, SPEI_RESERVED2]
+                .iter()
+                .fold(0u64, |m, e| m | (1 << e.0));
+            context.SetInterest(interest, interest)?;
+            context.SetNotifyWin32Event()?;
+            Ok((recognizer, context, grammar))
+        };
+        let (_recognizer, context, _grammar) = match setup() {
+            Ok(parts) => parts,
+            Err(e) => {
+                let _ = ready.send(Err(format!("Failed to start speech recognizer: {}", e)));
+                return;
+            }
+        };
+        let _ = ready.send(Ok(()));
+
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            if context.WaitForNotifyEvent(POLL_MS).is_err() {
+                continue;
+            }
+            loop {
+                let mut event = SPEVENT::default();
+                let mut fetched = 0;
+                if context.GetEvents(1, &mut event, &mut fetched).is_err() || fetched == 0 {
+                    break;
+                }
+                if event._bitfield & 0xFFFF != SPEI_RECOGNITION.0 || event.lParam.0 == 0 {
+                    continue;
+                }
+                // Recognition events carry a reference to the result, released on drop.
+                let result = ISpRecoResult::from_raw(event.lParam.0 as *mut _);
+                let mut text = PWSTR::null();
+                if result
+                    .GetText(WHOLE_PHRASE, WHOLE_PHRASE, true, &mut text, None)
+                    .is_ok()
+                    && !text.is_null()
+                {
+                    if let Ok(phrase) = text.to_string() {
+                        heard(&phrase);
+                    }
+                    CoTaskMemFree(Some(text.0 as *const _));
+                }
+            }
+        }
+    }
+
+    pub fn start(_app: &AppHandle, phrases: Vec<String>) -> Result<(), String> {
+        let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        if phrases.is_empty() {
+            return Ok(());
+        }
+        let (ready_tx, ready_rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("voice-commands".to_string())
+            .spawn(move || unsafe {
+                let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+                listen(&phrases, generation, ready_tx);
+                CoUninitialize();
+            })
+            .map_err(|e| format!("Failed to spawn speech recognizer: {}", e))?;
+
+        ready_rx
+            .recv()
+            .map_err(|_| "Speech recognizer thread exited".to_string())?
+    }
+
+    pub fn stop(_app: &AppHandle) {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    };
+
+    use cocoa::base::{id, nil, NO};
+    use cocoa::foundation::NSString;
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Class, Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+    use tauri::AppHandle;
+
+    use super::heard;
+
+    const DELEGATE_CLASS: &str = "ScreenShareHostVoiceDelegate";
+
+    static RECOGNIZER: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn did_recognize(_this: &Object, _sel: Sel, _recognizer: id, command: id) {
+        unsafe {
+            let utf8: *const std::os::raw::c_char = msg_send![command, UTF8String];
+            if !utf8.is_null() {
+                heard(&std::ffi::CStr::from_ptr(utf8).to_string_lossy());
+            }
+        }
+    }
+
+    fn delegate_class() -> &'static Class {
+        if let Some(class) = Class::get(DELEGATE_CLASS) {
+            return class;
+        }
+        let mut decl =
+            ClassDecl::new(DELEGATE_CLASS, class!(NSObject)).expect("voice delegate class");
+        unsafe {
+            decl.add_method(
+                sel!(speechRecognizer:didRecognizeCommand:),
+                did_recognize as extern "C" fn(&Object, Sel, id, id),
+            );
+        }
+        decl.register()
+    }
+
+    /// Runs on the main thread, whose run loop delivers recognitions.
+    unsafe fn listen(phrases: &[String]) -> Result<(), String> {
+        let recognizer: id = msg_send![class!(NSSpeechRecognizer), alloc];
+        let recognizer: id = msg_send![recognizer, init];
+        if recognizer == nil {
+            return Err("Speech recognition is unavailable".to_string());
+        }
+        let commands: id = msg_send![class!(NSMutableArray), array];
+        for phrase in phrases {
+            let phrase = NSString::alloc(nil).init_str(phrase);
+            let _: () = msg_send![commands, addObject: phrase];
+            let _: () = msg_send![phrase, release];
+        }
+        let delegate: id = msg_send![delegate_class(), new];
+        let _: () = msg_send![recognizer, setCommands: commands];
+        let _: () = msg_send![recognizer, setDelegate: delegate];
+        let _: () = msg_send![recognizer, setListensInForegroundOnly: NO];
+        let _: () = msg_send![recognizer, startListening];
+        RECOGNIZER.store(recognizer as usize, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn start(app: &AppHandle, phrases: Vec<String>) -> Result<(), String> {
+        if phrases.is_empty() {
+            return Ok(());
+        }
+        let (tx, rx) = mpsc::channel();
+        app.run_on_main_thread(move || {
+            let _ = tx.send(unsafe { listen(&phrases) });
+        })
+        .map_err(|e| e.to_string())?;
+        rx.recv()
+            .map_err(|_| "Speech recognizer did not start".to_string())?
+    }
+
+    pub fn stop(app: &AppHandle) {
+        let recognizer = RECOGNIZER.swap(0, Ordering::SeqCst);
+        if recognizer == 0 {
+            return;
+        }
+        let _ = app.run_on_main_thread(move || unsafe {
+            let recognizer = recognizer as id;
+            let _: () = msg_send![recognizer, stopListening];
+            let delegate: id = msg_send![recognizer, delegate];
+            let _: () = msg_send![recognizer, setDelegate: nil];
+            let _: () = msg_send![delegate, release];
+            let _: () = msg_send![recognizer, release];
+        });
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use tauri::AppHandle;
+
+    // There is no system speech service to rely on; an embedded recognizer (vosk)
+    // would need a bundled model and is not wired up.
+    pub fn start(_app: &AppHandle, phrases: Vec<String>) -> Result<(), String> {
+        if phrases.is_empty() {
+            return Ok(());
+        }
+        Err("Voice commands are not supported on this platform".to_string())
+    }
+
+    pub fn stop(_app: &AppHandle) {}
+}
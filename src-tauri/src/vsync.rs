@@ -0,0 +1,189 @@
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+// ==========================================
+// Display Refresh Synchronization
+// ==========================================
+
+// Blocks until the next display refresh, so automation stepping through animations
+// samples or acts once per frame instead of at arbitrary points between frames.
+// Windows waits on DWM composition, macOS on a CVDisplayLink for the main display.
+// Elsewhere, and when those are unavailable, a timer ticks at a nominal 60 Hz on a
+// fixed phase, which keeps successive waits frame-spaced but not tied to scan-out.
+
+const FALLBACK_INTERVAL: Duration = Duration::from_nanos(16_666_667);
+
+/// Each platform constructs only its own source.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VsyncSource {
+    Dwm,
+    DisplayLink,
+    Timer,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VsyncReport {
+    pub frames: u32,
+    pub source: VsyncSource,
+    pub elapsed_us: u64,
+    /// Mean time between the refreshes waited on.
+    pub interval_us: f64,
+}
+
+/// Phase reference for the timer fallback.
+static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+
+fn timer_wait(count: u32) {
+    let phase = EPOCH.elapsed().as_nanos() % FALLBACK_INTERVAL.as_nanos();
+    let first = FALLBACK_INTERVAL - Duration::from_nanos(phase as u64);
+    std::thread::sleep(first + FALLBACK_INTERVAL * (count - 1));
+}
+
+/// Waits for `count` display refreshes (at least one).
+pub fn wait(count: u32) -> VsyncReport {
+    let count = count.max(1);
+    let started = Instant::now();
+    let source = match platform::wait(count) {
+        Ok(source) => source,
+        Err(e) => {
+            log::debug!("vsync unavailable, using timer: {}", e);
+            timer_wait(count);
+            VsyncSource::Timer
+        }
+    };
+    let elapsed = started.elapsed();
+    VsyncReport {
+        frames: count,
+        source,
+        elapsed_us: elapsed.as_micros() as u64,
+        interval_us: elapsed.as_secs_f64() * 1_000_000.0 / count as f64,
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Graphics::Dwm::DwmFlush;
+
+    use super::VsyncSource;
+
+    /// DwmFlush returns after the next composition pass, which DWM paces to vblank.
+    pub fn wait(count: u32) -> Result<VsyncSource, String> {
+        for _ in 0..count {
+            unsafe { DwmFlush() }.map_err(|e| format!("DwmFlush failed: {}", e))?;
+        }
+        Ok(VsyncSource::Dwm)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{
+        ffi::c_void,
+        sync::{Condvar, Mutex},
+    };
+
+    use super::VsyncSource;
+
+    type CVDisplayLinkRef = *mut c_void;
+    type OutputCallback = extern "C" fn(
+        CVDisplayLinkRef,
+        *const c_void,
+        *const c_void,
+        u64,
+        *mut u64,
+        *mut c_void,
+    ) -> i32;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGMainDisplayID() -> u32;
+    }
+
+    #[link(name = "CoreVideo", kind = "framework")]
+    extern "C" {
+        fn CVDisplayLinkCreateWithCGDisplay(display: u32, link: *mut CVDisplayLinkRef) -> i32;
+        fn CVDisplayLinkSetOutputCallback(
+            link: CVDisplayLinkRef,
+            callback: OutputCallback,
+            context: *mut c_void,
+        ) -> i32;
+        fn CVDisplayLinkStart(link: CVDisplayLinkRef) -> i32;
+        fn CVDisplayLinkStop(link: CVDisplayLinkRef) -> i32;
+        fn CVDisplayLinkRelease(link: CVDisplayLinkRef);
+    }
+
+    /// Refreshes seen so far, signalled from the display link's thread.
+    type Ticks = (Mutex<u32>, Condvar);
+
+    extern "C" fn on_refresh(
+        _link: CVDisplayLinkRef,
+        _now: *const c_void,
+        _output: *const c_void,
+        _flags_in: u64,
+        _flags_out: *mut u64,
+        context: *mut c_void,
+    ) -> i32 {
+        let ticks = unsafe { &*(context as *const Ticks) };
+        *ticks.0.lock().unwrap() += 1;
+        ticks.1.notify_all();
+        0
+    }
+
+    pub fn wait(count: u32) -> Result<VsyncSource, String> {
+        let ticks: Ticks = (Mutex::new(0), Condvar::new());
+        unsafe {
+            let mut link: CVDisplayLinkRef = std::ptr::null_mut();
+            if CVDisplayLinkCreateWithCGDisplay(CGMainDisplayID(), &mut link) != 0 || link.is_null()
+            {
+                return Err("CVDisplayLinkCreateWithCGDisplay failed".to_string());
+            }
+            CVDisplayLinkSetOutputCallback(link, on_refresh, &ticks as *const Ticks as *mut c_void);
+            if CVDisplayLinkStart(link) != 0 {
+                CVDisplayLinkRelease(link);
+                return Err("CVDisplayLinkStart failed".to_string());
+            }
+
+            // A display that stops refreshing (asleep, disconnected) must not hang us.
+            let limit = super::FALLBACK_INTERVAL * (count + 10) * 4;
+            let seen = ticks
+                .1
+                .wait_timeout_while(ticks.0.lock().unwrap(), limit, |seen| *seen < count)
+                .map(|(seen, _)| *seen)
+                .unwrap_or(0);
+
+            // Stop waits for an in-progress callback, so `ticks` outlives every call.
+            CVDisplayLinkStop(link);
+            CVDisplayLinkRelease(link);
+            if seen < count {
+                return Err(format!(
+                    "Display link delivered {} of {} refreshes within {:?}",
+                    seen, count, limit
+                ));
+            }
+        }
+        Ok(VsyncSource::DisplayLink)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use super::VsyncSource;
+
+    pub fn wait(_count: u32) -> Result<VsyncSource, String> {
+        Err("No refresh source on this platform".to_string())
+    }
+}
+
+// ==========================================
+// VSYNC COMMANDS
+// ==========================================
+
+/// Waits for `count` display refreshes (default 1).
+#[tauri::command]
+pub async fn wait_for_vsync(count: Option<u32>) -> VsyncReport {
+    wait(count.unwrap_or(1))
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    crash, events,
+    speech::{self, Announcement},
+};
+
+// ==========================================
+// Watchdog
+// ==========================================
+
+const CHECK_INTERVAL: Duration = Duration::from_millis(1000);
+/// How often supervised threads beat, in milliseconds, e.g. as a thread timer.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub const HEARTBEAT_MS: u32 = 1000;
+/// Silence after which a supervised thread counts as stalled.
+pub const THREAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub type RestartFn = Arc<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+struct Entry {
+    last_beat: Instant,
+    timeout: Duration,
+    restart: Option<RestartFn>,
+    restarts: u32,
+    stalled: bool,
+    /// Set by `suspend` until the next heartbeat.
+    suspended: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub last_beat_ms_ago: u128,
+    pub timeout_ms: u128,
+    pub restarts: u32,
+    pub stalled: bool,
+}
+
+#[derive(Clone, Serialize)]
+struct RestartedEvent {
+    subsystem: String,
+    stalled_ms: u128,
+    restarted: bool,
+    error: Option<String>,
+}
+
+static WATCHDOG: Lazy<Mutex<HashMap<String, Entry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Bumped on every start/stop; a supervisor thread exits once its generation is stale.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Starts supervising `name`. The subsystem must call `heartbeat` more often than `timeout`.
+pub fn register(name: &str, timeout: Duration, restart: Option<RestartFn>) {
+    let mut entries = WATCHDOG.lock().unwrap();
+    entries.insert(
+        name.to_string(),
+        Entry {
+            last_beat: Instant::now(),
+            timeout,
+            restart,
+            restarts: 0,
+            stalled: false,
+            suspended: false,
+        },
+    );
+}
+
+pub fn unregister(name: &str) {
+    WATCHDOG.lock().unwrap().remove(name);
+}
+
+pub fn heartbeat(name: &str) {
+    if let Some(entry) = WATCHDOG.lock().unwrap().get_mut(name) {
+        entry.last_beat = Instant::now();
+        entry.stalled = false;
+        entry.suspended = false;
+    }
+}
+
+/// Holds off stall detection for `name` until its next heartbeat, e.g. while a worker
+/// runs a job of unbounded length.
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub fn suspend(name: &str) {
+    if let Some(entry) = WATCHDOG.lock().unwrap().get_mut(name) {
+        entry.suspended = true;
+    }
+}
+
+pub fn start(app: AppHandle) -> Result<(), String> {
+    // The main thread can't be replaced, so a stall there is only reported.
+    register("ui", THREAD_TIMEOUT, None);
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    thread::Builder::new()
+        .name("watchdog".to_string())
+        .spawn(move || loop {
+            thread::sleep(CHECK_INTERVAL);
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                break;
+            }
+
+            // The UI thread owns the native overlay windows; ping it through the event loop.
+            let _ = app.run_on_main_thread(|| heartbeat("ui"));
+
+            for restarted in restart_stalled() {
+                speech::announce(
+                    Announcement::SubsystemRestarted,
+                    &format!("{} was restarted", restarted.subsystem),
+                );
+                events::emit(&app, "subsystem://restarted", restarted);
+            }
+        })
+        .map_err(|e| format!("Failed to spawn watchdog thread: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    unregister("ui");
+    Ok(())
+}
+
+/// Restarts the subsystems that stopped beating and have a way to restart.
+fn restart_stalled() -> Vec<RestartedEvent> {
+    let mut reports = Vec::new();
+    for (name, stalled_for, restart) in collect_stalled() {
+        log::error!(
+            "subsystem '{}' unresponsive for {}ms; recent native operations: {:?}",
+            name,
+            stalled_for.as_millis(),
+            crash::recent_breadcrumbs()
+                .iter()
+                .rev()
+                .take(10)
+                .map(|b| format!("{}: {}", b.category, b.message))
+                .collect::<Vec<_>>()
+        );
+        crash::breadcrumb("watchdog", format!("{} stalled", name));
+
+        let Some(restart) = restart else {
+            continue;
+        };
+
+        let result = restart();
+        if let Some(entry) = WATCHDOG.lock().unwrap().get_mut(&name) {
+            entry.restarts += 1;
+            entry.last_beat = Instant::now();
+            entry.stalled = false;
+        }
+        if let Err(e) = &result {
+            log::error!("failed to restart subsystem '{}': {}", name, e);
+        }
+
+        reports.push(RestartedEvent {
+            subsystem: name,
+            stalled_ms: stalled_for.as_millis(),
+            restarted: result.is_ok(),
+            error: result.err(),
+        });
+    }
+    reports
+}
+
+/// Marks newly stalled entries and returns them so restarts run without holding the lock.
+fn collect_stalled() -> Vec<(String, Duration, Option<RestartFn>)> {
+    let mut entries = WATCHDOG.lock().unwrap();
+    let now = Instant::now();
+
+    entries
+        .iter_mut()
+        .filter_map(|(name, entry)| {
+            let elapsed = now.duration_since(entry.last_beat);
+            if elapsed <= entry.timeout || entry.stalled || entry.suspended {
+                return None;
+            }
+            entry.stalled = true;
+            Some((name.clone(), elapsed, entry.restart.clone()))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_watchdog_status() -> Vec<SubsystemHealth> {
+    let entries = WATCHDOG.lock().unwrap();
+    let now = Instant::now();
+
+    let mut status: Vec<SubsystemHealth> = entries
+        .iter()
+        .map(|(name, entry)| SubsystemHealth {
+            name: name.clone(),
+            last_beat_ms_ago: now.duration_since(entry.last_beat).as_millis(),
+            timeout_ms: entry.timeout.as_millis(),
+            restarts: entry.restarts,
+            stalled: entry.stalled,
+        })
+        .collect();
+    status.sort_by(|a, b| a.name.cmp(&b.name));
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+
+    use super::*;
+
+    #[test]
+    fn stalled_subsystems_are_restarted() {
+        static RESTARTS: AtomicU32 = AtomicU32::new(0);
+        register(
+            "test-stalled",
+            Duration::from_millis(10),
+            Some(Arc::new(|| {
+                RESTARTS.fetch_add(1, Ordering::SeqCst);
+                heartbeat("test-stalled");
+                Ok(())
+            })),
+        );
+        register(
+            "test-beating",
+            Duration::from_secs(60),
+            Some(Arc::new(|| Ok(()))),
+        );
+        thread::sleep(Duration::from_millis(30));
+
+        let restarted: Vec<_> = restart_stalled()
+            .into_iter()
+            .filter(|r| r.subsystem.starts_with("test-"))
+            .collect();
+        assert_eq!(restarted.len(), 1);
+        assert_eq!(restarted[0].subsystem, "test-stalled");
+        assert!(restarted[0].restarted);
+        assert_eq!(RESTARTS.load(Ordering::SeqCst), 1);
+
+        let health = get_watchdog_status();
+        let stalled = health.iter().find(|h| h.name == "test-stalled").unwrap();
+        assert_eq!(stalled.restarts, 1);
+        assert!(!stalled.stalled);
+        unregister("test-stalled");
+        unregister("test-beating");
+    }
+
+    #[test]
+    fn suspended_subsystems_are_left_alone() {
+        register(
+            "test-suspended",
+            Duration::from_millis(10),
+            Some(Arc::new(|| Ok(()))),
+        );
+        suspend("test-suspended");
+        thread::sleep(Duration::from_millis(30));
+
+        assert!(!restart_stalled()
+            .iter()
+            .any(|r| r.subsystem == "test-suspended"));
+        unregister("test-suspended");
+    }
+}
@@ -0,0 +1,596 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+
+use crate::{
+    capture,
+    coords::{self, CoordinateSpace},
+    crash, input, middleware,
+    timing::Scheduler,
+};
+
+// ==========================================
+// WebDriver Endpoint
+// ==========================================
+
+// A subset of W3C WebDriver: sessions, the element-less actions API, screenshots and the
+// window rect. The "window" is the whole virtual desktop, and viewport coordinates are
+// physical desktop pixels.
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:4444";
+
+const MAX_BODY: usize = 16 * 1024 * 1024;
+const MAX_HEADER_LINE: u64 = 8 * 1024;
+/// Interval between interpolated pointer positions during a timed pointerMove.
+const MOVE_STEP: Duration = Duration::from_millis(8);
+
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static SESSION: Lazy<Mutex<Option<Session>>> = Lazy::new(|| Mutex::new(None));
+
+struct Session {
+    id: String,
+    pointer: (i32, i32),
+    pressed_keys: Vec<Key>,
+    pressed_buttons: Vec<MouseButton>,
+}
+
+struct WdError {
+    status: u16,
+    error: &'static str,
+    message: String,
+}
+
+impl WdError {
+    fn new(status: u16, error: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            error,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(400, "invalid argument", message)
+    }
+}
+
+impl From<String> for WdError {
+    fn from(message: String) -> Self {
+        Self::new(500, "unknown error", message)
+    }
+}
+
+type WdResult = Result<Value, WdError>;
+
+// ==========================================
+// SERVER
+// ==========================================
+
+pub fn start(addr: &str) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Failed to bind WebDriver endpoint: {}", e))?;
+    // Non-blocking accept lets `stop` take effect without a wake-up connection.
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    log::info!("WebDriver endpoint listening on {}", addr);
+
+    thread::Builder::new()
+        .name("webdriver".to_string())
+        .spawn(move || {
+            let port = listener.local_addr().map_or(0, |a| a.port());
+            while GENERATION.load(Ordering::SeqCst) == generation {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let _ = stream.set_nonblocking(false);
+                        let _ = thread::Builder::new()
+                            .name("webdriver-client".to_string())
+                            .spawn(move || handle_client(stream, port));
+                    }
+                    Err(_) => thread::sleep(Duration::from_millis(50)),
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn WebDriver endpoint: {}", e))?;
+
+    Ok(())
+}
+
+pub fn stop() -> Result<(), String> {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    release_session();
+    Ok(())
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+    keep_alive: bool,
+    host: Option<String>,
+    origin: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Reads one line, or nothing if the peer hung up or sent a line over `MAX_HEADER_LINE`.
+fn read_line(reader: &mut BufReader<TcpStream>) -> Option<String> {
+    let mut line = String::new();
+    reader
+        .by_ref()
+        .take(MAX_HEADER_LINE)
+        .read_line(&mut line)
+        .ok()?;
+    line.ends_with('\n').then_some(line)
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> Option<HttpRequest> {
+    let line = read_line(reader)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.split('?').next()?.to_string();
+    let mut keep_alive = parts.next() == Some("HTTP/1.1");
+
+    let mut content_length = 0usize;
+    let (mut host, mut origin, mut content_type) = (None, None, None);
+    loop {
+        let header = read_line(reader)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':')?;
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.trim().parse().ok()?,
+            "connection" => keep_alive = !value.trim().eq_ignore_ascii_case("close"),
+            "host" => host = Some(value.trim().to_string()),
+            "origin" => origin = Some(value.trim().to_string()),
+            "content-type" => content_type = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return None;
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(HttpRequest {
+        method,
+        path,
+        body,
+        keep_alive,
+        host,
+        origin,
+        content_type,
+    })
+}
+
+/// Turns away web pages, which can reach a loopback port too. After DNS rebinding their
+/// Host header still names the page's domain, browsers send Origin on cross-origin
+/// POSTs, and a JSON body can't be sent cross-origin without a preflight.
+fn check_request(request: &HttpRequest, port: u16) -> Result<(), WdError> {
+    let host_ok = request.host.as_deref().is_some_and(|host| {
+        [format!("127.0.0.1:{}", port), format!("localhost:{}", port)]
+            .iter()
+            .any(|allowed| host.eq_ignore_ascii_case(allowed))
+    });
+    if !host_ok {
+        return Err(WdError::new(
+            403,
+            "unknown error",
+            format!("Host must be 127.0.0.1:{} or localhost:{}", port, port),
+        ));
+    }
+    if request.origin.is_some() {
+        return Err(WdError::new(
+            403,
+            "unknown error",
+            "Requests with an Origin header are refused",
+        ));
+    }
+    let json = request.content_type.as_deref().is_some_and(|value| {
+        let media_type = value.split(';').next().unwrap_or_default();
+        media_type.trim().eq_ignore_ascii_case("application/json")
+    });
+    if !request.body.is_empty() && !json {
+        return Err(WdError::invalid_argument(
+            "Request bodies must be application/json",
+        ));
+    }
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, port: u16) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+
+    while let Some(request) = read_request(&mut reader) {
+        let (status, body) = match check_request(&request, port).and_then(|_| route(&request)) {
+            Ok(value) => (200, json!({ "value": value })),
+            Err(e) => (
+                e.status,
+                json!({ "value": { "error": e.error, "message": e.message, "stacktrace": "" } }),
+            ),
+        };
+
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-cache\r\n{}\r\n",
+            status,
+            if status == 200 { "OK" } else { "Error" },
+            body.len(),
+            if request.keep_alive { "" } else { "Connection: close\r\n" },
+        );
+        if writer.write_all(response.as_bytes()).is_err()
+            || writer.write_all(body.as_bytes()).is_err()
+            || !request.keep_alive
+        {
+            break;
+        }
+    }
+}
+
+fn route(request: &HttpRequest) -> WdResult {
+    let body: Value = if request.body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&request.body)
+            .map_err(|e| WdError::invalid_argument(format!("Invalid JSON body: {}", e)))?
+    };
+    let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["status"]) => Ok(status()),
+        ("POST", ["session"]) => new_session(),
+        ("DELETE", ["session", id]) => with_session(id, |_| Ok(())).map(|_| {
+            release_session();
+            Value::Null
+        }),
+        ("GET", ["session", id, "timeouts"]) => with_session(id, |_| {
+            Ok(json!({ "script": 30000, "pageLoad": 300000, "implicit": 0 }))
+        }),
+        ("POST", ["session", id, "timeouts"]) => with_session(id, |_| Ok(Value::Null)),
+        ("POST", ["session", id, "actions"]) => with_session(id, |session| {
+            guarded("run_input_job", &body, || {
+                perform_actions(session, &body)?;
+                Ok(Value::Null)
+            })
+        }),
+        ("DELETE", ["session", id, "actions"]) => with_session(id, |session| {
+            guarded("run_input_job", &body, || {
+                release_inputs(session);
+                Ok(Value::Null)
+            })
+        }),
+        ("GET", ["session", id, "screenshot"]) => {
+            with_session(id, |_| guarded("get_screenshot", &body, screenshot))
+        }
+        ("GET", ["session", id, "window", "rect"]) => with_session(id, |_| {
+            let rect = coords::virtual_screen();
+            Ok(json!({ "x": rect.x, "y": rect.y, "width": rect.width, "height": rect.height }))
+        }),
+        _ => Err(WdError::new(
+            404,
+            "unknown command",
+            format!("{} {} is not supported", request.method, request.path),
+        )),
+    }
+}
+
+/// Runs an endpoint through the command pipeline under `WEBDRIVER_ORIGIN`, so policies,
+/// rate limits, dry-run and the audit log cover it. Input goes by the name of
+/// `run_input_job`, which plays the same kind of event sequence.
+fn guarded(method: &str, params: &Value, f: impl FnOnce() -> WdResult) -> WdResult {
+    let mut failure = None;
+    middleware::run_as(middleware::WEBDRIVER_ORIGIN, method, params, || {
+        f().map_err(|e| {
+            let message = e.message.clone();
+            failure = Some(e);
+            message
+        })
+    })
+    .map_err(|reason| failure.unwrap_or_else(|| WdError::new(500, "unsupported operation", reason)))
+}
+
+// ==========================================
+// SESSIONS
+// ==========================================
+
+fn status() -> Value {
+    let busy = SESSION.lock().unwrap().is_some();
+    json!({
+        "ready": !busy,
+        "message": if busy { "A session is already active" } else { "Ready for new sessions" },
+    })
+}
+
+fn new_session() -> WdResult {
+    let mut slot = SESSION.lock().unwrap();
+    if slot.is_some() {
+        return Err(WdError::new(
+            500,
+            "session not created",
+            "Only one session may be active at a time",
+        ));
+    }
+
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("No randomness for a session: {}", e))?;
+    let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    *slot = Some(Session {
+        id: id.clone(),
+        pointer: (0, 0),
+        pressed_keys: Vec::new(),
+        pressed_buttons: Vec::new(),
+    });
+    crash::breadcrumb("webdriver", format!("session {} created", id));
+
+    Ok(json!({
+        "sessionId": id,
+        "capabilities": {
+            "browserName": "native-desktop",
+            "browserVersion": env!("CARGO_PKG_VERSION"),
+            "platformName": std::env::consts::OS,
+            "setWindowRect": false,
+        },
+    }))
+}
+
+/// Runs `f` against the active session if `id` matches it. Holding the lock for the
+/// duration serializes action chains from concurrent connections.
+fn with_session<T>(
+    id: &str,
+    f: impl FnOnce(&mut Session) -> Result<T, WdError>,
+) -> Result<T, WdError> {
+    let mut slot = SESSION.lock().unwrap();
+    match slot.as_mut() {
+        Some(session) if session.id == id => f(session),
+        _ => Err(WdError::new(
+            404,
+            "invalid session id",
+            format!("No active session '{}'", id),
+        )),
+    }
+}
+
+fn release_session() {
+    if let Some(mut session) = SESSION.lock().unwrap().take() {
+        release_inputs(&mut session);
+        crash::breadcrumb("webdriver", format!("session {} deleted", session.id));
+    }
+}
+
+fn release_inputs(session: &mut Session) {
+    let mut enigo = Enigo::new();
+    for key in session.pressed_keys.drain(..).rev() {
+        enigo.key_up(key);
+    }
+    for button in session.pressed_buttons.drain(..) {
+        enigo.mouse_up(button);
+    }
+}
+
+// ==========================================
+// ACTIONS
+// ==========================================
+
+/// Maps a W3C key value to a key. Special keys use the spec's private-use code points.
+fn parse_key(value: &str) -> Result<Key, WdError> {
+    let mut chars = value.chars();
+    let (Some(ch), None) = (chars.next(), chars.next()) else {
+        return Err(WdError::invalid_argument(format!(
+            "Key value '{}' must be a single character",
+            value
+        )));
+    };
+
+    Ok(match ch {
+        '\u{E003}' => Key::Backspace,
+        '\u{E004}' => Key::Tab,
+        '\u{E006}' | '\u{E007}' => Key::Return,
+        '\u{E008}' | '\u{E050}' => Key::Shift,
+        '\u{E009}' | '\u{E051}' => Key::Control,
+        '\u{E00A}' | '\u{E052}' => Key::Alt,
+        '\u{E00C}' => Key::Escape,
+        '\u{E00D}' => Key::Space,
+        '\u{E00E}' => Key::PageUp,
+        '\u{E00F}' => Key::PageDown,
+        '\u{E010}' => Key::End,
+        '\u{E011}' => Key::Home,
+        '\u{E012}' => Key::LeftArrow,
+        '\u{E013}' => Key::UpArrow,
+        '\u{E014}' => Key::RightArrow,
+        '\u{E015}' => Key::DownArrow,
+        '\u{E017}' => Key::Delete,
+        '\u{E03D}' | '\u{E053}' => Key::Meta,
+        '\u{E031}' => Key::F1,
+        '\u{E032}' => Key::F2,
+        '\u{E033}' => Key::F3,
+        '\u{E034}' => Key::F4,
+        '\u{E035}' => Key::F5,
+        '\u{E036}' => Key::F6,
+        '\u{E037}' => Key::F7,
+        '\u{E038}' => Key::F8,
+        '\u{E039}' => Key::F9,
+        '\u{E03A}' => Key::F10,
+        '\u{E03B}' => Key::F11,
+        '\u{E03C}' => Key::F12,
+        '\u{E000}'..='\u{F8FF}' => {
+            return Err(WdError::invalid_argument(format!(
+                "Unsupported special key U+{:04X}",
+                ch as u32
+            )))
+        }
+        ch => Key::Layout(ch),
+    })
+}
+
+/// W3C button numbering: 0 = primary, 1 = middle, 2 = secondary.
+fn parse_button(action: &Value) -> Result<MouseButton, WdError> {
+    match action.get("button").and_then(Value::as_u64).unwrap_or(0) {
+        0 => Ok(MouseButton::Left),
+        1 => Ok(MouseButton::Middle),
+        2 => Ok(MouseButton::Right),
+        other => Err(WdError::invalid_argument(format!(
+            "Unsupported button {}",
+            other
+        ))),
+    }
+}
+
+fn number(action: &Value, name: &str) -> Result<f64, WdError> {
+    action.get(name).map_or(Ok(0.0), |v| {
+        v.as_f64()
+            .ok_or_else(|| WdError::invalid_argument(format!("'{}' must be a number", name)))
+    })
+}
+
+fn duration(action: &Value) -> Result<Duration, WdError> {
+    Ok(Duration::from_millis(
+        number(action, "duration")?.max(0.0) as u64
+    ))
+}
+
+fn move_pointer(
+    session: &mut Session,
+    action: &Value,
+    scheduler: &mut Scheduler,
+) -> Result<(), WdError> {
+    let x = number(action, "x")?;
+    let y = number(action, "y")?;
+    let target = match action.get("origin") {
+        None => coords::to_native(CoordinateSpace::Physical, x, y, None)?,
+        Some(Value::String(origin)) if origin == "viewport" => {
+            coords::to_native(CoordinateSpace::Physical, x, y, None)?
+        }
+        Some(Value::String(origin)) if origin == "pointer" => (
+            session.pointer.0 + x.round() as i32,
+            session.pointer.1 + y.round() as i32,
+        ),
+        Some(_) => {
+            return Err(WdError::new(
+                400,
+                "no such element",
+                "Element origins are not supported; there is no DOM",
+            ))
+        }
+    };
+
+    let total = duration(action)?;
+    let (from_x, from_y) = session.pointer;
+    let steps = (total.as_millis() / MOVE_STEP.as_millis()).max(1) as u32;
+    let started = Instant::now();
+
+    for step in 1..=steps {
+        let t = step as f64 / steps as f64;
+        let x = from_x + ((target.0 - from_x) as f64 * t).round() as i32;
+        let y = from_y + ((target.1 - from_y) as f64 * t).round() as i32;
+        scheduler.wait_until(started + total.mul_f64(t));
+        input::move_cursor(x, y)?;
+    }
+
+    session.pointer = target;
+    Ok(())
+}
+
+fn perform_actions(session: &mut Session, body: &Value) -> Result<(), WdError> {
+    let sources = body
+        .get("actions")
+        .and_then(Value::as_array)
+        .ok_or_else(|| WdError::invalid_argument("'actions' must be an array"))?;
+
+    let mut tracks = Vec::with_capacity(sources.len());
+    for source in sources {
+        let kind = source.get("type").and_then(Value::as_str).unwrap_or("");
+        if !matches!(kind, "pointer" | "key" | "none") {
+            return Err(WdError::invalid_argument(format!(
+                "Unsupported input source type '{}'",
+                kind
+            )));
+        }
+        let actions = source
+            .get("actions")
+            .and_then(Value::as_array)
+            .ok_or_else(|| WdError::invalid_argument("Input source needs an 'actions' array"))?;
+        tracks.push(actions);
+    }
+
+    crash::breadcrumb(
+        "webdriver",
+        format!("actions across {} sources", tracks.len()),
+    );
+    let mut enigo = Enigo::new();
+    let mut scheduler = Scheduler::new();
+    let ticks = tracks.iter().map(|t| t.len()).max().unwrap_or(0);
+
+    // Actions at the same index form a tick; a tick lasts as long as its longest action.
+    for tick in 0..ticks {
+        let tick_start = Instant::now();
+        let mut tick_length = Duration::ZERO;
+
+        for action in tracks.iter().filter_map(|t| t.get(tick)) {
+            let kind = action.get("type").and_then(Value::as_str).unwrap_or("");
+            match kind {
+                "pause" => tick_length = tick_length.max(duration(action)?),
+                "pointerMove" => move_pointer(session, action, &mut scheduler)?,
+                "pointerDown" => {
+                    let button = parse_button(action)?;
+                    enigo.mouse_down(button);
+                    session.pressed_buttons.push(button);
+                }
+                "pointerUp" => {
+                    let button = parse_button(action)?;
+                    enigo.mouse_up(button);
+                    session.pressed_buttons.retain(|b| *b != button);
+                }
+                "keyDown" | "keyUp" => {
+                    let value = action
+                        .get("value")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| WdError::invalid_argument("Key action needs a 'value'"))?;
+                    let key = parse_key(value)?;
+                    if kind == "keyDown" {
+                        enigo.key_down(key);
+                        session.pressed_keys.push(key);
+                    } else {
+                        enigo.key_up(key);
+                        session.pressed_keys.retain(|k| *k != key);
+                    }
+                }
+                other => {
+                    return Err(WdError::invalid_argument(format!(
+                        "Unsupported action '{}'",
+                        other
+                    )))
+                }
+            }
+        }
+
+        scheduler.wait_until(tick_start + tick_length);
+    }
+
+    Ok(())
+}
+
+fn screenshot() -> WdResult {
+    let image = capture::capture_rect(coords::virtual_screen())?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+    Ok(Value::String(STANDARD.encode(png.into_inner())))
+}
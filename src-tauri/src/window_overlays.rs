@@ -0,0 +1,163 @@
+use std::{thread, time::Duration};
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::{
+    app_window::{self, WindowCriteria, WindowInfo},
+    coords::Rect,
+    crash, events, OverlayEvent, OverlayManager,
+};
+
+// ==========================================
+// Window-Tracking Overlays
+// ==========================================
+
+// An overlay covering another application's window, e.g. a chat client that must
+// stay hidden during a share. One thread per overlay polls the window's frame and
+// moves the overlay after it on the main thread; the overlay hides while the window
+// is minimized and closes when the window does. Tracking ends by itself once the
+// overlay is closed some other way.
+
+/// Emitted with the overlay's id when the window it covered closed, taking the
+/// overlay with it.
+const WINDOW_CLOSED_EVENT: &str = "overlay://window-closed";
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AttachedOverlay {
+    pub overlay: u64,
+    pub window: WindowInfo,
+}
+
+/// Opens an overlay over the window `criteria` matches and keeps it there. Runs on
+/// the main thread.
+pub fn attach(
+    manager: &OverlayManager,
+    criteria: &WindowCriteria,
+    text: Option<&str>,
+    opacity: Option<f64>,
+) -> Result<AttachedOverlay, String> {
+    if criteria.is_empty() {
+        return Err("Window criteria must set a title, process or pid".to_string());
+    }
+    let window = app_window::find_window(criteria)
+        .ok_or_else(|| "No window matches the criteria".to_string())?;
+    let overlay = cover(manager, &window, text, opacity)?;
+    Ok(AttachedOverlay { overlay, window })
+}
+
+/// Opens an overlay over `window`, keeps it there and returns its id. Runs on the
+/// main thread.
+pub fn cover(
+    manager: &OverlayManager,
+    window: &WindowInfo,
+    text: Option<&str>,
+    opacity: Option<f64>,
+) -> Result<u64, String> {
+    crate::check_options(opacity, None)?;
+    let app = crate::OVERLAY_APP
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("Window tracking needs the app to be running")?;
+
+    crash::breadcrumb(
+        "overlay",
+        format!("attach to window {} ({})", window.id, window.process),
+    );
+    let overlay = crate::open_overlay_in(manager, window.rect, None, text)?;
+    crate::style_overlays_in(manager, &[overlay], opacity, None, None, None, None)?;
+    if !window.visible {
+        crate::set_overlay_visible_in(manager, overlay, false)?;
+    }
+
+    let shared = manager.share();
+    let (id, rect, visible) = (window.id, window.rect, window.visible);
+    thread::Builder::new()
+        .name("window-overlay".to_string())
+        .spawn(move || track(shared, app, overlay, id, rect, visible))
+        .map_err(|e| {
+            let _ = crate::destroy_overlay_in(manager, overlay);
+            format!("Failed to spawn window tracker: {}", e)
+        })?;
+
+    Ok(overlay)
+}
+
+/// Follows window `window` with `overlay`, starting from where the window was when
+/// the overlay opened.
+fn track(
+    manager: OverlayManager,
+    app: AppHandle,
+    overlay: u64,
+    window: u64,
+    mut rect: Rect,
+    mut visible: bool,
+) {
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if manager.handle(overlay).is_none() {
+            return;
+        }
+
+        let Some(current) = app_window::window(window) else {
+            let handle = app.clone();
+            let _ = app.run_on_main_thread(move || {
+                if crate::destroy_overlay_in(&manager, overlay).is_ok() {
+                    crash::breadcrumb("overlay", format!("window of {} closed", overlay));
+                    events::emit(&handle, WINDOW_CLOSED_EVENT, OverlayEvent { id: overlay });
+                }
+            });
+            return;
+        };
+
+        // A minimized window's frame means nothing, so the overlay keeps its place
+        // until the window comes back.
+        let moved = current.visible && current.rect != rect;
+        let shown = (current.visible != visible).then_some(current.visible);
+        if !moved && shown.is_none() {
+            continue;
+        }
+        if current.visible {
+            rect = current.rect;
+        }
+        visible = current.visible;
+
+        let shared = manager.share();
+        let _ = app.run_on_main_thread(move || {
+            let result = if moved {
+                crate::move_overlay_in(&shared, overlay, rect)
+            } else {
+                Ok(())
+            }
+            .and_then(|_| match shown {
+                Some(shown) => crate::set_overlay_visible_in(&shared, overlay, shown),
+                None => Ok(()),
+            });
+            // Not found means it was closed meanwhile; the next poll ends tracking.
+            if let Err(e) = result {
+                if shared.handle(overlay).is_some() {
+                    log::warn!("window overlay {} not updated: {}", overlay, e);
+                }
+            }
+        });
+    }
+}
+
+// ==========================================
+// WINDOW OVERLAY COMMANDS
+// ==========================================
+
+/// Covers a window, found by title, process or pid, with the privacy overlay and
+/// keeps the overlay on it as the window moves, resizes or is minimized. The overlay
+/// closes with the window.
+#[tauri::command]
+pub fn attach_overlay_to_window(
+    state: State<'_, OverlayManager>,
+    criteria: WindowCriteria,
+    text: Option<String>,
+    opacity: Option<f64>,
+) -> Result<AttachedOverlay, String> {
+    attach(&state, &criteria, text.as_deref(), opacity)
+}